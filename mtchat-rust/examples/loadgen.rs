@@ -0,0 +1,255 @@
+//! Load generator for message throughput.
+//!
+//! Creates dialogs on a running instance, connects every participant over
+//! WebSocket and has every participant send messages concurrently. Reports
+//! send latency (HTTP round trip), delivery latency (send start until the
+//! `message.new` event arrives at a participant) and throughput.
+//!
+//! ```bash
+//! cargo run --release --features test_utils --example loadgen -- \
+//!     --dialogs 20 --users-per-dialog 3 --messages 200
+//! ```
+//!
+//! Run it against an instance without per-user rate limits, otherwise the
+//! numbers measure the limiter; rejected sends are counted as errors.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use multitenancy_chat_api::test_utils::WsTestClient;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Marker prefix of generated messages; followed by the send offset in µs
+const MARKER: &str = "loadgen-";
+
+#[derive(Debug, Parser)]
+#[command(name = "loadgen", about = "MTChat message throughput load generator")]
+struct Args {
+    /// HTTP base URL of the API
+    #[arg(long, env = "API_BASE_URL", default_value = "http://localhost:8080")]
+    base_url: String,
+
+    /// Management API token
+    #[arg(long, env = "ADMIN_API_TOKEN", default_value = "")]
+    admin_token: String,
+
+    /// Dialogs to create
+    #[arg(long, default_value_t = 10)]
+    dialogs: usize,
+
+    /// Participants per dialog; each sends and receives
+    #[arg(long, default_value_t = 2)]
+    users_per_dialog: usize,
+
+    /// Messages each participant sends
+    #[arg(long, default_value_t = 100)]
+    messages: usize,
+
+    /// Pause between the messages of one participant, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    interval_ms: u64,
+
+    /// Give up waiting for outstanding events after this many seconds of silence
+    #[arg(long, default_value_t = 10)]
+    idle_timeout_secs: u64,
+}
+
+/// Latency samples in microseconds
+#[derive(Default)]
+struct Samples(Vec<u64>);
+
+impl Samples {
+    fn percentile(&self, p: f64) -> Duration {
+        if self.0.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted = self.0.clone();
+        sorted.sort_unstable();
+        let rank = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+        Duration::from_micros(sorted[rank])
+    }
+
+    fn max(&self) -> Duration {
+        Duration::from_micros(self.0.iter().copied().max().unwrap_or(0))
+    }
+
+    fn report(&self, label: &str) {
+        println!(
+            "{:<10} n={:<8} p50={:>9.2?} p99={:>9.2?} max={:>9.2?}",
+            label,
+            self.0.len(),
+            self.percentile(50.0),
+            self.percentile(99.0),
+            self.max()
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let client = Client::new();
+    let base_url = args.base_url.trim_end_matches('/').to_string();
+
+    // Setup: dialogs and one WebSocket per participant
+    let mut dialogs = Vec::with_capacity(args.dialogs);
+    for _ in 0..args.dialogs {
+        let users: Vec<Uuid> = (0..args.users_per_dialog).map(|_| Uuid::new_v4()).collect();
+        let dialog_id = create_dialog(&client, &base_url, &args.admin_token, &users).await;
+        dialogs.push((dialog_id, users));
+    }
+    let mut sockets = Vec::new();
+    for (_, users) in &dialogs {
+        for user in users {
+            let ws = WsTestClient::connect(&base_url, &user.to_string())
+                .await
+                .unwrap_or_else(|e| panic!("WebSocket connect failed: {}", e));
+            sockets.push(ws);
+        }
+    }
+    println!(
+        "{} dialogs, {} connections, {} messages per participant",
+        dialogs.len(),
+        sockets.len(),
+        args.messages
+    );
+
+    let start = Instant::now();
+    let delivery = Arc::new(Mutex::new(Samples::default()));
+    let expected_events = args.messages * args.users_per_dialog;
+    let idle_timeout = Duration::from_secs(args.idle_timeout_secs);
+
+    // Consumers: every participant receives every message of its dialog
+    let mut consumers = Vec::new();
+    for mut ws in sockets {
+        let delivery = delivery.clone();
+        consumers.push(tokio::spawn(async move {
+            let mut received = 0;
+            let mut last_event = Duration::ZERO;
+            let mut latencies = Vec::with_capacity(expected_events);
+            while received < expected_events {
+                let Some(event) = ws.next_event(idle_timeout).await else {
+                    break;
+                };
+                if event.event_type != "message.new" {
+                    continue;
+                }
+                if let Some(sent_at) = event.str("content").and_then(parse_marker) {
+                    last_event = start.elapsed();
+                    latencies.push((last_event.as_micros() as u64).saturating_sub(sent_at));
+                    received += 1;
+                }
+            }
+            delivery.lock().await.0.extend(latencies);
+            ws.close().await;
+            (received, last_event)
+        }));
+    }
+
+    // Senders: every participant sends its messages one after another
+    let mut senders = Vec::new();
+    for (dialog_id, users) in &dialogs {
+        for user in users {
+            let client = client.clone();
+            let url = format!(
+                "{}/api/v1/dialogs/{}/messages?user_id={}",
+                base_url, dialog_id, user
+            );
+            let messages = args.messages;
+            let interval = Duration::from_millis(args.interval_ms);
+            senders.push(tokio::spawn(async move {
+                let mut latencies = Vec::with_capacity(messages);
+                let mut errors = 0;
+                for _ in 0..messages {
+                    let sent_at = start.elapsed().as_micros();
+                    let begin = Instant::now();
+                    let resp = client
+                        .post(&url)
+                        .json(&json!({ "content": format!("{}{}", MARKER, sent_at) }))
+                        .send()
+                        .await;
+                    match resp {
+                        Ok(resp) if resp.status().is_success() => {
+                            latencies.push(begin.elapsed().as_micros() as u64);
+                        }
+                        _ => errors += 1,
+                    }
+                    if !interval.is_zero() {
+                        tokio::time::sleep(interval).await;
+                    }
+                }
+                (latencies, errors)
+            }));
+        }
+    }
+
+    let mut send = Samples::default();
+    let mut send_errors = 0;
+    for sender in senders {
+        let (latencies, errors) = sender.await.expect("sender task panicked");
+        send.0.extend(latencies);
+        send_errors += errors;
+    }
+    let send_elapsed = start.elapsed();
+
+    // Throughput is measured until the last event, not the idle timeout
+    let mut events = 0;
+    let mut elapsed = send_elapsed;
+    let connections = consumers.len();
+    for consumer in consumers {
+        let (received, last_event) = consumer.await.expect("consumer task panicked");
+        events += received;
+        elapsed = elapsed.max(last_event);
+    }
+    let delivery = delivery.lock().await;
+
+    println!();
+    send.report("send");
+    delivery.report("delivery");
+    println!();
+    println!(
+        "messages:  {} sent, {} failed, {:.0}/s",
+        send.0.len(),
+        send_errors,
+        send.0.len() as f64 / send_elapsed.as_secs_f64()
+    );
+    println!(
+        "events:    {} of {} received, {:.0}/s",
+        events,
+        expected_events * connections,
+        events as f64 / elapsed.as_secs_f64()
+    );
+    println!("duration:  {:.2?}", elapsed);
+}
+
+async fn create_dialog(client: &Client, base_url: &str, token: &str, users: &[Uuid]) -> Uuid {
+    let resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "loadgen",
+            "title": "Load test",
+            "participants": users,
+        }))
+        .send()
+        .await
+        .expect("Create dialog failed");
+    let status = resp.status();
+    let body: Value = resp.json().await.unwrap_or_default();
+    body["data"]["id"]
+        .as_str()
+        .and_then(|id| id.parse().ok())
+        .unwrap_or_else(|| panic!("Create dialog failed ({}): {}", status, body))
+}
+
+/// Send offset embedded in the content (which may come back as HTML)
+fn parse_marker(content: &str) -> Option<u64> {
+    let rest = &content[content.find(MARKER)? + MARKER.len()..];
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
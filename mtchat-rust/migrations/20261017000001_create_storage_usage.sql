@@ -0,0 +1,40 @@
+-- Migration: Create storage_usage table
+-- Tracks attachment bytes per dialog and per tenant (scope_level0 value) for quotas
+
+CREATE TABLE storage_usage (
+    -- 'dialog' (scope_id = dialog UUID) or 'tenant' (scope_id = scope_level0 value)
+    scope_type TEXT NOT NULL,
+    scope_id TEXT NOT NULL,
+
+    -- Total attachment bytes currently stored
+    used_bytes BIGINT NOT NULL DEFAULT 0,
+
+    -- Quota override in bytes (NULL = use the configured default)
+    quota_bytes BIGINT,
+
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (scope_type, scope_id),
+    CONSTRAINT chk_storage_usage_scope_type CHECK (scope_type IN ('dialog', 'tenant')),
+    CONSTRAINT chk_storage_usage_quota CHECK (quota_bytes IS NULL OR quota_bytes >= 0)
+);
+
+-- Backfill dialog usage from existing attachments
+INSERT INTO storage_usage (scope_type, scope_id, used_bytes)
+SELECT 'dialog', m.dialog_id::text, SUM(a.size)
+FROM attachments a
+JOIN messages m ON m.id = a.message_id
+GROUP BY m.dialog_id;
+
+-- Backfill tenant usage: each dialog counts once towards every tenant in its access scopes
+INSERT INTO storage_usage (scope_type, scope_id, used_bytes)
+SELECT 'tenant', t.tenant, SUM(u.used_bytes)
+FROM storage_usage u
+JOIN (
+    SELECT DISTINCT s.dialog_id, unnest(s.scope_level0) AS tenant
+    FROM dialog_access_scopes s
+) t ON t.dialog_id::text = u.scope_id
+WHERE u.scope_type = 'dialog'
+GROUP BY t.tenant;
+
+COMMENT ON TABLE storage_usage IS 'Attachment storage usage and quota overrides per dialog and tenant';
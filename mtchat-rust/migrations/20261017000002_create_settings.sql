@@ -0,0 +1,10 @@
+-- Migration: Create settings table
+-- Runtime settings changed via the Management API without a redeploy
+
+CREATE TABLE settings (
+    key TEXT PRIMARY KEY,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE settings IS 'Runtime setting overrides; missing keys use the configured defaults';
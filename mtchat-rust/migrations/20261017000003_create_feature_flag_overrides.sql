@@ -0,0 +1,21 @@
+-- Migration: Create feature_flag_overrides table
+-- Per-tenant and per-dialog feature flag overrides for staged rollouts
+
+CREATE TABLE feature_flag_overrides (
+    flag TEXT NOT NULL,
+
+    -- 'tenant' (scope_id = scope_level0 value) or 'dialog' (scope_id = dialog UUID)
+    scope_type TEXT NOT NULL,
+    scope_id TEXT NOT NULL,
+
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (flag, scope_type, scope_id),
+    CONSTRAINT chk_feature_flag_scope_type CHECK (scope_type IN ('dialog', 'tenant'))
+);
+
+-- Lookup of all overrides for a dialog and its tenants
+CREATE INDEX idx_feature_flag_overrides_scope ON feature_flag_overrides(scope_type, scope_id);
+
+COMMENT ON TABLE feature_flag_overrides IS 'Feature flag overrides; dialog overrides win over tenant overrides, which win over the global feature_flags setting';
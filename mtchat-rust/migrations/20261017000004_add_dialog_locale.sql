@@ -0,0 +1,7 @@
+-- Per-dialog timezone and locale for generated content (system messages,
+-- notification webhooks). NULL means the client's own settings apply.
+ALTER TABLE dialogs ADD COLUMN timezone TEXT;
+ALTER TABLE dialogs ADD COLUMN locale TEXT;
+
+COMMENT ON COLUMN dialogs.timezone IS 'IANA timezone name, e.g. Europe/Moscow';
+COMMENT ON COLUMN dialogs.locale IS 'BCP 47 language tag, e.g. ru-RU';
@@ -0,0 +1,16 @@
+-- Migration: Create message_stars table
+-- Messages a user starred (bookmarked) across all their dialogs
+
+CREATE TABLE message_stars (
+    user_id TEXT NOT NULL,
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    starred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, message_id)
+);
+
+-- Newest-first listing of a user's starred messages
+CREATE INDEX idx_message_stars_user ON message_stars(user_id, starred_at DESC);
+
+COMMENT ON TABLE message_stars IS 'Per-user starred messages';
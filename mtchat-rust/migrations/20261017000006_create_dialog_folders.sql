@@ -0,0 +1,26 @@
+-- Migration: Create dialog_folders table
+-- Per-user named folders with saved filter criteria over participating dialogs
+
+CREATE TABLE dialog_folders (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+
+    -- Filter criteria (NULL = no filter on that field)
+    object_type TEXT,
+    search TEXT,
+    archived BOOLEAN,
+    unread_only BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Display order within the user's folder list
+    position INTEGER NOT NULL DEFAULT 0,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT uq_dialog_folders_user_name UNIQUE (user_id, name)
+);
+
+CREATE INDEX idx_dialog_folders_user ON dialog_folders(user_id, position);
+
+COMMENT ON TABLE dialog_folders IS 'Saved dialog list filters (custom folders) per user';
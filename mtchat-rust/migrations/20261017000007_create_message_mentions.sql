@@ -0,0 +1,18 @@
+-- Migration: Create message_mentions table
+-- Users mentioned in a message, extracted from mention spans in its content
+
+CREATE TABLE message_mentions (
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+
+    PRIMARY KEY (message_id, user_id)
+);
+
+-- "Dialogs where I am mentioned" lookups
+CREATE INDEX idx_message_mentions_user ON message_mentions(user_id, dialog_id);
+
+COMMENT ON TABLE message_mentions IS 'Mentioned users per message';
+
+-- Saved folders can filter on unread mentions too
+ALTER TABLE dialog_folders ADD COLUMN mentions_only BOOLEAN NOT NULL DEFAULT FALSE;
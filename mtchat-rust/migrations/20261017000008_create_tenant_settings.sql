@@ -0,0 +1,19 @@
+-- Migration: Create tenant_settings table
+-- Per-tenant widget branding and behavior, served to the embedded widget
+
+CREATE TABLE tenant_settings (
+    -- scope_level0 value identifying the tenant
+    tenant_uid TEXT PRIMARY KEY,
+
+    -- Free-form objects interpreted by the widget (colors, logo, ...)
+    branding JSONB NOT NULL DEFAULT '{}',
+    behavior JSONB NOT NULL DEFAULT '{}',
+
+    -- Defaults for the widget UI (NULL = widget/browser default)
+    locale TEXT,
+    timezone TEXT,
+
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE tenant_settings IS 'Per-tenant widget configuration managed via the Management API';
@@ -0,0 +1,6 @@
+-- Migration: Add snooze to dialog_participants
+-- Notifications for the participant are suppressed until snoozed_until passes
+
+ALTER TABLE dialog_participants ADD COLUMN snoozed_until TIMESTAMPTZ;
+
+COMMENT ON COLUMN dialog_participants.snoozed_until IS 'Notifications suppressed until this time (NULL or past = not snoozed)';
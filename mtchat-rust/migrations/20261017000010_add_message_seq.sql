@@ -0,0 +1,45 @@
+-- Migration: Per-dialog message sequence numbers
+-- Every message gets a monotonic seq within its dialog, assigned by a trigger
+-- inside the insert transaction. The dialog row stays locked until commit, so
+-- seq order matches commit order and clients can order/deduplicate by it.
+
+ALTER TABLE dialogs ADD COLUMN last_message_seq BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE messages ADD COLUMN seq BIGINT;
+
+-- Backfill existing messages in chronological order
+UPDATE messages m
+SET seq = numbered.seq
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY dialog_id ORDER BY sent_at, id) AS seq
+    FROM messages
+) numbered
+WHERE m.id = numbered.id;
+
+UPDATE dialogs d
+SET last_message_seq = counts.max_seq
+FROM (
+    SELECT dialog_id, MAX(seq) AS max_seq
+    FROM messages
+    GROUP BY dialog_id
+) counts
+WHERE d.id = counts.dialog_id;
+
+ALTER TABLE messages ALTER COLUMN seq SET NOT NULL;
+CREATE UNIQUE INDEX idx_messages_dialog_seq ON messages(dialog_id, seq);
+
+CREATE FUNCTION assign_message_seq() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE dialogs
+    SET last_message_seq = last_message_seq + 1
+    WHERE id = NEW.dialog_id
+    RETURNING last_message_seq INTO NEW.seq;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_messages_assign_seq
+    BEFORE INSERT ON messages
+    FOR EACH ROW EXECUTE FUNCTION assign_message_seq();
+
+COMMENT ON COLUMN messages.seq IS 'Monotonic per-dialog sequence number (assigned on insert)';
+COMMENT ON COLUMN dialogs.last_message_seq IS 'Highest messages.seq assigned in this dialog';
@@ -0,0 +1,81 @@
+-- Migration: Dialog changelog for delta sync
+-- Triggers record every message create/edit/delete and read-state change with
+-- a per-dialog event seq, so clients can fetch everything after a cursor.
+
+ALTER TABLE dialogs ADD COLUMN last_event_seq BIGINT NOT NULL DEFAULT 0;
+
+CREATE TABLE dialog_events (
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    seq BIGINT NOT NULL,
+    event_type VARCHAR(30) NOT NULL,
+    message_id UUID,
+    user_id VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (dialog_id, seq),
+    CONSTRAINT chk_dialog_event_type
+        CHECK (event_type IN ('message.new', 'message.edited', 'message.deleted', 'message.read'))
+);
+
+CREATE FUNCTION record_dialog_event(
+    p_dialog_id UUID,
+    p_event_type VARCHAR,
+    p_message_id UUID,
+    p_user_id VARCHAR
+) RETURNS VOID AS $$
+DECLARE
+    next_seq BIGINT;
+BEGIN
+    UPDATE dialogs
+    SET last_event_seq = last_event_seq + 1
+    WHERE id = p_dialog_id
+    RETURNING last_event_seq INTO next_seq;
+
+    -- Dialog is being deleted (cascade): nothing to sync
+    IF next_seq IS NULL THEN
+        RETURN;
+    END IF;
+
+    INSERT INTO dialog_events (dialog_id, seq, event_type, message_id, user_id)
+    VALUES (p_dialog_id, next_seq, p_event_type, p_message_id, p_user_id);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION log_message_event() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        PERFORM record_dialog_event(NEW.dialog_id, 'message.new', NEW.id, NEW.sender_id);
+    ELSIF TG_OP = 'UPDATE' THEN
+        PERFORM record_dialog_event(NEW.dialog_id, 'message.edited', NEW.id, NEW.sender_id);
+    ELSE
+        PERFORM record_dialog_event(OLD.dialog_id, 'message.deleted', OLD.id, OLD.sender_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_messages_log_changes
+    AFTER INSERT OR DELETE ON messages
+    FOR EACH ROW EXECUTE FUNCTION log_message_event();
+
+CREATE TRIGGER trg_messages_log_edit
+    AFTER UPDATE OF content ON messages
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content)
+    EXECUTE FUNCTION log_message_event();
+
+CREATE FUNCTION log_read_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM record_dialog_event(NEW.dialog_id, 'message.read', NEW.last_read_message_id, NEW.user_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_participants_log_read
+    AFTER UPDATE OF last_read_message_id ON dialog_participants
+    FOR EACH ROW
+    WHEN (NEW.last_read_message_id IS NOT NULL
+          AND OLD.last_read_message_id IS DISTINCT FROM NEW.last_read_message_id)
+    EXECUTE FUNCTION log_read_event();
+
+COMMENT ON TABLE dialog_events IS 'Per-dialog changelog for delta sync (filled by triggers)';
+COMMENT ON COLUMN dialogs.last_event_seq IS 'Highest dialog_events.seq assigned in this dialog';
@@ -0,0 +1,9 @@
+-- Migration: Soft deletion of dialogs
+-- Deleted dialogs are hidden everywhere and can be restored until the purge
+-- job hard-deletes them after the retention window.
+
+ALTER TABLE dialogs ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_dialogs_deleted_at ON dialogs(deleted_at) WHERE deleted_at IS NOT NULL;
+
+COMMENT ON COLUMN dialogs.deleted_at IS 'Soft deletion time (NULL = not deleted)';
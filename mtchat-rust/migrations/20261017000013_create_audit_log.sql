@@ -0,0 +1,20 @@
+-- Migration: Audit log for privileged Management API actions
+-- Entries outlive the dialogs they reference (no foreign key), so purged
+-- dialogs keep their audit trail.
+
+CREATE TABLE audit_log (
+    id UUID PRIMARY KEY,
+    actor VARCHAR(255) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    dialog_id UUID,
+    user_id VARCHAR(255),
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_created ON audit_log(created_at DESC);
+CREATE INDEX idx_audit_log_dialog ON audit_log(dialog_id, created_at DESC) WHERE dialog_id IS NOT NULL;
+
+COMMENT ON TABLE audit_log IS 'Privileged actions (impersonation), append-only';
+COMMENT ON COLUMN audit_log.actor IS 'Support engineer or system that performed the action';
+COMMENT ON COLUMN audit_log.user_id IS 'User acted as or on behalf of';
@@ -0,0 +1,8 @@
+-- Migration: Message versions for optimistic concurrency
+-- Every edit increments the version; edits can require the version they were
+-- based on, so concurrent edits from two devices conflict instead of the last
+-- one silently winning.
+
+ALTER TABLE messages ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+COMMENT ON COLUMN messages.version IS 'Incremented on every edit (starts at 1)';
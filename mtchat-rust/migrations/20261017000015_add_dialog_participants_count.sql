@@ -0,0 +1,36 @@
+-- Migration: Denormalized participant count on dialogs
+-- Dialog lists return the count without counting dialog_participants per
+-- request. A trigger keeps it in step with joins and leaves inside the same
+-- transaction.
+
+ALTER TABLE dialogs ADD COLUMN participants_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE dialogs d
+SET participants_count = counts.cnt
+FROM (
+    SELECT dialog_id, COUNT(*) AS cnt
+    FROM dialog_participants
+    GROUP BY dialog_id
+) counts
+WHERE d.id = counts.dialog_id;
+
+CREATE FUNCTION update_dialog_participants_count() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE dialogs
+        SET participants_count = participants_count + 1
+        WHERE id = NEW.dialog_id;
+    ELSE
+        UPDATE dialogs
+        SET participants_count = participants_count - 1
+        WHERE id = OLD.dialog_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dialog_participants_count
+    AFTER INSERT OR DELETE ON dialog_participants
+    FOR EACH ROW EXECUTE FUNCTION update_dialog_participants_count();
+
+COMMENT ON COLUMN dialogs.participants_count IS 'Number of dialog_participants rows (maintained by trigger)';
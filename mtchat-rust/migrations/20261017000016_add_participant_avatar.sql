@@ -0,0 +1,8 @@
+-- Migration: Participant avatars
+-- The original image key; variants are rendered next to it by the thumbnail job.
+
+ALTER TABLE dialog_participants ADD COLUMN avatar_s3_key TEXT;
+ALTER TABLE dialog_participants ADD COLUMN avatar_variants_ready BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN dialog_participants.avatar_s3_key IS 'Storage key of the uploaded avatar image (NULL = no avatar)';
+COMMENT ON COLUMN dialog_participants.avatar_variants_ready IS 'Resized avatar variants exist next to avatar_s3_key';
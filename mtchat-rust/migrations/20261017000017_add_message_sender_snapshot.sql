@@ -0,0 +1,35 @@
+-- Migration: Sender profile snapshot on messages
+-- The sender's display name and company at send time, so messages keep a
+-- sender name after the sender left the dialog. A trigger copies them from
+-- dialog_participants on insert, whichever code path inserts the message.
+
+ALTER TABLE messages ADD COLUMN sender_display_name VARCHAR(255);
+ALTER TABLE messages ADD COLUMN sender_company VARCHAR(255);
+
+-- Backfill from current participants (senders who already left stay NULL)
+UPDATE messages m
+SET sender_display_name = dp.display_name,
+    sender_company = dp.company
+FROM dialog_participants dp
+WHERE dp.dialog_id = m.dialog_id
+  AND dp.user_id = m.sender_id
+  AND m.sender_id IS NOT NULL;
+
+CREATE FUNCTION snapshot_message_sender() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.sender_id IS NOT NULL AND NEW.sender_display_name IS NULL THEN
+        SELECT display_name, company
+        INTO NEW.sender_display_name, NEW.sender_company
+        FROM dialog_participants
+        WHERE dialog_id = NEW.dialog_id AND user_id = NEW.sender_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_messages_sender_snapshot
+    BEFORE INSERT ON messages
+    FOR EACH ROW EXECUTE FUNCTION snapshot_message_sender();
+
+COMMENT ON COLUMN messages.sender_display_name IS 'Sender display name at send time (set by trigger)';
+COMMENT ON COLUMN messages.sender_company IS 'Sender company at send time (set by trigger)';
@@ -0,0 +1,8 @@
+-- Migration: Integration metadata on messages
+-- A JSON object the host system attaches to a message (e.g. the quote line
+-- item it refers to). Returned with the message, in WebSocket events and
+-- in webhooks.
+
+ALTER TABLE messages ADD COLUMN metadata JSONB;
+
+COMMENT ON COLUMN messages.metadata IS 'Host-defined JSON object attached on send (NULL = none)';
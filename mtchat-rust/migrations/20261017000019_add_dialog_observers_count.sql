@@ -0,0 +1,39 @@
+-- Migration: Read-only observers
+-- Observers (joined_as = 'observer') are counted separately from
+-- participants. The count trigger now maintains both counters.
+
+ALTER TABLE dialogs ADD COLUMN observers_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE dialogs d
+SET participants_count = counts.participants,
+    observers_count = counts.observers
+FROM (
+    SELECT dialog_id,
+           COUNT(*) FILTER (WHERE joined_as <> 'observer') AS participants,
+           COUNT(*) FILTER (WHERE joined_as = 'observer') AS observers
+    FROM dialog_participants
+    GROUP BY dialog_id
+) counts
+WHERE d.id = counts.dialog_id;
+
+CREATE OR REPLACE FUNCTION update_dialog_participants_count() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.joined_as = 'observer' THEN
+            UPDATE dialogs SET observers_count = observers_count + 1 WHERE id = NEW.dialog_id;
+        ELSE
+            UPDATE dialogs SET participants_count = participants_count + 1 WHERE id = NEW.dialog_id;
+        END IF;
+    ELSE
+        IF OLD.joined_as = 'observer' THEN
+            UPDATE dialogs SET observers_count = observers_count - 1 WHERE id = OLD.dialog_id;
+        ELSE
+            UPDATE dialogs SET participants_count = participants_count - 1 WHERE id = OLD.dialog_id;
+        END IF;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON COLUMN dialogs.participants_count IS 'Number of dialog_participants rows except observers (maintained by trigger)';
+COMMENT ON COLUMN dialogs.observers_count IS 'Number of observer dialog_participants rows (maintained by trigger)';
@@ -0,0 +1,7 @@
+-- Migration: Participant company identifier
+-- `company` is free text; hosts can pass a stable company id so participants
+-- of the same company are grouped even if the name is spelled differently.
+
+ALTER TABLE dialog_participants ADD COLUMN company_uid VARCHAR(255);
+
+COMMENT ON COLUMN dialog_participants.company_uid IS 'Host-defined company identifier (groups participants by company)';
@@ -0,0 +1,28 @@
+-- Migration: Dialog notes
+-- A shared description pinned above the chat (agreement summaries and the
+-- like), edited by the participants. Edits are versioned like messages, and
+-- every replaced revision is kept in dialog_notes_history.
+
+CREATE TABLE dialog_notes (
+    dialog_id UUID PRIMARY KEY REFERENCES dialogs(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    updated_by VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE dialog_notes_history (
+    id UUID PRIMARY KEY,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    updated_by VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_dialog_notes_history_dialog ON dialog_notes_history(dialog_id, version DESC);
+
+COMMENT ON TABLE dialog_notes IS 'Shared dialog description (sanitized HTML), one row per dialog';
+COMMENT ON COLUMN dialog_notes.version IS 'Incremented on every edit (starts at 1)';
+COMMENT ON TABLE dialog_notes_history IS 'Replaced revisions of dialog notes';
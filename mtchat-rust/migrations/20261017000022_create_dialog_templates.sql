@@ -0,0 +1,25 @@
+-- Migration: Auto-create dialog templates
+-- Per object type, `GET /dialogs/by-object/...` creates the dialog on first
+-- access from a template instead of returning null. Auto-created dialogs carry
+-- a creation key (the caller's tenants, or '' for shared dialogs); the unique
+-- index makes concurrent first accesses converge on a single dialog.
+
+CREATE TABLE dialog_templates (
+    object_type VARCHAR(255) PRIMARY KEY,
+    -- Title of created dialogs; '{object_id}' is replaced with the object ID
+    title TEXT,
+    -- Access scopes of created dialogs: [{scope_level0, scope_level1, scope_level2}]
+    access_scopes JSONB NOT NULL DEFAULT '[]',
+    -- Replace scope_level0 with the caller's tenants (one dialog per tenant set)
+    caller_tenant BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE dialogs ADD COLUMN auto_create_key TEXT;
+
+CREATE UNIQUE INDEX idx_dialogs_auto_create
+    ON dialogs(object_type, object_id, auto_create_key)
+    WHERE auto_create_key IS NOT NULL;
+
+COMMENT ON TABLE dialog_templates IS 'Per object type templates for dialogs created on first access';
+COMMENT ON COLUMN dialogs.auto_create_key IS 'Creation key of dialogs created from a template (NULL = created explicitly)';
@@ -0,0 +1,12 @@
+-- Migration: Delayed participant removal
+-- Management can remove a participant with a grace period: until
+-- `pending_removal_at` the participant stays in the dialog read-only (to
+-- export context), then the removal job deletes the row.
+
+ALTER TABLE dialog_participants ADD COLUMN pending_removal_at TIMESTAMPTZ;
+
+CREATE INDEX idx_participants_pending_removal
+    ON dialog_participants(pending_removal_at)
+    WHERE pending_removal_at IS NOT NULL;
+
+COMMENT ON COLUMN dialog_participants.pending_removal_at IS 'When a participant removed with a grace period is finally removed (NULL = not being removed)';
@@ -0,0 +1,8 @@
+-- Migration: Per-dialog slow mode
+-- When set, each participant can send one message per `slow_mode_secs`.
+-- The time of the last message is tracked in Redis, not here.
+
+ALTER TABLE dialogs ADD COLUMN slow_mode_secs INTEGER
+    CHECK (slow_mode_secs IS NULL OR slow_mode_secs > 0);
+
+COMMENT ON COLUMN dialogs.slow_mode_secs IS 'Minimum seconds between messages of one participant (NULL = slow mode off)';
@@ -0,0 +1,8 @@
+-- Migration: Keep reply references to deleted messages
+-- Replies used to lose `reply_to_id` when the original was deleted
+-- (ON DELETE SET NULL). Keeping it lets clients show "original deleted" in
+-- the reply preview. The API checks that the original is in the same dialog.
+
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS messages_reply_to_id_fkey;
+
+COMMENT ON COLUMN messages.reply_to_id IS 'Message this is a reply to (may no longer exist)';
@@ -0,0 +1,7 @@
+-- Migration: Structured message content
+-- Optional typed blocks (text, quote, file, key_value, actions) rendered by
+-- clients next to the HTML `content`. Validated by the API.
+
+ALTER TABLE messages ADD COLUMN content_blocks JSONB;
+
+COMMENT ON COLUMN messages.content_blocks IS 'Structured content blocks (JSON array), NULL for plain messages';
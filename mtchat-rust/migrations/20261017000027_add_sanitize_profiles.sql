@@ -0,0 +1,12 @@
+-- Migration: HTML sanitization profiles
+-- Dialogs and tenants can select how much formatting their messages keep.
+-- NULL falls back to the tenant profile, then to the global setting.
+
+ALTER TABLE dialogs ADD COLUMN sanitize_profile TEXT
+    CHECK (sanitize_profile IN ('minimal', 'standard', 'rich'));
+
+ALTER TABLE tenant_settings ADD COLUMN sanitize_profile TEXT
+    CHECK (sanitize_profile IN ('minimal', 'standard', 'rich'));
+
+COMMENT ON COLUMN dialogs.sanitize_profile IS 'HTML sanitization profile of messages (NULL = tenant or global profile)';
+COMMENT ON COLUMN tenant_settings.sanitize_profile IS 'HTML sanitization profile of the tenant''s dialogs (NULL = global profile)';
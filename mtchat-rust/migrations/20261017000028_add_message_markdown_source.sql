@@ -0,0 +1,7 @@
+-- Migration: Markdown message input
+-- Messages sent as Markdown keep their source next to the rendered,
+-- sanitized HTML in `content`.
+
+ALTER TABLE messages ADD COLUMN content_markdown TEXT;
+
+COMMENT ON COLUMN messages.content_markdown IS 'Markdown source of content (NULL for messages sent as HTML)';
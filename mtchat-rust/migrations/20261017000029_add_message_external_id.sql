@@ -0,0 +1,24 @@
+-- Migration: External message IDs
+-- Every message gets an external_id in the deployment's configured format
+-- (UUID, ULID or Snowflake) for mapping to host systems. Existing messages,
+-- and rows inserted without one, use the UUID primary key as text.
+
+ALTER TABLE messages ADD COLUMN external_id TEXT;
+
+UPDATE messages SET external_id = id::text;
+
+ALTER TABLE messages ALTER COLUMN external_id SET NOT NULL;
+CREATE UNIQUE INDEX idx_messages_external_id ON messages(external_id);
+
+CREATE FUNCTION default_message_external_id() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.external_id := COALESCE(NEW.external_id, NEW.id::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_messages_default_external_id
+    BEFORE INSERT ON messages
+    FOR EACH ROW EXECUTE FUNCTION default_message_external_id();
+
+COMMENT ON COLUMN messages.external_id IS 'Message ID in the configured external format (UUID, ULID or Snowflake)';
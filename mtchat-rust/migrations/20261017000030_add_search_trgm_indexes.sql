@@ -0,0 +1,9 @@
+-- Migration: Message and attachment search
+-- Trigram indexes for the ILIKE search of GET /api/v1/search
+-- (pg_trgm is enabled by 20250316000001_add_trgm_indexes.sql)
+
+CREATE INDEX IF NOT EXISTS idx_messages_content_trgm
+ON messages USING GIN (content gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_filename_trgm
+ON attachments USING GIN (filename gin_trgm_ops);
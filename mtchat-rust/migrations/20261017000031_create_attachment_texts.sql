@@ -0,0 +1,14 @@
+-- Migration: Extracted attachment text
+-- Text of PDF and DOCX attachments, written by the text extraction job
+-- (`text-extract` feature) and matched by attachment search
+
+CREATE TABLE attachment_texts (
+    attachment_id UUID PRIMARY KEY REFERENCES attachments(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    extracted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_attachment_texts_content_trgm
+ON attachment_texts USING GIN (content gin_trgm_ops);
+
+COMMENT ON TABLE attachment_texts IS 'Plain text extracted from document attachments';
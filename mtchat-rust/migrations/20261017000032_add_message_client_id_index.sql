@@ -0,0 +1,7 @@
+-- Migration: Client IDs of batch-sent messages
+-- Messages composed offline and sent in a batch carry the client's ID in
+-- metadata->>'client_id'; a retried batch looks them up to skip duplicates
+
+CREATE INDEX idx_messages_client_id
+ON messages (dialog_id, sender_id, (metadata->>'client_id'))
+WHERE metadata ? 'client_id';
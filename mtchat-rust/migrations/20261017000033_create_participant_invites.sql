@@ -0,0 +1,23 @@
+-- Migration: Participant invites by email
+-- Participants invited before the host system has a user ID for them. The
+-- host activates an email's invites with a user ID, which turns them into
+-- dialog_participants rows; until then they are listed as pending participants.
+
+CREATE TABLE participant_invites (
+    id UUID PRIMARY KEY,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    -- Trimmed and lowercase
+    email TEXT NOT NULL,
+    joined_as VARCHAR(20) NOT NULL DEFAULT 'participant',
+    display_name TEXT NOT NULL,
+    company TEXT,
+    company_uid TEXT,
+    phone TEXT,
+    invited_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT uq_participant_invites_dialog_email UNIQUE (dialog_id, email)
+);
+
+CREATE INDEX idx_participant_invites_email ON participant_invites(email);
+
+COMMENT ON TABLE participant_invites IS 'Pending participant invitations by email';
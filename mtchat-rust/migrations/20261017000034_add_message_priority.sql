@@ -0,0 +1,9 @@
+-- Migration: Message priority
+-- Urgent messages skip the notification delay and can be listed on their own
+
+ALTER TABLE messages
+ADD COLUMN priority VARCHAR(10) NOT NULL DEFAULT 'normal';
+
+CREATE INDEX idx_messages_urgent
+ON messages (dialog_id, id)
+WHERE priority = 'urgent';
@@ -0,0 +1,37 @@
+-- Migration: Response time SLAs
+-- An SLA is set per object type (sla_policies) or per dialog (overriding the
+-- policy). The responding company is held to it: the SLA job finds each
+-- dialog's oldest message from another company that the responding company
+-- has not answered, and remembers it in dialog_sla_state so sla.warning and
+-- sla.breached are sent once per unanswered message. The state is only
+-- recomputed when the dialog's last_message_seq moved past checked_seq.
+
+ALTER TABLE dialogs
+ADD COLUMN sla_response_secs INTEGER,
+ADD COLUMN sla_warning_secs INTEGER,
+-- company_uid or company name (NULL = the company of the dialog's creator)
+ADD COLUMN sla_responder_company TEXT;
+
+CREATE TABLE sla_policies (
+    object_type VARCHAR(255) PRIMARY KEY,
+    response_secs INTEGER NOT NULL,
+    -- NULL = a share of response_secs
+    warning_secs INTEGER,
+    -- company_uid or company name (NULL = the company of the dialog's creator)
+    responder_company TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE dialog_sla_state (
+    dialog_id UUID PRIMARY KEY REFERENCES dialogs(id) ON DELETE CASCADE,
+    checked_seq BIGINT NOT NULL,
+    -- Oldest unanswered message (NULL = nothing waiting for an answer)
+    message_id UUID,
+    sender_id TEXT,
+    pending_since TIMESTAMPTZ,
+    warned_at TIMESTAMPTZ,
+    breached_at TIMESTAMPTZ
+);
+
+COMMENT ON TABLE sla_policies IS 'Per object type response time SLAs';
+COMMENT ON TABLE dialog_sla_state IS 'Oldest unanswered message per dialog and escalations sent for it';
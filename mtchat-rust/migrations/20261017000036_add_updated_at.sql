@@ -0,0 +1,42 @@
+-- Migration: Change tracking for incremental exports
+-- Dialogs, messages and participants get updated_at, bumped by a trigger
+-- whenever a row changes. Columns passed as trigger arguments are ignored,
+-- so counters rewritten by every message don't mark rows as changed.
+
+ALTER TABLE dialogs ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE messages ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE dialog_participants ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE dialogs SET updated_at = COALESCE(deleted_at, created_at);
+UPDATE messages SET updated_at = COALESCE(last_edited_at, sent_at);
+UPDATE dialog_participants SET updated_at = joined_at;
+
+CREATE INDEX idx_dialogs_updated_at ON dialogs(updated_at, id);
+CREATE INDEX idx_messages_updated_at ON messages(updated_at, id);
+CREATE INDEX idx_dialog_participants_updated_at
+    ON dialog_participants(updated_at, dialog_id, user_id);
+
+CREATE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - TG_ARGV - 'updated_at' IS DISTINCT FROM to_jsonb(OLD) - TG_ARGV - 'updated_at' THEN
+        NEW.updated_at := NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dialogs_updated_at
+    BEFORE UPDATE ON dialogs
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at('last_message_seq', 'last_event_seq');
+
+CREATE TRIGGER trg_messages_updated_at
+    BEFORE UPDATE ON messages
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TRIGGER trg_dialog_participants_updated_at
+    BEFORE UPDATE ON dialog_participants
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at('unread_count');
+
+COMMENT ON COLUMN dialogs.updated_at IS 'Last change of the row, except message and event counters (maintained by trigger)';
+COMMENT ON COLUMN messages.updated_at IS 'Last change of the row (maintained by trigger)';
+COMMENT ON COLUMN dialog_participants.updated_at IS 'Last change of the row, except unread_count (maintained by trigger)';
@@ -0,0 +1,19 @@
+-- Migration: Soft bans
+-- A banned user can't join the dialog and doesn't see it through scope
+-- matching until the ban is lifted or expires. Existing participation is
+-- not touched; the host removes the participant separately if needed.
+
+CREATE TABLE dialog_bans (
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    reason TEXT,
+    banned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- NULL = permanent
+    expires_at TIMESTAMPTZ,
+
+    PRIMARY KEY (dialog_id, user_id)
+);
+
+CREATE INDEX idx_dialog_bans_user ON dialog_bans(user_id);
+
+COMMENT ON TABLE dialog_bans IS 'Users blocked from joining a dialog';
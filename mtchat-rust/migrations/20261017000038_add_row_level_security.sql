@@ -0,0 +1,58 @@
+-- Migration: Row-level security policies
+-- With DATABASE_ROW_LEVEL_SECURITY the service enables row-level security on
+-- these tables at startup and sets app.tenant (the caller's scope_level0 as a
+-- text array) and app.user_id on the connections a Chat API request uses.
+-- Dialogs are then only visible to their participants and to callers whose
+-- tenant matches an access scope, and messages only with their dialog, even
+-- to a query that skips the access check. Without app.tenant (management
+-- API, jobs) every row is visible. Only reads are restricted. The policies
+-- don't apply to superusers or roles with BYPASSRLS.
+
+-- Tenants of the current Chat API request (NULL outside of one)
+CREATE FUNCTION request_tenants() RETURNS TEXT[] AS $$
+    SELECT NULLIF(current_setting('app.tenant', true), '')::TEXT[]
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION request_user_id() RETURNS TEXT AS $$
+    SELECT current_setting('app.user_id', true)
+$$ LANGUAGE sql STABLE;
+
+CREATE POLICY dialog_access_scopes_tenant_read ON dialog_access_scopes FOR SELECT USING (
+    request_tenants() IS NULL
+    OR scope_level0 = '{}'
+    OR scope_level0 && request_tenants()
+    OR EXISTS (
+        SELECT 1 FROM dialog_participants dp
+        WHERE dp.dialog_id = dialog_access_scopes.dialog_id AND dp.user_id = request_user_id()
+    )
+);
+
+-- Scopes are matched by tenant only; finer scope levels are left to the
+-- access checks of the service
+CREATE POLICY dialogs_tenant_read ON dialogs FOR SELECT USING (
+    request_tenants() IS NULL
+    OR EXISTS (
+        SELECT 1 FROM dialog_participants dp
+        WHERE dp.dialog_id = dialogs.id AND dp.user_id = request_user_id()
+    )
+    OR EXISTS (
+        SELECT 1 FROM dialog_access_scopes s
+        WHERE s.dialog_id = dialogs.id
+          AND (s.scope_level0 = '{}' OR s.scope_level0 && request_tenants())
+    )
+);
+
+CREATE POLICY messages_tenant_read ON messages FOR SELECT USING (
+    request_tenants() IS NULL
+    OR EXISTS (SELECT 1 FROM dialogs d WHERE d.id = messages.dialog_id)
+);
+
+CREATE POLICY dialogs_insert ON dialogs FOR INSERT WITH CHECK (true);
+CREATE POLICY dialogs_update ON dialogs FOR UPDATE USING (true);
+CREATE POLICY dialogs_delete ON dialogs FOR DELETE USING (true);
+CREATE POLICY dialog_access_scopes_insert ON dialog_access_scopes FOR INSERT WITH CHECK (true);
+CREATE POLICY dialog_access_scopes_update ON dialog_access_scopes FOR UPDATE USING (true);
+CREATE POLICY dialog_access_scopes_delete ON dialog_access_scopes FOR DELETE USING (true);
+CREATE POLICY messages_insert ON messages FOR INSERT WITH CHECK (true);
+CREATE POLICY messages_update ON messages FOR UPDATE USING (true);
+CREATE POLICY messages_delete ON messages FOR DELETE USING (true);
@@ -0,0 +1,32 @@
+-- Compressed storage of large message content
+--
+-- Content above a size threshold is stored zstd-compressed in
+-- content_compressed; content then keeps only a prefix for SQL-side previews.
+-- For search, content_search_vector keeps the distinct words of such messages
+-- (without positions, so it stays small). Compression and decompression
+-- happen in the application.
+
+ALTER TABLE messages
+    ADD COLUMN content_encoding VARCHAR(10) NOT NULL DEFAULT 'plain',
+    ADD COLUMN content_compressed BYTEA,
+    ADD COLUMN content_search_vector TSVECTOR,
+    ADD CONSTRAINT chk_messages_content_encoding
+        CHECK (content_encoding IN ('plain', 'zstd')
+               AND (content_encoding = 'plain') = (content_compressed IS NULL));
+
+CREATE INDEX idx_messages_content_search_vector
+ON messages USING GIN (content_search_vector);
+
+COMMENT ON COLUMN messages.content_encoding IS 'plain: full content in content; zstd: content_compressed holds it, content a prefix';
+COMMENT ON COLUMN messages.content_compressed IS 'zstd-compressed content (NULL for plain messages)';
+COMMENT ON COLUMN messages.content_search_vector IS 'Distinct words of compressed content, for search (NULL for plain messages)';
+
+-- An edit of a compressed message may only change the compressed part
+DROP TRIGGER trg_messages_log_edit ON messages;
+
+CREATE TRIGGER trg_messages_log_edit
+    AFTER UPDATE OF content, content_compressed ON messages
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content
+          OR OLD.content_compressed IS DISTINCT FROM NEW.content_compressed)
+    EXECUTE FUNCTION log_message_event();
@@ -0,0 +1,17 @@
+-- Fix: set_updated_at() never bumped messages.updated_at
+--
+-- A trigger created without arguments has TG_ARGV NULL, not an empty array,
+-- so both sides of the comparison were NULL and no change was detected.
+
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - COALESCE(TG_ARGV, '{}') - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - COALESCE(TG_ARGV, '{}') - 'updated_at' THEN
+        NEW.updated_at := NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Edits made since are known from last_edited_at
+UPDATE messages SET updated_at = last_edited_at WHERE last_edited_at > updated_at;
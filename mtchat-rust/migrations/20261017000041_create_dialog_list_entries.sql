@@ -0,0 +1,188 @@
+-- Read model for the dialog list
+--
+-- One row per participant and dialog with everything the participating
+-- dialog list filters, orders and shows: dialog fields, the participant's
+-- archive and unread state, participant companies (for search) and the
+-- message count and last message. Maintained by triggers, so listing is one
+-- index scan on (user_id, dialog_created_at).
+
+CREATE TABLE dialog_list_entries (
+    user_id TEXT NOT NULL,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    dialog_created_at TIMESTAMPTZ NOT NULL,
+    object_type VARCHAR(100) NOT NULL,
+    title VARCHAR(500),
+    is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Companies of all participants, one per line
+    companies TEXT NOT NULL DEFAULT '',
+    is_archived BOOLEAN NOT NULL DEFAULT FALSE,
+    unread_count INTEGER NOT NULL DEFAULT 0,
+    messages_count BIGINT NOT NULL DEFAULT 0,
+    last_message_at TIMESTAMPTZ,
+    -- Preview of the latest message (same shape as MessagePreview)
+    last_message JSONB,
+    PRIMARY KEY (user_id, dialog_id)
+);
+
+CREATE INDEX idx_dialog_list_entries_user_created
+    ON dialog_list_entries(user_id, dialog_created_at DESC, dialog_id DESC);
+CREATE INDEX idx_dialog_list_entries_dialog ON dialog_list_entries(dialog_id);
+
+COMMENT ON TABLE dialog_list_entries IS 'Per-participant dialog list rows (maintained by triggers)';
+
+-- Latest message lookups of the triggers below
+CREATE INDEX idx_messages_dialog_sent_at ON messages(dialog_id, sent_at DESC);
+
+-- Preview of a message as listed. The length matches LAST_MESSAGE_PREVIEW_CHARS.
+CREATE FUNCTION dialog_list_preview(m messages) RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'id', m.id,
+        'sender_id', m.sender_id,
+        'content', LEFT(m.content, 200),
+        'sent_at', m.sent_at,
+        'message_type', m.message_type
+    )
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE FUNCTION dialog_list_companies(p_dialog_id UUID) RETURNS TEXT AS $$
+    SELECT COALESCE(string_agg(DISTINCT company, E'\n'), '')
+    FROM dialog_participants
+    WHERE dialog_id = p_dialog_id AND company IS NOT NULL
+$$ LANGUAGE sql STABLE;
+
+-- Participants: add, remove and update their rows
+CREATE FUNCTION dialog_list_sync_participant() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO dialog_list_entries (user_id, dialog_id, dialog_created_at, object_type, title,
+                                         is_deleted, is_archived, unread_count,
+                                         messages_count, last_message_at, last_message)
+        SELECT NEW.user_id, d.id, d.created_at, d.object_type, d.title,
+               d.deleted_at IS NOT NULL, NEW.is_archived, NEW.unread_count,
+               (SELECT COUNT(*) FROM messages m WHERE m.dialog_id = d.id),
+               latest.sent_at, latest.preview
+        FROM dialogs d
+        LEFT JOIN LATERAL (
+            SELECT m.sent_at, dialog_list_preview(m) AS preview
+            FROM messages m WHERE m.dialog_id = d.id ORDER BY m.sent_at DESC LIMIT 1
+        ) latest ON true
+        WHERE d.id = NEW.dialog_id
+        ON CONFLICT (user_id, dialog_id) DO NOTHING;
+    ELSIF TG_OP = 'DELETE' THEN
+        DELETE FROM dialog_list_entries WHERE user_id = OLD.user_id AND dialog_id = OLD.dialog_id;
+    ELSE
+        UPDATE dialog_list_entries
+        SET user_id = NEW.user_id, is_archived = NEW.is_archived, unread_count = NEW.unread_count
+        WHERE user_id = OLD.user_id AND dialog_id = OLD.dialog_id;
+    END IF;
+
+    IF TG_OP = 'INSERT' OR TG_OP = 'DELETE'
+        OR OLD.company IS DISTINCT FROM NEW.company THEN
+        UPDATE dialog_list_entries
+        SET companies = dialog_list_companies(COALESCE(NEW.dialog_id, OLD.dialog_id))
+        WHERE dialog_id = COALESCE(NEW.dialog_id, OLD.dialog_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dialog_list_participant_changes
+    AFTER INSERT OR DELETE ON dialog_participants
+    FOR EACH ROW EXECUTE FUNCTION dialog_list_sync_participant();
+
+CREATE TRIGGER trg_dialog_list_participant_update
+    AFTER UPDATE OF user_id, is_archived, unread_count, company ON dialog_participants
+    FOR EACH ROW
+    WHEN (OLD.user_id IS DISTINCT FROM NEW.user_id
+          OR OLD.is_archived IS DISTINCT FROM NEW.is_archived
+          OR OLD.unread_count IS DISTINCT FROM NEW.unread_count
+          OR OLD.company IS DISTINCT FROM NEW.company)
+    EXECUTE FUNCTION dialog_list_sync_participant();
+
+-- Dialogs: copy the listed fields
+CREATE FUNCTION dialog_list_sync_dialog() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE dialog_list_entries
+    SET object_type = NEW.object_type, title = NEW.title, is_deleted = NEW.deleted_at IS NOT NULL
+    WHERE dialog_id = NEW.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dialog_list_dialog_update
+    AFTER UPDATE OF object_type, title, deleted_at ON dialogs
+    FOR EACH ROW
+    WHEN (OLD.object_type IS DISTINCT FROM NEW.object_type
+          OR OLD.title IS DISTINCT FROM NEW.title
+          OR (OLD.deleted_at IS NULL) <> (NEW.deleted_at IS NULL))
+    EXECUTE FUNCTION dialog_list_sync_dialog();
+
+-- Messages: a new message is counted and becomes the last one unless older
+-- (imports). Deleting or editing the last message looks up the latest again.
+CREATE FUNCTION dialog_list_sync_message() RETURNS TRIGGER AS $$
+DECLARE
+    latest messages;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE dialog_list_entries e
+        SET messages_count = e.messages_count + 1,
+            last_message_at = GREATEST(e.last_message_at, NEW.sent_at),
+            last_message = CASE
+                WHEN e.last_message_at IS NULL OR NEW.sent_at >= e.last_message_at
+                    THEN dialog_list_preview(NEW)
+                ELSE e.last_message
+            END
+        WHERE e.dialog_id = NEW.dialog_id;
+    ELSIF TG_OP = 'UPDATE' THEN
+        UPDATE dialog_list_entries
+        SET last_message = dialog_list_preview(NEW)
+        WHERE dialog_id = NEW.dialog_id AND last_message->>'id' = NEW.id::text;
+    ELSE
+        UPDATE dialog_list_entries
+        SET messages_count = messages_count - 1
+        WHERE dialog_id = OLD.dialog_id;
+
+        IF EXISTS (SELECT 1 FROM dialog_list_entries
+                   WHERE dialog_id = OLD.dialog_id AND last_message->>'id' = OLD.id::text) THEN
+            SELECT * INTO latest FROM messages
+            WHERE dialog_id = OLD.dialog_id
+            ORDER BY sent_at DESC
+            LIMIT 1;
+            UPDATE dialog_list_entries
+            SET last_message_at = latest.sent_at,
+                last_message = CASE WHEN latest.id IS NULL THEN NULL
+                                    ELSE dialog_list_preview(latest) END
+            WHERE dialog_id = OLD.dialog_id;
+        END IF;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dialog_list_message_changes
+    AFTER INSERT OR DELETE ON messages
+    FOR EACH ROW EXECUTE FUNCTION dialog_list_sync_message();
+
+CREATE TRIGGER trg_dialog_list_message_update
+    AFTER UPDATE OF content, sender_id ON messages
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content
+          OR OLD.sender_id IS DISTINCT FROM NEW.sender_id)
+    EXECUTE FUNCTION dialog_list_sync_message();
+
+-- Backfill
+INSERT INTO dialog_list_entries (user_id, dialog_id, dialog_created_at, object_type, title,
+                                 is_deleted, companies, is_archived, unread_count,
+                                 messages_count, last_message_at, last_message)
+SELECT dp.user_id, d.id, d.created_at, d.object_type, d.title,
+       d.deleted_at IS NOT NULL, dialog_list_companies(d.id), dp.is_archived, dp.unread_count,
+       stats.messages_count, latest.sent_at, latest.preview
+FROM dialog_participants dp
+JOIN dialogs d ON d.id = dp.dialog_id
+CROSS JOIN LATERAL (
+    SELECT COUNT(*) AS messages_count FROM messages m WHERE m.dialog_id = d.id
+) stats
+LEFT JOIN LATERAL (
+    SELECT m.sent_at, dialog_list_preview(m) AS preview
+    FROM messages m WHERE m.dialog_id = d.id ORDER BY m.sent_at DESC LIMIT 1
+) latest ON true;
@@ -0,0 +1,23 @@
+-- Activity feed of a dialog
+--
+-- Non-message events (joins, leaves, archives, pins, access scope changes),
+-- written by the activity event subscriber. Archive and pin rows are
+-- personal: only the participant they concern sees them.
+
+CREATE TABLE dialog_activity (
+    id UUID PRIMARY KEY,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    activity_type VARCHAR(50) NOT NULL,
+    -- Participant the activity concerns (NULL for dialog-wide changes)
+    user_id TEXT,
+    -- User whose action caused it (NULL for management, jobs and integrations)
+    actor_id TEXT,
+    personal BOOLEAN NOT NULL DEFAULT FALSE,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_dialog_activity_dialog_created
+    ON dialog_activity(dialog_id, created_at DESC, id DESC);
+
+COMMENT ON TABLE dialog_activity IS 'Per-dialog activity feed (written by the event bus)';
@@ -0,0 +1,8 @@
+-- Migration: Per-dialog notification delay
+-- Overrides the `notification_delay_ms` runtime setting (and its per object
+-- type values) for the dialog's notification jobs.
+
+ALTER TABLE dialogs ADD COLUMN notification_delay_ms INTEGER
+    CHECK (notification_delay_ms IS NULL OR notification_delay_ms >= 0);
+
+COMMENT ON COLUMN dialogs.notification_delay_ms IS 'Delay before checking whether a notified message was read (NULL = object type or global setting)';
@@ -0,0 +1,21 @@
+-- Dead-lettered background jobs
+--
+-- Queue jobs (notifications, thumbnails, text extraction, attachment
+-- cleanup) that failed permanently: out of retries or aborted. The payload
+-- is kept so a job can be inspected and re-enqueued by hand.
+
+CREATE TABLE job_dead_letters (
+    id UUID PRIMARY KEY,
+    job_type VARCHAR(50) NOT NULL,
+    -- Queue task ID
+    task_id TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_job_dead_letters_type_failed
+    ON job_dead_letters(job_type, failed_at DESC);
+
+COMMENT ON TABLE job_dead_letters IS 'Background jobs that failed permanently';
@@ -0,0 +1,16 @@
+-- Processed inbound integration events
+--
+-- Event IDs pushed to POST /api/v1/integrations/events, kept for longer than
+-- the timestamp window so a replayed event returns the recorded result
+-- instead of being applied again. result is NULL while the event is applied.
+
+CREATE TABLE inbound_events (
+    id UUID PRIMARY KEY,
+    event_type VARCHAR(50) NOT NULL,
+    result JSONB,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_inbound_events_received_at ON inbound_events(received_at);
+
+COMMENT ON TABLE inbound_events IS 'Recently processed inbound integration events, for replay protection';
@@ -0,0 +1,150 @@
+-- Fix: dialog list entries drifted under concurrent writes
+--
+-- Under READ COMMITTED a participant joining while a message was sent
+-- could miss the message in both triggers: the new entry counted messages
+-- without seeing the uncommitted one, and the message trigger updated
+-- entries without seeing the uncommitted participant. Both triggers now lock
+-- the dialog row first, so they run one after the other and the second one
+-- sees the first one's rows. FOR NO KEY UPDATE is the lock sending a
+-- message already takes (assign_message_seq) and does not conflict with the
+-- key share locks of foreign key checks on the dialog.
+
+CREATE OR REPLACE FUNCTION dialog_list_sync_participant() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM 1 FROM dialogs WHERE id = COALESCE(NEW.dialog_id, OLD.dialog_id) FOR NO KEY UPDATE;
+
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO dialog_list_entries (user_id, dialog_id, dialog_created_at, object_type, title,
+                                         is_deleted, is_archived, unread_count,
+                                         messages_count, last_message_at, last_message)
+        SELECT NEW.user_id, d.id, d.created_at, d.object_type, d.title,
+               d.deleted_at IS NOT NULL, NEW.is_archived, NEW.unread_count,
+               (SELECT COUNT(*) FROM messages m WHERE m.dialog_id = d.id),
+               latest.sent_at, latest.preview
+        FROM dialogs d
+        LEFT JOIN LATERAL (
+            SELECT m.sent_at, dialog_list_preview(m) AS preview
+            FROM messages m WHERE m.dialog_id = d.id ORDER BY m.sent_at DESC LIMIT 1
+        ) latest ON true
+        WHERE d.id = NEW.dialog_id
+        ON CONFLICT (user_id, dialog_id) DO NOTHING;
+    ELSIF TG_OP = 'DELETE' THEN
+        DELETE FROM dialog_list_entries WHERE user_id = OLD.user_id AND dialog_id = OLD.dialog_id;
+    ELSE
+        UPDATE dialog_list_entries
+        SET user_id = NEW.user_id, is_archived = NEW.is_archived, unread_count = NEW.unread_count
+        WHERE user_id = OLD.user_id AND dialog_id = OLD.dialog_id;
+    END IF;
+
+    IF TG_OP = 'INSERT' OR TG_OP = 'DELETE'
+        OR OLD.company IS DISTINCT FROM NEW.company THEN
+        UPDATE dialog_list_entries
+        SET companies = dialog_list_companies(COALESCE(NEW.dialog_id, OLD.dialog_id))
+        WHERE dialog_id = COALESCE(NEW.dialog_id, OLD.dialog_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION dialog_list_sync_message() RETURNS TRIGGER AS $$
+DECLARE
+    latest messages;
+BEGIN
+    PERFORM 1 FROM dialogs WHERE id = COALESCE(NEW.dialog_id, OLD.dialog_id) FOR NO KEY UPDATE;
+
+    IF TG_OP = 'INSERT' THEN
+        UPDATE dialog_list_entries e
+        SET messages_count = e.messages_count + 1,
+            last_message_at = GREATEST(e.last_message_at, NEW.sent_at),
+            last_message = CASE
+                WHEN e.last_message_at IS NULL OR NEW.sent_at >= e.last_message_at
+                    THEN dialog_list_preview(NEW)
+                ELSE e.last_message
+            END
+        WHERE e.dialog_id = NEW.dialog_id;
+    ELSIF TG_OP = 'UPDATE' THEN
+        UPDATE dialog_list_entries
+        SET last_message = dialog_list_preview(NEW)
+        WHERE dialog_id = NEW.dialog_id AND last_message->>'id' = NEW.id::text;
+    ELSE
+        UPDATE dialog_list_entries
+        SET messages_count = messages_count - 1
+        WHERE dialog_id = OLD.dialog_id;
+
+        IF EXISTS (SELECT 1 FROM dialog_list_entries
+                   WHERE dialog_id = OLD.dialog_id AND last_message->>'id' = OLD.id::text) THEN
+            SELECT * INTO latest FROM messages
+            WHERE dialog_id = OLD.dialog_id
+            ORDER BY sent_at DESC
+            LIMIT 1;
+            UPDATE dialog_list_entries
+            SET last_message_at = latest.sent_at,
+                last_message = CASE WHEN latest.id IS NULL THEN NULL
+                                    ELSE dialog_list_preview(latest) END
+            WHERE dialog_id = OLD.dialog_id;
+        END IF;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Rebuild the entries of the given dialogs from the source tables: missing
+-- entries are added, drifted ones corrected and orphaned ones removed.
+-- Returns the number of entries changed. Used by the reconciliation job.
+CREATE FUNCTION dialog_list_rebuild(p_dialog_ids UUID[]) RETURNS INTEGER AS $$
+DECLARE
+    upserted INTEGER;
+    removed INTEGER;
+BEGIN
+    -- Same lock as the triggers, in id order so concurrent rebuilds cannot deadlock
+    PERFORM 1 FROM dialogs WHERE id = ANY(p_dialog_ids) ORDER BY id FOR NO KEY UPDATE;
+
+    INSERT INTO dialog_list_entries AS e (user_id, dialog_id, dialog_created_at, object_type,
+                                          title, is_deleted, companies, is_archived,
+                                          unread_count, messages_count, last_message_at,
+                                          last_message)
+    SELECT dp.user_id, d.id, d.created_at, d.object_type, d.title,
+           d.deleted_at IS NOT NULL, dialog_list_companies(d.id), dp.is_archived,
+           dp.unread_count, stats.messages_count, latest.sent_at, latest.preview
+    FROM dialog_participants dp
+    JOIN dialogs d ON d.id = dp.dialog_id
+    CROSS JOIN LATERAL (
+        SELECT COUNT(*) AS messages_count FROM messages m WHERE m.dialog_id = d.id
+    ) stats
+    LEFT JOIN LATERAL (
+        SELECT m.sent_at, dialog_list_preview(m) AS preview
+        FROM messages m WHERE m.dialog_id = d.id ORDER BY m.sent_at DESC LIMIT 1
+    ) latest ON true
+    WHERE dp.dialog_id = ANY(p_dialog_ids)
+    ON CONFLICT (user_id, dialog_id) DO UPDATE
+    SET dialog_created_at = EXCLUDED.dialog_created_at,
+        object_type = EXCLUDED.object_type,
+        title = EXCLUDED.title,
+        is_deleted = EXCLUDED.is_deleted,
+        companies = EXCLUDED.companies,
+        is_archived = EXCLUDED.is_archived,
+        unread_count = EXCLUDED.unread_count,
+        messages_count = EXCLUDED.messages_count,
+        last_message_at = EXCLUDED.last_message_at,
+        last_message = EXCLUDED.last_message
+    WHERE (e.dialog_created_at, e.object_type, e.title, e.is_deleted, e.companies,
+           e.is_archived, e.unread_count, e.messages_count, e.last_message_at, e.last_message)
+        IS DISTINCT FROM
+          (EXCLUDED.dialog_created_at, EXCLUDED.object_type, EXCLUDED.title,
+           EXCLUDED.is_deleted, EXCLUDED.companies, EXCLUDED.is_archived,
+           EXCLUDED.unread_count, EXCLUDED.messages_count, EXCLUDED.last_message_at,
+           EXCLUDED.last_message);
+    GET DIAGNOSTICS upserted = ROW_COUNT;
+
+    DELETE FROM dialog_list_entries e
+    WHERE e.dialog_id = ANY(p_dialog_ids)
+      AND NOT EXISTS (SELECT 1 FROM dialog_participants dp
+                      WHERE dp.dialog_id = e.dialog_id AND dp.user_id = e.user_id);
+    GET DIAGNOSTICS removed = ROW_COUNT;
+
+    RETURN upserted + removed;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Entries that drifted before this fix
+SELECT dialog_list_rebuild(ARRAY(SELECT id FROM dialogs));
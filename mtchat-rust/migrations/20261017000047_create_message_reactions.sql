@@ -0,0 +1,14 @@
+-- Migration: Create message_reactions table
+-- Emoji reactions of participants to messages (behind the `reactions` feature flag)
+
+CREATE TABLE message_reactions (
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    emoji VARCHAR(32) NOT NULL,
+    dialog_id UUID NOT NULL REFERENCES dialogs(id) ON DELETE CASCADE,
+    reacted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (message_id, user_id, emoji)
+);
+
+COMMENT ON TABLE message_reactions IS 'Emoji reactions of users to messages';
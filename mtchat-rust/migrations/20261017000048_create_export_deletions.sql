@@ -0,0 +1,58 @@
+-- Migration: Tombstones of hard-deleted rows for incremental exports
+-- Purged dialogs, deleted messages and removed participants leave a row
+-- here, so warehouse syncs can delete them too. Statement triggers with
+-- transition tables keep bulk deletes (a purged dialog cascading to its
+-- messages) to one insert per table.
+
+CREATE TABLE export_deletions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    entity VARCHAR(20) NOT NULL,
+    dialog_id UUID NOT NULL,
+    message_id UUID,
+    user_id TEXT,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_export_deletions_deleted_at ON export_deletions(deleted_at, id);
+
+COMMENT ON TABLE export_deletions IS 'Tombstones of hard-deleted dialogs, messages and participants (maintained by triggers)';
+COMMENT ON COLUMN export_deletions.entity IS 'Export the row belonged to: dialogs, messages or participants';
+
+CREATE FUNCTION export_record_dialog_deletions() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO export_deletions (entity, dialog_id)
+    SELECT 'dialogs', id FROM deleted_rows;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION export_record_message_deletions() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO export_deletions (entity, dialog_id, message_id)
+    SELECT 'messages', dialog_id, id FROM deleted_rows;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION export_record_participant_deletions() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO export_deletions (entity, dialog_id, user_id)
+    SELECT 'participants', dialog_id, user_id FROM deleted_rows;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dialogs_export_deletions
+    AFTER DELETE ON dialogs
+    REFERENCING OLD TABLE AS deleted_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION export_record_dialog_deletions();
+
+CREATE TRIGGER trg_messages_export_deletions
+    AFTER DELETE ON messages
+    REFERENCING OLD TABLE AS deleted_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION export_record_message_deletions();
+
+CREATE TRIGGER trg_dialog_participants_export_deletions
+    AFTER DELETE ON dialog_participants
+    REFERENCING OLD TABLE AS deleted_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION export_record_participant_deletions();
@@ -0,0 +1,11 @@
+-- Fix: a soft-deleted dialog blocked creating a new one from the template
+--
+-- The creation key was unique across deleted dialogs too, so after an
+-- auto-created dialog was deleted the next access found the deleted dialog
+-- and handed it out. Only live dialogs now hold their key.
+
+DROP INDEX idx_dialogs_auto_create;
+
+CREATE UNIQUE INDEX idx_dialogs_auto_create
+    ON dialogs(object_type, object_id, auto_create_key)
+    WHERE auto_create_key IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,86 @@
+-- Migration: Fail-closed row-level security
+-- The policies of 038 let every row through when app.tenant was unset and
+-- only restricted reads. Now a connection sees nothing unless it carries a
+-- Chat API tenant context (app.tenant, app.user_id) or the service bypass
+-- (app.rls_bypass = 'on', set for the management API, jobs and other
+-- service work). Writes are checked like reads, and the policies also cover
+-- participants, attachments, dialog list entries and reactions. As before,
+-- they don't apply to superusers or roles with BYPASSRLS.
+
+-- Whether the connection runs service work, unrestricted by the policies
+CREATE FUNCTION rls_bypass() RETURNS BOOLEAN AS $$
+    SELECT COALESCE(current_setting('app.rls_bypass', true), '') = 'on'
+$$ LANGUAGE sql STABLE;
+
+-- Membership lookup for rls_dialog_visible. Runs with the bypass on, since
+-- the policies of the tables it reads call rls_dialog_visible themselves.
+-- Scopes are matched by tenant only; finer scope levels are left to the
+-- access checks of the service.
+CREATE FUNCTION rls_dialog_member(p_dialog_id UUID) RETURNS BOOLEAN AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM dialog_participants dp
+        WHERE dp.dialog_id = p_dialog_id AND dp.user_id = request_user_id()
+    )
+    OR EXISTS (
+        SELECT 1 FROM dialog_access_scopes s
+        WHERE s.dialog_id = p_dialog_id
+          AND (s.scope_level0 = '{}' OR s.scope_level0 && request_tenants())
+    )
+$$ LANGUAGE sql STABLE SET app.rls_bypass = 'on';
+
+-- Whether the current connection may see and write the rows of a dialog.
+-- The bypass is checked first so the nested calls from rls_dialog_member
+-- return without recursing.
+CREATE FUNCTION rls_dialog_visible(p_dialog_id UUID) RETURNS BOOLEAN AS $$
+BEGIN
+    IF rls_bypass() THEN
+        RETURN true;
+    END IF;
+    IF request_tenants() IS NULL THEN
+        RETURN false;
+    END IF;
+    RETURN rls_dialog_member(p_dialog_id);
+END
+$$ LANGUAGE plpgsql STABLE;
+
+DROP POLICY dialog_access_scopes_tenant_read ON dialog_access_scopes;
+DROP POLICY dialogs_tenant_read ON dialogs;
+DROP POLICY messages_tenant_read ON messages;
+DROP POLICY dialogs_insert ON dialogs;
+DROP POLICY dialogs_update ON dialogs;
+DROP POLICY dialogs_delete ON dialogs;
+DROP POLICY dialog_access_scopes_insert ON dialog_access_scopes;
+DROP POLICY dialog_access_scopes_update ON dialog_access_scopes;
+DROP POLICY dialog_access_scopes_delete ON dialog_access_scopes;
+DROP POLICY messages_insert ON messages;
+DROP POLICY messages_update ON messages;
+DROP POLICY messages_delete ON messages;
+
+CREATE POLICY dialogs_tenant_access ON dialogs
+    USING (rls_dialog_visible(id))
+    WITH CHECK (rls_dialog_visible(id));
+
+CREATE POLICY dialog_access_scopes_tenant_access ON dialog_access_scopes
+    USING (rls_dialog_visible(dialog_id))
+    WITH CHECK (rls_dialog_visible(dialog_id));
+
+CREATE POLICY dialog_participants_tenant_access ON dialog_participants
+    USING (rls_dialog_visible(dialog_id))
+    WITH CHECK (rls_dialog_visible(dialog_id));
+
+CREATE POLICY messages_tenant_access ON messages
+    USING (rls_dialog_visible(dialog_id))
+    WITH CHECK (rls_dialog_visible(dialog_id));
+
+-- Attachments follow their message
+CREATE POLICY attachments_tenant_access ON attachments
+    USING (rls_bypass() OR EXISTS (SELECT 1 FROM messages m WHERE m.id = attachments.message_id))
+    WITH CHECK (rls_bypass() OR EXISTS (SELECT 1 FROM messages m WHERE m.id = attachments.message_id));
+
+CREATE POLICY dialog_list_entries_tenant_access ON dialog_list_entries
+    USING (rls_dialog_visible(dialog_id))
+    WITH CHECK (rls_dialog_visible(dialog_id));
+
+CREATE POLICY message_reactions_tenant_access ON message_reactions
+    USING (rls_dialog_visible(dialog_id))
+    WITH CHECK (rls_dialog_visible(dialog_id));
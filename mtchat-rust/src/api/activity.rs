@@ -0,0 +1,63 @@
+//! Dialog activity feed: joins, leaves, archives, pins and scope changes
+
+use axum::extract::{Path, Query, State};
+use axum::response::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{DialogActivity, MAX_ACTIVITY_PAGE};
+use crate::middleware::UserId;
+
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
+
+// ============ DTOs ============
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    /// Return entries older than this entry (cursor from the previous page)
+    pub before: Option<Uuid>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivityResponse {
+    /// Newest first
+    pub activity: Vec<DialogActivity>,
+    /// Whether older entries are available
+    pub has_more: bool,
+}
+
+// ============ Handlers ============
+
+/// List the activity of a dialog; archives and pins only show up for the
+/// participant they concern
+pub async fn list_activity(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<ApiResponse<ActivityResponse>>, ApiError> {
+    if !state.participants.exists(dialog_id, &user_id).await? {
+        return Err(ApiError::new(
+            ErrorCode::NotParticipant,
+            "Not a participant",
+        ));
+    }
+
+    let limit = query.limit.clamp(1, MAX_ACTIVITY_PAGE);
+    let mut activity = state
+        .activity
+        .list(dialog_id, &user_id, query.before, limit + 1)
+        .await?;
+    let has_more = activity.len() as i64 > limit;
+    activity.truncate(limit as usize);
+
+    Ok(Json(ApiResponse {
+        data: ActivityResponse { activity, has_more },
+    }))
+}
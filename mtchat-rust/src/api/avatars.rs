@@ -0,0 +1,273 @@
+//! Participant avatar endpoints
+//!
+//! The client asks for a presigned upload URL, uploads the image and then sets
+//! the returned key as its avatar in the dialog. The thumbnail job renders the
+//! resized variants; until then the URLs of the variants are absent.
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{avatar, DialogParticipant};
+use crate::jobs::{AttachmentCleanupJob, ThumbnailJob};
+use crate::middleware::UserId;
+use crate::services::BlobStorage;
+
+use super::upload::PresignUploadResponse;
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
+
+// ============ DTOs ============
+
+#[derive(Debug, Deserialize)]
+pub struct PresignAvatarRequest {
+    pub content_type: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetAvatarRequest {
+    /// Key returned by the presign endpoint, after the upload completed
+    pub s3_key: String,
+}
+
+/// Download URLs of a participant's avatar
+#[derive(Debug, Clone, Serialize)]
+pub struct AvatarUrls {
+    /// Uploaded image
+    pub url: String,
+    /// 64x64 PNG (absent until rendered)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub small_url: Option<String>,
+    /// 256x256 PNG (absent until rendered)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medium_url: Option<String>,
+}
+
+impl AvatarUrls {
+    /// Smallest available image, for list rows and message senders
+    pub fn thumbnail(&self) -> &str {
+        self.small_url.as_deref().unwrap_or(&self.url)
+    }
+}
+
+// ============ Helpers ============
+
+/// Avatar URLs of a participant (`None` without an avatar, or if signing fails)
+pub(crate) async fn avatar_urls(
+    storage: &dyn BlobStorage,
+    participant: &DialogParticipant,
+) -> Option<AvatarUrls> {
+    let key = participant.avatar_s3_key.as_deref()?;
+    if !storage.is_configured() {
+        return None;
+    }
+
+    let sign = |key: String| async move {
+        storage
+            .generate_download_url(&key)
+            .await
+            .map_err(|e| tracing::warn!(key = %key, error = %e, "Failed to sign avatar URL"))
+            .ok()
+    };
+    let [small, medium] = avatar::AVATAR_VARIANT_SIZES;
+    let (small_url, medium_url) = if participant.avatar_variants_ready {
+        (
+            sign(avatar::avatar_variant_key(key, small)).await,
+            sign(avatar::avatar_variant_key(key, medium)).await,
+        )
+    } else {
+        (None, None)
+    };
+
+    Some(AvatarUrls {
+        url: sign(key.to_string()).await?,
+        small_url,
+        medium_url,
+    })
+}
+
+/// Avatar URLs of the participants that have one, by user id
+pub(crate) async fn resolve_avatars(
+    storage: &dyn BlobStorage,
+    participants: &[DialogParticipant],
+) -> HashMap<String, AvatarUrls> {
+    let mut avatars = HashMap::new();
+    for participant in participants {
+        if let Some(urls) = avatar_urls(storage, participant).await {
+            avatars.insert(participant.user_id.clone(), urls);
+        }
+    }
+    avatars
+}
+
+async fn require_participant(
+    state: &AppState,
+    dialog_id: Uuid,
+    user_id: &str,
+) -> Result<DialogParticipant, ApiError> {
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    state
+        .participants
+        .find(dialog_id, user_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::NotParticipant, "Not a participant"))
+}
+
+/// Delete the files of a replaced or removed avatar in the background
+pub(crate) async fn cleanup_avatar(state: &AppState, dialog_id: Uuid, old_key: Option<String>) {
+    let Some(old_key) = old_key else {
+        return;
+    };
+    let job = AttachmentCleanupJob::new(dialog_id, avatar::avatar_object_keys(&old_key));
+    if let Err(e) = state.jobs.enqueue_attachment_cleanup(job).await {
+        tracing::warn!(key = %old_key, error = %e, "Failed to enqueue avatar cleanup");
+    }
+}
+
+// ============ Handlers ============
+
+/// Presigned upload URL for a new avatar of the current user in a dialog
+pub async fn presign_avatar_upload(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<PresignAvatarRequest>,
+) -> Result<Json<ApiResponse<PresignUploadResponse>>, ApiError> {
+    if !state.storage.is_configured() {
+        return Err(ApiError::Internal("File uploads are not configured".into()));
+    }
+
+    if !avatar::is_avatar_type(&req.content_type) {
+        return Err(ApiError::new(
+            ErrorCode::UnsupportedFileType,
+            format!(
+                "Avatar type '{}' is not allowed. Allowed types: {:?}",
+                req.content_type,
+                avatar::AVATAR_TYPES
+            ),
+        ));
+    }
+    if !avatar::is_valid_avatar_size(req.size) {
+        return Err(ApiError::new(
+            ErrorCode::FileTooLarge,
+            format!(
+                "Avatar size must be between 1 byte and {} bytes",
+                avatar::MAX_AVATAR_SIZE
+            ),
+        ));
+    }
+
+    require_participant(&state, dialog_id, &user_id).await?;
+
+    let s3_key = format!(
+        "{}{}.{}",
+        avatar::avatar_key_prefix(dialog_id, &user_id),
+        Uuid::now_v7(),
+        avatar::avatar_extension(&req.content_type)
+    );
+    let upload_url = state
+        .storage
+        .generate_upload_url(&s3_key, &req.content_type, req.size)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(Json(ApiResponse {
+        data: PresignUploadResponse {
+            upload_url,
+            s3_key,
+            expires_in: state.storage.upload_expiry_secs(),
+        },
+    }))
+}
+
+/// Set an uploaded image as the current user's avatar in a dialog
+pub async fn set_avatar(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<SetAvatarRequest>,
+) -> Result<Json<ApiResponse<AvatarUrls>>, ApiError> {
+    if !state.storage.is_configured() {
+        return Err(ApiError::Internal("File uploads are not configured".into()));
+    }
+
+    let participant = require_participant(&state, dialog_id, &user_id).await?;
+
+    // Only keys issued to this participant, and only after the upload finished
+    let prefix = avatar::avatar_key_prefix(dialog_id, &user_id);
+    let name = req.s3_key.strip_prefix(&prefix).unwrap_or_default();
+    if name.is_empty() || name.contains('/') {
+        return Err(ApiError::BadRequest(
+            "s3_key was not issued for this participant's avatar".into(),
+        ));
+    }
+    if participant.avatar_s3_key.as_deref() == Some(req.s3_key.as_str()) {
+        return Err(ApiError::BadRequest("Avatar is already set".into()));
+    }
+    let (content_type, size) = match state.storage.get_object_info(&req.s3_key).await {
+        Ok(info) => info,
+        Err(crate::services::StorageError::NotFound(_)) => {
+            return Err(ApiError::BadRequest("Avatar has not been uploaded".into()));
+        }
+        Err(e) => return Err(ApiError::Internal(e.to_string())),
+    };
+    if !avatar::is_avatar_type(&content_type) || !avatar::is_valid_avatar_size(size) {
+        return Err(ApiError::new(
+            ErrorCode::UnsupportedFileType,
+            "Uploaded file is not a valid avatar image",
+        ));
+    }
+
+    let old_key = state
+        .participants
+        .set_avatar(dialog_id, &user_id, Some(&req.s3_key))
+        .await?;
+    cleanup_avatar(&state, dialog_id, old_key).await;
+
+    if let Err(e) = state
+        .jobs
+        .enqueue_thumbnail(ThumbnailJob::avatar(dialog_id, &user_id, &req.s3_key))
+        .await
+    {
+        tracing::warn!(key = %req.s3_key, error = %e, "Failed to enqueue avatar variants");
+    }
+
+    let url = state
+        .storage
+        .generate_download_url(&req.s3_key)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(Json(ApiResponse {
+        data: AvatarUrls {
+            url,
+            small_url: None,
+            medium_url: None,
+        },
+    }))
+}
+
+/// Remove the current user's avatar in a dialog
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    require_participant(&state, dialog_id, &user_id).await?;
+
+    let old_key = state
+        .participants
+        .set_avatar(dialog_id, &user_id, None)
+        .await?;
+    cleanup_avatar(&state, dialog_id, old_key).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
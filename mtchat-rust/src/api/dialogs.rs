@@ -1,15 +1,21 @@
 use axum::extract::{Path, Query, State};
 use axum::response::Json;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 use crate::domain::{
-    self, system_messages, Dialog, DialogParticipant, JoinedAs, Message, ParticipantProfile,
+    self, system_messages, BulkDialogAction, Dialog, DialogAccessScope, DialogFilter,
+    DialogParticipant, JoinedAs, Message, MessagePreview, ParticipantProfile, MAX_BULK_DIALOGS,
+    MAX_SNOOZE_SECS,
 };
-use crate::middleware::{OptionalScopeConfig, ScopeConfig, UserId};
-use crate::webhooks::WebhookEvent;
+use crate::events::DomainEvent;
+use crate::middleware::{self, OptionalScopeConfig, ScopeConfig, UserId};
+use crate::repositories::ListedDialog;
+use crate::webhooks::{ArchiveTrigger, WebhookEvent};
 use crate::ws;
 
+use super::avatars::{cleanup_avatar, resolve_avatars, AvatarUrls};
 use super::{ApiError, ApiResponse, AppState, ErrorCode};
 
 // ============ DTOs ============
@@ -26,6 +32,12 @@ pub struct DialogsQuery {
     pub search: Option<String>,
     #[serde(default)]
     pub archived: Option<bool>,
+    /// Only dialogs with unread messages (participating only)
+    #[serde(default)]
+    pub unread: bool,
+    /// Only dialogs with unread mentions of the user (participating only)
+    #[serde(default)]
+    pub mentions: bool,
     #[serde(default = "default_dialogs_limit")]
     pub limit: i64,
     #[serde(default)]
@@ -46,6 +58,8 @@ pub struct ListByObjectQuery {
 pub struct JoinDialogRequest {
     pub display_name: String,
     pub company: String,
+    /// Stable company identifier for grouping
+    pub company_uid: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
 }
@@ -55,6 +69,27 @@ pub struct SetNotificationsRequest {
     pub enabled: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SnoozeRequest {
+    /// Snooze length in seconds (1 to 30 days)
+    pub duration_secs: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkActionRequest {
+    pub dialog_ids: Vec<Uuid>,
+    pub action: BulkDialogAction,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkActionResponse {
+    pub action: BulkDialogAction,
+    /// Dialogs the action was applied to
+    pub updated: Vec<Uuid>,
+    /// Requested dialogs the user does not participate in
+    pub skipped: Vec<Uuid>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct LastMessage {
     pub id: Uuid,
@@ -63,6 +98,9 @@ pub struct LastMessage {
     pub sender_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sender_name: Option<String>,
+    /// Sender's smallest available avatar image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_avatar_url: Option<String>,
     pub sent_at: chrono::DateTime<chrono::Utc>,
     pub message_type: String,
 }
@@ -74,6 +112,8 @@ pub struct ParticipantSummary {
     pub display_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub company: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<AvatarUrls>,
 }
 
 #[derive(Debug, Serialize)]
@@ -82,6 +122,9 @@ pub struct DialogResponse {
     pub dialog: Dialog,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub participants_count: Option<i64>,
+    /// Read-only observers, not included in `participants_count`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub observers_count: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub i_am_participant: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -94,45 +137,162 @@ pub struct DialogResponse {
     pub is_pinned: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notifications_enabled: Option<bool>,
+    /// End of an active snooze (absent when not snoozed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<chrono::DateTime<chrono::Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_message_at: Option<chrono::DateTime<chrono::Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_message: Option<LastMessage>,
+    /// Messages in the dialog (`GET /dialogs` and folder lists)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages_count: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub participants: Option<Vec<ParticipantSummary>>,
+    /// Effective feature flags (single-dialog responses only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<BTreeMap<String, bool>>,
 }
 
-/// Build a `LastMessage` DTO from a message, resolving `sender_name` from the
-/// dialog's participants. System messages (no `sender_id`) get no sender name.
-fn build_last_message(msg: &Message, participants: &[DialogParticipant]) -> LastMessage {
+/// Build a `LastMessage` DTO from a message preview, resolving `sender_name`
+/// and the sender's avatar from the dialog's participants. System messages
+/// (no `sender_id`) get neither.
+fn build_last_message(
+    msg: &MessagePreview,
+    participants: &[DialogParticipant],
+    avatars: &HashMap<String, AvatarUrls>,
+) -> LastMessage {
     let sender_name = msg.sender_id.as_ref().and_then(|sid| {
         participants
             .iter()
             .find(|p| &p.user_id == sid)
             .and_then(|p| p.display_name.clone())
     });
+    let sender_avatar_url = msg
+        .sender_id
+        .as_ref()
+        .and_then(|sid| avatars.get(sid))
+        .map(|a| a.thumbnail().to_string());
     LastMessage {
         id: msg.id,
         content: msg.content.clone(),
         sender_id: msg.sender_id.clone(),
         sender_name,
+        sender_avatar_url,
         sent_at: msg.sent_at,
         message_type: msg.message_type.as_str().to_string(),
     }
 }
 
 /// Build the participant summary list for a dialog from its participants.
-fn build_participant_summaries(participants: &[DialogParticipant]) -> Vec<ParticipantSummary> {
+/// Observers are left out, like they are from `participants_count`.
+fn build_participant_summaries(
+    participants: &[DialogParticipant],
+    avatars: &HashMap<String, AvatarUrls>,
+) -> Vec<ParticipantSummary> {
     participants
         .iter()
+        .filter(|p| !p.joined_as.is_observer())
         .map(|p| ParticipantSummary {
             user_id: p.user_id.clone(),
             display_name: p.display_name.clone(),
             company: p.company.clone(),
+            avatar: avatars.get(&p.user_id).cloned(),
         })
         .collect()
 }
 
+/// Build list items for dialogs, batch-fetching the per-dialog data.
+/// `participating` selects the participant view (unread count, archive state,
+/// last message) over the can-join view.
+pub(super) async fn build_dialog_responses(
+    state: &AppState,
+    user_id: &str,
+    dialogs: Vec<ListedDialog>,
+    participating: bool,
+) -> Result<Vec<DialogResponse>, ApiError> {
+    // Batch fetch all supplementary data in parallel to avoid N+1 queries.
+    // Message counts and last messages already come with the listed dialogs.
+    let dialog_ids: Vec<Uuid> = dialogs.iter().map(|d| d.dialog.id).collect();
+    let participant_map = if participating {
+        state
+            .participants
+            .find_by_dialogs_and_user(&dialog_ids, user_id)
+            .await?
+    } else {
+        HashMap::new()
+    };
+    let all_participants_map = state
+        .participants
+        .list_by_dialogs_batch(&dialog_ids)
+        .await?;
+
+    // Build responses using batch-fetched data
+    let now = chrono::Utc::now();
+    let mut responses = Vec::new();
+    for listed in dialogs {
+        let dialog = listed.dialog;
+        let participants_count = dialog.participants_count as i64;
+        let observers_count = dialog.observers_count as i64;
+
+        let (unread_count, is_archived, is_pinned, notifications_enabled, snoozed_until) =
+            if participating {
+                let participant = participant_map.get(&dialog.id);
+                (
+                    participant.map(|p| p.unread_count as i64),
+                    participant.map(|p| p.is_archived),
+                    participant.map(|p| p.is_pinned),
+                    participant.map(|p| p.notifications_enabled),
+                    participant.and_then(|p| p.active_snooze(now)),
+                )
+            } else {
+                (None, None, None, None, None)
+            };
+
+        let dialog_participants = all_participants_map.get(&dialog.id);
+        // last_message exposes message content, so it is only returned to actual
+        // participants (consistent with the v0.3.7 "no reading before join" rule).
+        // The participant list itself is not sensitive and is always returned.
+        let avatars = resolve_avatars(
+            state.storage.as_ref(),
+            dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]),
+        )
+        .await;
+        let last_message = if participating {
+            listed.last_message.map(|m| {
+                build_last_message(
+                    &m,
+                    dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]),
+                    &avatars,
+                )
+            })
+        } else {
+            None
+        };
+        let participants = dialog_participants.map(|v| build_participant_summaries(v, &avatars));
+
+        responses.push(DialogResponse {
+            dialog,
+            participants_count: Some(participants_count),
+            observers_count: Some(observers_count),
+            i_am_participant: Some(participating),
+            can_join: Some(!participating),
+            unread_count,
+            is_archived,
+            is_pinned,
+            notifications_enabled,
+            snoozed_until,
+            last_message_at: listed.last_message_at,
+            last_message,
+            messages_count: Some(listed.messages_count),
+            participants,
+            features: None,
+        });
+    }
+
+    Ok(responses)
+}
+
 // ============ Handlers ============
 
 pub async fn list_dialogs(
@@ -144,7 +304,6 @@ pub async fn list_dialogs(
     let dialog_type = params.r#type.as_deref().unwrap_or("participating");
 
     let search = params.search.as_deref();
-    let archived = params.archived;
 
     // Cap limit at 100 to prevent excessive queries
     let limit = params.limit.min(100);
@@ -154,11 +313,28 @@ pub async fn list_dialogs(
         "participating" => {
             state
                 .dialogs
-                .find_participating(&user_id, search, archived, limit, offset)
+                .find_participating(
+                    &user_id,
+                    &DialogFilter {
+                        search: params.search.clone(),
+                        archived: params.archived,
+                        unread_only: params.unread,
+                        mentions_only: params.mentions,
+                        ..Default::default()
+                    },
+                    limit,
+                    offset,
+                )
                 .await?
         }
         "available" => {
             // Available dialogs are never archived (user is not a participant yet)
+            // and have no per-user unread state
+            if params.unread || params.mentions {
+                return Err(ApiError::BadRequest(
+                    "unread and mentions filters apply to participating dialogs only".into(),
+                ));
+            }
             if let Some(scope) = &scope_config {
                 state
                     .dialogs
@@ -183,71 +359,8 @@ pub async fn list_dialogs(
         }
     };
 
-    // Batch fetch all supplementary data in parallel to avoid N+1 queries
-    let dialog_ids: Vec<Uuid> = dialogs.iter().map(|d| d.id).collect();
-    let last_message_map = state.dialogs.get_last_message_at_batch(&dialog_ids).await?;
-    let participants_count_map = state.dialogs.count_participants_batch(&dialog_ids).await?;
-    let participant_map = if dialog_type == "participating" {
-        state
-            .participants
-            .find_by_dialogs_and_user(&dialog_ids, &user_id)
-            .await?
-    } else {
-        std::collections::HashMap::new()
-    };
-    let last_message_full_map = state.dialogs.get_last_message_batch(&dialog_ids).await?;
-    let all_participants_map = state
-        .participants
-        .list_by_dialogs_batch(&dialog_ids)
-        .await?;
-
-    // Build responses using batch-fetched data
-    let mut responses = Vec::new();
-    for dialog in dialogs {
-        let participants_count = participants_count_map.get(&dialog.id).copied().unwrap_or(0);
-
-        let (unread_count, is_archived, is_pinned, notifications_enabled) =
-            if dialog_type == "participating" {
-                let participant = participant_map.get(&dialog.id);
-                (
-                    participant.map(|p| p.unread_count as i64),
-                    participant.map(|p| p.is_archived),
-                    participant.map(|p| p.is_pinned),
-                    participant.map(|p| p.notifications_enabled),
-                )
-            } else {
-                (None, None, None, None)
-            };
-
-        let last_message_at = last_message_map.get(&dialog.id).copied();
-
-        let dialog_participants = all_participants_map.get(&dialog.id);
-        // last_message exposes message content, so it is only returned to actual
-        // participants (consistent with the v0.3.7 "no reading before join" rule).
-        // The participant list itself is not sensitive and is always returned.
-        let last_message = if dialog_type == "participating" {
-            last_message_full_map.get(&dialog.id).map(|m| {
-                build_last_message(m, dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]))
-            })
-        } else {
-            None
-        };
-        let participants = dialog_participants.map(|v| build_participant_summaries(v));
-
-        responses.push(DialogResponse {
-            dialog,
-            participants_count: Some(participants_count),
-            i_am_participant: Some(dialog_type == "participating"),
-            can_join: Some(dialog_type == "available"),
-            unread_count,
-            is_archived,
-            is_pinned,
-            notifications_enabled,
-            last_message_at,
-            last_message,
-            participants,
-        });
-    }
+    let responses =
+        build_dialog_responses(&state, &user_id, dialogs, dialog_type == "participating").await?;
 
     Ok(Json(ApiResponse { data: responses }))
 }
@@ -297,7 +410,6 @@ pub async fn list_dialogs_by_object(
 
     // Batch fetch supplementary data in parallel to avoid N+1 queries
     let dialog_ids: Vec<Uuid> = dialogs.iter().map(|d| d.id).collect();
-    let participants_count_map = state.dialogs.count_participants_batch(&dialog_ids).await?;
     let last_message_map = state.dialogs.get_last_message_at_batch(&dialog_ids).await?;
     let participant_map = state
         .participants
@@ -309,50 +421,124 @@ pub async fn list_dialogs_by_object(
         .list_by_dialogs_batch(&dialog_ids)
         .await?;
 
+    let now = chrono::Utc::now();
     let mut responses = Vec::new();
     for dialog in dialogs {
-        let participants_count = participants_count_map.get(&dialog.id).copied().unwrap_or(0);
+        let participants_count = dialog.participants_count as i64;
+        let observers_count = dialog.observers_count as i64;
         let last_message_at = last_message_map.get(&dialog.id).copied();
         let participant = participant_map.get(&dialog.id);
         let i_am_participant = participant.is_some();
 
-        let (unread_count, is_archived, is_pinned, notifications_enabled) = (
+        let (unread_count, is_archived, is_pinned, notifications_enabled, snoozed_until) = (
             participant.map(|p| p.unread_count as i64),
             participant.map(|p| p.is_archived),
             participant.map(|p| p.is_pinned),
             participant.map(|p| p.notifications_enabled),
+            participant.and_then(|p| p.active_snooze(now)),
         );
 
         let dialog_participants = all_participants_map.get(&dialog.id);
         // last_message exposes message content, so it is only returned to actual
         // participants (v0.3.7 "no reading before join"). participants is always returned.
+        let avatars = resolve_avatars(
+            state.storage.as_ref(),
+            dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]),
+        )
+        .await;
         let last_message = if i_am_participant {
             last_message_full_map.get(&dialog.id).map(|m| {
-                build_last_message(m, dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]))
+                build_last_message(
+                    &MessagePreview::of(m),
+                    dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]),
+                    &avatars,
+                )
             })
         } else {
             None
         };
-        let participants = dialog_participants.map(|v| build_participant_summaries(v));
+        let participants = dialog_participants.map(|v| build_participant_summaries(v, &avatars));
 
         responses.push(DialogResponse {
             dialog,
             participants_count: Some(participants_count),
+            observers_count: Some(observers_count),
             i_am_participant: Some(i_am_participant),
             can_join: Some(!i_am_participant),
             unread_count,
             is_archived,
             is_pinned,
             notifications_enabled,
+            snoozed_until,
             last_message_at,
             last_message,
+            messages_count: None,
             participants,
+            features: None,
         });
     }
 
     Ok(Json(ApiResponse { data: responses }))
 }
 
+/// Create the dialog of an object from its object type's template
+///
+/// Returns whether a dialog the caller can access via scope now exists. Nothing
+/// is created without a template, or when the template's scopes would not
+/// grant the caller access.
+async fn auto_create_dialog(
+    state: &AppState,
+    object_type: &str,
+    object_id: &str,
+    scope_config: &ScopeConfig,
+) -> Result<bool, ApiError> {
+    let Some(template) = state.dialog_templates.find(object_type).await? else {
+        return Ok(false);
+    };
+    if domain::validation::validate_identifier(object_id, "object_id").is_err()
+        || (template.caller_tenant && scope_config.scope_level0.is_empty())
+    {
+        return Ok(false);
+    }
+
+    let dialog = Dialog::new(
+        object_id,
+        object_type,
+        template.title_for(object_id),
+        None,
+        None,
+        None,
+    );
+    let scopes: Vec<DialogAccessScope> = template
+        .scopes_for(&scope_config.scope_level0)
+        .into_iter()
+        .map(|s| DialogAccessScope::new(dialog.id, s.scope_level0, s.scope_level1, s.scope_level2))
+        .collect();
+    let grants_access = scopes.iter().any(|s| {
+        s.matches(
+            &scope_config.scope_level0,
+            &scope_config.scope_level1,
+            &scope_config.scope_level2,
+        )
+    });
+    if !grants_access {
+        return Ok(false);
+    }
+
+    let key = template.creation_key(&scope_config.scope_level0);
+    // The new dialog is only visible to the caller once its scopes exist
+    let created = middleware::as_service(state.dialogs.create_auto(&dialog, &key, &scopes)).await?;
+    if created.id == dialog.id {
+        tracing::info!(
+            dialog_id = %created.id,
+            object_type = %object_type,
+            object_id = %object_id,
+            "Auto-created dialog on first access"
+        );
+    }
+    Ok(true)
+}
+
 pub async fn get_dialog_by_object(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -371,11 +557,24 @@ pub async fn get_dialog_by_object(
     // per object (one per scope); a globally-newest lookup would 403 every tenant
     // but the owner of the latest dialog. `None` means no accessible dialog, which
     // is reported as `data: null` rather than leaking another tenant's dialog.
-    let dialog = state
+    let mut dialog = state
         .dialogs
         .find_by_object_for_user(&object_type, &object_id, &user_id, scope)
         .await?;
 
+    // First access to an object with an auto-create template: create the dialog
+    // and look it up again, so the usual access filtering applies
+    if dialog.is_none() {
+        if let Some(scope_config) = &scope_config {
+            if auto_create_dialog(&state, &object_type, &object_id, scope_config).await? {
+                dialog = state
+                    .dialogs
+                    .find_by_object_for_user(&object_type, &object_id, &user_id, scope)
+                    .await?;
+            }
+        }
+    }
+
     if let Some(dialog) = dialog {
         // The dialog is already access-filtered: the user is either a participant
         // or can join it via scope.
@@ -384,9 +583,9 @@ pub async fn get_dialog_by_object(
 
         // Use batch methods to avoid N+1 queries (consistent with list_dialogs)
         let dialog_ids = &[dialog.id];
-        let participants_count_map = state.dialogs.count_participants_batch(dialog_ids).await?;
         let last_message_map = state.dialogs.get_last_message_at_batch(dialog_ids).await?;
-        let participants_count = participants_count_map.get(&dialog.id).copied().unwrap_or(0);
+        let participants_count = dialog.participants_count as i64;
+        let observers_count = dialog.observers_count as i64;
         let last_message_at = last_message_map.get(&dialog.id).copied();
 
         let last_message_full_map = state.dialogs.get_last_message_batch(dialog_ids).await?;
@@ -394,28 +593,42 @@ pub async fn get_dialog_by_object(
         let dialog_participants = all_participants_map.get(&dialog.id);
         // last_message exposes message content, so it is only returned to actual
         // participants (v0.3.7 "no reading before join"). participants is always returned.
+        let avatars = resolve_avatars(
+            state.storage.as_ref(),
+            dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]),
+        )
+        .await;
         let last_message = if i_am_participant {
             last_message_full_map.get(&dialog.id).map(|m| {
-                build_last_message(m, dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]))
+                build_last_message(
+                    &MessagePreview::of(m),
+                    dialog_participants.map(|v| v.as_slice()).unwrap_or(&[]),
+                    &avatars,
+                )
             })
         } else {
             None
         };
-        let participants = dialog_participants.map(|v| build_participant_summaries(v));
+        let participants = dialog_participants.map(|v| build_participant_summaries(v, &avatars));
+        let features = state.feature_flags.flags_for_dialog(dialog.id).await?;
 
         Ok(Json(ApiResponse {
             data: Some(DialogResponse {
                 dialog,
                 participants_count: Some(participants_count),
+                observers_count: Some(observers_count),
                 i_am_participant: Some(i_am_participant),
                 can_join: Some(can_join),
                 unread_count: None,
                 is_archived: None,
                 is_pinned: None,
                 notifications_enabled: None,
+                snoozed_until: None,
                 last_message_at,
                 last_message,
+                messages_count: None,
                 participants,
+                features: Some(features),
             }),
         }))
     } else {
@@ -442,11 +655,19 @@ pub async fn join_dialog(
         return Err(ApiError::BadRequest("Already a participant".into()));
     }
 
+    if state.bans.is_banned(dialog_id, &user_id).await? {
+        return Err(ApiError::new(
+            ErrorCode::BannedFromDialog,
+            "Banned from this dialog",
+        ));
+    }
+
     // Check scope access
     let has_access = state
         .scopes
         .check_access(
             dialog_id,
+            &user_id,
             &scope_config.scope_level0,
             &scope_config.scope_level1,
             &scope_config.scope_level2,
@@ -462,25 +683,28 @@ pub async fn join_dialog(
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     domain::validation::validate_company(&Some(req.company.clone()))
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_company_uid(&req.company_uid)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     domain::validation::validate_email(&req.email)
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     domain::validation::validate_phone(&req.phone)
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
 
     // All DB writes in a transaction
-    let mut tx = state.db.begin().await?;
+    let mut tx = middleware::begin_transaction(&state.db).await?;
 
     // Join with profile
     let profile = ParticipantProfile {
         display_name: req.display_name.clone(),
         company: Some(req.company.clone()),
+        company_uid: req.company_uid.clone(),
         email: req.email.clone(),
         phone: req.phone.clone(),
     };
     let participant = sqlx::query_as::<_, crate::domain::DialogParticipant>(
         r#"INSERT INTO dialog_participants
-           (dialog_id, user_id, joined_as, joined_at, display_name, company, email, phone)
-           VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7)
+           (dialog_id, user_id, joined_as, joined_at, display_name, company, company_uid, email, phone)
+           VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7, $8)
            RETURNING *"#,
     )
     .bind(dialog_id)
@@ -488,6 +712,7 @@ pub async fn join_dialog(
     .bind(&JoinedAs::Joined)
     .bind(&profile.display_name)
     .bind(&profile.company)
+    .bind(&profile.company_uid)
     .bind(&profile.email)
     .bind(&profile.phone)
     .fetch_one(&mut *tx)
@@ -507,11 +732,15 @@ pub async fn join_dialog(
     // Create system message "participant joined"
     let system_msg = Message::system(
         dialog_id,
-        system_messages::participant_joined_content(&req.display_name, Some(&req.company)),
+        system_messages::participant_joined_content(
+            &req.display_name,
+            Some(&req.company),
+            &dialog.locale_context(),
+        ),
     );
     let system_msg = sqlx::query_as::<_, Message>(
-        r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type)
-           VALUES ($1, $2, $3, $4, $5, $6, $7)
+        r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type, external_id)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
            RETURNING *"#,
     )
     .bind(system_msg.id)
@@ -521,18 +750,32 @@ pub async fn join_dialog(
     .bind(system_msg.sent_at)
     .bind(system_msg.reply_to_id)
     .bind(system_msg.message_type.as_str())
+    .bind(&system_msg.external_id)
     .fetch_one(&mut *tx)
     .await?;
 
     tx.commit().await?;
 
     // Broadcast and webhook after transaction is committed
-    ws::broadcast_message(&state.connections, dialog_id, &system_msg).await;
+    ws::broadcast_message(
+        &state.connections,
+        &state.participants,
+        dialog_id,
+        &system_msg,
+        None,
+    )
+    .await;
     ws::broadcast_participant_joined(&state.connections, dialog_id, &user_id).await;
     state
         .webhooks
         .send(WebhookEvent::participant_joined(&dialog, &participant))
         .await;
+    state.events.publish(DomainEvent::ParticipantJoined {
+        dialog_id,
+        user_id: user_id.clone(),
+        joined_as: JoinedAs::Joined,
+        added_by: None,
+    });
 
     Ok(Json(serde_json::json!({
         "status": "joined",
@@ -562,39 +805,65 @@ pub async fn leave_dialog(
     // Create system message
     let system_msg = Message::system(
         dialog_id,
-        system_messages::participant_left_content(&display_name),
+        system_messages::participant_left_content(&display_name, &dialog.locale_context()),
     );
 
-    // All DB writes in a transaction
-    let mut tx = state.db.begin().await?;
-
-    // Remove participant
-    sqlx::query("DELETE FROM dialog_participants WHERE dialog_id = $1 AND user_id = $2")
-        .bind(dialog_id)
-        .bind(&user_id)
-        .execute(&mut *tx)
+    // All DB writes in a transaction. Removing the participant can end the
+    // user's access to the dialog, which the row-level security policies
+    // would apply to the rest of the transaction.
+    let system_msg = middleware::as_service(async {
+        let mut tx = middleware::begin_transaction(&state.db).await?;
+
+        // Remove participant
+        sqlx::query("DELETE FROM dialog_participants WHERE dialog_id = $1 AND user_id = $2")
+            .bind(dialog_id)
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Insert system message
+        let system_msg = sqlx::query_as::<_, Message>(
+            r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type, external_id)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+               RETURNING *"#,
+        )
+        .bind(system_msg.id)
+        .bind(system_msg.dialog_id)
+        .bind(system_msg.sender_id)
+        .bind(&system_msg.content)
+        .bind(system_msg.sent_at)
+        .bind(system_msg.reply_to_id)
+        .bind(system_msg.message_type.as_str())
+        .bind(&system_msg.external_id)
+        .fetch_one(&mut *tx)
         .await?;
 
-    // Insert system message
-    let system_msg = sqlx::query_as::<_, Message>(
-        r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type)
-           VALUES ($1, $2, $3, $4, $5, $6, $7)
-           RETURNING *"#,
-    )
-    .bind(system_msg.id)
-    .bind(system_msg.dialog_id)
-    .bind(system_msg.sender_id)
-    .bind(&system_msg.content)
-    .bind(system_msg.sent_at)
-    .bind(system_msg.reply_to_id)
-    .bind(system_msg.message_type.as_str())
-    .fetch_one(&mut *tx)
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(system_msg)
+    })
     .await?;
 
-    tx.commit().await?;
+    if let Err(e) = state.jobs.cancel_notifications(dialog_id, &user_id).await {
+        tracing::warn!(error = %e, "Failed to cancel pending notifications");
+    }
+    if participant.is_some() {
+        state.events.publish(DomainEvent::ParticipantLeft {
+            dialog_id,
+            user_id: user_id.clone(),
+            removed_by: None,
+        });
+    }
+    cleanup_avatar(&state, dialog_id, participant.and_then(|p| p.avatar_s3_key)).await;
 
     // Broadcast and webhook after transaction is committed
-    ws::broadcast_message(&state.connections, dialog_id, &system_msg).await;
+    ws::broadcast_message(
+        &state.connections,
+        &state.participants,
+        dialog_id,
+        &system_msg,
+        None,
+    )
+    .await;
     ws::broadcast_participant_left(&state.connections, dialog_id, &user_id).await;
     state
         .webhooks
@@ -619,11 +888,32 @@ pub async fn archive_dialog(
         ));
     }
 
-    state
+    let changed = state
         .participants
         .set_archived(dialog_id, &user_id, true)
         .await?;
 
+    if changed {
+        state.events.publish(DomainEvent::ArchiveChanged {
+            dialog_id,
+            user_ids: vec![user_id.clone()],
+            archived: true,
+            trigger: ArchiveTrigger::User,
+            triggered_by: Some(user_id.clone()),
+        });
+        if let Some(dialog) = state.dialogs.find_by_id(dialog_id).await? {
+            state
+                .webhooks
+                .send(WebhookEvent::dialog_archived(
+                    &dialog,
+                    vec![user_id.clone()],
+                    ArchiveTrigger::User,
+                    Some(&user_id),
+                ))
+                .await;
+        }
+    }
+
     Ok(Json(serde_json::json!({ "status": "archived" })))
 }
 
@@ -640,11 +930,32 @@ pub async fn unarchive_dialog(
         ));
     }
 
-    state
+    let changed = state
         .participants
         .set_archived(dialog_id, &user_id, false)
         .await?;
 
+    if changed {
+        state.events.publish(DomainEvent::ArchiveChanged {
+            dialog_id,
+            user_ids: vec![user_id.clone()],
+            archived: false,
+            trigger: ArchiveTrigger::User,
+            triggered_by: Some(user_id.clone()),
+        });
+        if let Some(dialog) = state.dialogs.find_by_id(dialog_id).await? {
+            state
+                .webhooks
+                .send(WebhookEvent::dialog_unarchived(
+                    &dialog,
+                    vec![user_id.clone()],
+                    ArchiveTrigger::User,
+                    Some(&user_id),
+                ))
+                .await;
+        }
+    }
+
     Ok(Json(serde_json::json!({ "status": "unarchived" })))
 }
 
@@ -661,10 +972,17 @@ pub async fn pin_dialog(
         ));
     }
 
-    state
+    let changed = state
         .participants
         .set_pinned(dialog_id, &user_id, true)
         .await?;
+    if changed {
+        state.events.publish(DomainEvent::PinChanged {
+            dialog_id,
+            user_id,
+            pinned: true,
+        });
+    }
 
     Ok(Json(serde_json::json!({ "status": "pinned" })))
 }
@@ -682,10 +1000,17 @@ pub async fn unpin_dialog(
         ));
     }
 
-    state
+    let changed = state
         .participants
         .set_pinned(dialog_id, &user_id, false)
         .await?;
+    if changed {
+        state.events.publish(DomainEvent::PinChanged {
+            dialog_id,
+            user_id,
+            pinned: false,
+        });
+    }
 
     Ok(Json(serde_json::json!({ "status": "unpinned" })))
 }
@@ -714,6 +1039,209 @@ pub async fn set_dialog_notifications(
     })))
 }
 
+/// Suppress notifications for the dialog until the snooze expires
+pub async fn snooze_dialog(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<SnoozeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !(1..=MAX_SNOOZE_SECS).contains(&req.duration_secs) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("duration_secs must be between 1 and {}", MAX_SNOOZE_SECS),
+        ));
+    }
+
+    let until = chrono::Utc::now() + chrono::Duration::seconds(req.duration_secs);
+    if !state
+        .participants
+        .set_snooze(dialog_id, &user_id, Some(until))
+        .await?
+    {
+        return Err(ApiError::new(
+            ErrorCode::NotParticipant,
+            "Not a participant",
+        ));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "snoozed",
+        "snoozed_until": until
+    })))
+}
+
+pub async fn unsnooze_dialog(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !state
+        .participants
+        .set_snooze(dialog_id, &user_id, None)
+        .await?
+    {
+        return Err(ApiError::new(
+            ErrorCode::NotParticipant,
+            "Not a participant",
+        ));
+    }
+
+    Ok(Json(serde_json::json!({ "status": "unsnoozed" })))
+}
+
+/// Apply one per-user action to many dialogs at once
+pub async fn bulk_dialog_action(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(req): Json<BulkActionRequest>,
+) -> Result<Json<ApiResponse<BulkActionResponse>>, ApiError> {
+    let mut dialog_ids = req.dialog_ids;
+    dialog_ids.sort_unstable();
+    dialog_ids.dedup();
+
+    if dialog_ids.is_empty() || dialog_ids.len() > MAX_BULK_DIALOGS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("dialog_ids must contain 1 to {} dialogs", MAX_BULK_DIALOGS),
+        ));
+    }
+
+    // Read pointers before marking read, only needed for message.read webhooks
+    let previous_reads =
+        if req.action == BulkDialogAction::MarkRead && state.webhooks.read_receipts_enabled() {
+            Some(
+                state
+                    .participants
+                    .find_by_dialogs_and_user(&dialog_ids, &user_id)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+    let updated = state
+        .participants
+        .apply_bulk_action(&user_id, &dialog_ids, req.action)
+        .await?;
+    let skipped: Vec<Uuid> = dialog_ids
+        .into_iter()
+        .filter(|id| !updated.contains(id))
+        .collect();
+
+    if !updated.is_empty() {
+        ws::broadcast_dialogs_bulk_updated(
+            &state.connections,
+            &user_id,
+            req.action,
+            updated.clone(),
+        )
+        .await;
+        publish_bulk_activity(&state, &user_id, req.action, &updated);
+        send_bulk_archive_webhooks(&state, &user_id, req.action, &updated).await?;
+        if let Some(previous_reads) = previous_reads {
+            send_bulk_read_receipts(&state, &user_id, &updated, previous_reads).await?;
+        }
+    }
+
+    Ok(Json(ApiResponse {
+        data: BulkActionResponse {
+            action: req.action,
+            updated,
+            skipped,
+        },
+    }))
+}
+
+/// Activity feed events of a bulk (un)archive or (un)pin
+fn publish_bulk_activity(
+    state: &AppState,
+    user_id: &str,
+    action: BulkDialogAction,
+    dialog_ids: &[Uuid],
+) {
+    for &dialog_id in dialog_ids {
+        let event = match action {
+            BulkDialogAction::Archive | BulkDialogAction::Unarchive => {
+                DomainEvent::ArchiveChanged {
+                    dialog_id,
+                    user_ids: vec![user_id.to_string()],
+                    archived: action == BulkDialogAction::Archive,
+                    trigger: ArchiveTrigger::BulkAction,
+                    triggered_by: Some(user_id.to_string()),
+                }
+            }
+            BulkDialogAction::Pin | BulkDialogAction::Unpin => DomainEvent::PinChanged {
+                dialog_id,
+                user_id: user_id.to_string(),
+                pinned: action == BulkDialogAction::Pin,
+            },
+            _ => return,
+        };
+        state.events.publish(event);
+    }
+}
+
+/// Send dialog.archived / dialog.unarchived for a bulk (un)archive
+async fn send_bulk_archive_webhooks(
+    state: &AppState,
+    user_id: &str,
+    action: BulkDialogAction,
+    dialog_ids: &[Uuid],
+) -> Result<(), ApiError> {
+    if !matches!(
+        action,
+        BulkDialogAction::Archive | BulkDialogAction::Unarchive
+    ) {
+        return Ok(());
+    }
+
+    for dialog in state.dialogs.find_by_ids(dialog_ids).await? {
+        let user_ids = vec![user_id.to_string()];
+        let event = if action == BulkDialogAction::Archive {
+            WebhookEvent::dialog_archived(
+                &dialog,
+                user_ids,
+                ArchiveTrigger::BulkAction,
+                Some(user_id),
+            )
+        } else {
+            WebhookEvent::dialog_unarchived(
+                &dialog,
+                user_ids,
+                ArchiveTrigger::BulkAction,
+                Some(user_id),
+            )
+        };
+        state.webhooks.send(event).await;
+    }
+    Ok(())
+}
+
+/// Send message.read for the dialogs whose read pointer a bulk mark_read moved
+async fn send_bulk_read_receipts(
+    state: &AppState,
+    user_id: &str,
+    dialog_ids: &[Uuid],
+    previous: HashMap<Uuid, domain::DialogParticipant>,
+) -> Result<(), ApiError> {
+    let current = state
+        .participants
+        .find_by_dialogs_and_user(dialog_ids, user_id)
+        .await?;
+    for dialog in state.dialogs.find_by_ids(dialog_ids).await? {
+        let Some(last_read) = current.get(&dialog.id).and_then(|p| p.last_read_message_id) else {
+            continue;
+        };
+        let previous = previous
+            .get(&dialog.id)
+            .and_then(|p| p.last_read_message_id);
+        super::participants::send_read_receipt(state, &dialog, user_id, previous, last_read)
+            .await?;
+    }
+    Ok(())
+}
+
 pub async fn get_dialog(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -733,6 +1261,7 @@ pub async fn get_dialog(
             .scopes
             .check_access(
                 dialog_id,
+                &user_id,
                 &scope.scope_level0,
                 &scope.scope_level1,
                 &scope.scope_level2,
@@ -749,18 +1278,24 @@ pub async fn get_dialog(
         ));
     }
 
-    let (participants, participants_count) = if is_participant {
+    let (participants, participants_count, observers_count) = if is_participant {
         let list = state.participants.list_by_dialog(dialog_id).await?;
-        let count = list.len() as i64;
-        (Some(build_participant_summaries(&list)), Some(count))
+        let avatars = resolve_avatars(state.storage.as_ref(), &list).await;
+        (
+            Some(build_participant_summaries(&list, &avatars)),
+            Some(dialog.participants_count as i64),
+            Some(dialog.observers_count as i64),
+        )
     } else {
-        (None, None)
+        (None, None, None)
     };
+    let features = state.feature_flags.flags_for_dialog(dialog_id).await?;
 
     Ok(Json(ApiResponse {
         data: DialogResponse {
             dialog,
             participants_count,
+            observers_count,
             i_am_participant: Some(is_participant),
             can_join: Some(!is_participant && has_scope_access),
             participants,
@@ -768,8 +1303,11 @@ pub async fn get_dialog(
             is_archived: None,
             is_pinned: None,
             notifications_enabled: None,
+            snoozed_until: None,
             last_message_at: None,
             last_message: None,
+            messages_count: None,
+            features: Some(features),
         },
     }))
 }
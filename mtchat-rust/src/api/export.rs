@@ -0,0 +1,134 @@
+//! Incremental exports for BI tools (Management API)
+//!
+//! Each page is JSON Lines, one flat row per line. `X-Next-Cursor` is the
+//! position after the page: pass it as `cursor` to get the next page, or
+//! keep it to resume the sync later. `X-Has-More` tells whether another
+//! page is available right away. Hard-deleted rows are exported as
+//! tombstones from `deletions`.
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::IntoResponse;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    ExportCursor, ExportEntity, DEFAULT_EXPORT_ROWS, EXPORT_SETTLE_SECS, MAX_EXPORT_ROWS,
+};
+
+use super::{ApiError, AppState, ErrorCode};
+
+/// Content type of export pages
+pub const JSONL_CONTENT_TYPE: &str = "application/x-ndjson";
+
+// ============ DTOs ============
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Rows changed at or after this time (ignored before the cursor)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// `X-Next-Cursor` of the previous page
+    pub cursor: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    DEFAULT_EXPORT_ROWS
+}
+
+// ============ Handlers ============
+
+/// Rows of a table changed since `updated_since` or `cursor`
+pub async fn management_export(
+    State(state): State<AppState>,
+    Path(entity): Path<ExportEntity>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let after = query
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            ExportCursor::decode(cursor)
+                .ok_or_else(|| ApiError::new(ErrorCode::InvalidInput, "Invalid export cursor"))
+        })
+        .transpose()?;
+    let limit = query.limit.clamp(1, MAX_EXPORT_ROWS);
+    let settled_before = Utc::now() - Duration::seconds(EXPORT_SETTLE_SECS);
+    let since = query.updated_since;
+    let export = &state.export;
+
+    let (body, has_more, last) = match entity {
+        ExportEntity::Dialogs => {
+            let (rows, has_more) = page(
+                export
+                    .dialogs(since, after.as_ref(), settled_before, limit + 1)
+                    .await?,
+                limit,
+            );
+            (jsonl(&rows)?, has_more, rows.last().map(|r| r.cursor()))
+        }
+        ExportEntity::Messages => {
+            let (rows, has_more) = page(
+                export
+                    .messages(since, after.as_ref(), settled_before, limit + 1)
+                    .await?,
+                limit,
+            );
+            (jsonl(&rows)?, has_more, rows.last().map(|r| r.cursor()))
+        }
+        ExportEntity::Participants => {
+            let (rows, has_more) = page(
+                export
+                    .participants(since, after.as_ref(), settled_before, limit + 1)
+                    .await?,
+                limit,
+            );
+            (jsonl(&rows)?, has_more, rows.last().map(|r| r.cursor()))
+        }
+        ExportEntity::Deletions => {
+            let (rows, has_more) = page(
+                export
+                    .deletions(since, after.as_ref(), settled_before, limit + 1)
+                    .await?,
+                limit,
+            );
+            (jsonl(&rows)?, has_more, rows.last().map(|r| r.cursor()))
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(JSONL_CONTENT_TYPE),
+    );
+    headers.insert(
+        "x-has-more",
+        HeaderValue::from_static(if has_more { "true" } else { "false" }),
+    );
+    if let Some(cursor) = last.or(after) {
+        if let Ok(value) = HeaderValue::from_str(&cursor.encode()) {
+            headers.insert("x-next-cursor", value);
+        }
+    }
+
+    Ok((headers, body))
+}
+
+/// Rows of a page fetched with one extra row, and whether there was one
+fn page<T>(mut rows: Vec<T>, limit: i64) -> (Vec<T>, bool) {
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    (rows, has_more)
+}
+
+/// One JSON object per line
+fn jsonl<T: Serialize>(rows: &[T]) -> Result<Vec<u8>, ApiError> {
+    let mut body = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut body, row)
+            .map_err(|e| ApiError::Internal(format!("Failed to serialize export row: {}", e)))?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
@@ -0,0 +1,165 @@
+//! File routes for the local filesystem storage backend.
+//!
+//! Only mounted when `STORAGE_BACKEND=fs`. Access is granted by the signed
+//! `expires`/`signature` query parameters issued by
+//! [`FsStorage`](crate::services::FsStorage), the same way S3 presigned URLs work.
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::domain;
+use crate::services::{BlobStorage, FileAccess, FsStorage, FILES_ROUTE_PREFIX};
+
+use super::{ApiError, ErrorCode};
+
+#[derive(Debug, Deserialize)]
+pub struct SignedFileQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Build the router serving signed file URLs.
+pub fn router<S>(storage: Arc<FsStorage>) -> Router<S> {
+    Router::new()
+        .route(
+            &format!("{}/{{*key}}", FILES_ROUTE_PREFIX),
+            get(download_file)
+                .put(upload_file)
+                .layer(DefaultBodyLimit::max(
+                    domain::attachment_limits::MAX_FILE_SIZE as usize,
+                )),
+        )
+        .with_state(storage)
+}
+
+pub async fn download_file(
+    State(storage): State<Arc<FsStorage>>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedFileQuery>,
+) -> Result<Response, ApiError> {
+    if !storage.verify_signature(
+        FileAccess::Download,
+        &key,
+        query.expires,
+        "",
+        None,
+        &query.signature,
+    ) {
+        return Err(ApiError::Forbidden("Invalid or expired file URL".into()));
+    }
+
+    let (content_type, _) = storage.get_object_info(&key).await?;
+    let data = storage.get_object(&key).await?;
+
+    // Cached no longer than the URL stays valid, at most the configured expiry
+    let remaining = (query.expires - chrono::Utc::now().timestamp()).max(0) as u64;
+    let max_age = storage
+        .download_expiry_secs()
+        .map_or(remaining, |expiry| remaining.min(expiry));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CACHE_CONTROL,
+                format!("private, max-age={}", max_age),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+pub async fn upload_file(
+    State(storage): State<Arc<FsStorage>>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedFileQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    if !storage.verify_signature(
+        FileAccess::Upload,
+        &key,
+        query.expires,
+        content_type,
+        Some(body.len() as i64),
+        &query.signature,
+    ) {
+        return Err(ApiError::Forbidden("Invalid or expired file URL".into()));
+    }
+
+    if !domain::attachment_limits::is_valid_size(body.len() as i64) {
+        return Err(ApiError::new(
+            ErrorCode::FileTooLarge,
+            format!(
+                "File size must be between 1 byte and {} bytes",
+                domain::attachment_limits::MAX_FILE_SIZE
+            ),
+        ));
+    }
+
+    storage
+        .put_object(&key, body.to_vec(), content_type)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    use crate::services::FsStorageConfig;
+
+    #[tokio::test]
+    async fn test_download_cached_for_url_expiry() {
+        let storage = Arc::new(
+            FsStorage::new(FsStorageConfig {
+                root: std::env::temp_dir().join(format!("mtchat-fs-{}", uuid::Uuid::new_v4())),
+                public_url: "http://localhost:8080".to_string(),
+                secret: "test-secret".to_string(),
+                upload_expiry: Duration::from_secs(300),
+                download_expiry: Duration::from_secs(120),
+            })
+            .unwrap(),
+        );
+        let key = "dialogs/abc/file.txt";
+        storage
+            .put_object(key, b"hello".to_vec(), "text/plain")
+            .await
+            .unwrap();
+        let url = BlobStorage::generate_download_url(storage.as_ref(), key)
+            .await
+            .unwrap();
+        let path = url.strip_prefix("http://localhost:8080").unwrap();
+
+        let response = router::<()>(storage)
+            .oneshot(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let max_age: u64 = response.headers()[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .strip_prefix("private, max-age=")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((119..=120).contains(&max_age), "{}", max_age);
+    }
+}
@@ -0,0 +1,160 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    validation, DialogFilter, DialogFolder, MAX_FOLDERS_PER_USER, MAX_FOLDER_NAME_LENGTH,
+};
+use crate::middleware::UserId;
+
+use super::dialogs::{build_dialog_responses, DialogResponse};
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
+
+// ============ DTOs ============
+
+#[derive(Debug, Deserialize)]
+pub struct FolderRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub filter: DialogFilter,
+    /// Display position (update only; new folders go to the end)
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FolderDialogsQuery {
+    /// Dialogs per folder
+    #[serde(default = "default_per_folder")]
+    pub limit: i64,
+}
+
+fn default_per_folder() -> i64 {
+    20
+}
+
+#[derive(Debug, Serialize)]
+pub struct FolderWithDialogs {
+    pub folder: DialogFolder,
+    pub dialogs: Vec<DialogResponse>,
+}
+
+// ============ Handlers ============
+
+pub async fn list_folders(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<ApiResponse<Vec<DialogFolder>>>, ApiError> {
+    let folders = state.folders.list_for_user(&user_id).await?;
+    Ok(Json(ApiResponse { data: folders }))
+}
+
+pub async fn create_folder(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(req): Json<FolderRequest>,
+) -> Result<Json<ApiResponse<DialogFolder>>, ApiError> {
+    let filter = validate_folder(&req)?;
+
+    if state.folders.count_for_user(&user_id).await? >= MAX_FOLDERS_PER_USER {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("At most {} folders per user", MAX_FOLDERS_PER_USER),
+        ));
+    }
+
+    let folder = DialogFolder::new(&user_id, req.name.trim(), filter);
+    let folder = state.folders.create(&folder).await.map_err(name_conflict)?;
+
+    Ok(Json(ApiResponse { data: folder }))
+}
+
+pub async fn update_folder(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(folder_id): Path<Uuid>,
+    Json(req): Json<FolderRequest>,
+) -> Result<Json<ApiResponse<DialogFolder>>, ApiError> {
+    let filter = validate_folder(&req)?;
+
+    let folder = state
+        .folders
+        .update(folder_id, &user_id, req.name.trim(), &filter, req.position)
+        .await
+        .map_err(name_conflict)?
+        .ok_or_else(|| ApiError::new(ErrorCode::FolderNotFound, "Folder not found"))?;
+
+    Ok(Json(ApiResponse { data: folder }))
+}
+
+pub async fn delete_folder(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(folder_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !state.folders.delete(folder_id, &user_id).await? {
+        return Err(ApiError::new(ErrorCode::FolderNotFound, "Folder not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// All folders of the user with the first dialogs matching each folder
+pub async fn list_folder_dialogs(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<FolderDialogsQuery>,
+) -> Result<Json<ApiResponse<Vec<FolderWithDialogs>>>, ApiError> {
+    let limit = query.limit.clamp(1, 100);
+    let folders = state.folders.list_for_user(&user_id).await?;
+
+    let mut groups = Vec::with_capacity(folders.len());
+    for folder in folders {
+        let dialogs = state
+            .dialogs
+            .find_participating(&user_id, &folder.filter, limit, 0)
+            .await?;
+        let dialogs = build_dialog_responses(&state, &user_id, dialogs, true).await?;
+        groups.push(FolderWithDialogs { folder, dialogs });
+    }
+
+    Ok(Json(ApiResponse { data: groups }))
+}
+
+fn validate_folder(req: &FolderRequest) -> Result<DialogFilter, ApiError> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::new(ErrorCode::InvalidInput, "name is required"));
+    }
+    validation::validate_length(name, "name", MAX_FOLDER_NAME_LENGTH)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    validation::validate_optional_identifier(&req.filter.object_type, "object_type")
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    validation::validate_optional_length(
+        &req.filter.search,
+        "search",
+        validation::MAX_IDENTIFIER_LENGTH,
+    )
+    .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+
+    // Blank search means no search filter
+    let mut filter = req.filter.clone();
+    filter.search = filter
+        .search
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    Ok(filter)
+}
+
+fn name_conflict(e: sqlx::Error) -> ApiError {
+    if e.as_database_error()
+        .is_some_and(|db| db.is_unique_violation())
+    {
+        ApiError::new(
+            ErrorCode::InvalidInput,
+            "A folder with this name already exists",
+        )
+    } else {
+        e.into()
+    }
+}
@@ -1,9 +1,53 @@
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Json};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::config::{Dependency, HealthConfig};
+use crate::migrate;
 
 use super::AppState;
 
+/// Status of a single dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Ok,
+    Down,
+    /// Not configured for this deployment (never affects overall status)
+    Disabled,
+}
+
+/// Overall readiness status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ok,
+    /// A non-critical dependency is down
+    Degraded,
+    /// A critical dependency is down
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyCheck {
+    pub status: DependencyStatus,
+    pub critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub status: ReadinessStatus,
+    pub checks: BTreeMap<&'static str, DependencyCheck>,
+}
+
 pub async fn health() -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "ok",
@@ -11,12 +55,164 @@ pub async fn health() -> impl IntoResponse {
     }))
 }
 
+/// Readiness probe with per-dependency detail.
+///
+/// Returns 503 only when a critical dependency (see `HEALTH_CRITICAL_DEPS`) is down.
+/// Postgres also counts as down while migrations of this build are pending.
 pub async fn health_ready(State(state): State<AppState>) -> impl IntoResponse {
-    match sqlx::query("SELECT 1").execute(&state.db).await {
-        Ok(_) => (StatusCode::OK, Json(serde_json::json!({"status": "ready"}))),
+    let config = HealthConfig::get();
+    let timeout = config.probe_timeout;
+
+    let (postgres, redis, storage, jobs) = tokio::join!(
+        probe(timeout, async {
+            Some(migrate::check_current(&state.db).await)
+        }),
+        probe(timeout, async {
+            state
+                .presence
+                .ping()
+                .await
+                .map(|r| r.map_err(|e| e.to_string()))
+        }),
+        probe(timeout, async {
+            if !state.storage.is_configured() {
+                return None;
+            }
+            Some(
+                state
+                    .storage
+                    .health_check()
+                    .await
+                    .map_err(|e| e.to_string()),
+            )
+        }),
+        probe(timeout, async { jobs_status(&state) }),
+    );
+
+    let mut checks = BTreeMap::new();
+    for (dependency, (status, latency_ms, error)) in [
+        (Dependency::Postgres, postgres),
+        (Dependency::Redis, redis),
+        (Dependency::Storage, storage),
+        (Dependency::Jobs, jobs),
+    ] {
+        checks.insert(
+            dependency.as_str(),
+            DependencyCheck {
+                status,
+                critical: config.is_critical(dependency),
+                latency_ms,
+                error,
+            },
+        );
+    }
+
+    let status = overall_status(checks.values());
+    let code = match status {
+        ReadinessStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+
+    (code, Json(ReadinessResponse { status, checks }))
+}
+
+/// Job workers report through an in-process heartbeat
+fn jobs_status(state: &AppState) -> Option<Result<(), String>> {
+    if !state.jobs.is_enabled() {
+        return None;
+    }
+    let heartbeat = state.jobs.heartbeat();
+    if heartbeat.is_alive() {
+        Some(Ok(()))
+    } else {
+        Some(Err(match heartbeat.age_secs() {
+            Some(age) => format!("No worker heartbeat for {}s", age),
+            None => "Workers have not started".to_string(),
+        }))
+    }
+}
+
+/// Run a probe with a timeout. The probe returns `None` when the dependency
+/// is not configured.
+async fn probe<F>(timeout: Duration, check: F) -> (DependencyStatus, Option<u64>, Option<String>)
+where
+    F: Future<Output = Option<Result<(), String>>>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check).await;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    match result {
+        Ok(None) => (DependencyStatus::Disabled, None, None),
+        Ok(Some(Ok(()))) => (DependencyStatus::Ok, latency_ms, None),
+        Ok(Some(Err(e))) => (DependencyStatus::Down, latency_ms, Some(e)),
         Err(_) => (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({"status": "not_ready"})),
+            DependencyStatus::Down,
+            latency_ms,
+            Some(format!("Timed out after {}ms", timeout.as_millis())),
         ),
     }
 }
+
+fn overall_status<'a>(checks: impl IntoIterator<Item = &'a DependencyCheck>) -> ReadinessStatus {
+    let mut status = ReadinessStatus::Ok;
+    for check in checks {
+        if check.status == DependencyStatus::Down {
+            if check.critical {
+                return ReadinessStatus::Down;
+            }
+            status = ReadinessStatus::Degraded;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(status: DependencyStatus, critical: bool) -> DependencyCheck {
+        DependencyCheck {
+            status,
+            critical,
+            latency_ms: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_overall_status() {
+        assert_eq!(
+            overall_status(&[
+                check(DependencyStatus::Ok, true),
+                check(DependencyStatus::Disabled, false)
+            ]),
+            ReadinessStatus::Ok
+        );
+        assert_eq!(
+            overall_status(&[
+                check(DependencyStatus::Ok, true),
+                check(DependencyStatus::Down, false)
+            ]),
+            ReadinessStatus::Degraded
+        );
+        assert_eq!(
+            overall_status(&[
+                check(DependencyStatus::Down, true),
+                check(DependencyStatus::Ok, false)
+            ]),
+            ReadinessStatus::Down
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_timeout() {
+        let (status, _, error) = probe(Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Some(Ok(()))
+        })
+        .await;
+        assert_eq!(status, DependencyStatus::Down);
+        assert!(error.unwrap().contains("Timed out"));
+    }
+}
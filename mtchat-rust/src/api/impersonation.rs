@@ -0,0 +1,101 @@
+//! Read-only dialog views for support impersonation.
+//!
+//! A token issued through the Management API
+//! (`POST /api/v1/management/dialogs/{id}/impersonations`) is sent in the
+//! `X-Impersonation-Token` header. The views reuse the Chat API handlers with
+//! the impersonated user, so support sees exactly what the participant sees.
+//! Only GET routes exist here, and the token is not accepted anywhere else,
+//! which keeps impersonation read-only. Every view is written to the audit log.
+
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::response::Json;
+
+use crate::domain::{AuditEntry, AUDIT_IMPERSONATION_VIEWED};
+use crate::middleware::{OptionalScopeConfig, UserId};
+use crate::services::{ImpersonationClaims, IMPERSONATION_TOKEN_HEADER};
+
+use super::dialogs::{self, DialogResponse};
+use super::messages::{self, MessagesResponse, PaginationQuery};
+use super::{ApiError, ApiResponse, AppState, AuthChallenge};
+
+/// Verified impersonation token
+pub struct Impersonation(pub ImpersonationClaims);
+
+impl FromRequestParts<AppState> for Impersonation {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(IMPERSONATION_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| {
+                ApiError::unauthorized(AuthChallenge::IMPERSONATION, "Impersonation token required")
+            })?;
+
+        state
+            .impersonation
+            .verify(token)
+            .map(Impersonation)
+            .ok_or_else(|| {
+                ApiError::unauthorized(
+                    AuthChallenge::IMPERSONATION.invalid(),
+                    "Invalid or expired impersonation token",
+                )
+            })
+    }
+}
+
+/// Write the view to the audit log; the view is refused if that fails
+async fn record_view(
+    state: &AppState,
+    claims: &ImpersonationClaims,
+    resource: &str,
+) -> Result<(), ApiError> {
+    let entry = AuditEntry::new(
+        &claims.actor,
+        AUDIT_IMPERSONATION_VIEWED,
+        serde_json::json!({ "session_id": claims.sid, "resource": resource }),
+    )
+    .for_user(claims.dialog_id, &claims.user_id);
+    state.audit_log.record(&entry).await?;
+    Ok(())
+}
+
+/// The dialog as the impersonated participant sees it
+pub async fn get_dialog(
+    State(state): State<AppState>,
+    Impersonation(claims): Impersonation,
+) -> Result<Json<ApiResponse<DialogResponse>>, ApiError> {
+    record_view(&state, &claims, "dialog").await?;
+
+    dialogs::get_dialog(
+        State(state),
+        UserId(claims.user_id),
+        OptionalScopeConfig(None),
+        Path(claims.dialog_id),
+    )
+    .await
+}
+
+/// Messages of the dialog as the impersonated participant sees them
+pub async fn list_messages(
+    State(state): State<AppState>,
+    Impersonation(claims): Impersonation,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<(HeaderMap, Json<ApiResponse<MessagesResponse>>), ApiError> {
+    record_view(&state, &claims, "messages").await?;
+
+    messages::list_messages(
+        State(state),
+        UserId(claims.user_id),
+        Path(claims.dialog_id),
+        Query(pagination),
+    )
+    .await
+}
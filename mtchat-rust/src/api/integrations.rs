@@ -0,0 +1,250 @@
+//! Inbound integration events
+//!
+//! Host systems push events to `POST /api/v1/integrations/events`, signed
+//! like outgoing webhooks (`X-Webhook-Signature` over the raw body with
+//! `WEBHOOK_SECRET`). Each event is converted into an action: post a system
+//! message, archive a dialog or add a participant. Event IDs are recorded, so
+//! a replayed event returns the original result instead of acting again.
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::Json;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::DomainEvent;
+use crate::repositories::InboundEventClaim;
+use crate::webhooks::{verify_signature, ArchiveTrigger, WebhookEvent};
+use crate::ws;
+
+use super::management::{
+    management_add_participant, management_push_system_event, AddParticipantRequest,
+    SystemEventRequest,
+};
+use super::{ApiError, ApiResponse, AppState, AuthChallenge, ErrorCode};
+
+/// Maximum age (and clock skew) of an event's timestamp
+pub const MAX_INBOUND_EVENT_AGE_SECS: i64 = 300;
+
+/// How long processed event IDs are kept: an event is accepted for
+/// `MAX_INBOUND_EVENT_AGE_SECS` on either side of its timestamp
+pub const INBOUND_EVENT_RETENTION_SECS: i64 = 2 * MAX_INBOUND_EVENT_AGE_SECS;
+
+// ============ DTOs ============
+
+/// Event envelope, the same shape as outgoing webhook events
+#[derive(Debug, Deserialize)]
+pub struct InboundEvent {
+    pub id: Uuid,
+    /// `message.system`, `dialog.archive` or `participant.add`
+    pub r#type: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InboundSystemMessage {
+    pub dialog_id: Uuid,
+    #[serde(flatten)]
+    pub event: SystemEventRequest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InboundArchiveDialog {
+    pub dialog_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InboundAddParticipant {
+    pub dialog_id: Uuid,
+    #[serde(flatten)]
+    pub participant: AddParticipantRequest,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InboundEventResponse {
+    pub id: Uuid,
+    pub r#type: String,
+    /// Outcome of the action (the system message for `message.system`)
+    pub result: serde_json::Value,
+}
+
+// ============ Helpers ============
+
+fn parse_payload<T: DeserializeOwned>(event: &InboundEvent) -> Result<T, ApiError> {
+    serde_json::from_value(event.payload.clone()).map_err(|e| {
+        ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid payload for {}: {}", event.r#type, e),
+        )
+    })
+}
+
+/// Archive a dialog for all its participants
+async fn archive_dialog(state: &AppState, dialog_id: Uuid) -> Result<u64, ApiError> {
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let user_ids = state.participants.archive_all_for_dialog(dialog_id).await?;
+    if !user_ids.is_empty() {
+        ws::broadcast_dialog_archived(&state.connections, dialog_id, &user_ids).await;
+        state.events.publish(DomainEvent::ArchiveChanged {
+            dialog_id,
+            user_ids: user_ids.clone(),
+            archived: true,
+            trigger: ArchiveTrigger::Integration,
+            triggered_by: None,
+        });
+        let archived = user_ids.len() as u64;
+        state
+            .webhooks
+            .send(WebhookEvent::dialog_archived(
+                &dialog,
+                user_ids,
+                ArchiveTrigger::Integration,
+                None,
+            ))
+            .await;
+        return Ok(archived);
+    }
+    Ok(0)
+}
+
+/// Perform the action of an event; the outcome becomes the response `result`
+async fn apply_event(
+    state: &AppState,
+    event: &InboundEvent,
+) -> Result<serde_json::Value, ApiError> {
+    Ok(match event.r#type.as_str() {
+        "message.system" => {
+            let req: InboundSystemMessage = parse_payload(event)?;
+            let Json(response) = management_push_system_event(
+                State(state.clone()),
+                Path(req.dialog_id),
+                Json(req.event),
+            )
+            .await?;
+            serde_json::to_value(response.data).map_err(|e| ApiError::Internal(e.to_string()))?
+        }
+        "dialog.archive" => {
+            let req: InboundArchiveDialog = parse_payload(event)?;
+            let archived = archive_dialog(state, req.dialog_id).await?;
+            serde_json::json!({ "archived_participants": archived })
+        }
+        "participant.add" => {
+            let req: InboundAddParticipant = parse_payload(event)?;
+            let user_id = req.participant.user_id.clone();
+            management_add_participant(
+                State(state.clone()),
+                Path(req.dialog_id),
+                Json(req.participant),
+            )
+            .await?;
+            serde_json::json!({ "user_id": user_id })
+        }
+        other => {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("Unknown event type: {}", other),
+            ));
+        }
+    })
+}
+
+// ============ Handlers ============
+
+/// Apply an event pushed by the host system
+pub async fn receive_event(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ApiResponse<InboundEventResponse>>, ApiError> {
+    let secret = &state.config.webhooks.secret;
+    if secret.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::FeatureDisabled,
+            "Inbound events require WEBHOOK_SECRET",
+        ));
+    }
+
+    let signature = headers
+        .get("X-Webhook-Signature")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let body = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::BadRequest("Body must be UTF-8 JSON".into()))?;
+    if !verify_signature(secret, body, signature) {
+        return Err(ApiError::unauthorized(
+            AuthChallenge::WEBHOOK_SIGNATURE,
+            "Invalid or missing X-Webhook-Signature",
+        ));
+    }
+
+    let event: InboundEvent = serde_json::from_str(body)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, format!("Invalid event: {}", e)))?;
+    let age = (Utc::now() - event.timestamp).num_seconds().abs();
+    if age > MAX_INBOUND_EVENT_AGE_SECS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "timestamp must be within {} seconds of the server time",
+                MAX_INBOUND_EVENT_AGE_SECS
+            ),
+        ));
+    }
+
+    // A replayed event returns the recorded result instead of acting again
+    match state
+        .inbound_events
+        .claim(event.id, &event.r#type, INBOUND_EVENT_RETENTION_SECS)
+        .await?
+    {
+        InboundEventClaim::New => {}
+        InboundEventClaim::Applied { event_type, result } => {
+            tracing::info!(event_id = %event.id, "Replayed inbound integration event");
+            return Ok(Json(ApiResponse {
+                data: InboundEventResponse {
+                    id: event.id,
+                    r#type: event_type,
+                    result,
+                },
+            }));
+        }
+        InboundEventClaim::InProgress => {
+            return Err(ApiError::new(
+                ErrorCode::EventInProgress,
+                "The event is being applied",
+            ));
+        }
+    }
+
+    let result = match apply_event(&state, &event).await {
+        Ok(result) => result,
+        Err(e) => {
+            state.inbound_events.release(event.id).await?;
+            return Err(e);
+        }
+    };
+    state.inbound_events.complete(event.id, &result).await?;
+
+    tracing::info!(
+        event_id = %event.id,
+        event_type = %event.r#type,
+        "Applied inbound integration event"
+    );
+
+    Ok(Json(ApiResponse {
+        data: InboundEventResponse {
+            id: event.id,
+            r#type: event.r#type,
+            result,
+        },
+    }))
+}
@@ -1,15 +1,34 @@
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::Json;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, Row};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 use crate::domain::{
-    self, system_messages, Dialog, DialogAccessScope, DialogParticipant, JoinedAs, Message,
-    ParticipantProfile,
+    self, system_messages, AuditEntry, Dialog, DialogAccessScope, DialogBan, DialogParticipant,
+    DialogSla, DialogTemplate, FeatureFlagOverride, FlagScope, JobDeadLetter, JoinedAs, Message,
+    MessageAttribution, ParticipantInvite, ParticipantProfile, SanitizeProfile, ScopeTemplate,
+    SlaPolicy, SlaTarget, StorageScope, StorageUsage, TenantSettings, AUDIT_IMPERSONATION_ISSUED,
+    MAX_AUDIT_ACTOR_LENGTH, MAX_AUDIT_ENTRIES, MAX_BAN_REASON_LENGTH, MAX_BULK_DIALOGS,
+    MAX_DEAD_LETTERS, MAX_IMPORT_MESSAGES, MAX_QA_PAIRS, MAX_REMOVAL_GRACE_SECS, MAX_SLA_SECS,
+    MAX_TEMPLATE_SCOPES, MAX_TENANT_SETTINGS_BYTES, MIN_SLA_SECS,
 };
+use crate::events::DomainEvent;
+use crate::jobs::{TextExtractJob, ThumbnailJob};
+use crate::middleware;
+use crate::repositories::{ActivatedInvite, DialogChildren, DialogRepository};
+use crate::services::{
+    preview, text_extract, ImpersonationClaims, SettingEntry, TranscriptAttachment,
+    MAX_NOTIFICATION_DELAY_MS, MAX_SLOW_MODE_SECS, MAX_TRANSCRIPT_RECIPIENT_LENGTH,
+};
+use crate::webhooks::{EndpointHealth, WebhookEvent};
 use crate::ws;
 
+use super::avatars::cleanup_avatar;
+use super::messages::{insert_attachments, verify_attachments};
 use super::{ApiError, ApiResponse, AppState, ErrorCode};
 
 // ============ DTOs ============
@@ -19,6 +38,8 @@ pub struct ParticipantInput {
     pub user_id: String,
     pub display_name: String,
     pub company: Option<String>,
+    /// Stable company identifier for grouping
+    pub company_uid: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
 }
@@ -34,6 +55,305 @@ pub struct CreateDialogRequest {
     pub access_scopes: Vec<AccessScopeInput>,
     #[serde(default)]
     pub meta: Option<serde_json::Value>,
+    /// IANA timezone for generated content
+    pub timezone: Option<String>,
+    /// BCP 47 locale for generated content
+    pub locale: Option<String>,
+    /// Return the existing dialog of the same object with the same title
+    /// (adding missing participants) instead of creating a duplicate
+    #[serde(default)]
+    pub dedupe: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateDialogLocaleRequest {
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSlowModeRequest {
+    /// Minimum seconds between messages of one participant (null or 0 = off)
+    pub slow_mode_secs: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationDelayRequest {
+    /// Delay before checking whether a notified message was read
+    /// (null = the object type's or global delay)
+    pub notification_delay_ms: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSlaRequest {
+    /// Time to answer the oldest unanswered message (null or 0 = use the
+    /// object type's policy)
+    pub response_secs: Option<i32>,
+    /// Time after which `sla.warning` is sent (default: 80% of `response_secs`)
+    pub warning_secs: Option<i32>,
+    /// Company held to the SLA, by `company_uid` or name (default: the
+    /// dialog creator's company)
+    pub responder_company: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetSlaPolicyRequest {
+    pub response_secs: i32,
+    pub warning_secs: Option<i32>,
+    pub responder_company: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSanitizeProfileRequest {
+    /// Profile of the dialog's messages (null = tenant or global profile)
+    pub sanitize_profile: Option<SanitizeProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SystemEventRequest {
+    /// Event type, e.g. `status_changed` or `deadline_moved`
+    pub r#type: String,
+    /// Structured event data for client rendering
+    #[serde(default = "empty_object")]
+    pub payload: serde_json::Value,
+    /// Fallback text for clients without a renderer for the type
+    pub text: Option<String>,
+    /// When the event happened in the host system
+    pub occurred_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Integration data stored on the message (JSON object)
+    pub metadata: Option<serde_json::Value>,
+    /// Structured content rendered next to the message text
+    pub content_blocks: Option<Vec<domain::ContentBlock>>,
+}
+
+fn empty_object() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportMessagesRequest {
+    /// Sender IDs of the source system mapped to user IDs (unmapped IDs are kept)
+    #[serde(default)]
+    pub sender_map: HashMap<String, String>,
+    pub messages: Vec<ImportMessageInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportMessageInput {
+    pub sender_id: String,
+    /// Sender name shown on the message (default: the participant's current profile)
+    pub sender_display_name: Option<String>,
+    pub sender_company: Option<String>,
+    pub content: String,
+    /// Original send time
+    pub sent_at: DateTime<Utc>,
+    /// Original time of the last edit
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Files already uploaded under the dialog's storage prefix
+    #[serde(default)]
+    pub attachments: Vec<domain::AttachmentInput>,
+    pub metadata: Option<serde_json::Value>,
+    /// Message ID in the source system, kept as `external_id` (default: generated)
+    pub external_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportMessagesResponse {
+    pub imported: usize,
+    /// IDs of the created messages, in request order
+    pub message_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QaExportQuery {
+    /// Company name of the question's sender (as shown on the message)
+    pub company: Option<String>,
+    /// Company identifier of the question's sender
+    pub company_uid: Option<String>,
+    /// Questions sent at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Questions sent before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Continue after this question ID (`next_cursor` of the previous page)
+    pub after: Option<Uuid>,
+    #[serde(default = "default_qa_limit")]
+    pub limit: i64,
+}
+
+fn default_qa_limit() -> i64 {
+    100
+}
+
+#[derive(Debug, Serialize)]
+pub struct QaMessage {
+    pub id: Uuid,
+    pub sender_id: Option<String>,
+    pub sender_name: Option<String>,
+    pub sender_company: Option<String>,
+    /// Sanitized HTML
+    pub content: String,
+    pub sent_at: DateTime<Utc>,
+    pub edited: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<TranscriptAttachment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QaPair {
+    pub question: QaMessage,
+    /// Direct replies to the question, oldest first
+    pub answers: Vec<QaMessage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QaExportResponse {
+    pub dialog_id: Uuid,
+    pub object_type: String,
+    pub object_id: String,
+    pub pairs: Vec<QaPair>,
+    /// Pass as `after` to fetch the next page (`None` on the last page)
+    pub next_cursor: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTenantSettingsRequest {
+    /// Colors, logo and other visual settings for the widget
+    #[serde(default = "empty_object")]
+    pub branding: serde_json::Value,
+    /// Widget behavior switches
+    #[serde(default = "empty_object")]
+    pub behavior: serde_json::Value,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    /// HTML sanitization profile of the tenant's dialogs (null = global)
+    pub sanitize_profile: Option<SanitizeProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetDialogTemplateRequest {
+    /// Title of created dialogs; `{object_id}` is replaced with the object ID
+    pub title: Option<String>,
+    pub access_scopes: Vec<ScopeTemplate>,
+    /// Replace `scope_level0` with the caller's tenants (default: true)
+    #[serde(default = "default_true")]
+    pub caller_tenant: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListConnectionsQuery {
+    /// Only connections of this user
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceConnections {
+    pub instance_id: Uuid,
+    pub connections: usize,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Whether this is the instance that served the request
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserConnection {
+    pub user_id: String,
+    pub instance_id: Uuid,
+    pub connected_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectionsResponse {
+    pub total_connections: usize,
+    pub instances: Vec<InstanceConnections>,
+    pub users: Vec<UserConnection>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookHealthResponse {
+    /// Webhooks are configured
+    pub enabled: bool,
+    /// Delivery health per endpoint, as seen by this instance
+    pub endpoints: Vec<EndpointHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationBacklogResponse {
+    /// The job queue is configured
+    pub enabled: bool,
+    /// Jobs that waited longer than this are dropped
+    pub max_age_secs: i64,
+    /// How long the last job picked up by this instance waited in the queue
+    pub age_secs: u64,
+    pub stale_total: u64,
+    pub skipped_total: u64,
+    /// Jobs enqueued at or before this time are dropped
+    pub skipped_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SkipNotificationsRequest {
+    /// Keep jobs enqueued in the last this many seconds (default: 0 = skip all)
+    pub older_than_secs: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLettersQuery {
+    /// `notification`, `thumbnail`, `text_extract` or `attachment_cleanup`
+    pub job_type: Option<String>,
+    #[serde(default = "default_audit_limit")]
+    pub limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTranscriptLinkRequest {
+    /// Link lifetime in seconds (default and cap: `TRANSCRIPT_MAX_EXPIRY_SECS`)
+    pub expires_in_secs: Option<u64>,
+    /// Who the link is issued to, shown as a watermark on the transcript
+    pub recipient: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranscriptLinkResponse {
+    /// Signed transcript URL (HTML; append `&format=json` for the JSON feed)
+    pub url: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateImpersonationRequest {
+    /// Participant to view the dialog as
+    pub user_id: String,
+    /// Support engineer requesting access, recorded in the audit log
+    pub actor: String,
+    /// Why access is needed (ticket reference), recorded in the audit log
+    pub reason: Option<String>,
+    /// Token lifetime in seconds (default and cap: `IMPERSONATION_MAX_TTL_SECS`)
+    pub expires_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    /// Send as `X-Impersonation-Token` to the read-only impersonation routes
+    pub token: String,
+    pub session_id: Uuid,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub dialog_id: Option<Uuid>,
+    pub action: Option<String>,
+    #[serde(default = "default_audit_limit")]
+    pub limit: i64,
+}
+
+fn default_audit_limit() -> i64 {
+    100
 }
 
 #[derive(Debug, Deserialize)]
@@ -59,8 +379,87 @@ pub struct AddParticipantRequest {
     pub user_id: String,
     pub display_name: String,
     pub company: Option<String>,
+    /// Stable company identifier for grouping
+    pub company_uid: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    /// Add as a read-only observer (reads and receives events, cannot send)
+    #[serde(default)]
+    pub observer: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InviteParticipantRequest {
+    pub email: String,
+    pub display_name: String,
+    pub company: Option<String>,
+    /// Stable company identifier for grouping
+    pub company_uid: Option<String>,
+    pub phone: Option<String>,
+    /// Invite as a read-only observer
+    #[serde(default)]
+    pub observer: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BanUserRequest {
+    /// Note for moderators, not shown to the user
+    pub reason: Option<String>,
+    /// End of the ban (absent = until lifted)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivateInvitesRequest {
+    pub email: String,
+    /// User the invited participant became in the host system
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivateInvitesResponse {
+    /// Dialogs whose invites were activated
+    pub dialog_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveParticipantQuery {
+    /// Keep the participant read-only for this long before removing them
+    /// (default: `jobs.participant_removal_grace_secs`, 0 = remove now)
+    pub grace_secs: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferParticipantRequest {
+    /// User being replaced
+    pub from_user_id: String,
+    /// User taking over the participant's place
+    pub to_user_id: String,
+    /// Dialogs to transfer (all dialogs of `from_user_id` if omitted)
+    pub dialog_ids: Option<Vec<Uuid>>,
+    pub display_name: String,
+    /// Defaults to the company of the replaced participant
+    pub company: Option<String>,
+    /// Defaults to the company identifier of the replaced participant
+    pub company_uid: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
+    /// Who authored the replaced participant's messages afterwards
+    #[serde(default)]
+    pub messages: MessageAttribution,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferParticipantResponse {
+    pub transferred: Vec<Uuid>,
+    pub skipped: Vec<SkippedTransfer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedTransfer {
+    pub dialog_id: Uuid,
+    /// `dialog_not_found`, `not_participant` or `already_participant`
+    pub reason: &'static str,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,7 +472,36 @@ pub struct ManagementDialogResponse {
     #[serde(flatten)]
     pub dialog: Dialog,
     pub participants: Vec<DialogParticipant>,
+    /// Participants invited by email and not activated yet
+    pub invites: Vec<ParticipantInvite>,
     pub access_scopes: Vec<DialogAccessScope>,
+    /// Users banned from joining, bans in effect only
+    pub bans: Vec<DialogBan>,
+    /// Response time SLA and its current status (null = no SLA)
+    pub sla: Option<DialogSla>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetStorageQuotaRequest {
+    /// Quota in bytes (0 = unlimited, null = use the configured default)
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageUsageResponse {
+    #[serde(flatten)]
+    pub usage: StorageUsage,
+    /// Quota actually enforced (null = unlimited)
+    pub effective_quota_bytes: Option<i64>,
+}
+
+impl StorageUsageResponse {
+    fn new(state: &AppState, usage: StorageUsage) -> Self {
+        Self {
+            effective_quota_bytes: state.config.storage_quotas.effective_quota(&usage),
+            usage,
+        }
+    }
 }
 
 // ============ Handlers ============
@@ -90,15 +518,37 @@ pub async fn management_create_dialog(
             .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
         domain::validation::validate_company(&participant.company)
             .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+        domain::validation::validate_company_uid(&participant.company_uid)
+            .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
         domain::validation::validate_email(&participant.email)
             .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
         domain::validation::validate_phone(&participant.phone)
             .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     }
+    validate_locale_input(&state, &req.timezone, &req.locale).await?;
 
-    let mut tx = state.db.begin().await?;
+    let mut tx = middleware::begin_transaction(&state.db).await?;
 
-    // Create dialog
+    if req.dedupe {
+        if let Some(dialog) = find_duplicate_dialog(&mut tx, &req).await? {
+            let added = sync_participants(&mut tx, dialog.id, &req.participants).await?;
+            tx.commit().await?;
+
+            for user_id in added {
+                ws::broadcast_participant_joined(&state.connections, dialog.id, &user_id).await;
+                state.events.publish(DomainEvent::ParticipantJoined {
+                    dialog_id: dialog.id,
+                    user_id,
+                    joined_as: JoinedAs::Participant,
+                    added_by: None,
+                });
+            }
+            return Ok(Json(ApiResponse { data: dialog }));
+        }
+    }
+
+    // Create the dialog with its participants, scopes and "chat created"
+    // message in the same transaction
     let created_by = req.participants.first().map(|p| p.user_id.clone());
     let dialog = Dialog::new(
         req.object_id,
@@ -107,71 +557,40 @@ pub async fn management_create_dialog(
         req.object_url,
         created_by,
         req.meta,
-    );
-    let dialog = sqlx::query_as::<_, Dialog>(
-        r#"INSERT INTO dialogs (id, object_id, object_type, title, object_url, created_by, created_at, meta)
-           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-           RETURNING *"#,
     )
-    .bind(dialog.id)
-    .bind(dialog.object_id)
-    .bind(&dialog.object_type)
-    .bind(&dialog.title)
-    .bind(&dialog.object_url)
-    .bind(dialog.created_by)
-    .bind(dialog.created_at)
-    .bind(&dialog.meta)
-    .fetch_one(&mut *tx)
-    .await?;
-
-    // Add participants with their profiles
-    for participant in req.participants.iter() {
-        let profile = ParticipantProfile {
-            display_name: participant.display_name.clone(),
-            company: participant.company.clone(),
-            email: participant.email.clone(),
-            phone: participant.phone.clone(),
-        };
-        sqlx::query(
-            r#"INSERT INTO dialog_participants
-               (dialog_id, user_id, joined_as, joined_at, display_name, company, email, phone)
-               VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7)"#,
-        )
-        .bind(dialog.id)
-        .bind(&participant.user_id)
-        .bind(&JoinedAs::Participant)
-        .bind(&profile.display_name)
-        .bind(&profile.company)
-        .bind(&profile.email)
-        .bind(&profile.phone)
-        .execute(&mut *tx)
-        .await?;
-    }
-
-    // Add access scopes
-    for scope_input in req.access_scopes {
-        let scope = DialogAccessScope::new(
-            dialog.id,
-            scope_input.scope_level0,
-            scope_input.scope_level1,
-            scope_input.scope_level2,
-        );
-        sqlx::query(
-            r#"INSERT INTO dialog_access_scopes (id, dialog_id, scope_level0, scope_level1, scope_level2, created_at)
-               VALUES ($1, $2, $3, $4, $5, $6)"#,
-        )
-        .bind(scope.id)
-        .bind(scope.dialog_id)
-        .bind(&scope.scope_level0)
-        .bind(&scope.scope_level1)
-        .bind(&scope.scope_level2)
-        .bind(scope.created_at)
-        .execute(&mut *tx)
-        .await?;
-    }
-
-    // Create system message "chat created"
-    if !req.participants.is_empty() {
+    .with_locale(req.timezone, req.locale);
+    let participants = req
+        .participants
+        .iter()
+        .map(|participant| {
+            let profile = ParticipantProfile {
+                display_name: participant.display_name.clone(),
+                company: participant.company.clone(),
+                company_uid: participant.company_uid.clone(),
+                email: participant.email.clone(),
+                phone: participant.phone.clone(),
+            };
+            DialogParticipant::with_profile(
+                dialog.id,
+                &participant.user_id,
+                JoinedAs::Participant,
+                profile,
+            )
+        })
+        .collect();
+    let access_scopes = req
+        .access_scopes
+        .into_iter()
+        .map(|scope| {
+            DialogAccessScope::new(
+                dialog.id,
+                scope.scope_level0,
+                scope.scope_level1,
+                scope.scope_level2,
+            )
+        })
+        .collect();
+    let system_message = (!req.participants.is_empty()).then(|| {
         let participant_infos: Vec<system_messages::ParticipantInfo> = req
             .participants
             .iter()
@@ -180,24 +599,17 @@ pub async fn management_create_dialog(
                 company: p.company.clone(),
             })
             .collect();
-        let system_msg = Message::system(
+        Message::system(
             dialog.id,
-            system_messages::chat_created_content(participant_infos),
-        );
-        sqlx::query(
-            r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type)
-               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+            system_messages::chat_created_content(participant_infos, &dialog.locale_context()),
         )
-        .bind(system_msg.id)
-        .bind(system_msg.dialog_id)
-        .bind(system_msg.sender_id)
-        .bind(&system_msg.content)
-        .bind(system_msg.sent_at)
-        .bind(system_msg.reply_to_id)
-        .bind(system_msg.message_type.as_str())
-        .execute(&mut *tx)
-        .await?;
-    }
+    });
+    let children = DialogChildren {
+        participants,
+        access_scopes,
+        system_message,
+    };
+    let dialog = DialogRepository::insert_with_children(&mut tx, &dialog, &children).await?;
 
     tx.commit().await?;
 
@@ -209,6 +621,69 @@ pub async fn management_create_dialog(
     Ok(Json(ApiResponse { data: dialog }))
 }
 
+/// Existing dialog a `dedupe` create resolves to: the oldest live dialog of
+/// the object with the same title.
+///
+/// Takes a transaction-scoped advisory lock on the object first, so
+/// concurrent creates for one object run one after another and the second
+/// finds the dialog the first created.
+async fn find_duplicate_dialog(
+    conn: &mut PgConnection,
+    req: &CreateDialogRequest,
+) -> Result<Option<Dialog>, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("dialog:{}:{}", req.object_type, req.object_id))
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query_as::<_, Dialog>(
+        r#"SELECT * FROM dialogs
+           WHERE object_type = $1 AND object_id = $2 AND title IS NOT DISTINCT FROM $3
+             AND deleted_at IS NULL
+           ORDER BY created_at
+           LIMIT 1"#,
+    )
+    .bind(&req.object_type)
+    .bind(&req.object_id)
+    .bind(&req.title)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Add the requested participants missing from an existing dialog and cancel
+/// pending removals of those already in it. Returns the added user IDs.
+async fn sync_participants(
+    conn: &mut PgConnection,
+    dialog_id: Uuid,
+    participants: &[ParticipantInput],
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut added = Vec::new();
+    for participant in participants {
+        let inserted = sqlx::query(
+            r#"INSERT INTO dialog_participants
+               (dialog_id, user_id, joined_as, joined_at, display_name, company, company_uid, email, phone)
+               VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7, $8)
+               ON CONFLICT (dialog_id, user_id) DO UPDATE SET pending_removal_at = NULL
+               RETURNING (xmax = 0) AS inserted"#,
+        )
+        .bind(dialog_id)
+        .bind(&participant.user_id)
+        .bind(&JoinedAs::Participant)
+        .bind(&participant.display_name)
+        .bind(&participant.company)
+        .bind(&participant.company_uid)
+        .bind(&participant.email)
+        .bind(&participant.phone)
+        .fetch_one(&mut *conn)
+        .await?
+        .get::<bool, _>("inserted");
+        if inserted {
+            added.push(participant.user_id.clone());
+        }
+    }
+    Ok(added)
+}
+
 /// Find an existing dialog by object + access scopes (for idempotent find-or-create).
 ///
 /// Matches the dialog whose access scopes are exactly equal to the requested
@@ -296,6 +771,8 @@ pub async fn management_add_participant(
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     domain::validation::validate_company(&req.company)
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_company_uid(&req.company_uid)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     domain::validation::validate_email(&req.email)
         .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
     domain::validation::validate_phone(&req.phone)
@@ -311,65 +788,1597 @@ pub async fn management_add_participant(
     let profile = ParticipantProfile {
         display_name: req.display_name,
         company: req.company,
+        company_uid: req.company_uid,
         email: req.email,
         phone: req.phone,
     };
+    let joined_as = if req.observer {
+        JoinedAs::Observer
+    } else {
+        JoinedAs::Participant
+    };
+    let added = state
+        .participants
+        .add_with_profile_if_not_exists(dialog_id, &req.user_id, joined_as.clone(), &profile)
+        .await?;
+    // Adding a participant again cancels their pending removal
     state
         .participants
-        .add_with_profile_if_not_exists(dialog_id, &req.user_id, JoinedAs::Participant, &profile)
+        .set_pending_removal(dialog_id, &req.user_id, None)
         .await?;
 
     // Broadcast participant joined event (for dialog list updates)
     ws::broadcast_participant_joined(&state.connections, dialog_id, &req.user_id).await;
+    if added {
+        state.events.publish(DomainEvent::ParticipantJoined {
+            dialog_id,
+            user_id: req.user_id,
+            joined_as,
+            added_by: None,
+        });
+    }
 
     Ok(StatusCode::CREATED)
 }
 
-pub async fn management_remove_participant(
+/// Invite a participant by email, before they have a user ID. Inviting the
+/// same email again updates the pending invite.
+pub async fn management_invite_participant(
     State(state): State<AppState>,
-    Path((dialog_id, user_id)): Path<(Uuid, String)>,
-) -> Result<StatusCode, ApiError> {
-    state.participants.remove(dialog_id, &user_id).await?;
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<InviteParticipantRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<ParticipantInvite>>), ApiError> {
+    domain::validation::validate_invite_email(&req.email)
+        .and_then(|_| domain::validation::validate_display_name(&req.display_name))
+        .and_then(|_| domain::validation::validate_company(&req.company))
+        .and_then(|_| domain::validation::validate_company_uid(&req.company_uid))
+        .and_then(|_| domain::validation::validate_phone(&req.phone))
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
 
-    // Broadcast participant left event (for dialog list updates)
-    ws::broadcast_participant_left(&state.connections, dialog_id, &user_id).await;
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Dialog not found".into()))?;
 
-    Ok(StatusCode::NO_CONTENT)
+    let profile = ParticipantProfile {
+        display_name: req.display_name,
+        company: req.company,
+        company_uid: req.company_uid,
+        email: None,
+        phone: req.phone,
+    };
+    let joined_as = if req.observer {
+        JoinedAs::Observer
+    } else {
+        JoinedAs::Participant
+    };
+    let invite = ParticipantInvite::new(dialog_id, &req.email, joined_as, profile);
+    let invite = state.invites.upsert(&invite).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse { data: invite })))
 }
 
-pub async fn management_delete_dialog(
+pub async fn management_list_invites(
     State(state): State<AppState>,
     Path(dialog_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<ParticipantInvite>>>, ApiError> {
+    let invites = state.invites.list_by_dialog(dialog_id).await?;
+    Ok(Json(ApiResponse { data: invites }))
+}
+
+pub async fn management_revoke_invite(
+    State(state): State<AppState>,
+    Path((dialog_id, invite_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, ApiError> {
-    state.dialogs.delete(dialog_id).await?;
+    if !state.invites.delete(dialog_id, invite_id).await? {
+        return Err(ApiError::NotFound("Invite not found".into()));
+    }
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn management_update_access_scopes(
+/// Ban a user from joining the dialog. Banning again replaces the reason
+/// and expiry. A current participant stays in the dialog.
+pub async fn management_ban_user(
     State(state): State<AppState>,
-    Path(dialog_id): Path<Uuid>,
-    Json(req): Json<UpdateAccessScopesRequest>,
-) -> Result<Json<ApiResponse<Vec<DialogAccessScope>>>, ApiError> {
-    // Check dialog exists
+    Path((dialog_id, user_id)): Path<(Uuid, String)>,
+    Json(req): Json<BanUserRequest>,
+) -> Result<Json<ApiResponse<DialogBan>>, ApiError> {
+    domain::validation::validate_identifier(&user_id, "user_id")
+        .and_then(|_| {
+            domain::validation::validate_optional_length(
+                &req.reason,
+                "reason",
+                MAX_BAN_REASON_LENGTH,
+            )
+        })
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    if req
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "expires_at must be in the future",
+        ));
+    }
+
     state
         .dialogs
         .find_by_id(dialog_id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Dialog not found".into()))?;
 
-    // Delete existing scopes and create new ones
-    let new_scopes: Vec<DialogAccessScope> = req
-        .access_scopes
-        .into_iter()
-        .map(|s| DialogAccessScope::new(dialog_id, s.scope_level0, s.scope_level1, s.scope_level2))
-        .collect();
+    let ban = DialogBan::new(dialog_id, &user_id, req.reason, req.expires_at);
+    let ban = state.bans.upsert(&ban).await?;
 
-    let created = state
-        .scopes
+    Ok(Json(ApiResponse { data: ban }))
+}
+
+pub async fn management_list_bans(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<DialogBan>>>, ApiError> {
+    let bans = state.bans.list_active(dialog_id).await?;
+    Ok(Json(ApiResponse { data: bans }))
+}
+
+pub async fn management_unban_user(
+    State(state): State<AppState>,
+    Path((dialog_id, user_id)): Path<(Uuid, String)>,
+) -> Result<StatusCode, ApiError> {
+    if !state.bans.delete(dialog_id, &user_id).await? {
+        return Err(ApiError::NotFound("Ban not found".into()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Resolve an email's pending invites to the user ID the host assigned, in
+/// every dialog the email was invited to
+pub async fn management_activate_invites(
+    State(state): State<AppState>,
+    Json(req): Json<ActivateInvitesRequest>,
+) -> Result<Json<ApiResponse<ActivateInvitesResponse>>, ApiError> {
+    domain::validation::validate_invite_email(&req.email)
+        .and_then(|_| domain::validation::validate_identifier(&req.user_id, "user_id"))
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+
+    let email = domain::normalize_email(&req.email);
+    let activated = state.invites.activate(&email, &req.user_id).await?;
+
+    let mut dialog_ids = Vec::with_capacity(activated.len());
+    for ActivatedInvite { invite, joined } in activated {
+        // Existing participants were kept, so nobody joined
+        if joined {
+            ws::broadcast_participant_joined(&state.connections, invite.dialog_id, &req.user_id)
+                .await;
+            state.events.publish(DomainEvent::ParticipantJoined {
+                dialog_id: invite.dialog_id,
+                user_id: req.user_id.clone(),
+                joined_as: invite.joined_as.clone(),
+                added_by: None,
+            });
+        }
+        dialog_ids.push(invite.dialog_id);
+    }
+    tracing::info!(
+        user_id = %req.user_id,
+        dialogs = dialog_ids.len(),
+        "Activated participant invites"
+    );
+
+    Ok(Json(ApiResponse {
+        data: ActivateInvitesResponse { dialog_ids },
+    }))
+}
+
+/// Remove a participant. With a grace period the participant stays in the
+/// dialog read-only until `pending_removal_at`, when the removal job removes
+/// them (202 Accepted); without one they are removed immediately.
+pub async fn management_remove_participant(
+    State(state): State<AppState>,
+    Path((dialog_id, user_id)): Path<(Uuid, String)>,
+    Query(query): Query<RemoveParticipantQuery>,
+) -> Result<StatusCode, ApiError> {
+    let grace_secs = query
+        .grace_secs
+        .unwrap_or(state.config.jobs.participant_removal_grace_secs);
+    if !(0..=MAX_REMOVAL_GRACE_SECS).contains(&grace_secs) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "grace_secs must be between 0 and {}",
+                MAX_REMOVAL_GRACE_SECS
+            ),
+        ));
+    }
+
+    let participant = state.participants.find(dialog_id, &user_id).await?;
+    if grace_secs > 0 {
+        if participant.is_none() {
+            return Err(ApiError::new(
+                ErrorCode::ParticipantNotFound,
+                "Participant not found",
+            ));
+        }
+        let removal_at = Utc::now() + chrono::Duration::seconds(grace_secs);
+        state
+            .participants
+            .set_pending_removal(dialog_id, &user_id, Some(removal_at))
+            .await?;
+        // Read-only participants get no notifications
+        if let Err(e) = state.jobs.cancel_notifications(dialog_id, &user_id).await {
+            tracing::warn!(error = %e, "Failed to cancel pending notifications");
+        }
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    if state.participants.remove(dialog_id, &user_id).await? {
+        state.events.publish(DomainEvent::ParticipantLeft {
+            dialog_id,
+            user_id: user_id.clone(),
+            removed_by: None,
+        });
+    }
+
+    if let Err(e) = state.jobs.cancel_notifications(dialog_id, &user_id).await {
+        tracing::warn!(error = %e, "Failed to cancel pending notifications");
+    }
+    cleanup_avatar(&state, dialog_id, participant.and_then(|p| p.avatar_s3_key)).await;
+
+    // Broadcast participant left event (for dialog list updates)
+    ws::broadcast_participant_left(&state.connections, dialog_id, &user_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Transfer a participant's place to another user (an employee replaced by a
+/// colleague). Role, join time and read state carry over; personal list state
+/// (archive, pin, snooze, mute) and the avatar are reset. Each dialog is
+/// transferred in its own transaction and gets a "participant replaced"
+/// system message. Dialogs where the new user already participates are skipped.
+pub async fn management_transfer_participant(
+    State(state): State<AppState>,
+    Json(req): Json<TransferParticipantRequest>,
+) -> Result<Json<ApiResponse<TransferParticipantResponse>>, ApiError> {
+    if req.from_user_id.is_empty() || req.to_user_id.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "from_user_id and to_user_id are required",
+        ));
+    }
+    if req.from_user_id == req.to_user_id {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "from_user_id and to_user_id must differ",
+        ));
+    }
+    if req
+        .dialog_ids
+        .as_ref()
+        .is_some_and(|ids| ids.is_empty() || ids.len() > MAX_BULK_DIALOGS)
+    {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("dialog_ids must contain 1 to {} dialogs", MAX_BULK_DIALOGS),
+        ));
+    }
+    domain::validation::validate_display_name(&req.display_name)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_company(&req.company)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_company_uid(&req.company_uid)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_email(&req.email)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_phone(&req.phone)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+
+    let dialog_ids = match &req.dialog_ids {
+        Some(ids) => ids.clone(),
+        None => {
+            state
+                .participants
+                .get_user_dialogs(&req.from_user_id)
+                .await?
+        }
+    };
+
+    let mut transferred = Vec::new();
+    let mut skipped = Vec::new();
+    for dialog_id in dialog_ids {
+        let skip = |reason| SkippedTransfer { dialog_id, reason };
+        let Some(dialog) = state.dialogs.find_by_id(dialog_id).await? else {
+            skipped.push(skip("dialog_not_found"));
+            continue;
+        };
+        let Some(old) = state
+            .participants
+            .find(dialog_id, &req.from_user_id)
+            .await?
+        else {
+            skipped.push(skip("not_participant"));
+            continue;
+        };
+        if state
+            .participants
+            .exists(dialog_id, &req.to_user_id)
+            .await?
+        {
+            skipped.push(skip("already_participant"));
+            continue;
+        }
+
+        let Some((participant, system_msg)) =
+            transfer_in_dialog(&state, &dialog, &old, &req).await?
+        else {
+            // Left or joined concurrently
+            skipped.push(skip("not_participant"));
+            continue;
+        };
+        transferred.push(dialog_id);
+
+        if let Err(e) = state
+            .jobs
+            .cancel_notifications(dialog_id, &req.from_user_id)
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to cancel pending notifications");
+        }
+        cleanup_avatar(&state, dialog_id, old.avatar_s3_key).await;
+
+        ws::broadcast_message(
+            &state.connections,
+            &state.participants,
+            dialog_id,
+            &system_msg,
+            None,
+        )
+        .await;
+        ws::broadcast_participant_left(&state.connections, dialog_id, &req.from_user_id).await;
+        ws::broadcast_participant_joined(&state.connections, dialog_id, &req.to_user_id).await;
+        state
+            .webhooks
+            .send(WebhookEvent::participant_left(&dialog, &req.from_user_id))
+            .await;
+        state
+            .webhooks
+            .send(WebhookEvent::participant_joined(&dialog, &participant))
+            .await;
+        state.events.publish(DomainEvent::ParticipantLeft {
+            dialog_id,
+            user_id: req.from_user_id.clone(),
+            removed_by: None,
+        });
+        state.events.publish(DomainEvent::ParticipantJoined {
+            dialog_id,
+            user_id: req.to_user_id.clone(),
+            joined_as: participant.joined_as.clone(),
+            added_by: None,
+        });
+    }
+
+    Ok(Json(ApiResponse {
+        data: TransferParticipantResponse {
+            transferred,
+            skipped,
+        },
+    }))
+}
+
+/// Move one dialog's participant row, mentions and (optionally) messages to
+/// the new user. `None` if the row changed since it was read.
+async fn transfer_in_dialog(
+    state: &AppState,
+    dialog: &Dialog,
+    old: &DialogParticipant,
+    req: &TransferParticipantRequest,
+) -> Result<Option<(DialogParticipant, Message)>, ApiError> {
+    let company = req.company.clone().or_else(|| old.company.clone());
+    let company_uid = req.company_uid.clone().or_else(|| old.company_uid.clone());
+    let system_msg = Message::system(
+        dialog.id,
+        system_messages::participant_replaced_content(
+            old.display_name.as_deref().unwrap_or("Участник"),
+            &req.display_name,
+            company.as_deref(),
+            &dialog.locale_context(),
+        ),
+    );
+
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+
+    let participant = sqlx::query_as::<_, DialogParticipant>(
+        r#"UPDATE dialog_participants
+           SET user_id = $3, display_name = $4, company = $5, company_uid = $6,
+               email = $7, phone = $8,
+               notifications_enabled = TRUE, is_archived = FALSE, is_pinned = FALSE,
+               snoozed_until = NULL, avatar_s3_key = NULL, avatar_variants_ready = FALSE
+           WHERE dialog_id = $1 AND user_id = $2
+             AND NOT EXISTS (
+                 SELECT 1 FROM dialog_participants WHERE dialog_id = $1 AND user_id = $3
+             )
+           RETURNING *"#,
+    )
+    .bind(dialog.id)
+    .bind(&req.from_user_id)
+    .bind(&req.to_user_id)
+    .bind(&req.display_name)
+    .bind(&company)
+    .bind(&company_uid)
+    .bind(&req.email)
+    .bind(&req.phone)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(participant) = participant else {
+        return Ok(None);
+    };
+
+    // Mentions follow the read state, so unread mention counts carry over
+    sqlx::query(
+        r#"UPDATE message_mentions mm SET user_id = $3
+           WHERE mm.dialog_id = $1 AND mm.user_id = $2
+             AND NOT EXISTS (
+                 SELECT 1 FROM message_mentions WHERE message_id = mm.message_id AND user_id = $3
+             )"#,
+    )
+    .bind(dialog.id)
+    .bind(&req.from_user_id)
+    .bind(&req.to_user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if req.messages == MessageAttribution::Reassign {
+        // The sender snapshot keeps the name the messages were sent under
+        sqlx::query(
+            r#"UPDATE messages SET sender_id = $3
+               WHERE dialog_id = $1 AND sender_id = $2"#,
+        )
+        .bind(dialog.id)
+        .bind(&req.from_user_id)
+        .bind(&req.to_user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let system_msg = sqlx::query_as::<_, Message>(
+        r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type, external_id)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+           RETURNING *"#,
+    )
+    .bind(system_msg.id)
+    .bind(system_msg.dialog_id)
+    .bind(system_msg.sender_id)
+    .bind(&system_msg.content)
+    .bind(system_msg.sent_at)
+    .bind(system_msg.reply_to_id)
+    .bind(system_msg.message_type.as_str())
+    .bind(&system_msg.external_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some((participant, system_msg)))
+}
+
+/// Soft-delete a dialog. It is hidden everywhere, can be restored within
+/// `jobs.dialog_retention_secs` and is purged by the purge job afterwards.
+pub async fn management_delete_dialog(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state.dialogs.soft_delete(dialog_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn management_restore_dialog(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ManagementDialogResponse>>, ApiError> {
+    let deleted_since =
+        chrono::Utc::now() - chrono::Duration::seconds(state.config.jobs.dialog_retention_secs);
+    let dialog = state
+        .dialogs
+        .restore(dialog_id, deleted_since)
+        .await?
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::DialogNotFound,
+                "No deleted dialog to restore (never deleted, or retention expired)",
+            )
+        })?;
+
+    let participants = state.participants.list_by_dialog(dialog_id).await?;
+    let invites = state.invites.list_by_dialog(dialog_id).await?;
+    let access_scopes = state.scopes.find_by_dialog(dialog_id).await?;
+    let bans = state.bans.list_active(dialog_id).await?;
+    let sla = dialog_sla(&state, &dialog).await?;
+
+    Ok(Json(ApiResponse {
+        data: ManagementDialogResponse {
+            dialog,
+            participants,
+            invites,
+            access_scopes,
+            bans,
+            sla,
+        },
+    }))
+}
+
+pub async fn management_get_dialog_storage(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<StorageUsageResponse>>>, ApiError> {
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let usages = state.storage_usage.find_for_dialog(dialog_id).await?;
+
+    Ok(Json(ApiResponse {
+        data: usages
+            .into_iter()
+            .map(|u| StorageUsageResponse::new(&state, u))
+            .collect(),
+    }))
+}
+
+pub async fn management_set_dialog_quota(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<SetStorageQuotaRequest>,
+) -> Result<Json<ApiResponse<StorageUsageResponse>>, ApiError> {
+    validate_quota(req.quota_bytes)?;
+
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let usage = state
+        .storage_usage
+        .set_quota(
+            StorageScope::Dialog,
+            &dialog_id.to_string(),
+            req.quota_bytes,
+        )
+        .await?;
+
+    Ok(Json(ApiResponse {
+        data: StorageUsageResponse::new(&state, usage),
+    }))
+}
+
+pub async fn management_get_tenant_storage(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Result<Json<ApiResponse<StorageUsageResponse>>, ApiError> {
+    let usage = state
+        .storage_usage
+        .find(StorageScope::Tenant, &tenant)
+        .await?;
+
+    Ok(Json(ApiResponse {
+        data: StorageUsageResponse::new(&state, usage),
+    }))
+}
+
+pub async fn management_get_tenant_settings(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Result<Json<ApiResponse<TenantSettings>>, ApiError> {
+    let settings = state
+        .tenant_settings
+        .find(&tenant)
+        .await?
+        .unwrap_or_else(|| TenantSettings::empty(&tenant));
+
+    Ok(Json(ApiResponse { data: settings }))
+}
+
+/// Replace the widget settings of a tenant
+pub async fn management_set_tenant_settings(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+    Json(req): Json<SetTenantSettingsRequest>,
+) -> Result<Json<ApiResponse<TenantSettings>>, ApiError> {
+    for (field, value) in [("branding", &req.branding), ("behavior", &req.behavior)] {
+        if !value.is_object() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("{} must be a JSON object", field),
+            ));
+        }
+        if value.to_string().len() > MAX_TENANT_SETTINGS_BYTES {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("{} exceeds {} bytes", field, MAX_TENANT_SETTINGS_BYTES),
+            ));
+        }
+    }
+    validate_locale_input(&state, &req.timezone, &req.locale).await?;
+
+    let settings = TenantSettings {
+        branding: req.branding,
+        behavior: req.behavior,
+        locale: req.locale,
+        timezone: req.timezone,
+        sanitize_profile: req.sanitize_profile,
+        ..TenantSettings::empty(tenant)
+    };
+    let settings = state.tenant_settings.upsert(&settings).await?;
+
+    Ok(Json(ApiResponse { data: settings }))
+}
+
+pub async fn management_delete_tenant_settings(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.tenant_settings.delete(&tenant).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn management_list_dialog_templates(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<DialogTemplate>>>, ApiError> {
+    let templates = state.dialog_templates.list().await?;
+    Ok(Json(ApiResponse { data: templates }))
+}
+
+/// Create dialogs of an object type on first access, from this template
+pub async fn management_set_dialog_template(
+    State(state): State<AppState>,
+    Path(object_type): Path<String>,
+    Json(req): Json<SetDialogTemplateRequest>,
+) -> Result<Json<ApiResponse<DialogTemplate>>, ApiError> {
+    let invalid =
+        |e: domain::validation::ValidationError| ApiError::new(ErrorCode::InvalidInput, e.message);
+    domain::validation::validate_identifier(&object_type, "object_type").map_err(invalid)?;
+    domain::validation::validate_title(&req.title).map_err(invalid)?;
+    if req.access_scopes.is_empty() || req.access_scopes.len() > MAX_TEMPLATE_SCOPES {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "access_scopes must contain 1 to {} scopes",
+                MAX_TEMPLATE_SCOPES
+            ),
+        ));
+    }
+    for scope in &req.access_scopes {
+        for value in scope
+            .scope_level0
+            .iter()
+            .chain(&scope.scope_level1)
+            .chain(&scope.scope_level2)
+        {
+            domain::validation::validate_identifier(value, "access_scopes").map_err(invalid)?;
+        }
+    }
+
+    let template = DialogTemplate {
+        title: req.title,
+        caller_tenant: req.caller_tenant,
+        ..DialogTemplate::new(object_type, req.access_scopes)
+    };
+    let template = state.dialog_templates.upsert(&template).await?;
+
+    Ok(Json(ApiResponse { data: template }))
+}
+
+pub async fn management_delete_dialog_template(
+    State(state): State<AppState>,
+    Path(object_type): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.dialog_templates.delete(&object_type).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn management_list_sla_policies(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<SlaPolicy>>>, ApiError> {
+    let policies = state.sla.list_policies().await?;
+    Ok(Json(ApiResponse { data: policies }))
+}
+
+/// Hold dialogs of an object type to a response time SLA (dialogs with an
+/// SLA of their own keep it)
+pub async fn management_set_sla_policy(
+    State(state): State<AppState>,
+    Path(object_type): Path<String>,
+    Json(req): Json<SetSlaPolicyRequest>,
+) -> Result<Json<ApiResponse<SlaPolicy>>, ApiError> {
+    domain::validation::validate_identifier(&object_type, "object_type")
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    validate_sla(req.response_secs, req.warning_secs)?;
+    let responder_company = responder_company(req.responder_company.as_deref())?;
+
+    let policy = state
+        .sla
+        .upsert_policy(
+            &object_type,
+            req.response_secs,
+            req.warning_secs,
+            responder_company,
+        )
+        .await?;
+
+    Ok(Json(ApiResponse { data: policy }))
+}
+
+pub async fn management_delete_sla_policy(
+    State(state): State<AppState>,
+    Path(object_type): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.sla.delete_policy(&object_type).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn management_set_tenant_quota(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+    Json(req): Json<SetStorageQuotaRequest>,
+) -> Result<Json<ApiResponse<StorageUsageResponse>>, ApiError> {
+    validate_quota(req.quota_bytes)?;
+
+    let usage = state
+        .storage_usage
+        .set_quota(StorageScope::Tenant, &tenant, req.quota_bytes)
+        .await?;
+
+    Ok(Json(ApiResponse {
+        data: StorageUsageResponse::new(&state, usage),
+    }))
+}
+
+// ============ Runtime Settings ============
+
+#[derive(Debug, Deserialize)]
+pub struct SetSettingRequest {
+    pub value: serde_json::Value,
+}
+
+pub async fn management_list_settings(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<SettingEntry>>>, ApiError> {
+    let settings = state.settings.list().await?;
+    Ok(Json(ApiResponse { data: settings }))
+}
+
+pub async fn management_set_setting(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Json(req): Json<SetSettingRequest>,
+) -> Result<Json<ApiResponse<SettingEntry>>, ApiError> {
+    let setting = state.settings.set(&key, req.value).await?;
+    tracing::info!(key = %key, value = %setting.value, "Runtime setting updated");
+    Ok(Json(ApiResponse { data: setting }))
+}
+
+pub async fn management_reset_setting(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+) -> Result<Json<ApiResponse<SettingEntry>>, ApiError> {
+    let setting = state.settings.reset(&key).await?;
+    tracing::info!(key = %key, "Runtime setting reset to default");
+    Ok(Json(ApiResponse { data: setting }))
+}
+
+// ============ Feature Flags ============
+
+#[derive(Debug, Deserialize)]
+pub struct ListFeatureFlagsQuery {
+    pub flag: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeatureFlagsResponse {
+    /// Global values (the `feature_flags` runtime setting)
+    pub global: BTreeMap<String, bool>,
+    pub overrides: Vec<FeatureFlagOverride>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetFeatureFlagRequest {
+    pub enabled: bool,
+}
+
+pub async fn management_list_feature_flags(
+    State(state): State<AppState>,
+    Query(query): Query<ListFeatureFlagsQuery>,
+) -> Result<Json<ApiResponse<FeatureFlagsResponse>>, ApiError> {
+    let overrides = state
+        .feature_flags
+        .list_overrides(query.flag.as_deref())
+        .await?;
+    let mut global = state.settings.current().feature_flags.clone();
+    if let Some(flag) = &query.flag {
+        global.retain(|name, _| name == flag);
+    }
+
+    Ok(Json(ApiResponse {
+        data: FeatureFlagsResponse { global, overrides },
+    }))
+}
+
+pub async fn management_set_tenant_feature_flag(
+    State(state): State<AppState>,
+    Path((flag, tenant)): Path<(String, String)>,
+    Json(req): Json<SetFeatureFlagRequest>,
+) -> Result<Json<ApiResponse<FeatureFlagOverride>>, ApiError> {
+    set_feature_flag(&state, &flag, FlagScope::Tenant, &tenant, req.enabled).await
+}
+
+pub async fn management_delete_tenant_feature_flag(
+    State(state): State<AppState>,
+    Path((flag, tenant)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    delete_feature_flag(&state, &flag, FlagScope::Tenant, &tenant).await
+}
+
+pub async fn management_set_dialog_feature_flag(
+    State(state): State<AppState>,
+    Path((flag, dialog_id)): Path<(String, Uuid)>,
+    Json(req): Json<SetFeatureFlagRequest>,
+) -> Result<Json<ApiResponse<FeatureFlagOverride>>, ApiError> {
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    set_feature_flag(
+        &state,
+        &flag,
+        FlagScope::Dialog,
+        &dialog_id.to_string(),
+        req.enabled,
+    )
+    .await
+}
+
+pub async fn management_delete_dialog_feature_flag(
+    State(state): State<AppState>,
+    Path((flag, dialog_id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    delete_feature_flag(&state, &flag, FlagScope::Dialog, &dialog_id.to_string()).await
+}
+
+/// Effective flags of a dialog
+pub async fn management_get_dialog_feature_flags(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<BTreeMap<String, bool>>>, ApiError> {
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let flags = state.feature_flags.flags_for_dialog(dialog_id).await?;
+    Ok(Json(ApiResponse { data: flags }))
+}
+
+async fn set_feature_flag(
+    state: &AppState,
+    flag: &str,
+    scope_type: FlagScope,
+    scope_id: &str,
+    enabled: bool,
+) -> Result<Json<ApiResponse<FeatureFlagOverride>>, ApiError> {
+    let stored = state
+        .feature_flags
+        .set_override(flag, scope_type, scope_id, enabled)
+        .await?;
+    tracing::info!(
+        flag,
+        scope_type = scope_type.as_str(),
+        scope_id,
+        enabled,
+        "Feature flag override set"
+    );
+    Ok(Json(ApiResponse { data: stored }))
+}
+
+async fn delete_feature_flag(
+    state: &AppState,
+    flag: &str,
+    scope_type: FlagScope,
+    scope_id: &str,
+) -> Result<StatusCode, ApiError> {
+    if !state
+        .feature_flags
+        .remove_override(flag, scope_type, scope_id)
+        .await?
+    {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            "Feature flag override not found",
+        ));
+    }
+    tracing::info!(
+        flag,
+        scope_type = scope_type.as_str(),
+        scope_id,
+        "Feature flag override removed"
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Effective configuration with secrets redacted
+pub async fn management_get_config(
+    State(state): State<AppState>,
+) -> Json<ApiResponse<serde_json::Value>> {
+    Json(ApiResponse {
+        data: state.config.redacted(),
+    })
+}
+
+fn validate_quota(quota_bytes: Option<i64>) -> Result<(), ApiError> {
+    if quota_bytes.is_some_and(|q| q < 0) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "quota_bytes must be >= 0",
+        ));
+    }
+    Ok(())
+}
+
+pub async fn management_update_access_scopes(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<UpdateAccessScopesRequest>,
+) -> Result<Json<ApiResponse<Vec<DialogAccessScope>>>, ApiError> {
+    // Check dialog exists
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Dialog not found".into()))?;
+
+    // Delete existing scopes and create new ones
+    let new_scopes: Vec<DialogAccessScope> = req
+        .access_scopes
+        .into_iter()
+        .map(|s| DialogAccessScope::new(dialog_id, s.scope_level0, s.scope_level1, s.scope_level2))
+        .collect();
+
+    let created = state
+        .scopes
         .replace_for_dialog(dialog_id, new_scopes)
         .await?;
+    state.events.publish(DomainEvent::AccessScopesChanged {
+        dialog_id,
+        scopes: created.clone(),
+    });
+
+    Ok(Json(ApiResponse { data: created }))
+}
+
+/// Set or clear the timezone and locale of a dialog
+pub async fn management_update_dialog_locale(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<UpdateDialogLocaleRequest>,
+) -> Result<Json<ApiResponse<Dialog>>, ApiError> {
+    validate_locale_input(&state, &req.timezone, &req.locale).await?;
+
+    let dialog = state
+        .dialogs
+        .update_locale(dialog_id, req.timezone.as_deref(), req.locale.as_deref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    Ok(Json(ApiResponse { data: dialog }))
+}
+
+/// Turn slow mode on or off for a dialog
+pub async fn management_update_slow_mode(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<UpdateSlowModeRequest>,
+) -> Result<Json<ApiResponse<Dialog>>, ApiError> {
+    let slow_mode_secs = req.slow_mode_secs.filter(|secs| *secs != 0);
+    if slow_mode_secs.is_some_and(|secs| !(1..=MAX_SLOW_MODE_SECS).contains(&secs)) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "slow_mode_secs must be between 0 and {}",
+                MAX_SLOW_MODE_SECS
+            ),
+        ));
+    }
+
+    let dialog = state
+        .dialogs
+        .update_slow_mode(dialog_id, slow_mode_secs)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    Ok(Json(ApiResponse { data: dialog }))
+}
+
+/// Set or clear the notification delay of a dialog, overriding the runtime
+/// settings
+pub async fn management_update_notification_delay(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<UpdateNotificationDelayRequest>,
+) -> Result<Json<ApiResponse<Dialog>>, ApiError> {
+    if req
+        .notification_delay_ms
+        .is_some_and(|ms| !(0..=MAX_NOTIFICATION_DELAY_MS as i32).contains(&ms))
+    {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "notification_delay_ms must be between 0 and {}",
+                MAX_NOTIFICATION_DELAY_MS
+            ),
+        ));
+    }
+
+    let dialog = state
+        .dialogs
+        .update_notification_delay(dialog_id, req.notification_delay_ms)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    Ok(Json(ApiResponse { data: dialog }))
+}
+
+/// Set or clear the response time SLA of a dialog, overriding the policy
+/// of its object type
+pub async fn management_update_sla(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<UpdateSlaRequest>,
+) -> Result<Json<ApiResponse<Dialog>>, ApiError> {
+    let response_secs = req.response_secs.filter(|secs| *secs != 0);
+    let (warning_secs, responder) = match response_secs {
+        Some(response_secs) => {
+            validate_sla(response_secs, req.warning_secs)?;
+            (
+                req.warning_secs,
+                responder_company(req.responder_company.as_deref())?,
+            )
+        }
+        None => (None, None),
+    };
+
+    let dialog = state
+        .dialogs
+        .update_sla(dialog_id, response_secs, warning_secs, responder)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+    // The responding company decides which message is unanswered
+    state.sla.clear_state(dialog_id).await?;
+
+    Ok(Json(ApiResponse { data: dialog }))
+}
+
+fn validate_sla(response_secs: i32, warning_secs: Option<i32>) -> Result<(), ApiError> {
+    if !(MIN_SLA_SECS..=MAX_SLA_SECS).contains(&response_secs) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "response_secs must be between {} and {}",
+                MIN_SLA_SECS, MAX_SLA_SECS
+            ),
+        ));
+    }
+    if warning_secs.is_some_and(|secs| !(1..response_secs).contains(&secs)) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "warning_secs must be positive and less than response_secs",
+        ));
+    }
+    Ok(())
+}
+
+/// Trimmed responding company of an SLA (blank = the creator's company)
+fn responder_company(company: Option<&str>) -> Result<Option<&str>, ApiError> {
+    let company = company.map(str::trim).filter(|c| !c.is_empty());
+    if company.is_some_and(|c| c.len() > 255) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "responder_company must be at most 255 characters",
+        ));
+    }
+    Ok(company)
+}
+
+/// SLA of a dialog with the status of its oldest unanswered message
+async fn dialog_sla(state: &AppState, dialog: &Dialog) -> Result<Option<DialogSla>, ApiError> {
+    let policy = match dialog.sla_response_secs {
+        Some(_) => None,
+        None => state.sla.find_policy(&dialog.object_type).await?,
+    };
+    let Some(target) = SlaTarget::resolve(dialog, policy.as_ref()) else {
+        return Ok(None);
+    };
+    let unanswered = state
+        .sla
+        .oldest_unanswered(
+            dialog.id,
+            SlaTarget::responder_company(dialog, policy.as_ref()),
+        )
+        .await?;
+    Ok(Some(DialogSla::new(
+        target,
+        unanswered.as_ref(),
+        chrono::Utc::now(),
+    )))
+}
 
-    Ok(Json(ApiResponse { data: created }))
+/// Select the HTML sanitization profile of a dialog's messages. Applies to
+/// messages sent or edited afterwards.
+pub async fn management_update_sanitize_profile(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<UpdateSanitizeProfileRequest>,
+) -> Result<Json<ApiResponse<Dialog>>, ApiError> {
+    let dialog = state
+        .dialogs
+        .update_sanitize_profile(dialog_id, req.sanitize_profile)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    Ok(Json(ApiResponse { data: dialog }))
+}
+
+/// Store a lifecycle event of the bound object as a system message
+pub async fn management_push_system_event(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<SystemEventRequest>,
+) -> Result<Json<ApiResponse<Message>>, ApiError> {
+    if !system_messages::is_valid_object_event_type(&req.r#type) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "type must be up to {} lowercase letters, digits, '_' or '.'",
+                system_messages::MAX_OBJECT_EVENT_TYPE_LENGTH
+            ),
+        ));
+    }
+    if !req.payload.is_object() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "payload must be a JSON object",
+        ));
+    }
+    if req.payload.to_string().len() > system_messages::MAX_OBJECT_EVENT_PAYLOAD_BYTES {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "payload exceeds {} bytes",
+                system_messages::MAX_OBJECT_EVENT_PAYLOAD_BYTES
+            ),
+        ));
+    }
+    domain::validation::validate_optional_length(
+        &req.text,
+        "text",
+        domain::validation::MAX_TITLE_LENGTH,
+    )
+    .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_message_metadata(&req.metadata)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validate_content_blocks(&req.content_blocks)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let system_msg = Message::system(
+        dialog_id,
+        system_messages::object_event_content(
+            &req.r#type,
+            &req.payload,
+            req.text.as_deref(),
+            req.occurred_at,
+            &dialog.locale_context(),
+        ),
+    )
+    .with_metadata(req.metadata)
+    .with_content_blocks(req.content_blocks);
+
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+
+    let stored = system_msg.stored_content();
+    let system_msg = sqlx::query_as::<_, Message>(
+        r#"INSERT INTO messages (id, dialog_id, sender_id, content, content_encoding, content_compressed, sent_at, reply_to_id, message_type, metadata, content_blocks, external_id, content_search_vector)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, strip(to_tsvector('simple', $13)))
+           RETURNING *"#,
+    )
+    .bind(system_msg.id)
+    .bind(system_msg.dialog_id)
+    .bind(&system_msg.sender_id)
+    .bind(&stored.content)
+    .bind(stored.encoding)
+    .bind(&stored.compressed)
+    .bind(system_msg.sent_at)
+    .bind(system_msg.reply_to_id)
+    .bind(system_msg.message_type.as_str())
+    .bind(&system_msg.metadata)
+    .bind(&system_msg.content_blocks)
+    .bind(&system_msg.external_id)
+    .bind(&stored.search_text)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Lifecycle events are news for every participant (observers keep no counter)
+    sqlx::query(
+        r#"UPDATE dialog_participants
+           SET unread_count = unread_count + 1
+           WHERE dialog_id = $1 AND joined_as <> 'observer'"#,
+    )
+    .bind(dialog_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    ws::broadcast_message(
+        &state.connections,
+        &state.participants,
+        dialog_id,
+        &system_msg,
+        None,
+    )
+    .await;
+    state
+        .webhooks
+        .send(WebhookEvent::message_new(&dialog, &system_msg))
+        .await;
+
+    Ok(Json(ApiResponse { data: system_msg }))
+}
+
+/// Backfill historical messages (migrations from another chat)
+///
+/// Messages keep their original timestamps and are stored without side
+/// effects: no unread counters, notifications, webhooks or broadcasts.
+pub async fn management_import_messages(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<ImportMessagesRequest>,
+) -> Result<Json<ApiResponse<ImportMessagesResponse>>, ApiError> {
+    if req.messages.is_empty() || req.messages.len() > MAX_IMPORT_MESSAGES {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("messages must contain 1 to {} items", MAX_IMPORT_MESSAGES),
+        ));
+    }
+
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+    let profile = super::messages::sanitize_profile(&state, &dialog).await?;
+
+    let max_message_length = state.settings.current().max_message_length;
+    let now = Utc::now();
+    let invalid = |index: usize, message: String| {
+        ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("messages[{}]: {}", index, message),
+        )
+    };
+
+    let mut attachments_size: i64 = 0;
+    for (index, input) in req.messages.iter().enumerate() {
+        let sender_id = req
+            .sender_map
+            .get(&input.sender_id)
+            .unwrap_or(&input.sender_id);
+        domain::validation::validate_identifier(sender_id, "sender_id")
+            .and_then(|_| domain::validation::validate_message_content(&input.content))
+            .and_then(|_| {
+                domain::validation::validate_optional_length(
+                    &input.sender_display_name,
+                    "sender_display_name",
+                    domain::validation::MAX_DISPLAY_NAME_LENGTH,
+                )
+            })
+            .and_then(|_| domain::validation::validate_company(&input.sender_company))
+            .and_then(|_| domain::validation::validate_message_metadata(&input.metadata))
+            .and_then(|_| {
+                domain::validation::validate_optional_identifier(&input.external_id, "external_id")
+            })
+            .map_err(|e| invalid(index, e.message))?;
+        if input.content.len() > max_message_length {
+            return Err(invalid(
+                index,
+                format!(
+                    "content exceeds maximum length of {} characters",
+                    max_message_length
+                ),
+            ));
+        }
+        if input.sent_at > now {
+            return Err(invalid(index, "sent_at must not be in the future".into()));
+        }
+        if input.edited_at.is_some_and(|at| at < input.sent_at) {
+            return Err(invalid(
+                index,
+                "edited_at must not be before sent_at".into(),
+            ));
+        }
+
+        verify_attachments(&state, dialog_id, &input.attachments).await?;
+        attachments_size += input.attachments.iter().map(|a| a.size).sum::<i64>();
+    }
+
+    let external_ids: Vec<&str> = req
+        .messages
+        .iter()
+        .filter_map(|m| m.external_id.as_deref())
+        .collect();
+    if !external_ids.is_empty() {
+        let mut unique = external_ids.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        if unique.len() < external_ids.len() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                "external_id must be unique within the import",
+            ));
+        }
+        let taken: Option<String> = sqlx::query_scalar(
+            "SELECT external_id FROM messages WHERE external_id = ANY($1) LIMIT 1",
+        )
+        .bind(&unique)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some(taken) = taken {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("external_id {:?} is already used by another message", taken),
+            ));
+        }
+    }
+
+    // Insert oldest first, so sequence numbers follow the original order
+    let mut order: Vec<usize> = (0..req.messages.len()).collect();
+    order.sort_by_key(|&i| req.messages[i].sent_at);
+
+    let mut message_ids = vec![Uuid::nil(); req.messages.len()];
+    let mut pdf_attachments = Vec::new();
+    let mut text_attachments = Vec::new();
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+    super::upload::reserve_storage(&state, &mut tx, dialog_id, attachments_size).await?;
+
+    for index in order {
+        let input = &req.messages[index];
+        let sender_id = req
+            .sender_map
+            .get(&input.sender_id)
+            .unwrap_or(&input.sender_id);
+        let content = domain::sanitize_html_with(&input.content, profile);
+        let message = Message::new(dialog_id, sender_id, content)
+            .with_sent_at(input.sent_at)
+            .with_external_id(input.external_id.clone())
+            .with_metadata(input.metadata.clone());
+
+        let stored = message.stored_content();
+        sqlx::query(
+            r#"INSERT INTO messages (id, dialog_id, sender_id, content, content_encoding, content_compressed, sent_at, last_edited_at, message_type, metadata, sender_display_name, sender_company, external_id, content_search_vector)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, strip(to_tsvector('simple', $14)))"#,
+        )
+        .bind(message.id)
+        .bind(message.dialog_id)
+        .bind(&message.sender_id)
+        .bind(&stored.content)
+        .bind(stored.encoding)
+        .bind(&stored.compressed)
+        .bind(message.sent_at)
+        .bind(input.edited_at)
+        .bind(message.message_type.as_str())
+        .bind(&message.metadata)
+        .bind(&input.sender_display_name)
+        .bind(&input.sender_company)
+        .bind(&message.external_id)
+        .bind(&stored.search_text)
+        .execute(&mut *tx)
+        .await?;
+
+        let attachments = insert_attachments(&mut tx, message.id, &input.attachments).await?;
+        pdf_attachments.extend(attachments.iter().filter(|a| a.is_pdf()).map(|a| a.id));
+        text_attachments.extend(
+            attachments
+                .iter()
+                .filter(|a| text_extract::is_extractable(&a.content_type))
+                .map(|a| a.id),
+        );
+        message_ids[index] = message.id;
+    }
+
+    tx.commit().await?;
+
+    if preview::is_enabled() {
+        for attachment_id in pdf_attachments {
+            if let Err(e) = state
+                .jobs
+                .enqueue_thumbnail(ThumbnailJob::new(attachment_id))
+                .await
+            {
+                tracing::warn!(
+                    attachment_id = %attachment_id,
+                    error = %e,
+                    "Failed to enqueue thumbnail job"
+                );
+            }
+        }
+    }
+    if text_extract::is_enabled() {
+        for attachment_id in text_attachments {
+            if let Err(e) = state
+                .jobs
+                .enqueue_text_extract(TextExtractJob::new(attachment_id))
+                .await
+            {
+                tracing::warn!(
+                    attachment_id = %attachment_id,
+                    error = %e,
+                    "Failed to enqueue text extraction job"
+                );
+            }
+        }
+    }
+
+    tracing::info!(
+        dialog_id = %dialog_id,
+        count = message_ids.len(),
+        "Imported messages"
+    );
+
+    Ok(Json(ApiResponse {
+        data: ImportMessagesResponse {
+            imported: message_ids.len(),
+            message_ids,
+        },
+    }))
+}
+
+fn qa_message(
+    message: Message,
+    attachments: &mut HashMap<Uuid, Vec<TranscriptAttachment>>,
+) -> QaMessage {
+    QaMessage {
+        id: message.id,
+        attachments: attachments.remove(&message.id).unwrap_or_default(),
+        sender_id: message.sender_id,
+        sender_name: message.sender_display_name,
+        sender_company: message.sender_company,
+        content: message.content,
+        sent_at: message.sent_at,
+        edited: message.last_edited_at.is_some(),
+    }
+}
+
+/// Export questions (messages with direct replies) and their answers, e.g.
+/// to publish tender clarifications
+pub async fn management_export_qa_pairs(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Query(query): Query<QaExportQuery>,
+) -> Result<Json<ApiResponse<QaExportResponse>>, ApiError> {
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let limit = query.limit.clamp(1, MAX_QA_PAIRS);
+    let questions = state
+        .messages
+        .list_answered(
+            dialog_id,
+            query.company.as_deref(),
+            query.company_uid.as_deref(),
+            query.from,
+            query.to,
+            query.after,
+            limit + 1,
+        )
+        .await?;
+    let has_more = questions.len() as i64 > limit;
+    let questions: Vec<Message> = questions.into_iter().take(limit as usize).collect();
+
+    let question_ids: Vec<Uuid> = questions.iter().map(|m| m.id).collect();
+    let replies = state
+        .messages
+        .list_replies(dialog_id, &question_ids)
+        .await?;
+
+    let message_ids: Vec<Uuid> = question_ids
+        .iter()
+        .copied()
+        .chain(replies.iter().map(|m| m.id))
+        .collect();
+    let mut attachments: HashMap<Uuid, Vec<TranscriptAttachment>> = HashMap::new();
+    for a in state.attachments.list_by_messages(&message_ids).await? {
+        attachments
+            .entry(a.message_id)
+            .or_default()
+            .push((&a).into());
+    }
+
+    let mut answers: HashMap<Uuid, Vec<QaMessage>> = HashMap::new();
+    for reply in replies {
+        if let Some(question_id) = reply.reply_to_id {
+            let answer = qa_message(reply, &mut attachments);
+            answers.entry(question_id).or_default().push(answer);
+        }
+    }
+
+    let next_cursor = if has_more {
+        question_ids.last().copied()
+    } else {
+        None
+    };
+    let pairs = questions
+        .into_iter()
+        .map(|question| QaPair {
+            answers: answers.remove(&question.id).unwrap_or_default(),
+            question: qa_message(question, &mut attachments),
+        })
+        .collect();
+
+    Ok(Json(ApiResponse {
+        data: QaExportResponse {
+            dialog_id,
+            object_type: dialog.object_type,
+            object_id: dialog.object_id,
+            pairs,
+            next_cursor,
+        },
+    }))
+}
+
+async fn validate_locale_input(
+    state: &AppState,
+    timezone: &Option<String>,
+    locale: &Option<String>,
+) -> Result<(), ApiError> {
+    domain::validation::validate_timezone(timezone)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validation::validate_locale(locale)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    if let Some(tz) = timezone {
+        if !state.dialogs.is_known_timezone(tz).await? {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("Unknown timezone '{}'", tz),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Look up a message by its external ID
+pub async fn management_get_message_by_external_id(
+    State(state): State<AppState>,
+    Path(external_id): Path<String>,
+) -> Result<Json<ApiResponse<Message>>, ApiError> {
+    let message = state
+        .messages
+        .find_by_external_id(&external_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::MessageNotFound, "Message not found"))?;
+
+    Ok(Json(ApiResponse { data: message }))
 }
 
 pub async fn management_get_dialog(
@@ -383,13 +2392,285 @@ pub async fn management_get_dialog(
         .ok_or_else(|| ApiError::NotFound("Dialog not found".into()))?;
 
     let participants = state.participants.list_by_dialog(dialog_id).await?;
+    let invites = state.invites.list_by_dialog(dialog_id).await?;
     let access_scopes = state.scopes.find_by_dialog(dialog_id).await?;
+    let bans = state.bans.list_active(dialog_id).await?;
+    let sla = dialog_sla(&state, &dialog).await?;
 
     Ok(Json(ApiResponse {
         data: ManagementDialogResponse {
             dialog,
             participants,
+            invites,
             access_scopes,
+            bans,
+            sla,
+        },
+    }))
+}
+
+/// Issue an expiring signed link to a read-only transcript of the dialog
+pub async fn management_create_transcript_link(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<CreateTranscriptLinkRequest>,
+) -> Result<Json<ApiResponse<TranscriptLinkResponse>>, ApiError> {
+    let recipient = req.recipient.as_deref().map(str::trim).unwrap_or_default();
+    domain::validation::validate_length(recipient, "recipient", MAX_TRANSCRIPT_RECIPIENT_LENGTH)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+
+    let max_expiry = state.transcripts.max_expiry().as_secs();
+    let expires_in = req.expires_in_secs.unwrap_or(max_expiry);
+    if expires_in == 0 || expires_in > max_expiry {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("expires_in_secs must be between 1 and {}", max_expiry),
+        ));
+    }
+
+    if state.dialogs.find_by_id(dialog_id).await?.is_none() {
+        return Err(ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"));
+    }
+
+    // Links carry whole-second expiries
+    let expires_at =
+        chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp() + expires_in as i64, 0)
+            .ok_or_else(|| ApiError::Internal("Invalid link expiry".into()))?;
+    let url = state
+        .transcripts
+        .signed_url(dialog_id, recipient, expires_at);
+
+    Ok(Json(ApiResponse {
+        data: TranscriptLinkResponse { url, expires_at },
+    }))
+}
+
+/// Issue a short-lived token to view the dialog as one of its participants
+/// (read-only). Issuing is recorded in the audit log.
+pub async fn management_create_impersonation(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<CreateImpersonationRequest>,
+) -> Result<Json<ApiResponse<ImpersonationResponse>>, ApiError> {
+    let actor = req.actor.trim();
+    if actor.is_empty() {
+        return Err(ApiError::new(ErrorCode::InvalidInput, "actor is required"));
+    }
+    domain::validation::validate_length(actor, "actor", MAX_AUDIT_ACTOR_LENGTH)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+
+    let max_ttl = state.impersonation.max_ttl().as_secs();
+    let expires_in = req.expires_in_secs.unwrap_or(max_ttl);
+    if expires_in == 0 || expires_in > max_ttl {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("expires_in_secs must be between 1 and {}", max_ttl),
+        ));
+    }
+
+    if state.dialogs.find_by_id(dialog_id).await?.is_none() {
+        return Err(ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"));
+    }
+    if !state.participants.exists(dialog_id, &req.user_id).await? {
+        return Err(ApiError::new(
+            ErrorCode::ParticipantNotFound,
+            "User is not a participant of this dialog",
+        ));
+    }
+
+    let claims = ImpersonationClaims {
+        sid: Uuid::now_v7(),
+        dialog_id,
+        user_id: req.user_id,
+        actor: actor.to_string(),
+        exp: chrono::Utc::now().timestamp() + expires_in as i64,
+    };
+    let expires_at = claims.expires_at();
+
+    // Record before handing out the token: no unaudited access
+    let entry = AuditEntry::new(
+        actor,
+        AUDIT_IMPERSONATION_ISSUED,
+        serde_json::json!({
+            "session_id": claims.sid,
+            "reason": req.reason,
+            "expires_at": expires_at,
+        }),
+    )
+    .for_user(dialog_id, &claims.user_id);
+    state.audit_log.record(&entry).await?;
+
+    tracing::info!(
+        dialog_id = %dialog_id,
+        user_id = %claims.user_id,
+        actor = %claims.actor,
+        session_id = %claims.sid,
+        "Impersonation token issued"
+    );
+
+    Ok(Json(ApiResponse {
+        data: ImpersonationResponse {
+            token: state.impersonation.issue(&claims),
+            session_id: claims.sid,
+            expires_at,
+        },
+    }))
+}
+
+/// Audit log entries, newest first
+pub async fn management_list_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<ApiResponse<Vec<AuditEntry>>>, ApiError> {
+    let limit = query.limit.clamp(1, MAX_AUDIT_ENTRIES);
+    let entries = state
+        .audit_log
+        .list(query.dialog_id, query.action.as_deref(), limit)
+        .await?;
+
+    Ok(Json(ApiResponse { data: entries }))
+}
+
+/// Connected WebSocket users across all instances
+pub async fn management_list_connections(
+    State(state): State<AppState>,
+    Query(query): Query<ListConnectionsQuery>,
+) -> Result<Json<ApiResponse<ConnectionsResponse>>, ApiError> {
+    let registry = &state.ws_registry;
+    let snapshots = match registry.snapshots().await {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to read connection snapshots, listing this instance only: {}",
+                e
+            );
+            vec![registry.local_snapshot()]
+        }
+    };
+
+    let mut instances = Vec::with_capacity(snapshots.len());
+    let mut users = Vec::new();
+    for snapshot in snapshots {
+        instances.push(InstanceConnections {
+            instance_id: snapshot.instance_id,
+            connections: snapshot.connections,
+            updated_at: snapshot.updated_at,
+            current: snapshot.instance_id == registry.instance_id(),
+        });
+        users.extend(
+            snapshot
+                .users
+                .into_iter()
+                .filter(|u| query.user_id.as_ref().map_or(true, |id| *id == u.user_id))
+                .map(|u| UserConnection {
+                    user_id: u.user_id,
+                    instance_id: snapshot.instance_id,
+                    connected_at: u.connected_at,
+                }),
+        );
+    }
+    users.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+
+    Ok(Json(ApiResponse {
+        data: ConnectionsResponse {
+            total_connections: instances.iter().map(|i| i.connections).sum(),
+            instances,
+            users,
         },
     }))
 }
+
+/// Close the WebSocket connection of a user on whichever instance holds it
+pub async fn management_disconnect_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .ws_registry
+        .disconnect(&user_id)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to relay disconnect: {}", e)))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Notification queue backlog: queue wait and dropped jobs
+pub async fn management_get_notification_backlog(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<NotificationBacklogResponse>>, ApiError> {
+    let skipped_before = state
+        .jobs
+        .notifications_skipped_before()
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(Json(ApiResponse {
+        data: notification_backlog(&state, skipped_before),
+    }))
+}
+
+/// Drop queued notification jobs after a worker outage instead of sending
+/// them late
+pub async fn management_skip_notifications(
+    State(state): State<AppState>,
+    Json(req): Json<SkipNotificationsRequest>,
+) -> Result<Json<ApiResponse<NotificationBacklogResponse>>, ApiError> {
+    if req.older_than_secs < 0 {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "older_than_secs must not be negative",
+        ));
+    }
+
+    let cutoff = Utc::now() - chrono::Duration::seconds(req.older_than_secs);
+    let skipped = state
+        .jobs
+        .skip_notifications_before(cutoff)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(Json(ApiResponse {
+        data: notification_backlog(&state, skipped.then_some(cutoff)),
+    }))
+}
+
+fn notification_backlog(
+    state: &AppState,
+    skipped_before: Option<DateTime<Utc>>,
+) -> NotificationBacklogResponse {
+    let backlog = state.jobs.notification_backlog().snapshot();
+    NotificationBacklogResponse {
+        enabled: state.jobs.is_enabled(),
+        max_age_secs: state.config.jobs.notification_max_age_secs,
+        age_secs: backlog.age_secs,
+        stale_total: backlog.stale_total,
+        skipped_total: backlog.skipped_total,
+        skipped_before,
+    }
+}
+
+/// Queue jobs that failed permanently, newest first
+pub async fn management_list_job_dead_letters(
+    State(state): State<AppState>,
+    Query(query): Query<DeadLettersQuery>,
+) -> Result<Json<ApiResponse<Vec<JobDeadLetter>>>, ApiError> {
+    let limit = query.limit.clamp(1, MAX_DEAD_LETTERS);
+    let letters = state
+        .job_dead_letters
+        .list(query.job_type.as_deref(), limit)
+        .await?;
+
+    Ok(Json(ApiResponse { data: letters }))
+}
+
+/// Webhook delivery health: circuit state, failures and dead letters
+pub async fn management_get_webhook_health(
+    State(state): State<AppState>,
+) -> Json<ApiResponse<WebhookHealthResponse>> {
+    let endpoints: Vec<EndpointHealth> = state.webhooks.health().into_iter().collect();
+
+    Json(ApiResponse {
+        data: WebhookHealthResponse {
+            enabled: !endpoints.is_empty(),
+            endpoints,
+        },
+    })
+}
@@ -1,13 +1,21 @@
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::Json;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::domain::{self, Message};
-use crate::jobs::NotificationJob;
-use crate::middleware::UserId;
+use crate::domain::{
+    self, ContentFormat, Dialog, DialogParticipant, Message, MessageDayCount, MessagePriority,
+    ReactionSummary, ReplyPreview, SanitizeProfile, SenderProfile, StarredMessage, StoredContent,
+    MAX_BATCH_MESSAGES, MAX_CALENDAR_DAYS, MAX_REACTION_LENGTH, REACTIONS_FLAG,
+};
+use crate::events::DomainEvent;
+use crate::middleware::{self, UserId};
+use crate::repositories::{MessageRepository, StorageUsageRepository};
+use crate::services::SlowModeError;
 use crate::webhooks::WebhookEvent;
 use crate::ws;
 
@@ -18,14 +26,74 @@ use super::{ApiError, ApiResponse, AppState, ErrorCode};
 #[derive(Debug, Deserialize)]
 pub struct SendMessageRequest {
     pub content: String,
+    /// Format of `content`; Markdown is rendered to HTML on the server
+    #[serde(default)]
+    pub content_format: ContentFormat,
     pub reply_to: Option<Uuid>,
     #[serde(default)]
     pub attachments: Vec<domain::AttachmentInput>,
+    /// Integration data for the host system (JSON object)
+    pub metadata: Option<serde_json::Value>,
+    /// Structured content rendered next to `content`
+    pub content_blocks: Option<Vec<domain::ContentBlock>>,
+    /// `urgent` skips the notification delay (creator and invited
+    /// participants only)
+    #[serde(default)]
+    pub priority: MessagePriority,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct EditMessageRequest {
     pub content: String,
+    #[serde(default)]
+    pub content_format: ContentFormat,
+    /// Version the edit is based on; the edit fails with 409 if the message
+    /// changed since (same as sending it in `If-Match`)
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+/// Message composed offline, sent in a batch
+#[derive(Debug, Deserialize)]
+pub struct BatchMessageInput {
+    /// Client's ID of the message, unique per sender and dialog; a message
+    /// already stored under it is not sent again
+    pub client_id: String,
+    /// When the message was composed on the client
+    pub client_sent_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub message: SendMessageRequest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendMessageBatchRequest {
+    /// Messages in the order they were composed
+    pub messages: Vec<BatchMessageInput>,
+}
+
+/// Expected version from an `If-Match` header (`"3"`, `W/"3"` or `3`)
+pub(super) fn if_match_version(headers: &HeaderMap) -> Result<Option<i32>, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .map(|v| v.trim().trim_start_matches("W/").trim_matches('"'))
+        .and_then(|v| v.parse().ok())
+        .map(Some)
+        .ok_or_else(|| ApiError::new(ErrorCode::InvalidInput, "If-Match must be a version number"))
+}
+
+fn version_conflict(current: Message) -> ApiError {
+    ApiError::new(
+        ErrorCode::VersionConflict,
+        format!(
+            "Message was changed since version was read (current version: {})",
+            current.version
+        ),
+    )
+    .with_details(serde_json::json!({ "current": current }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -35,6 +103,45 @@ pub struct PaginationQuery {
     pub before: Option<Uuid>,
     pub after: Option<Uuid>,
     pub around: Option<Uuid>,
+    /// Jump to a date: the page around the first message of this day
+    pub around_date: Option<chrono::NaiveDate>,
+    /// IANA timezone the day of `around_date` is in (default: the dialog's
+    /// timezone, else UTC)
+    pub tz: Option<String>,
+    /// Comma-separated extra data to embed (`sender`)
+    pub include: Option<String>,
+    /// Return Markdown sources instead of HTML where available
+    #[serde(default)]
+    pub content_format: ContentFormat,
+    /// Only messages of this priority (pages with `before`)
+    pub priority: Option<MessagePriority>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarQuery {
+    /// First day (inclusive)
+    pub from: chrono::NaiveDate,
+    /// Last day (inclusive)
+    pub to: chrono::NaiveDate,
+    /// IANA timezone of the days (default: the dialog's timezone, else UTC)
+    pub tz: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContentFormatQuery {
+    #[serde(default)]
+    pub content_format: ContentFormat,
+}
+
+impl PaginationQuery {
+    fn includes(&self, name: &str) -> bool {
+        includes(self.include.as_deref(), name)
+    }
+}
+
+/// Whether a comma-separated `include` parameter names `name`
+fn includes(include: Option<&str>, name: &str) -> bool {
+    include.is_some_and(|include| include.split(',').any(|v| v.trim() == name))
 }
 
 fn default_limit() -> i64 {
@@ -47,6 +154,40 @@ pub struct MessageWithAttachments {
     pub message: Message,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub attachments: Vec<domain::AttachmentResponse>,
+    /// Whether the current user starred the message
+    pub is_starred: bool,
+    /// Preview of the message this one replies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<ReplyPreview>,
+    /// Sender profile at send time (`include=sender`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender: Option<SenderProfile>,
+    /// Sender's current smallest avatar image (`include=sender`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchSentMessage {
+    pub client_id: String,
+    /// Stored by an earlier attempt of the batch and not sent again
+    pub duplicate: bool,
+    #[serde(flatten)]
+    pub message: MessageWithAttachments,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SendMessageBatchResponse {
+    /// Messages in request order
+    pub messages: Vec<BatchSentMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StarredMessagesQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// Message ID of the last item of the previous page
+    pub before: Option<Uuid>,
 }
 
 #[derive(Debug, Serialize)]
@@ -60,6 +201,36 @@ pub struct MessagesResponse {
     pub has_more_after: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MessageContextQuery {
+    /// Surrounding messages, split evenly before and after the message
+    #[serde(default = "default_context_limit")]
+    pub limit: i64,
+    /// Comma-separated extra data to embed (`sender`)
+    pub include: Option<String>,
+    #[serde(default)]
+    pub content_format: ContentFormat,
+}
+
+fn default_context_limit() -> i64 {
+    20
+}
+
+/// A message with its surrounding messages, e.g. to open a search result
+#[derive(Debug, Serialize)]
+pub struct MessageContextResponse {
+    /// The requested message, also contained in `messages`
+    pub message_id: Uuid,
+    /// Messages in chronological order
+    pub messages: Vec<MessageWithAttachments>,
+    pub has_more_before: bool,
+    pub has_more_after: bool,
+    /// `before` cursor of the previous page (None without older messages)
+    pub before_cursor: Option<Uuid>,
+    /// `after` cursor of the next page (None without newer messages)
+    pub after_cursor: Option<Uuid>,
+}
+
 // ============ Handlers ============
 
 pub async fn list_messages(
@@ -67,7 +238,7 @@ pub async fn list_messages(
     UserId(user_id): UserId,
     Path(dialog_id): Path<Uuid>,
     Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<ApiResponse<MessagesResponse>>, ApiError> {
+) -> Result<(HeaderMap, Json<ApiResponse<MessagesResponse>>), ApiError> {
     // Check user is participant (potential participants cannot read messages)
     if !state.participants.exists(dialog_id, &user_id).await? {
         return Err(ApiError::Forbidden(
@@ -75,8 +246,37 @@ pub async fn list_messages(
         ));
     }
 
+    // A date jump is a jump to the first message of that day. Past the last
+    // message there is nothing to center on, so the latest page is returned.
+    let around = match (pagination.around, pagination.around_date) {
+        (Some(around_id), _) => Some(around_id),
+        (None, Some(date)) => {
+            let timezone = calendar_timezone(&state, dialog_id, pagination.tz.clone()).await?;
+            state
+                .messages
+                .first_on_or_after_date(dialog_id, date, &timezone)
+                .await?
+        }
+        (None, None) => None,
+    };
+
+    // A priority filter pages backwards from the latest match only
+    if pagination.priority.is_some() && (around.is_some() || pagination.after.is_some()) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "priority can only be combined with before",
+        ));
+    }
+
     // Determine pagination mode: around, after, before, or latest
-    let (messages, has_more_before, has_more_after) = if let Some(around_id) = pagination.around {
+    let (messages, has_more_before, has_more_after) = if let Some(priority) = pagination.priority {
+        let msgs = state
+            .messages
+            .list_by_priority(dialog_id, priority, pagination.limit, pagination.before)
+            .await?;
+        let has_more = msgs.len() as i64 >= pagination.limit;
+        (msgs, has_more, false)
+    } else if let Some(around_id) = around {
         // Load messages centered around a specific message (jump to message)
         state
             .messages
@@ -103,8 +303,9 @@ pub async fn list_messages(
         (msgs, has_more, false)
     };
 
-    // Get participant to find first unread message (only for regular pagination, not "around")
-    let first_unread_message_id = if pagination.around.is_none() {
+    // Get participant to find first unread message (only for regular pagination,
+    // not "around" or filtered lists)
+    let first_unread_message_id = if around.is_none() && pagination.priority.is_none() {
         let participant = state.participants.find(dialog_id, &user_id).await?;
         if let Some(ref p) = participant {
             if let Some(last_read_id) = p.last_read_message_id {
@@ -141,9 +342,87 @@ pub async fn list_messages(
         None
     };
 
-    // Batch fetch attachments for all messages
+    let messages_with_attachments = with_attachments(
+        &state,
+        &user_id,
+        dialog_id,
+        messages,
+        pagination.includes("sender"),
+        pagination.content_format,
+    )
+    .await?;
+
+    let headers = super::attachment_url_headers(
+        messages_with_attachments
+            .iter()
+            .flat_map(|m| m.attachments.iter()),
+    );
+    Ok((
+        headers,
+        Json(ApiResponse {
+            data: MessagesResponse {
+                messages: messages_with_attachments,
+                first_unread_message_id,
+                has_more_before: Some(has_more_before),
+                has_more_after: Some(has_more_after),
+            },
+        }),
+    ))
+}
+
+pub(crate) fn pending_removal_error() -> ApiError {
+    ApiError::new(
+        ErrorCode::ParticipantRemovalPending,
+        "Participant is being removed from the dialog (read-only)",
+    )
+}
+
+/// HTML sanitization profile of a dialog's messages: the dialog's own
+/// profile, else the strictest of its tenants, else the global setting
+pub(crate) async fn sanitize_profile(
+    state: &AppState,
+    dialog: &Dialog,
+) -> Result<SanitizeProfile, ApiError> {
+    let tenants = match dialog.sanitize_profile {
+        Some(_) => Vec::new(),
+        None => {
+            state
+                .tenant_settings
+                .sanitize_profiles_for_dialog(dialog.id)
+                .await?
+        }
+    };
+    Ok(SanitizeProfile::resolve(
+        dialog.sanitize_profile,
+        &tenants,
+        state.settings.current().sanitize_profile,
+    ))
+}
+
+/// Messages of a dialog with their attachments, stars and reply previews,
+/// and with `include_sender` the sender profiles
+async fn with_attachments(
+    state: &AppState,
+    user_id: &str,
+    dialog_id: Uuid,
+    messages: Vec<Message>,
+    include_sender: bool,
+    content_format: ContentFormat,
+) -> Result<Vec<MessageWithAttachments>, ApiError> {
+    // Batch fetch attachments and stars for all messages
     let message_ids: Vec<Uuid> = messages.iter().map(|m| m.id).collect();
     let all_attachments = state.attachments.list_by_messages(&message_ids).await?;
+    let starred = state
+        .message_stars
+        .starred_among(user_id, &message_ids)
+        .await?;
+    let mut reply_to_ids: Vec<Uuid> = messages.iter().filter_map(|m| m.reply_to_id).collect();
+    reply_to_ids.sort_unstable();
+    reply_to_ids.dedup();
+    let reply_previews = state
+        .messages
+        .reply_previews(dialog_id, &reply_to_ids)
+        .await?;
 
     // Group attachments by message_id
     let mut attachments_map: HashMap<Uuid, Vec<domain::Attachment>> = HashMap::new();
@@ -162,8 +441,19 @@ pub async fn list_messages(
         .collect();
 
     // Generate all presigned URLs concurrently
-    let presigned_urls = if state.s3.is_configured() && !all_s3_keys.is_empty() {
-        state.s3.generate_download_urls_batch(&all_s3_keys).await
+    let presigned_urls = if state.storage.is_configured() && !all_s3_keys.is_empty() {
+        state
+            .storage
+            .generate_download_urls_batch(&all_s3_keys)
+            .await
+    } else {
+        HashMap::new()
+    };
+
+    // Current avatars of the senders (they are not part of the snapshot)
+    let avatars = if include_sender {
+        let participants = state.participants.list_by_dialog(dialog_id).await?;
+        super::avatars::resolve_avatars(state.storage.as_ref(), &participants).await
     } else {
         HashMap::new()
     };
@@ -176,7 +466,7 @@ pub async fn list_messages(
         let attachment_responses: Vec<_> = attachments
             .iter()
             .map(|att| {
-                let url = presigned_urls.get(&att.s3_key).cloned().unwrap_or_default();
+                let url = presigned_urls.get(&att.s3_key).cloned();
                 let thumbnail_url = att
                     .thumbnail_s3_key
                     .as_ref()
@@ -185,52 +475,82 @@ pub async fn list_messages(
             })
             .collect();
 
+        let is_starred = starred.contains(&message.id);
+        let reply_to = message
+            .reply_to_id
+            .and_then(|id| reply_previews.get(&id).cloned());
+        let (sender, sender_avatar_url) = if include_sender {
+            (
+                message.sender_profile(),
+                message
+                    .sender_id
+                    .as_ref()
+                    .and_then(|sid| avatars.get(sid))
+                    .map(|a| a.thumbnail().to_string()),
+            )
+        } else {
+            (None, None)
+        };
         messages_with_attachments.push(MessageWithAttachments {
-            message,
+            message: message.in_format(content_format),
             attachments: attachment_responses,
+            is_starred,
+            reply_to,
+            sender,
+            sender_avatar_url,
         });
     }
 
-    Ok(Json(ApiResponse {
-        data: MessagesResponse {
-            messages: messages_with_attachments,
-            first_unread_message_id,
-            has_more_before: Some(has_more_before),
-            has_more_after: Some(has_more_after),
-        },
-    }))
+    Ok(messages_with_attachments)
 }
 
-pub async fn send_message(
-    State(state): State<AppState>,
-    UserId(sender_id): UserId,
-    Path(dialog_id): Path<Uuid>,
-    Json(req): Json<SendMessageRequest>,
-) -> Result<Json<ApiResponse<MessageWithAttachments>>, ApiError> {
-    // Verify dialog exists
-    let dialog = state
-        .dialogs
-        .find_by_id(dialog_id)
-        .await?
-        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+/// Sanitized HTML content of a message and, for Markdown input, its source
+fn render_content(
+    content: &str,
+    format: ContentFormat,
+    profile: SanitizeProfile,
+) -> (String, Option<String>) {
+    match format {
+        ContentFormat::Html => (domain::sanitize_html_with(content, profile), None),
+        ContentFormat::Markdown => {
+            let html = domain::render_markdown(content);
+            (
+                domain::sanitize_html_with(&html, profile),
+                Some(content.to_string()),
+            )
+        }
+    }
+}
 
-    // Check user is participant (potential participants cannot send messages)
-    if !state.participants.exists(dialog_id, &sender_id).await? {
-        return Err(ApiError::Forbidden(
-            "Not a participant. Join the dialog first.".into(),
-        ));
+/// Reject writes from a participant whose removal is pending
+async fn ensure_not_pending_removal(
+    state: &AppState,
+    dialog_id: Uuid,
+    user_id: &str,
+) -> Result<(), ApiError> {
+    let participant = state.participants.find(dialog_id, user_id).await?;
+    if participant.is_some_and(|p| p.is_pending_removal()) {
+        return Err(pending_removal_error());
     }
+    Ok(())
+}
 
+/// Validate attachments of a new message and verify the files were uploaded
+pub(crate) async fn verify_attachments(
+    state: &AppState,
+    dialog_id: Uuid,
+    attachments: &[domain::AttachmentInput],
+) -> Result<(), ApiError> {
     // Validate attachment count
-    if req.attachments.len() > domain::attachment_limits::MAX_ATTACHMENTS_PER_MESSAGE {
+    if attachments.len() > domain::attachment_limits::MAX_ATTACHMENTS_PER_MESSAGE {
         return Err(ApiError::BadRequest(format!(
             "Maximum {} attachments per message",
             domain::attachment_limits::MAX_ATTACHMENTS_PER_MESSAGE
         )));
     }
 
-    // Validate and verify attachments exist in S3
-    for att_input in &req.attachments {
+    // Validate and verify attachments exist in storage
+    for att_input in attachments {
         // Validate S3 key (path traversal and dialog ownership)
         domain::validation::validate_s3_key(&att_input.s3_key, dialog_id)
             .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
@@ -248,10 +568,10 @@ pub async fn send_message(
             return Err(ApiError::BadRequest("Invalid file size".into()));
         }
 
-        // Verify file exists in S3 (only if S3 is configured)
-        if state.s3.is_configured() {
+        // Verify file exists in storage (only if storage is configured)
+        if state.storage.is_configured() {
             let exists = state
-                .s3
+                .storage
                 .object_exists(&att_input.s3_key)
                 .await
                 .map_err(|e| ApiError::Internal(e.to_string()))?;
@@ -264,94 +584,280 @@ pub async fn send_message(
         }
     }
 
+    Ok(())
+}
+
+/// Store the attachments of a new message
+pub(crate) async fn insert_attachments(
+    conn: &mut PgConnection,
+    message_id: Uuid,
+    inputs: &[domain::AttachmentInput],
+) -> Result<Vec<domain::Attachment>, sqlx::Error> {
+    let mut created_attachments = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let att = domain::Attachment::new(
+            message_id,
+            &input.filename,
+            &input.content_type,
+            input.size,
+            &input.s3_key,
+        );
+        let created = sqlx::query_as::<_, domain::Attachment>(
+            r#"INSERT INTO attachments (id, message_id, filename, content_type, size, s3_key, width, height, thumbnail_s3_key, created_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               RETURNING *"#,
+        )
+        .bind(att.id)
+        .bind(att.message_id)
+        .bind(&att.filename)
+        .bind(&att.content_type)
+        .bind(att.size)
+        .bind(&att.s3_key)
+        .bind(att.width)
+        .bind(att.height)
+        .bind(&att.thumbnail_s3_key)
+        .bind(att.created_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        created_attachments.push(created);
+    }
+    Ok(created_attachments)
+}
+
+/// A new message, validated and rendered, ready to be stored
+struct PreparedMessage {
+    message: Message,
+    /// Format `content` is returned in
+    format: ContentFormat,
+    reply_to: Option<ReplyPreview>,
+    attachments: Vec<domain::AttachmentInput>,
+    broadcast: Option<domain::BroadcastMention>,
+}
+
+impl PreparedMessage {
+    fn attachments_size(&self) -> i64 {
+        self.attachments.iter().map(|a| a.size).sum()
+    }
+}
+
+/// Participant sending to a dialog; observers and participants whose
+/// removal is pending cannot send
+async fn find_sender(
+    state: &AppState,
+    dialog_id: Uuid,
+    sender_id: &str,
+) -> Result<DialogParticipant, ApiError> {
+    // Potential participants cannot send messages
+    let sender = state
+        .participants
+        .find(dialog_id, sender_id)
+        .await?
+        .ok_or_else(|| ApiError::Forbidden("Not a participant. Join the dialog first.".into()))?;
+    if sender.joined_as.is_observer() {
+        return Err(ApiError::new(
+            ErrorCode::ObserverReadOnly,
+            "Observers cannot send messages",
+        ));
+    }
+    if sender.is_pending_removal() {
+        return Err(pending_removal_error());
+    }
+    Ok(sender)
+}
+
+/// Validate a new message and render its content
+async fn prepare_message(
+    state: &AppState,
+    dialog: &Dialog,
+    sender: &DialogParticipant,
+    profile: SanitizeProfile,
+    req: SendMessageRequest,
+) -> Result<PreparedMessage, ApiError> {
+    // The original must be a message of this dialog
+    let reply_to = match req.reply_to {
+        Some(reply_to_id) => {
+            let original = state
+                .messages
+                .find_by_id_and_dialog(reply_to_id, dialog.id)
+                .await?
+                .ok_or_else(|| {
+                    ApiError::new(
+                        ErrorCode::InvalidInput,
+                        "reply_to must be a message of this dialog",
+                    )
+                })?;
+            Some(ReplyPreview::of(&original))
+        }
+        None => None,
+    };
+
+    verify_attachments(state, dialog.id, &req.attachments).await?;
+
     // Validate content length (before sanitization)
-    if req.content.len() > domain::validation::MAX_MESSAGE_LENGTH {
+    let max_message_length = state.settings.current().max_message_length;
+    if req.content.len() > max_message_length {
         return Err(ApiError::new(
             ErrorCode::InvalidInput,
             format!(
                 "Message content exceeds maximum length of {} characters",
-                domain::validation::MAX_MESSAGE_LENGTH
+                max_message_length
             ),
         ));
     }
 
-    // Sanitize message content (removes XSS, preserves formatting)
-    let sanitized_content = domain::sanitize_html(&req.content);
+    domain::validation::validate_message_metadata(&req.metadata)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    domain::validate_content_blocks(&req.content_blocks)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
 
-    // All DB writes in a transaction
-    let mut tx = state.db.begin().await?;
+    // Sanitize message content (removes XSS, keeps the profile's formatting)
+    let (sanitized_content, markdown_source) =
+        render_content(&req.content, req.content_format, profile);
+
+    // @channel / @here are limited to the creator and invited participants
+    let broadcast = domain::extract_broadcast_mention(&sanitized_content);
+    if broadcast.is_some() && !domain::BroadcastMention::allowed_for(&sender.joined_as) {
+        return Err(ApiError::new(
+            ErrorCode::BroadcastMentionForbidden,
+            "Only the creator and invited participants can use @channel and @here",
+        ));
+    }
+    if !req.priority.allowed_for(&sender.joined_as) {
+        return Err(ApiError::new(
+            ErrorCode::UrgentPriorityForbidden,
+            "Only the creator and invited participants can send urgent messages",
+        ));
+    }
 
-    // Create message
-    let mut message = Message::new(dialog_id, &sender_id, sanitized_content);
+    let mut message = Message::new(dialog.id, &sender.user_id, sanitized_content)
+        .with_markdown_source(markdown_source)
+        .with_metadata(req.metadata)
+        .with_content_blocks(req.content_blocks)
+        .with_priority(req.priority);
     if let Some(reply_to) = req.reply_to {
         message = message.with_reply(reply_to);
     }
+    Ok(PreparedMessage {
+        message,
+        format: req.content_format,
+        reply_to,
+        attachments: req.attachments,
+        broadcast,
+    })
+}
+
+/// Enforce the dialog's slow mode (fails open if Redis is unavailable)
+async fn check_slow_mode(
+    state: &AppState,
+    dialog: &Dialog,
+    sender_id: &str,
+) -> Result<(), ApiError> {
+    let Some(slow_mode_secs) = dialog.slow_mode_secs else {
+        return Ok(());
+    };
+    match state
+        .slow_mode
+        .check_and_record(dialog.id, sender_id, slow_mode_secs)
+        .await
+    {
+        Ok(()) => Ok(()),
+        Err(SlowModeError::Redis(e)) => {
+            tracing::warn!(dialog_id = %dialog.id, error = %e, "Slow mode check failed");
+            Ok(())
+        }
+        Err(e @ SlowModeError::TooSoon { retry_after_secs }) => Err(ApiError::new(
+            ErrorCode::SlowModeActive,
+            e.to_string(),
+        )
+        .with_details(serde_json::json!({
+            "slow_mode_secs": slow_mode_secs,
+            "retry_after_secs": retry_after_secs,
+        }))),
+    }
+}
+
+/// Store a prepared message with its attachments and mentions
+async fn store_message(
+    conn: &mut PgConnection,
+    prepared: &PreparedMessage,
+) -> Result<(Message, Vec<domain::Attachment>), sqlx::Error> {
+    let message = &prepared.message;
+    let stored = message.stored_content();
     let message = sqlx::query_as::<_, Message>(
-        r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type)
-           VALUES ($1, $2, $3, $4, $5, $6, $7)
+        r#"INSERT INTO messages (id, dialog_id, sender_id, content, content_encoding, content_compressed, sent_at, reply_to_id, message_type, metadata, content_blocks, content_markdown, external_id, priority, content_search_vector)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, strip(to_tsvector('simple', $15)))
            RETURNING *"#,
     )
     .bind(message.id)
     .bind(message.dialog_id)
-    .bind(message.sender_id)
-    .bind(&message.content)
+    .bind(&message.sender_id)
+    .bind(&stored.content)
+    .bind(stored.encoding)
+    .bind(&stored.compressed)
     .bind(message.sent_at)
     .bind(message.reply_to_id)
     .bind(message.message_type.as_str())
-    .fetch_one(&mut *tx)
+    .bind(&message.metadata)
+    .bind(&message.content_blocks)
+    .bind(&message.content_markdown)
+    .bind(&message.external_id)
+    .bind(message.priority.as_str())
+    .bind(&stored.search_text)
+    .fetch_one(&mut *conn)
     .await?;
 
-    // Create attachments
-    let mut created_attachments = Vec::new();
-    for input in &req.attachments {
-        let att = domain::Attachment::new(
-            message.id,
-            &input.filename,
-            &input.content_type,
-            input.size,
-            &input.s3_key,
-        );
-        let created = sqlx::query_as::<_, domain::Attachment>(
-            r#"INSERT INTO attachments (id, message_id, filename, content_type, size, s3_key, width, height, thumbnail_s3_key, created_at)
-               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-               RETURNING *"#,
+    let attachments = insert_attachments(conn, message.id, &prepared.attachments).await?;
+
+    // Record mentioned users
+    let mentions = domain::extract_mentions(&message.content);
+    if !mentions.is_empty() {
+        sqlx::query(
+            r#"INSERT INTO message_mentions (message_id, dialog_id, user_id)
+               SELECT $1, $2, UNNEST($3::text[])
+               ON CONFLICT DO NOTHING"#,
         )
-        .bind(att.id)
-        .bind(att.message_id)
-        .bind(&att.filename)
-        .bind(&att.content_type)
-        .bind(att.size)
-        .bind(&att.s3_key)
-        .bind(att.width)
-        .bind(att.height)
-        .bind(&att.thumbnail_s3_key)
-        .bind(att.created_at)
-        .fetch_one(&mut *tx)
+        .bind(message.id)
+        .bind(message.dialog_id)
+        .bind(&mentions)
+        .execute(&mut *conn)
         .await?;
-        created_attachments.push(created);
     }
 
-    // Increment unread count for all participants except the sender
+    Ok((message, attachments))
+}
+
+/// Update participants after `count` messages were sent: unread counters,
+/// archive state and the sender's read position. Returns the participants
+/// the dialog was unarchived for.
+async fn record_sent_messages(
+    conn: &mut PgConnection,
+    dialog_id: Uuid,
+    sender_id: &str,
+    count: i32,
+    last_message_id: Uuid,
+) -> Result<Vec<String>, sqlx::Error> {
+    // Increment unread count for all participants except the sender and observers
     sqlx::query(
         r#"UPDATE dialog_participants
-           SET unread_count = unread_count + 1
-           WHERE dialog_id = $1 AND user_id != $2"#,
+           SET unread_count = unread_count + $3
+           WHERE dialog_id = $1 AND user_id != $2 AND joined_as <> 'observer'"#,
     )
     .bind(dialog_id)
-    .bind(&sender_id)
-    .execute(&mut *tx)
+    .bind(sender_id)
+    .bind(count)
+    .execute(&mut *conn)
     .await?;
 
     // Auto-unarchive: when a new message is sent, unarchive dialog for all participants
-    let unarchive_result = sqlx::query(
+    let unarchived_ids: Vec<String> = sqlx::query_scalar(
         r#"UPDATE dialog_participants
            SET is_archived = false
-           WHERE dialog_id = $1 AND is_archived = true"#,
+           WHERE dialog_id = $1 AND is_archived = true
+           RETURNING user_id"#,
     )
     .bind(dialog_id)
-    .execute(&mut *tx)
+    .fetch_all(&mut *conn)
     .await?;
-    let unarchived = unarchive_result.rows_affected();
 
     // Mark sender's own message as read (so divider doesn't appear before own messages)
     sqlx::query(
@@ -360,29 +866,30 @@ pub async fn send_message(
            WHERE dialog_id = $1 AND user_id = $2"#,
     )
     .bind(dialog_id)
-    .bind(&sender_id)
-    .bind(message.id)
-    .execute(&mut *tx)
+    .bind(sender_id)
+    .bind(last_message_id)
+    .execute(&mut *conn)
     .await?;
 
-    tx.commit().await?;
+    Ok(unarchived_ids)
+}
 
-    // Generate presigned URLs for response (after commit, non-transactional)
-    let mut attachment_responses = Vec::new();
-    for att in &created_attachments {
-        let url = if state.s3.is_configured() {
-            state
-                .s3
-                .generate_download_url(&att.s3_key)
-                .await
-                .unwrap_or_else(|_| String::new())
+/// Attachments with download URLs for a response
+async fn attachment_responses(
+    state: &AppState,
+    attachments: &[domain::Attachment],
+) -> Vec<domain::AttachmentResponse> {
+    let mut responses = Vec::with_capacity(attachments.len());
+    for att in attachments {
+        let url = if state.storage.is_configured() {
+            state.storage.generate_download_url(&att.s3_key).await.ok()
         } else {
-            String::new()
+            None
         };
 
         let thumbnail_url = if let Some(ref thumb_key) = att.thumbnail_s3_key {
-            if state.s3.is_configured() {
-                state.s3.generate_download_url(thumb_key).await.ok()
+            if state.storage.is_configured() {
+                state.storage.generate_download_url(thumb_key).await.ok()
             } else {
                 None
             }
@@ -390,72 +897,309 @@ pub async fn send_message(
             None
         };
 
-        attachment_responses.push(domain::AttachmentResponse::from_attachment(
+        responses.push(domain::AttachmentResponse::from_attachment(
             att,
             url,
             thumbnail_url,
         ));
     }
+    responses
+}
 
-    // Broadcast and webhook after transaction is committed
-    // Fetch participants once for both unarchive broadcast and notifications
-    let participants = if unarchived > 0 || state.jobs.is_enabled() {
-        state.participants.list_by_dialog(dialog_id).await?
-    } else {
-        vec![]
-    };
+pub async fn send_message(
+    State(state): State<AppState>,
+    UserId(sender_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<SendMessageRequest>,
+) -> Result<(HeaderMap, Json<ApiResponse<MessageWithAttachments>>), ApiError> {
+    // Verify dialog exists
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+    let sender = find_sender(&state, dialog_id, &sender_id).await?;
+
+    let profile = sanitize_profile(&state, &dialog).await?;
+    let prepared = prepare_message(&state, &dialog, &sender, profile, req).await?;
+
+    // Slow mode, checked last so rejected messages don't start the interval
+    check_slow_mode(&state, &dialog, &sender_id).await?;
 
-    // Run broadcast, webhook, and notifications in parallel
-    let broadcast_future = async {
-        if unarchived > 0 {
-            tracing::debug!(dialog_id = %dialog_id, count = unarchived, "Auto-unarchived dialog for participants");
-            let participant_ids: Vec<String> =
-                participants.iter().map(|p| p.user_id.clone()).collect();
-            ws::broadcast_dialog_unarchived(&state.connections, dialog_id, &participant_ids).await;
+    // All DB writes in a transaction, with the new attachments counted
+    // towards the storage quotas
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+    super::upload::reserve_storage(&state, &mut tx, dialog_id, prepared.attachments_size()).await?;
+    let (message, created_attachments) = store_message(&mut tx, &prepared).await?;
+    let unarchived_ids =
+        record_sent_messages(&mut tx, dialog_id, &sender_id, 1, message.id).await?;
+    tx.commit().await?;
+
+    // Generate presigned URLs for response (after commit, non-transactional)
+    let attachment_responses = attachment_responses(&state, &created_attachments).await;
+
+    // Broadcast, webhooks and jobs after the transaction is committed
+    let PreparedMessage {
+        format,
+        reply_to,
+        broadcast,
+        ..
+    } = prepared;
+    state.events.publish(DomainEvent::MessageSent {
+        dialog,
+        message: message.clone(),
+        reply_to: reply_to.clone(),
+        attachments: created_attachments,
+        unarchived_for: unarchived_ids,
+        broadcast,
+    });
+
+    let headers = super::attachment_url_headers(&attachment_responses);
+    Ok((
+        headers,
+        Json(ApiResponse {
+            data: MessageWithAttachments {
+                message: message.in_format(format),
+                attachments: attachment_responses,
+                is_starred: false,
+                reply_to,
+                sender: None,
+                sender_avatar_url: None,
+            },
+        }),
+    ))
+}
+
+/// Point an error at the batch item that caused it
+fn batch_item_error(index: usize, err: ApiError) -> ApiError {
+    match err {
+        ApiError::Structured {
+            code,
+            message,
+            details,
+        } => ApiError::Structured {
+            code,
+            message: format!("messages[{}]: {}", index, message),
+            details,
+        },
+        ApiError::BadRequest(message) => {
+            ApiError::BadRequest(format!("messages[{}]: {}", index, message))
         }
-        ws::broadcast_message(&state.connections, dialog_id, &message).await;
-    };
+        other => other,
+    }
+}
 
-    let webhook_future = state
-        .webhooks
-        .send(WebhookEvent::message_new(&dialog, &message));
-
-    let notifications_future = async {
-        if state.jobs.is_enabled() {
-            for participant in &participants {
-                if participant.user_id != sender_id {
-                    let job = NotificationJob::new(
-                        dialog_id,
-                        &participant.user_id,
-                        message.id,
-                        &sender_id,
-                    );
-                    if let Err(e) = state.jobs.enqueue_notification(job).await {
-                        tracing::warn!(
-                            recipient_id = %participant.user_id,
-                            error = %e,
-                            "Failed to enqueue notification job"
-                        );
-                    }
-                }
-            }
+/// Messages a sender already stored under the given client IDs
+async fn find_by_client_ids<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    dialog_id: Uuid,
+    sender_id: &str,
+    client_ids: &[&str],
+) -> Result<Vec<Message>, sqlx::Error> {
+    sqlx::query_as::<_, Message>(
+        r#"SELECT * FROM messages
+           WHERE dialog_id = $1 AND sender_id = $2
+             AND metadata ? 'client_id' AND metadata->>'client_id' = ANY($3)"#,
+    )
+    .bind(dialog_id)
+    .bind(sender_id)
+    .bind(client_ids)
+    .fetch_all(executor)
+    .await
+}
+
+/// Send messages composed offline, in order and all or nothing
+///
+/// Each message records its client ID and composition time in `metadata`.
+/// Messages already stored under their client ID (a retried batch) are
+/// returned as duplicates instead of being sent again. Slow mode counts the
+/// batch as one send.
+pub async fn send_message_batch(
+    State(state): State<AppState>,
+    UserId(sender_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Json(req): Json<SendMessageBatchRequest>,
+) -> Result<(HeaderMap, Json<ApiResponse<SendMessageBatchResponse>>), ApiError> {
+    if req.messages.is_empty() || req.messages.len() > MAX_BATCH_MESSAGES {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("messages must contain 1 to {} items", MAX_BATCH_MESSAGES),
+        ));
+    }
+    let mut client_ids: Vec<&str> = Vec::with_capacity(req.messages.len());
+    for (index, input) in req.messages.iter().enumerate() {
+        domain::validation::validate_identifier(&input.client_id, "client_id").map_err(|e| {
+            batch_item_error(index, ApiError::new(ErrorCode::InvalidInput, e.message))
+        })?;
+        if client_ids.contains(&input.client_id.as_str()) {
+            return Err(batch_item_error(
+                index,
+                ApiError::new(
+                    ErrorCode::InvalidInput,
+                    "client_id must be unique within the batch",
+                ),
+            ));
         }
-    };
+        client_ids.push(&input.client_id);
+    }
+
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+    let sender = find_sender(&state, dialog_id, &sender_id).await?;
+    let profile = sanitize_profile(&state, &dialog).await?;
 
-    // Execute all in parallel
-    tokio::join!(broadcast_future, webhook_future, notifications_future);
+    let mut stored: HashMap<String, Message> =
+        find_by_client_ids(&state.db, dialog_id, &sender_id, &client_ids)
+            .await?
+            .into_iter()
+            .filter_map(|m| Some((m.client_id()?.to_string(), m)))
+            .collect();
 
-    Ok(Json(ApiResponse {
-        data: MessageWithAttachments {
+    // Response order and format of every item
+    let order: Vec<(String, ContentFormat)> = req
+        .messages
+        .iter()
+        .map(|m| (m.client_id.clone(), m.message.content_format))
+        .collect();
+
+    let mut prepared = Vec::new();
+    for (index, input) in req.messages.into_iter().enumerate() {
+        if stored.contains_key(&input.client_id) {
+            continue;
+        }
+        let mut message = prepare_message(&state, &dialog, &sender, profile, input.message)
+            .await
+            .map_err(|e| batch_item_error(index, e))?;
+        message.message = message
+            .message
+            .with_client_origin(&input.client_id, input.client_sent_at);
+        prepared.push((input.client_id, message));
+    }
+
+    if !prepared.is_empty() {
+        check_slow_mode(&state, &dialog, &sender_id).await?;
+    }
+
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+
+    // Retries of one sender's batch run one after another, so a retry racing
+    // the original finds the messages it stored
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("message-batch:{}:{}", dialog_id, sender_id))
+        .execute(&mut *tx)
+        .await?;
+    let new_ids: Vec<&str> = prepared.iter().map(|(id, _)| id.as_str()).collect();
+    for message in find_by_client_ids(&mut *tx, dialog_id, &sender_id, &new_ids).await? {
+        if let Some(client_id) = message.client_id() {
+            stored.insert(client_id.to_string(), message);
+        }
+    }
+    prepared.retain(|(client_id, _)| !stored.contains_key(client_id));
+
+    let attachments_size: i64 = prepared.iter().map(|(_, p)| p.attachments_size()).sum();
+    super::upload::reserve_storage(&state, &mut tx, dialog_id, attachments_size).await?;
+
+    let mut sent = Vec::with_capacity(prepared.len());
+    for (client_id, prepared) in prepared {
+        let (message, attachments) = store_message(&mut tx, &prepared).await?;
+        sent.push((client_id, message, prepared, attachments));
+    }
+    let mut unarchived_ids = match sent.last() {
+        Some((_, last, ..)) => {
+            record_sent_messages(&mut tx, dialog_id, &sender_id, sent.len() as i32, last.id).await?
+        }
+        None => Vec::new(),
+    };
+    tx.commit().await?;
+
+    // Broadcast in order, after the transaction is committed
+    let mut items = HashMap::with_capacity(sent.len());
+    for (client_id, message, prepared, attachments) in sent {
+        let attachment_responses = attachment_responses(&state, &attachments).await;
+        state.events.publish(DomainEvent::MessageSent {
+            dialog: dialog.clone(),
+            message: message.clone(),
+            reply_to: prepared.reply_to.clone(),
+            attachments,
+            unarchived_for: std::mem::take(&mut unarchived_ids),
+            broadcast: prepared.broadcast,
+        });
+        let item = MessageWithAttachments {
             message,
             attachments: attachment_responses,
-        },
-    }))
+            is_starred: false,
+            reply_to: prepared.reply_to,
+            sender: None,
+            sender_avatar_url: None,
+        };
+        items.insert(client_id, item);
+    }
+
+    // Attachments, reply previews and stars of the messages stored before
+    let stored_ids: Vec<Uuid> = stored.values().map(|m| m.id).collect();
+    let mut stored_attachments: HashMap<Uuid, Vec<domain::Attachment>> = HashMap::new();
+    for att in state.attachments.list_by_messages(&stored_ids).await? {
+        stored_attachments
+            .entry(att.message_id)
+            .or_default()
+            .push(att);
+    }
+    let reply_ids: Vec<Uuid> = stored.values().filter_map(|m| m.reply_to_id).collect();
+    let reply_previews = state.messages.reply_previews(dialog_id, &reply_ids).await?;
+    let starred = state
+        .message_stars
+        .starred_among(&sender_id, &stored_ids)
+        .await?;
+
+    let mut messages = Vec::with_capacity(order.len());
+    for (client_id, format) in order {
+        let (duplicate, item) = match items.remove(&client_id) {
+            Some(item) => (false, item),
+            None => {
+                let Some(message) = stored.remove(&client_id) else {
+                    continue;
+                };
+                let attachments = stored_attachments.remove(&message.id).unwrap_or_default();
+                let item = MessageWithAttachments {
+                    attachments: attachment_responses(&state, &attachments).await,
+                    is_starred: starred.contains(&message.id),
+                    reply_to: message
+                        .reply_to_id
+                        .and_then(|id| reply_previews.get(&id).cloned()),
+                    sender: None,
+                    sender_avatar_url: None,
+                    message,
+                };
+                (true, item)
+            }
+        };
+        messages.push(BatchSentMessage {
+            client_id,
+            duplicate,
+            message: MessageWithAttachments {
+                message: item.message.in_format(format),
+                ..item
+            },
+        });
+    }
+
+    let headers =
+        super::attachment_url_headers(messages.iter().flat_map(|m| &m.message.attachments));
+    Ok((
+        headers,
+        Json(ApiResponse {
+            data: SendMessageBatchResponse { messages },
+        }),
+    ))
 }
 
 pub async fn get_message(
     State(state): State<AppState>,
     Path((dialog_id, message_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<ContentFormatQuery>,
 ) -> Result<Json<ApiResponse<Message>>, ApiError> {
     let message = state
         .messages
@@ -463,15 +1207,84 @@ pub async fn get_message(
         .await?
         .ok_or_else(|| ApiError::new(ErrorCode::MessageNotFound, "Message not found"))?;
 
-    Ok(Json(ApiResponse { data: message }))
+    Ok(Json(ApiResponse {
+        data: message.in_format(query.content_format),
+    }))
+}
+
+/// A message with its surrounding messages and the cursors to continue
+/// scrolling from there in [`list_messages`]
+pub async fn get_message_context(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((dialog_id, message_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<MessageContextQuery>,
+) -> Result<(HeaderMap, Json<ApiResponse<MessageContextResponse>>), ApiError> {
+    // Same access as the message list: a search hit may lead to a dialog the
+    // user has left since
+    if !state.participants.exists(dialog_id, &user_id).await? {
+        return Err(ApiError::Forbidden(
+            "Not a participant. Join the dialog first.".into(),
+        ));
+    }
+    if state
+        .messages
+        .find_by_id_and_dialog(message_id, dialog_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::new(
+            ErrorCode::MessageNotFound,
+            "Message not found",
+        ));
+    }
+
+    let limit = query.limit.clamp(2, 100);
+    let (messages, has_more_before, has_more_after) = state
+        .messages
+        .list_around(dialog_id, message_id, limit)
+        .await?;
+    let before_cursor = messages.first().filter(|_| has_more_before).map(|m| m.id);
+    let after_cursor = messages.last().filter(|_| has_more_after).map(|m| m.id);
+
+    let messages = with_attachments(
+        &state,
+        &user_id,
+        dialog_id,
+        messages,
+        includes(query.include.as_deref(), "sender"),
+        query.content_format,
+    )
+    .await?;
+
+    let headers = super::attachment_url_headers(messages.iter().flat_map(|m| m.attachments.iter()));
+    Ok((
+        headers,
+        Json(ApiResponse {
+            data: MessageContextResponse {
+                message_id,
+                messages,
+                has_more_before,
+                has_more_after,
+                before_cursor,
+                after_cursor,
+            },
+        }),
+    ))
 }
 
 pub async fn edit_message(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path((dialog_id, message_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
     Json(req): Json<EditMessageRequest>,
 ) -> Result<Json<ApiResponse<Message>>, ApiError> {
+    let expected_version = match req.version {
+        Some(version) => Some(version),
+        None => if_match_version(&headers)?,
+    };
+
     // Find message
     let message = state
         .messages
@@ -486,28 +1299,40 @@ pub async fn edit_message(
             "Can only edit own messages",
         ));
     }
+    ensure_not_pending_removal(&state, dialog_id, &user_id).await?;
 
     // Can't edit system messages
     if message.message_type != domain::MessageType::User {
         return Err(ApiError::BadRequest("Cannot edit system messages".into()));
     }
 
+    if expected_version.is_some_and(|v| v != message.version) {
+        return Err(version_conflict(message));
+    }
+
     // Validate content length
-    if req.content.len() > domain::validation::MAX_MESSAGE_LENGTH {
+    let max_message_length = state.settings.current().max_message_length;
+    if req.content.len() > max_message_length {
         return Err(ApiError::new(
             ErrorCode::InvalidInput,
             format!(
                 "Message content exceeds maximum length of {} characters",
-                domain::validation::MAX_MESSAGE_LENGTH
+                max_message_length
             ),
         ));
     }
 
     // Sanitize content
-    let sanitized = domain::sanitize_html(&req.content);
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+    let profile = sanitize_profile(&state, &dialog).await?;
+    let (sanitized, markdown_source) = render_content(&req.content, req.content_format, profile);
 
     // All DB writes in a transaction
-    let mut tx = state.db.begin().await?;
+    let mut tx = middleware::begin_transaction(&state.db).await?;
 
     // Save old content to history
     sqlx::query(
@@ -520,25 +1345,67 @@ pub async fn edit_message(
     .execute(&mut *tx)
     .await?;
 
-    // Update message content
+    // Update message content, re-checking the expected version in case of a
+    // concurrent edit since the message was read above
+    let stored = StoredContent::encode(&sanitized);
     let updated = sqlx::query_as::<_, Message>(
         r#"UPDATE messages
-           SET content = $2, last_edited_at = NOW()
-           WHERE id = $1
+           SET content = $2, content_markdown = $4, content_encoding = $5, content_compressed = $6,
+               content_search_vector = strip(to_tsvector('simple', $7)),
+               last_edited_at = NOW(), version = version + 1
+           WHERE id = $1 AND ($3::int IS NULL OR version = $3)
            RETURNING *"#,
     )
     .bind(message_id)
-    .bind(&sanitized)
+    .bind(&stored.content)
+    .bind(expected_version)
+    .bind(&markdown_source)
+    .bind(stored.encoding)
+    .bind(&stored.compressed)
+    .bind(&stored.search_text)
     .fetch_optional(&mut *tx)
-    .await?
-    .ok_or_else(|| ApiError::Internal("Failed to update message".into()))?;
+    .await?;
+    let Some(updated) = updated else {
+        tx.rollback().await?;
+        let current = state
+            .messages
+            .find_by_id_and_dialog(message_id, dialog_id)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::MessageNotFound, "Message not found"))?;
+        return Err(version_conflict(current));
+    };
+
+    // Mentions follow the edited content
+    sqlx::query("DELETE FROM message_mentions WHERE message_id = $1")
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+    let mentions = domain::extract_mentions(&updated.content);
+    if !mentions.is_empty() {
+        sqlx::query(
+            r#"INSERT INTO message_mentions (message_id, dialog_id, user_id)
+               SELECT $1, $2, UNNEST($3::text[])
+               ON CONFLICT DO NOTHING"#,
+        )
+        .bind(message_id)
+        .bind(dialog_id)
+        .bind(&mentions)
+        .execute(&mut *tx)
+        .await?;
+    }
 
     tx.commit().await?;
 
-    // Broadcast via WebSocket after transaction is committed
-    ws::broadcast_message_edited(&state.connections, &updated).await;
+    // Broadcast and webhooks after the transaction is committed
+    state.events.publish(DomainEvent::MessageEdited {
+        dialog,
+        message: updated.clone(),
+        previous_content: message.content,
+    });
 
-    Ok(Json(ApiResponse { data: updated }))
+    Ok(Json(ApiResponse {
+        data: updated.in_format(req.content_format),
+    }))
 }
 
 pub async fn delete_message(
@@ -560,12 +1427,372 @@ pub async fn delete_message(
             "Can only delete own messages",
         ));
     }
+    ensure_not_pending_removal(&state, dialog_id, &user_id).await?;
+
+    // Delete message (attachment rows are removed by cascade, files by a job)
+    // and release the storage of its attachments in the same transaction
+    let attachment_keys = state.attachments.list_keys_by_message(message_id).await?;
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+    if let Some(size) = MessageRepository::delete_with_attachments_size(&mut tx, message_id).await?
+    {
+        StorageUsageRepository::add_dialog_bytes(&mut tx, dialog_id, -size).await?;
+    }
+    tx.commit().await?;
+
+    // Broadcast, webhooks and attachment file cleanup
+    let dialog = state.dialogs.find_by_id(dialog_id).await?;
+    state.events.publish(DomainEvent::MessageDeleted {
+        dialog,
+        message,
+        attachment_keys,
+    });
+
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    // Delete message
-    state.messages.delete(message_id).await?;
+// ============ Stars ============
 
-    // Broadcast via WebSocket
-    ws::broadcast_message_deleted(&state.connections, dialog_id, message_id).await;
+pub async fn star_message(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((dialog_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    if !state.participants.exists(dialog_id, &user_id).await? {
+        return Err(ApiError::new(
+            ErrorCode::NotParticipant,
+            "Not a participant. Join the dialog first.",
+        ));
+    }
+
+    state
+        .messages
+        .find_by_id_and_dialog(message_id, dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::MessageNotFound, "Message not found"))?;
+
+    state
+        .message_stars
+        .star(&user_id, dialog_id, message_id)
+        .await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+pub async fn unstar_message(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((dialog_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    // Idempotent: removing a missing star is not an error
+    state
+        .message_stars
+        .unstar(&user_id, dialog_id, message_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============ Reactions ============
+
+/// Checks shared by the reaction handlers: the user is a participant,
+/// reactions are enabled for the dialog and the message exists.
+async fn check_reaction_access(
+    state: &AppState,
+    user_id: &str,
+    dialog_id: Uuid,
+    message_id: Uuid,
+) -> Result<(), ApiError> {
+    if !state.participants.exists(dialog_id, user_id).await? {
+        return Err(ApiError::new(
+            ErrorCode::NotParticipant,
+            "Not a participant. Join the dialog first.",
+        ));
+    }
+
+    state
+        .feature_flags
+        .require(dialog_id, REACTIONS_FLAG)
+        .await?;
+
+    state
+        .messages
+        .find_by_id_and_dialog(message_id, dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::MessageNotFound, "Message not found"))?;
+
+    Ok(())
+}
+
+fn validate_reaction(emoji: &str) -> Result<(), ApiError> {
+    if !domain::is_valid_reaction(emoji) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "Reaction must be 1-{} characters without whitespace",
+                MAX_REACTION_LENGTH
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_reactions(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((dialog_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiResponse<Vec<ReactionSummary>>>, ApiError> {
+    check_reaction_access(&state, &user_id, dialog_id, message_id).await?;
+
+    let reactions = state
+        .message_reactions
+        .summarize(message_id, &user_id)
+        .await?;
+    Ok(Json(ApiResponse { data: reactions }))
+}
+
+pub async fn add_reaction(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((dialog_id, message_id, emoji)): Path<(Uuid, Uuid, String)>,
+) -> Result<StatusCode, ApiError> {
+    validate_reaction(&emoji)?;
+    check_reaction_access(&state, &user_id, dialog_id, message_id).await?;
+
+    let added = state
+        .message_reactions
+        .add(dialog_id, message_id, &user_id, &emoji)
+        .await?;
+    if added {
+        ws::broadcast_message_reaction(
+            &state.connections,
+            &state.participants,
+            dialog_id,
+            message_id,
+            &user_id,
+            &emoji,
+            true,
+        )
+        .await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_reaction(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((dialog_id, message_id, emoji)): Path<(Uuid, Uuid, String)>,
+) -> Result<StatusCode, ApiError> {
+    check_reaction_access(&state, &user_id, dialog_id, message_id).await?;
+
+    // Idempotent: removing a missing reaction is not an error
+    let removed = state
+        .message_reactions
+        .remove(dialog_id, message_id, &user_id, &emoji)
+        .await?;
+    if removed {
+        ws::broadcast_message_reaction(
+            &state.connections,
+            &state.participants,
+            dialog_id,
+            message_id,
+            &user_id,
+            &emoji,
+            false,
+        )
+        .await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Message counts per day of a dialog, for a jump-to-date picker
+pub async fn message_calendar(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Query(query): Query<CalendarQuery>,
+) -> Result<Json<ApiResponse<Vec<MessageDayCount>>>, ApiError> {
+    if !state.participants.exists(dialog_id, &user_id).await? {
+        return Err(ApiError::Forbidden(
+            "Not a participant. Join the dialog first.".into(),
+        ));
+    }
+    let days = (query.to - query.from).num_days() + 1;
+    if days < 1 {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            "'from' must not be after 'to'",
+        ));
+    }
+    if days > MAX_CALENDAR_DAYS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("At most {} days per request", MAX_CALENDAR_DAYS),
+        ));
+    }
+
+    let timezone = calendar_timezone(&state, dialog_id, query.tz).await?;
+    let counts = state
+        .messages
+        .count_by_day(dialog_id, query.from, query.to, &timezone)
+        .await?;
+
+    Ok(Json(ApiResponse { data: counts }))
+}
+
+/// Timezone that dates of a dialog's messages are taken in: the requested
+/// one, else the dialog's, else UTC
+async fn calendar_timezone(
+    state: &AppState,
+    dialog_id: Uuid,
+    requested: Option<String>,
+) -> Result<String, ApiError> {
+    domain::validation::validate_timezone(&requested)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidInput, e.message))?;
+    if let Some(tz) = requested {
+        if !state.dialogs.is_known_timezone(&tz).await? {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("Unknown timezone '{}'", tz),
+            ));
+        }
+        return Ok(tz);
+    }
+    let dialog = state.dialogs.find_by_id(dialog_id).await?;
+    Ok(dialog
+        .and_then(|d| d.timezone)
+        .unwrap_or_else(|| "UTC".to_string()))
+}
+
+/// Starred messages of the current user across all their dialogs
+pub async fn list_starred_messages(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<StarredMessagesQuery>,
+) -> Result<Json<ApiResponse<Vec<StarredMessage>>>, ApiError> {
+    let limit = query.limit.clamp(1, 100);
+    let messages = state
+        .message_stars
+        .list_for_user(&user_id, limit, query.before)
+        .await?;
+    Ok(Json(ApiResponse { data: messages }))
+}
+
+// ============ Actions ============
+
+/// Click a callback button of an integration message
+///
+/// The click is recorded as a system message in the dialog and forwarded to
+/// the host as a `message.action` webhook.
+pub async fn click_message_action(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((message_id, action_id)): Path<(Uuid, String)>,
+) -> Result<Json<ApiResponse<Message>>, ApiError> {
+    let message = state
+        .messages
+        .find_by_id(message_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::MessageNotFound, "Message not found"))?;
+    let dialog_id = message.dialog_id;
+
+    let participant = state
+        .participants
+        .find(dialog_id, &user_id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::NotParticipant,
+                "Not a participant. Join the dialog first.",
+            )
+        })?;
+    if participant.joined_as.is_observer() {
+        return Err(ApiError::new(
+            ErrorCode::ObserverReadOnly,
+            "Observers cannot use message actions",
+        ));
+    }
+    if participant.is_pending_removal() {
+        return Err(pending_removal_error());
+    }
+
+    let action = message.action_button(&action_id).ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::ActionNotFound,
+            "Message has no action with this id",
+        )
+    })?;
+
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let system_msg = Message::system(
+        dialog_id,
+        domain::system_messages::action_clicked_content(
+            participant.display_name.as_deref().unwrap_or(&user_id),
+            message.id,
+            &action.id,
+            &action.label,
+            &dialog.locale_context(),
+        ),
+    );
+
+    let mut tx = middleware::begin_transaction(&state.db).await?;
+
+    let system_msg = sqlx::query_as::<_, Message>(
+        r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type, external_id)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+           RETURNING *"#,
+    )
+    .bind(system_msg.id)
+    .bind(system_msg.dialog_id)
+    .bind(&system_msg.sender_id)
+    .bind(&system_msg.content)
+    .bind(system_msg.sent_at)
+    .bind(system_msg.reply_to_id)
+    .bind(system_msg.message_type.as_str())
+    .bind(&system_msg.external_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // The click is news for everyone but the participant who clicked
+    sqlx::query(
+        r#"UPDATE dialog_participants
+           SET unread_count = unread_count + 1
+           WHERE dialog_id = $1 AND user_id <> $2 AND joined_as <> 'observer'"#,
+    )
+    .bind(dialog_id)
+    .bind(&user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    ws::broadcast_message(
+        &state.connections,
+        &state.participants,
+        dialog_id,
+        &system_msg,
+        None,
+    )
+    .await;
+    state
+        .webhooks
+        .send(WebhookEvent::message_new(&dialog, &system_msg))
+        .await;
+    state
+        .webhooks
+        .send(WebhookEvent::message_action(
+            &dialog,
+            &message,
+            action,
+            &user_id,
+            system_msg.id,
+        ))
+        .await;
+
+    Ok(Json(ApiResponse { data: system_msg }))
+}
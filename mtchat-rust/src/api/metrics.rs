@@ -0,0 +1,182 @@
+//! Prometheus metrics endpoint.
+//!
+//! Exposes per-instance gauges and counters in the Prometheus text format.
+//! Scrape every instance: values are not aggregated across the cluster.
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::AppState;
+use crate::jobs::JobRetrySnapshot;
+
+static DB_POOL_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+static DB_STATEMENT_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+
+/// Count a request that found no free database connection
+pub(crate) fn record_db_pool_timeout() {
+    DB_POOL_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a request whose statement exceeded `statement_timeout`
+pub(crate) fn record_db_statement_timeout() {
+    DB_STATEMENT_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let ws = state.ws_registry.metrics();
+    let cleanup = state.jobs.cleanup_metrics().snapshot();
+    let unread = state.jobs.unread_drift_metrics().snapshot();
+    let backlog = state.jobs.notification_backlog().snapshot();
+    let retries = state.jobs.retry_metrics().snapshot();
+    let instance = state.ws_registry.instance_id();
+    let pool_size = state.db.size();
+    let pool_idle = state.db.num_idle() as u32;
+
+    let mut body = String::new();
+    for (name, kind, help, value) in [
+        (
+            "mtchat_ws_connections",
+            "gauge",
+            "Open WebSocket connections on this instance",
+            ws.connections as u64,
+        ),
+        (
+            "mtchat_ws_connections_opened_total",
+            "counter",
+            "WebSocket connections opened since start",
+            ws.opened_total,
+        ),
+        (
+            "mtchat_ws_forced_disconnects_total",
+            "counter",
+            "WebSocket connections closed through the Management API",
+            ws.forced_disconnects_total,
+        ),
+        (
+            "mtchat_attachment_cleanup_deleted_total",
+            "counter",
+            "Attachment files deleted after their message or dialog was deleted",
+            cleanup.deleted_total,
+        ),
+        (
+            "mtchat_attachment_cleanup_skipped_total",
+            "counter",
+            "Attachment files kept because another attachment still references them",
+            cleanup.skipped_total,
+        ),
+        (
+            "mtchat_attachment_cleanup_failed_total",
+            "counter",
+            "Failed attachment file deletions (retried)",
+            cleanup.failed_total,
+        ),
+        (
+            "mtchat_unread_drift_repaired_total",
+            "counter",
+            "Participant unread counters found drifted and repaired",
+            unread.repaired_total,
+        ),
+        (
+            "mtchat_unread_drift_total",
+            "counter",
+            "Sum of the corrections made to drifted unread counters",
+            unread.drift_total,
+        ),
+        (
+            "mtchat_notification_backlog_age_seconds",
+            "gauge",
+            "How long the last notification job waited in the queue",
+            backlog.age_secs,
+        ),
+        (
+            "mtchat_notification_jobs_stale_total",
+            "counter",
+            "Notification jobs dropped because they waited longer than the max age",
+            backlog.stale_total,
+        ),
+        (
+            "mtchat_notification_jobs_skipped_total",
+            "counter",
+            "Notification jobs dropped because the backlog was skipped",
+            backlog.skipped_total,
+        ),
+        (
+            "mtchat_storage_available",
+            "gauge",
+            "1 while file storage calls go through, 0 while its circuit breaker is open",
+            state.storage.is_available() as u64,
+        ),
+        (
+            "mtchat_db_pool_connections",
+            "gauge",
+            "Open database connections in the pool",
+            pool_size as u64,
+        ),
+        (
+            "mtchat_db_pool_connections_in_use",
+            "gauge",
+            "Database connections currently used by requests and jobs",
+            pool_size.saturating_sub(pool_idle) as u64,
+        ),
+        (
+            "mtchat_db_pool_max_connections",
+            "gauge",
+            "Database pool size limit",
+            state.db.options().get_max_connections() as u64,
+        ),
+        (
+            "mtchat_db_pool_timeouts_total",
+            "counter",
+            "Requests rejected with 503 because no database connection was free",
+            DB_POOL_TIMEOUTS.load(Ordering::Relaxed),
+        ),
+        (
+            "mtchat_db_statement_timeouts_total",
+            "counter",
+            "Requests rejected with 503 because a statement exceeded the statement timeout",
+            DB_STATEMENT_TIMEOUTS.load(Ordering::Relaxed),
+        ),
+    ] {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} {}", name, kind);
+        let _ = writeln!(body, "{}{{instance=\"{}\"}} {}", name, instance, value);
+    }
+
+    // Per job type
+    for (name, help, value) in [
+        (
+            "mtchat_job_retries_total",
+            "Failed queue job attempts that were retried",
+            (|s: &JobRetrySnapshot| s.retries_total) as fn(&JobRetrySnapshot) -> u64,
+        ),
+        (
+            "mtchat_job_dead_lettered_total",
+            "Queue jobs that failed permanently and were dead-lettered",
+            |s| s.dead_lettered_total,
+        ),
+    ] {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} counter", name);
+        for (job_type, snapshot) in &retries {
+            let _ = writeln!(
+                body,
+                "{}{{instance=\"{}\",job_type=\"{}\"}} {}",
+                name,
+                instance,
+                job_type,
+                value(snapshot)
+            );
+        }
+    }
+
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+}
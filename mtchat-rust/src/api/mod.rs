@@ -1,31 +1,59 @@
 //! HTTP API handlers for MTChat.
 //!
-//! Organized by domain: health, management, dialogs, messages, upload, participants, websocket.
+//! Organized by domain: health, metrics, management, export, dialogs, folders, notes, messages, upload, files,
+//! participants, avatars, search, sync, activity, tenants, transcripts, impersonation, integrations, websocket.
 
+pub mod activity;
+pub mod avatars;
 pub mod dialogs;
+pub mod export;
+pub mod files;
+pub mod folders;
 pub mod health;
+pub mod impersonation;
+pub mod integrations;
 pub mod management;
 pub mod messages;
+pub mod metrics;
+pub mod notes;
 pub mod participants;
+mod routes;
+pub mod search;
+pub mod sync;
+pub mod tenants;
+pub mod transcripts;
 pub mod upload;
 pub mod ws_handler;
 
-use axum::http::StatusCode;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Json};
-use dashmap::DashMap;
 use serde::Serialize;
 use sqlx::PgPool;
 use std::sync::Arc;
 
+use crate::config::AppConfig;
+use crate::domain::AttachmentResponse;
+use crate::events::EventBus;
 use crate::jobs::JobProducer;
+use crate::middleware::current_request_id;
 use crate::repositories::{
-    AccessScopeRepository, AttachmentRepository, DialogRepository, MessageRepository,
-    ParticipantRepository,
+    AccessScopeRepository, AttachmentRepository, AuditLogRepository, DialogActivityRepository,
+    DialogBanRepository, DialogEventRepository, DialogFolderRepository, DialogNotesRepository,
+    DialogRepository, DialogTemplateRepository, ExportRepository, FeatureFlagRepository,
+    InboundEventRepository, JobDeadLetterRepository, MessageReactionRepository, MessageRepository,
+    MessageStarRepository, ParticipantInviteRepository, ParticipantRepository, SlaRepository,
+    StorageUsageRepository, TenantSettingsRepository,
+};
+use crate::services::{
+    BlobStorage, Broker, ConnectionRegistry, FeatureFlagError, FeatureFlagService, FsStorage,
+    ImpersonationSigner, PresenceService, SettingsError, SettingsService, SlowModeLimiter,
+    StorageError, TranscriptSigner, UploadLimiter,
 };
-use crate::services::{PresenceService, S3Service};
 use crate::webhooks::WebhookSender;
 use crate::ws;
 
+pub use routes::router;
+
 // ============ App State ============
 
 #[derive(Clone)]
@@ -34,41 +62,123 @@ pub struct AppState {
     pub connections: ws::Connections,
     // Repositories
     pub dialogs: Arc<DialogRepository>,
+    pub dialog_events: Arc<DialogEventRepository>,
+    pub activity: Arc<DialogActivityRepository>,
+    pub dialog_templates: Arc<DialogTemplateRepository>,
+    pub folders: Arc<DialogFolderRepository>,
+    pub notes: Arc<DialogNotesRepository>,
     pub participants: Arc<ParticipantRepository>,
+    pub invites: Arc<ParticipantInviteRepository>,
+    pub bans: Arc<DialogBanRepository>,
     pub scopes: Arc<AccessScopeRepository>,
     pub messages: Arc<MessageRepository>,
+    pub message_reactions: Arc<MessageReactionRepository>,
+    pub message_stars: Arc<MessageStarRepository>,
     pub attachments: Arc<AttachmentRepository>,
+    pub storage_usage: Arc<StorageUsageRepository>,
+    pub tenant_settings: Arc<TenantSettingsRepository>,
+    pub sla: Arc<SlaRepository>,
+    pub export: Arc<ExportRepository>,
     // Services
-    pub s3: Arc<S3Service>,
+    pub storage: Arc<dyn BlobStorage>,
+    /// Filesystem storage backend, whose signed file URLs the service serves
+    pub fs_storage: Option<Arc<FsStorage>>,
     pub presence: Arc<PresenceService>,
+    pub upload_limiter: Arc<UploadLimiter>,
+    pub slow_mode: Arc<SlowModeLimiter>,
+    pub settings: Arc<SettingsService>,
+    pub feature_flags: Arc<FeatureFlagService>,
+    pub ws_registry: Arc<ConnectionRegistry>,
+    /// Cross-instance pub/sub (None on a single instance without Redis)
+    pub broker: Option<Arc<dyn Broker>>,
+    pub transcripts: Arc<TranscriptSigner>,
+    pub impersonation: Arc<ImpersonationSigner>,
+    pub audit_log: Arc<AuditLogRepository>,
+    pub job_dead_letters: Arc<JobDeadLetterRepository>,
+    pub inbound_events: Arc<InboundEventRepository>,
+    // Effective configuration
+    pub config: Arc<AppConfig>,
     // Webhooks
     pub webhooks: WebhookSender,
     // Jobs
     pub jobs: JobProducer,
+    /// Domain events, consumed by WebSocket, webhook and job subscribers
+    pub events: EventBus,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         db: PgPool,
         webhooks: WebhookSender,
-        s3: S3Service,
+        storage: Arc<dyn BlobStorage>,
         presence: PresenceService,
+        upload_limiter: UploadLimiter,
+        slow_mode: SlowModeLimiter,
+        settings: Arc<SettingsService>,
+        config: Arc<AppConfig>,
         jobs: JobProducer,
+        ws_registry: Arc<ConnectionRegistry>,
     ) -> Self {
         Self {
             dialogs: Arc::new(DialogRepository::new(db.clone())),
+            dialog_events: Arc::new(DialogEventRepository::new(db.clone())),
+            activity: Arc::new(DialogActivityRepository::new(db.clone())),
+            dialog_templates: Arc::new(DialogTemplateRepository::new(db.clone())),
+            folders: Arc::new(DialogFolderRepository::new(db.clone())),
+            notes: Arc::new(DialogNotesRepository::new(db.clone())),
             participants: Arc::new(ParticipantRepository::new(db.clone())),
+            invites: Arc::new(ParticipantInviteRepository::new(db.clone())),
+            bans: Arc::new(DialogBanRepository::new(db.clone())),
             scopes: Arc::new(AccessScopeRepository::new(db.clone())),
             messages: Arc::new(MessageRepository::new(db.clone())),
+            message_reactions: Arc::new(MessageReactionRepository::new(db.clone())),
+            message_stars: Arc::new(MessageStarRepository::new(db.clone())),
             attachments: Arc::new(AttachmentRepository::new(db.clone())),
-            connections: Arc::new(DashMap::new()),
+            storage_usage: Arc::new(StorageUsageRepository::new(db.clone())),
+            tenant_settings: Arc::new(TenantSettingsRepository::new(db.clone())),
+            sla: Arc::new(SlaRepository::new(db.clone())),
+            export: Arc::new(ExportRepository::new(db.clone())),
+            feature_flags: Arc::new(FeatureFlagService::new(
+                FeatureFlagRepository::new(db.clone()),
+                settings.clone(),
+            )),
+            transcripts: Arc::new(
+                TranscriptSigner::new(config.transcripts.clone())
+                    .with_base_path(config.server.base_path()),
+            ),
+            impersonation: Arc::new(ImpersonationSigner::new(config.impersonation.clone())),
+            audit_log: Arc::new(AuditLogRepository::new(db.clone())),
+            job_dead_letters: Arc::new(JobDeadLetterRepository::new(db.clone())),
+            inbound_events: Arc::new(InboundEventRepository::new(db.clone())),
+            connections: ws_registry.connections().clone(),
+            ws_registry,
             db,
-            s3: Arc::new(s3),
+            storage,
+            fs_storage: None,
+            broker: None,
             presence: Arc::new(presence),
+            upload_limiter: Arc::new(upload_limiter),
+            slow_mode: Arc::new(slow_mode),
+            settings,
+            config,
             webhooks,
             jobs,
+            events: EventBus::new(),
         }
     }
+
+    /// Serve signed file URLs of the filesystem storage backend
+    pub fn with_fs_storage(mut self, fs_storage: Arc<FsStorage>) -> Self {
+        self.fs_storage = Some(fs_storage);
+        self
+    }
+
+    /// Share cross-instance pub/sub with the background tasks
+    pub fn with_broker(mut self, broker: Arc<dyn Broker>) -> Self {
+        self.broker = Some(broker);
+        self
+    }
 }
 
 // ============ Common Response/Error Types ============
@@ -87,6 +197,12 @@ pub struct ErrorResponse {
 pub struct ErrorBody {
     pub code: String,
     pub message: String,
+    /// `X-Request-Id` of the failed request, for correlating with server logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Error-specific data (e.g. the current message on a version conflict)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 /// Structured error codes for API responses
@@ -95,19 +211,39 @@ pub enum ErrorCode {
     // Not Found errors
     DialogNotFound,
     MessageNotFound,
+    ActionNotFound,
     ParticipantNotFound,
     AttachmentNotFound,
+    SettingNotFound,
+    FolderNotFound,
     // Bad Request errors
     InvalidInput,
     FileTooLarge,
     UnsupportedFileType,
     TooManyAttachments,
+    StorageQuotaExceeded,
     // Forbidden errors
     NotParticipant,
     NotMessageAuthor,
     ScopeMismatch,
-    // Auth errors
-    Unauthorized,
+    FeatureDisabled,
+    BroadcastMentionForbidden,
+    UrgentPriorityForbidden,
+    ObserverReadOnly,
+    ParticipantRemovalPending,
+    BannedFromDialog,
+    // Conflict errors
+    VersionConflict,
+    EventInProgress,
+    // Payload Too Large errors
+    PayloadTooLarge,
+    // Too Many Requests errors
+    UploadLimitExceeded,
+    SlowModeActive,
+    // Service Unavailable errors
+    DatabaseBusy,
+    StorageUnavailable,
+    UploadLimitUnavailable,
     // Generic fallbacks
     NotFound,
     BadRequest,
@@ -120,16 +256,33 @@ impl ErrorCode {
         match self {
             ErrorCode::DialogNotFound => "DIALOG_NOT_FOUND",
             ErrorCode::MessageNotFound => "MESSAGE_NOT_FOUND",
+            ErrorCode::ActionNotFound => "ACTION_NOT_FOUND",
             ErrorCode::ParticipantNotFound => "PARTICIPANT_NOT_FOUND",
             ErrorCode::AttachmentNotFound => "ATTACHMENT_NOT_FOUND",
+            ErrorCode::SettingNotFound => "SETTING_NOT_FOUND",
+            ErrorCode::FolderNotFound => "FOLDER_NOT_FOUND",
             ErrorCode::InvalidInput => "INVALID_INPUT",
             ErrorCode::FileTooLarge => "FILE_TOO_LARGE",
             ErrorCode::UnsupportedFileType => "UNSUPPORTED_FILE_TYPE",
             ErrorCode::TooManyAttachments => "TOO_MANY_ATTACHMENTS",
+            ErrorCode::StorageQuotaExceeded => "STORAGE_QUOTA_EXCEEDED",
             ErrorCode::NotParticipant => "NOT_PARTICIPANT",
             ErrorCode::NotMessageAuthor => "NOT_MESSAGE_AUTHOR",
             ErrorCode::ScopeMismatch => "SCOPE_MISMATCH",
-            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::FeatureDisabled => "FEATURE_DISABLED",
+            ErrorCode::BroadcastMentionForbidden => "BROADCAST_MENTION_FORBIDDEN",
+            ErrorCode::UrgentPriorityForbidden => "URGENT_PRIORITY_FORBIDDEN",
+            ErrorCode::ObserverReadOnly => "OBSERVER_READ_ONLY",
+            ErrorCode::ParticipantRemovalPending => "PARTICIPANT_REMOVAL_PENDING",
+            ErrorCode::BannedFromDialog => "BANNED_FROM_DIALOG",
+            ErrorCode::VersionConflict => "VERSION_CONFLICT",
+            ErrorCode::EventInProgress => "EVENT_IN_PROGRESS",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::UploadLimitExceeded => "UPLOAD_LIMIT_EXCEEDED",
+            ErrorCode::SlowModeActive => "SLOW_MODE_ACTIVE",
+            ErrorCode::DatabaseBusy => "DATABASE_BUSY",
+            ErrorCode::StorageUnavailable => "STORAGE_UNAVAILABLE",
+            ErrorCode::UploadLimitUnavailable => "UPLOAD_LIMIT_UNAVAILABLE",
             ErrorCode::NotFound => "NOT_FOUND",
             ErrorCode::BadRequest => "BAD_REQUEST",
             ErrorCode::Forbidden => "FORBIDDEN",
@@ -141,38 +294,160 @@ impl ErrorCode {
         match self {
             ErrorCode::DialogNotFound
             | ErrorCode::MessageNotFound
+            | ErrorCode::ActionNotFound
             | ErrorCode::ParticipantNotFound
             | ErrorCode::AttachmentNotFound
+            | ErrorCode::SettingNotFound
+            | ErrorCode::FolderNotFound
             | ErrorCode::NotFound => StatusCode::NOT_FOUND,
 
             ErrorCode::InvalidInput
             | ErrorCode::FileTooLarge
             | ErrorCode::UnsupportedFileType
             | ErrorCode::TooManyAttachments
+            | ErrorCode::StorageQuotaExceeded
             | ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
 
             ErrorCode::NotParticipant
             | ErrorCode::NotMessageAuthor
             | ErrorCode::ScopeMismatch
+            | ErrorCode::FeatureDisabled
+            | ErrorCode::BroadcastMentionForbidden
+            | ErrorCode::UrgentPriorityForbidden
+            | ErrorCode::ObserverReadOnly
+            | ErrorCode::ParticipantRemovalPending
+            | ErrorCode::BannedFromDialog
             | ErrorCode::Forbidden => StatusCode::FORBIDDEN,
 
-            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::VersionConflict | ErrorCode::EventInProgress => StatusCode::CONFLICT,
+
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+
+            ErrorCode::UploadLimitExceeded | ErrorCode::SlowModeActive => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+
+            ErrorCode::DatabaseBusy
+            | ErrorCode::StorageUnavailable
+            | ErrorCode::UploadLimitUnavailable => StatusCode::SERVICE_UNAVAILABLE,
 
             ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    /// `Retry-After` hint (seconds) for errors that are worth retrying as is
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            ErrorCode::DatabaseBusy => Some(DB_BUSY_RETRY_AFTER_SECS),
+            ErrorCode::StorageUnavailable => Some(STORAGE_RETRY_AFTER_SECS),
+            ErrorCode::UploadLimitUnavailable => Some(UPLOAD_LIMIT_RETRY_AFTER_SECS),
+            _ => None,
+        }
+    }
+}
+
+/// `Retry-After` of `DATABASE_BUSY` responses
+pub const DB_BUSY_RETRY_AFTER_SECS: u64 = 1;
+
+/// `Retry-After` of `STORAGE_UNAVAILABLE` responses
+pub const STORAGE_RETRY_AFTER_SECS: u64 = 5;
+
+/// `Retry-After` of `UPLOAD_LIMIT_UNAVAILABLE` responses
+pub const UPLOAD_LIMIT_RETRY_AFTER_SECS: u64 = 5;
+
+/// Set to `unavailable` when some attachments in the response have
+/// `url: null` because storage is unavailable; clients fetch those URLs later
+/// from `GET /attachments/{id}/url`
+pub const ATTACHMENT_URLS_HEADER: &str = "x-attachment-urls";
+
+/// Response headers for a response with `attachments`
+pub(crate) fn attachment_url_headers<'a>(
+    attachments: impl IntoIterator<Item = &'a AttachmentResponse>,
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if attachments.into_iter().any(|a| a.is_url_unavailable()) {
+        headers.insert(
+            ATTACHMENT_URLS_HEADER,
+            HeaderValue::from_static("unavailable"),
+        );
+    }
+    headers
+}
+
+/// `WWW-Authenticate` challenge of a 401 response: how the endpoint expects
+/// the caller to authenticate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthChallenge {
+    scheme: &'static str,
+    realm: &'static str,
+    /// Credentials were sent but are not valid
+    invalid: bool,
+}
+
+impl AuthChallenge {
+    /// Chat API user token (`Authorization: Bearer`, `token` for WebSocket)
+    pub const CHAT: Self = Self::bearer("mtchat");
+    /// Management API admin token
+    pub const MANAGEMENT: Self = Self::bearer("mtchat-management");
+    /// Support impersonation token (`X-Impersonation-Token`)
+    pub const IMPERSONATION: Self = Self {
+        scheme: "Impersonation",
+        realm: "mtchat",
+        invalid: false,
+    };
+    /// Signature of inbound events (`X-Webhook-Signature`)
+    pub const WEBHOOK_SIGNATURE: Self = Self {
+        scheme: "Signature",
+        realm: "mtchat-integrations",
+        invalid: false,
+    };
+
+    const fn bearer(realm: &'static str) -> Self {
+        Self {
+            scheme: "Bearer",
+            realm,
+            invalid: false,
+        }
+    }
+
+    /// The same challenge for credentials that were sent but rejected
+    pub const fn invalid(self) -> Self {
+        Self {
+            invalid: true,
+            ..self
+        }
+    }
+
+    /// Header value, with `error="invalid_token"` for rejected bearer tokens
+    /// (RFC 6750)
+    pub fn header_value(&self) -> HeaderValue {
+        let mut value = format!("{} realm=\"{}\"", self.scheme, self.realm);
+        if self.invalid && self.scheme == "Bearer" {
+            value.push_str(", error=\"invalid_token\"");
+        }
+        HeaderValue::from_str(&value).expect("challenge is a valid header value")
+    }
 }
 
+/// Postgres `query_canceled`, raised when `statement_timeout` is exceeded
+const PG_QUERY_CANCELED: &str = "57014";
+
 pub enum ApiError {
     /// Error with structured code
     Structured {
         code: ErrorCode,
         message: String,
+        details: Option<serde_json::Value>,
     },
     /// Legacy errors (for backward compatibility)
     NotFound(String),
     BadRequest(String),
-    Unauthorized(String),
+    /// Missing or invalid credentials; 403 `Forbidden` is for authenticated
+    /// callers denied access
+    Unauthorized {
+        challenge: AuthChallenge,
+        message: String,
+    },
     Forbidden(String),
     Internal(String),
 }
@@ -183,43 +458,158 @@ impl ApiError {
         ApiError::Structured {
             code,
             message: message.into(),
+            details: None,
+        }
+    }
+
+    /// 401 asking the caller to authenticate as `challenge` describes
+    pub fn unauthorized(challenge: AuthChallenge, message: impl Into<String>) -> Self {
+        ApiError::Unauthorized {
+            challenge,
+            message: message.into(),
+        }
+    }
+
+    /// Attach error-specific data to a structured error
+    pub fn with_details(self, data: impl Serialize) -> Self {
+        match self {
+            ApiError::Structured { code, message, .. } => ApiError::Structured {
+                code,
+                message,
+                details: serde_json::to_value(data).ok(),
+            },
+            other => other,
         }
     }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        let (status, code, message) = match self {
-            ApiError::Structured { code, message } => (code.status_code(), code.as_str(), message),
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, "NOT_FOUND", msg),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "BAD_REQUEST", msg),
-            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg),
-            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN", msg),
+        let mut retry_after = None;
+        let mut challenge = None;
+        let (status, code, message, details) = match self {
+            ApiError::Structured {
+                code,
+                message,
+                details,
+            } => {
+                retry_after = code.retry_after_secs();
+                (code.status_code(), code.as_str(), message, details)
+            }
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, "NOT_FOUND", msg, None),
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "BAD_REQUEST", msg, None),
+            ApiError::Unauthorized {
+                challenge: c,
+                message,
+            } => {
+                challenge = Some(c);
+                (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", message, None)
+            }
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN", msg, None),
             ApiError::Internal(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "INTERNAL_ERROR",
                     "Internal server error".to_string(),
+                    None,
                 )
             }
         };
 
-        (
+        let mut response = (
             status,
             Json(ErrorResponse {
                 error: ErrorBody {
                     code: code.to_string(),
                     message,
+                    request_id: current_request_id(),
+                    details,
                 },
             }),
         )
-            .into_response()
+            .into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        if let Some(challenge) = challenge {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, challenge.header_value());
+        }
+        response
     }
 }
 
 impl From<sqlx::Error> for ApiError {
     fn from(e: sqlx::Error) -> Self {
-        ApiError::Internal(e.to_string())
+        match &e {
+            // Overload rather than a bug: tell the client to come back shortly
+            sqlx::Error::PoolTimedOut => {
+                metrics::record_db_pool_timeout();
+                tracing::warn!("No database connection available");
+                ApiError::new(
+                    ErrorCode::DatabaseBusy,
+                    "Database is busy, retry the request later",
+                )
+            }
+            sqlx::Error::Database(db) if db.code().as_deref() == Some(PG_QUERY_CANCELED) => {
+                metrics::record_db_statement_timeout();
+                tracing::warn!(error = %e, "Database statement timed out");
+                ApiError::new(
+                    ErrorCode::DatabaseBusy,
+                    "Database query timed out, retry the request later",
+                )
+            }
+            _ => ApiError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::NotFound(_) => {
+                ApiError::new(ErrorCode::AttachmentNotFound, "File not found")
+            }
+            StorageError::Unavailable(reason) => {
+                tracing::warn!(reason = %reason, "File storage unavailable");
+                ApiError::new(
+                    ErrorCode::StorageUnavailable,
+                    "File storage is unavailable, retry the request later",
+                )
+            }
+            other => ApiError::Internal(other.to_string()),
+        }
+    }
+}
+
+impl From<SettingsError> for ApiError {
+    fn from(e: SettingsError) -> Self {
+        match e {
+            SettingsError::UnknownKey(_) => {
+                ApiError::new(ErrorCode::SettingNotFound, e.to_string())
+            }
+            SettingsError::InvalidValue { .. } => {
+                ApiError::new(ErrorCode::InvalidInput, e.to_string())
+            }
+            SettingsError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl From<FeatureFlagError> for ApiError {
+    fn from(e: FeatureFlagError) -> Self {
+        match e {
+            FeatureFlagError::InvalidFlag(_) => {
+                ApiError::new(ErrorCode::InvalidInput, e.to_string())
+            }
+            FeatureFlagError::Disabled(_) => {
+                ApiError::new(ErrorCode::FeatureDisabled, e.to_string())
+            }
+            FeatureFlagError::Database(e) => e.into(),
+        }
     }
 }
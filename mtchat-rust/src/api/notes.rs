@@ -0,0 +1,154 @@
+//! Dialog notes endpoints
+//!
+//! A shared description pinned above the chat. Participants read and edit it;
+//! edits can require the version they were based on (body `version` or
+//! `If-Match`), like message edits.
+
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Json;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::domain::{
+    self, DialogNotes, DialogNotesRevision, DialogParticipant, MAX_DIALOG_NOTES_LENGTH,
+    MAX_NOTES_HISTORY,
+};
+use crate::middleware::UserId;
+use crate::ws;
+
+use super::messages::{if_match_version, pending_removal_error};
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
+
+// ============ DTOs ============
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotesRequest {
+    pub content: String,
+    /// Version the edit is based on (0 before the first edit); the edit fails
+    /// with 409 if the notes changed since (same as sending it in `If-Match`)
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotesHistoryQuery {
+    #[serde(default = "default_history_limit")]
+    pub limit: i64,
+}
+
+fn default_history_limit() -> i64 {
+    20
+}
+
+// ============ Helpers ============
+
+async fn require_participant(
+    state: &AppState,
+    dialog_id: Uuid,
+    user_id: &str,
+) -> Result<DialogParticipant, ApiError> {
+    state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    state
+        .participants
+        .find(dialog_id, user_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::NotParticipant, "Not a participant"))
+}
+
+async fn current_notes(state: &AppState, dialog_id: Uuid) -> Result<DialogNotes, ApiError> {
+    Ok(state
+        .notes
+        .find(dialog_id)
+        .await?
+        .unwrap_or_else(|| DialogNotes::empty(dialog_id)))
+}
+
+// ============ Handlers ============
+
+pub async fn get_notes(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<DialogNotes>>, ApiError> {
+    require_participant(&state, dialog_id, &user_id).await?;
+    let notes = current_notes(&state, dialog_id).await?;
+    Ok(Json(ApiResponse { data: notes }))
+}
+
+pub async fn update_notes(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<UpdateNotesRequest>,
+) -> Result<Json<ApiResponse<DialogNotes>>, ApiError> {
+    let expected_version = match req.version {
+        Some(version) => Some(version),
+        None => if_match_version(&headers)?,
+    };
+
+    let participant = require_participant(&state, dialog_id, &user_id).await?;
+    if participant.joined_as.is_observer() {
+        return Err(ApiError::new(
+            ErrorCode::ObserverReadOnly,
+            "Observers cannot edit dialog notes",
+        ));
+    }
+    if participant.is_pending_removal() {
+        return Err(pending_removal_error());
+    }
+
+    if req.content.len() > MAX_DIALOG_NOTES_LENGTH {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "Notes exceed maximum length of {} characters",
+                MAX_DIALOG_NOTES_LENGTH
+            ),
+        ));
+    }
+    let sanitized = domain::sanitize_html(&req.content);
+
+    let Some(notes) = state
+        .notes
+        .update(dialog_id, &sanitized, &user_id, expected_version)
+        .await?
+    else {
+        let current = current_notes(&state, dialog_id).await?;
+        return Err(ApiError::new(
+            ErrorCode::VersionConflict,
+            format!(
+                "Notes were changed since version was read (current version: {})",
+                current.version
+            ),
+        )
+        .with_details(serde_json::json!({ "current": current })));
+    };
+
+    let user_ids = state
+        .participants
+        .get_dialog_participants_user_ids(&[dialog_id])
+        .await?;
+    ws::broadcast_dialog_notes_updated(&state.connections, &notes, &user_ids).await;
+
+    Ok(Json(ApiResponse { data: notes }))
+}
+
+/// Replaced revisions of the notes, newest first
+pub async fn list_notes_history(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Query(query): Query<NotesHistoryQuery>,
+) -> Result<Json<ApiResponse<Vec<DialogNotesRevision>>>, ApiError> {
+    require_participant(&state, dialog_id, &user_id).await?;
+    let limit = query.limit.clamp(1, MAX_NOTES_HISTORY);
+    let history = state.notes.list_history(dialog_id, limit).await?;
+    Ok(Json(ApiResponse { data: history }))
+}
@@ -1,13 +1,20 @@
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::response::Json;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::domain::DialogParticipant;
+use crate::domain::{
+    Dialog, DialogParticipant, JoinedAs, ParticipantInvite, ParticipantSort, MAX_BULK_DIALOGS,
+    MAX_PARTICIPANTS_PAGE,
+};
 use crate::middleware::{OptionalScopeConfig, UserId};
+use crate::webhooks::WebhookEvent;
 use crate::ws;
 
-use super::{ApiError, ApiResponse, AppState};
+use super::avatars::{avatar_urls, resolve_avatars, AvatarUrls};
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
 
 // ============ DTOs ============
 
@@ -16,6 +23,138 @@ pub struct ParticipantResponse {
     #[serde(flatten)]
     pub participant: DialogParticipant,
     pub is_online: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<AvatarUrls>,
+}
+
+/// Participant invited by email who has no user ID yet
+#[derive(Debug, Serialize)]
+pub struct InvitedParticipantResponse {
+    pub invite_id: Uuid,
+    /// Always `invited`
+    pub status: &'static str,
+    /// Role on activation
+    pub joined_as: JoinedAs,
+    pub display_name: String,
+    pub company: Option<String>,
+    pub company_uid: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub invited_at: DateTime<Utc>,
+}
+
+impl From<ParticipantInvite> for InvitedParticipantResponse {
+    fn from(invite: ParticipantInvite) -> Self {
+        Self {
+            invite_id: invite.id,
+            status: "invited",
+            joined_as: invite.joined_as,
+            display_name: invite.display_name,
+            company: invite.company,
+            company_uid: invite.company_uid,
+            email: Some(invite.email),
+            phone: invite.phone,
+            invited_at: invite.invited_at,
+        }
+    }
+}
+
+/// Participant or pending invite in a participant list
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ParticipantEntry {
+    Participant(ParticipantResponse),
+    Invited(InvitedParticipantResponse),
+}
+
+impl ParticipantEntry {
+    fn company_uid(&self) -> Option<&String> {
+        match self {
+            Self::Participant(p) => p.participant.company_uid.as_ref(),
+            Self::Invited(i) => i.company_uid.as_ref(),
+        }
+    }
+
+    fn company(&self) -> Option<&String> {
+        match self {
+            Self::Participant(p) => p.participant.company.as_ref(),
+            Self::Invited(i) => i.company.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListParticipantsQuery {
+    /// `company` returns the participants grouped by company
+    pub group_by: Option<String>,
+    /// Display name or company contains this text (case-insensitive)
+    pub search: Option<String>,
+    /// Only participants that are online
+    #[serde(default)]
+    pub online_only: bool,
+    #[serde(default)]
+    pub sort: ParticipantSort,
+    /// Page size (all participants when absent)
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// Participants of one company with stats for the dialog header
+#[derive(Debug, Serialize)]
+pub struct CompanyGroup {
+    /// Company identifier the group is keyed by (absent for groups keyed by name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company_uid: Option<String>,
+    /// Company name (absent for participants without a company)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    /// Messages that no participant of the company has read yet (observers
+    /// are not counted)
+    pub unread_count: i32,
+    /// Latest message sent by a participant of the company
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity_at: Option<DateTime<Utc>>,
+    pub participants: Vec<ParticipantEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ParticipantList {
+    Participants(Vec<ParticipantEntry>),
+    Companies(Vec<CompanyGroup>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchGetParticipantsRequest {
+    pub dialog_ids: Vec<Uuid>,
+}
+
+/// Participant in a roster, with what a list row needs
+#[derive(Debug, Serialize)]
+pub struct RosterParticipant {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub company: Option<String>,
+    pub joined_as: JoinedAs,
+    pub is_online: bool,
+    /// Smallest avatar image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DialogRoster {
+    pub dialog_id: Uuid,
+    /// Ordered by join time
+    pub participants: Vec<RosterParticipant>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchGetParticipantsResponse {
+    pub dialogs: Vec<DialogRoster>,
+    /// Requested dialogs the user cannot access (or that do not exist)
+    pub skipped: Vec<Uuid>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,7 +169,19 @@ pub async fn list_participants(
     UserId(user_id): UserId,
     OptionalScopeConfig(scope_config): OptionalScopeConfig,
     Path(dialog_id): Path<Uuid>,
-) -> Result<Json<ApiResponse<Vec<ParticipantResponse>>>, ApiError> {
+    Query(query): Query<ListParticipantsQuery>,
+) -> Result<Json<ApiResponse<ParticipantList>>, ApiError> {
+    let by_company = match query.group_by.as_deref() {
+        None => false,
+        Some("company") => true,
+        Some(other) => {
+            return Err(ApiError::new(
+                ErrorCode::InvalidInput,
+                format!("Unknown group_by '{}', expected 'company'", other),
+            ));
+        }
+    };
+
     // Check if user is participant
     let is_participant = state.participants.exists(dialog_id, &user_id).await?;
 
@@ -41,6 +192,7 @@ pub async fn list_participants(
                 .scopes
                 .check_access(
                     dialog_id,
+                    &user_id,
                     &scope.scope_level0,
                     &scope.scope_level1,
                     &scope.scope_level2,
@@ -55,19 +207,77 @@ pub async fn list_participants(
         }
     }
 
-    let participants = state.participants.list_by_dialog(dialog_id).await?;
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let limit = query.limit.map(|l| l.clamp(1, MAX_PARTICIPANTS_PAGE));
+    let offset = query.offset.max(0);
 
-    // Get online status for all participants
-    let user_ids: Vec<String> = participants.iter().map(|p| p.user_id.clone()).collect();
-    let online_users = state
-        .presence
-        .get_online_users(&user_ids)
-        .await
-        .unwrap_or_default();
+    // Online status lives in presence, not the database: filter by the IDs
+    // of the participants online now
+    let online_only = if query.online_only {
+        let user_ids = state
+            .participants
+            .get_dialog_participants_user_ids(&[dialog_id])
+            .await?;
+        Some(
+            state
+                .presence
+                .get_online_users(&user_ids)
+                .await
+                .unwrap_or_default(),
+        )
+    } else {
+        None
+    };
+
+    let participants = state
+        .participants
+        .list_page(
+            dialog_id,
+            search,
+            online_only.as_deref(),
+            query.sort,
+            limit,
+            offset,
+        )
+        .await?;
+
+    // Pending invites follow the participants, on the page where they run out
+    let with_invites = online_only.is_none()
+        && match limit {
+            None => true,
+            Some(limit) if (participants.len() as i64) < limit => {
+                !participants.is_empty()
+                    || offset == 0
+                    || !state
+                        .participants
+                        .list_page(dialog_id, search, None, query.sort, Some(1), offset - 1)
+                        .await?
+                        .is_empty()
+            }
+            Some(_) => false,
+        };
+
+    // Get online status for the listed participants
+    let online_users = match online_only {
+        Some(online) => online,
+        None => {
+            let user_ids: Vec<String> = participants.iter().map(|p| p.user_id.clone()).collect();
+            state
+                .presence
+                .get_online_users(&user_ids)
+                .await
+                .unwrap_or_default()
+        }
+    };
+    let mut avatars = resolve_avatars(state.storage.as_ref(), &participants).await;
 
     // Build response with online status
     // For non-participants, hide contact details (email, phone)
-    let responses: Vec<ParticipantResponse> = participants
+    let mut responses: Vec<ParticipantEntry> = participants
         .into_iter()
         .map(|p| {
             let participant = if is_participant {
@@ -80,14 +290,191 @@ pub async fn list_participants(
                     ..p
                 }
             };
-            ParticipantResponse {
+            ParticipantEntry::Participant(ParticipantResponse {
                 is_online: online_users.contains(&participant.user_id),
+                avatar: avatars.remove(&participant.user_id),
                 participant,
-            }
+            })
         })
         .collect();
 
-    Ok(Json(ApiResponse { data: responses }))
+    // Invited by email and not activated yet: listed after the participants
+    let invites = if with_invites {
+        state.invites.list_by_dialog(dialog_id).await?
+    } else {
+        Vec::new()
+    };
+    let search = search.map(str::to_lowercase);
+    let invites = invites.into_iter().filter(|invite| {
+        search.as_ref().map_or(true, |s| {
+            invite.display_name.to_lowercase().contains(s)
+                || invite
+                    .company
+                    .as_ref()
+                    .is_some_and(|c| c.to_lowercase().contains(s))
+        })
+    });
+    responses.extend(invites.map(|invite| {
+        let mut invited = InvitedParticipantResponse::from(invite);
+        if !is_participant {
+            invited.email = None;
+            invited.phone = None;
+        }
+        ParticipantEntry::Invited(invited)
+    }));
+
+    if !by_company {
+        return Ok(Json(ApiResponse {
+            data: ParticipantList::Participants(responses),
+        }));
+    }
+
+    let last_sent = state.messages.last_sent_at_by_sender(dialog_id).await?;
+    Ok(Json(ApiResponse {
+        data: ParticipantList::Companies(group_by_company(responses, &last_sent)),
+    }))
+}
+
+/// Participants of many dialogs at once, e.g. for the avatars of a list of
+/// objects. Each dialog needs the access of [`list_participants`].
+pub async fn batch_get_participants(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    OptionalScopeConfig(scope_config): OptionalScopeConfig,
+    Json(req): Json<BatchGetParticipantsRequest>,
+) -> Result<Json<ApiResponse<BatchGetParticipantsResponse>>, ApiError> {
+    let mut dialog_ids = req.dialog_ids;
+    dialog_ids.sort_unstable();
+    dialog_ids.dedup();
+
+    if dialog_ids.is_empty() || dialog_ids.len() > MAX_BULK_DIALOGS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("dialog_ids must contain 1 to {} dialogs", MAX_BULK_DIALOGS),
+        ));
+    }
+
+    let no_scope = Vec::new();
+    let (level0, level1, level2) = match &scope_config {
+        Some(scope) => (
+            &scope.scope_level0,
+            &scope.scope_level1,
+            &scope.scope_level2,
+        ),
+        None => (&no_scope, &no_scope, &no_scope),
+    };
+    let accessible = state
+        .scopes
+        .filter_accessible(&dialog_ids, &user_id, level0, level1, level2)
+        .await?;
+    let skipped: Vec<Uuid> = dialog_ids
+        .into_iter()
+        .filter(|id| !accessible.contains(id))
+        .collect();
+
+    let mut rosters = state
+        .participants
+        .list_by_dialogs_batch(&accessible)
+        .await?;
+    let mut user_ids: Vec<String> = rosters
+        .values()
+        .flatten()
+        .map(|p| p.user_id.clone())
+        .collect();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    let online_users = state
+        .presence
+        .get_online_users(&user_ids)
+        .await
+        .unwrap_or_default();
+    // Avatars are per dialog, so they are keyed by dialog and user
+    let mut avatars = HashMap::new();
+    for participant in rosters.values().flatten() {
+        if let Some(urls) = avatar_urls(state.storage.as_ref(), participant).await {
+            avatars.insert(
+                (participant.dialog_id, participant.user_id.clone()),
+                urls.thumbnail().to_string(),
+            );
+        }
+    }
+
+    let dialogs = accessible
+        .into_iter()
+        .map(|dialog_id| DialogRoster {
+            dialog_id,
+            participants: rosters
+                .remove(&dialog_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| RosterParticipant {
+                    is_online: online_users.contains(&p.user_id),
+                    avatar_url: avatars.remove(&(dialog_id, p.user_id.clone())),
+                    user_id: p.user_id,
+                    display_name: p.display_name,
+                    company: p.company,
+                    joined_as: p.joined_as,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(ApiResponse {
+        data: BatchGetParticipantsResponse { dialogs, skipped },
+    }))
+}
+
+/// Group participants by company identifier, or by company name for those
+/// without one. Groups keep the order of their first participant; participants
+/// without a company come last. Invites count towards neither unread nor
+/// activity.
+fn group_by_company(
+    participants: Vec<ParticipantEntry>,
+    last_sent: &HashMap<String, DateTime<Utc>>,
+) -> Vec<CompanyGroup> {
+    let mut groups: Vec<CompanyGroup> = Vec::new();
+    let mut index: HashMap<(Option<String>, Option<String>), usize> = HashMap::new();
+    let mut unread: Vec<Option<i32>> = Vec::new();
+
+    for response in participants {
+        let key = match response.company_uid() {
+            Some(uid) => (Some(uid.clone()), None),
+            None => (None, response.company().cloned()),
+        };
+        let i = *index.entry(key.clone()).or_insert_with(|| {
+            groups.push(CompanyGroup {
+                company_uid: key.0,
+                company: None,
+                unread_count: 0,
+                last_activity_at: None,
+                participants: Vec::new(),
+            });
+            unread.push(None);
+            groups.len() - 1
+        });
+
+        let group = &mut groups[i];
+        if group.company.is_none() {
+            group.company = response.company().cloned();
+        }
+        if let ParticipantEntry::Participant(ParticipantResponse { participant: p, .. }) = &response
+        {
+            if !p.joined_as.is_observer() {
+                unread[i] = Some(unread[i].map_or(p.unread_count, |u| u.min(p.unread_count)));
+            }
+            if let Some(at) = last_sent.get(&p.user_id) {
+                group.last_activity_at = Some(group.last_activity_at.map_or(*at, |g| g.max(*at)));
+            }
+        }
+        group.participants.push(response);
+    }
+
+    for (group, unread) in groups.iter_mut().zip(unread) {
+        group.unread_count = unread.unwrap_or(0);
+    }
+    // Stable sort: only moves the group without a company to the end
+    groups.sort_by_key(|g| g.company_uid.is_none() && g.company.is_none());
+    groups
 }
 
 pub async fn mark_as_read(
@@ -97,7 +484,7 @@ pub async fn mark_as_read(
     Json(req): Json<MarkAsReadRequest>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
     // Check dialog exists
-    state
+    let dialog = state
         .dialogs
         .find_by_id(dialog_id)
         .await?
@@ -108,6 +495,17 @@ pub async fn mark_as_read(
         return Err(ApiError::Forbidden("Not a participant".into()));
     }
 
+    // Previous read pointer, only needed for message.read webhooks
+    let previous = if state.webhooks.read_receipts_enabled() {
+        state
+            .participants
+            .find(dialog_id, &user_id)
+            .await?
+            .and_then(|p| p.last_read_message_id)
+    } else {
+        None
+    };
+
     // Mark as read
     state
         .participants
@@ -123,7 +521,132 @@ pub async fn mark_as_read(
     )
     .await;
 
+    send_read_receipt(
+        &state,
+        &dialog,
+        &user_id,
+        previous,
+        req.last_read_message_id,
+    )
+    .await?;
+
     Ok(Json(serde_json::json!({
         "success": true
     })))
 }
+
+/// Send `message.read` if the endpoint opted in and the read pointer moved
+/// forward (message IDs are time-ordered)
+pub(crate) async fn send_read_receipt(
+    state: &AppState,
+    dialog: &Dialog,
+    user_id: &str,
+    previous: Option<Uuid>,
+    current: Uuid,
+) -> Result<(), ApiError> {
+    if !state.webhooks.read_receipts_enabled() || previous.is_some_and(|p| p >= current) {
+        return Ok(());
+    }
+    // The client may send an ID that isn't a message of this dialog
+    let Some(message) = state
+        .messages
+        .find_by_id_and_dialog(current, dialog.id)
+        .await?
+    else {
+        return Ok(());
+    };
+    state
+        .webhooks
+        .send_read_receipt(WebhookEvent::message_read(dialog, user_id, &message))
+        .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ParticipantProfile;
+
+    fn participant(
+        user_id: &str,
+        company_uid: Option<&str>,
+        company: Option<&str>,
+        unread_count: i32,
+    ) -> ParticipantEntry {
+        let mut p = DialogParticipant::new(Uuid::nil(), user_id, JoinedAs::Participant);
+        p.company_uid = company_uid.map(String::from);
+        p.company = company.map(String::from);
+        p.unread_count = unread_count;
+        ParticipantEntry::Participant(ParticipantResponse {
+            participant: p,
+            is_online: false,
+            avatar: None,
+        })
+    }
+
+    fn invited(email: &str, company_uid: Option<&str>) -> ParticipantEntry {
+        let profile = ParticipantProfile {
+            display_name: email.into(),
+            company: None,
+            company_uid: company_uid.map(String::from),
+            email: None,
+            phone: None,
+        };
+        let invite = ParticipantInvite::new(Uuid::nil(), email, JoinedAs::Participant, profile);
+        ParticipantEntry::Invited(invite.into())
+    }
+
+    #[test]
+    fn test_group_by_company() {
+        let now = Utc::now();
+        let earlier = now - chrono::Duration::minutes(5);
+        let last_sent = HashMap::from([("a1".to_string(), earlier), ("a2".to_string(), now)]);
+
+        let mut p = DialogParticipant::new(Uuid::nil(), "b2", JoinedAs::Observer);
+        p.company = Some("Beta".into());
+        let observer = ParticipantEntry::Participant(ParticipantResponse {
+            participant: p,
+            is_online: false,
+            avatar: None,
+        });
+        let groups = group_by_company(
+            vec![
+                participant("x", None, None, 1),
+                participant("a1", Some("acme"), Some("Acme Inc"), 7),
+                participant("b1", None, Some("Beta"), 4),
+                participant("a2", Some("acme"), Some("ACME"), 3),
+                observer,
+                invited("supplier@acme.example", Some("acme")),
+            ],
+            &last_sent,
+        );
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].company_uid.as_deref(), Some("acme"));
+        assert_eq!(groups[0].company.as_deref(), Some("Acme Inc"));
+        // The invite is listed but its unread count of 0 doesn't count
+        assert_eq!(groups[0].participants.len(), 3);
+        assert_eq!(groups[0].unread_count, 3);
+        assert_eq!(groups[0].last_activity_at, Some(now));
+
+        assert_eq!(groups[1].company.as_deref(), Some("Beta"));
+        assert_eq!(groups[1].unread_count, 4);
+        assert!(groups[1].last_activity_at.is_none());
+
+        // Participants without a company come last
+        assert!(groups[2].company_uid.is_none() && groups[2].company.is_none());
+        let ParticipantEntry::Participant(first) = &groups[2].participants[0] else {
+            panic!("expected a participant");
+        };
+        assert_eq!(first.participant.user_id, "x");
+    }
+
+    #[test]
+    fn test_invited_participant_serialization() {
+        let json = serde_json::to_value(invited(" Supplier@Example.com", None)).unwrap();
+        assert_eq!(json["status"], "invited");
+        assert_eq!(json["email"], "supplier@example.com");
+        assert_eq!(json["joined_as"], "participant");
+        assert!(json.get("user_id").is_none());
+    }
+}
@@ -0,0 +1,506 @@
+//! HTTP router of the service
+//!
+//! The binary serves exactly this router, so every handler and middleware
+//! of the library is exposed without separate wiring in `main.rs`.
+
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware as axum_middleware,
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::middleware;
+use crate::services::{IMPERSONATION_ROUTE_PREFIX, TRANSCRIPTS_ROUTE_PREFIX};
+
+use super::{
+    activity, avatars, dialogs, export, files, folders, health, impersonation, integrations,
+    management, messages, metrics, notes, participants, search, sync, tenants, transcripts, upload,
+    ws_handler, AppState,
+};
+
+/// Build the full application router: health and metrics, the Management
+/// and Chat APIs, WebSocket, signed links and the request-wide layers
+/// (rate limiting, request IDs, CORS).
+pub fn router(state: AppState) -> Router {
+    let cors_config = state.config.cors.clone();
+    tracing::info!(
+        "CORS configured: origins={}, methods={}, credentials={}",
+        cors_config.allowed_origins,
+        cors_config.allowed_methods,
+        cors_config.allow_credentials
+    );
+    let cors = cors_config.into_layer();
+
+    // Rate limiting
+    let rate_limit_config = &state.config.rate_limit;
+    let rate_limiter = rate_limit_config.create_limiter();
+    if rate_limiter.is_some() {
+        tracing::info!(
+            "Rate limiting enabled: {} req/s, burst {}",
+            rate_limit_config.requests_per_second,
+            rate_limit_config.burst_size
+        );
+    } else {
+        tracing::info!("Rate limiting disabled");
+    }
+
+    // Request body limits (structured 413 instead of extractor rejections)
+    let management_body_limit = state.config.body_limits.management_bytes;
+    let chat_settings = state.settings.clone();
+
+    // Management API routes (with admin auth middleware)
+    let management_routes = Router::new()
+        .route("/dialogs", post(management::management_create_dialog))
+        .route("/dialogs/search", post(management::management_find_dialog))
+        .route(
+            "/dialogs/{id}",
+            get(management::management_get_dialog)
+                .layer(axum_middleware::from_fn(middleware::etag))
+                .delete(management::management_delete_dialog),
+        )
+        .route(
+            "/dialogs/{id}/restore",
+            post(management::management_restore_dialog),
+        )
+        .route(
+            "/dialogs/{id}/participants",
+            post(management::management_add_participant),
+        )
+        .route(
+            "/dialogs/{id}/participants/{user_id}",
+            delete(management::management_remove_participant),
+        )
+        .route(
+            "/dialogs/{id}/invites",
+            get(management::management_list_invites)
+                .post(management::management_invite_participant),
+        )
+        .route(
+            "/dialogs/{id}/invites/{invite_id}",
+            delete(management::management_revoke_invite),
+        )
+        .route("/dialogs/{id}/bans", get(management::management_list_bans))
+        .route(
+            "/dialogs/{id}/bans/{user_id}",
+            put(management::management_ban_user).delete(management::management_unban_user),
+        )
+        .route(
+            "/invites/activate",
+            post(management::management_activate_invites),
+        )
+        .route(
+            "/participants/transfer",
+            post(management::management_transfer_participant),
+        )
+        .route(
+            "/dialogs/{id}/access-scopes",
+            put(management::management_update_access_scopes),
+        )
+        .route(
+            "/dialogs/{id}/storage",
+            get(management::management_get_dialog_storage)
+                .put(management::management_set_dialog_quota),
+        )
+        .route(
+            "/dialogs/{id}/transcript-links",
+            post(management::management_create_transcript_link),
+        )
+        .route(
+            "/dialogs/{id}/impersonations",
+            post(management::management_create_impersonation),
+        )
+        .route("/audit-log", get(management::management_list_audit_log))
+        .route(
+            "/tenants/{tenant}/storage",
+            get(management::management_get_tenant_storage)
+                .put(management::management_set_tenant_quota),
+        )
+        .route(
+            "/tenants/{tenant}/settings",
+            get(management::management_get_tenant_settings)
+                .put(management::management_set_tenant_settings)
+                .delete(management::management_delete_tenant_settings),
+        )
+        .route(
+            "/dialog-templates",
+            get(management::management_list_dialog_templates),
+        )
+        .route(
+            "/dialog-templates/{object_type}",
+            put(management::management_set_dialog_template)
+                .delete(management::management_delete_dialog_template),
+        )
+        .route(
+            "/sla-policies",
+            get(management::management_list_sla_policies),
+        )
+        .route("/export/{entity}", get(export::management_export))
+        .route(
+            "/sla-policies/{object_type}",
+            put(management::management_set_sla_policy)
+                .delete(management::management_delete_sla_policy),
+        )
+        .route("/connections", get(management::management_list_connections))
+        .route(
+            "/connections/{user_id}",
+            delete(management::management_disconnect_user),
+        )
+        .route(
+            "/webhooks/health",
+            get(management::management_get_webhook_health),
+        )
+        .route(
+            "/jobs/notifications",
+            get(management::management_get_notification_backlog),
+        )
+        .route(
+            "/jobs/notifications/skip",
+            post(management::management_skip_notifications),
+        )
+        .route(
+            "/jobs/dead-letters",
+            get(management::management_list_job_dead_letters),
+        )
+        .route("/config", get(management::management_get_config))
+        .route(
+            "/dialogs/{id}/system-events",
+            post(management::management_push_system_event),
+        )
+        .route(
+            "/dialogs/{id}/messages/import",
+            post(management::management_import_messages),
+        )
+        .route(
+            "/messages/by-external-id/{external_id}",
+            get(management::management_get_message_by_external_id),
+        )
+        .route(
+            "/dialogs/{id}/qa-pairs",
+            get(management::management_export_qa_pairs),
+        )
+        .route(
+            "/dialogs/{id}/locale",
+            put(management::management_update_dialog_locale),
+        )
+        .route(
+            "/dialogs/{id}/slow-mode",
+            put(management::management_update_slow_mode),
+        )
+        .route(
+            "/dialogs/{id}/notification-delay",
+            put(management::management_update_notification_delay),
+        )
+        .route("/dialogs/{id}/sla", put(management::management_update_sla))
+        .route(
+            "/dialogs/{id}/sanitize-profile",
+            put(management::management_update_sanitize_profile),
+        )
+        .route(
+            "/dialogs/{id}/feature-flags",
+            get(management::management_get_dialog_feature_flags),
+        )
+        .route(
+            "/feature-flags",
+            get(management::management_list_feature_flags),
+        )
+        .route(
+            "/feature-flags/{flag}/tenants/{tenant}",
+            put(management::management_set_tenant_feature_flag)
+                .delete(management::management_delete_tenant_feature_flag),
+        )
+        .route(
+            "/feature-flags/{flag}/dialogs/{dialog_id}",
+            put(management::management_set_dialog_feature_flag)
+                .delete(management::management_delete_dialog_feature_flag),
+        )
+        .route("/settings", get(management::management_list_settings))
+        .route(
+            "/settings/{key}",
+            put(management::management_set_setting).delete(management::management_reset_setting),
+        )
+        .layer(axum_middleware::from_fn(move |req, next| {
+            middleware::body_limit(req, next, management_body_limit)
+        }))
+        .layer(DefaultBodyLimit::disable())
+        .layer(axum_middleware::from_fn(middleware::admin_auth::admin_auth));
+
+    // Chat API routes (with optional JWT middleware)
+    let chat_routes = Router::new()
+        // Dialogs (polled by widgets, so answered with 304 when unchanged)
+        .route(
+            "/dialogs",
+            get(dialogs::list_dialogs).layer(axum_middleware::from_fn(middleware::etag)),
+        )
+        .route(
+            "/dialogs/{id}",
+            get(dialogs::get_dialog).layer(axum_middleware::from_fn(middleware::etag)),
+        )
+        .route(
+            "/dialogs/by-object/{object_type}/{object_id}",
+            get(dialogs::get_dialog_by_object),
+        )
+        .route(
+            "/dialogs/by-object/{object_type}/{object_id}/list",
+            get(dialogs::list_dialogs_by_object),
+        )
+        // Folders (saved dialog filters)
+        .route(
+            "/folders",
+            get(folders::list_folders).post(folders::create_folder),
+        )
+        .route("/folders/dialogs", get(folders::list_folder_dialogs))
+        .route(
+            "/folders/{id}",
+            put(folders::update_folder).delete(folders::delete_folder),
+        )
+        .route("/dialogs/bulk-actions", post(dialogs::bulk_dialog_action))
+        .route(
+            "/dialogs/participants:batchGet",
+            post(participants::batch_get_participants),
+        )
+        .route("/dialogs/{id}/join", post(dialogs::join_dialog))
+        .route("/dialogs/{id}/leave", post(dialogs::leave_dialog))
+        .route("/dialogs/{id}/archive", post(dialogs::archive_dialog))
+        .route("/dialogs/{id}/unarchive", post(dialogs::unarchive_dialog))
+        .route("/dialogs/{id}/pin", post(dialogs::pin_dialog))
+        .route("/dialogs/{id}/unpin", post(dialogs::unpin_dialog))
+        .route(
+            "/dialogs/{id}/notifications",
+            post(dialogs::set_dialog_notifications),
+        )
+        .route(
+            "/dialogs/{id}/snooze",
+            post(dialogs::snooze_dialog).delete(dialogs::unsnooze_dialog),
+        )
+        .route(
+            "/dialogs/{id}/notes",
+            get(notes::get_notes).put(notes::update_notes),
+        )
+        .route(
+            "/dialogs/{id}/notes/history",
+            get(notes::list_notes_history),
+        )
+        .route("/dialogs/{id}/read", post(participants::mark_as_read))
+        .route(
+            "/dialogs/{id}/participants",
+            get(participants::list_participants).layer(axum_middleware::from_fn(middleware::etag)),
+        )
+        .route(
+            "/dialogs/{id}/avatar/upload-url",
+            post(avatars::presign_avatar_upload),
+        )
+        .route(
+            "/dialogs/{id}/avatar",
+            put(avatars::set_avatar).delete(avatars::delete_avatar),
+        )
+        // Messages
+        .route(
+            "/dialogs/{dialog_id}/messages",
+            get(messages::list_messages).post(messages::send_message),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/batch",
+            post(messages::send_message_batch),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/calendar",
+            get(messages::message_calendar),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/{id}",
+            get(messages::get_message)
+                .layer(axum_middleware::from_fn(middleware::etag))
+                .put(messages::edit_message)
+                .delete(messages::delete_message),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/{id}/context",
+            get(messages::get_message_context),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/{id}/star",
+            post(messages::star_message).delete(messages::unstar_message),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/{id}/reactions",
+            get(messages::list_reactions),
+        )
+        .route(
+            "/dialogs/{dialog_id}/messages/{id}/reactions/{emoji}",
+            put(messages::add_reaction).delete(messages::remove_reaction),
+        )
+        .route(
+            "/messages/{id}/actions/{action_id}",
+            post(messages::click_message_action),
+        )
+        .route("/dialogs/{id}/sync", get(sync::sync_dialog))
+        .route("/dialogs/{id}/activity", get(activity::list_activity))
+        .route("/starred-messages", get(messages::list_starred_messages))
+        .route("/search", get(search::search))
+        // Upload API
+        .route(
+            "/tenants/{tenant_uid}/widget-config",
+            get(tenants::get_widget_config),
+        )
+        .route("/upload/presign", post(upload::presign_upload))
+        .route("/attachments/{id}/url", get(upload::get_attachment_url))
+        // Chat API bodies are sized for the largest valid message
+        .layer(axum_middleware::from_fn(move |req, next| {
+            let limit = middleware::chat_body_limit(chat_settings.current().max_message_length);
+            middleware::body_limit(req, next, limit)
+        }))
+        .layer(DefaultBodyLimit::disable());
+    // Tenant context for the row-level security policies, set once the
+    // request is authenticated
+    let chat_routes = if state.config.database.row_level_security {
+        chat_routes.layer(axum_middleware::from_fn(middleware::tenant_context))
+    } else {
+        chat_routes
+    };
+    // Apply JWT middleware to all Chat API routes (when enabled)
+    let chat_routes = chat_routes.layer(axum_middleware::from_fn(middleware::jwt_auth::jwt_auth));
+
+    let mut api = Router::new()
+        // Management API (admin auth)
+        .nest("/api/v1/management", management_routes)
+        // Chat API (JWT auth when enabled)
+        .nest("/api/v1", chat_routes)
+        // WebSocket (JWT validated in handler)
+        .route("/api/v1/ws", get(ws_handler::ws_handler))
+        // Inbound integration events (signed with the webhook secret)
+        .route(
+            "/api/v1/integrations/events",
+            post(integrations::receive_event),
+        )
+        // Read-only transcripts (signed links)
+        .route(
+            &format!("{}/{{id}}", TRANSCRIPTS_ROUTE_PREFIX),
+            get(transcripts::get_transcript),
+        )
+        // Read-only support views (impersonation tokens)
+        .route(
+            &format!("{}/dialog", IMPERSONATION_ROUTE_PREFIX),
+            get(impersonation::get_dialog),
+        )
+        .route(
+            &format!("{}/messages", IMPERSONATION_ROUTE_PREFIX),
+            get(impersonation::list_messages),
+        );
+
+    // Signed file URLs for the filesystem storage backend
+    if let Some(fs) = state.fs_storage.clone() {
+        api = api.merge(files::router(fs));
+    }
+
+    // Health and metrics are also served at the root, for probes and
+    // scrapers that reach the instance directly instead of via the gateway
+    let probes = Router::new()
+        .route("/health", get(health::health))
+        .route("/health/ready", get(health::health_ready))
+        .route("/metrics", get(metrics::metrics));
+    let base_path = state.config.server.base_path();
+    let app = if base_path.is_empty() {
+        probes.merge(api)
+    } else {
+        tracing::info!("Serving the API under {}", base_path);
+        probes.clone().nest(&base_path, api.merge(probes))
+    };
+
+    let app = app
+        .layer(axum_middleware::from_fn(move |req, next| {
+            middleware::rate_limit(req, next, rate_limiter.clone())
+        }))
+        .layer(axum_middleware::from_fn(middleware::request_id))
+        .layer(cors);
+    // Everything outside the Chat API's tenant context is service work for
+    // the row-level security policies
+    let app = if state.config.database.row_level_security {
+        app.layer(axum_middleware::from_fn(middleware::service_context))
+    } else {
+        app
+    };
+    app.with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    use crate::config::AppConfig;
+    use crate::jobs::JobProducer;
+    use crate::repositories::SettingsRepository;
+    use crate::services::{
+        ConnectionRegistry, PresenceService, RuntimeSettings, S3Service, SettingsService,
+        SlowModeLimiter, UploadLimiter,
+    };
+    use crate::webhooks::WebhookSender;
+
+    fn test_state(config: AppConfig) -> AppState {
+        let db = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/mtchat_unused")
+            .unwrap();
+        let settings = Arc::new(SettingsService::new(
+            SettingsRepository::new(db.clone()),
+            RuntimeSettings::default(),
+            None,
+        ));
+        AppState::new(
+            db,
+            WebhookSender::noop(),
+            Arc::new(S3Service::noop()),
+            PresenceService::noop(),
+            UploadLimiter::noop(),
+            SlowModeLimiter::noop(),
+            settings,
+            Arc::new(config),
+            JobProducer::noop(),
+            Arc::new(ConnectionRegistry::local()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_router_serves_library_routes() {
+        let app = router(test_state(AppConfig::default()));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("x-request-id"));
+
+        let response = app
+            .oneshot(Request::get("/api/v1/unknown").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn status(app: &Router, uri: &str) -> StatusCode {
+        app.clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_router_under_base_path() {
+        let mut config = AppConfig::default();
+        config.server.base_path = "/chat/".into();
+        let app = router(test_state(config));
+
+        assert_eq!(status(&app, "/metrics").await, StatusCode::OK);
+        assert_eq!(status(&app, "/chat/metrics").await, StatusCode::OK);
+        // The WebSocket route rejects plain GETs, but exists only under the base path
+        assert_ne!(status(&app, "/chat/api/v1/ws").await, StatusCode::NOT_FOUND);
+        assert_eq!(status(&app, "/api/v1/ws").await, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,137 @@
+//! Search across the dialogs of the current user.
+//!
+//! `scope=messages` (default) matches message content, `scope=attachments`
+//! matches attachment filenames and returns each attachment with its parent
+//! message for context. Matching is a case-insensitive substring search
+//! backed by trigram indexes.
+
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{AttachmentResponse, Message};
+use crate::middleware::UserId;
+
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
+
+/// Shortest accepted search query (trigram indexes need at least this much)
+pub const MIN_SEARCH_QUERY_CHARS: usize = 2;
+
+/// What to search in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchScope {
+    #[default]
+    Messages,
+    Attachments,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    #[serde(default)]
+    pub scope: SearchScope,
+    /// Only search in this dialog
+    pub dialog_id: Option<Uuid>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// ID of the last result of the previous page (message ID, or attachment
+    /// ID for `scope=attachments`)
+    pub before: Option<Uuid>,
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    /// Matching message, or the parent message of the matching attachment
+    pub message: Message,
+    /// Matching attachment (`scope=attachments`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<AttachmentResponse>,
+}
+
+/// Search messages or attachments in the dialogs the user participates in
+pub async fn search(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<SearchQuery>,
+) -> Result<(HeaderMap, Json<ApiResponse<Vec<SearchHit>>>), ApiError> {
+    let q = query.q.trim();
+    if q.chars().count() < MIN_SEARCH_QUERY_CHARS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidInput,
+            format!("q must be at least {} characters", MIN_SEARCH_QUERY_CHARS),
+        ));
+    }
+    let limit = query.limit.clamp(1, 100);
+
+    let hits = match query.scope {
+        SearchScope::Messages => state
+            .messages
+            .search(&user_id, q, query.dialog_id, limit, query.before)
+            .await?
+            .into_iter()
+            .map(|message| SearchHit {
+                message,
+                attachment: None,
+            })
+            .collect(),
+        SearchScope::Attachments => {
+            let attachments = state
+                .attachments
+                .search(&user_id, q, query.dialog_id, limit, query.before)
+                .await?;
+
+            let message_ids: Vec<Uuid> = attachments.iter().map(|a| a.message_id).collect();
+            let messages: HashMap<Uuid, Message> = state
+                .messages
+                .find_many(&message_ids)
+                .await?
+                .into_iter()
+                .map(|m| (m.id, m))
+                .collect();
+
+            let keys: Vec<&str> = attachments
+                .iter()
+                .flat_map(|a| {
+                    std::iter::once(a.s3_key.as_str()).chain(a.thumbnail_s3_key.as_deref())
+                })
+                .collect();
+            let urls = if state.storage.is_configured() && !keys.is_empty() {
+                state.storage.generate_download_urls_batch(&keys).await
+            } else {
+                HashMap::new()
+            };
+
+            attachments
+                .iter()
+                .filter_map(|att| {
+                    // Several attachments can share a parent message
+                    let message = messages.get(&att.message_id)?.clone();
+                    let url = urls.get(&att.s3_key).cloned();
+                    let thumbnail_url = att
+                        .thumbnail_s3_key
+                        .as_ref()
+                        .and_then(|key| urls.get(key).cloned());
+                    Some(SearchHit {
+                        message,
+                        attachment: Some(AttachmentResponse::from_attachment(
+                            att,
+                            url,
+                            thumbnail_url,
+                        )),
+                    })
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let headers = super::attachment_url_headers(hits.iter().filter_map(|h| h.attachment.as_ref()));
+    Ok((headers, Json(ApiResponse { data: hits })))
+}
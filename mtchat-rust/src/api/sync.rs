@@ -0,0 +1,107 @@
+//! Delta sync: changes of a dialog after a cursor
+
+use axum::extract::{Path, Query, State};
+use axum::response::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{DialogEvent, Message, MAX_SYNC_EVENTS};
+use crate::middleware::UserId;
+
+use super::{ApiError, ApiResponse, AppState};
+
+// ============ DTOs ============
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// Last event seq the client has seen (0 = from the beginning)
+    #[serde(default)]
+    pub since_seq: i64,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncEvent {
+    #[serde(flatten)]
+    pub event: DialogEvent,
+    /// Current message state for `message.new`/`message.edited`;
+    /// absent if the message was deleted since
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<Message>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    pub events: Vec<SyncEvent>,
+    /// Cursor for the next request
+    pub last_seq: i64,
+    /// Whether more events are available after `last_seq`
+    pub has_more: bool,
+}
+
+// ============ Handlers ============
+
+pub async fn sync_dialog(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(dialog_id): Path<Uuid>,
+    Query(query): Query<SyncQuery>,
+) -> Result<Json<ApiResponse<SyncResponse>>, ApiError> {
+    if !state.participants.exists(dialog_id, &user_id).await? {
+        return Err(ApiError::Forbidden(
+            "Not a participant. Join the dialog first.".into(),
+        ));
+    }
+
+    let limit = query.limit.clamp(1, MAX_SYNC_EVENTS);
+    let mut events = state
+        .dialog_events
+        .list_since(dialog_id, query.since_seq.max(0), limit + 1)
+        .await?;
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+
+    let message_ids: Vec<Uuid> = events
+        .iter()
+        .filter(|e| e.has_message())
+        .filter_map(|e| e.message_id)
+        .collect();
+    let messages: HashMap<Uuid, Message> = if message_ids.is_empty() {
+        HashMap::new()
+    } else {
+        state
+            .messages
+            .find_by_ids(dialog_id, &message_ids)
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect()
+    };
+
+    let last_seq = events.last().map_or(query.since_seq, |e| e.seq);
+    let events = events
+        .into_iter()
+        .map(|event| {
+            let message = if event.has_message() {
+                event.message_id.and_then(|id| messages.get(&id).cloned())
+            } else {
+                None
+            };
+            SyncEvent { event, message }
+        })
+        .collect();
+
+    Ok(Json(ApiResponse {
+        data: SyncResponse {
+            events,
+            last_seq,
+            has_more,
+        },
+    }))
+}
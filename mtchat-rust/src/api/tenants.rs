@@ -0,0 +1,66 @@
+use axum::extract::{Path, State};
+use axum::response::Json;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::domain::{SanitizeProfile, TenantSettings};
+use crate::middleware::OptionalScopeConfig;
+
+use super::{ApiError, ApiResponse, AppState, ErrorCode};
+
+// ============ DTOs ============
+
+/// Everything the embedded widget needs about a tenant
+#[derive(Debug, Serialize)]
+pub struct WidgetConfigResponse {
+    pub tenant_uid: String,
+    pub branding: serde_json::Value,
+    pub behavior: serde_json::Value,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    /// HTML sanitization profile of the tenant's dialogs (tenant or global);
+    /// a dialog's own profile is returned with the dialog
+    pub sanitize_profile: SanitizeProfile,
+    /// Effective feature flags of the tenant (global values with tenant overrides)
+    pub features: BTreeMap<String, bool>,
+}
+
+// ============ Handlers ============
+
+/// Widget configuration of a tenant. Unconfigured tenants get empty settings.
+pub async fn get_widget_config(
+    State(state): State<AppState>,
+    OptionalScopeConfig(scope_config): OptionalScopeConfig,
+    Path(tenant_uid): Path<String>,
+) -> Result<Json<ApiResponse<WidgetConfigResponse>>, ApiError> {
+    // Users bound to tenants only see their own configuration
+    if let Some(scope) = &scope_config {
+        if !scope.scope_level0.is_empty() && !scope.scope_level0.contains(&tenant_uid) {
+            return Err(ApiError::new(
+                ErrorCode::ScopeMismatch,
+                "No access to this tenant",
+            ));
+        }
+    }
+
+    let settings = state
+        .tenant_settings
+        .find(&tenant_uid)
+        .await?
+        .unwrap_or_else(|| TenantSettings::empty(&tenant_uid));
+    let features = state.feature_flags.flags_for_tenant(&tenant_uid).await?;
+
+    Ok(Json(ApiResponse {
+        data: WidgetConfigResponse {
+            tenant_uid: settings.tenant_uid,
+            branding: settings.branding,
+            behavior: settings.behavior,
+            locale: settings.locale,
+            timezone: settings.timezone,
+            sanitize_profile: settings
+                .sanitize_profile
+                .unwrap_or(state.settings.current().sanitize_profile),
+            features,
+        },
+    }))
+}
@@ -0,0 +1,88 @@
+//! Public read-only dialog transcripts.
+//!
+//! Access is granted by the signed `expires`/`signature` query parameters of
+//! links issued through the Management API
+//! (`POST /api/v1/management/dialogs/{id}/transcript-links`), so auditors can
+//! open them without a chat account.
+
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Json, Response};
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::services::{Transcript, TranscriptFormat, MAX_TRANSCRIPT_MESSAGES};
+
+use super::{ApiError, AppState, ErrorCode};
+
+#[derive(Debug, Deserialize)]
+pub struct TranscriptQuery {
+    pub expires: i64,
+    pub signature: String,
+    #[serde(default)]
+    pub recipient: String,
+    #[serde(default)]
+    pub format: TranscriptFormat,
+}
+
+pub async fn get_transcript(
+    State(state): State<AppState>,
+    Path(dialog_id): Path<Uuid>,
+    Query(query): Query<TranscriptQuery>,
+) -> Result<Response, ApiError> {
+    if !state
+        .transcripts
+        .verify(dialog_id, &query.recipient, query.expires, &query.signature)
+    {
+        return Err(ApiError::Forbidden(
+            "Invalid or expired transcript link".into(),
+        ));
+    }
+
+    let dialog = state
+        .dialogs
+        .find_by_id(dialog_id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
+
+    let messages = state
+        .messages
+        .list_chronological(dialog_id, MAX_TRANSCRIPT_MESSAGES)
+        .await?;
+    let message_ids: Vec<Uuid> = messages.iter().map(|m| m.id).collect();
+    let attachments = state.attachments.list_by_messages(&message_ids).await?;
+
+    let names: HashMap<String, String> = state
+        .participants
+        .list_by_dialog(dialog_id)
+        .await?
+        .into_iter()
+        .filter_map(|p| p.display_name.map(|name| (p.user_id, name)))
+        .collect();
+
+    let recipient = Some(query.recipient).filter(|r| !r.is_empty());
+    let expires_at = chrono::DateTime::from_timestamp(query.expires, 0).unwrap_or_default();
+    let transcript = Transcript::build(
+        &dialog,
+        messages,
+        &attachments,
+        &names,
+        recipient,
+        expires_at,
+    );
+
+    let headers = [
+        (header::CACHE_CONTROL, "private, no-store"),
+        (header::HeaderName::from_static("x-robots-tag"), "noindex"),
+    ];
+    Ok(match query.format {
+        TranscriptFormat::Html => (
+            headers,
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            transcript.render_html(),
+        )
+            .into_response(),
+        TranscriptFormat::Json => (headers, Json(transcript)).into_response(),
+    })
+}
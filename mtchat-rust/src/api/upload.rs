@@ -1,10 +1,13 @@
 use axum::extract::{Path, State};
 use axum::response::Json;
 use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
 use uuid::Uuid;
 
-use crate::domain;
+use crate::domain::{self, StorageUsage};
 use crate::middleware::UserId;
+use crate::repositories::StorageUsageRepository;
+use crate::services::UploadLimitError;
 
 use super::{ApiError, ApiResponse, AppState, ErrorCode};
 
@@ -25,15 +28,82 @@ pub struct PresignUploadResponse {
     pub expires_in: u64,
 }
 
+// ============ Helpers ============
+
+/// Reject `additional` bytes if they would exceed the quota of the dialog or
+/// any tenant it belongs to.
+///
+/// Only a precheck (uploads are not stored yet): the bytes are counted by
+/// [`reserve_storage`] when the attachments are saved.
+pub(crate) async fn check_storage_quota(
+    state: &AppState,
+    dialog_id: Uuid,
+    additional: i64,
+) -> Result<(), ApiError> {
+    let usages = state.storage_usage.find_for_dialog(dialog_id).await?;
+    match quota_exceeded(state, &usages, additional) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Count `additional` bytes of new attachments towards the dialog and its
+/// tenants inside the transaction storing them, rejecting them if a quota
+/// would be exceeded.
+///
+/// The usage rows stay locked until the transaction ends, so concurrent
+/// messages cannot both fit into the last free bytes, and a rolled back
+/// message releases its bytes.
+pub(crate) async fn reserve_storage(
+    state: &AppState,
+    conn: &mut PgConnection,
+    dialog_id: Uuid,
+    additional: i64,
+) -> Result<(), ApiError> {
+    if additional <= 0 {
+        return Ok(());
+    }
+
+    let usages = StorageUsageRepository::lock_for_dialog(conn, dialog_id).await?;
+    if let Some(err) = quota_exceeded(state, &usages, additional) {
+        return Err(err);
+    }
+    StorageUsageRepository::add_dialog_bytes(conn, dialog_id, additional).await?;
+    Ok(())
+}
+
+fn quota_exceeded(state: &AppState, usages: &[StorageUsage], additional: i64) -> Option<ApiError> {
+    let usage = state
+        .config
+        .storage_quotas
+        .find_exceeded(usages, additional)?;
+    let quota = state
+        .config
+        .storage_quotas
+        .effective_quota(usage)
+        .unwrap_or_default();
+    Some(ApiError::new(
+        ErrorCode::StorageQuotaExceeded,
+        format!(
+            "Storage quota exceeded for {} '{}': {} of {} bytes used, {} more requested",
+            usage.scope_type.as_str(),
+            usage.scope_id,
+            usage.used_bytes,
+            quota,
+            additional
+        ),
+    ))
+}
+
 // ============ Handlers ============
 
 pub async fn presign_upload(
     State(state): State<AppState>,
-    UserId(_user_id): UserId,
+    UserId(user_id): UserId,
     Json(req): Json<PresignUploadRequest>,
 ) -> Result<Json<ApiResponse<PresignUploadResponse>>, ApiError> {
-    // Check S3 is configured
-    if !state.s3.is_configured() {
+    // Check storage is configured
+    if !state.storage.is_configured() {
         return Err(ApiError::Internal("File uploads are not configured".into()));
     }
 
@@ -67,6 +137,40 @@ pub async fn presign_upload(
         .await?
         .ok_or_else(|| ApiError::new(ErrorCode::DialogNotFound, "Dialog not found"))?;
 
+    // Only participants can upload into a dialog
+    if !state.participants.exists(req.dialog_id, &user_id).await? {
+        return Err(ApiError::new(
+            ErrorCode::NotParticipant,
+            "Not a participant",
+        ));
+    }
+
+    // Enforce per-user hourly upload limits; without Redis the URL is only
+    // issued if `upload_limits.fail_open` allows it
+    match state
+        .upload_limiter
+        .check_and_record(&user_id, req.size)
+        .await
+    {
+        Ok(()) => {}
+        Err(UploadLimitError::Redis(e)) if state.upload_limiter.fails_open() => {
+            tracing::warn!(user_id = %user_id, error = %e, "Upload limit check failed, allowing upload");
+        }
+        Err(UploadLimitError::Redis(e)) => {
+            tracing::error!(user_id = %user_id, error = %e, "Upload limit check failed");
+            return Err(ApiError::new(
+                ErrorCode::UploadLimitUnavailable,
+                "Upload limits can't be checked right now",
+            ));
+        }
+        Err(e) => {
+            return Err(ApiError::new(ErrorCode::UploadLimitExceeded, e.to_string()));
+        }
+    }
+
+    // Reject uploads that would exceed dialog or tenant storage quotas
+    check_storage_quota(&state, req.dialog_id, req.size).await?;
+
     // Generate S3 key
     // Format: dialogs/{dialog_id}/{uuid}.{ext}
     let ext = req.filename.rsplit('.').next().unwrap_or("bin");
@@ -75,8 +179,8 @@ pub async fn presign_upload(
 
     // Generate presigned URL
     let upload_url = state
-        .s3
-        .generate_upload_url(&s3_key, &req.content_type)
+        .storage
+        .generate_upload_url(&s3_key, &req.content_type, req.size)
         .await
         .map_err(|e| ApiError::Internal(e.to_string()))?;
 
@@ -84,7 +188,7 @@ pub async fn presign_upload(
         data: PresignUploadResponse {
             upload_url,
             s3_key,
-            expires_in: 300, // 5 minutes
+            expires_in: state.storage.upload_expiry_secs(),
         },
     }))
 }
@@ -93,8 +197,8 @@ pub async fn get_attachment_url(
     State(state): State<AppState>,
     Path(attachment_id): Path<Uuid>,
 ) -> Result<Json<ApiResponse<serde_json::Value>>, ApiError> {
-    // Check S3 is configured
-    if !state.s3.is_configured() {
+    // Check storage is configured
+    if !state.storage.is_configured() {
         return Err(ApiError::Internal("File storage is not configured".into()));
     }
 
@@ -105,31 +209,25 @@ pub async fn get_attachment_url(
         .await?
         .ok_or_else(|| ApiError::new(ErrorCode::AttachmentNotFound, "Attachment not found"))?;
 
-    // Generate presigned download URL
+    // Generate download URL (presigned, or CDN URL in public-read mode)
     let url = state
-        .s3
+        .storage
         .generate_download_url(&attachment.s3_key)
-        .await
-        .map_err(|e| ApiError::Internal(e.to_string()))?;
+        .await?;
 
     // Also get thumbnail URL if available
     let thumbnail_url = if let Some(ref thumb_key) = attachment.thumbnail_s3_key {
-        Some(
-            state
-                .s3
-                .generate_download_url(thumb_key)
-                .await
-                .map_err(|e| ApiError::Internal(e.to_string()))?,
-        )
+        Some(state.storage.generate_download_url(thumb_key).await?)
     } else {
         None
     };
 
+    // Public-read (CDN) URLs don't expire
     Ok(Json(ApiResponse {
         data: serde_json::json!({
             "url": url,
             "thumbnail_url": thumbnail_url,
-            "expires_in": 3600
+            "expires_in": state.storage.download_expiry_secs()
         }),
     }))
 }
@@ -1,14 +1,13 @@
 use axum::extract::{Query, State, WebSocketUpgrade};
-use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use jsonwebtoken::decode;
 use std::collections::HashMap;
 
 use crate::config::JwtConfig;
-use crate::middleware::JwtClaims;
+use crate::middleware::as_service;
+use crate::middleware::jwt_auth::token_user_id;
 use crate::ws;
 
-use super::AppState;
+use super::{ApiError, AppState, AuthChallenge};
 
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
@@ -18,56 +17,47 @@ pub async fn ws_handler(
     // Extract user_id - from JWT token if enabled, otherwise from query param
     let user_id = match extract_user_id(&params) {
         Ok(id) => id,
-        Err(response) => return response,
+        Err(e) => return e.into_response(),
     };
 
+    // Initial read state, so the client needs no REST calls for unread counts
+    let sync_state = params.get("sync").is_some_and(|v| v == "true" || v == "1");
+
+    // The connection checks dialog access itself, as service work
     ws.on_upgrade(move |socket| {
-        ws::handle_socket(
+        as_service(ws::handle_socket(
             socket,
-            state.connections,
+            state.ws_registry,
             user_id,
             state.presence,
             state.participants,
-        )
+            state.dialogs,
+            sync_state,
+        ))
     })
     .into_response()
 }
 
 /// Extract user_id from JWT token (if enabled) or query parameter
-#[allow(clippy::result_large_err)]
-fn extract_user_id(params: &HashMap<String, String>) -> Result<String, Response> {
+fn extract_user_id(params: &HashMap<String, String>) -> Result<String, ApiError> {
     // If JWT auth is enabled, validate token and extract user_id from claims
     if let Some(config) = JwtConfig::get() {
         let token = params.get("token").ok_or_else(|| {
-            (StatusCode::UNAUTHORIZED, "token query parameter required").into_response()
+            ApiError::unauthorized(AuthChallenge::CHAT, "token query parameter required")
         })?;
-
-        let token_data = decode::<JwtClaims>(token, &config.decoding_key, &config.validation)
-            .map_err(|e| {
-                tracing::debug!("WebSocket JWT validation failed: {}", e);
-                (StatusCode::UNAUTHORIZED, "Invalid token").into_response()
-            })?;
-
-        return token_data
-            .claims
-            .user_id(&config.user_id_claim)
-            .ok_or_else(|| {
-                tracing::debug!(
-                    "WebSocket JWT validation failed: missing claim '{}'",
-                    config.user_id_claim
-                );
-                (StatusCode::UNAUTHORIZED, "Invalid token").into_response()
-            });
+        return token_user_id(token, config);
     }
 
     // JWT disabled - use user_id query parameter
-    let user_id = params.get("user_id").ok_or_else(|| {
-        (StatusCode::BAD_REQUEST, "Missing user_id query parameter").into_response()
-    })?;
-
-    if user_id.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "user_id cannot be empty").into_response());
+    match params.get("user_id") {
+        Some(user_id) if !user_id.is_empty() => Ok(user_id.clone()),
+        Some(_) => Err(ApiError::unauthorized(
+            AuthChallenge::CHAT,
+            "user_id cannot be empty",
+        )),
+        None => Err(ApiError::unauthorized(
+            AuthChallenge::CHAT,
+            "Missing user_id query parameter",
+        )),
     }
-
-    Ok(user_id.clone())
 }
@@ -0,0 +1,462 @@
+//! Service assembly
+//!
+//! Builds the services, the application state and the router from the
+//! configuration. The binary uses this as is; host Rust services use it to
+//! mount the chat in their own axum application:
+//!
+//! ```ignore
+//! let (chat, state) = multitenancy_chat_api::build_router(config).await?;
+//! multitenancy_chat_api::spawn_background(&state).await?;
+//! let app = Router::new().merge(chat).layer(host_layers);
+//! ```
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use apalis_redis::RedisStorage;
+use axum::Router;
+use fred::prelude::*;
+use fred::types::Builder;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgConnection, PgPool};
+
+use crate::api::{self, AppState};
+use crate::config::{AppConfig, BrokerBackend, HealthConfig, JwtConfig, StorageBackend};
+use crate::domain::IdGenerator;
+use crate::events;
+use crate::jobs::{
+    run_workers, start_workers, AttachmentCleanupJob, JobContext, JobProducer, NotificationJob,
+    TextExtractJob, ThumbnailJob, WorkerError,
+};
+use crate::middleware;
+use crate::migrate;
+use crate::repositories::{FeatureFlagRepository, SettingsRepository};
+use crate::services::{
+    BlobStorage, Broker, BrokerError, ConnectionRegistry, EventStream, FsStorage, GuardedStorage,
+    PgBroker, PresenceService, RedisBroker, RuntimeSettings, S3Service, SettingsService,
+    SlowModeLimiter, Subscription, UploadLimiter, DISCONNECT_CHANNEL, SETTINGS_CHANNEL,
+};
+use crate::webhooks::WebhookSender;
+
+/// Errors while building the service
+#[derive(Debug, thiserror::Error)]
+pub enum StartupError {
+    #[error("Failed to connect to database: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to run migrations: {0}")]
+    Migrations(#[from] sqlx::migrate::MigrateError),
+    #[error("Failed to set up webhooks: {0}")]
+    Webhooks(String),
+    #[error("Failed to connect to the event stream: {0}")]
+    EventStream(String),
+    #[error("Failed to initialize file storage: {0}")]
+    Storage(String),
+    #[error("Failed to connect to Redis: {0}")]
+    Redis(String),
+    #[error("Failed to start job workers: {0}")]
+    Workers(#[from] WorkerError),
+}
+
+/// Connect to the configured database and build the router and state.
+///
+/// The routes are mounted under `server.base_path`: serve the router as is
+/// or merge it into a host router. Background work starts with
+/// [`spawn_background`].
+pub async fn build_router(config: Arc<AppConfig>) -> Result<(Router, AppState), StartupError> {
+    let db_config = &config.database;
+    tracing::info!(
+        "Connecting to database (pool: {}-{} connections)...",
+        db_config.min_connections,
+        db_config.max_connections
+    );
+    let mut connect_options = PgConnectOptions::from_str(&db_config.url)?;
+    // Every statement a request runs is bounded, so a slow query cannot hold
+    // a pool connection indefinitely
+    if let Some(timeout) = db_config.statement_timeout_setting() {
+        connect_options = connect_options.options([("statement_timeout", timeout)]);
+    }
+    let mut pool_options = PgPoolOptions::new()
+        .max_connections(db_config.max_connections)
+        .min_connections(db_config.min_connections)
+        .acquire_timeout(db_config.acquire_timeout)
+        .idle_timeout(Some(db_config.idle_timeout))
+        .max_lifetime(Some(db_config.max_lifetime));
+    if db_config.row_level_security {
+        tracing::info!("Row-level security: Chat API connections restricted to the tenant context");
+        pool_options = middleware::with_tenant_context(pool_options);
+    }
+    let db = pool_options.connect_with(connect_options).await?;
+
+    build_router_with_db(config, db).await
+}
+
+/// Same as [`build_router`] on a pool owned by the host, e.g. to share its
+/// connection limit. `database` settings other than the pool are ignored.
+pub async fn build_router_with_db(
+    config: Arc<AppConfig>,
+    db: PgPool,
+) -> Result<(Router, AppState), StartupError> {
+    // Startup is service work for the row-level security policies
+    middleware::as_service(build(config, db)).await
+}
+
+async fn build(config: Arc<AppConfig>, db: PgPool) -> Result<(Router, AppState), StartupError> {
+    // Process-wide settings read by the middleware
+    middleware::init_admin_token(
+        config.admin.api_token.as_deref(),
+        config.admin.previous_api_token.as_deref(),
+    );
+    JwtConfig::init(&config.jwt);
+    HealthConfig::init(config.health.clone());
+    IdGenerator::init(&config.ids);
+
+    let mut conn = db.acquire().await?;
+    if config.database.run_migrations {
+        migrate::run(&mut conn).await?;
+    } else {
+        // Applied by a separate `migrate` run; readiness fails until then
+        let status = migrate::status(&mut conn).await?;
+        if status.is_current() {
+            tracing::info!("Skipping migrations ({})", status);
+        } else {
+            tracing::warn!("Skipping migrations, {}", status);
+        }
+    }
+    sync_row_level_security(&mut conn, config.database.row_level_security).await?;
+    drop(conn);
+
+    // Initialize webhook sender
+    let mut webhooks = if config.webhooks.is_configured() {
+        tracing::info!("Webhooks enabled, sending to: {}", config.webhooks.url);
+        WebhookSender::new(config.webhooks.clone()).map_err(StartupError::Webhooks)?
+    } else {
+        if config.webhooks.url.is_empty() != config.webhooks.secret.is_empty() {
+            tracing::warn!(
+                "Webhooks disabled: WEBHOOK_URL and WEBHOOK_SECRET must be set together"
+            );
+        } else {
+            tracing::info!("Webhooks disabled (WEBHOOK_URL or WEBHOOK_SECRET not set)");
+        }
+        WebhookSender::noop()
+    };
+
+    // Optional event stream (Kafka/NATS), fed with the same events
+    if config.event_stream.is_enabled() {
+        let stream = EventStream::connect(&config.event_stream)
+            .await
+            .map_err(|e| StartupError::EventStream(e.to_string()))?;
+        webhooks = webhooks.with_event_stream(stream);
+    }
+
+    let (storage, fs_storage) = build_storage(&config).await?;
+
+    // Initialize Redis, presence service, and job queue
+    let (redis_pool, presence, upload_limiter, slow_mode, jobs) = match config.redis.url() {
+        Some(url) => {
+            tracing::info!("Connecting to Redis...");
+            let redis_pool = Arc::new(connect_redis(url).await?);
+            tracing::info!("Redis connected, presence tracking enabled");
+
+            let jobs = job_producer(url, redis_pool.clone()).await?;
+            tracing::info!("Job queue enabled");
+
+            (
+                Some(redis_pool.clone()),
+                Some(PresenceService::new(redis_pool.clone())),
+                UploadLimiter::new(redis_pool.clone(), config.upload_limits.clone()),
+                SlowModeLimiter::new(redis_pool),
+                jobs,
+            )
+        }
+        None => {
+            tracing::info!(
+                "Redis disabled (REDIS_URL not set), upload limits, slow mode and job queue disabled, presence tracked per instance"
+            );
+            (
+                None,
+                None,
+                UploadLimiter::noop(),
+                SlowModeLimiter::noop(),
+                JobProducer::noop(),
+            )
+        }
+    };
+
+    // Cross-instance pub/sub: Redis, or Postgres LISTEN/NOTIFY without Redis
+    let broker: Option<Arc<dyn Broker>> = match config.broker.backend {
+        BrokerBackend::Redis => match (&redis_pool, config.redis.url()) {
+            (Some(pool), Some(url)) => Some(Arc::new(RedisBroker::new(pool.clone(), url))),
+            _ => None,
+        },
+        BrokerBackend::Postgres => Some(Arc::new(PgBroker::new(db.clone()))),
+    };
+    match &broker {
+        Some(broker) => tracing::info!("Cross-instance pub/sub via {}", broker.name()),
+        None => tracing::warn!(
+            "Cross-instance pub/sub disabled (no Redis, BROKER_BACKEND=postgres to use the database)"
+        ),
+    }
+
+    // Runtime settings: defaults from config, overrides from the settings table
+    let settings = Arc::new(SettingsService::new(
+        SettingsRepository::new(db.clone()),
+        RuntimeSettings {
+            archive_after_secs: config.jobs.archive_after_secs,
+            ..Default::default()
+        },
+        broker.clone(),
+    ));
+    settings.reload().await?;
+
+    // WebSocket connection registry: snapshots via Redis, force-disconnect via the broker
+    let mut ws_registry = match &redis_pool {
+        Some(pool) => ConnectionRegistry::new(pool.clone()),
+        None => ConnectionRegistry::local(),
+    };
+    if let Some(broker) = &broker {
+        ws_registry = ws_registry.with_broker(broker.clone());
+    }
+    let ws_registry = Arc::new(ws_registry);
+    // Without Redis, presence follows this instance's connections (single instance)
+    let presence =
+        presence.unwrap_or_else(|| PresenceService::local(ws_registry.connections().clone()));
+
+    let mut state = AppState::new(
+        db,
+        webhooks,
+        storage,
+        presence,
+        upload_limiter,
+        slow_mode,
+        settings,
+        config,
+        jobs,
+        ws_registry,
+    );
+    if let Some(fs) = fs_storage {
+        state = state.with_fs_storage(fs);
+    }
+    if let Some(broker) = broker {
+        state = state.with_broker(broker);
+    }
+    events::spawn_subscribers(&state);
+
+    Ok((api::router(state.clone()), state))
+}
+
+/// Start the background work of a built service: runtime settings reloads,
+/// cross-instance WebSocket control and, with Redis, the job workers.
+///
+/// Call once per process.
+pub async fn spawn_background(state: &AppState) -> Result<(), StartupError> {
+    let settings_subscription = subscribe(state.broker.as_deref(), SETTINGS_CHANNEL)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                "Settings invalidation disabled, falling back to periodic reload: {}",
+                e
+            )
+        })
+        .ok()
+        .flatten();
+    state.settings.clone().spawn_reloader(settings_subscription);
+
+    let disconnect_subscription = subscribe(state.broker.as_deref(), DISCONNECT_CHANNEL)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                "Cross-instance force-disconnect disabled, only local connections can be closed: {}",
+                e
+            )
+        })
+        .ok()
+        .flatten();
+    state.ws_registry.clone().spawn(disconnect_subscription);
+
+    // Start job workers if Redis is configured
+    if let Some((
+        redis,
+        notification_storage,
+        thumbnail_storage,
+        cleanup_storage,
+        text_extract_storage,
+    )) = state.jobs.worker_backends()
+    {
+        let job_ctx = JobContext {
+            db: state.db.clone(),
+            redis: redis.clone(),
+            dialogs: state.dialogs.clone(),
+            participants: state.participants.clone(),
+            messages: state.messages.clone(),
+            attachments: state.attachments.clone(),
+            feature_flags: Arc::new(FeatureFlagRepository::new(state.db.clone())),
+            sla: state.sla.clone(),
+            export: state.export.clone(),
+            storage: state.storage.clone(),
+            webhooks: state.webhooks.clone(),
+            connections: state.connections.clone(),
+            jobs: state.jobs.clone(),
+            settings: state.settings.clone(),
+            events: state.events.clone(),
+        };
+
+        let monitor = start_workers(
+            notification_storage,
+            thumbnail_storage,
+            cleanup_storage,
+            text_extract_storage,
+            redis,
+            job_ctx,
+            state.config.jobs.clone(),
+        )
+        .await?;
+
+        run_workers(monitor, state.jobs.heartbeat().clone());
+    }
+
+    Ok(())
+}
+
+/// Attachment storage: S3 by default, the local filesystem with
+/// `STORAGE_BACKEND=fs` (also returned on its own for the file routes)
+pub(crate) async fn build_storage(
+    config: &AppConfig,
+) -> Result<(Arc<dyn BlobStorage>, Option<Arc<FsStorage>>), StartupError> {
+    Ok(match config.storage.backend {
+        StorageBackend::Fs => {
+            let fs = Arc::new(
+                FsStorage::new(config.storage.fs.clone())
+                    .map_err(|e| StartupError::Storage(e.to_string()))?
+                    .with_base_path(config.server.base_path()),
+            );
+            tracing::info!("Filesystem storage enabled, root: {}", fs.root().display());
+            (fs.clone(), Some(fs))
+        }
+        StorageBackend::S3 => {
+            let s3: Arc<dyn BlobStorage> = if config.s3.is_configured() {
+                tracing::info!("S3 enabled, bucket: {}", config.s3.bucket);
+                if let Some(cdn) = &config.s3.cdn_base_url {
+                    tracing::info!("S3 public-read mode, download URLs served from: {}", cdn);
+                }
+                let s3: Arc<dyn BlobStorage> = Arc::new(S3Service::new(config.s3.clone()).await);
+                Arc::new(GuardedStorage::new(s3, config.s3.circuit_breaker()))
+            } else {
+                tracing::warn!("S3 disabled: S3_ENDPOINT not set");
+                Arc::new(S3Service::noop())
+            };
+            (s3, None)
+        }
+    })
+}
+
+/// Tables with row-level security policies
+const RLS_TABLES: &[&str] = &[
+    "dialogs",
+    "dialog_access_scopes",
+    "dialog_participants",
+    "messages",
+    "attachments",
+    "dialog_list_entries",
+    "message_reactions",
+];
+
+/// Enable the row-level security policies in row-level security mode and
+/// disable them otherwise. While enabled, they keep indexes on non-leakproof
+/// operators (full-text search) from being used, so they are not left on
+/// for nothing.
+async fn sync_row_level_security(
+    conn: &mut PgConnection,
+    enabled: bool,
+) -> Result<(), sqlx::Error> {
+    for table in RLS_TABLES {
+        let current: bool =
+            sqlx::query_scalar("SELECT relrowsecurity FROM pg_class WHERE oid = $1::regclass")
+                .bind(table)
+                .fetch_one(&mut *conn)
+                .await?;
+        if current == enabled {
+            continue;
+        }
+        let action = if enabled {
+            "ENABLE ROW LEVEL SECURITY, FORCE ROW LEVEL SECURITY"
+        } else {
+            "DISABLE ROW LEVEL SECURITY, NO FORCE ROW LEVEL SECURITY"
+        };
+        sqlx::query(&format!("ALTER TABLE {} {}", table, action))
+            .execute(&mut *conn)
+            .await?;
+        tracing::info!(table, enabled, "Switched row-level security");
+    }
+    Ok(())
+}
+
+async fn connect_redis(url: &str) -> Result<Pool, StartupError> {
+    let redis_config = Config::from_url(url).map_err(|e| StartupError::Redis(e.to_string()))?;
+    let pool = Builder::from_config(redis_config)
+        // Enable TCP keepalive so the OS probes idle connections and
+        // detects silently-dropped sockets (NAT/firewall idle timeout)
+        // before they are handed out from the pool and hang on the next
+        // command. Without this, an idle-killed connection stays "alive"
+        // to the app and the first mget after a lull blocks forever.
+        .with_connection_config(|c| {
+            c.tcp = fred::types::config::TcpConfig {
+                keepalive: Some(
+                    socket2::TcpKeepalive::new()
+                        .with_time(Duration::from_secs(60))
+                        .with_interval(Duration::from_secs(10)),
+                ),
+                ..Default::default()
+            };
+        })
+        .build_pool(5)
+        .map_err(|e| StartupError::Redis(e.to_string()))?;
+    pool.init()
+        .await
+        .map_err(|e| StartupError::Redis(e.to_string()))?;
+    Ok(pool)
+}
+
+async fn job_producer(url: &str, redis_pool: Arc<Pool>) -> Result<JobProducer, StartupError> {
+    let apalis_conn = apalis_redis::connect(url)
+        .await
+        .map_err(|e| StartupError::Redis(e.to_string()))?;
+    let notification_storage: RedisStorage<NotificationJob> = RedisStorage::new_with_config(
+        apalis_conn.clone(),
+        apalis_redis::Config::default().set_poll_interval(Duration::from_millis(200)),
+    );
+    let thumbnail_storage: RedisStorage<ThumbnailJob> = RedisStorage::new_with_config(
+        apalis_conn.clone(),
+        apalis_redis::Config::default().set_poll_interval(Duration::from_millis(500)),
+    );
+    let cleanup_storage: RedisStorage<AttachmentCleanupJob> = RedisStorage::new_with_config(
+        apalis_conn.clone(),
+        apalis_redis::Config::default().set_poll_interval(Duration::from_secs(1)),
+    );
+    // Own namespace, so thumbnail workers (same job shape) never take these
+    let text_extract_storage: RedisStorage<TextExtractJob> = RedisStorage::new_with_config(
+        apalis_conn,
+        apalis_redis::Config::default()
+            .set_namespace("mtchat_text_extract")
+            .set_poll_interval(Duration::from_secs(1)),
+    );
+
+    Ok(JobProducer::new(
+        redis_pool,
+        notification_storage,
+        thumbnail_storage,
+        cleanup_storage,
+        text_extract_storage,
+    ))
+}
+
+/// Subscribe to a broker channel (`None` without a broker)
+async fn subscribe(
+    broker: Option<&dyn Broker>,
+    channel: &str,
+) -> Result<Option<Subscription>, BrokerError> {
+    match broker {
+        Some(broker) => broker.subscribe(channel).await.map(Some),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,327 @@
+//! Administration commands of the binary
+//!
+//! Maintenance tasks an operator runs against the configured database with
+//! the library's repositories, instead of crafting Management API calls.
+//! They don't reach running instances: connected clients see the changes on
+//! their next load, not through WebSocket events.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::app::{build_storage, StartupError};
+use crate::config::{AppConfig, Command, CreateDialogArgs};
+use crate::domain::{
+    avatar, system_messages, validation, Dialog, DialogAccessScope, DialogParticipant, IdGenerator,
+    JoinedAs, Message, ParticipantProfile,
+};
+use crate::migrate::{self, SchemaStatus};
+use crate::repositories::{
+    AttachmentRepository, DialogChildren, DialogRepository, FeatureFlagRepository,
+    ParticipantRepository,
+};
+use crate::seed::{self, SeedError};
+use crate::services::BlobStorage;
+
+/// Trigram indexes the search endpoints use, with their tables
+const SEARCH_INDEXES: &[(&str, &str)] = &[
+    ("messages", "idx_messages_content_trgm"),
+    ("attachments", "idx_attachments_filename_trgm"),
+    ("attachment_texts", "idx_attachment_texts_content_trgm"),
+    ("dialogs", "idx_dialogs_title_trgm"),
+    ("dialog_participants", "idx_participants_company_trgm"),
+];
+
+/// Errors of an administration command
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to run migrations: {0}")]
+    Migrations(#[from] sqlx::migrate::MigrateError),
+    #[error("{0}")]
+    Startup(#[from] StartupError),
+    #[error("Database schema is behind ({0})")]
+    SchemaBehind(SchemaStatus),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Dialog {0} not found")]
+    DialogNotFound(Uuid),
+    #[error("Purging deletes dialog {0} permanently; pass --yes to confirm")]
+    NotConfirmed(Uuid),
+    #[error("Failed to seed demo data: {0}")]
+    Seed(#[from] SeedError),
+}
+
+/// Run a command other than `serve`
+pub async fn run(command: Command, config: &AppConfig) -> Result<(), CliError> {
+    IdGenerator::init(&config.ids);
+
+    match command {
+        Command::Serve => Ok(()),
+        Command::Migrate { check } => {
+            let db = connect(config).await?;
+            let mut conn = db.acquire().await?;
+            let status = if check {
+                migrate::status(&mut conn).await?
+            } else {
+                migrate::run(&mut conn).await?
+            };
+            if !status.is_current() {
+                for version in &status.pending {
+                    println!("pending: {}", version);
+                }
+                return Err(CliError::SchemaBehind(status));
+            }
+            println!("Database schema is up to date ({})", status);
+            Ok(())
+        }
+        Command::CreateDialog(args) => {
+            let db = connect(config).await?;
+            let dialog = create_dialog(&db, args).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&dialog).expect("dialog serializes")
+            );
+            Ok(())
+        }
+        Command::PurgeDialog { dialog_id, yes } => {
+            if !yes {
+                return Err(CliError::NotConfirmed(dialog_id));
+            }
+            let db = connect(config).await?;
+            let (storage, _) = build_storage(config).await?;
+            let files = purge_dialog(&db, storage, dialog_id).await?;
+            println!("Purged dialog {} ({} files deleted)", dialog_id, files);
+            Ok(())
+        }
+        Command::RotateAdminToken => {
+            print_admin_token_rotation(config);
+            Ok(())
+        }
+        Command::ReindexSearch => {
+            let db = connect(config).await?;
+            reindex_search(&db).await
+        }
+        Command::Seed(options) => {
+            let db = connect(config).await?;
+            let (storage, _) = build_storage(config).await?;
+            let summary = seed::seed(&db, storage.as_ref(), &options).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&summary).expect("summary serializes")
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Small pool for a command; migrations and reindexing need no statement
+/// timeout, the other commands run a few short statements. Commands are
+/// service work, so the connections bypass the row-level security policies.
+async fn connect(config: &AppConfig) -> Result<PgPool, sqlx::Error> {
+    let options =
+        PgConnectOptions::from_str(&config.database.url)?.options([("app.rls_bypass", "on")]);
+    PgPoolOptions::new()
+        .max_connections(2)
+        .acquire_timeout(config.database.acquire_timeout)
+        .connect_with(options)
+        .await
+}
+
+/// Create a dialog with its participants, access scope and "chat created"
+/// message, as the Management API does
+pub async fn create_dialog(db: &PgPool, args: CreateDialogArgs) -> Result<Dialog, CliError> {
+    validation::validate_title(&args.title).map_err(|e| CliError::InvalidInput(e.message))?;
+    let participants = args
+        .participants
+        .iter()
+        .map(|arg| parse_participant(arg))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let created_by = participants.first().map(|(user_id, _)| user_id.clone());
+    let dialog = Dialog::new(
+        args.object_id,
+        args.object_type,
+        args.title,
+        args.object_url,
+        created_by,
+        None,
+    );
+    let system_message = (!participants.is_empty()).then(|| {
+        let infos = participants
+            .iter()
+            .map(|(_, name)| system_messages::ParticipantInfo {
+                name: name.clone(),
+                company: None,
+            })
+            .collect();
+        Message::system(
+            dialog.id,
+            system_messages::chat_created_content(infos, &dialog.locale_context()),
+        )
+    });
+    let participants = participants
+        .into_iter()
+        .map(|(user_id, display_name)| {
+            let profile = ParticipantProfile {
+                display_name,
+                company: None,
+                company_uid: None,
+                email: None,
+                phone: None,
+            };
+            DialogParticipant::with_profile(dialog.id, &user_id, JoinedAs::Participant, profile)
+        })
+        .collect();
+    let has_scope = !(args.scope_level0.is_empty()
+        && args.scope_level1.is_empty()
+        && args.scope_level2.is_empty());
+    let access_scopes = if has_scope {
+        vec![DialogAccessScope::new(
+            dialog.id,
+            args.scope_level0,
+            args.scope_level1,
+            args.scope_level2,
+        )]
+    } else {
+        Vec::new()
+    };
+    let children = DialogChildren {
+        participants,
+        access_scopes,
+        system_message,
+    };
+
+    let mut tx = db.begin().await?;
+    let dialog = DialogRepository::insert_with_children(&mut tx, &dialog, &children).await?;
+    tx.commit().await?;
+    Ok(dialog)
+}
+
+/// `USER_ID` or `USER_ID=DISPLAY_NAME`; the display name defaults to the ID
+fn parse_participant(arg: &str) -> Result<(String, String), CliError> {
+    let (user_id, name) = match arg.split_once('=') {
+        Some((user_id, name)) => (user_id.trim(), name.trim()),
+        None => (arg.trim(), arg.trim()),
+    };
+    if user_id.is_empty() {
+        return Err(CliError::InvalidInput(format!(
+            "participant '{}' has no user ID",
+            arg
+        )));
+    }
+    validation::validate_display_name(name).map_err(|e| CliError::InvalidInput(e.message))?;
+    Ok((user_id.to_string(), name.to_string()))
+}
+
+/// Delete a dialog now, deleted or not, with everything referencing it,
+/// then its attachment and avatar files (as the purge job does after the
+/// retention period). Returns the number of deleted files.
+pub async fn purge_dialog(
+    db: &PgPool,
+    storage: Arc<dyn BlobStorage>,
+    dialog_id: Uuid,
+) -> Result<usize, CliError> {
+    let dialogs = DialogRepository::new(db.clone());
+    let attachments = AttachmentRepository::new(db.clone());
+    let participants = ParticipantRepository::new(db.clone());
+
+    let mut keys = attachments.list_keys_by_dialog(dialog_id).await?;
+    for avatar_key in participants.list_avatar_keys_by_dialog(dialog_id).await? {
+        keys.extend(avatar::avatar_object_keys(&avatar_key));
+    }
+
+    FeatureFlagRepository::new(db.clone())
+        .delete_for_dialog(dialog_id)
+        .await?;
+    if !dialogs.delete(dialog_id).await? {
+        return Err(CliError::DialogNotFound(dialog_id));
+    }
+
+    if !storage.is_configured() {
+        return Ok(0);
+    }
+    let mut deleted = 0;
+    for key in &keys {
+        // Forwarded attachments share the file
+        if attachments.is_key_referenced(key).await? {
+            continue;
+        }
+        match storage.delete_object(key).await {
+            Ok(()) => deleted += 1,
+            Err(e) => tracing::warn!(key = %key, error = %e, "Failed to delete file"),
+        }
+    }
+    Ok(deleted)
+}
+
+/// Rebuild the search indexes without blocking writes
+async fn reindex_search(db: &PgPool) -> Result<(), CliError> {
+    let mut conn = db.acquire().await?;
+    sqlx::query("SET statement_timeout = 0")
+        .execute(&mut *conn)
+        .await?;
+    for (table, index) in SEARCH_INDEXES {
+        println!("Reindexing {}...", index);
+        sqlx::query(&format!("REINDEX INDEX CONCURRENTLY {}", index))
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(&format!("ANALYZE {}", table))
+            .execute(&mut *conn)
+            .await?;
+    }
+    println!("Rebuilt {} search indexes", SEARCH_INDEXES.len());
+    Ok(())
+}
+
+/// The admin token is configuration, so rotating it means rolling out a new
+/// value; the previous one stays accepted until every client switched
+fn print_admin_token_rotation(config: &AppConfig) {
+    let token = generate_admin_token();
+    println!("New admin token: {}", token);
+    println!();
+    if config.admin.api_token.is_some() {
+        println!("Roll it out without downtime:");
+        println!("  1. Set ADMIN_API_TOKEN to the new token and ADMIN_API_TOKEN_PREVIOUS to the current one, restart the instances");
+        println!("  2. Switch Management API clients to the new token");
+        println!("  3. Unset ADMIN_API_TOKEN_PREVIOUS and restart the instances");
+    } else {
+        println!("No admin token is configured yet: set ADMIN_API_TOKEN to the new token and restart the instances.");
+    }
+}
+
+/// Two random UUIDs (244 random bits) as 64 hex characters
+fn generate_admin_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_participant() {
+        assert_eq!(
+            parse_participant("user-1=Anna Smith").unwrap(),
+            ("user-1".to_string(), "Anna Smith".to_string())
+        );
+        assert_eq!(
+            parse_participant("user-1").unwrap(),
+            ("user-1".to_string(), "user-1".to_string())
+        );
+        assert!(parse_participant("=Anna").is_err());
+        assert!(parse_participant("user-1=").is_err());
+    }
+
+    #[test]
+    fn test_generated_admin_tokens_differ() {
+        let token = generate_admin_token();
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_admin_token());
+    }
+}
@@ -0,0 +1,27 @@
+//! Request body limit configuration
+//!
+//! Chat API bodies are limited by the `max_message_length` setting (see
+//! [`chat_body_limit`](crate::middleware::chat_body_limit)); the Management API
+//! limit is configured here (`[body_limits]` section) since its bulk
+//! endpoints take larger payloads.
+//!
+//! Environment variables:
+//! - `BODY_LIMIT_MANAGEMENT_BYTES` - Max Management API request body (default: 4 MiB)
+
+use serde::{Deserialize, Serialize};
+
+/// Request body limits (`[body_limits]` section)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BodyLimitConfig {
+    /// Maximum Management API request body in bytes
+    pub management_bytes: usize,
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        Self {
+            management_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
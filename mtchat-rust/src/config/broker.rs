@@ -0,0 +1,26 @@
+//! Cross-instance pub/sub configuration
+//!
+//! Selects the [`Broker`](crate::services::Broker) backend instances use to
+//! notify each other (settings invalidation, force-disconnect).
+//!
+//! Environment variables:
+//! - `BROKER_BACKEND` - `redis` (default, needs `REDIS_URL`) or `postgres`
+//!   (`LISTEN/NOTIFY` on the main database, for deployments without Redis)
+
+use serde::{Deserialize, Serialize};
+
+/// Pub/sub backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerBackend {
+    #[default]
+    Redis,
+    Postgres,
+}
+
+/// Pub/sub settings (`[broker]` section)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrokerConfig {
+    pub backend: BrokerBackend,
+}
@@ -1,6 +1,21 @@
+//! CORS configuration (`[cors]` section)
+//!
+//! Environment variables:
+//! - `CORS_ALLOWED_ORIGINS` - Comma-separated origins or `*` (default: `*`)
+//! - `CORS_ALLOWED_METHODS` - Comma-separated methods (default: `GET,POST,PUT,DELETE,OPTIONS`)
+//! - `CORS_ALLOWED_HEADERS` - Comma-separated headers or `*` (default: `*`)
+//! - `CORS_ALLOW_CREDENTIALS` - Allow credentials (default: false)
+//! - `CORS_MAX_AGE` - Preflight cache in seconds (default: 3600)
+
+use axum::http::{header, HeaderValue};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 
+use crate::middleware::REQUEST_ID_HEADER;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CorsConfig {
     /// Comma-separated origins or "*" for all
     pub allowed_origins: String,
@@ -27,22 +42,16 @@ impl Default for CorsConfig {
 }
 
 impl CorsConfig {
-    pub fn from_env() -> Self {
-        Self {
-            allowed_origins: std::env::var("CORS_ALLOWED_ORIGINS")
-                .unwrap_or_else(|_| "*".to_string()),
-            allowed_methods: std::env::var("CORS_ALLOWED_METHODS")
-                .unwrap_or_else(|_| "GET,POST,PUT,DELETE,OPTIONS".to_string()),
-            allowed_headers: std::env::var("CORS_ALLOWED_HEADERS")
-                .unwrap_or_else(|_| "*".to_string()),
-            allow_credentials: std::env::var("CORS_ALLOW_CREDENTIALS")
-                .map(|v| v == "true" || v == "1")
-                .unwrap_or(false),
-            max_age: std::env::var("CORS_MAX_AGE")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(3600),
+    /// Origins that are not valid header values (would fail at layer construction)
+    pub fn invalid_origins(&self) -> Vec<&str> {
+        if self.allowed_origins == "*" {
+            return Vec::new();
         }
+        self.allowed_origins
+            .split(',')
+            .map(str::trim)
+            .filter(|s| s.parse::<HeaderValue>().is_err())
+            .collect()
     }
 
     pub fn into_layer(self) -> CorsLayer {
@@ -84,6 +93,10 @@ impl CorsConfig {
             layer.allow_headers(headers)
         };
 
+        // Let the widget read the request ID for error reports and the ETag
+        // for conditional requests
+        layer = layer.expose_headers([REQUEST_ID_HEADER.clone(), header::ETAG]);
+
         // Credentials & Max Age
         if self.allow_credentials {
             layer = layer.allow_credentials(true);
@@ -0,0 +1,124 @@
+//! Readiness probe configuration
+//!
+//! Controls which dependencies are critical for `/health/ready`. A failing
+//! critical dependency makes the service `down` (503); a failing non-critical
+//! one only makes it `degraded` (200), so e.g. an S3 outage doesn't pull the
+//! instance out of the load balancer.
+//!
+//! Environment variables:
+//! - `HEALTH_CRITICAL_DEPS` - Comma-separated critical dependencies
+//!   (`postgres`, `redis`, `storage`, `jobs`; default: `postgres`)
+//! - `HEALTH_PROBE_TIMEOUT_MS` - Timeout per probe in milliseconds (default: 2000)
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::serde_helpers::{comma_list, millis};
+
+static HEALTH_CONFIG: OnceLock<HealthConfig> = OnceLock::new();
+
+/// Dependency probed by the readiness endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dependency {
+    Postgres,
+    Redis,
+    Storage,
+    Jobs,
+}
+
+impl Dependency {
+    pub const ALL: [Dependency; 4] = [
+        Dependency::Postgres,
+        Dependency::Redis,
+        Dependency::Storage,
+        Dependency::Jobs,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dependency::Postgres => "postgres",
+            Dependency::Redis => "redis",
+            Dependency::Storage => "storage",
+            Dependency::Jobs => "jobs",
+        }
+    }
+}
+
+/// Readiness probe configuration (`[health]` section)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Dependencies whose failure makes the service `down`
+    #[serde(rename = "critical_deps", deserialize_with = "comma_list")]
+    pub critical: Vec<Dependency>,
+    /// Timeout for each dependency probe
+    #[serde(rename = "probe_timeout_ms", with = "millis")]
+    pub probe_timeout: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            critical: vec![Dependency::Postgres],
+            probe_timeout: Duration::from_millis(2000),
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Install the global config. Called once at startup.
+    pub fn init(config: HealthConfig) {
+        let config = HEALTH_CONFIG.get_or_init(|| config);
+        tracing::info!(
+            "Health critical dependencies: {}",
+            config
+                .critical
+                .iter()
+                .map(|d| d.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+    }
+
+    /// Get the global config (defaults if `init` was not called)
+    pub fn get() -> &'static HealthConfig {
+        HEALTH_CONFIG.get_or_init(Self::default)
+    }
+
+    pub fn is_critical(&self, dependency: Dependency) -> bool {
+        self.critical.contains(&dependency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_critical_deps() {
+        let config: HealthConfig =
+            serde_json::from_value(serde_json::json!({ "critical_deps": "postgres, redis,,jobs" }))
+                .unwrap();
+        assert_eq!(
+            config.critical,
+            vec![Dependency::Postgres, Dependency::Redis, Dependency::Jobs]
+        );
+
+        let config: HealthConfig =
+            serde_json::from_value(serde_json::json!({ "critical_deps": ["storage"] })).unwrap();
+        assert_eq!(config.critical, vec![Dependency::Storage]);
+
+        let result: Result<HealthConfig, _> =
+            serde_json::from_value(serde_json::json!({ "critical_deps": "postgres,unknown" }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_default_only_postgres_critical() {
+        let config = HealthConfig::default();
+        assert!(config.is_critical(Dependency::Postgres));
+        assert!(!config.is_critical(Dependency::Storage));
+    }
+}
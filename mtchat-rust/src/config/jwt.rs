@@ -3,13 +3,43 @@
 //! When enabled (JWT_AUTH_ENABLED=true), validates JWT tokens on Chat API requests.
 //! The token is expected to be signed with HS256 algorithm.
 //! Expiration is NOT checked - the token is validated only for signature correctness.
+//!
+//! Environment variables:
+//! - `JWT_AUTH_ENABLED`: Set to "true" or "1" to enable JWT auth
+//! - `JWT_SECRET`: Secret key for HS256 signature verification (required if enabled)
+//! - `JWT_USER_ID_CLAIM`: Claim name to read user ID from (default: `sub`).
+//!   Use this when your host application encodes the user identifier under
+//!   a non-standard claim like `user_id`, `userId`, `id`, etc.
 
 use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 
 static JWT_CONFIG: OnceLock<Option<JwtConfig>> = OnceLock::new();
 
-/// JWT configuration loaded from environment variables
+/// JWT auth settings (`[jwt]` section)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JwtAuthConfig {
+    /// Validate JWT tokens on Chat API requests
+    pub enabled: bool,
+    /// Secret key for HS256 signature verification (required if enabled)
+    pub secret: Option<String>,
+    /// Claim name to read the user ID from
+    pub user_id_claim: String,
+}
+
+impl Default for JwtAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secret: None,
+            user_id_claim: "sub".to_string(),
+        }
+    }
+}
+
+/// Runtime JWT configuration built from [`JwtAuthConfig`]
 pub struct JwtConfig {
     /// Decoding key for JWT signature verification
     pub decoding_key: DecodingKey,
@@ -20,31 +50,19 @@ pub struct JwtConfig {
 }
 
 impl JwtConfig {
-    /// Initialize JWT configuration from environment variables.
-    /// Must be called once at startup.
+    /// Initialize JWT configuration. Must be called once at startup.
     ///
-    /// Environment variables:
-    /// - `JWT_AUTH_ENABLED`: Set to "true" or "1" to enable JWT auth
-    /// - `JWT_SECRET`: Secret key for HS256 signature verification (required if enabled)
-    /// - `JWT_USER_ID_CLAIM`: Claim name to read user ID from (default: `sub`).
-    ///   Use this when your host application encodes the user identifier under
-    ///   a non-standard claim like `user_id`, `userId`, `id`, etc.
-    pub fn init() {
+    /// The settings are validated by [`AppConfig`](super::AppConfig), so an
+    /// enabled config always carries a secret.
+    pub fn init(settings: &JwtAuthConfig) {
         JWT_CONFIG.get_or_init(|| {
-            let enabled = std::env::var("JWT_AUTH_ENABLED")
-                .map(|v| v == "true" || v == "1")
-                .unwrap_or(false);
-
-            if !enabled {
-                tracing::info!("JWT auth disabled (JWT_AUTH_ENABLED not set)");
-                return None;
-            }
-
-            let secret = std::env::var("JWT_SECRET")
-                .expect("JWT_SECRET required when JWT_AUTH_ENABLED=true");
-
-            let user_id_claim =
-                std::env::var("JWT_USER_ID_CLAIM").unwrap_or_else(|_| "sub".to_string());
+            let secret = match (settings.enabled, settings.secret.as_deref()) {
+                (true, Some(secret)) => secret,
+                _ => {
+                    tracing::info!("JWT auth disabled (JWT_AUTH_ENABLED not set)");
+                    return None;
+                }
+            };
 
             let mut validation = Validation::new(Algorithm::HS256);
             // Don't validate expiration - token is reused from host application
@@ -54,12 +72,12 @@ impl JwtConfig {
 
             tracing::info!(
                 "JWT auth enabled for Chat API (user_id claim: {})",
-                user_id_claim
+                settings.user_id_claim
             );
             Some(JwtConfig {
                 decoding_key: DecodingKey::from_secret(secret.as_bytes()),
                 validation,
-                user_id_claim,
+                user_id_claim: settings.user_id_claim.clone(),
             })
         });
     }
@@ -1,9 +1,24 @@
+mod app;
+mod body_limit;
+mod broker;
 mod cors;
 mod database;
+mod health;
 mod jwt;
 mod rate_limit;
+pub(crate) mod serde_helpers;
+mod storage_quota;
 
+pub use app::{
+    AdminConfig, AppConfig, CliArgs, Command, ConfigError, CreateDialogArgs, EnvVars, ListenAddr,
+    LogFormat, RedisConfig, ServerConfig, StorageBackend, StorageConfig, DEFAULT_CONFIG_FILE,
+    ENV_KEYS, REDACTED,
+};
+pub use body_limit::BodyLimitConfig;
+pub use broker::{BrokerBackend, BrokerConfig};
 pub use cors::CorsConfig;
 pub use database::DatabaseConfig;
-pub use jwt::JwtConfig;
+pub use health::{Dependency, HealthConfig};
+pub use jwt::{JwtAuthConfig, JwtConfig};
 pub use rate_limit::RateLimitConfig;
+pub use storage_quota::StorageQuotaConfig;
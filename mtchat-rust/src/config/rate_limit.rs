@@ -1,7 +1,12 @@
 //! Rate limiting configuration
 //!
-//! Configures request rate limits via environment variables.
+//! Configures request rate limits (`[rate_limit]` section).
 //! Uses a token bucket algorithm (governor crate) for smooth rate limiting.
+//!
+//! Environment variables:
+//! - `RATE_LIMIT_ENABLED` - Enable rate limiting (default: false)
+//! - `RATE_LIMIT_RPS` - Requests per second (default: 100)
+//! - `RATE_LIMIT_BURST` - Burst capacity (default: 50)
 
 use governor::{
     clock::DefaultClock,
@@ -9,10 +14,12 @@ use governor::{
     state::{InMemoryState, NotKeyed},
     Quota, RateLimiter,
 };
+use serde::{Deserialize, Serialize};
 use std::{num::NonZeroU32, sync::Arc, time::Duration};
 
-/// Rate limiting configuration from environment variables
-#[derive(Debug, Clone)]
+/// Rate limiting configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RateLimitConfig {
     /// Whether rate limiting is enabled
     pub enabled: bool,
@@ -23,34 +30,6 @@ pub struct RateLimitConfig {
 }
 
 impl RateLimitConfig {
-    /// Create config from environment variables
-    ///
-    /// Environment variables:
-    /// - `RATE_LIMIT_ENABLED` - Enable rate limiting (default: false)
-    /// - `RATE_LIMIT_RPS` - Requests per second (default: 100)
-    /// - `RATE_LIMIT_BURST` - Burst capacity (default: 50)
-    pub fn from_env() -> Self {
-        let enabled = std::env::var("RATE_LIMIT_ENABLED")
-            .map(|v| v == "true" || v == "1")
-            .unwrap_or(false);
-
-        let requests_per_second = std::env::var("RATE_LIMIT_RPS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(100);
-
-        let burst_size = std::env::var("RATE_LIMIT_BURST")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(50);
-
-        Self {
-            enabled,
-            requests_per_second,
-            burst_size,
-        }
-    }
-
     /// Create a rate limiter from this config
     pub fn create_limiter(
         &self,
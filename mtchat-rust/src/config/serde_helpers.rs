@@ -0,0 +1,90 @@
+//! Serde helpers for configuration fields
+//!
+//! Durations are written as plain integers (seconds or milliseconds) so the
+//! TOML file and the environment variables use the same units.
+
+use serde::de::{self, Deserialize, Deserializer, IntoDeserializer};
+use serde::Serializer;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// `Duration` as whole seconds
+pub mod secs {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+/// `Duration` as whole milliseconds
+pub mod millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// List given either as an array or as a comma-separated string
+/// (the form environment variables use)
+pub fn comma_list<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum ListOrString<T> {
+        List(Vec<T>),
+        String(String),
+    }
+
+    match ListOrString::<T>::deserialize(deserializer)? {
+        ListOrString::List(items) => Ok(items),
+        ListOrString::String(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| T::deserialize(s.to_string().into_deserializer()))
+            .collect::<Result<Vec<T>, de::value::Error>>()
+            .map_err(de::Error::custom),
+    }
+}
+
+/// Map given either as a table or as `Name: value` pairs separated by `;`
+/// (the form environment variables use)
+pub fn header_map<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum MapOrString {
+        Map(BTreeMap<String, String>),
+        String(String),
+    }
+
+    match MapOrString::deserialize(deserializer)? {
+        MapOrString::Map(map) => Ok(map),
+        MapOrString::String(s) => s
+            .split(';')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once(':').ok_or_else(|| {
+                    de::Error::custom(format!("expected `Name: value`, got {:?}", pair))
+                })?;
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect(),
+    }
+}
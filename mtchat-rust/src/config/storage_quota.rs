@@ -0,0 +1,48 @@
+//! Storage quota configuration
+//!
+//! Default attachment storage quotas per dialog and per tenant. Individual
+//! dialogs and tenants can override them via the management API.
+//!
+//! Environment variables:
+//! - `STORAGE_QUOTA_DIALOG_BYTES` - Default quota per dialog (default: 0, unlimited)
+//! - `STORAGE_QUOTA_TENANT_BYTES` - Default quota per tenant (default: 0, unlimited)
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{StorageScope, StorageUsage};
+
+/// Default storage quotas (`[storage_quotas]` section)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageQuotaConfig {
+    /// Default quota per dialog in bytes (0 = unlimited)
+    pub dialog_bytes: i64,
+    /// Default quota per tenant in bytes (0 = unlimited)
+    pub tenant_bytes: i64,
+}
+
+impl StorageQuotaConfig {
+    /// Default quota for a scope type
+    pub fn default_for(&self, scope_type: StorageScope) -> i64 {
+        match scope_type {
+            StorageScope::Dialog => self.dialog_bytes,
+            StorageScope::Tenant => self.tenant_bytes,
+        }
+    }
+
+    /// Effective quota of a usage row (`None` = unlimited)
+    pub fn effective_quota(&self, usage: &StorageUsage) -> Option<i64> {
+        usage.effective_quota(self.default_for(usage.scope_type))
+    }
+
+    /// Find the first scope whose quota would be exceeded by `additional` bytes
+    pub fn find_exceeded<'a>(
+        &self,
+        usages: &'a [StorageUsage],
+        additional: i64,
+    ) -> Option<&'a StorageUsage> {
+        usages
+            .iter()
+            .find(|u| u.would_exceed(additional, self.default_for(u.scope_type)))
+    }
+}
@@ -24,7 +24,7 @@ pub struct Attachment {
     pub width: Option<i32>,
     /// Image height in pixels (None for non-images)
     pub height: Option<i32>,
-    /// S3 key for thumbnail (images only)
+    /// S3 key for thumbnail (images, and first-page previews of PDFs)
     pub thumbnail_s3_key: Option<String>,
     pub created_at: DateTime<Utc>,
 }
@@ -96,6 +96,15 @@ pub enum AttachmentType {
     Other,
 }
 
+/// Why an attachment response has no URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlStatus {
+    /// Storage could not generate the URL right now; fetch it later from
+    /// `GET /attachments/{id}/url`
+    Unavailable,
+}
+
 /// Attachment response with presigned URLs
 #[derive(Debug, Serialize)]
 pub struct AttachmentResponse {
@@ -107,18 +116,22 @@ pub struct AttachmentResponse {
     pub width: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub height: Option<i32>,
-    /// Presigned download URL
-    pub url: String,
-    /// Presigned thumbnail URL (images only)
+    /// Presigned download URL (`null` if storage is unavailable)
+    pub url: Option<String>,
+    /// Set when `url` is `null`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_status: Option<UrlStatus>,
+    /// Presigned thumbnail URL (images and PDF previews)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_url: Option<String>,
 }
 
 impl AttachmentResponse {
-    /// Create response from attachment with presigned URLs
+    /// Create response from attachment with presigned URLs (`url` is `None`
+    /// if it could not be generated)
     pub fn from_attachment(
         attachment: &Attachment,
-        url: String,
+        url: Option<String>,
         thumbnail_url: Option<String>,
     ) -> Self {
         Self {
@@ -128,10 +141,15 @@ impl AttachmentResponse {
             size: attachment.size,
             width: attachment.width,
             height: attachment.height,
+            url_status: url.is_none().then_some(UrlStatus::Unavailable),
             url,
             thumbnail_url,
         }
     }
+
+    pub fn is_url_unavailable(&self) -> bool {
+        self.url_status == Some(UrlStatus::Unavailable)
+    }
 }
 
 /// Input for creating an attachment (from message send request)
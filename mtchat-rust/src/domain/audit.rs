@@ -0,0 +1,59 @@
+//! Audit log entry
+//!
+//! Records privileged actions taken through the Management API, such as a
+//! support engineer viewing a dialog as one of its participants.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// An impersonation token was issued
+pub const AUDIT_IMPERSONATION_ISSUED: &str = "impersonation.issued";
+
+/// An impersonation token was used to view a dialog
+pub const AUDIT_IMPERSONATION_VIEWED: &str = "impersonation.viewed";
+
+/// Maximum length of the `actor` label
+pub const MAX_AUDIT_ACTOR_LENGTH: usize = 255;
+
+/// Maximum number of entries returned by one audit log request
+pub const MAX_AUDIT_ENTRIES: i64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// Who performed the action
+    pub actor: String,
+    /// Action type, e.g. `impersonation.issued`
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialog_id: Option<Uuid>,
+    /// User acted as or on behalf of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Action-specific data
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(actor: impl Into<String>, action: &str, details: serde_json::Value) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            actor: actor.into(),
+            action: action.to_string(),
+            dialog_id: None,
+            user_id: None,
+            details,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Attach the dialog and user the action concerns
+    pub fn for_user(mut self, dialog_id: Uuid, user_id: impl Into<String>) -> Self {
+        self.dialog_id = Some(dialog_id);
+        self.user_id = Some(user_id.into());
+        self
+    }
+}
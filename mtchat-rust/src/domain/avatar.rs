@@ -0,0 +1,69 @@
+//! Participant avatars
+//!
+//! Avatars belong to a participant of a dialog, like the rest of the profile.
+//! The client uploads the image to a presigned URL under the participant's
+//! key prefix, then sets it; the thumbnail job renders square PNG variants
+//! next to the original.
+
+use uuid::Uuid;
+
+/// Maximum avatar upload size (5 MB)
+pub const MAX_AVATAR_SIZE: i64 = 5 * 1024 * 1024;
+
+/// Image types accepted as avatars (ones the thumbnail job can decode)
+pub const AVATAR_TYPES: &[&str] = &["image/jpeg", "image/png", "image/webp"];
+
+/// Edge lengths of the rendered square variants in pixels
+pub const AVATAR_VARIANT_SIZES: [u32; 2] = [64, 256];
+
+/// Content type of rendered variants
+pub const AVATAR_VARIANT_CONTENT_TYPE: &str = "image/png";
+
+/// Check if a content type can be used as an avatar
+pub fn is_avatar_type(content_type: &str) -> bool {
+    AVATAR_TYPES.contains(&content_type)
+}
+
+/// Check avatar upload size
+pub fn is_valid_avatar_size(size: i64) -> bool {
+    size > 0 && size <= MAX_AVATAR_SIZE
+}
+
+/// File extension for an avatar content type
+pub fn avatar_extension(content_type: &str) -> &'static str {
+    match content_type {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        _ => "jpg",
+    }
+}
+
+/// Storage key prefix for the avatars of a participant
+///
+/// `avatars/{dialog_id}/{url-encoded user_id}/`
+pub fn avatar_key_prefix(dialog_id: Uuid, user_id: &str) -> String {
+    format!("avatars/{}/{}/", dialog_id, urlencoding::encode(user_id))
+}
+
+/// Storage key of a rendered variant
+///
+/// `avatars/d/u/0192.jpg` -> `avatars/d/u/0192_64.png`
+pub fn avatar_variant_key(s3_key: &str, size: u32) -> String {
+    let name_start = s3_key.rfind('/').map_or(0, |i| i + 1);
+    let stem = match s3_key[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => &s3_key[..name_start + dot],
+        _ => s3_key,
+    };
+    format!("{}_{}.png", stem, size)
+}
+
+/// Original and variant keys of an avatar (for deletion)
+pub fn avatar_object_keys(s3_key: &str) -> Vec<String> {
+    std::iter::once(s3_key.to_string())
+        .chain(
+            AVATAR_VARIANT_SIZES
+                .iter()
+                .map(|size| avatar_variant_key(s3_key, *size)),
+        )
+        .collect()
+}
@@ -0,0 +1,47 @@
+//! Soft bans
+//!
+//! A ban keeps a user from joining a dialog: it is left out of the user's
+//! available dialogs and scope access, until it is lifted or expires. It
+//! doesn't remove a user who already participates.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Most characters of a ban reason
+pub const MAX_BAN_REASON_LENGTH: usize = 500;
+
+/// User blocked from joining a dialog
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogBan {
+    pub dialog_id: Uuid,
+    pub user_id: String,
+    /// Note for moderators, not shown to the user
+    pub reason: Option<String>,
+    pub banned_at: DateTime<Utc>,
+    /// End of the ban (`None` = until lifted)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl DialogBan {
+    pub fn new(
+        dialog_id: Uuid,
+        user_id: &str,
+        reason: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            dialog_id,
+            user_id: user_id.to_string(),
+            reason,
+            banned_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    /// Whether the ban is in effect at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.map_or(true, |expires_at| expires_at > now)
+    }
+}
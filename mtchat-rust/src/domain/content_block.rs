@@ -0,0 +1,258 @@
+//! Structured message content
+//!
+//! Messages can carry `content_blocks` next to their HTML `content`: typed
+//! blocks that clients render natively (quote summaries, file links, action
+//! buttons). Block text is plain text, never HTML. `content` stays the
+//! fallback for clients, notifications and search.
+
+use serde::{Deserialize, Serialize};
+
+use super::validation::ValidationError;
+
+/// Maximum number of blocks in one message
+pub const MAX_CONTENT_BLOCKS: usize = 20;
+
+/// Maximum serialized size of all blocks of a message in bytes
+pub const MAX_CONTENT_BLOCKS_BYTES: usize = 16_384;
+
+/// Maximum rows of a key-value block
+pub const MAX_KEY_VALUE_ROWS: usize = 50;
+
+/// Maximum buttons of an actions block
+pub const MAX_ACTION_BUTTONS: usize = 5;
+
+/// Maximum length of a button ID
+pub const MAX_ACTION_ID_LENGTH: usize = 64;
+
+/// One block of structured message content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    /// Paragraph of plain text
+    Text { text: String },
+    /// Quoted text, optionally attributed
+    Quote {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        author: Option<String>,
+    },
+    /// Link to a file
+    File {
+        name: String,
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+    },
+    /// Table of labelled values, e.g. a quote summary
+    KeyValue {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        rows: Vec<KeyValueRow>,
+    },
+    /// Row of buttons
+    Actions { buttons: Vec<ActionButton> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyValueRow {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionButton {
+    /// Identifier of the action, unique within the message
+    pub id: String,
+    pub label: String,
+    /// Link opened by the button (otherwise the client handles `id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub style: ButtonStyle,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonStyle {
+    #[default]
+    Default,
+    Primary,
+    Danger,
+}
+
+fn invalid(message: impl Into<String>) -> ValidationError {
+    ValidationError {
+        field: "content_blocks",
+        message: message.into(),
+    }
+}
+
+fn require_text(value: &str, what: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(invalid(format!("{} must not be empty", what)));
+    }
+    Ok(())
+}
+
+fn require_http_url(url: &str) -> Result<(), ValidationError> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(invalid(format!(
+            "url must be an http(s) URL, got {:?}",
+            url
+        )));
+    }
+    Ok(())
+}
+
+/// Validate the content blocks of a message: limited count and size,
+/// non-empty text and http(s) links only
+pub fn validate_content_blocks(blocks: &Option<Vec<ContentBlock>>) -> Result<(), ValidationError> {
+    let Some(blocks) = blocks else {
+        return Ok(());
+    };
+    if blocks.is_empty() {
+        return Err(invalid("content_blocks must not be empty"));
+    }
+    if blocks.len() > MAX_CONTENT_BLOCKS {
+        return Err(invalid(format!(
+            "content_blocks exceeds {} blocks",
+            MAX_CONTENT_BLOCKS
+        )));
+    }
+    let size = serde_json::to_string(blocks).map_or(usize::MAX, |s| s.len());
+    if size > MAX_CONTENT_BLOCKS_BYTES {
+        return Err(invalid(format!(
+            "content_blocks exceeds {} bytes",
+            MAX_CONTENT_BLOCKS_BYTES
+        )));
+    }
+
+    let mut action_ids = Vec::new();
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } | ContentBlock::Quote { text, .. } => {
+                require_text(text, "text")?;
+            }
+            ContentBlock::File {
+                name, url, size, ..
+            } => {
+                require_text(name, "file name")?;
+                require_http_url(url)?;
+                if size.is_some_and(|s| s < 0) {
+                    return Err(invalid("file size must not be negative"));
+                }
+            }
+            ContentBlock::KeyValue { rows, .. } => {
+                if rows.is_empty() || rows.len() > MAX_KEY_VALUE_ROWS {
+                    return Err(invalid(format!(
+                        "key_value blocks must have 1 to {} rows",
+                        MAX_KEY_VALUE_ROWS
+                    )));
+                }
+                for row in rows {
+                    require_text(&row.key, "key")?;
+                }
+            }
+            ContentBlock::Actions { buttons } => {
+                if buttons.is_empty() || buttons.len() > MAX_ACTION_BUTTONS {
+                    return Err(invalid(format!(
+                        "actions blocks must have 1 to {} buttons",
+                        MAX_ACTION_BUTTONS
+                    )));
+                }
+                for button in buttons {
+                    require_text(&button.id, "button id")?;
+                    require_text(&button.label, "button label")?;
+                    if button.id.len() > MAX_ACTION_ID_LENGTH {
+                        return Err(invalid(format!(
+                            "button id exceeds {} characters",
+                            MAX_ACTION_ID_LENGTH
+                        )));
+                    }
+                    if let Some(url) = &button.url {
+                        require_http_url(url)?;
+                    }
+                    if action_ids.contains(&button.id.as_str()) {
+                        return Err(invalid(format!("duplicate button id {:?}", button.id)));
+                    }
+                    action_ids.push(button.id.as_str());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(value: serde_json::Value) -> Option<Vec<ContentBlock>> {
+        Some(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn test_parse_quote_summary() {
+        let parsed = blocks(serde_json::json!([
+            { "type": "text", "text": "New quote" },
+            { "type": "key_value", "title": "Quote #7", "rows": [
+                { "key": "Price", "value": "1 200 EUR" }
+            ]},
+            { "type": "actions", "buttons": [
+                { "id": "accept", "label": "Accept", "style": "primary" },
+                { "id": "open", "label": "Open", "url": "https://tms.example.com/q/7" }
+            ]}
+        ]));
+        assert!(validate_content_blocks(&parsed).is_ok());
+
+        let parsed = parsed.unwrap();
+        let ContentBlock::Actions { buttons } = &parsed[2] else {
+            panic!("expected actions block");
+        };
+        assert_eq!(buttons[0].style, ButtonStyle::Primary);
+        assert_eq!(buttons[1].style, ButtonStyle::Default);
+    }
+
+    #[test]
+    fn test_unknown_block_type_is_rejected() {
+        let result: Result<Vec<ContentBlock>, _> =
+            serde_json::from_value(serde_json::json!([{ "type": "video", "url": "x" }]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_content_blocks() {
+        assert!(validate_content_blocks(&None).is_ok());
+        assert!(validate_content_blocks(&Some(vec![])).is_err());
+        assert!(validate_content_blocks(&blocks(serde_json::json!([
+            { "type": "text", "text": "  " }
+        ])))
+        .is_err());
+        assert!(validate_content_blocks(&blocks(serde_json::json!([
+            { "type": "file", "name": "a.pdf", "url": "javascript:alert(1)" }
+        ])))
+        .is_err());
+        assert!(validate_content_blocks(&blocks(serde_json::json!([
+            { "type": "key_value", "rows": [] }
+        ])))
+        .is_err());
+        assert!(validate_content_blocks(&blocks(serde_json::json!([
+            { "type": "actions", "buttons": [
+                { "id": "a", "label": "A" },
+                { "id": "a", "label": "Again" }
+            ]}
+        ])))
+        .is_err());
+
+        let many = vec![ContentBlock::Text { text: "x".into() }; MAX_CONTENT_BLOCKS + 1];
+        assert!(validate_content_blocks(&Some(many)).is_err());
+
+        let large = vec![ContentBlock::Text {
+            text: "x".repeat(MAX_CONTENT_BLOCKS_BYTES),
+        }];
+        let err = validate_content_blocks(&Some(large)).unwrap_err();
+        assert_eq!(err.field, "content_blocks");
+    }
+}
@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::SanitizeProfile;
+
 /// A dialog (chat room) bound to a specific business object.
 ///
 /// Multiple dialogs can be created for the same object.
@@ -23,9 +25,49 @@ pub struct Dialog {
     /// User ID who created this dialog (external identifier)
     pub created_by: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Last change of the dialog's own fields (not of its messages)
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
     /// Free-form metadata supplied by the host application. Opaque to MTChat.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<serde_json::Value>,
+    /// IANA timezone for generated content (e.g. "Europe/Moscow")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// BCP 47 locale for generated content (e.g. "ru-RU")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Soft deletion time; deleted dialogs are hidden until restored or purged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Slow mode: minimum seconds between messages of one participant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_mode_secs: Option<i32>,
+    /// Delay before checking whether a notified message was read
+    /// (overrides the object type's and the global delay)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification_delay_ms: Option<i32>,
+    /// Response time SLA of the dialog (overrides the object type's policy)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_response_secs: Option<i32>,
+    /// SLA warning time (default: a share of `sla_response_secs`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_warning_secs: Option<i32>,
+    /// Company held to the SLA, by `company_uid` or name (default: the
+    /// creator's company)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_responder_company: Option<String>,
+    /// HTML sanitization profile of the dialog's messages (overrides tenant
+    /// and global profiles)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanitize_profile: Option<SanitizeProfile>,
+    /// Number of participants, kept up to date by a database trigger.
+    /// Returned as `participants_count` by the Chat API list responses.
+    #[serde(skip)]
+    pub participants_count: i32,
+    /// Number of read-only observers (not included in `participants_count`)
+    #[serde(skip)]
+    pub observers_count: i32,
 }
 
 impl Dialog {
@@ -38,6 +80,7 @@ impl Dialog {
         created_by: Option<String>,
         meta: Option<serde_json::Value>,
     ) -> Self {
+        let now = Utc::now();
         Self {
             id: Uuid::now_v7(),
             object_id: object_id.into(),
@@ -45,8 +88,51 @@ impl Dialog {
             title,
             object_url,
             created_by,
-            created_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
             meta,
+            timezone: None,
+            locale: None,
+            deleted_at: None,
+            slow_mode_secs: None,
+            notification_delay_ms: None,
+            sla_response_secs: None,
+            sla_warning_secs: None,
+            sla_responder_company: None,
+            sanitize_profile: None,
+            participants_count: 0,
+            observers_count: 0,
+        }
+    }
+
+    /// Set the timezone and locale used when rendering generated content
+    pub fn with_locale(mut self, timezone: Option<String>, locale: Option<String>) -> Self {
+        self.timezone = timezone;
+        self.locale = locale;
+        self
+    }
+
+    /// Locale context embedded in system messages and notification webhooks
+    pub fn locale_context(&self) -> LocaleContext {
+        LocaleContext {
+            timezone: self.timezone.clone(),
+            locale: self.locale.clone(),
         }
     }
 }
+
+/// Timezone and locale a dialog's generated content should be displayed in,
+/// so participants from different regions see the same localized times.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocaleContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+impl LocaleContext {
+    pub fn is_empty(&self) -> bool {
+        self.timezone.is_none() && self.locale.is_none()
+    }
+}
@@ -0,0 +1,100 @@
+//! Dialog activity entry
+//!
+//! Non-message events of a dialog (joins, leaves, archives, pins, access
+//! scope changes) for an activity sidebar next to the message flow. Rows are
+//! written by the activity event subscriber.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::IdGenerator;
+
+/// Maximum number of entries returned by one activity request
+pub const MAX_ACTIVITY_PAGE: i64 = 100;
+
+/// Kind of activity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum ActivityType {
+    ParticipantJoined,
+    ParticipantLeft,
+    DialogArchived,
+    DialogUnarchived,
+    DialogPinned,
+    DialogUnpinned,
+    AccessScopesChanged,
+}
+
+impl ActivityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityType::ParticipantJoined => "participant_joined",
+            ActivityType::ParticipantLeft => "participant_left",
+            ActivityType::DialogArchived => "dialog_archived",
+            ActivityType::DialogUnarchived => "dialog_unarchived",
+            ActivityType::DialogPinned => "dialog_pinned",
+            ActivityType::DialogUnpinned => "dialog_unpinned",
+            ActivityType::AccessScopesChanged => "access_scopes_changed",
+        }
+    }
+
+    /// Archiving and pinning are per participant: only the participant they
+    /// concern sees them in the feed
+    pub fn is_personal(&self) -> bool {
+        matches!(
+            self,
+            ActivityType::DialogArchived
+                | ActivityType::DialogUnarchived
+                | ActivityType::DialogPinned
+                | ActivityType::DialogUnpinned
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogActivity {
+    pub id: Uuid,
+    pub dialog_id: Uuid,
+    #[serde(rename = "type")]
+    pub activity_type: ActivityType,
+    /// Participant the activity concerns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// User whose action caused it (absent for management, jobs and integrations)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor_id: Option<String>,
+    /// Only visible to `user_id`
+    #[serde(skip)]
+    pub personal: bool,
+    /// Type-specific data, e.g. `joined_as` or the archive `trigger`
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DialogActivity {
+    pub fn new(
+        dialog_id: Uuid,
+        activity_type: ActivityType,
+        user_id: Option<String>,
+        actor_id: Option<String>,
+    ) -> Self {
+        Self {
+            id: IdGenerator::get().new_id(),
+            dialog_id,
+            activity_type,
+            user_id,
+            actor_id,
+            personal: activity_type.is_personal(),
+            details: serde_json::json!({}),
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = details;
+        self
+    }
+}
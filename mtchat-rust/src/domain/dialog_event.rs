@@ -0,0 +1,36 @@
+//! Dialog changelog entry
+//!
+//! Rows are written by database triggers on message and read-state changes;
+//! clients replay them after a `seq` cursor to resync (delta sync).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Maximum number of events returned by one sync request
+pub const MAX_SYNC_EVENTS: i64 = 500;
+
+/// A change in a dialog, ordered by `seq` within the dialog
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogEvent {
+    pub seq: i64,
+    /// `message.new`, `message.edited`, `message.deleted` or `message.read`
+    #[sqlx(rename = "event_type")]
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Affected message; for `message.read` the user's last read message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<Uuid>,
+    /// Message sender, or the reader for `message.read`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DialogEvent {
+    /// Whether the event carries the current message content
+    pub fn has_message(&self) -> bool {
+        matches!(self.event_type.as_str(), "message.new" | "message.edited")
+    }
+}
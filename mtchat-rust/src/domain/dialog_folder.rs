@@ -0,0 +1,64 @@
+//! Dialog folder entity
+//!
+//! A user's named folder ("Urgent tenders") holding saved filter criteria.
+//! Folders never store dialogs: membership is evaluated over the user's
+//! participating dialogs every time the folder is listed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Maximum number of folders per user
+pub const MAX_FOLDERS_PER_USER: i64 = 50;
+
+/// Maximum folder name length
+pub const MAX_FOLDER_NAME_LENGTH: usize = 100;
+
+/// Filter criteria for a user's participating dialogs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct DialogFilter {
+    /// Only dialogs bound to this object type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_type: Option<String>,
+    /// Dialog title or participant company contains this text (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    /// Archived state for the user (None = both)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+    /// Only dialogs with unread messages for the user
+    #[serde(default)]
+    pub unread_only: bool,
+    /// Only dialogs with unread messages mentioning the user
+    #[serde(default)]
+    pub mentions_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogFolder {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub filter: DialogFilter,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DialogFolder {
+    pub fn new(user_id: impl Into<String>, name: impl Into<String>, filter: DialogFilter) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::now_v7(),
+            user_id: user_id.into(),
+            name: name.into(),
+            filter,
+            position: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
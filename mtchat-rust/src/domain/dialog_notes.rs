@@ -0,0 +1,54 @@
+//! Dialog notes
+//!
+//! A shared description of a dialog (agreement summaries and the like),
+//! edited by the participants and shown above the messages.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Maximum length of the notes content (HTML)
+pub const MAX_DIALOG_NOTES_LENGTH: usize = 20_000;
+
+/// Maximum number of revisions in one history request
+pub const MAX_NOTES_HISTORY: i64 = 100;
+
+/// Current notes of a dialog
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogNotes {
+    pub dialog_id: Uuid,
+    /// Sanitized HTML
+    pub content: String,
+    /// Incremented on every edit (0 until the notes are first written)
+    pub version: i32,
+    /// Author of the current revision
+    pub updated_by: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl DialogNotes {
+    /// Notes of a dialog nobody has written yet
+    pub fn empty(dialog_id: Uuid) -> Self {
+        Self {
+            dialog_id,
+            content: String::new(),
+            version: 0,
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// A replaced revision of dialog notes
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogNotesRevision {
+    pub id: Uuid,
+    pub dialog_id: Uuid,
+    pub content: String,
+    pub version: i32,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+    /// When the next edit replaced this revision
+    pub replaced_at: DateTime<Utc>,
+}
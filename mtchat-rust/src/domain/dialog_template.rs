@@ -0,0 +1,80 @@
+//! Auto-create dialog template
+//!
+//! Configured per object type through the Management API. When a user opens
+//! an object that has no accessible dialog yet, the dialog is created from the
+//! template instead of the host provisioning it beforehand.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+
+/// Maximum number of access scopes in a template
+pub const MAX_TEMPLATE_SCOPES: usize = 20;
+
+/// Access scope of dialogs created from a template
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopeTemplate {
+    #[serde(default)]
+    pub scope_level0: Vec<String>,
+    #[serde(default)]
+    pub scope_level1: Vec<String>,
+    #[serde(default)]
+    pub scope_level2: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DialogTemplate {
+    pub object_type: String,
+    /// Title of created dialogs; `{object_id}` is replaced with the object ID
+    pub title: Option<String>,
+    pub access_scopes: Json<Vec<ScopeTemplate>>,
+    /// Replace `scope_level0` with the caller's tenants, so every tenant set
+    /// gets its own dialog
+    pub caller_tenant: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DialogTemplate {
+    pub fn new(object_type: impl Into<String>, access_scopes: Vec<ScopeTemplate>) -> Self {
+        Self {
+            object_type: object_type.into(),
+            title: None,
+            access_scopes: Json(access_scopes),
+            caller_tenant: true,
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn title_for(&self, object_id: &str) -> Option<String> {
+        self.title
+            .as_ref()
+            .map(|title| title.replace("{object_id}", object_id))
+    }
+
+    /// Access scopes of a dialog created for a caller with the given tenants
+    pub fn scopes_for(&self, caller_tenants: &[String]) -> Vec<ScopeTemplate> {
+        self.access_scopes
+            .iter()
+            .cloned()
+            .map(|mut scope| {
+                if self.caller_tenant {
+                    scope.scope_level0 = caller_tenants.to_vec();
+                }
+                scope
+            })
+            .collect()
+    }
+
+    /// Key identifying the dialog created for a caller: the sorted tenants
+    /// with `caller_tenant`, otherwise one shared dialog per object
+    pub fn creation_key(&self, caller_tenants: &[String]) -> String {
+        if !self.caller_tenant {
+            return String::new();
+        }
+        let mut tenants = caller_tenants.to_vec();
+        tenants.sort();
+        tenants.dedup();
+        tenants.join("\n")
+    }
+}
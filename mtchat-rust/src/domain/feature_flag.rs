@@ -0,0 +1,134 @@
+//! Feature flag overrides
+//!
+//! Flags are on or off globally (the `feature_flags` runtime setting) and can
+//! be overridden per tenant and per dialog. A dialog override wins over tenant
+//! overrides, which win over the global value.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+
+/// Maximum flag name length
+pub const MAX_FLAG_LENGTH: usize = 64;
+
+/// What a flag override applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum FlagScope {
+    /// Every dialog of a tenant (scope_id = scope_level0 value)
+    Tenant,
+    /// A single dialog (scope_id = dialog UUID)
+    Dialog,
+}
+
+impl FlagScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagScope::Tenant => "tenant",
+            FlagScope::Dialog => "dialog",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FeatureFlagOverride {
+    pub flag: String,
+    pub scope_type: FlagScope,
+    pub scope_id: String,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Flag names are lowercase identifiers (`reactions`, `message_threads`)
+pub fn is_valid_flag_name(flag: &str) -> bool {
+    !flag.is_empty()
+        && flag.len() <= MAX_FLAG_LENGTH
+        && flag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Resolve the effective flags of a dialog from the global values and the
+/// overrides of the dialog and its tenants.
+///
+/// When the dialog's tenants disagree, a tenant that disabled the flag wins,
+/// so a rollout never leaks into a tenant that opted out.
+pub fn resolve_flags(
+    global: &BTreeMap<String, bool>,
+    overrides: &[FeatureFlagOverride],
+) -> BTreeMap<String, bool> {
+    let mut flags = global.clone();
+
+    let mut tenant: BTreeMap<&str, bool> = BTreeMap::new();
+    for o in overrides
+        .iter()
+        .filter(|o| o.scope_type == FlagScope::Tenant)
+    {
+        tenant
+            .entry(o.flag.as_str())
+            .and_modify(|enabled| *enabled &= o.enabled)
+            .or_insert(o.enabled);
+    }
+    for (flag, enabled) in tenant {
+        flags.insert(flag.to_string(), enabled);
+    }
+
+    for o in overrides
+        .iter()
+        .filter(|o| o.scope_type == FlagScope::Dialog)
+    {
+        flags.insert(o.flag.clone(), o.enabled);
+    }
+
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn o(flag: &str, scope_type: FlagScope, enabled: bool) -> FeatureFlagOverride {
+        FeatureFlagOverride {
+            flag: flag.to_string(),
+            scope_type,
+            scope_id: "x".to_string(),
+            enabled,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let global = BTreeMap::from([
+            ("reactions".to_string(), false),
+            ("threads".to_string(), true),
+        ]);
+        let flags = resolve_flags(
+            &global,
+            &[
+                o("reactions", FlagScope::Tenant, true),
+                o("threads", FlagScope::Tenant, false),
+                o("threads", FlagScope::Dialog, true),
+                o("polls", FlagScope::Tenant, true),
+                o("polls", FlagScope::Tenant, false),
+            ],
+        );
+
+        assert!(flags["reactions"]);
+        assert!(flags["threads"]);
+        // Conflicting tenants: disabled wins
+        assert!(!flags["polls"]);
+    }
+
+    #[test]
+    fn test_flag_name_validation() {
+        assert!(is_valid_flag_name("reactions"));
+        assert!(is_valid_flag_name("message_threads-v2"));
+        assert!(!is_valid_flag_name(""));
+        assert!(!is_valid_flag_name("Reactions"));
+        assert!(!is_valid_flag_name("a b"));
+        assert!(!is_valid_flag_name(&"a".repeat(MAX_FLAG_LENGTH + 1)));
+    }
+}
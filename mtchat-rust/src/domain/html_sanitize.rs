@@ -1,40 +1,103 @@
 //! HTML Sanitization for message content
 //!
-//! Allows safe HTML tags while preventing XSS attacks.
+//! Allows safe HTML tags while preventing XSS attacks. Deployments choose how
+//! much formatting to keep with a [`SanitizeProfile`]: globally (the
+//! `sanitize_profile` runtime setting), per tenant or per dialog.
 
 use ammonia::Builder;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
-/// Create a configured sanitizer
-fn create_sanitizer() -> Builder<'static> {
-    let mut builder = Builder::default();
+/// Named set of allowed tags, from the strictest to the most permissive
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum SanitizeProfile {
+    /// Paragraphs, bold, italic, links and mentions
+    Minimal,
+    /// Message formatting of the widget editor (lists, quotes, code)
+    #[default]
+    Standard,
+    /// Standard plus tables and images
+    Rich,
+}
+
+impl SanitizeProfile {
+    pub const ALL: [SanitizeProfile; 3] = [Self::Minimal, Self::Standard, Self::Rich];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Standard => "standard",
+            Self::Rich => "rich",
+        }
+    }
+
+    /// Effective profile of a dialog: its own profile, else the strictest
+    /// profile among its tenants, else the global default
+    pub fn resolve(
+        dialog: Option<SanitizeProfile>,
+        tenants: &[SanitizeProfile],
+        global: SanitizeProfile,
+    ) -> SanitizeProfile {
+        dialog
+            .or_else(|| tenants.iter().min().copied())
+            .unwrap_or(global)
+    }
+
+    /// Tags kept by this profile
+    pub fn allowed_tags(&self) -> Vec<&'static str> {
+        // Paragraphs, bold, italic, links, mentions
+        const MINIMAL: [&str; 8] = ["p", "br", "strong", "b", "em", "i", "a", "span"];
+        // Underline, strikethrough (`del` from Markdown), lists, quotes, code
+        const STANDARD: [&str; 10] = [
+            "u",
+            "s",
+            "strike",
+            "del",
+            "ul",
+            "ol",
+            "li",
+            "blockquote",
+            "code",
+            "pre",
+        ];
+        // Tables and images
+        const RICH: [&str; 9] = [
+            "table", "thead", "tbody", "tfoot", "tr", "th", "td", "caption", "img",
+        ];
+
+        let mut tags = MINIMAL.to_vec();
+        if *self >= Self::Standard {
+            tags.extend(STANDARD);
+        }
+        if *self >= Self::Rich {
+            tags.extend(RICH);
+        }
+        tags
+    }
+}
 
-    // Allowed HTML tags for message formatting
-    let allowed_tags: HashSet<&str> = [
-        "p",
-        "br", // Paragraphs and line breaks
-        "strong",
-        "b", // Bold
-        "em",
-        "i", // Italic
-        "u", // Underline
-        "s",
-        "strike", // Strikethrough
-        "a",      // Links
-        "ul",
-        "ol",
-        "li",         // Lists
-        "blockquote", // Quotes
-        "code",
-        "pre",  // Code
-        "span", // For mentions
-    ]
-    .into_iter()
-    .collect();
+/// Create a sanitizer for a profile
+fn create_sanitizer(profile: SanitizeProfile) -> Builder<'static> {
+    let mut builder = Builder::default();
 
     // Set allowed tags
-    builder.tags(allowed_tags);
+    builder.tags(profile.allowed_tags().into_iter().collect());
 
     // Allowed attributes for specific tags
     // Note: "rel" is handled specially by ammonia via link_rel()
@@ -53,8 +116,20 @@ fn create_sanitizer() -> Builder<'static> {
         .into_iter()
         .collect(),
     );
-    tag_attributes.insert("code", ["class"].into_iter().collect());
-    tag_attributes.insert("pre", ["class"].into_iter().collect());
+    if profile >= SanitizeProfile::Standard {
+        tag_attributes.insert("code", ["class"].into_iter().collect());
+        tag_attributes.insert("pre", ["class"].into_iter().collect());
+    }
+    if profile >= SanitizeProfile::Rich {
+        tag_attributes.insert("th", ["colspan", "rowspan"].into_iter().collect());
+        tag_attributes.insert("td", ["colspan", "rowspan"].into_iter().collect());
+        tag_attributes.insert(
+            "img",
+            ["src", "alt", "title", "width", "height"]
+                .into_iter()
+                .collect(),
+        );
+    }
 
     // Set allowed attributes for each tag
     builder.tag_attributes(tag_attributes);
@@ -62,7 +137,7 @@ fn create_sanitizer() -> Builder<'static> {
     // Set link rel attribute (prevents opener attacks)
     builder.link_rel(Some("noopener noreferrer"));
 
-    // Only allow http and https URLs
+    // Only allow http and https URLs (links and image sources)
     builder.url_schemes(["http", "https"].into_iter().collect());
 
     // Strip dangerous content
@@ -71,9 +146,22 @@ fn create_sanitizer() -> Builder<'static> {
     builder
 }
 
-static SANITIZER: Lazy<Builder<'static>> = Lazy::new(create_sanitizer);
+static MINIMAL_SANITIZER: Lazy<Builder<'static>> =
+    Lazy::new(|| create_sanitizer(SanitizeProfile::Minimal));
+static STANDARD_SANITIZER: Lazy<Builder<'static>> =
+    Lazy::new(|| create_sanitizer(SanitizeProfile::Standard));
+static RICH_SANITIZER: Lazy<Builder<'static>> =
+    Lazy::new(|| create_sanitizer(SanitizeProfile::Rich));
+
+fn sanitizer(profile: SanitizeProfile) -> &'static Builder<'static> {
+    match profile {
+        SanitizeProfile::Minimal => &MINIMAL_SANITIZER,
+        SanitizeProfile::Standard => &STANDARD_SANITIZER,
+        SanitizeProfile::Rich => &RICH_SANITIZER,
+    }
+}
 
-/// Sanitize HTML content from user input
+/// Sanitize HTML content from user input with the standard profile
 ///
 /// Removes dangerous elements like:
 /// - script, style, iframe tags
@@ -81,14 +169,19 @@ static SANITIZER: Lazy<Builder<'static>> = Lazy::new(create_sanitizer);
 /// - javascript: URLs
 ///
 /// Preserves formatting tags:
-/// - p, br, strong, em, u, s, a, ul, ol, li, blockquote, code, pre, span
+/// - p, br, strong, em, u, s, del, a, ul, ol, li, blockquote, code, pre, span
 pub fn sanitize_html(html: &str) -> String {
+    sanitize_html_with(html, SanitizeProfile::Standard)
+}
+
+/// Sanitize HTML content, keeping the tags of `profile`
+pub fn sanitize_html_with(html: &str, profile: SanitizeProfile) -> String {
     // If content doesn't look like HTML, just escape and return
     if !html.contains('<') {
         return ammonia::clean(html);
     }
 
-    SANITIZER.clean(html).to_string()
+    sanitizer(profile).clean(html).to_string()
 }
 
 #[cfg(test)]
@@ -201,4 +294,66 @@ mod tests {
         // Script tag should be removed, content preserved as text
         assert!(!output.contains("<script>"));
     }
+
+    #[test]
+    fn test_minimal_profile() {
+        let input = r#"<p><strong>Bold</strong> <a href="https://example.com">link</a></p><ul><li>Item</li></ul>"#;
+        let output = sanitize_html_with(input, SanitizeProfile::Minimal);
+        assert!(output.contains("<strong>"));
+        assert!(output.contains("<a"));
+        assert!(!output.contains("<ul>"));
+        assert!(!output.contains("<li>"));
+        assert!(output.contains("Item"));
+    }
+
+    #[test]
+    fn test_standard_profile_strips_tables_and_images() {
+        let input = r#"<table><tr><td>Cell</td></tr></table><img src="https://example.com/a.png">"#;
+        let output = sanitize_html(input);
+        assert!(!output.contains("<table>"));
+        assert!(!output.contains("<img"));
+        assert!(output.contains("Cell"));
+    }
+
+    #[test]
+    fn test_rich_profile_keeps_tables_and_images() {
+        let input = r#"<table><tbody><tr><td colspan="2" onclick="x()">Cell</td></tr></tbody></table><img src="https://example.com/a.png" alt="Chart" onerror="x()">"#;
+        let output = sanitize_html_with(input, SanitizeProfile::Rich);
+        assert!(output.contains("<table>"));
+        assert!(output.contains(r#"<td colspan="2">"#));
+        assert!(output.contains(r#"<img src="https://example.com/a.png" alt="Chart">"#));
+        assert!(!output.contains("onclick"));
+        assert!(!output.contains("onerror"));
+
+        let output =
+            sanitize_html_with(r#"<img src="javascript:alert(1)">"#, SanitizeProfile::Rich);
+        assert!(!output.contains("javascript:"));
+    }
+
+    #[test]
+    fn test_resolve_profile() {
+        use SanitizeProfile::*;
+        assert_eq!(SanitizeProfile::resolve(None, &[], Standard), Standard);
+        assert_eq!(SanitizeProfile::resolve(None, &[Rich], Standard), Rich);
+        // Tenants that disagree get the strictest profile
+        assert_eq!(
+            SanitizeProfile::resolve(None, &[Rich, Minimal], Standard),
+            Minimal
+        );
+        assert_eq!(
+            SanitizeProfile::resolve(Some(Rich), &[Minimal], Standard),
+            Rich
+        );
+    }
+
+    #[test]
+    fn test_profile_order_and_names() {
+        assert!(SanitizeProfile::Minimal < SanitizeProfile::Standard);
+        assert!(SanitizeProfile::Standard < SanitizeProfile::Rich);
+        assert_eq!(SanitizeProfile::default(), SanitizeProfile::Standard);
+        for profile in SanitizeProfile::ALL {
+            let json = serde_json::to_value(profile).unwrap();
+            assert_eq!(json, profile.as_str());
+        }
+    }
 }
@@ -0,0 +1,187 @@
+//! External message IDs
+//!
+//! Primary keys are always UUIDv7. Every message also gets an `external_id`
+//! in the format the deployment chose at startup, for host systems that
+//! map messages to IDs of their own shape:
+//!
+//! - `uuid` (default): the message's UUIDv7, as text
+//! - `ulid`: ULID with the same timestamp and randomness as the UUIDv7
+//! - `snowflake`: 64-bit number (41 bits of milliseconds since 2024-01-01,
+//!   10 bits of worker ID, 12 bits of sequence), as decimal text
+//!
+//! Environment variables:
+//! - `EXTERNAL_ID_FORMAT` - `uuid`, `ulid` or `snowflake` (default: `uuid`)
+//! - `SNOWFLAKE_WORKER_ID` - Worker ID of this instance, 0-1023 (default: 0)
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use uuid::Uuid;
+
+static ID_GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
+
+/// Snowflake epoch, 2024-01-01T00:00:00Z in Unix milliseconds
+pub const SNOWFLAKE_EPOCH_MS: u64 = 1_704_067_200_000;
+
+/// Largest Snowflake worker ID (10 bits)
+pub const MAX_SNOWFLAKE_WORKER_ID: u16 = 1023;
+
+const SNOWFLAKE_SEQUENCE_BITS: u32 = 12;
+const SNOWFLAKE_WORKER_BITS: u32 = 10;
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Format of external message IDs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdFormat {
+    #[default]
+    Uuid,
+    Ulid,
+    Snowflake,
+}
+
+/// External ID settings (`[ids]` section)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdConfig {
+    pub external_format: IdFormat,
+    /// Distinct per instance when several instances generate Snowflake IDs
+    pub snowflake_worker_id: u16,
+}
+
+/// Generator of external message IDs
+#[derive(Debug)]
+pub struct IdGenerator {
+    format: IdFormat,
+    worker_id: u64,
+    /// Last Snowflake millisecond and the sequence used within it
+    snowflake_state: Mutex<(u64, u64)>,
+}
+
+impl IdGenerator {
+    pub fn new(config: &IdConfig) -> Self {
+        Self {
+            format: config.external_format,
+            worker_id: u64::from(config.snowflake_worker_id.min(MAX_SNOWFLAKE_WORKER_ID)),
+            snowflake_state: Mutex::new((0, 0)),
+        }
+    }
+
+    /// Install the global generator. Called once at startup.
+    pub fn init(config: &IdConfig) {
+        let generator = ID_GENERATOR.get_or_init(|| Self::new(config));
+        tracing::info!("External message ID format: {:?}", generator.format);
+    }
+
+    /// Get the global generator (UUIDs if `init` was not called)
+    pub fn get() -> &'static IdGenerator {
+        ID_GENERATOR.get_or_init(|| Self::new(&IdConfig::default()))
+    }
+
+    /// New primary key
+    pub fn new_id(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+
+    /// External ID for a new record with primary key `id`
+    pub fn external_id(&self, id: Uuid) -> String {
+        match self.format {
+            IdFormat::Uuid => id.to_string(),
+            IdFormat::Ulid => ulid_from_uuid(id),
+            IdFormat::Snowflake => self.next_snowflake().to_string(),
+        }
+    }
+
+    fn next_snowflake(&self) -> u64 {
+        let mut state = self
+            .snowflake_state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let (last_ms, sequence) = *state;
+        // Never go back in time, even if the clock does
+        let mut now = snowflake_millis().max(last_ms);
+        let mut next = if now == last_ms { sequence + 1 } else { 0 };
+        if next >> SNOWFLAKE_SEQUENCE_BITS != 0 {
+            // Sequence exhausted for this millisecond: wait for the next one
+            while snowflake_millis() <= now {
+                std::hint::spin_loop();
+            }
+            now += 1;
+            next = 0;
+        }
+        *state = (now, next);
+        (now << (SNOWFLAKE_WORKER_BITS + SNOWFLAKE_SEQUENCE_BITS))
+            | (self.worker_id << SNOWFLAKE_SEQUENCE_BITS)
+            | next
+    }
+}
+
+fn snowflake_millis() -> u64 {
+    let unix_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    unix_ms.saturating_sub(SNOWFLAKE_EPOCH_MS)
+}
+
+/// Encode the 128 bits of a UUIDv7 as a ULID. Both put a 48-bit Unix
+/// millisecond timestamp first, so the ULID sorts like the UUID.
+fn ulid_from_uuid(id: Uuid) -> String {
+    let value = id.as_u128();
+    (0..26)
+        .rev()
+        .map(|i| CROCKFORD_ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(format: IdFormat, worker_id: u16) -> IdGenerator {
+        IdGenerator::new(&IdConfig {
+            external_format: format,
+            snowflake_worker_id: worker_id,
+        })
+    }
+
+    #[test]
+    fn test_uuid_format_mirrors_primary_key() {
+        let generator = generator(IdFormat::Uuid, 0);
+        let id = generator.new_id();
+        assert_eq!(generator.external_id(id), id.to_string());
+    }
+
+    #[test]
+    fn test_ulid_format() {
+        let generator = generator(IdFormat::Ulid, 0);
+        assert_eq!(
+            generator.external_id(Uuid::nil()),
+            "00000000000000000000000000"
+        );
+
+        let first = generator.new_id();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = generator.new_id();
+        let (a, b) = (generator.external_id(first), generator.external_id(second));
+        assert_eq!(a.len(), 26);
+        assert!(a.chars().all(|c| CROCKFORD_ALPHABET.contains(&(c as u8))));
+        assert!(a < b, "ULIDs sort by time: {} {}", a, b);
+    }
+
+    #[test]
+    fn test_snowflake_format() {
+        let generator = generator(IdFormat::Snowflake, 5);
+        let ids: Vec<u64> = (0..5000)
+            .map(|_| generator.external_id(Uuid::nil()).parse().unwrap())
+            .collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]), "strictly increasing");
+        assert!(ids
+            .iter()
+            .all(|id| (id >> SNOWFLAKE_SEQUENCE_BITS) & 0x3ff == 5));
+        assert!(ids[0] < i64::MAX as u64);
+    }
+
+    #[test]
+    fn test_id_format_serde() {
+        let format: IdFormat = serde_json::from_str(r#""snowflake""#).unwrap();
+        assert_eq!(format, IdFormat::Snowflake);
+        assert_eq!(IdFormat::default(), IdFormat::Uuid);
+    }
+}
@@ -0,0 +1,69 @@
+//! Participant invitations by email
+//!
+//! External suppliers are often invited before the host system has a user
+//! ID for them. Management stores the invite with the email and profile; once
+//! the host knows the user ID it activates the email's invites, which turns
+//! them into participants. Until then invites are listed as pending
+//! participants.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{IdGenerator, JoinedAs, ParticipantProfile};
+
+/// Pending invitation of a participant by email
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ParticipantInvite {
+    pub id: Uuid,
+    pub dialog_id: Uuid,
+    /// Normalized (trimmed, lowercase) email the invite is activated by
+    pub email: String,
+    /// Role the participant gets on activation
+    pub joined_as: JoinedAs,
+    pub display_name: String,
+    pub company: Option<String>,
+    pub company_uid: Option<String>,
+    pub phone: Option<String>,
+    pub invited_at: DateTime<Utc>,
+}
+
+impl ParticipantInvite {
+    /// Invite with the profile the participant gets on activation; the
+    /// profile email is replaced by the invite email
+    pub fn new(
+        dialog_id: Uuid,
+        email: &str,
+        joined_as: JoinedAs,
+        profile: ParticipantProfile,
+    ) -> Self {
+        Self {
+            id: IdGenerator::get().new_id(),
+            dialog_id,
+            email: normalize_email(email),
+            joined_as,
+            display_name: profile.display_name,
+            company: profile.company,
+            company_uid: profile.company_uid,
+            phone: profile.phone,
+            invited_at: Utc::now(),
+        }
+    }
+
+    /// Profile of the participant the invite turns into
+    pub fn profile(&self) -> ParticipantProfile {
+        ParticipantProfile {
+            display_name: self.display_name.clone(),
+            company: self.company.clone(),
+            company_uid: self.company_uid.clone(),
+            email: Some(self.email.clone()),
+            phone: self.phone.clone(),
+        }
+    }
+}
+
+/// Email as invites are stored and looked up: trimmed and lowercase
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
@@ -0,0 +1,47 @@
+//! Dead-lettered background job
+//!
+//! A queue job that failed permanently, kept with its payload so it can be
+//! inspected and re-enqueued by hand.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Maximum number of dead letters returned by one request
+pub const MAX_DEAD_LETTERS: i64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct JobDeadLetter {
+    pub id: Uuid,
+    /// Queue the job came from, e.g. `notification`
+    pub job_type: String,
+    /// Task ID in the queue
+    pub task_id: String,
+    /// The job as enqueued
+    pub payload: serde_json::Value,
+    /// Error of the last attempt
+    pub error: String,
+    pub attempts: i32,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl JobDeadLetter {
+    pub fn new(
+        job_type: &str,
+        task_id: impl Into<String>,
+        payload: serde_json::Value,
+        error: impl Into<String>,
+        attempts: usize,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            job_type: job_type.to_string(),
+            task_id: task_id.into(),
+            payload,
+            error: error.into(),
+            attempts: attempts.min(i32::MAX as usize) as i32,
+            failed_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Markdown message input
+//!
+//! Clients that don't produce HTML can send `content_format: "markdown"`.
+//! The server renders the source to HTML, which is sanitized like any other
+//! message content and stored as `content`; the source is stored next to it
+//! so clients can ask for it back.
+
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+
+/// Format of message content in requests and responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFormat {
+    #[default]
+    Html,
+    Markdown,
+}
+
+impl ContentFormat {
+    pub fn is_markdown(&self) -> bool {
+        *self == Self::Markdown
+    }
+}
+
+/// Render Markdown (CommonMark with tables and strikethrough) to HTML.
+///
+/// The output is not safe to store as is: raw HTML in the source passes
+/// through, so sanitize it like HTML input.
+pub fn render_markdown(source: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut output = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut output, Parser::new_ext(source, options));
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::sanitize_html;
+
+    #[test]
+    fn test_render_formatting() {
+        let html = render_markdown("**Bold** and _italic_ and ~~gone~~");
+        assert_eq!(
+            html,
+            "<p><strong>Bold</strong> and <em>italic</em> and <del>gone</del></p>"
+        );
+    }
+
+    #[test]
+    fn test_render_lists_and_links() {
+        let html = render_markdown("- [Order](https://example.com/o/1)\n- Second");
+        assert!(html.contains("<ul>"));
+        assert!(html.contains(r#"<a href="https://example.com/o/1">Order</a>"#));
+    }
+
+    #[test]
+    fn test_raw_html_is_sanitized_afterwards() {
+        let html = render_markdown("Hi <script>alert(1)</script> [x](javascript:alert(1))");
+        let sanitized = sanitize_html(&html);
+        assert!(!sanitized.contains("<script>"));
+        assert!(!sanitized.contains("javascript:"));
+
+        // Strikethrough survives the standard profile
+        assert!(sanitize_html(&render_markdown("~~gone~~")).contains("<del>"));
+    }
+
+    #[test]
+    fn test_content_format_serde() {
+        let format: ContentFormat = serde_json::from_str(r#""markdown""#).unwrap();
+        assert!(format.is_markdown());
+        assert_eq!(ContentFormat::default(), ContentFormat::Html);
+    }
+}
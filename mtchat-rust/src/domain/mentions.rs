@@ -0,0 +1,174 @@
+//! Mention extraction from message content
+//!
+//! Mentions are `<span>` elements produced by the editor, either the Tiptap
+//! format (`data-type="mention" data-id="..."`) or the legacy
+//! `data-mention="..."` attribute. Extraction runs on sanitized HTML, where
+//! attribute values are always double-quoted.
+//!
+//! Broadcast mentions are the plain-text tokens `@channel` and `@here`.
+
+use serde::{Deserialize, Serialize};
+
+use super::JoinedAs;
+
+/// Maximum number of distinct users recorded as mentioned in one message
+pub const MAX_MENTIONS_PER_MESSAGE: usize = 50;
+
+/// User IDs mentioned in sanitized message HTML, deduplicated in order of
+/// first appearance
+pub fn extract_mentions(html: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find("<span") {
+        let tag = &rest[start..];
+        let end = tag.find('>').unwrap_or(tag.len());
+        let attrs = &tag[..end];
+        rest = &tag[end..];
+
+        let user_id = attr(attrs, "data-mention").or_else(|| {
+            (attr(attrs, "data-type") == Some("mention"))
+                .then(|| attr(attrs, "data-id"))
+                .flatten()
+        });
+
+        if let Some(user_id) = user_id.filter(|id| !id.is_empty()) {
+            if !mentions.iter().any(|m| m == user_id) {
+                mentions.push(user_id.to_string());
+                if mentions.len() == MAX_MENTIONS_PER_MESSAGE {
+                    break;
+                }
+            }
+        }
+    }
+
+    mentions
+}
+
+/// Special mention addressing several participants at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastMention {
+    /// `@channel`: every participant of the dialog
+    Channel,
+    /// `@here`: participants online when the message is sent
+    Here,
+}
+
+impl BroadcastMention {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BroadcastMention::Channel => "channel",
+            BroadcastMention::Here => "here",
+        }
+    }
+
+    /// Whether a participant may use broadcast mentions.
+    ///
+    /// Users who joined on their own via scope access cannot page the
+    /// whole dialog; the creator and invited participants can.
+    pub fn allowed_for(joined_as: &JoinedAs) -> bool {
+        !matches!(joined_as, JoinedAs::Joined | JoinedAs::Observer)
+    }
+}
+
+/// Broadcast mention in message HTML (`@channel` wins over `@here`)
+pub fn extract_broadcast_mention(html: &str) -> Option<BroadcastMention> {
+    if has_token(html, "@channel") {
+        Some(BroadcastMention::Channel)
+    } else if has_token(html, "@here") {
+        Some(BroadcastMention::Here)
+    } else {
+        None
+    }
+}
+
+/// Whether `token` occurs as a standalone word (not inside an email,
+/// URL or longer name)
+fn has_token(text: &str, token: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '@' | '/');
+    text.match_indices(token).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + token.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Value of a double-quoted attribute inside a tag
+fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {}=\"", name);
+    let start = tag.find(&needle)? + needle.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_tiptap_mentions() {
+        let html = r#"<p>Hi <span data-type="mention" data-id="user-1" data-label="Ann" class="mtchat-mention">@Ann</span> and <span data-type="mention" data-id="user-2">@Bob</span></p>"#;
+        assert_eq!(extract_mentions(html), vec!["user-1", "user-2"]);
+    }
+
+    #[test]
+    fn test_extracts_legacy_mentions() {
+        let html = r#"<span data-mention="user-1" class="mtchat-mention">@User</span>"#;
+        assert_eq!(extract_mentions(html), vec!["user-1"]);
+    }
+
+    #[test]
+    fn test_deduplicates() {
+        let html =
+            r#"<span data-mention="u">@U</span> <span data-type="mention" data-id="u">@U</span>"#;
+        assert_eq!(extract_mentions(html), vec!["u"]);
+    }
+
+    #[test]
+    fn test_ignores_other_spans() {
+        let html = r#"<span class="highlight" data-id="user-1">text</span>"#;
+        assert!(extract_mentions(html).is_empty());
+        assert!(extract_mentions("plain text").is_empty());
+    }
+
+    #[test]
+    fn test_broadcast_mentions() {
+        assert_eq!(
+            extract_broadcast_mention("<p>@channel please review</p>"),
+            Some(BroadcastMention::Channel)
+        );
+        assert_eq!(
+            extract_broadcast_mention("Hi @here, anyone?"),
+            Some(BroadcastMention::Here)
+        );
+        assert_eq!(
+            extract_broadcast_mention("@here and @channel"),
+            Some(BroadcastMention::Channel)
+        );
+    }
+
+    #[test]
+    fn test_broadcast_mentions_need_standalone_token() {
+        assert_eq!(extract_broadcast_mention("mail me@channel.com"), None);
+        assert_eq!(extract_broadcast_mention("@channels are fun"), None);
+        assert_eq!(extract_broadcast_mention("@heretic"), None);
+        assert_eq!(extract_broadcast_mention("no mention here"), None);
+    }
+
+    #[test]
+    fn test_broadcast_mention_roles() {
+        assert!(BroadcastMention::allowed_for(&JoinedAs::Creator));
+        assert!(BroadcastMention::allowed_for(&JoinedAs::Participant));
+        assert!(!BroadcastMention::allowed_for(&JoinedAs::Joined));
+        assert!(!BroadcastMention::allowed_for(&JoinedAs::Observer));
+    }
+
+    #[test]
+    fn test_caps_mentions() {
+        let html: String = (0..MAX_MENTIONS_PER_MESSAGE + 10)
+            .map(|i| format!(r#"<span data-mention="u{}">@u</span>"#, i))
+            .collect();
+        assert_eq!(extract_mentions(&html).len(), MAX_MENTIONS_PER_MESSAGE);
+    }
+}
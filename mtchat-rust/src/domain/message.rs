@@ -1,9 +1,32 @@
 //! Message entity
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
-use uuid::Uuid;
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::{FromRow, Row};
+use uuid::{NoContext, Timestamp, Uuid};
+
+use super::{ActionButton, ContentBlock, ContentFormat, IdGenerator, JoinedAs, StoredContent};
+
+/// Maximum number of messages in one import request
+pub const MAX_IMPORT_MESSAGES: usize = 500;
+
+/// Maximum number of messages in one batch send
+pub const MAX_BATCH_MESSAGES: usize = 100;
+
+/// Maximum number of question/answer pairs in one export page
+pub const MAX_QA_PAIRS: i64 = 500;
+
+/// Characters of the original message content shown in a reply preview
+pub const REPLY_PREVIEW_CHARS: usize = 200;
+
+/// Characters of the last message content shown in the dialog list (also
+/// in the `dialog_list_preview` database function)
+pub const LAST_MESSAGE_PREVIEW_CHARS: usize = 200;
+
+/// Maximum number of days in one message calendar request
+pub const MAX_CALENDAR_DAYS: i64 = 366;
 
 /// Message type: user-sent or system-generated
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -52,49 +75,253 @@ impl sqlx::Type<sqlx::Postgres> for MessageType {
     }
 }
 
+/// Message priority: urgent messages skip the notification delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum MessagePriority {
+    #[default]
+    Normal,
+    Urgent,
+}
+
+impl MessagePriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessagePriority::Normal => "normal",
+            MessagePriority::Urgent => "urgent",
+        }
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        matches!(self, MessagePriority::Urgent)
+    }
+
+    /// Whether a participant may send messages with this priority.
+    ///
+    /// Like broadcast mentions, urgent messages are limited to the creator
+    /// and invited participants.
+    pub fn allowed_for(&self, joined_as: &JoinedAs) -> bool {
+        !self.is_urgent() || !matches!(joined_as, JoinedAs::Joined | JoinedAs::Observer)
+    }
+}
+
 /// A message in a dialog
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub id: Uuid,
+    /// ID in the deployment's external ID format, for mapping to host systems
+    pub external_id: String,
     pub dialog_id: Uuid,
     /// External user identifier (from JWT token or host system). NULL for system messages.
     pub sender_id: Option<String>,
     pub content: String,
     pub sent_at: DateTime<Utc>,
     pub last_edited_at: Option<DateTime<Utc>>,
+    /// Last change of the message (edits, metadata)
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
     /// Reference to the message this is a reply to
     pub reply_to_id: Option<Uuid>,
     /// Message type: 'user' or 'system'
     #[serde(default)]
     pub message_type: MessageType,
+    /// `normal` or `urgent`
+    #[serde(default)]
+    pub priority: MessagePriority,
+    /// Monotonic per-dialog sequence number, assigned by the database on insert
+    /// (0 until the message is stored)
+    #[serde(default)]
+    pub seq: i64,
+    /// Incremented on every edit, for conditional edits
+    #[serde(default)]
+    pub version: i32,
+    /// Integration data attached by the host system (JSON object)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Structured content rendered next to `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_blocks: Option<Json<Vec<ContentBlock>>>,
+    /// Markdown source of `content` for messages written in Markdown
+    #[serde(skip)]
+    pub content_markdown: Option<String>,
+    /// Format of `content` as returned: `markdown` only when the client asked
+    /// for it and the message has a Markdown source
+    #[serde(default)]
+    pub content_format: ContentFormat,
+    /// Sender display name at send time (snapshotted by the database)
+    #[serde(skip)]
+    pub sender_display_name: Option<String>,
+    /// Sender company at send time (snapshotted by the database)
+    #[serde(skip)]
+    pub sender_company: Option<String>,
+}
+
+// Loaded by hand to decompress the stored content (see `StoredContent`)
+impl<'r> FromRow<'r, PgRow> for Message {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let content =
+            StoredContent::from_row(row)?
+                .decode()
+                .map_err(|source| sqlx::Error::ColumnDecode {
+                    index: "content_compressed".to_string(),
+                    source,
+                })?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            external_id: row.try_get("external_id")?,
+            dialog_id: row.try_get("dialog_id")?,
+            sender_id: row.try_get("sender_id")?,
+            content,
+            sent_at: row.try_get("sent_at")?,
+            last_edited_at: row.try_get("last_edited_at")?,
+            updated_at: row.try_get("updated_at")?,
+            reply_to_id: row.try_get("reply_to_id")?,
+            message_type: row.try_get("message_type")?,
+            priority: row.try_get("priority")?,
+            seq: row.try_get("seq")?,
+            version: row.try_get("version")?,
+            metadata: row.try_get("metadata")?,
+            content_blocks: row.try_get("content_blocks")?,
+            content_markdown: row.try_get("content_markdown")?,
+            content_format: ContentFormat::default(),
+            sender_display_name: row.try_get("sender_display_name")?,
+            sender_company: row.try_get("sender_company")?,
+        })
+    }
+}
+
+/// Compact view of the message a reply refers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplyPreview {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_id: Option<String>,
+    /// First [`REPLY_PREVIEW_CHARS`] characters of the content
+    pub content: String,
+    /// The original message was deleted (sender and content are empty)
+    pub deleted: bool,
+}
+
+impl ReplyPreview {
+    /// Preview of an existing message
+    pub fn of(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            sender_id: message.sender_id.clone(),
+            content: message.content.chars().take(REPLY_PREVIEW_CHARS).collect(),
+            deleted: false,
+        }
+    }
+
+    /// Preview of a message that no longer exists
+    pub fn deleted(id: Uuid) -> Self {
+        Self {
+            id,
+            sender_id: None,
+            content: String::new(),
+            deleted: true,
+        }
+    }
+}
+
+/// Latest message of a dialog as shown in the dialog list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessagePreview {
+    pub id: Uuid,
+    pub sender_id: Option<String>,
+    /// First [`LAST_MESSAGE_PREVIEW_CHARS`] characters of the content
+    pub content: String,
+    pub sent_at: DateTime<Utc>,
+    pub message_type: MessageType,
+}
+
+impl MessagePreview {
+    pub fn of(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            sender_id: message.sender_id.clone(),
+            content: message
+                .content
+                .chars()
+                .take(LAST_MESSAGE_PREVIEW_CHARS)
+                .collect(),
+            sent_at: message.sent_at,
+            message_type: message.message_type,
+        }
+    }
+}
+
+/// Number of messages sent on one day, for jump-to-date pickers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct MessageDayCount {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+/// Sender profile of a message as it was when the message was sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SenderProfile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
 }
 
 impl Message {
     /// Create a new user message
     pub fn new(dialog_id: Uuid, sender_id: impl Into<String>, content: impl Into<String>) -> Self {
+        let ids = IdGenerator::get();
+        let id = ids.new_id(); // Time-ordered UUID for efficient sorting
+        let now = Utc::now();
         Self {
-            id: Uuid::now_v7(), // Time-ordered UUID for efficient sorting
+            id,
+            external_id: ids.external_id(id),
             dialog_id,
             sender_id: Some(sender_id.into()),
             content: content.into(),
-            sent_at: Utc::now(),
+            sent_at: now,
             last_edited_at: None,
+            updated_at: now,
             reply_to_id: None,
             message_type: MessageType::User,
+            priority: MessagePriority::Normal,
+            seq: 0,
+            version: 1,
+            metadata: None,
+            content_blocks: None,
+            content_markdown: None,
+            content_format: ContentFormat::Html,
+            sender_display_name: None,
+            sender_company: None,
         }
     }
 
     /// Create a system message (no sender)
     pub fn system(dialog_id: Uuid, content: impl Into<String>) -> Self {
+        let ids = IdGenerator::get();
+        let id = ids.new_id();
+        let now = Utc::now();
         Self {
-            id: Uuid::now_v7(),
+            id,
+            external_id: ids.external_id(id),
             dialog_id,
             sender_id: None,
             content: content.into(),
-            sent_at: Utc::now(),
+            sent_at: now,
             last_edited_at: None,
+            updated_at: now,
             reply_to_id: None,
             message_type: MessageType::System,
+            priority: MessagePriority::Normal,
+            seq: 0,
+            version: 1,
+            metadata: None,
+            content_blocks: None,
+            content_markdown: None,
+            content_format: ContentFormat::Html,
+            sender_display_name: None,
+            sender_company: None,
         }
     }
 
@@ -103,6 +330,87 @@ impl Message {
         self
     }
 
+    pub fn with_priority(mut self, priority: MessagePriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: Option<serde_json::Value>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Content in the form it is stored (compressed when large)
+    pub fn stored_content(&self) -> StoredContent {
+        StoredContent::encode(&self.content)
+    }
+
+    pub fn with_content_blocks(mut self, blocks: Option<Vec<ContentBlock>>) -> Self {
+        self.content_blocks = blocks.map(Json);
+        self
+    }
+
+    /// Keep the Markdown source the (sanitized HTML) content was rendered from
+    pub fn with_markdown_source(mut self, source: Option<String>) -> Self {
+        self.content_markdown = source;
+        self
+    }
+
+    /// Return `content` in the requested format. Messages without a Markdown
+    /// source stay HTML.
+    pub fn in_format(mut self, format: ContentFormat) -> Self {
+        if format.is_markdown() {
+            if let Some(source) = &self.content_markdown {
+                self.content = source.clone();
+                self.content_format = ContentFormat::Markdown;
+            }
+        }
+        self
+    }
+
+    /// Backdate an imported message. The ID is derived from `sent_at`, so
+    /// imported history sorts before messages sent later.
+    pub fn with_sent_at(mut self, sent_at: DateTime<Utc>) -> Self {
+        let ts = Timestamp::from_unix(
+            NoContext,
+            sent_at.timestamp().max(0) as u64,
+            sent_at.timestamp_subsec_nanos(),
+        );
+        self.id = Uuid::new_v7(ts);
+        self.external_id = IdGenerator::get().external_id(self.id);
+        self.sent_at = sent_at;
+        self
+    }
+
+    /// Record the client's ID of an offline-composed message and when it was
+    /// composed, as `client_id` and `client_sent_at` in `metadata`
+    pub fn with_client_origin(mut self, client_id: &str, client_sent_at: DateTime<Utc>) -> Self {
+        let mut metadata = match self.metadata.take() {
+            Some(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        metadata.insert("client_id".into(), client_id.into());
+        metadata.insert(
+            "client_sent_at".into(),
+            serde_json::to_value(client_sent_at).unwrap_or_default(),
+        );
+        self.metadata = Some(serde_json::Value::Object(metadata));
+        self
+    }
+
+    /// Client ID of an offline-composed message (see [`Message::with_client_origin`])
+    pub fn client_id(&self) -> Option<&str> {
+        self.metadata.as_ref()?.get("client_id")?.as_str()
+    }
+
+    /// Keep the ID an imported message had in the source system
+    pub fn with_external_id(mut self, external_id: Option<String>) -> Self {
+        if let Some(external_id) = external_id {
+            self.external_id = external_id;
+        }
+        self
+    }
+
     pub fn is_edited(&self) -> bool {
         self.last_edited_at.is_some()
     }
@@ -110,4 +418,32 @@ impl Message {
     pub fn is_system(&self) -> bool {
         self.message_type == MessageType::System
     }
+
+    /// Callback button `action_id` of the message's actions blocks (link
+    /// buttons are opened by the client and have no callback)
+    pub fn action_button(&self, action_id: &str) -> Option<&ActionButton> {
+        self.content_blocks
+            .iter()
+            .flat_map(|blocks| blocks.0.iter())
+            .filter_map(|block| match block {
+                ContentBlock::Actions { buttons } => Some(buttons),
+                _ => None,
+            })
+            .flatten()
+            .find(|button| button.id == action_id && button.url.is_none())
+    }
+
+    /// Snapshotted sender profile (`None` for system messages and senders
+    /// without a profile)
+    pub fn sender_profile(&self) -> Option<SenderProfile> {
+        if self.sender_id.is_none()
+            || (self.sender_display_name.is_none() && self.sender_company.is_none())
+        {
+            return None;
+        }
+        Some(SenderProfile {
+            display_name: self.sender_display_name.clone(),
+            company: self.sender_company.clone(),
+        })
+    }
 }
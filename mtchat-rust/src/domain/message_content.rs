@@ -0,0 +1,182 @@
+//! Stored form of message content
+//!
+//! Content larger than [`COMPRESS_CONTENT_ABOVE_BYTES`] is stored
+//! zstd-compressed in `content_compressed`, marked by `content_encoding =
+//! 'zstd'`. The `content` column of such a message keeps the first
+//! [`COMPRESSED_CONTENT_PREFIX_CHARS`] characters, so reply and dialog list
+//! previews (cut in SQL) still work, and `content_search_vector` the distinct
+//! words of its text for search. Loading a [`Message`](super::Message)
+//! decompresses it again.
+
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, Row};
+
+/// Content size (bytes) above which messages are stored compressed
+pub const COMPRESS_CONTENT_ABOVE_BYTES: usize = 8 * 1024;
+
+/// Characters of compressed content kept uncompressed in `content`
+pub const COMPRESSED_CONTENT_PREFIX_CHARS: usize = 1000;
+
+/// zstd level: fast, still several times smaller for HTML
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encoding of the stored message content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum ContentEncoding {
+    /// Full content in `content`
+    #[default]
+    Plain,
+    /// Full content zstd-compressed in `content_compressed`
+    Zstd,
+}
+
+/// Message content as stored in the `content`, `content_encoding` and
+/// `content_compressed` columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredContent {
+    /// Full content, or its prefix when compressed
+    pub content: String,
+    pub encoding: ContentEncoding,
+    pub compressed: Option<Vec<u8>>,
+    /// Text without markup when compressed, for the search vector (not loaded
+    /// from rows)
+    pub search_text: Option<String>,
+}
+
+impl StoredContent {
+    /// Stored form of `content`: compressed when large and compression pays off
+    pub fn encode(content: &str) -> Self {
+        let plain = || Self {
+            content: content.to_string(),
+            encoding: ContentEncoding::Plain,
+            compressed: None,
+            search_text: None,
+        };
+        if content.len() <= COMPRESS_CONTENT_ABOVE_BYTES {
+            return plain();
+        }
+        match zstd::bulk::compress(content.as_bytes(), ZSTD_LEVEL) {
+            Ok(compressed) if compressed.len() < content.len() => Self {
+                content: content
+                    .chars()
+                    .take(COMPRESSED_CONTENT_PREFIX_CHARS)
+                    .collect(),
+                encoding: ContentEncoding::Zstd,
+                compressed: Some(compressed),
+                search_text: Some(search_text(content)),
+            },
+            _ => plain(),
+        }
+    }
+
+    /// Full content
+    pub fn decode(self) -> Result<String, sqlx::error::BoxDynError> {
+        match (self.encoding, self.compressed) {
+            (ContentEncoding::Plain, _) => Ok(self.content),
+            (ContentEncoding::Zstd, Some(compressed)) => {
+                let bytes = zstd::stream::decode_all(compressed.as_slice())?;
+                Ok(String::from_utf8(bytes)?)
+            }
+            (ContentEncoding::Zstd, None) => Err("zstd content without compressed data".into()),
+        }
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for StoredContent {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            content: row.try_get("content")?,
+            encoding: row.try_get("content_encoding")?,
+            compressed: row.try_get("content_compressed")?,
+            search_text: None,
+        })
+    }
+}
+
+/// Inline elements, whose tags do not separate words
+const INLINE_TAGS: [&str; 12] = [
+    "a", "b", "code", "em", "i", "mark", "s", "span", "strong", "sub", "sup", "u",
+];
+
+/// Text of sanitized HTML for search: tags removed (block tags separate
+/// words), common entities decoded, whitespace collapsed
+fn search_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let name: String = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if !INLINE_TAGS.contains(&name.to_ascii_lowercase().as_str()) {
+            text.push(' ');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_content_stays_plain() {
+        let stored = StoredContent::encode("<p>Hello</p>");
+        assert_eq!(stored.encoding, ContentEncoding::Plain);
+        assert_eq!(stored.compressed, None);
+        assert_eq!(stored.decode().unwrap(), "<p>Hello</p>");
+    }
+
+    #[test]
+    fn test_large_content_round_trips() {
+        let content = "<p>Привет, <strong>мир</strong></p>".repeat(1000);
+        let stored = StoredContent::encode(&content);
+        assert_eq!(stored.encoding, ContentEncoding::Zstd);
+        assert!(stored.compressed.as_ref().unwrap().len() < content.len() / 10);
+        assert_eq!(
+            stored.content.chars().count(),
+            COMPRESSED_CONTENT_PREFIX_CHARS
+        );
+        assert!(content.starts_with(&stored.content));
+        let search_text = stored.search_text.clone().unwrap();
+        assert!(search_text.starts_with("Привет, мир Привет, мир"));
+        assert_eq!(stored.decode().unwrap(), content);
+    }
+
+    #[test]
+    fn test_search_text() {
+        assert_eq!(
+            search_text("<p>Fish &amp; <em>chips</em></p><ul><li>one</li><li>two</li></ul>"),
+            "Fish & chips one two"
+        );
+        assert_eq!(search_text("a <b"), "a <b");
+    }
+
+    #[test]
+    fn test_corrupt_data_fails_to_decode() {
+        let stored = StoredContent {
+            content: String::new(),
+            encoding: ContentEncoding::Zstd,
+            compressed: Some(vec![1, 2, 3]),
+            search_text: None,
+        };
+        assert!(stored.decode().is_err());
+    }
+}
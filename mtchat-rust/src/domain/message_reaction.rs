@@ -0,0 +1,46 @@
+//! Message reaction entity
+//!
+//! Emoji reactions of participants to a message. Reactions are rolled out
+//! with the `reactions` feature flag.
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Feature flag gating reactions
+pub const REACTIONS_FLAG: &str = "reactions";
+
+/// Maximum length of a reaction in characters
+pub const MAX_REACTION_LENGTH: usize = 32;
+
+/// Reactions to a message with one emoji
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct ReactionSummary {
+    pub emoji: String,
+    pub count: i64,
+    /// Whether the current user reacted with it
+    pub reacted: bool,
+}
+
+/// Whether a reaction is 1 to [`MAX_REACTION_LENGTH`] characters without
+/// whitespace or control characters
+pub fn is_valid_reaction(emoji: &str) -> bool {
+    !emoji.is_empty()
+        && emoji.chars().count() <= MAX_REACTION_LENGTH
+        && !emoji.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reaction_validation() {
+        assert!(is_valid_reaction("👍"));
+        assert!(is_valid_reaction("👍🏽"));
+        assert!(is_valid_reaction(":thumbsup:"));
+        assert!(!is_valid_reaction(""));
+        assert!(!is_valid_reaction("thumbs up"));
+        assert!(!is_valid_reaction("\u{0}"));
+        assert!(!is_valid_reaction(&"a".repeat(MAX_REACTION_LENGTH + 1)));
+    }
+}
@@ -0,0 +1,18 @@
+//! Starred message entity
+//!
+//! A message a user bookmarked. Stars are private to the user.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::Message;
+
+/// A starred message with the time it was starred
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StarredMessage {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub message: Message,
+    pub starred_at: DateTime<Utc>,
+}
@@ -4,18 +4,90 @@
 
 mod access_scope;
 mod attachment;
+mod audit;
+pub mod avatar;
+mod ban;
+mod content_block;
 mod dialog;
+mod dialog_activity;
+mod dialog_event;
+mod dialog_folder;
+mod dialog_notes;
+mod dialog_template;
+mod export;
+pub mod feature_flag;
 pub mod html_sanitize;
+mod id;
+mod invite;
+mod job_dead_letter;
+mod markdown;
+pub mod mentions;
 mod message;
+mod message_content;
+mod message_reaction;
+mod message_star;
 mod participant;
+mod setting;
+mod sla;
+mod storage_usage;
 pub mod system_messages;
+mod tenant_settings;
 pub mod validation;
 
 pub use access_scope::DialogAccessScope;
 pub use attachment::{
     limits as attachment_limits, Attachment, AttachmentInput, AttachmentResponse, AttachmentType,
+    UrlStatus,
 };
-pub use dialog::Dialog;
-pub use html_sanitize::sanitize_html;
-pub use message::{Message, MessageType};
-pub use participant::{DialogParticipant, JoinedAs, ParticipantProfile};
+pub use audit::{
+    AuditEntry, AUDIT_IMPERSONATION_ISSUED, AUDIT_IMPERSONATION_VIEWED, MAX_AUDIT_ACTOR_LENGTH,
+    MAX_AUDIT_ENTRIES,
+};
+pub use ban::{DialogBan, MAX_BAN_REASON_LENGTH};
+pub use content_block::{
+    validate_content_blocks, ActionButton, ButtonStyle, ContentBlock, KeyValueRow,
+    MAX_ACTION_BUTTONS, MAX_CONTENT_BLOCKS, MAX_CONTENT_BLOCKS_BYTES, MAX_KEY_VALUE_ROWS,
+};
+pub use dialog::{Dialog, LocaleContext};
+pub use dialog_activity::{ActivityType, DialogActivity, MAX_ACTIVITY_PAGE};
+pub use dialog_event::{DialogEvent, MAX_SYNC_EVENTS};
+pub use dialog_folder::{DialogFilter, DialogFolder, MAX_FOLDERS_PER_USER, MAX_FOLDER_NAME_LENGTH};
+pub use dialog_notes::{
+    DialogNotes, DialogNotesRevision, MAX_DIALOG_NOTES_LENGTH, MAX_NOTES_HISTORY,
+};
+pub use dialog_template::{DialogTemplate, ScopeTemplate, MAX_TEMPLATE_SCOPES};
+pub use export::{
+    ExportCursor, ExportDeletion, ExportDialog, ExportEntity, ExportMessage, ExportParticipant,
+    DEFAULT_EXPORT_ROWS, EXPORT_SETTLE_SECS, MAX_EXPORT_ROWS,
+};
+pub use feature_flag::{FeatureFlagOverride, FlagScope};
+pub use html_sanitize::{sanitize_html, sanitize_html_with, SanitizeProfile};
+pub use id::{IdConfig, IdFormat, IdGenerator, MAX_SNOWFLAKE_WORKER_ID, SNOWFLAKE_EPOCH_MS};
+pub use invite::{normalize_email, ParticipantInvite};
+pub use job_dead_letter::{JobDeadLetter, MAX_DEAD_LETTERS};
+pub use markdown::{render_markdown, ContentFormat};
+pub use mentions::{extract_broadcast_mention, extract_mentions, BroadcastMention};
+pub use message::{
+    Message, MessageDayCount, MessagePreview, MessagePriority, MessageType, ReplyPreview,
+    SenderProfile, LAST_MESSAGE_PREVIEW_CHARS, MAX_BATCH_MESSAGES, MAX_CALENDAR_DAYS,
+    MAX_IMPORT_MESSAGES, MAX_QA_PAIRS, REPLY_PREVIEW_CHARS,
+};
+pub use message_content::{
+    ContentEncoding, StoredContent, COMPRESSED_CONTENT_PREFIX_CHARS, COMPRESS_CONTENT_ABOVE_BYTES,
+};
+pub use message_reaction::{
+    is_valid_reaction, ReactionSummary, MAX_REACTION_LENGTH, REACTIONS_FLAG,
+};
+pub use message_star::StarredMessage;
+pub use participant::{
+    BulkDialogAction, DialogParticipant, JoinedAs, MessageAttribution, ParticipantProfile,
+    ParticipantSort, MAX_BULK_DIALOGS, MAX_PARTICIPANTS_PAGE, MAX_REMOVAL_GRACE_SECS,
+    MAX_SNOOZE_SECS,
+};
+pub use setting::{QuietHours, Setting};
+pub use sla::{
+    DialogSla, DialogSlaState, SlaPolicy, SlaSource, SlaStatus, SlaTarget, UnansweredMessage,
+    DEFAULT_SLA_WARNING_PERCENT, MAX_SLA_SECS, MIN_SLA_SECS,
+};
+pub use storage_usage::{StorageScope, StorageUsage};
+pub use tenant_settings::{TenantSettings, MAX_TENANT_SETTINGS_BYTES};
@@ -16,6 +16,8 @@ pub enum JoinedAs {
     Participant,
     /// Joined via scope access
     Joined,
+    /// Added by management as a read-only observer
+    Observer,
 }
 
 impl JoinedAs {
@@ -24,8 +26,15 @@ impl JoinedAs {
             JoinedAs::Creator => "creator",
             JoinedAs::Participant => "participant",
             JoinedAs::Joined => "joined",
+            JoinedAs::Observer => "observer",
         }
     }
+
+    /// Observers read messages and receive events, but cannot send, do not
+    /// accumulate unread counts and get no notifications
+    pub fn is_observer(&self) -> bool {
+        matches!(self, JoinedAs::Observer)
+    }
 }
 
 impl From<String> for JoinedAs {
@@ -34,6 +43,7 @@ impl From<String> for JoinedAs {
             "creator" => JoinedAs::Creator,
             "participant" => JoinedAs::Participant,
             "joined" => JoinedAs::Joined,
+            "observer" => JoinedAs::Observer,
             _ => JoinedAs::Participant,
         }
     }
@@ -57,6 +67,9 @@ pub struct DialogParticipant {
     /// External user identifier (from JWT token or host system)
     pub user_id: String,
     pub joined_at: DateTime<Utc>,
+    /// Last change of the participant, except `unread_count`
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
     /// How the user joined the dialog
     pub joined_as: JoinedAs,
     pub notifications_enabled: bool,
@@ -68,6 +81,8 @@ pub struct DialogParticipant {
     pub display_name: Option<String>,
     /// Company/organization name
     pub company: Option<String>,
+    /// Host-defined company identifier (groups participants by company)
+    pub company_uid: Option<String>,
     /// Contact email (optional, can be hidden)
     pub email: Option<String>,
     /// Contact phone (optional, can be hidden)
@@ -76,6 +91,80 @@ pub struct DialogParticipant {
     pub is_archived: bool,
     /// Whether this dialog is pinned for this participant
     pub is_pinned: bool,
+    /// Notifications are suppressed until this time (past values mean not snoozed)
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Removal scheduled by management: until then the participant is read-only
+    pub pending_removal_at: Option<DateTime<Utc>>,
+    /// Storage key of the uploaded avatar (exposed as URLs, not as the key)
+    #[serde(skip)]
+    pub avatar_s3_key: Option<String>,
+    /// Whether the thumbnail job rendered the avatar variants
+    #[serde(skip)]
+    pub avatar_variants_ready: bool,
+}
+
+/// Longest snooze a user can set (30 days)
+pub const MAX_SNOOZE_SECS: i64 = 30 * 24 * 3600;
+
+/// Longest grace period before a removed participant is finally removed (30 days)
+pub const MAX_REMOVAL_GRACE_SECS: i64 = 30 * 24 * 3600;
+
+/// Maximum number of dialogs in one bulk request (actions, participant lookup)
+pub const MAX_BULK_DIALOGS: usize = 100;
+
+/// Per-user state change applied to many dialogs at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkDialogAction {
+    Archive,
+    Unarchive,
+    Pin,
+    Unpin,
+    /// Disable notifications
+    Mute,
+    /// Enable notifications
+    Unmute,
+    /// Read up to the latest message
+    MarkRead,
+}
+
+impl BulkDialogAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BulkDialogAction::Archive => "archive",
+            BulkDialogAction::Unarchive => "unarchive",
+            BulkDialogAction::Pin => "pin",
+            BulkDialogAction::Unpin => "unpin",
+            BulkDialogAction::Mute => "mute",
+            BulkDialogAction::Unmute => "unmute",
+            BulkDialogAction::MarkRead => "mark_read",
+        }
+    }
+}
+
+/// Maximum number of participants in one page of the participant list
+pub const MAX_PARTICIPANTS_PAGE: i64 = 100;
+
+/// Order of the participant list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantSort {
+    /// Oldest participants first
+    #[default]
+    JoinedAt,
+    /// By display name (case-insensitive), participants without one last
+    Name,
+}
+
+/// Who authored a replaced participant's messages after a transfer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageAttribution {
+    /// Messages stay attributed to the previous user
+    #[default]
+    Keep,
+    /// Messages are attributed to the new user (who can then edit them)
+    Reassign,
 }
 
 /// Profile information for a participant
@@ -83,26 +172,45 @@ pub struct DialogParticipant {
 pub struct ParticipantProfile {
     pub display_name: String,
     pub company: Option<String>,
+    pub company_uid: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
 }
 
 impl DialogParticipant {
+    /// End of the snooze if it is still in effect at `now`
+    pub fn active_snooze(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.snoozed_until.filter(|until| *until > now)
+    }
+
+    /// Whether management removed the participant with a grace period. Such
+    /// participants can still read the dialog but no longer write to it.
+    pub fn is_pending_removal(&self) -> bool {
+        self.pending_removal_at.is_some()
+    }
+
     pub fn new(dialog_id: Uuid, user_id: impl Into<String>, joined_as: JoinedAs) -> Self {
+        let now = Utc::now();
         Self {
             dialog_id,
             user_id: user_id.into(),
-            joined_at: Utc::now(),
+            joined_at: now,
+            updated_at: now,
             joined_as,
             notifications_enabled: true,
             last_read_message_id: None,
             unread_count: 0,
             display_name: None,
             company: None,
+            company_uid: None,
             email: None,
             phone: None,
             is_archived: false,
             is_pinned: false,
+            snoozed_until: None,
+            pending_removal_at: None,
+            avatar_s3_key: None,
+            avatar_variants_ready: false,
         }
     }
 
@@ -112,20 +220,27 @@ impl DialogParticipant {
         joined_as: JoinedAs,
         profile: ParticipantProfile,
     ) -> Self {
+        let now = Utc::now();
         Self {
             dialog_id,
             user_id: user_id.into(),
-            joined_at: Utc::now(),
+            joined_at: now,
+            updated_at: now,
             joined_as,
             notifications_enabled: true,
             last_read_message_id: None,
             unread_count: 0,
             display_name: Some(profile.display_name),
             company: profile.company,
+            company_uid: profile.company_uid,
             email: profile.email,
             phone: profile.phone,
             is_archived: false,
             is_pinned: false,
+            snoozed_until: None,
+            pending_removal_at: None,
+            avatar_s3_key: None,
+            avatar_variants_ready: false,
         }
     }
 }
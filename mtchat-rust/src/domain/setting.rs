@@ -0,0 +1,62 @@
+//! Runtime setting override
+//!
+//! A value stored in the `settings` table, overriding the configured default
+//! of a runtime setting, and value types of settings.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Setting {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Window of local hours, e.g. 20 to 8 for the night. `end_hour` is
+/// exclusive; windows may wrap around midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+impl QuietHours {
+    /// Whether the local `hour` (0-23) falls into the window
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_hours_contains() {
+        let night = QuietHours {
+            start_hour: 20,
+            end_hour: 8,
+        };
+        assert!(night.contains(20));
+        assert!(night.contains(23));
+        assert!(night.contains(0));
+        assert!(night.contains(7));
+        assert!(!night.contains(8));
+        assert!(!night.contains(13));
+
+        let lunch = QuietHours {
+            start_hour: 12,
+            end_hour: 14,
+        };
+        assert!(lunch.contains(12));
+        assert!(lunch.contains(13));
+        assert!(!lunch.contains(14));
+        assert!(!lunch.contains(3));
+    }
+}
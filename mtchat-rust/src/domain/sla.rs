@@ -0,0 +1,254 @@
+//! Response time SLAs
+//!
+//! An SLA is configured per object type, or per dialog to override it, and
+//! holds one company to it: the responding company, by default the company
+//! of the dialog's creator. A message from any other company is answered once
+//! someone from the responding company writes after it; the SLA clock runs
+//! from the oldest unanswered message. Messages of the responding company
+//! never start it. Participants without a company count as a company of
+//! their own.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::Dialog;
+
+/// Shortest configurable response time (1 minute)
+pub const MIN_SLA_SECS: i32 = 60;
+
+/// Longest configurable response time (30 days)
+pub const MAX_SLA_SECS: i32 = 30 * 24 * 3600;
+
+/// Share of the response time after which `sla.warning` is sent, unless a
+/// warning time is configured
+pub const DEFAULT_SLA_WARNING_PERCENT: i32 = 80;
+
+/// SLA of all dialogs of an object type
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SlaPolicy {
+    pub object_type: String,
+    /// Time to answer the oldest unanswered message
+    pub response_secs: i32,
+    /// Time after which a warning is sent (default: 80% of `response_secs`)
+    pub warning_secs: Option<i32>,
+    /// Company held to the SLA, by `company_uid` or name (default: the
+    /// company of each dialog's creator)
+    pub responder_company: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where the SLA of a dialog comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaSource {
+    /// Set on the dialog
+    Dialog,
+    /// Policy of the dialog's object type
+    ObjectType,
+}
+
+/// Response time a dialog is held to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SlaTarget {
+    pub response_secs: i32,
+    pub warning_secs: i32,
+    pub source: SlaSource,
+}
+
+impl SlaTarget {
+    /// The dialog's own SLA if set, else its object type's policy
+    pub fn resolve(dialog: &Dialog, policy: Option<&SlaPolicy>) -> Option<Self> {
+        if let Some(response_secs) = dialog.sla_response_secs {
+            return Some(Self::new(
+                response_secs,
+                dialog.sla_warning_secs,
+                SlaSource::Dialog,
+            ));
+        }
+        policy.map(|p| Self::new(p.response_secs, p.warning_secs, SlaSource::ObjectType))
+    }
+
+    /// Company held to the SLA resolved like [`Self::resolve`] (None = the
+    /// company of the dialog's creator)
+    pub fn responder_company<'a>(
+        dialog: &'a Dialog,
+        policy: Option<&'a SlaPolicy>,
+    ) -> Option<&'a str> {
+        if dialog.sla_response_secs.is_some() {
+            return dialog.sla_responder_company.as_deref();
+        }
+        policy.and_then(|p| p.responder_company.as_deref())
+    }
+
+    pub fn new(response_secs: i32, warning_secs: Option<i32>, source: SlaSource) -> Self {
+        Self {
+            response_secs,
+            warning_secs: warning_secs.unwrap_or(
+                (i64::from(response_secs) * i64::from(DEFAULT_SLA_WARNING_PERCENT) / 100) as i32,
+            ),
+            source,
+        }
+    }
+
+    /// Status of a message waiting for an answer since `pending_since`
+    pub fn status_at(&self, pending_since: DateTime<Utc>, now: DateTime<Utc>) -> SlaStatus {
+        let waited = now - pending_since;
+        if waited >= Duration::seconds(self.response_secs.into()) {
+            SlaStatus::Breached
+        } else if waited >= Duration::seconds(self.warning_secs.into()) {
+            SlaStatus::Warning
+        } else {
+            SlaStatus::Pending
+        }
+    }
+
+    pub fn due_at(&self, pending_since: DateTime<Utc>) -> DateTime<Utc> {
+        pending_since + Duration::seconds(self.response_secs.into())
+    }
+}
+
+/// SLA status of a dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaStatus {
+    /// No message is waiting for an answer
+    Answered,
+    /// A message is waiting, within the warning time
+    Pending,
+    /// A message is waiting past the warning time
+    Warning,
+    /// A message is waiting past the response time
+    Breached,
+}
+
+/// Oldest message of a dialog the responding company has not answered yet
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct UnansweredMessage {
+    pub message_id: Uuid,
+    pub sender_id: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// SLA of a dialog with its current status
+#[derive(Debug, Clone, Serialize)]
+pub struct DialogSla {
+    #[serde(flatten)]
+    pub target: SlaTarget,
+    pub status: SlaStatus,
+    /// Oldest unanswered message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_message_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_since: Option<DateTime<Utc>>,
+    /// When the SLA is (or was) breached if the message stays unanswered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl DialogSla {
+    pub fn new(
+        target: SlaTarget,
+        unanswered: Option<&UnansweredMessage>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        match unanswered {
+            Some(message) => Self {
+                target,
+                status: target.status_at(message.sent_at, now),
+                pending_message_id: Some(message.message_id),
+                pending_since: Some(message.sent_at),
+                due_at: Some(target.due_at(message.sent_at)),
+            },
+            None => Self {
+                target,
+                status: SlaStatus::Answered,
+                pending_message_id: None,
+                pending_since: None,
+                due_at: None,
+            },
+        }
+    }
+}
+
+/// What the SLA job last saw of a dialog, so each escalation is sent once
+/// per unanswered message
+#[derive(Debug, Clone, FromRow)]
+pub struct DialogSlaState {
+    pub dialog_id: Uuid,
+    /// `last_message_seq` of the dialog when the unanswered message was found
+    pub checked_seq: i64,
+    pub message_id: Option<Uuid>,
+    pub sender_id: Option<String>,
+    pub pending_since: Option<DateTime<Utc>>,
+    pub warned_at: Option<DateTime<Utc>>,
+    pub breached_at: Option<DateTime<Utc>>,
+}
+
+impl DialogSlaState {
+    pub fn unanswered(&self) -> Option<UnansweredMessage> {
+        Some(UnansweredMessage {
+            message_id: self.message_id?,
+            sender_id: self.sender_id.clone()?,
+            sent_at: self.pending_since?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_warning_time() {
+        let target = SlaTarget::new(4 * 3600, None, SlaSource::Dialog);
+        assert_eq!(target.warning_secs, 4 * 3600 * 80 / 100);
+
+        let target = SlaTarget::new(3600, Some(600), SlaSource::Dialog);
+        assert_eq!(target.warning_secs, 600);
+    }
+
+    #[test]
+    fn test_status_at() {
+        let target = SlaTarget::new(3600, Some(1800), SlaSource::ObjectType);
+        let since = Utc::now();
+        let at = |secs| since + Duration::seconds(secs);
+
+        assert_eq!(target.status_at(since, at(0)), SlaStatus::Pending);
+        assert_eq!(target.status_at(since, at(1800)), SlaStatus::Warning);
+        assert_eq!(target.status_at(since, at(3599)), SlaStatus::Warning);
+        assert_eq!(target.status_at(since, at(3600)), SlaStatus::Breached);
+        assert_eq!(target.due_at(since), at(3600));
+    }
+
+    #[test]
+    fn test_dialog_overrides_policy() {
+        let mut dialog = Dialog::new("tender-1", "tender", None, None, None, None);
+        let policy = SlaPolicy {
+            object_type: "tender".into(),
+            response_secs: 7200,
+            warning_secs: None,
+            responder_company: Some("Supplier Ltd".into()),
+            updated_at: Utc::now(),
+        };
+
+        let target = SlaTarget::resolve(&dialog, Some(&policy)).unwrap();
+        assert_eq!(target.source, SlaSource::ObjectType);
+        assert_eq!(target.response_secs, 7200);
+        assert_eq!(
+            SlaTarget::responder_company(&dialog, Some(&policy)),
+            Some("Supplier Ltd")
+        );
+
+        // The dialog's own SLA also replaces the responding company
+        dialog.sla_response_secs = Some(600);
+        let target = SlaTarget::resolve(&dialog, Some(&policy)).unwrap();
+        assert_eq!(target.source, SlaSource::Dialog);
+        assert_eq!(target.response_secs, 600);
+        assert_eq!(SlaTarget::responder_company(&dialog, Some(&policy)), None);
+
+        dialog.sla_response_secs = None;
+        assert!(SlaTarget::resolve(&dialog, None).is_none());
+    }
+}
@@ -0,0 +1,90 @@
+//! Storage usage entity
+//!
+//! Attachment bytes stored per dialog and per tenant, with optional quota overrides.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// What a storage usage row is counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum StorageScope {
+    /// A single dialog (scope_id = dialog UUID)
+    Dialog,
+    /// A tenant (scope_id = scope_level0 value of the dialog's access scopes)
+    Tenant,
+}
+
+impl StorageScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageScope::Dialog => "dialog",
+            StorageScope::Tenant => "tenant",
+        }
+    }
+}
+
+/// Storage usage of a dialog or tenant
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StorageUsage {
+    pub scope_type: StorageScope,
+    pub scope_id: String,
+    /// Total attachment bytes currently stored
+    pub used_bytes: i64,
+    /// Quota override in bytes (None = configured default)
+    pub quota_bytes: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StorageUsage {
+    /// Effective quota in bytes, given the configured default (`None` = unlimited)
+    ///
+    /// A quota of 0, whether default or override, means unlimited.
+    pub fn effective_quota(&self, default_quota: i64) -> Option<i64> {
+        match self.quota_bytes.unwrap_or(default_quota) {
+            0 => None,
+            quota => Some(quota),
+        }
+    }
+
+    /// Check if adding `additional` bytes would exceed the quota
+    pub fn would_exceed(&self, additional: i64, default_quota: i64) -> bool {
+        self.effective_quota(default_quota)
+            .is_some_and(|quota| self.used_bytes.saturating_add(additional) > quota)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(used_bytes: i64, quota_bytes: Option<i64>) -> StorageUsage {
+        StorageUsage {
+            scope_type: StorageScope::Dialog,
+            scope_id: "d".into(),
+            used_bytes,
+            quota_bytes,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_default_quota() {
+        assert!(!usage(90, None).would_exceed(10, 100));
+        assert!(usage(90, None).would_exceed(11, 100));
+    }
+
+    #[test]
+    fn test_override_wins_over_default() {
+        assert!(usage(90, Some(50)).would_exceed(1, 1000));
+        assert!(!usage(90, Some(0)).would_exceed(1_000_000, 100));
+    }
+
+    #[test]
+    fn test_zero_default_is_unlimited() {
+        assert_eq!(usage(0, None).effective_quota(0), None);
+        assert!(!usage(i64::MAX, None).would_exceed(1, 0));
+    }
+}
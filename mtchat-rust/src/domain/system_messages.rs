@@ -1,11 +1,14 @@
 //! System message content generators
 //!
 //! System messages store structured JSON content that the frontend
-//! formats according to the user's locale.
+//! formats according to the user's locale. When the dialog has a timezone or
+//! locale set, it is embedded so every participant sees the same rendering.
 
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use super::LocaleContext;
+
 /// Participant info for system messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParticipantInfo {
@@ -15,16 +18,20 @@ pub struct ParticipantInfo {
 }
 
 /// Generate content for "chat created" system message
-pub fn chat_created_content(participants: Vec<ParticipantInfo>) -> String {
-    json!({
+pub fn chat_created_content(participants: Vec<ParticipantInfo>, locale: &LocaleContext) -> String {
+    let content = json!({
         "event": "chat_created",
         "participants": participants
-    })
-    .to_string()
+    });
+    with_locale(content, locale)
 }
 
 /// Generate content for "participant joined" system message
-pub fn participant_joined_content(name: &str, company: Option<&str>) -> String {
+pub fn participant_joined_content(
+    name: &str,
+    company: Option<&str>,
+    locale: &LocaleContext,
+) -> String {
     let mut content = json!({
         "event": "participant_joined",
         "name": name
@@ -32,16 +39,106 @@ pub fn participant_joined_content(name: &str, company: Option<&str>) -> String {
     if let Some(c) = company {
         content["company"] = json!(c);
     }
-    content.to_string()
+    with_locale(content, locale)
 }
 
 /// Generate content for "participant left" system message
-pub fn participant_left_content(name: &str) -> String {
-    json!({
+pub fn participant_left_content(name: &str, locale: &LocaleContext) -> String {
+    let content = json!({
         "event": "participant_left",
         "name": name
-    })
-    .to_string()
+    });
+    with_locale(content, locale)
+}
+
+/// Generate content for "participant replaced" system message (a user's place
+/// in the dialog was transferred to another user)
+pub fn participant_replaced_content(
+    old_name: &str,
+    new_name: &str,
+    company: Option<&str>,
+    locale: &LocaleContext,
+) -> String {
+    let mut content = json!({
+        "event": "participant_replaced",
+        "old_name": old_name,
+        "name": new_name
+    });
+    if let Some(c) = company {
+        content["company"] = json!(c);
+    }
+    with_locale(content, locale)
+}
+
+/// Generate content for "action clicked" system message (a participant
+/// pressed a callback button of an integration message)
+pub fn action_clicked_content(
+    name: &str,
+    message_id: uuid::Uuid,
+    action_id: &str,
+    label: &str,
+    locale: &LocaleContext,
+) -> String {
+    let content = json!({
+        "event": "action_clicked",
+        "name": name,
+        "message_id": message_id,
+        "action_id": action_id,
+        "label": label
+    });
+    with_locale(content, locale)
+}
+
+/// Maximum length of a host-defined object event type
+pub const MAX_OBJECT_EVENT_TYPE_LENGTH: usize = 64;
+
+/// Maximum serialized size of an object event payload
+pub const MAX_OBJECT_EVENT_PAYLOAD_BYTES: usize = 16 * 1024;
+
+/// Object event types are lowercase names like `status_changed` or
+/// `deadline.moved` so clients can map them to renderers.
+pub fn is_valid_object_event_type(event_type: &str) -> bool {
+    !event_type.is_empty()
+        && event_type.len() <= MAX_OBJECT_EVENT_TYPE_LENGTH
+        && event_type.starts_with(|c: char| c.is_ascii_lowercase())
+        && event_type
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.'))
+}
+
+/// Generate content for a lifecycle event of the bound object pushed by the host
+/// (status changed, deadline moved, ...).
+///
+/// `text` is a ready-made fallback for clients without a renderer for the type.
+pub fn object_event_content(
+    event_type: &str,
+    payload: &serde_json::Value,
+    text: Option<&str>,
+    occurred_at: Option<chrono::DateTime<chrono::Utc>>,
+    locale: &LocaleContext,
+) -> String {
+    let mut content = json!({
+        "event": "object_event",
+        "type": event_type,
+        "payload": payload
+    });
+    if let Some(t) = text {
+        content["text"] = json!(t);
+    }
+    if let Some(at) = occurred_at {
+        content["occurred_at"] = json!(at);
+    }
+    with_locale(content, locale)
+}
+
+fn with_locale(mut content: serde_json::Value, locale: &LocaleContext) -> String {
+    if let Some(tz) = &locale.timezone {
+        content["timezone"] = json!(tz);
+    }
+    if let Some(l) = &locale.locale {
+        content["locale"] = json!(l);
+    }
+    content.to_string()
 }
 
 #[cfg(test)]
@@ -60,7 +157,7 @@ mod tests {
                 company: None,
             },
         ];
-        let content = chat_created_content(participants);
+        let content = chat_created_content(participants, &LocaleContext::default());
         assert!(content.contains("chat_created"));
         assert!(content.contains("Иван Иванов"));
         assert!(content.contains("ООО Ромашка"));
@@ -69,7 +166,8 @@ mod tests {
 
     #[test]
     fn test_participant_joined_content_with_company() {
-        let content = participant_joined_content("Алексей", Some("ООО Василёк"));
+        let content =
+            participant_joined_content("Алексей", Some("ООО Василёк"), &LocaleContext::default());
         assert!(content.contains("participant_joined"));
         assert!(content.contains("Алексей"));
         assert!(content.contains("ООО Василёк"));
@@ -77,28 +175,105 @@ mod tests {
 
     #[test]
     fn test_participant_joined_content_without_company() {
-        let content = participant_joined_content("Алексей", None);
+        let content = participant_joined_content("Алексей", None, &LocaleContext::default());
         assert!(content.contains("participant_joined"));
         assert!(content.contains("Алексей"));
         assert!(!content.contains("company"));
     }
 
+    #[test]
+    fn test_participant_replaced_content() {
+        let content = participant_replaced_content(
+            "Алексей",
+            "Мария",
+            Some("ООО Василёк"),
+            &LocaleContext::default(),
+        );
+        assert!(content.contains("participant_replaced"));
+        assert!(content.contains("Алексей"));
+        assert!(content.contains("Мария"));
+        assert!(content.contains("ООО Василёк"));
+    }
+
     #[test]
     fn test_participant_left_content() {
-        let content = participant_left_content("Алексей");
+        let content = participant_left_content("Алексей", &LocaleContext::default());
         assert!(content.contains("participant_left"));
         assert!(content.contains("Алексей"));
     }
 
+    #[test]
+    fn test_action_clicked_content() {
+        let message_id = uuid::Uuid::now_v7();
+        let content = action_clicked_content(
+            "Алексей",
+            message_id,
+            "approve",
+            "Согласовать",
+            &LocaleContext::default(),
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed["event"], "action_clicked");
+        assert_eq!(parsed["name"], "Алексей");
+        assert_eq!(parsed["message_id"], message_id.to_string());
+        assert_eq!(parsed["action_id"], "approve");
+        assert_eq!(parsed["label"], "Согласовать");
+    }
+
+    #[test]
+    fn test_object_event_content() {
+        let payload = json!({ "from": "open", "to": "closed" });
+        let content = object_event_content(
+            "status_changed",
+            &payload,
+            Some("Тендер закрыт"),
+            None,
+            &LocaleContext::default(),
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed["event"], "object_event");
+        assert_eq!(parsed["type"], "status_changed");
+        assert_eq!(parsed["payload"]["to"], "closed");
+        assert_eq!(parsed["text"], "Тендер закрыт");
+        assert!(parsed.get("occurred_at").is_none());
+    }
+
+    #[test]
+    fn test_object_event_type_validation() {
+        assert!(is_valid_object_event_type("status_changed"));
+        assert!(is_valid_object_event_type("deadline.moved"));
+        assert!(!is_valid_object_event_type(""));
+        assert!(!is_valid_object_event_type("Status"));
+        assert!(!is_valid_object_event_type("_status"));
+        assert!(!is_valid_object_event_type("status changed"));
+        assert!(!is_valid_object_event_type(&"a".repeat(65)));
+    }
+
     #[test]
     fn test_json_format() {
         // Verify JSON can be parsed
-        let content = chat_created_content(vec![ParticipantInfo {
-            name: "Test".to_string(),
-            company: None,
-        }]);
+        let content = chat_created_content(
+            vec![ParticipantInfo {
+                name: "Test".to_string(),
+                company: None,
+            }],
+            &LocaleContext::default(),
+        );
         let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
         assert_eq!(parsed["event"], "chat_created");
         assert!(parsed["participants"].is_array());
+        assert!(parsed.get("timezone").is_none());
+    }
+
+    #[test]
+    fn test_locale_embedded() {
+        let locale = LocaleContext {
+            timezone: Some("Europe/Moscow".to_string()),
+            locale: Some("ru-RU".to_string()),
+        };
+        let content = participant_left_content("Алексей", &locale);
+        let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed["timezone"], "Europe/Moscow");
+        assert_eq!(parsed["locale"], "ru-RU");
     }
 }
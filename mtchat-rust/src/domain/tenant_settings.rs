@@ -0,0 +1,43 @@
+//! Tenant settings entity
+//!
+//! Branding and behavior of the embedded widget for a tenant (a
+//! `scope_level0` value). MTChat stores the objects as given; only the widget
+//! interprets their keys.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::SanitizeProfile;
+
+/// Maximum serialized size of the `branding` and `behavior` objects
+pub const MAX_TENANT_SETTINGS_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TenantSettings {
+    pub tenant_uid: String,
+    /// Colors, logo and other visual settings
+    pub branding: serde_json::Value,
+    /// Widget behavior switches
+    pub behavior: serde_json::Value,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    /// HTML sanitization profile of the tenant's dialogs (`None` = global)
+    pub sanitize_profile: Option<SanitizeProfile>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TenantSettings {
+    /// Settings of a tenant nobody has configured yet
+    pub fn empty(tenant_uid: impl Into<String>) -> Self {
+        Self {
+            tenant_uid: tenant_uid.into(),
+            branding: serde_json::json!({}),
+            behavior: serde_json::json!({}),
+            locale: None,
+            timezone: None,
+            sanitize_profile: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
@@ -21,6 +21,15 @@ pub const MAX_PHONE_LENGTH: usize = 50;
 /// Maximum length for external identifiers (user_id, object_id, scope values)
 pub const MAX_IDENTIFIER_LENGTH: usize = 255;
 
+/// Maximum length for an IANA timezone name
+pub const MAX_TIMEZONE_LENGTH: usize = 64;
+
+/// Maximum length for a BCP 47 locale tag
+pub const MAX_LOCALE_LENGTH: usize = 35;
+
+/// Maximum serialized size of message metadata in bytes
+pub const MAX_MESSAGE_METADATA_BYTES: usize = 4096;
+
 /// Validation error with field name and limit
 #[derive(Debug)]
 pub struct ValidationError {
@@ -97,11 +106,39 @@ pub fn validate_company(company: &Option<String>) -> Result<(), ValidationError>
     validate_optional_length(company, "company", MAX_COMPANY_LENGTH)
 }
 
+/// Validate company identifier
+pub fn validate_company_uid(company_uid: &Option<String>) -> Result<(), ValidationError> {
+    validate_optional_length(company_uid, "company_uid", MAX_IDENTIFIER_LENGTH)
+}
+
 /// Validate email
 pub fn validate_email(email: &Option<String>) -> Result<(), ValidationError> {
     validate_optional_length(email, "email", MAX_EMAIL_LENGTH)
 }
 
+/// Validate the email of a participant invite: required and roughly
+/// `local@domain` (delivery is up to the host)
+pub fn validate_invite_email(email: &str) -> Result<(), ValidationError> {
+    let email = email.trim();
+    if email.is_empty() {
+        return Err(ValidationError::required("email"));
+    }
+    if email.len() > MAX_EMAIL_LENGTH {
+        return Err(ValidationError::too_long("email", MAX_EMAIL_LENGTH));
+    }
+    let valid = email
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        && !email.contains(char::is_whitespace);
+    if !valid {
+        return Err(ValidationError {
+            field: "email",
+            message: "email must be an email address".to_string(),
+        });
+    }
+    Ok(())
+}
+
 /// Validate phone
 pub fn validate_phone(phone: &Option<String>) -> Result<(), ValidationError> {
     validate_optional_length(phone, "phone", MAX_PHONE_LENGTH)
@@ -129,6 +166,75 @@ pub fn validate_optional_identifier(
     Ok(())
 }
 
+/// Validate IANA timezone name syntax ("UTC", "Europe/Moscow", "America/Argentina/Buenos_Aires").
+/// Whether the zone exists is checked against the database's zone list.
+pub fn validate_timezone(timezone: &Option<String>) -> Result<(), ValidationError> {
+    let Some(tz) = timezone else {
+        return Ok(());
+    };
+    validate_length(tz, "timezone", MAX_TIMEZONE_LENGTH)?;
+    let valid = !tz.is_empty()
+        && tz.split('/').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+        });
+    if !valid {
+        return Err(ValidationError {
+            field: "timezone",
+            message: format!(
+                "Invalid timezone '{}': expected an IANA name like Europe/Moscow",
+                tz
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Validate BCP 47 locale tag syntax ("ru", "en-US", "zh-Hant-TW")
+pub fn validate_locale(locale: &Option<String>) -> Result<(), ValidationError> {
+    let Some(tag) = locale else {
+        return Ok(());
+    };
+    validate_length(tag, "locale", MAX_LOCALE_LENGTH)?;
+    let mut parts = tag.split('-');
+    let language_ok = parts
+        .next()
+        .is_some_and(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+    let subtags_ok =
+        parts.all(|p| (1..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !language_ok || !subtags_ok {
+        return Err(ValidationError {
+            field: "locale",
+            message: format!("Invalid locale '{}': expected a BCP 47 tag like ru-RU", tag),
+        });
+    }
+    Ok(())
+}
+
+/// Validate integration metadata of a message: a JSON object of limited size
+pub fn validate_message_metadata(
+    metadata: &Option<serde_json::Value>,
+) -> Result<(), ValidationError> {
+    let Some(metadata) = metadata else {
+        return Ok(());
+    };
+    if !metadata.is_object() {
+        return Err(ValidationError {
+            field: "metadata",
+            message: "metadata must be a JSON object".to_string(),
+        });
+    }
+    if metadata.to_string().len() > MAX_MESSAGE_METADATA_BYTES {
+        return Err(ValidationError {
+            field: "metadata",
+            message: format!("metadata exceeds {} bytes", MAX_MESSAGE_METADATA_BYTES),
+        });
+    }
+    Ok(())
+}
+
 /// Validate S3 key for path traversal attacks and dialog ownership
 pub fn validate_s3_key(s3_key: &str, dialog_id: uuid::Uuid) -> Result<(), ValidationError> {
     // Check for path traversal sequences
@@ -195,6 +301,53 @@ mod tests {
         assert!(validate_message_content("hello").is_ok());
     }
 
+    #[test]
+    fn test_validate_message_metadata() {
+        assert!(validate_message_metadata(&None).is_ok());
+        assert!(validate_message_metadata(&Some(serde_json::json!({"quote_line": 7}))).is_ok());
+        assert!(validate_message_metadata(&Some(serde_json::json!([1, 2]))).is_err());
+        assert!(validate_message_metadata(&Some(serde_json::json!("text"))).is_err());
+
+        let large = "x".repeat(MAX_MESSAGE_METADATA_BYTES);
+        let err = validate_message_metadata(&Some(serde_json::json!({ "k": large }))).unwrap_err();
+        assert_eq!(err.field, "metadata");
+    }
+
+    #[test]
+    fn test_validate_invite_email() {
+        assert!(validate_invite_email("supplier@example.com").is_ok());
+        assert!(validate_invite_email(" Supplier@Example.com ").is_ok());
+        assert!(validate_invite_email("").is_err());
+        assert!(validate_invite_email("supplier").is_err());
+        assert!(validate_invite_email("@example.com").is_err());
+        assert!(validate_invite_email("supplier@localhost").is_err());
+        assert!(validate_invite_email("sup plier@example.com").is_err());
+    }
+
+    #[test]
+    fn test_validate_timezone() {
+        assert!(validate_timezone(&None).is_ok());
+        assert!(validate_timezone(&Some("UTC".into())).is_ok());
+        assert!(validate_timezone(&Some("Europe/Moscow".into())).is_ok());
+        assert!(validate_timezone(&Some("America/Argentina/Buenos_Aires".into())).is_ok());
+        assert!(validate_timezone(&Some("Etc/GMT+3".into())).is_ok());
+        assert!(validate_timezone(&Some("".into())).is_err());
+        assert!(validate_timezone(&Some("Europe//Moscow".into())).is_err());
+        assert!(validate_timezone(&Some("Europe/Mos cow".into())).is_err());
+    }
+
+    #[test]
+    fn test_validate_locale() {
+        assert!(validate_locale(&None).is_ok());
+        assert!(validate_locale(&Some("ru".into())).is_ok());
+        assert!(validate_locale(&Some("en-US".into())).is_ok());
+        assert!(validate_locale(&Some("zh-Hant-TW".into())).is_ok());
+        assert!(validate_locale(&Some("".into())).is_err());
+        assert!(validate_locale(&Some("english".into())).is_err());
+        assert!(validate_locale(&Some("en_US".into())).is_err());
+        assert!(validate_locale(&Some("en-".into())).is_err());
+    }
+
     #[test]
     fn test_validate_s3_key_valid() {
         let dialog_id = uuid::Uuid::parse_str("12345678-1234-1234-1234-123456789abc").unwrap();
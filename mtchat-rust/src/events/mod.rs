@@ -0,0 +1,301 @@
+//! Domain events.
+//!
+//! Handlers publish what happened (a message was sent, edited or deleted, a
+//! participant joined or archived the dialog) to the [`EventBus`] instead of
+//! running every side effect themselves. Each subscriber runs in its own task
+//! and receives every event in publish order:
+//!
+//! - [`WsSubscriber`]: WebSocket broadcast to connected participants
+//! - [`WebhookSubscriber`]: outgoing webhooks (and the event stream, which is
+//!   fed with the webhook events)
+//! - [`JobSubscriber`]: notification, thumbnail, text extraction and
+//!   attachment cleanup jobs
+//! - [`ActivitySubscriber`]: the dialog activity feed
+//!
+//! A new integration is one more [`Subscriber`], registered in
+//! [`spawn_subscribers`]. Subscribers see the request ID of the request that
+//! published the event, so webhooks and jobs keep carrying it.
+//!
+//! Delivery is in memory and per instance: a subscriber that falls more than
+//! [`EVENT_BUS_CAPACITY`] events behind skips the oldest ones (logged).
+
+mod subscribers;
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use crate::api::AppState;
+use crate::domain::{
+    Attachment, BroadcastMention, Dialog, DialogAccessScope, JoinedAs, Message, ReplyPreview,
+};
+use crate::middleware::{as_service, current_request_id, with_request_id};
+use crate::webhooks::ArchiveTrigger;
+
+pub use subscribers::{ActivitySubscriber, JobSubscriber, WebhookSubscriber, WsSubscriber};
+
+/// Events a subscriber may lag behind before it skips events
+pub const EVENT_BUS_CAPACITY: usize = 4096;
+
+/// Something that happened in a dialog
+#[derive(Debug, Clone)]
+pub enum DomainEvent {
+    /// A participant sent a message
+    MessageSent {
+        dialog: Dialog,
+        message: Message,
+        reply_to: Option<ReplyPreview>,
+        attachments: Vec<Attachment>,
+        /// Participants the message unarchived the dialog for
+        unarchived_for: Vec<String>,
+        /// `@channel` / `@here` in the message
+        broadcast: Option<BroadcastMention>,
+    },
+    /// The author edited a message
+    MessageEdited {
+        dialog: Dialog,
+        /// Message after the edit
+        message: Message,
+        previous_content: String,
+    },
+    /// The author deleted a message
+    MessageDeleted {
+        /// `None` if the dialog was deleted meanwhile
+        dialog: Option<Dialog>,
+        message: Message,
+        /// Storage keys of the message's attachments and their thumbnails
+        attachment_keys: Vec<String>,
+    },
+    /// A user became a participant
+    ParticipantJoined {
+        dialog_id: Uuid,
+        user_id: String,
+        joined_as: JoinedAs,
+        /// User who added them (`None` for management, invites or themselves)
+        added_by: Option<String>,
+    },
+    /// A participant left or was removed
+    ParticipantLeft {
+        dialog_id: Uuid,
+        user_id: String,
+        /// User who removed them (`None` for management or themselves)
+        removed_by: Option<String>,
+    },
+    /// The dialog was archived or unarchived for participants
+    ArchiveChanged {
+        dialog_id: Uuid,
+        user_ids: Vec<String>,
+        archived: bool,
+        trigger: ArchiveTrigger,
+        triggered_by: Option<String>,
+    },
+    /// A participant pinned or unpinned the dialog
+    PinChanged {
+        dialog_id: Uuid,
+        user_id: String,
+        pinned: bool,
+    },
+    /// The access scopes of the dialog were replaced
+    AccessScopesChanged {
+        dialog_id: Uuid,
+        scopes: Vec<DialogAccessScope>,
+    },
+}
+
+impl DomainEvent {
+    /// Name for logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MessageSent { .. } => "message_sent",
+            Self::MessageEdited { .. } => "message_edited",
+            Self::MessageDeleted { .. } => "message_deleted",
+            Self::ParticipantJoined { .. } => "participant_joined",
+            Self::ParticipantLeft { .. } => "participant_left",
+            Self::ArchiveChanged { .. } => "archive_changed",
+            Self::PinChanged { .. } => "pin_changed",
+            Self::AccessScopesChanged { .. } => "access_scopes_changed",
+        }
+    }
+}
+
+/// Published event with the request it came from
+#[derive(Debug)]
+struct Envelope {
+    event: DomainEvent,
+    request_id: Option<String>,
+}
+
+/// Consumer of domain events
+pub trait Subscriber: Send + Sync + 'static {
+    /// Name for logs
+    fn name(&self) -> &'static str;
+
+    /// Handle one event. Events are handled one at a time, in publish order.
+    fn handle(&self, event: &DomainEvent) -> impl Future<Output = ()> + Send;
+}
+
+/// In-process bus of domain events (cheap to clone)
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Arc<Envelope>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { tx }
+    }
+
+    /// Publish an event to all subscribers (non-blocking)
+    pub fn publish(&self, event: DomainEvent) {
+        let envelope = Envelope {
+            event,
+            request_id: current_request_id(),
+        };
+        if let Err(e) = self.tx.send(Arc::new(envelope)) {
+            tracing::debug!(
+                event = e.0.event.name(),
+                "No event subscribers, event dropped"
+            );
+        }
+    }
+
+    /// Number of running subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    /// Run `subscriber` on every event published from now on
+    pub fn spawn_subscriber<S: Subscriber>(&self, subscriber: S) {
+        // Subscribe before spawning so no event published after this call is missed
+        let mut rx = self.tx.subscribe();
+        // Subscribers react to events as service work, not in the tenant
+        // context of the request that published them
+        tokio::spawn(as_service(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(envelope) => {
+                        with_request_id(
+                            envelope.request_id.clone(),
+                            subscriber.handle(&envelope.event),
+                        )
+                        .await
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            subscriber = subscriber.name(),
+                            skipped,
+                            "Event subscriber fell behind, events skipped"
+                        );
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+}
+
+/// Start the built-in subscribers. Called once when the service is built.
+pub fn spawn_subscribers(state: &AppState) {
+    state.events.spawn_subscriber(WsSubscriber::new(state));
+    state.events.spawn_subscriber(WebhookSubscriber::new(state));
+    state.events.spawn_subscriber(JobSubscriber::new(state));
+    state
+        .events
+        .spawn_subscriber(ActivitySubscriber::new(state));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ActivityType;
+    use tokio::sync::mpsc;
+
+    struct Recorder(mpsc::UnboundedSender<(&'static str, Option<String>)>);
+
+    impl Subscriber for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        async fn handle(&self, event: &DomainEvent) {
+            let _ = self.0.send((event.name(), current_request_id()));
+        }
+    }
+
+    fn edited() -> DomainEvent {
+        let dialog = Dialog::new("order-1", "order", None, None, None, None);
+        let message = Message::new(dialog.id, "user-1", "Hello");
+        DomainEvent::MessageEdited {
+            dialog,
+            message,
+            previous_content: "Hi".into(),
+        }
+    }
+
+    #[test]
+    fn test_activity_entries() {
+        let dialog_id = Uuid::new_v4();
+        let archived = DomainEvent::ArchiveChanged {
+            dialog_id,
+            user_ids: vec!["user-1".into(), "user-2".into()],
+            archived: true,
+            trigger: ArchiveTrigger::AutoArchive,
+            triggered_by: None,
+        };
+        let entries = subscribers::activity_entries(&archived);
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.personal));
+        assert_eq!(entries[0].activity_type, ActivityType::DialogArchived);
+        assert_eq!(entries[1].user_id.as_deref(), Some("user-2"));
+        assert_eq!(entries[0].details["trigger"], "auto_archive");
+
+        let joined = DomainEvent::ParticipantJoined {
+            dialog_id,
+            user_id: "user-3".into(),
+            joined_as: JoinedAs::Observer,
+            added_by: None,
+        };
+        let entries = subscribers::activity_entries(&joined);
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].personal);
+        assert_eq!(entries[0].details["joined_as"], "observer");
+
+        assert!(subscribers::activity_entries(&edited()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events_with_request_id() {
+        let bus = EventBus::new();
+        // Without subscribers events are dropped
+        bus.publish(edited());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        bus.spawn_subscriber(Recorder(tx.clone()));
+        bus.spawn_subscriber(Recorder(tx));
+        assert_eq!(bus.subscriber_count(), 2);
+
+        with_request_id(Some("req-1".into()), async { bus.publish(edited()) }).await;
+        bus.publish(edited());
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            received.push(rx.recv().await.unwrap());
+        }
+        let with_id = received
+            .iter()
+            .filter(|(_, id)| id.as_deref() == Some("req-1"))
+            .count();
+        assert_eq!(with_id, 2);
+        assert!(received.iter().all(|(name, _)| *name == "message_edited"));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! Built-in event subscribers.
+
+use std::sync::Arc;
+
+use super::{DomainEvent, Subscriber};
+use crate::api::AppState;
+use crate::domain::{ActivityType, Attachment, BroadcastMention, Dialog, DialogActivity, Message};
+use crate::jobs::{
+    AttachmentCleanupJob, JobProducer, NotificationJob, TextExtractJob, ThumbnailJob,
+};
+use crate::repositories::{DialogActivityRepository, ParticipantRepository};
+use crate::services::{preview, text_extract, PresenceService, SettingsService};
+use crate::webhooks::{ArchiveTrigger, WebhookEvent, WebhookSender};
+use crate::ws::{self, Connections};
+
+/// Broadcasts events to the WebSocket connections of participants
+pub struct WsSubscriber {
+    connections: Connections,
+    participants: Arc<ParticipantRepository>,
+}
+
+impl WsSubscriber {
+    pub fn new(state: &AppState) -> Self {
+        Self {
+            connections: state.connections.clone(),
+            participants: state.participants.clone(),
+        }
+    }
+}
+
+impl Subscriber for WsSubscriber {
+    fn name(&self) -> &'static str {
+        "ws"
+    }
+
+    async fn handle(&self, event: &DomainEvent) {
+        match event {
+            DomainEvent::MessageSent {
+                message,
+                reply_to,
+                unarchived_for,
+                ..
+            } => {
+                if !unarchived_for.is_empty() {
+                    tracing::debug!(dialog_id = %message.dialog_id, count = unarchived_for.len(), "Auto-unarchived dialog for participants");
+                    ws::broadcast_dialog_unarchived(
+                        &self.connections,
+                        message.dialog_id,
+                        unarchived_for,
+                    )
+                    .await;
+                }
+                ws::broadcast_message(
+                    &self.connections,
+                    &self.participants,
+                    message.dialog_id,
+                    message,
+                    reply_to.as_ref(),
+                )
+                .await;
+            }
+            DomainEvent::MessageEdited { message, .. } => {
+                ws::broadcast_message_edited(&self.connections, &self.participants, message).await;
+            }
+            DomainEvent::MessageDeleted { message, .. } => {
+                ws::broadcast_message_deleted(
+                    &self.connections,
+                    &self.participants,
+                    message.dialog_id,
+                    message.id,
+                )
+                .await;
+            }
+            // Membership, archive and pin changes are broadcast by their handlers
+            _ => {}
+        }
+    }
+}
+
+/// Sends events as outgoing webhooks
+pub struct WebhookSubscriber {
+    webhooks: WebhookSender,
+}
+
+impl WebhookSubscriber {
+    pub fn new(state: &AppState) -> Self {
+        Self {
+            webhooks: state.webhooks.clone(),
+        }
+    }
+}
+
+impl Subscriber for WebhookSubscriber {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, event: &DomainEvent) {
+        match event {
+            DomainEvent::MessageSent {
+                dialog,
+                message,
+                attachments,
+                unarchived_for,
+                ..
+            } => {
+                if !unarchived_for.is_empty() {
+                    self.webhooks
+                        .send(WebhookEvent::dialog_unarchived(
+                            dialog,
+                            unarchived_for.clone(),
+                            ArchiveTrigger::NewMessage,
+                            message.sender_id.as_deref(),
+                        ))
+                        .await;
+                }
+                self.webhooks
+                    .send(WebhookEvent::message_new(dialog, message).with_attachments(attachments))
+                    .await;
+            }
+            DomainEvent::MessageEdited {
+                dialog,
+                message,
+                previous_content,
+            } => {
+                self.webhooks
+                    .send(WebhookEvent::message_edited(
+                        dialog,
+                        message,
+                        previous_content,
+                    ))
+                    .await;
+            }
+            DomainEvent::MessageDeleted {
+                dialog: Some(dialog),
+                message,
+                ..
+            } => {
+                self.webhooks
+                    .send(WebhookEvent::message_deleted(dialog, message))
+                    .await;
+            }
+            DomainEvent::MessageDeleted { dialog: None, .. } => {}
+            // Membership and archive webhooks are sent by their handlers
+            _ => {}
+        }
+    }
+}
+
+/// Enqueues the background jobs that follow events
+pub struct JobSubscriber {
+    jobs: JobProducer,
+    participants: Arc<ParticipantRepository>,
+    presence: Arc<PresenceService>,
+    settings: Arc<SettingsService>,
+}
+
+impl JobSubscriber {
+    pub fn new(state: &AppState) -> Self {
+        Self {
+            jobs: state.jobs.clone(),
+            participants: state.participants.clone(),
+            presence: state.presence.clone(),
+            settings: state.settings.clone(),
+        }
+    }
+
+    /// Notification jobs for the recipients of a new message
+    async fn enqueue_notifications(
+        &self,
+        dialog: &Dialog,
+        message: &Message,
+        broadcast: Option<BroadcastMention>,
+    ) {
+        let Some(sender_id) = message.sender_id.as_deref() else {
+            return;
+        };
+        let participants = match self.participants.list_by_dialog(dialog.id).await {
+            Ok(participants) => participants,
+            Err(e) => {
+                tracing::warn!(dialog_id = %dialog.id, error = %e, "Failed to load participants for notifications");
+                return;
+            }
+        };
+
+        // Recipients reached by a broadcast mention get mention-priority jobs
+        let mentioned: Vec<String> = match broadcast {
+            Some(BroadcastMention::Channel) => {
+                participants.iter().map(|p| p.user_id.clone()).collect()
+            }
+            Some(BroadcastMention::Here) => {
+                let user_ids: Vec<String> =
+                    participants.iter().map(|p| p.user_id.clone()).collect();
+                self.presence
+                    .get_online_users(&user_ids)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::warn!(error = %e, "Failed to resolve online users for @here");
+                        Vec::new()
+                    })
+            }
+            None => Vec::new(),
+        };
+
+        let settings = self.settings.current();
+        for participant in &participants {
+            if participant.user_id != sender_id
+                && !participant.joined_as.is_observer()
+                && !participant.is_pending_removal()
+            {
+                let mut job =
+                    NotificationJob::new(dialog.id, &participant.user_id, message.id, sender_id)
+                        .with_priority(message.priority);
+                if let Some(mention) = broadcast {
+                    if mentioned.contains(&participant.user_id) {
+                        job = job.with_broadcast(mention);
+                    }
+                }
+                let delay_ms = settings.notification_delay_for(
+                    dialog,
+                    message.priority,
+                    job.broadcast.is_some(),
+                );
+                if let Err(e) = self.jobs.enqueue_notification(job, delay_ms).await {
+                    tracing::warn!(
+                        recipient_id = %participant.user_id,
+                        error = %e,
+                        "Failed to enqueue notification job"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Preview and text extraction jobs for new attachments
+    async fn enqueue_attachment_jobs(&self, attachments: &[Attachment]) {
+        if preview::is_enabled() {
+            for att in attachments.iter().filter(|a| a.is_pdf()) {
+                if let Err(e) = self.jobs.enqueue_thumbnail(ThumbnailJob::new(att.id)).await {
+                    tracing::warn!(
+                        attachment_id = %att.id,
+                        error = %e,
+                        "Failed to enqueue thumbnail job"
+                    );
+                }
+            }
+        }
+        if text_extract::is_enabled() {
+            for att in attachments
+                .iter()
+                .filter(|a| text_extract::is_extractable(&a.content_type))
+            {
+                if let Err(e) = self
+                    .jobs
+                    .enqueue_text_extract(TextExtractJob::new(att.id))
+                    .await
+                {
+                    tracing::warn!(
+                        attachment_id = %att.id,
+                        error = %e,
+                        "Failed to enqueue text extraction job"
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Subscriber for JobSubscriber {
+    fn name(&self) -> &'static str {
+        "jobs"
+    }
+
+    async fn handle(&self, event: &DomainEvent) {
+        if !self.jobs.is_enabled() {
+            return;
+        }
+        match event {
+            DomainEvent::MessageSent {
+                dialog,
+                message,
+                attachments,
+                broadcast,
+                ..
+            } => {
+                self.enqueue_notifications(dialog, message, *broadcast)
+                    .await;
+                self.enqueue_attachment_jobs(attachments).await;
+            }
+            DomainEvent::MessageDeleted {
+                message,
+                attachment_keys,
+                ..
+            } => {
+                if let Err(e) = self
+                    .jobs
+                    .enqueue_attachment_cleanup(AttachmentCleanupJob::new(
+                        message.dialog_id,
+                        attachment_keys.clone(),
+                    ))
+                    .await
+                {
+                    tracing::warn!(message_id = %message.id, error = %e, "Failed to enqueue attachment cleanup");
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Records the dialog activity feed
+pub struct ActivitySubscriber {
+    activity: Arc<DialogActivityRepository>,
+}
+
+impl ActivitySubscriber {
+    pub fn new(state: &AppState) -> Self {
+        Self {
+            activity: state.activity.clone(),
+        }
+    }
+}
+
+impl Subscriber for ActivitySubscriber {
+    fn name(&self) -> &'static str {
+        "activity"
+    }
+
+    async fn handle(&self, event: &DomainEvent) {
+        let entries = activity_entries(event);
+        if let Err(e) = self.activity.record(&entries).await {
+            tracing::warn!(event = event.name(), error = %e, "Failed to record dialog activity");
+        }
+    }
+}
+
+/// Activity feed entries of an event (none for message edits and deletions)
+pub(crate) fn activity_entries(event: &DomainEvent) -> Vec<DialogActivity> {
+    match event {
+        DomainEvent::MessageSent {
+            message,
+            unarchived_for,
+            ..
+        } => archive_entries(
+            message.dialog_id,
+            unarchived_for,
+            false,
+            ArchiveTrigger::NewMessage,
+            message.sender_id.as_deref(),
+        ),
+        DomainEvent::MessageEdited { .. } | DomainEvent::MessageDeleted { .. } => Vec::new(),
+        DomainEvent::ParticipantJoined {
+            dialog_id,
+            user_id,
+            joined_as,
+            added_by,
+        } => vec![DialogActivity::new(
+            *dialog_id,
+            ActivityType::ParticipantJoined,
+            Some(user_id.clone()),
+            added_by.clone(),
+        )
+        .with_details(serde_json::json!({ "joined_as": joined_as }))],
+        DomainEvent::ParticipantLeft {
+            dialog_id,
+            user_id,
+            removed_by,
+        } => vec![DialogActivity::new(
+            *dialog_id,
+            ActivityType::ParticipantLeft,
+            Some(user_id.clone()),
+            removed_by.clone(),
+        )],
+        DomainEvent::ArchiveChanged {
+            dialog_id,
+            user_ids,
+            archived,
+            trigger,
+            triggered_by,
+        } => archive_entries(
+            *dialog_id,
+            user_ids,
+            *archived,
+            *trigger,
+            triggered_by.as_deref(),
+        ),
+        DomainEvent::PinChanged {
+            dialog_id,
+            user_id,
+            pinned,
+        } => {
+            let activity_type = if *pinned {
+                ActivityType::DialogPinned
+            } else {
+                ActivityType::DialogUnpinned
+            };
+            vec![DialogActivity::new(
+                *dialog_id,
+                activity_type,
+                Some(user_id.clone()),
+                Some(user_id.clone()),
+            )]
+        }
+        DomainEvent::AccessScopesChanged { dialog_id, scopes } => {
+            let scopes: Vec<_> = scopes
+                .iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "scope_level0": s.scope_level0,
+                        "scope_level1": s.scope_level1,
+                        "scope_level2": s.scope_level2,
+                    })
+                })
+                .collect();
+            vec![
+                DialogActivity::new(*dialog_id, ActivityType::AccessScopesChanged, None, None)
+                    .with_details(serde_json::json!({ "scopes": scopes })),
+            ]
+        }
+    }
+}
+
+/// One personal entry per participant the dialog was (un)archived for
+fn archive_entries(
+    dialog_id: uuid::Uuid,
+    user_ids: &[String],
+    archived: bool,
+    trigger: ArchiveTrigger,
+    triggered_by: Option<&str>,
+) -> Vec<DialogActivity> {
+    let activity_type = if archived {
+        ActivityType::DialogArchived
+    } else {
+        ActivityType::DialogUnarchived
+    };
+    user_ids
+        .iter()
+        .map(|user_id| {
+            DialogActivity::new(
+                dialog_id,
+                activity_type,
+                Some(user_id.clone()),
+                triggered_by.map(str::to_string),
+            )
+            .with_details(serde_json::json!({ "trigger": trigger }))
+        })
+        .collect()
+}
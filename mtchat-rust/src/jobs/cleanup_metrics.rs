@@ -0,0 +1,77 @@
+//! Attachment cleanup counters.
+//!
+//! Updated by the cleanup worker and read by the metrics endpoint; both run
+//! in the same process, so plain atomics are enough (per-instance values).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Snapshot of the cleanup counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupSnapshot {
+    /// Storage objects deleted
+    pub deleted_total: u64,
+    /// Storage objects kept because another attachment still references them
+    pub skipped_total: u64,
+    /// Failed delete attempts (the job is retried)
+    pub failed_total: u64,
+}
+
+/// Attachment cleanup counters (shared, cheap to clone).
+#[derive(Clone, Default)]
+pub struct CleanupMetrics {
+    deleted_total: Arc<AtomicU64>,
+    skipped_total: Arc<AtomicU64>,
+    failed_total: Arc<AtomicU64>,
+}
+
+impl CleanupMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_deleted(&self) {
+        self.deleted_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_skipped(&self) {
+        self.skipped_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failed(&self) {
+        self.failed_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CleanupSnapshot {
+        CleanupSnapshot {
+            deleted_total: self.deleted_total.load(Ordering::Relaxed),
+            skipped_total: self.skipped_total.load(Ordering::Relaxed),
+            failed_total: self.failed_total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_are_shared_between_clones() {
+        let metrics = CleanupMetrics::new();
+        let clone = metrics.clone();
+
+        clone.record_deleted();
+        clone.record_deleted();
+        clone.record_skipped();
+        metrics.record_failed();
+
+        assert_eq!(
+            metrics.snapshot(),
+            CleanupSnapshot {
+                deleted_total: 2,
+                skipped_total: 1,
+                failed_total: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Notification digests.
+//!
+//! With the `notification_digest_window_secs` runtime setting, unread
+//! `notification.pending` notifications of a recipient are not sent one by
+//! one. They are counted per dialog in Redis; the first one opens the
+//! recipient's window, and once it has ended a cron job sends a single
+//! `notification.digest` listing the dialogs that are still unread. Mentions
+//! and urgent messages are never held back.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use apalis::prelude::*;
+use chrono::Utc;
+use fred::clients::Pool as RedisPool;
+use fred::interfaces::{HashesInterface, KeysInterface, SortedSetsInterface, TransactionInterface};
+use fred::types::SetOptions;
+use uuid::Uuid;
+
+use super::handlers::JobContext;
+use super::types::{NotificationJob, SendDigestsJob};
+use crate::webhooks::{DigestDialog, WebhookEvent};
+
+/// Sorted set of recipients with an open window (score: window end in milliseconds)
+const DIGEST_DUE_KEY: &str = "mtchat:notifications:digests";
+
+/// How long counts outlive their window if digests are not sent (worker down)
+const DIGEST_KEY_TTL_SECS: i64 = 86400;
+
+/// Redis hash counting the held back notifications of a recipient per dialog
+fn digest_key(recipient_id: &str) -> String {
+    format!("mtchat:notifications:digest:{}", recipient_id)
+}
+
+/// Hold back a notification for the recipient's digest, opening a window
+/// of `window_secs` unless one is open already
+pub async fn add_to_digest(
+    redis: &RedisPool,
+    job: &NotificationJob,
+    window_secs: u64,
+) -> Result<(), fred::error::Error> {
+    let key = digest_key(&job.recipient_id);
+    redis
+        .hincrby::<i64, _, _>(&key, job.dialog_id.to_string(), 1)
+        .await?;
+    redis
+        .expire::<(), _>(&key, window_secs as i64 + DIGEST_KEY_TTL_SECS, None)
+        .await?;
+
+    let due_ms = Utc::now().timestamp_millis() + window_secs as i64 * 1000;
+    redis
+        .zadd::<(), _, _>(
+            DIGEST_DUE_KEY,
+            Some(SetOptions::NX),
+            None,
+            false,
+            false,
+            (due_ms as f64, job.recipient_id.clone()),
+        )
+        .await
+}
+
+/// Handle digest job.
+///
+/// Each due recipient is claimed by removing it from the due set, so a
+/// digest goes out once even with several instances running the job.
+pub async fn handle_send_digests(job: SendDigestsJob, ctx: Data<JobContext>) -> Result<(), Error> {
+    let now_ms = Utc::now().timestamp_millis() as f64;
+    let recipients: Vec<String> = match ctx
+        .redis
+        .zrangebyscore(DIGEST_DUE_KEY, "-inf", now_ms, false, None)
+        .await
+    {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            tracing::error!(run_id = %job.run_id, error = %e, "Failed to list due digests");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    let mut sent = 0;
+    for recipient_id in recipients {
+        match ctx
+            .redis
+            .zrem::<i64, _, _>(DIGEST_DUE_KEY, recipient_id.as_str())
+            .await
+        {
+            Ok(1) => {}
+            Ok(_) => continue, // claimed by another instance
+            Err(e) => {
+                tracing::warn!(recipient_id = %recipient_id, error = %e, "Failed to claim digest");
+                continue;
+            }
+        }
+
+        let counts = match take_counts(&ctx.redis, &recipient_id).await {
+            Ok(counts) => counts,
+            Err(e) => {
+                tracing::warn!(recipient_id = %recipient_id, error = %e, "Failed to read digest");
+                continue;
+            }
+        };
+        match send_digest(&ctx, &recipient_id, counts).await {
+            Ok(true) => sent += 1,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(recipient_id = %recipient_id, error = %e, "Failed to send digest");
+            }
+        }
+    }
+
+    if sent > 0 {
+        tracing::info!(run_id = %job.run_id, sent, "Notification digests sent");
+    } else {
+        tracing::debug!(run_id = %job.run_id, "No notification digests due");
+    }
+
+    Ok(())
+}
+
+/// Read and delete the counts of a recipient in one transaction, so
+/// notifications added meanwhile start the next digest
+async fn take_counts(
+    redis: &RedisPool,
+    recipient_id: &str,
+) -> Result<HashMap<String, u64>, fred::error::Error> {
+    let key = digest_key(recipient_id);
+    let trx = redis.next().multi();
+    trx.hgetall::<(), _>(&key).await?;
+    trx.del::<(), _>(&key).await?;
+    let (counts, _): (HashMap<String, u64>, i64) = trx.exec(true).await?;
+    Ok(counts)
+}
+
+/// Send the digest of the dialogs still unread, returning whether one was sent
+async fn send_digest(
+    ctx: &JobContext,
+    recipient_id: &str,
+    counts: HashMap<String, u64>,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let mut dialogs = Vec::with_capacity(counts.len());
+    for (dialog_id, count) in counts {
+        let Ok(dialog_id) = Uuid::parse_str(&dialog_id) else {
+            continue;
+        };
+        let Some(participant) = ctx.participants.find(dialog_id, recipient_id).await? else {
+            continue;
+        };
+        // Read, muted or snoozed during the window
+        if participant.unread_count == 0
+            || !participant.notifications_enabled
+            || participant.active_snooze(now).is_some()
+        {
+            continue;
+        }
+        let Some(dialog) = ctx.dialogs.find_by_id(dialog_id).await? else {
+            continue;
+        };
+        dialogs.push(DigestDialog::new(&dialog, count, participant.unread_count));
+    }
+
+    if dialogs.is_empty() {
+        tracing::debug!(recipient_id, "Digest dialogs already read, skipping");
+        return Ok(false);
+    }
+    dialogs.sort_by(|a, b| {
+        b.notification_count
+            .cmp(&a.notification_count)
+            .then(a.dialog_id.cmp(&b.dialog_id))
+    });
+
+    ctx.webhooks
+        .send(WebhookEvent::notification_digest(recipient_id, dialogs))
+        .await;
+    Ok(true)
+}
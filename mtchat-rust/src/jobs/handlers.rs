@@ -7,9 +7,21 @@ use chrono::{Duration, Utc};
 use fred::clients::Pool as RedisPool;
 use sqlx::PgPool;
 
-use super::types::{AutoArchiveJob, NotificationJob};
-use crate::repositories::{DialogRepository, MessageRepository, ParticipantRepository};
-use crate::webhooks::{WebhookEvent, WebhookSender};
+use super::digest::add_to_digest;
+use super::producer::{is_notification_cancelled, is_notification_skipped, JobProducer};
+use super::types::{
+    AttachmentCleanupJob, AutoArchiveJob, NotificationJob, PurgeDeletedDialogsJob,
+    RemovePendingParticipantsJob, ThumbnailJob,
+};
+use super::worker::WorkerConfig;
+use crate::domain::avatar;
+use crate::events::{DomainEvent, EventBus};
+use crate::repositories::{
+    AttachmentRepository, DialogRepository, ExportRepository, FeatureFlagRepository,
+    MessageRepository, ParticipantRepository, SlaRepository,
+};
+use crate::services::{preview, BlobStorage, SettingsService, StorageError};
+use crate::webhooks::{ArchiveTrigger, WebhookEvent, WebhookSender};
 use crate::ws::{self, Connections};
 
 /// Shared context for job handlers.
@@ -20,30 +32,97 @@ pub struct JobContext {
     pub dialogs: Arc<DialogRepository>,
     pub participants: Arc<ParticipantRepository>,
     pub messages: Arc<MessageRepository>,
+    pub attachments: Arc<AttachmentRepository>,
+    pub feature_flags: Arc<FeatureFlagRepository>,
+    pub sla: Arc<SlaRepository>,
+    pub export: Arc<ExportRepository>,
+    pub storage: Arc<dyn BlobStorage>,
     pub webhooks: WebhookSender,
     pub connections: Connections,
-    /// Seconds of inactivity before auto-archive (default: 259200 = 3 days)
-    pub archive_after_secs: i64,
+    /// Producer for follow-up jobs (attachment cleanup after purge)
+    pub jobs: JobProducer,
+    /// Runtime settings (notification delay, archive window)
+    pub settings: Arc<SettingsService>,
+    /// Domain events (dialog activity of archives and removals)
+    pub events: EventBus,
 }
 
-/// Notification delay in milliseconds (check if message was read).
-const NOTIFICATION_DELAY_MS: u64 = 1000;
-
 /// Handle notification job.
 ///
 /// Waits briefly, then checks if the message has been read by the recipient.
-/// If not read and notifications are enabled, sends a webhook.
-pub async fn handle_notification(job: NotificationJob, ctx: Data<JobContext>) -> Result<(), Error> {
+/// If not read and notifications are enabled, sends a webhook. Broadcast
+/// mentions send `notification.mention` instead, even for muted dialogs.
+/// Urgent messages skip the wait and send `notification.urgent`. Within the
+/// `notification_digest_window_secs` setting, pending notifications are held
+/// back for a `notification.digest` instead (see [`super::digest`]).
+///
+/// Jobs that waited in the queue longer than `notification_max_age_secs`, or
+/// were enqueued before the backlog was skipped, are dropped.
+pub async fn handle_notification(
+    job: NotificationJob,
+    ctx: Data<JobContext>,
+    config: Data<WorkerConfig>,
+) -> Result<(), Error> {
+    let backlog = ctx.jobs.notification_backlog();
+    let age_secs = (Utc::now() - job.enqueued_at).num_seconds();
+    backlog.record_age(age_secs);
+    if age_secs > config.notification_max_age_secs {
+        backlog.record_stale();
+        tracing::info!(
+            recipient_id = %job.recipient_id,
+            message_id = %job.message_id,
+            age_secs,
+            "Notification job is stale, skipping"
+        );
+        return Ok(());
+    }
+
+    match is_notification_skipped(&ctx.redis, &job).await {
+        Ok(true) => {
+            backlog.record_skipped();
+            tracing::debug!(
+                message_id = %job.message_id,
+                "Notification job enqueued before the backlog skip, skipping"
+            );
+            return Ok(());
+        }
+        Ok(false) => {}
+        Err(e) => tracing::warn!(error = %e, "Failed to check notification backlog skip"),
+    }
+
     // Wait before checking read status (gives user time to read if in chat)
-    tokio::time::sleep(std::time::Duration::from_millis(NOTIFICATION_DELAY_MS)).await;
+    let delay_ms = job.delay_ms.unwrap_or_else(|| {
+        if job.priority.is_urgent() {
+            0
+        } else {
+            ctx.settings.current().notification_delay_ms
+        }
+    });
+    if delay_ms > 0 {
+        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+    }
 
     tracing::debug!(
         dialog_id = %job.dialog_id,
         recipient_id = %job.recipient_id,
         message_id = %job.message_id,
+        request_id = job.request_id.as_deref().unwrap_or_default(),
         "Processing notification job"
     );
 
+    // Recipient was removed after the job was enqueued
+    match is_notification_cancelled(&ctx.redis, &job).await {
+        Ok(true) => {
+            tracing::debug!(
+                recipient_id = %job.recipient_id,
+                "Notifications cancelled for recipient, skipping"
+            );
+            return Ok(());
+        }
+        Ok(false) => {}
+        Err(e) => tracing::warn!(error = %e, "Failed to check notification cancellation"),
+    }
+
     // Get participant to check read status
     let participant = match ctx
         .participants
@@ -64,8 +143,9 @@ pub async fn handle_notification(job: NotificationJob, ctx: Data<JobContext>) ->
         }
     };
 
-    // Check if notifications are enabled for this user
-    if !participant.notifications_enabled {
+    // Check if notifications are enabled for this user.
+    // Broadcast mentions are mention-priority and bypass the dialog mute.
+    if !participant.notifications_enabled && job.broadcast.is_none() {
         tracing::debug!(
             recipient_id = %job.recipient_id,
             "Notifications disabled for user, skipping"
@@ -73,6 +153,18 @@ pub async fn handle_notification(job: NotificationJob, ctx: Data<JobContext>) ->
         return Ok(());
     }
 
+    // Snoozed dialogs stay quiet until the snooze expires (same bypass as the mute)
+    if let Some(until) = participant.active_snooze(chrono::Utc::now()) {
+        if job.broadcast.is_none() {
+            tracing::debug!(
+                recipient_id = %job.recipient_id,
+                snoozed_until = %until,
+                "Dialog snoozed for user, skipping"
+            );
+            return Ok(());
+        }
+    }
+
     // Check if message has been read
     // If unread_count is 0, message was read
     if participant.unread_count == 0 {
@@ -84,6 +176,24 @@ pub async fn handle_notification(job: NotificationJob, ctx: Data<JobContext>) ->
         return Ok(());
     }
 
+    // Message not read - hold it back for the recipient's digest
+    let digest_window_secs = ctx.settings.current().notification_digest_window_secs;
+    if digest_window_secs > 0 && job.broadcast.is_none() && !job.priority.is_urgent() {
+        match add_to_digest(&ctx.redis, &job, digest_window_secs).await {
+            Ok(()) => {
+                tracing::debug!(
+                    recipient_id = %job.recipient_id,
+                    message_id = %job.message_id,
+                    "Notification added to digest"
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to add notification to digest, sending it")
+            }
+        }
+    }
+
     // Message not read - send notification webhook
     tracing::info!(
         recipient_id = %job.recipient_id,
@@ -130,33 +240,48 @@ pub async fn handle_notification(job: NotificationJob, ctx: Data<JobContext>) ->
     };
 
     // Send webhook with notification info
-    ctx.webhooks
-        .send(WebhookEvent::notification_pending(
+    let mut event = match job.broadcast {
+        _ if job.priority.is_urgent() => {
+            WebhookEvent::notification_urgent(&dialog, &message, &job.recipient_id, sender_company)
+        }
+        Some(mention) => WebhookEvent::notification_mention(
             &dialog,
             &message,
             &job.recipient_id,
             sender_company,
-        ))
-        .await;
+            mention,
+        ),
+        None => {
+            WebhookEvent::notification_pending(&dialog, &message, &job.recipient_id, sender_company)
+        }
+    };
+    event.request_id = job.request_id;
+    ctx.webhooks.send(event).await;
 
     Ok(())
 }
 
 /// Handle auto-archive job.
 ///
-/// Finds dialogs with no activity for N seconds and archives them.
+/// Finds dialogs with no activity for N seconds and archives them. With the
+/// `archive_quiet_hours` setting, a dialog is only archived while its local
+/// time is inside the window, so it doesn't vanish during the working day.
 pub async fn handle_auto_archive(job: AutoArchiveJob, ctx: Data<JobContext>) -> Result<(), Error> {
-    let cutoff = Utc::now() - Duration::seconds(ctx.archive_after_secs);
+    let settings = ctx.settings.current();
+    let archive_after_secs = settings.archive_after_secs;
+    let quiet_hours = settings.archive_quiet_hours;
+    let cutoff = Utc::now() - Duration::seconds(archive_after_secs);
 
     tracing::info!(
         run_id = %job.run_id,
-        archive_after_secs = ctx.archive_after_secs,
+        archive_after_secs,
         cutoff = %cutoff,
+        quiet_hours = ?quiet_hours,
         "Starting auto-archive job"
     );
 
     // Find inactive dialogs
-    let inactive_dialogs = match ctx.dialogs.find_inactive_since(cutoff).await {
+    let inactive_dialogs = match ctx.dialogs.find_inactive_since(cutoff, quiet_hours).await {
         Ok(dialogs) => dialogs,
         Err(e) => {
             tracing::error!(error = %e, "Failed to find inactive dialogs");
@@ -177,26 +302,37 @@ pub async fn handle_auto_archive(job: AutoArchiveJob, ctx: Data<JobContext>) ->
 
     let mut archived_count = 0;
     for dialog_id in inactive_dialogs {
-        // Get participant user_ids before archiving (for WebSocket notification)
-        let user_ids: Vec<String> = match ctx.participants.list_by_dialog(dialog_id).await {
-            Ok(participants) => participants
-                .iter()
-                .filter(|p| !p.is_archived) // Only notify non-archived participants
-                .map(|p| p.user_id.clone())
-                .collect(),
-            Err(e) => {
-                tracing::warn!(dialog_id = %dialog_id, error = %e, "Failed to get participants");
-                continue;
-            }
-        };
-
         match ctx.participants.archive_all_for_dialog(dialog_id).await {
-            Ok(count) if count > 0 => {
-                archived_count += count;
-                tracing::debug!(dialog_id = %dialog_id, participants = count, "Archived dialog");
+            Ok(user_ids) if !user_ids.is_empty() => {
+                archived_count += user_ids.len();
+                tracing::debug!(dialog_id = %dialog_id, participants = user_ids.len(), "Archived dialog");
 
                 // Broadcast to affected users
                 ws::broadcast_dialog_archived(&ctx.connections, dialog_id, &user_ids).await;
+                ctx.events.publish(DomainEvent::ArchiveChanged {
+                    dialog_id,
+                    user_ids: user_ids.clone(),
+                    archived: true,
+                    trigger: ArchiveTrigger::AutoArchive,
+                    triggered_by: None,
+                });
+
+                match ctx.dialogs.find_by_id(dialog_id).await {
+                    Ok(Some(dialog)) => {
+                        ctx.webhooks
+                            .send(WebhookEvent::dialog_archived(
+                                &dialog,
+                                user_ids,
+                                ArchiveTrigger::AutoArchive,
+                                None,
+                            ))
+                            .await;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::warn!(dialog_id = %dialog_id, error = %e, "Failed to load archived dialog");
+                    }
+                }
             }
             Ok(_) => {
                 // No participants were archived (all already archived)
@@ -216,6 +352,382 @@ pub async fn handle_auto_archive(job: AutoArchiveJob, ctx: Data<JobContext>) ->
     Ok(())
 }
 
+/// Handle purge job.
+///
+/// Hard-deletes dialogs soft-deleted longer than `dialog_retention_secs` ago
+/// and enqueues deletion of their attachment files. Also drops export
+/// tombstones older than `export_deletion_retention_secs`.
+pub async fn handle_purge_deleted_dialogs(
+    job: PurgeDeletedDialogsJob,
+    ctx: Data<JobContext>,
+    config: Data<WorkerConfig>,
+) -> Result<(), Error> {
+    let tombstone_cutoff = Utc::now() - Duration::seconds(config.export_deletion_retention_secs);
+    match ctx.export.delete_deletions_before(tombstone_cutoff).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!(run_id = %job.run_id, count, "Dropped old export tombstones"),
+        Err(e) => tracing::warn!(error = %e, "Failed to drop old export tombstones"),
+    }
+
+    let cutoff = Utc::now() - Duration::seconds(config.dialog_retention_secs);
+
+    let dialog_ids = match ctx.dialogs.find_deleted_before(cutoff).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to find deleted dialogs");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    if dialog_ids.is_empty() {
+        tracing::debug!(run_id = %job.run_id, "No deleted dialogs to purge");
+        return Ok(());
+    }
+
+    let mut purged = 0;
+    for dialog_id in dialog_ids {
+        match purge_dialog(&ctx, dialog_id).await {
+            Ok(true) => purged += 1,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(dialog_id = %dialog_id, error = %e, "Failed to purge dialog");
+            }
+        }
+    }
+
+    tracing::info!(run_id = %job.run_id, purged, "Purge job completed");
+
+    Ok(())
+}
+
+/// Delete a dialog with everything referencing it, then its attachment and
+/// avatar files.
+/// Returns false if the dialog was already gone.
+async fn purge_dialog(ctx: &JobContext, dialog_id: uuid::Uuid) -> Result<bool, sqlx::Error> {
+    let mut keys = ctx.attachments.list_keys_by_dialog(dialog_id).await?;
+    for avatar_key in ctx
+        .participants
+        .list_avatar_keys_by_dialog(dialog_id)
+        .await?
+    {
+        keys.extend(avatar::avatar_object_keys(&avatar_key));
+    }
+
+    ctx.feature_flags.delete_for_dialog(dialog_id).await?;
+    if !ctx.dialogs.delete(dialog_id).await? {
+        return Ok(false);
+    }
+
+    tracing::debug!(dialog_id = %dialog_id, files = keys.len(), "Purged dialog");
+
+    let job = AttachmentCleanupJob::new(dialog_id, keys);
+    if let Err(e) = ctx.jobs.enqueue_attachment_cleanup(job.clone()).await {
+        // The rows are gone: delete the files now rather than orphan them
+        tracing::warn!(dialog_id = %dialog_id, error = %e, "Failed to enqueue attachment cleanup");
+        delete_attachment_objects(ctx, &job).await;
+    }
+
+    Ok(true)
+}
+
+/// Participants removed per query by the removal job
+const REMOVAL_BATCH_SIZE: i64 = 500;
+
+/// Handle participant removal job.
+///
+/// Removes participants whose removal grace period has ended, then does what
+/// an immediate removal does: cancels their pending notifications, deletes
+/// their avatar and tells clients they left.
+pub async fn handle_remove_pending_participants(
+    job: RemovePendingParticipantsJob,
+    ctx: Data<JobContext>,
+) -> Result<(), Error> {
+    let mut removed = 0;
+    loop {
+        let participants = match ctx.participants.remove_due(REMOVAL_BATCH_SIZE).await {
+            Ok(participants) => participants,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to remove pending participants");
+                return Err(Error::Failed(Arc::new(Box::new(e))));
+            }
+        };
+        let batch_len = participants.len() as i64;
+
+        for participant in participants {
+            let dialog_id = participant.dialog_id;
+            if let Err(e) = ctx
+                .jobs
+                .cancel_notifications(dialog_id, &participant.user_id)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to cancel pending notifications");
+            }
+            if let Some(avatar_key) = &participant.avatar_s3_key {
+                let cleanup =
+                    AttachmentCleanupJob::new(dialog_id, avatar::avatar_object_keys(avatar_key));
+                if let Err(e) = ctx.jobs.enqueue_attachment_cleanup(cleanup).await {
+                    tracing::warn!(key = %avatar_key, error = %e, "Failed to enqueue avatar cleanup");
+                }
+            }
+            ws::broadcast_participant_left(&ctx.connections, dialog_id, &participant.user_id).await;
+            ctx.events.publish(DomainEvent::ParticipantLeft {
+                dialog_id,
+                user_id: participant.user_id,
+                removed_by: None,
+            });
+            removed += 1;
+        }
+
+        if batch_len < REMOVAL_BATCH_SIZE {
+            break;
+        }
+    }
+
+    if removed > 0 {
+        tracing::info!(run_id = %job.run_id, removed, "Removed participants after grace period");
+    } else {
+        tracing::debug!(run_id = %job.run_id, "No participants pending removal");
+    }
+
+    Ok(())
+}
+
+/// Handle attachment cleanup job.
+///
+/// Deletes the storage objects of removed attachments. Keys still referenced
+/// by another attachment are kept. Fails if any delete failed, so the job is
+/// retried (deleting an already deleted object is not an error).
+pub async fn handle_attachment_cleanup(
+    job: AttachmentCleanupJob,
+    ctx: Data<JobContext>,
+) -> Result<(), Error> {
+    let failed = delete_attachment_objects(&ctx, &job).await;
+    if failed > 0 {
+        return Err(Error::Failed(Arc::new(
+            format!(
+                "{} of {} attachment objects not deleted",
+                failed,
+                job.keys.len()
+            )
+            .into(),
+        )));
+    }
+
+    tracing::debug!(dialog_id = %job.dialog_id, keys = job.keys.len(), "Attachment cleanup completed");
+
+    Ok(())
+}
+
+/// Delete the job's storage objects, skipping keys still in use.
+/// Returns the number of objects that could not be deleted.
+async fn delete_attachment_objects(ctx: &JobContext, job: &AttachmentCleanupJob) -> usize {
+    if !ctx.storage.is_configured() {
+        return 0;
+    }
+
+    let metrics = ctx.jobs.cleanup_metrics();
+    let mut failed = 0;
+
+    for key in &job.keys {
+        match ctx.attachments.is_key_referenced(key).await {
+            Ok(true) => {
+                metrics.record_skipped();
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(key = %key, error = %e, "Failed to check attachment key references");
+                metrics.record_failed();
+                failed += 1;
+                continue;
+            }
+        }
+
+        match ctx.storage.delete_object(key).await {
+            Ok(()) => metrics.record_deleted(),
+            Err(e) => {
+                tracing::warn!(dialog_id = %job.dialog_id, key = %key, error = %e, "Failed to delete attachment file");
+                metrics.record_failed();
+                failed += 1;
+            }
+        }
+    }
+
+    failed
+}
+
+/// Handle thumbnail job.
+///
+/// Renders the first page of a PDF attachment to PNG, uploads it next to the
+/// original and stores its key in `thumbnail_s3_key`. Rendering failures are
+/// logged and not retried: the widget falls back to a generic file icon.
+pub async fn handle_thumbnail(
+    job: ThumbnailJob,
+    ctx: Data<JobContext>,
+    config: Data<WorkerConfig>,
+) -> Result<(), Error> {
+    if !ctx.storage.is_configured() {
+        return Ok(());
+    }
+
+    match job {
+        ThumbnailJob::Attachment { attachment_id } => {
+            render_attachment_preview(&ctx, &config, attachment_id).await
+        }
+        ThumbnailJob::Avatar {
+            dialog_id,
+            user_id,
+            avatar_s3_key,
+        } => render_avatar_variants(&ctx, dialog_id, &user_id, &avatar_s3_key).await,
+    }
+}
+
+/// Render the first page of a PDF attachment as its thumbnail
+async fn render_attachment_preview(
+    ctx: &JobContext,
+    config: &WorkerConfig,
+    attachment_id: uuid::Uuid,
+) -> Result<(), Error> {
+    if !preview::is_enabled() {
+        return Ok(());
+    }
+
+    let attachment = match ctx.attachments.find_by_id(attachment_id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => {
+            tracing::debug!(attachment_id = %attachment_id, "Attachment gone, skipping thumbnail");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!(attachment_id = %attachment_id, error = %e, "Failed to load attachment");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    if !attachment.is_pdf() || attachment.thumbnail_s3_key.is_some() {
+        return Ok(());
+    }
+
+    let data = match ctx.storage.get_object(&attachment.s3_key).await {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!(attachment_id = %attachment.id, error = %e, "Failed to download attachment");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    let pdfium_lib_path = config.pdfium_lib_path.clone();
+    let png = match tokio::task::spawn_blocking(move || {
+        preview::render_pdf_preview(&data, pdfium_lib_path.as_deref())
+    })
+    .await
+    {
+        Ok(Ok(png)) => png,
+        Ok(Err(e)) => {
+            tracing::warn!(attachment_id = %attachment.id, error = %e, "Failed to render PDF preview");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!(attachment_id = %attachment.id, error = %e, "PDF preview task panicked");
+            return Ok(());
+        }
+    };
+
+    let thumbnail_key = preview::preview_key(&attachment.s3_key);
+    if let Err(e) = ctx
+        .storage
+        .put_object(&thumbnail_key, png, preview::PREVIEW_CONTENT_TYPE)
+        .await
+    {
+        tracing::warn!(attachment_id = %attachment.id, error = %e, "Failed to upload PDF preview");
+        return Err(Error::Failed(Arc::new(Box::new(e))));
+    }
+
+    if let Err(e) = ctx
+        .attachments
+        .update_thumbnail(attachment.id, &thumbnail_key)
+        .await
+    {
+        tracing::error!(attachment_id = %attachment.id, error = %e, "Failed to save preview key");
+        return Err(Error::Failed(Arc::new(Box::new(e))));
+    }
+
+    tracing::debug!(attachment_id = %attachment.id, key = %thumbnail_key, "PDF preview rendered");
+
+    Ok(())
+}
+
+/// Render the resized variants of a participant's avatar
+async fn render_avatar_variants(
+    ctx: &JobContext,
+    dialog_id: uuid::Uuid,
+    user_id: &str,
+    avatar_s3_key: &str,
+) -> Result<(), Error> {
+    let data = match ctx.storage.get_object(avatar_s3_key).await {
+        Ok(data) => data,
+        Err(StorageError::NotFound(_)) => {
+            tracing::debug!(key = %avatar_s3_key, "Avatar gone, skipping variants");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::warn!(key = %avatar_s3_key, error = %e, "Failed to download avatar");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    let variants = match tokio::task::spawn_blocking(move || {
+        preview::render_avatar_variants(&data, &avatar::AVATAR_VARIANT_SIZES)
+    })
+    .await
+    {
+        Ok(Ok(variants)) => variants,
+        Ok(Err(e)) => {
+            tracing::warn!(key = %avatar_s3_key, error = %e, "Failed to render avatar variants");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!(key = %avatar_s3_key, error = %e, "Avatar rendering task panicked");
+            return Ok(());
+        }
+    };
+
+    for (size, png) in variants {
+        let key = avatar::avatar_variant_key(avatar_s3_key, size);
+        if let Err(e) = ctx
+            .storage
+            .put_object(&key, png, avatar::AVATAR_VARIANT_CONTENT_TYPE)
+            .await
+        {
+            tracing::warn!(key = %key, error = %e, "Failed to upload avatar variant");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    }
+
+    match ctx
+        .participants
+        .set_avatar_variants_ready(dialog_id, user_id, avatar_s3_key)
+        .await
+    {
+        Ok(true) => {
+            tracing::debug!(key = %avatar_s3_key, "Avatar variants rendered");
+        }
+        Ok(false) => {
+            // Replaced or removed meanwhile: the variants are orphans now
+            tracing::debug!(key = %avatar_s3_key, "Avatar changed, deleting rendered variants");
+            let job =
+                AttachmentCleanupJob::new(dialog_id, avatar::avatar_object_keys(avatar_s3_key));
+            delete_attachment_objects(ctx, &job).await;
+        }
+        Err(e) => {
+            tracing::error!(key = %avatar_s3_key, error = %e, "Failed to mark avatar variants");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     // Tests require database fixtures - see integration tests
@@ -0,0 +1,113 @@
+//! Worker liveness heartbeat.
+//!
+//! The workers report every failed poll of their Redis queues here. Each
+//! interval in which the monitor ran and its workers polled without an
+//! error records a timestamp, which the readiness probe checks for
+//! staleness. A lost Redis connection or stopped workers thus fail the
+//! probe even while the monitor task itself is still running.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+
+/// Interval between heartbeats.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Heartbeats older than this mean the workers are gone.
+pub const HEARTBEAT_STALE_AFTER_SECS: i64 = 30;
+
+/// Timestamp of the last worker heartbeat (shared, cheap to clone).
+#[derive(Clone, Default)]
+pub struct WorkerHeartbeat {
+    last_beat: Arc<AtomicI64>,
+    poll_errors: Arc<AtomicU64>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl WorkerHeartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a heartbeat now.
+    pub fn beat(&self) {
+        self.last_beat
+            .store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    /// Record a failed poll of a job queue.
+    pub fn poll_failed(&self) {
+        self.poll_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that a worker stopped polling; no heartbeats follow.
+    pub fn worker_stopped(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Number of failed polls so far.
+    pub fn poll_errors(&self) -> u64 {
+        self.poll_errors.load(Ordering::Relaxed)
+    }
+
+    /// Record a heartbeat if the workers are still polling and no poll
+    /// failed since `poll_errors` was read. Returns the current count, to
+    /// pass to the next call.
+    pub fn beat_if_polling(&self, poll_errors: u64) -> u64 {
+        let current = self.poll_errors();
+        if current == poll_errors && !self.stopped.load(Ordering::Relaxed) {
+            self.beat();
+        }
+        current
+    }
+
+    /// Seconds since the last heartbeat (`None` if there never was one).
+    pub fn age_secs(&self) -> Option<i64> {
+        match self.last_beat.load(Ordering::Relaxed) {
+            0 => None,
+            last => Some(Utc::now().timestamp() - last),
+        }
+    }
+
+    /// Check if workers have reported recently.
+    pub fn is_alive(&self) -> bool {
+        self.age_secs()
+            .is_some_and(|age| age <= HEARTBEAT_STALE_AFTER_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat() {
+        let heartbeat = WorkerHeartbeat::new();
+        assert!(heartbeat.age_secs().is_none());
+        assert!(!heartbeat.is_alive());
+
+        heartbeat.beat();
+        assert!(heartbeat.is_alive());
+        assert!(heartbeat.clone().age_secs().unwrap() <= 1);
+    }
+
+    #[test]
+    fn test_failed_polls_skip_the_beat() {
+        let heartbeat = WorkerHeartbeat::new();
+        let errors = heartbeat.poll_errors();
+        heartbeat.poll_failed();
+        let errors = heartbeat.beat_if_polling(errors);
+        assert!(heartbeat.age_secs().is_none());
+
+        // An interval without failures beats again
+        let errors = heartbeat.beat_if_polling(errors);
+        assert!(heartbeat.is_alive());
+
+        heartbeat.last_beat.store(0, Ordering::Relaxed);
+        heartbeat.worker_stopped();
+        heartbeat.beat_if_polling(errors);
+        assert!(heartbeat.age_secs().is_none());
+    }
+}
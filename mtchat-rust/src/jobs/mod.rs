@@ -1,8 +1,19 @@
 //! Background job processing for MTChat.
 //!
 //! This module provides:
-//! - Smart notifications (only notify if message not read after 1 second)
+//! - Smart notifications (only notify if message not read after 1 second),
+//!   dropping jobs that went stale in the queue during worker downtime
+//! - Per-recipient notification digests
 //! - Auto-archiving of inactive dialogs
+//! - Purging of soft-deleted dialogs after the retention window
+//! - Deleting attachment files of deleted messages and purged dialogs
+//! - Repairing drifted unread counters and dialog list entries
+//! - Removing participants whose removal grace period ended
+//! - Escalating dialogs whose response time SLA is running out
+//! - Retrying failed queue jobs with backoff and dead-lettering the ones
+//!   that fail permanently
+//! - Preview thumbnails for PDF attachments (`pdf-preview` feature)
+//! - Text of PDF and DOCX attachments for search (`text-extract` feature)
 //!
 //! # Architecture
 //!
@@ -25,12 +36,30 @@
 //!                         └─────────────────────────────────────────┘
 //! ```
 
+pub mod cleanup_metrics;
+pub mod digest;
 pub mod handlers;
+pub mod heartbeat;
+pub mod notification_backlog;
 pub mod producer;
+pub mod reconcile_dialog_list;
+pub mod reconcile_unread;
+pub mod retry;
+pub mod retry_metrics;
+pub mod sla;
+pub mod text_extract;
 pub mod types;
 pub mod worker;
 
+pub use cleanup_metrics::{CleanupMetrics, CleanupSnapshot};
 pub use handlers::JobContext;
+pub use heartbeat::WorkerHeartbeat;
+pub use notification_backlog::{NotificationBacklogMetrics, NotificationBacklogSnapshot};
 pub use producer::JobProducer;
-pub use types::NotificationJob;
-pub use worker::{start_workers, WorkerConfig};
+pub use reconcile_unread::{UnreadDriftMetrics, UnreadDriftSnapshot};
+pub use retry::RetryConfig;
+pub use retry_metrics::{JobRetryMetrics, JobRetrySnapshot};
+pub use types::{
+    AttachmentCleanupJob, NotificationJob, SendDigestsJob, TextExtractJob, ThumbnailJob,
+};
+pub use worker::{run_workers, start_workers, WorkerConfig, WorkerError};
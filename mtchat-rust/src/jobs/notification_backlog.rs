@@ -0,0 +1,83 @@
+//! Notification queue backlog counters.
+//!
+//! Updated by the notification worker and read by the metrics endpoint and
+//! the Management API; both run in the same process, so plain atomics are
+//! enough (per-instance values).
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Snapshot of the backlog counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotificationBacklogSnapshot {
+    /// How long the last notification job waited in the queue, in seconds
+    pub age_secs: u64,
+    /// Jobs dropped because they waited longer than `notification_max_age_secs`
+    pub stale_total: u64,
+    /// Jobs dropped because the backlog was skipped through the Management API
+    pub skipped_total: u64,
+}
+
+/// Notification backlog counters (shared, cheap to clone).
+#[derive(Clone, Default)]
+pub struct NotificationBacklogMetrics {
+    age_secs: Arc<AtomicI64>,
+    stale_total: Arc<AtomicU64>,
+    skipped_total: Arc<AtomicU64>,
+}
+
+impl NotificationBacklogMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the queue wait of a job the worker picked up
+    pub fn record_age(&self, age_secs: i64) {
+        self.age_secs.store(age_secs.max(0), Ordering::Relaxed);
+    }
+
+    pub fn record_stale(&self) {
+        self.stale_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_skipped(&self) {
+        self.skipped_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> NotificationBacklogSnapshot {
+        NotificationBacklogSnapshot {
+            age_secs: self.age_secs.load(Ordering::Relaxed) as u64,
+            stale_total: self.stale_total.load(Ordering::Relaxed),
+            skipped_total: self.skipped_total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_are_shared_between_clones() {
+        let metrics = NotificationBacklogMetrics::new();
+        let clone = metrics.clone();
+
+        clone.record_age(7200);
+        clone.record_stale();
+        metrics.record_skipped();
+        metrics.record_skipped();
+
+        assert_eq!(
+            metrics.snapshot(),
+            NotificationBacklogSnapshot {
+                age_secs: 7200,
+                stale_total: 1,
+                skipped_total: 2,
+            }
+        );
+
+        // Clock skew between instances must not underflow the gauge
+        metrics.record_age(-3);
+        assert_eq!(metrics.snapshot().age_secs, 0);
+    }
+}
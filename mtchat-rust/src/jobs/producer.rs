@@ -1,28 +1,87 @@
 //! Job producer for enqueueing background tasks.
 
+use std::sync::Arc;
+
 use apalis::prelude::Storage;
 use apalis_redis::RedisStorage;
+use chrono::{DateTime, TimeZone, Utc};
+use fred::clients::Pool;
+use fred::interfaces::KeysInterface;
+use uuid::Uuid;
+
+use super::cleanup_metrics::CleanupMetrics;
+use super::heartbeat::WorkerHeartbeat;
+use super::notification_backlog::NotificationBacklogMetrics;
+use super::reconcile_unread::UnreadDriftMetrics;
+use super::retry_metrics::JobRetryMetrics;
+use super::types::{AttachmentCleanupJob, NotificationJob, TextExtractJob, ThumbnailJob};
+use crate::middleware::current_request_id;
 
-use super::types::NotificationJob;
+/// How long a cancellation marker is kept. Notification jobs older than this
+/// are expected to have run already.
+const CANCEL_MARKER_TTL: i64 = 3600;
+
+/// Redis key marking notifications of a user in a dialog as cancelled
+/// (value: cancellation time in milliseconds)
+fn cancel_marker_key(dialog_id: Uuid, user_id: &str) -> String {
+    format!("mtchat:notifications:cancelled:{}:{}", dialog_id, user_id)
+}
+
+/// Redis key marking all notifications enqueued up to a time as skipped
+/// (value: cutoff in milliseconds)
+const SKIP_MARKER_KEY: &str = "mtchat:notifications:skipped_before";
 
 /// Job producer for enqueueing background tasks.
 #[derive(Clone)]
 pub struct JobProducer {
+    redis: Option<Arc<Pool>>,
     notifications: Option<RedisStorage<NotificationJob>>,
+    thumbnails: Option<RedisStorage<ThumbnailJob>>,
+    cleanups: Option<RedisStorage<AttachmentCleanupJob>>,
+    text_extracts: Option<RedisStorage<TextExtractJob>>,
+    heartbeat: WorkerHeartbeat,
+    cleanup_metrics: CleanupMetrics,
+    unread_drift_metrics: UnreadDriftMetrics,
+    notification_backlog: NotificationBacklogMetrics,
+    retry_metrics: JobRetryMetrics,
 }
 
 impl JobProducer {
     /// Create a new job producer.
-    pub fn new(notifications: RedisStorage<NotificationJob>) -> Self {
+    pub fn new(
+        redis: Arc<Pool>,
+        notifications: RedisStorage<NotificationJob>,
+        thumbnails: RedisStorage<ThumbnailJob>,
+        cleanups: RedisStorage<AttachmentCleanupJob>,
+        text_extracts: RedisStorage<TextExtractJob>,
+    ) -> Self {
         Self {
+            redis: Some(redis),
             notifications: Some(notifications),
+            thumbnails: Some(thumbnails),
+            cleanups: Some(cleanups),
+            text_extracts: Some(text_extracts),
+            heartbeat: WorkerHeartbeat::new(),
+            cleanup_metrics: CleanupMetrics::new(),
+            unread_drift_metrics: UnreadDriftMetrics::new(),
+            notification_backlog: NotificationBacklogMetrics::new(),
+            retry_metrics: JobRetryMetrics::new(),
         }
     }
 
     /// Create a no-op producer (when job queue is disabled).
     pub fn noop() -> Self {
         Self {
+            redis: None,
             notifications: None,
+            thumbnails: None,
+            cleanups: None,
+            text_extracts: None,
+            heartbeat: WorkerHeartbeat::new(),
+            cleanup_metrics: CleanupMetrics::new(),
+            unread_drift_metrics: UnreadDriftMetrics::new(),
+            notification_backlog: NotificationBacklogMetrics::new(),
+            retry_metrics: JobRetryMetrics::new(),
         }
     }
 
@@ -31,10 +90,61 @@ impl JobProducer {
         self.notifications.is_some()
     }
 
+    /// Redis pool and queues for the workers (None when the queue is disabled).
+    #[allow(clippy::type_complexity)]
+    pub fn worker_backends(
+        &self,
+    ) -> Option<(
+        Arc<Pool>,
+        RedisStorage<NotificationJob>,
+        RedisStorage<ThumbnailJob>,
+        RedisStorage<AttachmentCleanupJob>,
+        RedisStorage<TextExtractJob>,
+    )> {
+        Some((
+            self.redis.clone()?,
+            self.notifications.clone()?,
+            self.thumbnails.clone()?,
+            self.cleanups.clone()?,
+            self.text_extracts.clone()?,
+        ))
+    }
+
+    /// Heartbeat of the workers consuming this producer's queues.
+    pub fn heartbeat(&self) -> &WorkerHeartbeat {
+        &self.heartbeat
+    }
+
+    /// Counters of the attachment cleanup worker.
+    pub fn cleanup_metrics(&self) -> &CleanupMetrics {
+        &self.cleanup_metrics
+    }
+
+    /// Counters of the unread reconciliation job.
+    pub fn unread_drift_metrics(&self) -> &UnreadDriftMetrics {
+        &self.unread_drift_metrics
+    }
+
+    /// Queue wait and dropped jobs of the notification worker.
+    pub fn notification_backlog(&self) -> &NotificationBacklogMetrics {
+        &self.notification_backlog
+    }
+
+    /// Retries and dead-lettered jobs of the queue workers.
+    pub fn retry_metrics(&self) -> &JobRetryMetrics {
+        &self.retry_metrics
+    }
+
     /// Enqueue a notification job immediately.
     ///
-    /// The handler will add a small delay to check if user read the message.
-    pub async fn enqueue_notification(&self, job: NotificationJob) -> Result<(), JobProducerError> {
+    /// The handler waits `delay_ms` before checking if the user read the
+    /// message. The job carries the current request ID for the resulting
+    /// webhook.
+    pub async fn enqueue_notification(
+        &self,
+        mut job: NotificationJob,
+        delay_ms: u64,
+    ) -> Result<(), JobProducerError> {
         let notifications = match &self.notifications {
             Some(n) => n,
             None => {
@@ -43,6 +153,11 @@ impl JobProducer {
             }
         };
 
+        if job.request_id.is_none() {
+            job.request_id = current_request_id();
+        }
+        job.delay_ms = Some(delay_ms);
+
         // Push job immediately (handler will add delay)
         notifications
             .clone()
@@ -54,6 +169,178 @@ impl JobProducer {
 
         Ok(())
     }
+
+    /// Cancel pending notifications of a user in a dialog (participant removed).
+    ///
+    /// Queued jobs cannot be removed from apalis storage, so a marker is set
+    /// that the handler checks; jobs enqueued before it are dropped.
+    pub async fn cancel_notifications(
+        &self,
+        dialog_id: Uuid,
+        user_id: &str,
+    ) -> Result<(), JobProducerError> {
+        let Some(redis) = &self.redis else {
+            return Ok(());
+        };
+
+        redis
+            .set::<(), _, _>(
+                cancel_marker_key(dialog_id, user_id),
+                Utc::now().timestamp_millis(),
+                Some(fred::types::Expiration::EX(CANCEL_MARKER_TTL)),
+                None,
+                false,
+            )
+            .await
+            .map_err(|e| JobProducerError::Redis(e.to_string()))?;
+
+        tracing::debug!(%dialog_id, user_id, "Pending notifications cancelled");
+
+        Ok(())
+    }
+
+    /// Skip queued notifications enqueued at or before `cutoff` (after an
+    /// outage of the workers).
+    ///
+    /// Like cancellations, a marker is set that the handler checks. Returns
+    /// false when the queue is disabled.
+    pub async fn skip_notifications_before(
+        &self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<bool, JobProducerError> {
+        let Some(redis) = &self.redis else {
+            return Ok(false);
+        };
+
+        redis
+            .set::<(), _, _>(
+                SKIP_MARKER_KEY,
+                cutoff.timestamp_millis(),
+                None,
+                None,
+                false,
+            )
+            .await
+            .map_err(|e| JobProducerError::Redis(e.to_string()))?;
+
+        tracing::info!(%cutoff, "Notification backlog skipped");
+
+        Ok(true)
+    }
+
+    /// Cutoff of the last skip of the notification backlog, if any.
+    pub async fn notifications_skipped_before(
+        &self,
+    ) -> Result<Option<DateTime<Utc>>, JobProducerError> {
+        let Some(redis) = &self.redis else {
+            return Ok(None);
+        };
+
+        skipped_before(redis)
+            .await
+            .map_err(|e| JobProducerError::Redis(e.to_string()))
+    }
+
+    /// Enqueue a thumbnail job for an attachment.
+    pub async fn enqueue_thumbnail(&self, job: ThumbnailJob) -> Result<(), JobProducerError> {
+        let thumbnails = match &self.thumbnails {
+            Some(t) => t,
+            None => {
+                tracing::debug!("Job queue disabled, skipping thumbnail");
+                return Ok(());
+            }
+        };
+
+        thumbnails
+            .clone()
+            .push(job)
+            .await
+            .map_err(|e| JobProducerError::Apalis(e.to_string()))?;
+
+        tracing::debug!("Thumbnail job enqueued");
+
+        Ok(())
+    }
+
+    /// Enqueue text extraction of a document attachment.
+    pub async fn enqueue_text_extract(&self, job: TextExtractJob) -> Result<(), JobProducerError> {
+        let text_extracts = match &self.text_extracts {
+            Some(t) => t,
+            None => {
+                tracing::debug!("Job queue disabled, skipping text extraction");
+                return Ok(());
+            }
+        };
+
+        text_extracts
+            .clone()
+            .push(job)
+            .await
+            .map_err(|e| JobProducerError::Apalis(e.to_string()))?;
+
+        tracing::debug!("Text extraction job enqueued");
+
+        Ok(())
+    }
+
+    /// Enqueue deletion of storage objects left behind by deleted attachments.
+    pub async fn enqueue_attachment_cleanup(
+        &self,
+        job: AttachmentCleanupJob,
+    ) -> Result<(), JobProducerError> {
+        let cleanups = match &self.cleanups {
+            Some(c) => c,
+            None => {
+                tracing::debug!("Job queue disabled, skipping attachment cleanup");
+                return Ok(());
+            }
+        };
+
+        if job.keys.is_empty() {
+            return Ok(());
+        }
+
+        let keys = job.keys.len();
+        cleanups
+            .clone()
+            .push(job)
+            .await
+            .map_err(|e| JobProducerError::Apalis(e.to_string()))?;
+
+        tracing::debug!(keys, "Attachment cleanup job enqueued");
+
+        Ok(())
+    }
+}
+
+/// Whether a notification job was enqueued before its recipient's
+/// notifications in the dialog were cancelled.
+pub async fn is_notification_cancelled(
+    redis: &Pool,
+    job: &NotificationJob,
+) -> Result<bool, fred::error::Error> {
+    let cancelled_at: Option<i64> = redis
+        .get(cancel_marker_key(job.dialog_id, &job.recipient_id))
+        .await?;
+    Ok(cancelled_at
+        .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+        .is_some_and(|at: DateTime<Utc>| job.enqueued_at <= at))
+}
+
+/// Whether a notification job was enqueued before the notification backlog
+/// was skipped.
+pub async fn is_notification_skipped(
+    redis: &Pool,
+    job: &NotificationJob,
+) -> Result<bool, fred::error::Error> {
+    Ok(skipped_before(redis)
+        .await?
+        .is_some_and(|cutoff| job.enqueued_at <= cutoff))
+}
+
+async fn skipped_before(redis: &Pool) -> Result<Option<DateTime<Utc>>, fred::error::Error> {
+    let cutoff: Option<i64> = redis.get(SKIP_MARKER_KEY).await?;
+    Ok(cutoff.and_then(|ms| Utc.timestamp_millis_opt(ms).single()))
 }
 
 /// Errors that can occur when producing jobs.
@@ -64,6 +351,9 @@ pub enum JobProducerError {
 
     #[error("Apalis error: {0}")]
     Apalis(String),
+
+    #[error("Redis error: {0}")]
+    Redis(String),
 }
 
 #[cfg(test)]
@@ -74,5 +364,36 @@ mod tests {
     fn test_noop_producer_is_not_enabled() {
         let producer = JobProducer::noop();
         assert!(!producer.is_enabled());
+        assert!(producer.worker_backends().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_noop_producer_cancel_is_noop() {
+        let producer = JobProducer::noop();
+        assert!(producer
+            .cancel_notifications(Uuid::new_v4(), "user-1")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_noop_producer_has_no_backlog_to_skip() {
+        let producer = JobProducer::noop();
+        assert!(!producer
+            .skip_notifications_before(Utc::now())
+            .await
+            .unwrap());
+        assert!(producer
+            .notifications_skipped_before()
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_noop_producer_skips_attachment_cleanup() {
+        let producer = JobProducer::noop();
+        let job = AttachmentCleanupJob::new(Uuid::new_v4(), vec!["a/file.pdf".into()]);
+        assert!(producer.enqueue_attachment_cleanup(job).await.is_ok());
     }
 }
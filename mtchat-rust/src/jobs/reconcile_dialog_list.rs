@@ -0,0 +1,54 @@
+//! Dialog list reconciliation.
+//!
+//! `dialog_list_entries` is maintained by triggers. The triggers serialize
+//! on the dialog row, but manual database edits or trigger bugs can still
+//! leave entries that do not match the participants and messages. A
+//! periodic job walks all dialogs in batches and rebuilds their entries.
+
+use std::sync::Arc;
+
+use apalis::prelude::*;
+
+use super::handlers::JobContext;
+use super::types::ReconcileDialogListJob;
+use super::worker::WorkerConfig;
+
+/// Handle dialog list reconciliation job.
+///
+/// Processes dialogs `dialog_list_reconcile_batch_size` at a time. A failed
+/// batch ends the run; the next scheduled run starts over.
+pub async fn handle_reconcile_dialog_list(
+    job: ReconcileDialogListJob,
+    ctx: Data<JobContext>,
+    config: Data<WorkerConfig>,
+) -> Result<(), Error> {
+    let mut after = None;
+    let mut repaired = 0;
+
+    loop {
+        let (count, last) = match ctx
+            .dialogs
+            .reconcile_list_entries(after, config.dialog_list_reconcile_batch_size)
+            .await
+        {
+            Ok(batch) => batch,
+            Err(e) => {
+                tracing::error!(run_id = %job.run_id, error = %e, "Failed to reconcile dialog list entries");
+                return Err(Error::Failed(Arc::new(Box::new(e))));
+            }
+        };
+        repaired += count;
+
+        match last {
+            Some(last) => after = Some(last),
+            None => break,
+        }
+    }
+
+    if repaired > 0 {
+        tracing::warn!(run_id = %job.run_id, repaired, "Repaired drifted dialog list entries");
+    }
+    tracing::info!(run_id = %job.run_id, repaired, "Dialog list reconciliation completed");
+
+    Ok(())
+}
@@ -0,0 +1,372 @@
+//! Retry policies and dead-lettering for queue jobs.
+//!
+//! Each queue worker retries failed jobs in-process with exponential backoff
+//! and jitter, configured per job type ([`RetryConfig`] in
+//! [`super::WorkerConfig`]). A job that is out of attempts, or that aborted,
+//! is recorded in `job_dead_letters` with its payload and then killed in the
+//! queue instead of being re-enqueued by the storage.
+//!
+//! ```text
+//! DeadLetterLayer ─▶ RetryLayer<JobRetryPolicy> ─▶ ... ─▶ handler
+//! ```
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use apalis::layers::retry::backoff::{
+    Backoff, ExponentialBackoff, ExponentialBackoffMaker, MakeBackoff,
+};
+use apalis::layers::retry::{HasherRng, Policy};
+use apalis::prelude::{Error, Request};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use tower::{Layer, Service};
+
+use super::retry_metrics::JobRetryMetrics;
+use crate::domain::JobDeadLetter;
+use crate::repositories::JobDeadLetterRepository;
+
+pub const NOTIFICATION_JOB: &str = "notification";
+pub const THUMBNAIL_JOB: &str = "thumbnail";
+pub const TEXT_EXTRACT_JOB: &str = "text_extract";
+pub const ATTACHMENT_CLEANUP_JOB: &str = "attachment_cleanup";
+
+/// Job types with a retry policy (the `job_type` of dead letters)
+pub const JOB_TYPES: [&str; 4] = [
+    NOTIFICATION_JOB,
+    THUMBNAIL_JOB,
+    TEXT_EXTRACT_JOB,
+    ATTACHMENT_CLEANUP_JOB,
+];
+
+/// Retry policy of a job type.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Attempts including the first one (1 = never retry).
+    pub max_attempts: usize,
+    /// Wait before the first retry, doubled for every further retry.
+    pub backoff_ms: u64,
+    /// Upper bound of the wait.
+    pub max_backoff_ms: u64,
+    /// Random extra wait as a fraction of the wait (0.0 - 1.0).
+    pub jitter: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Check the bounds, returning the first invalid field and the problem
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if self.max_attempts == 0 {
+            return Err(("max_attempts", "must be at least 1"));
+        }
+        if self.backoff_ms == 0 {
+            return Err(("backoff_ms", "must be positive"));
+        }
+        if self.max_backoff_ms < self.backoff_ms {
+            return Err(("max_backoff_ms", "must not be less than backoff_ms"));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(("jitter", "must be between 0.0 and 1.0"));
+        }
+        Ok(())
+    }
+
+    /// Retry policy for the worker of a job type
+    pub fn policy(&self, job_type: &'static str, metrics: JobRetryMetrics) -> JobRetryPolicy {
+        let backoff = ExponentialBackoffMaker::new(
+            Duration::from_millis(self.backoff_ms),
+            Duration::from_millis(self.max_backoff_ms),
+            self.jitter,
+            HasherRng::default(),
+        )
+        .expect("retry config is validated at startup")
+        .make_backoff();
+
+        JobRetryPolicy {
+            job_type,
+            max_attempts: self.max_attempts,
+            backoff,
+            metrics,
+        }
+    }
+}
+
+/// Retries failed jobs with backoff until `max_attempts`, then turns the
+/// error into an abort so the storage kills the job.
+#[derive(Clone)]
+pub struct JobRetryPolicy {
+    job_type: &'static str,
+    max_attempts: usize,
+    backoff: ExponentialBackoff<HasherRng>,
+    metrics: JobRetryMetrics,
+}
+
+impl<T, Res, Ctx> Policy<Request<T, Ctx>, Res, Error> for JobRetryPolicy
+where
+    T: Clone,
+    Ctx: Clone,
+{
+    type Future = BoxFuture<'static, ()>;
+
+    fn retry(
+        &mut self,
+        req: &mut Request<T, Ctx>,
+        result: &mut Result<Res, Error>,
+    ) -> Option<Self::Future> {
+        let err = match result {
+            Ok(_) | Err(Error::Abort(_)) => return None,
+            Err(err) => err,
+        };
+
+        let attempt = req.parts.attempt.current();
+        if attempt < self.max_attempts {
+            self.metrics.record_retry(self.job_type);
+            tracing::warn!(
+                job_type = self.job_type,
+                task_id = %req.parts.task_id,
+                attempt,
+                max_attempts = self.max_attempts,
+                error = %error_message(err),
+                "Job failed, retrying"
+            );
+            let counter = req.parts.attempt.clone();
+            return Some(
+                self.backoff
+                    .next_backoff()
+                    .map(move |_| {
+                        counter.increment();
+                    })
+                    .boxed(),
+            );
+        }
+
+        *err = Error::Abort(Arc::new(Box::new(RetriesExhausted {
+            attempts: attempt,
+            message: error_message(err),
+        })));
+        None
+    }
+
+    fn clone_request(&mut self, req: &Request<T, Ctx>) -> Option<Request<T, Ctx>> {
+        Some(req.clone())
+    }
+}
+
+/// A job failed on its last attempt
+#[derive(Debug, thiserror::Error)]
+#[error("failed after {attempts} attempts: {message}")]
+struct RetriesExhausted {
+    attempts: usize,
+    message: String,
+}
+
+/// Message of a job error without the apalis variant prefix
+fn error_message(err: &Error) -> String {
+    match err {
+        Error::Failed(e) | Error::Abort(e) => e.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Records jobs that failed permanently in `job_dead_letters`.
+///
+/// Wraps the retry layer, so it only sees the outcome of the last attempt.
+#[derive(Clone)]
+pub struct DeadLetterLayer {
+    job_type: &'static str,
+    dead_letters: Arc<JobDeadLetterRepository>,
+    metrics: JobRetryMetrics,
+}
+
+impl DeadLetterLayer {
+    pub fn new(
+        job_type: &'static str,
+        dead_letters: Arc<JobDeadLetterRepository>,
+        metrics: JobRetryMetrics,
+    ) -> Self {
+        Self {
+            job_type,
+            dead_letters,
+            metrics,
+        }
+    }
+}
+
+impl<S> Layer<S> for DeadLetterLayer {
+    type Service = DeadLetterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeadLetterService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service of [`DeadLetterLayer`]
+#[derive(Clone)]
+pub struct DeadLetterService<S> {
+    inner: S,
+    layer: DeadLetterLayer,
+}
+
+impl<S, T, Ctx> Service<Request<T, Ctx>> for DeadLetterService<S>
+where
+    S: Service<Request<T, Ctx>, Error = Error>,
+    S::Future: Send + 'static,
+    S::Response: Send,
+    T: Serialize + Clone + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<S::Response, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<T, Ctx>) -> Self::Future {
+        let args = req.args.clone();
+        let task_id = req.parts.task_id.to_string();
+        // Shared with the retry policy, so it ends at the last attempt
+        let attempt = req.parts.attempt.clone();
+        let layer = self.layer.clone();
+        let fut = self.inner.call(req);
+
+        async move {
+            let result = fut.await;
+            if let Err(err) = &result {
+                layer.metrics.record_dead_lettered(layer.job_type);
+                let payload = serde_json::to_value(&args).unwrap_or(serde_json::Value::Null);
+                let letter = JobDeadLetter::new(
+                    layer.job_type,
+                    task_id,
+                    payload,
+                    error_message(err),
+                    attempt.current(),
+                );
+                tracing::error!(
+                    job_type = layer.job_type,
+                    task_id = %letter.task_id,
+                    attempts = letter.attempts,
+                    error = %letter.error,
+                    "Job failed permanently, dead-lettering"
+                );
+                if let Err(e) = layer.dead_letters.record(&letter).await {
+                    tracing::error!(
+                        job_type = layer.job_type,
+                        task_id = %letter.task_id,
+                        error = %e,
+                        "Failed to record dead-lettered job"
+                    );
+                }
+            }
+            result
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed() -> Result<(), Error> {
+        Err(Error::Failed(Arc::new("storage unavailable".into())))
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(RetryConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let cases = [
+            RetryConfig {
+                max_attempts: 0,
+                ..Default::default()
+            },
+            RetryConfig {
+                backoff_ms: 0,
+                ..Default::default()
+            },
+            RetryConfig {
+                backoff_ms: 5000,
+                max_backoff_ms: 1000,
+                ..Default::default()
+            },
+            RetryConfig {
+                jitter: 1.5,
+                ..Default::default()
+            },
+        ];
+        for config in cases {
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_policy_retries_until_max_attempts() {
+        let config = RetryConfig {
+            max_attempts: 2,
+            backoff_ms: 1,
+            max_backoff_ms: 1,
+            jitter: 0.0,
+        };
+        let metrics = JobRetryMetrics::new();
+        let mut policy = config.policy(NOTIFICATION_JOB, metrics.clone());
+        let mut req: Request<u32, ()> = Request::new(7);
+        // The worker counts the first attempt before calling the handler
+        req.parts.attempt.increment();
+
+        let mut result = failed();
+        let wait = Policy::<_, (), Error>::retry(&mut policy, &mut req, &mut result);
+        wait.expect("first failure is retried").await;
+        assert_eq!(req.parts.attempt.current(), 2);
+
+        let mut result = failed();
+        assert!(Policy::<_, (), Error>::retry(&mut policy, &mut req, &mut result).is_none());
+        match result {
+            Err(Error::Abort(e)) => {
+                assert_eq!(
+                    e.to_string(),
+                    "failed after 2 attempts: storage unavailable"
+                )
+            }
+            other => panic!("expected abort, got {:?}", other),
+        }
+
+        let retries = metrics
+            .snapshot()
+            .into_iter()
+            .find(|(job_type, _)| *job_type == NOTIFICATION_JOB)
+            .map(|(_, s)| s.retries_total);
+        assert_eq!(retries, Some(1));
+    }
+
+    #[test]
+    fn test_policy_does_not_retry_aborts() {
+        let mut policy = RetryConfig::default().policy(THUMBNAIL_JOB, JobRetryMetrics::new());
+        let mut req: Request<u32, ()> = Request::new(7);
+        req.parts.attempt.increment();
+
+        let mut result: Result<(), Error> = Err(Error::Abort(Arc::new("corrupt file".into())));
+        assert!(Policy::<_, (), Error>::retry(&mut policy, &mut req, &mut result).is_none());
+        let mut result: Result<(), Error> = Ok(());
+        assert!(Policy::<_, (), Error>::retry(&mut policy, &mut req, &mut result).is_none());
+    }
+}
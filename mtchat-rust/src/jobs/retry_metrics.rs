@@ -0,0 +1,98 @@
+//! Job retry counters.
+//!
+//! Updated by the retry policies and dead-letter layers of the queue workers
+//! and read by the metrics endpoint; both run in the same process
+//! (per-instance values). Failures are rare, so a mutex is cheap enough.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use super::retry::JOB_TYPES;
+
+/// Snapshot of the counters of one job type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobRetrySnapshot {
+    /// Failed attempts that were retried
+    pub retries_total: u64,
+    /// Jobs that failed permanently and were dead-lettered
+    pub dead_lettered_total: u64,
+}
+
+/// Job retry counters per job type (shared, cheap to clone).
+#[derive(Clone)]
+pub struct JobRetryMetrics {
+    counters: Arc<Mutex<BTreeMap<&'static str, JobRetrySnapshot>>>,
+}
+
+impl Default for JobRetryMetrics {
+    fn default() -> Self {
+        // Every job type is reported, also before its first failure
+        let counters = JOB_TYPES
+            .iter()
+            .map(|job_type| (*job_type, JobRetrySnapshot::default()))
+            .collect();
+        Self {
+            counters: Arc::new(Mutex::new(counters)),
+        }
+    }
+}
+
+impl JobRetryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_retry(&self, job_type: &'static str) {
+        self.update(job_type, |counters| counters.retries_total += 1);
+    }
+
+    pub fn record_dead_lettered(&self, job_type: &'static str) {
+        self.update(job_type, |counters| counters.dead_lettered_total += 1);
+    }
+
+    /// Counters by job type
+    pub fn snapshot(&self) -> Vec<(&'static str, JobRetrySnapshot)> {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        counters.iter().map(|(k, v)| (*k, *v)).collect()
+    }
+
+    fn update(&self, job_type: &'static str, f: impl FnOnce(&mut JobRetrySnapshot)) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        f(counters.entry(job_type).or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_per_job_type() {
+        let metrics = JobRetryMetrics::new();
+        let clone = metrics.clone();
+
+        clone.record_retry("notification");
+        clone.record_retry("notification");
+        metrics.record_dead_lettered("notification");
+        metrics.record_retry("attachment_cleanup");
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), JOB_TYPES.len());
+        let get = |job_type| {
+            snapshot
+                .iter()
+                .find(|(t, _)| *t == job_type)
+                .map(|(_, s)| *s)
+                .unwrap()
+        };
+        assert_eq!(
+            get("notification"),
+            JobRetrySnapshot {
+                retries_total: 2,
+                dead_lettered_total: 1,
+            }
+        );
+        assert_eq!(get("attachment_cleanup").retries_total, 1);
+        assert_eq!(get("thumbnail"), JobRetrySnapshot::default());
+    }
+}
@@ -0,0 +1,169 @@
+//! Response time SLA checks.
+//!
+//! Walks dialogs with an SLA in batches. The oldest unanswered message is
+//! only looked up again when the dialog got new messages since the previous
+//! run. Each escalation (`sla.warning`, `sla.breached`) is claimed in the
+//! database before it is sent, so it goes out once per unanswered message
+//! even with several instances running the job.
+
+use std::sync::Arc;
+
+use apalis::prelude::*;
+use chrono::Utc;
+
+use super::handlers::JobContext;
+use super::types::CheckSlaJob;
+use crate::domain::{Dialog, DialogSlaState, SlaStatus, SlaTarget, UnansweredMessage};
+use crate::repositories::SlaCheck;
+use crate::webhooks::WebhookEvent;
+
+/// Dialogs checked per query
+const SLA_BATCH_SIZE: i64 = 500;
+
+/// Handle SLA check job.
+///
+/// A failed batch ends the run; the next scheduled run starts over.
+pub async fn handle_check_sla(job: CheckSlaJob, ctx: Data<JobContext>) -> Result<(), Error> {
+    let mut after = None;
+    let mut escalated = 0;
+
+    loop {
+        let checks = match ctx.sla.list_checks(after, SLA_BATCH_SIZE).await {
+            Ok(checks) => checks,
+            Err(e) => {
+                tracing::error!(run_id = %job.run_id, error = %e, "Failed to list dialogs with an SLA");
+                return Err(Error::Failed(Arc::new(Box::new(e))));
+            }
+        };
+        let batch_len = checks.len() as i64;
+        after = checks.last().map(|check| check.dialog_id);
+
+        for check in checks {
+            match check_dialog(&ctx, check).await {
+                Ok(sent) => escalated += usize::from(sent),
+                Err(e) => {
+                    tracing::error!(run_id = %job.run_id, error = %e, "Failed to check dialog SLA");
+                    return Err(Error::Failed(Arc::new(Box::new(e))));
+                }
+            }
+        }
+
+        if batch_len < SLA_BATCH_SIZE {
+            break;
+        }
+    }
+
+    if escalated > 0 {
+        tracing::info!(run_id = %job.run_id, escalated, "SLA escalations sent");
+    } else {
+        tracing::debug!(run_id = %job.run_id, "No SLA escalations");
+    }
+
+    Ok(())
+}
+
+/// Check one dialog, returning whether an escalation was sent
+async fn check_dialog(ctx: &JobContext, check: SlaCheck) -> Result<bool, sqlx::Error> {
+    let state = match check.state {
+        Some(state) if state.checked_seq == check.last_message_seq => state,
+        _ => {
+            let unanswered = ctx
+                .sla
+                .oldest_unanswered(check.dialog_id, check.responder_company.as_deref())
+                .await?;
+            ctx.sla
+                .save_state(check.dialog_id, check.last_message_seq, unanswered.as_ref())
+                .await?
+        }
+    };
+    let Some(message) = state.unanswered() else {
+        return Ok(false);
+    };
+
+    let status = check.target.status_at(message.sent_at, Utc::now());
+    if !needs_escalation(&state, status)
+        || !ctx
+            .sla
+            .claim_escalation(check.dialog_id, message.message_id, status)
+            .await?
+    {
+        return Ok(false);
+    }
+
+    let Some(dialog) = ctx.dialogs.find_by_id(check.dialog_id).await? else {
+        return Ok(false);
+    };
+    let event = escalation_event(&dialog, &message, &check.target, status);
+    tracing::info!(
+        dialog_id = %dialog.id,
+        message_id = %message.message_id,
+        event = %event.event_type,
+        "Sending SLA escalation"
+    );
+    ctx.webhooks.send(event).await;
+
+    Ok(true)
+}
+
+/// Whether `status` calls for an escalation not sent yet. A breach without a
+/// sent warning only sends `sla.breached`.
+fn needs_escalation(state: &DialogSlaState, status: SlaStatus) -> bool {
+    match status {
+        SlaStatus::Warning => state.warned_at.is_none() && state.breached_at.is_none(),
+        SlaStatus::Breached => state.breached_at.is_none(),
+        SlaStatus::Answered | SlaStatus::Pending => false,
+    }
+}
+
+fn escalation_event(
+    dialog: &Dialog,
+    message: &UnansweredMessage,
+    target: &SlaTarget,
+    status: SlaStatus,
+) -> WebhookEvent {
+    if status == SlaStatus::Breached {
+        WebhookEvent::sla_breached(dialog, message, target)
+    } else {
+        WebhookEvent::sla_warning(dialog, message, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn state() -> DialogSlaState {
+        DialogSlaState {
+            dialog_id: Uuid::now_v7(),
+            checked_seq: 3,
+            message_id: Some(Uuid::now_v7()),
+            sender_id: Some("buyer-1".into()),
+            pending_since: Some(Utc::now()),
+            warned_at: None,
+            breached_at: None,
+        }
+    }
+
+    #[test]
+    fn test_each_escalation_once() {
+        let mut state = state();
+        assert!(!needs_escalation(&state, SlaStatus::Pending));
+        assert!(needs_escalation(&state, SlaStatus::Warning));
+        assert!(needs_escalation(&state, SlaStatus::Breached));
+
+        state.warned_at = Some(Utc::now());
+        assert!(!needs_escalation(&state, SlaStatus::Warning));
+        assert!(needs_escalation(&state, SlaStatus::Breached));
+
+        state.breached_at = Some(Utc::now());
+        assert!(!needs_escalation(&state, SlaStatus::Breached));
+    }
+
+    #[test]
+    fn test_no_warning_after_breach() {
+        let mut state = state();
+        state.breached_at = Some(Utc::now());
+        assert!(!needs_escalation(&state, SlaStatus::Warning));
+    }
+}
@@ -0,0 +1,113 @@
+//! Document text extraction job.
+//!
+//! Downloads a PDF or DOCX attachment, extracts its text (see
+//! [`crate::services::text_extract`]), stores it in `attachment_texts` for
+//! attachment search and sends `attachment.text_extracted` with the beginning
+//! of the text so the host can index it. Without the `text-extract` feature
+//! jobs are skipped.
+
+use std::sync::Arc;
+
+use apalis::prelude::*;
+
+use super::handlers::JobContext;
+use super::types::TextExtractJob;
+use super::worker::WorkerConfig;
+use crate::services::text_extract;
+use crate::webhooks::WebhookEvent;
+
+/// Handle text extraction job.
+///
+/// Storage and database errors are retried; documents that cannot be parsed
+/// are logged and skipped.
+pub async fn handle_text_extract(
+    job: TextExtractJob,
+    ctx: Data<JobContext>,
+    config: Data<WorkerConfig>,
+) -> Result<(), Error> {
+    if !text_extract::is_enabled() || !ctx.storage.is_configured() {
+        return Ok(());
+    }
+
+    let attachment = match ctx.attachments.find_by_id(job.attachment_id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => {
+            tracing::debug!(attachment_id = %job.attachment_id, "Attachment gone, skipping text extraction");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!(attachment_id = %job.attachment_id, error = %e, "Failed to load attachment");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    if !text_extract::is_extractable(&attachment.content_type) {
+        return Ok(());
+    }
+
+    let data = match ctx.storage.get_object(&attachment.s3_key).await {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!(attachment_id = %attachment.id, error = %e, "Failed to download attachment");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    let content_type = attachment.content_type.clone();
+    let pdfium_lib_path = config.pdfium_lib_path.clone();
+    let text = match tokio::task::spawn_blocking(move || {
+        text_extract::extract_text(&content_type, &data, pdfium_lib_path.as_deref())
+    })
+    .await
+    {
+        Ok(Ok(text)) => text,
+        Ok(Err(e)) => {
+            tracing::warn!(attachment_id = %attachment.id, error = %e, "Failed to extract attachment text");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!(attachment_id = %attachment.id, error = %e, "Text extraction task panicked");
+            return Ok(());
+        }
+    };
+
+    if text.is_empty() {
+        tracing::debug!(attachment_id = %attachment.id, "No text in attachment");
+        return Ok(());
+    }
+
+    let extracted_at = match ctx.attachments.save_text(attachment.id, &text).await {
+        Ok(extracted_at) => extracted_at,
+        Err(e) => {
+            tracing::error!(attachment_id = %attachment.id, error = %e, "Failed to save attachment text");
+            return Err(Error::Failed(Arc::new(Box::new(e))));
+        }
+    };
+
+    tracing::debug!(
+        attachment_id = %attachment.id,
+        chars = text.chars().count(),
+        "Attachment text extracted"
+    );
+
+    // Webhook for host-side indexing (best effort, the text is already saved)
+    let message = ctx.messages.find_by_id(attachment.message_id).await;
+    let dialog = match message {
+        Ok(Some(message)) => ctx.dialogs.find_by_id(message.dialog_id).await,
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    match dialog {
+        Ok(Some(dialog)) => {
+            let event =
+                WebhookEvent::attachment_text_extracted(&dialog, &attachment, &text, extracted_at);
+            ctx.webhooks.send(event).await;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!(attachment_id = %attachment.id, error = %e, "Failed to load dialog for text webhook");
+        }
+    }
+
+    Ok(())
+}
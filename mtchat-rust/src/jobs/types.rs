@@ -4,6 +4,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::domain::{BroadcastMention, MessagePriority};
+
 /// Notification job - sends webhook after short delay if message not read.
 ///
 /// The delay allows checking if user read the message while in chat.
@@ -17,6 +19,22 @@ pub struct NotificationJob {
     pub message_id: Uuid,
     /// User who sent the message (external identifier)
     pub sender_id: String,
+    /// Set when the recipient was reached by `@channel` / `@here`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast: Option<BroadcastMention>,
+    /// Priority of the message
+    #[serde(default)]
+    pub priority: MessagePriority,
+    /// How long the handler waits before checking whether the message was
+    /// read (set when enqueued; older jobs use the global delay)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+    /// When the job was enqueued (compared with cancellation markers)
+    #[serde(default = "Utc::now")]
+    pub enqueued_at: DateTime<Utc>,
+    /// `X-Request-Id` of the request that sent the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl NotificationJob {
@@ -32,8 +50,24 @@ impl NotificationJob {
             recipient_id: recipient_id.into(),
             message_id,
             sender_id: sender_id.into(),
+            broadcast: None,
+            priority: MessagePriority::Normal,
+            delay_ms: None,
+            enqueued_at: Utc::now(),
+            request_id: None,
         }
     }
+
+    /// Mark as a mention-priority notification
+    pub fn with_broadcast(mut self, mention: BroadcastMention) -> Self {
+        self.broadcast = Some(mention);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: MessagePriority) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 
 /// Auto-archive job - archives inactive dialogs.
@@ -68,6 +102,200 @@ impl From<DateTime<Utc>> for AutoArchiveJob {
     }
 }
 
+/// Purge job - hard-deletes dialogs soft-deleted longer than the retention
+/// window, including their attachment files.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PurgeDeletedDialogsJob {
+    /// Unique run ID for logging
+    pub run_id: Uuid,
+    /// When this job was scheduled (used by cron)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Required by apalis-cron for scheduled job creation.
+impl From<DateTime<Utc>> for PurgeDeletedDialogsJob {
+    fn from(scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            run_id: Uuid::now_v7(),
+            scheduled_at: Some(scheduled_at),
+        }
+    }
+}
+
+/// Unread reconciliation job - recomputes participants' unread counters and
+/// repairs the ones that drifted.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReconcileUnreadJob {
+    /// Unique run ID for logging
+    pub run_id: Uuid,
+    /// When this job was scheduled (used by cron)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Required by apalis-cron for scheduled job creation.
+impl From<DateTime<Utc>> for ReconcileUnreadJob {
+    fn from(scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            run_id: Uuid::now_v7(),
+            scheduled_at: Some(scheduled_at),
+        }
+    }
+}
+
+/// Dialog list reconciliation job - rebuilds the `dialog_list_entries` read
+/// model from the source tables and repairs entries that drifted.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReconcileDialogListJob {
+    /// Unique run ID for logging
+    pub run_id: Uuid,
+    /// When this job was scheduled (used by cron)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Required by apalis-cron for scheduled job creation.
+impl From<DateTime<Utc>> for ReconcileDialogListJob {
+    fn from(scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            run_id: Uuid::now_v7(),
+            scheduled_at: Some(scheduled_at),
+        }
+    }
+}
+
+/// Participant removal job - removes participants whose removal grace
+/// period has ended.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RemovePendingParticipantsJob {
+    /// Unique run ID for logging
+    pub run_id: Uuid,
+    /// When this job was scheduled (used by cron)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Required by apalis-cron for scheduled job creation.
+impl From<DateTime<Utc>> for RemovePendingParticipantsJob {
+    fn from(scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            run_id: Uuid::now_v7(),
+            scheduled_at: Some(scheduled_at),
+        }
+    }
+}
+
+/// SLA job - finds unanswered messages of dialogs with a response time SLA
+/// and sends `sla.warning` / `sla.breached` webhooks.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CheckSlaJob {
+    /// Unique run ID for logging
+    pub run_id: Uuid,
+    /// When this job was scheduled (used by cron)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Required by apalis-cron for scheduled job creation.
+impl From<DateTime<Utc>> for CheckSlaJob {
+    fn from(scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            run_id: Uuid::now_v7(),
+            scheduled_at: Some(scheduled_at),
+        }
+    }
+}
+
+/// Digest job - sends the `notification.digest` webhooks whose window ended.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SendDigestsJob {
+    /// Unique run ID for logging
+    pub run_id: Uuid,
+    /// When this job was scheduled (used by cron)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Required by apalis-cron for scheduled job creation.
+impl From<DateTime<Utc>> for SendDigestsJob {
+    fn from(scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            run_id: Uuid::now_v7(),
+            scheduled_at: Some(scheduled_at),
+        }
+    }
+}
+
+/// Attachment cleanup job - deletes storage objects (files and thumbnails)
+/// of deleted messages or purged dialogs.
+///
+/// Keys still referenced by another attachment are kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentCleanupJob {
+    /// Dialog the attachments belonged to (for logging)
+    pub dialog_id: Uuid,
+    /// Storage keys to delete
+    pub keys: Vec<String>,
+}
+
+impl AttachmentCleanupJob {
+    pub fn new(dialog_id: Uuid, keys: Vec<String>) -> Self {
+        Self { dialog_id, keys }
+    }
+}
+
+/// Thumbnail job - renders preview images.
+///
+/// For attachments, renders the first page of PDFs (requires the
+/// `pdf-preview` feature); other types are skipped. For avatars, renders the
+/// square variants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ThumbnailJob {
+    /// Avatar variants of a participant
+    Avatar {
+        dialog_id: Uuid,
+        user_id: String,
+        /// Avatar the job was enqueued for (skipped if it changed since)
+        avatar_s3_key: String,
+    },
+    /// Preview of an attachment
+    Attachment { attachment_id: Uuid },
+}
+
+impl ThumbnailJob {
+    pub fn new(attachment_id: Uuid) -> Self {
+        Self::Attachment { attachment_id }
+    }
+
+    pub fn avatar(
+        dialog_id: Uuid,
+        user_id: impl Into<String>,
+        avatar_s3_key: impl Into<String>,
+    ) -> Self {
+        Self::Avatar {
+            dialog_id,
+            user_id: user_id.into(),
+            avatar_s3_key: avatar_s3_key.into(),
+        }
+    }
+}
+
+/// Text extraction job - stores the text of a PDF or DOCX attachment for
+/// search and sends `attachment.text_extracted` (requires the `text-extract`
+/// feature).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextExtractJob {
+    pub attachment_id: Uuid,
+}
+
+impl TextExtractJob {
+    pub fn new(attachment_id: Uuid) -> Self {
+        Self { attachment_id }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,6 +326,20 @@ mod tests {
         assert_eq!(job.recipient_id, deserialized.recipient_id);
         assert_eq!(job.message_id, deserialized.message_id);
         assert_eq!(job.sender_id, deserialized.sender_id);
+        assert_eq!(job.enqueued_at, deserialized.enqueued_at);
+    }
+
+    #[test]
+    fn test_notification_job_without_enqueued_at() {
+        // Jobs queued before enqueued_at existed still deserialize
+        let json = format!(
+            r#"{{"dialog_id":"{}","recipient_id":"r","message_id":"{}","sender_id":"s"}}"#,
+            Uuid::now_v7(),
+            Uuid::now_v7()
+        );
+        let job: NotificationJob = serde_json::from_str(&json).unwrap();
+        assert!(job.enqueued_at <= Utc::now());
+        assert_eq!(job.priority, MessagePriority::Normal);
     }
 
     #[test]
@@ -105,4 +347,41 @@ mod tests {
         let job = AutoArchiveJob::new();
         assert!(!job.run_id.is_nil());
     }
+
+    #[test]
+    fn test_thumbnail_job_serialization() {
+        let job = ThumbnailJob::new(Uuid::now_v7());
+
+        let json = serde_json::to_string(&job).unwrap();
+        let deserialized: ThumbnailJob = serde_json::from_str(&json).unwrap();
+        assert_eq!(job, deserialized);
+
+        let avatar = ThumbnailJob::avatar(Uuid::now_v7(), "user-1", "avatars/a/user-1/b.png");
+        let json = serde_json::to_string(&avatar).unwrap();
+        let deserialized: ThumbnailJob = serde_json::from_str(&json).unwrap();
+        assert_eq!(avatar, deserialized);
+    }
+
+    #[test]
+    fn test_text_extract_job_serialization() {
+        let job = TextExtractJob::new(Uuid::now_v7());
+
+        let json = serde_json::to_string(&job).unwrap();
+        let deserialized: TextExtractJob = serde_json::from_str(&json).unwrap();
+        assert_eq!(job, deserialized);
+    }
+
+    #[test]
+    fn test_attachment_cleanup_job_serialization() {
+        let job = AttachmentCleanupJob::new(
+            Uuid::now_v7(),
+            vec!["a/file.pdf".into(), "a/file.preview.png".into()],
+        );
+
+        let json = serde_json::to_string(&job).unwrap();
+        let deserialized: AttachmentCleanupJob = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(job.dialog_id, deserialized.dialog_id);
+        assert_eq!(job.keys, deserialized.keys);
+    }
 }
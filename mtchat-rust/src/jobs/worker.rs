@@ -5,21 +5,86 @@ use std::sync::Arc;
 
 use apalis::prelude::*;
 use apalis_cron::{CronStream, Schedule};
-use apalis_redis::RedisStorage;
+use apalis_redis::{RedisPollError, RedisStorage};
 use fred::clients::Pool as RedisPool;
+use serde::{Deserialize, Serialize};
 
-use super::handlers::{handle_auto_archive, handle_notification, JobContext};
-use super::types::NotificationJob;
+use super::digest::handle_send_digests;
+use super::handlers::{
+    handle_attachment_cleanup, handle_auto_archive, handle_notification,
+    handle_purge_deleted_dialogs, handle_remove_pending_participants, handle_thumbnail, JobContext,
+};
+use super::heartbeat::{WorkerHeartbeat, HEARTBEAT_INTERVAL};
+use super::reconcile_dialog_list::handle_reconcile_dialog_list;
+use super::reconcile_unread::handle_reconcile_unread;
+use super::retry::{
+    DeadLetterLayer, RetryConfig, ATTACHMENT_CLEANUP_JOB, NOTIFICATION_JOB, TEXT_EXTRACT_JOB,
+    THUMBNAIL_JOB,
+};
+use super::sla::handle_check_sla;
+use super::text_extract::handle_text_extract;
+use super::types::{AttachmentCleanupJob, NotificationJob, TextExtractJob, ThumbnailJob};
+use crate::middleware::ServiceContextLayer;
+use crate::repositories::JobDeadLetterRepository;
 
-/// Worker configuration.
-#[derive(Clone)]
+/// Worker configuration (`[jobs]` section).
+///
+/// Environment variables: `ARCHIVE_CRON`, `ARCHIVE_AFTER_SECS`,
+/// `NOTIFICATION_CONCURRENCY`, `NOTIFICATION_MAX_AGE_SECS`, `PURGE_CRON`,
+/// `DIALOG_RETENTION_SECS`, `EXPORT_DELETION_RETENTION_SECS`, `UNREAD_RECONCILE_CRON`, `UNREAD_RECONCILE_BATCH_SIZE`,
+/// `DIALOG_LIST_RECONCILE_CRON`, `DIALOG_LIST_RECONCILE_BATCH_SIZE`,
+/// `PARTICIPANT_REMOVAL_CRON`, `PARTICIPANT_REMOVAL_GRACE_SECS`, `SLA_CRON`,
+/// `NOTIFICATION_DIGEST_CRON`, and the `*_RETRY_*` variables of the queue
+/// workers (e.g. `NOTIFICATION_RETRY_MAX_ATTEMPTS`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WorkerConfig {
     /// Cron schedule for auto-archive job.
     pub archive_cron: String,
-    /// Seconds of inactivity before auto-archive (default: 259200 = 3 days).
+    /// Default for the `archive_after_secs` runtime setting (default: 259200 = 3 days).
     pub archive_after_secs: i64,
     /// Number of concurrent notification workers.
     pub notification_concurrency: usize,
+    /// Notification jobs that waited longer than this in the queue (worker
+    /// downtime) are dropped instead of sending stale notifications
+    /// (default: 3600 = 1 hour).
+    pub notification_max_age_secs: i64,
+    /// Cron schedule for purging soft-deleted dialogs.
+    pub purge_cron: String,
+    /// How long soft-deleted dialogs can be restored before they are purged
+    /// (default: 2592000 = 30 days).
+    pub dialog_retention_secs: i64,
+    /// How long tombstones of hard-deleted rows stay in the deletions
+    /// export; removed by the purge job (default: 7776000 = 90 days).
+    pub export_deletion_retention_secs: i64,
+    /// Cron schedule for repairing drifted unread counters.
+    pub unread_reconcile_cron: String,
+    /// Dialogs reconciled per query (default: 500).
+    pub unread_reconcile_batch_size: i64,
+    /// Cron schedule for rebuilding drifted dialog list entries.
+    pub dialog_list_reconcile_cron: String,
+    /// Dialogs whose list entries are rebuilt per query (default: 500).
+    pub dialog_list_reconcile_batch_size: i64,
+    /// Cron schedule for finally removing participants whose grace period ended.
+    pub participant_removal_cron: String,
+    /// Default grace period when management removes a participant
+    /// (default: 0 = remove immediately).
+    pub participant_removal_grace_secs: i64,
+    /// Cron schedule for checking response time SLAs.
+    pub sla_cron: String,
+    /// Cron schedule for sending notification digests whose window ended.
+    pub notification_digest_cron: String,
+    /// Retry policy of notification jobs.
+    pub notification_retry: RetryConfig,
+    /// Retry policy of thumbnail jobs.
+    pub thumbnail_retry: RetryConfig,
+    /// Retry policy of text extraction jobs.
+    pub text_extract_retry: RetryConfig,
+    /// Retry policy of attachment cleanup jobs (storage outages).
+    pub attachment_cleanup_retry: RetryConfig,
+    /// Directory of the pdfium shared library for PDF previews and text
+    /// extraction (default: the system library path).
+    pub pdfium_lib_path: Option<String>,
 }
 
 impl Default for WorkerConfig {
@@ -28,24 +93,44 @@ impl Default for WorkerConfig {
             archive_cron: "0 */5 * * * *".to_string(), // every 5 minutes
             archive_after_secs: 259200,                // 3 days
             notification_concurrency: 4,
-        }
-    }
-}
-
-impl WorkerConfig {
-    /// Create config from environment variables.
-    pub fn from_env() -> Self {
-        Self {
-            archive_cron: std::env::var("ARCHIVE_CRON")
-                .unwrap_or_else(|_| "0 */5 * * * *".to_string()),
-            archive_after_secs: std::env::var("ARCHIVE_AFTER_SECS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(259200), // 3 days
-            notification_concurrency: std::env::var("NOTIFICATION_CONCURRENCY")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(4),
+            notification_max_age_secs: 3600,         // 1 hour
+            purge_cron: "0 0 * * * *".to_string(),   // hourly
+            dialog_retention_secs: 2592000,          // 30 days
+            export_deletion_retention_secs: 7776000, // 90 days
+            unread_reconcile_cron: "0 30 3 * * *".to_string(), // daily at 03:30
+            unread_reconcile_batch_size: 500,
+            dialog_list_reconcile_cron: "0 0 4 * * *".to_string(), // daily at 04:00
+            dialog_list_reconcile_batch_size: 500,
+            participant_removal_cron: "0 * * * * *".to_string(), // every minute
+            participant_removal_grace_secs: 0,
+            sla_cron: "30 * * * * *".to_string(), // every minute
+            notification_digest_cron: "*/10 * * * * *".to_string(), // every 10 seconds
+            // Late notifications are of little use, give up quickly
+            notification_retry: RetryConfig {
+                max_attempts: 3,
+                backoff_ms: 1000,
+                max_backoff_ms: 10_000,
+                jitter: 0.5,
+            },
+            thumbnail_retry: RetryConfig {
+                max_attempts: 3,
+                backoff_ms: 5000,
+                max_backoff_ms: 60_000,
+                jitter: 0.5,
+            },
+            text_extract_retry: RetryConfig {
+                max_attempts: 3,
+                backoff_ms: 5000,
+                max_backoff_ms: 60_000,
+                jitter: 0.5,
+            },
+            attachment_cleanup_retry: RetryConfig {
+                max_attempts: 6,
+                backoff_ms: 1000,
+                max_backoff_ms: 60_000,
+                jitter: 0.5,
+            },
+            pdfium_lib_path: None,
         }
     }
 }
@@ -55,40 +140,210 @@ impl WorkerConfig {
 /// Returns a Monitor that manages the workers.
 pub async fn start_workers(
     notification_storage: RedisStorage<NotificationJob>,
+    thumbnail_storage: RedisStorage<ThumbnailJob>,
+    cleanup_storage: RedisStorage<AttachmentCleanupJob>,
+    text_extract_storage: RedisStorage<TextExtractJob>,
     _redis: Arc<RedisPool>,
     ctx: JobContext,
     config: WorkerConfig,
 ) -> Result<Monitor, WorkerError> {
+    // Queue workers retry failed jobs per their policy; jobs that fail
+    // permanently are dead-lettered
+    let retry_metrics = ctx.jobs.retry_metrics().clone();
+    let dead_letters = Arc::new(JobDeadLetterRepository::new(ctx.db.clone()));
+    let retry = |job_type: &'static str, config: &RetryConfig| {
+        (
+            DeadLetterLayer::new(job_type, dead_letters.clone(), retry_metrics.clone()),
+            config.policy(job_type, retry_metrics.clone()),
+        )
+    };
+
     // Build notification worker
+    let (dead_letter, policy) = retry(NOTIFICATION_JOB, &config.notification_retry);
     let notification_worker = WorkerBuilder::new("mtchat-notifications")
+        .layer(ServiceContextLayer)
+        .layer(dead_letter)
+        .retry(policy)
         .concurrency(config.notification_concurrency)
         .data(ctx.clone())
+        .data(config.clone())
         .backend(notification_storage)
         .build_fn(handle_notification);
 
+    // Build thumbnail worker (rendering is CPU-bound, keep it to one at a time)
+    let (dead_letter, policy) = retry(THUMBNAIL_JOB, &config.thumbnail_retry);
+    let thumbnail_worker = WorkerBuilder::new("mtchat-thumbnails")
+        .layer(ServiceContextLayer)
+        .layer(dead_letter)
+        .retry(policy)
+        .concurrency(1)
+        .data(ctx.clone())
+        .data(config.clone())
+        .backend(thumbnail_storage)
+        .build_fn(handle_thumbnail);
+
+    // Build text extraction worker (CPU-bound like thumbnails)
+    let (dead_letter, policy) = retry(TEXT_EXTRACT_JOB, &config.text_extract_retry);
+    let text_extract_worker = WorkerBuilder::new("mtchat-text-extract")
+        .layer(ServiceContextLayer)
+        .layer(dead_letter)
+        .retry(policy)
+        .concurrency(1)
+        .data(ctx.clone())
+        .data(config.clone())
+        .backend(text_extract_storage)
+        .build_fn(handle_text_extract);
+
+    // Build attachment cleanup worker
+    let (dead_letter, policy) = retry(ATTACHMENT_CLEANUP_JOB, &config.attachment_cleanup_retry);
+    let cleanup_worker = WorkerBuilder::new("mtchat-attachment-cleanup")
+        .layer(ServiceContextLayer)
+        .layer(dead_letter)
+        .retry(policy)
+        .concurrency(2)
+        .data(ctx.clone())
+        .backend(cleanup_storage)
+        .build_fn(handle_attachment_cleanup);
+
     // Build auto-archive cron worker
     let archive_schedule = Schedule::from_str(&config.archive_cron)
         .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
 
     let archive_worker = WorkerBuilder::new("mtchat-auto-archive")
-        .data(ctx)
+        .layer(ServiceContextLayer)
+        .data(ctx.clone())
         .backend(CronStream::new(archive_schedule))
         .build_fn(handle_auto_archive);
 
+    // Build purge cron worker for soft-deleted dialogs
+    let purge_schedule = Schedule::from_str(&config.purge_cron)
+        .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
+
+    let purge_worker = WorkerBuilder::new("mtchat-purge-dialogs")
+        .layer(ServiceContextLayer)
+        .data(ctx.clone())
+        .data(config.clone())
+        .backend(CronStream::new(purge_schedule))
+        .build_fn(handle_purge_deleted_dialogs);
+
+    // Build unread reconciliation cron worker
+    let reconcile_schedule = Schedule::from_str(&config.unread_reconcile_cron)
+        .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
+
+    let reconcile_worker = WorkerBuilder::new("mtchat-reconcile-unread")
+        .layer(ServiceContextLayer)
+        .data(ctx.clone())
+        .data(config.clone())
+        .backend(CronStream::new(reconcile_schedule))
+        .build_fn(handle_reconcile_unread);
+
+    // Build dialog list reconciliation cron worker
+    let list_reconcile_schedule = Schedule::from_str(&config.dialog_list_reconcile_cron)
+        .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
+
+    let list_reconcile_worker = WorkerBuilder::new("mtchat-reconcile-dialog-list")
+        .layer(ServiceContextLayer)
+        .data(ctx.clone())
+        .data(config.clone())
+        .backend(CronStream::new(list_reconcile_schedule))
+        .build_fn(handle_reconcile_dialog_list);
+
+    // Build cron worker finishing delayed participant removals
+    let removal_schedule = Schedule::from_str(&config.participant_removal_cron)
+        .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
+
+    let removal_worker = WorkerBuilder::new("mtchat-remove-participants")
+        .layer(ServiceContextLayer)
+        .data(ctx.clone())
+        .backend(CronStream::new(removal_schedule))
+        .build_fn(handle_remove_pending_participants);
+
+    // Build SLA cron worker
+    let sla_schedule = Schedule::from_str(&config.sla_cron)
+        .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
+
+    let sla_worker = WorkerBuilder::new("mtchat-check-sla")
+        .layer(ServiceContextLayer)
+        .data(ctx.clone())
+        .backend(CronStream::new(sla_schedule))
+        .build_fn(handle_check_sla);
+
+    // Build notification digest cron worker
+    let digest_schedule = Schedule::from_str(&config.notification_digest_cron)
+        .map_err(|e| WorkerError::InvalidCron(e.to_string()))?;
+
+    let digest_worker = WorkerBuilder::new("mtchat-notification-digests")
+        .layer(ServiceContextLayer)
+        .data(ctx)
+        .backend(CronStream::new(digest_schedule))
+        .build_fn(handle_send_digests);
+
     // Create monitor
     let monitor = Monitor::new()
         .register(notification_worker)
-        .register(archive_worker);
+        .register(thumbnail_worker)
+        .register(text_extract_worker)
+        .register(cleanup_worker)
+        .register(archive_worker)
+        .register(purge_worker)
+        .register(reconcile_worker)
+        .register(list_reconcile_worker)
+        .register(removal_worker)
+        .register(sla_worker)
+        .register(digest_worker);
 
     tracing::info!(
         notification_concurrency = config.notification_concurrency,
+        notification_max_age_secs = config.notification_max_age_secs,
         archive_cron = %config.archive_cron,
+        purge_cron = %config.purge_cron,
+        unread_reconcile_cron = %config.unread_reconcile_cron,
+        dialog_list_reconcile_cron = %config.dialog_list_reconcile_cron,
+        participant_removal_cron = %config.participant_removal_cron,
+        sla_cron = %config.sla_cron,
+        notification_digest_cron = %config.notification_digest_cron,
         "Job workers configured"
     );
 
     Ok(monitor)
 }
 
+/// Run the worker monitor in the background, beating `heartbeat` after
+/// every interval in which the workers polled their queues without errors.
+pub fn run_workers(monitor: Monitor, heartbeat: WorkerHeartbeat) {
+    let events = heartbeat.clone();
+    let monitor = monitor.on_event(move |worker| match worker.inner() {
+        Event::Error(e) if e.downcast_ref::<RedisPollError>().is_some() => {
+            tracing::warn!(worker = worker.id().name(), error = %e, "Job queue poll failed");
+            events.poll_failed();
+        }
+        Event::Stop | Event::Exit => events.worker_stopped(),
+        _ => {}
+    });
+    let monitor_task = tokio::spawn(async move {
+        tracing::info!("Job workers started");
+        if let Err(e) = monitor.run().await {
+            tracing::error!("Job workers error: {}", e);
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        // The first tick is immediate: the first beat follows a whole
+        // interval of polling
+        interval.tick().await;
+        let mut poll_errors = heartbeat.poll_errors();
+        loop {
+            interval.tick().await;
+            if monitor_task.is_finished() {
+                break;
+            }
+            poll_errors = heartbeat.beat_if_polling(poll_errors);
+        }
+        tracing::error!("Job workers stopped");
+    });
+}
+
 /// Errors that can occur when starting workers.
 #[derive(Debug, thiserror::Error)]
 pub enum WorkerError {
@@ -108,6 +363,26 @@ mod tests {
         let config = WorkerConfig::default();
         assert_eq!(config.archive_after_secs, 259200); // 3 days
         assert_eq!(config.notification_concurrency, 4);
+        assert_eq!(config.notification_max_age_secs, 3600);
+        assert_eq!(config.dialog_retention_secs, 2592000); // 30 days
+        assert_eq!(config.export_deletion_retention_secs, 7776000); // 90 days
+        assert!(Schedule::from_str(&config.purge_cron).is_ok());
+        assert!(Schedule::from_str(&config.unread_reconcile_cron).is_ok());
+        assert_eq!(config.unread_reconcile_batch_size, 500);
+        assert!(Schedule::from_str(&config.dialog_list_reconcile_cron).is_ok());
+        assert_eq!(config.dialog_list_reconcile_batch_size, 500);
+        assert!(Schedule::from_str(&config.participant_removal_cron).is_ok());
+        assert_eq!(config.participant_removal_grace_secs, 0);
+        assert!(Schedule::from_str(&config.sla_cron).is_ok());
+        assert!(Schedule::from_str(&config.notification_digest_cron).is_ok());
+        for retry in [
+            config.notification_retry,
+            config.thumbnail_retry,
+            config.text_extract_retry,
+            config.attachment_cleanup_retry,
+        ] {
+            assert_eq!(retry.validate(), Ok(()));
+        }
     }
 
     #[test]
@@ -1,14 +1,26 @@
 //! MTChat API - Embeddable Chat Service Backend
 //!
 //! Object-bound chat service with direct and potential participants.
-//! This library crate exposes internal modules for integration testing.
+//! This library crate exposes internal modules for integration testing and
+//! [`build_router`] / [`spawn_background`] for embedding the service in a
+//! host axum application.
 
 pub mod api;
+mod app;
+pub mod cli;
 pub mod config;
 pub mod domain;
+pub mod events;
 pub mod jobs;
+pub mod listener;
 pub mod middleware;
+pub mod migrate;
 pub mod repositories;
+pub mod seed;
 pub mod services;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
 pub mod webhooks;
 pub mod ws;
+
+pub use app::{build_router, build_router_with_db, spawn_background, StartupError};
@@ -0,0 +1,190 @@
+//! HTTP listeners
+//!
+//! The server listens on TCP by default. `LISTEN=unix:<path>` serves on a
+//! Unix domain socket, e.g. when the chat runs next to the host app behind
+//! nginx, and `LISTEN=systemd` on the socket passed by systemd socket
+//! activation.
+
+use axum::Router;
+use socket2::Socket;
+use std::fmt;
+use std::io;
+use std::os::fd::{FromRawFd, RawFd};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::net::{TcpListener, UnixListener};
+
+use crate::config::{ListenAddr, ServerConfig};
+
+/// First file descriptor passed by systemd (`SD_LISTEN_FDS_START`)
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// Bound listener the server accepts connections on
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix {
+        listener: UnixListener,
+        path: PathBuf,
+    },
+}
+
+impl Listener {
+    /// Bind the listener configured by `server.listen`
+    pub async fn bind(config: &ServerConfig) -> io::Result<Self> {
+        let addr = config
+            .listen()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        match addr {
+            ListenAddr::Tcp(addr) => Ok(Self::Tcp(TcpListener::bind(addr).await?)),
+            ListenAddr::Unix(path) => Self::bind_unix(path, config.socket_mode().unwrap_or(0o660)),
+            ListenAddr::Systemd => Self::from_systemd(
+                std::env::var("LISTEN_PID").ok().as_deref(),
+                std::env::var("LISTEN_FDS").ok().as_deref(),
+            ),
+        }
+    }
+
+    /// Create a Unix socket at `path`, replacing a stale socket left by a
+    /// previous run
+    fn bind_unix(path: PathBuf, mode: u32) -> io::Result<Self> {
+        remove_stale_socket(&path)?;
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+        Ok(Self::Unix { listener, path })
+    }
+
+    /// Take over the socket passed by systemd, given the `LISTEN_PID` and
+    /// `LISTEN_FDS` variables
+    fn from_systemd(listen_pid: Option<&str>, listen_fds: Option<&str>) -> io::Result<Self> {
+        let for_us = listen_pid.and_then(|pid| pid.parse::<u32>().ok()) == Some(std::process::id());
+        let count = listen_fds.and_then(|n| n.parse::<u32>().ok()).unwrap_or(0);
+        if !for_us || count == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no socket passed by systemd (LISTEN_PID/LISTEN_FDS not set for this process)",
+            ));
+        }
+        if count > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("expected one socket from systemd, got {}", count),
+            ));
+        }
+
+        // SAFETY: systemd passes the socket as fd 3 to the process named by
+        // LISTEN_PID, checked above; nothing else owns it
+        let socket = unsafe { Socket::from_raw_fd(SD_LISTEN_FDS_START) };
+        socket.set_nonblocking(true)?;
+        let local = socket.local_addr()?;
+        if local.is_unix() {
+            let path = local
+                .as_pathname()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            let listener = UnixListener::from_std(socket.into())?;
+            Ok(Self::Unix { listener, path })
+        } else {
+            Ok(Self::Tcp(TcpListener::from_std(socket.into())?))
+        }
+    }
+
+    /// Serve `app` until the listener fails
+    pub async fn serve(self, app: Router) -> io::Result<()> {
+        match self {
+            Self::Tcp(listener) => axum::serve(listener, app).await,
+            Self::Unix { listener, .. } => axum::serve(listener, app).await,
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "{}", addr),
+                Err(_) => write!(f, "tcp"),
+            },
+            Self::Unix { path, .. } => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(path),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn socket_config(path: &Path) -> ServerConfig {
+        ServerConfig {
+            listen: format!("unix:{}", path.display()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_on_unix_socket() {
+        let path = std::env::temp_dir().join(format!("mtchat-{}.sock", uuid::Uuid::new_v4()));
+        // A stale socket from a previous run is replaced
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let listener = Listener::bind(&socket_config(&path)).await.unwrap();
+        assert_eq!(listener.to_string(), format!("unix:{}", path.display()));
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        let app = Router::new().route("/health", get(|| async { "OK" }));
+        tokio::spawn(listener.serve(app));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.ends_with("OK"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refuses_to_replace_regular_file() {
+        let path = std::env::temp_dir().join(format!("mtchat-{}.sock", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "data").unwrap();
+
+        let err = Listener::bind(&socket_config(&path)).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_systemd_requires_sockets_for_this_process() {
+        let pid = std::process::id().to_string();
+        let other = (std::process::id() + 1).to_string();
+
+        let err = Listener::from_systemd(None, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = Listener::from_systemd(Some(&other), Some("1")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = Listener::from_systemd(Some(&pid), Some("0")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = Listener::from_systemd(Some(&pid), Some("2")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
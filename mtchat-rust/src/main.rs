@@ -2,27 +2,14 @@
 //!
 //! Object-bound chat service with direct and potential participants.
 
-use axum::{
-    middleware as axum_middleware,
-    routing::{delete, get, post, put},
-    Router,
-};
-use multitenancy_chat_api::config::{CorsConfig, DatabaseConfig, JwtConfig, RateLimitConfig};
-use sqlx::postgres::PgPoolOptions;
-use std::{env, sync::Arc};
+use clap::Parser;
+use multitenancy_chat_api::cli;
+use multitenancy_chat_api::config::{AppConfig, CliArgs, Command, LogFormat};
+use multitenancy_chat_api::listener::Listener;
+use std::sync::Arc;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use apalis_redis::RedisStorage;
-use fred::prelude::*;
-use fred::types::Builder;
-use multitenancy_chat_api::api::{self, AppState};
-use multitenancy_chat_api::jobs::{
-    start_workers, JobContext, JobProducer, NotificationJob, WorkerConfig,
-};
-use multitenancy_chat_api::middleware;
-use multitenancy_chat_api::services::{PresenceService, S3Config, S3Service};
-use multitenancy_chat_api::webhooks::{WebhookConfig, WebhookSender};
-
 #[tokio::main]
 async fn main() {
     rustls::crypto::ring::default_provider()
@@ -30,275 +17,82 @@ async fn main() {
         .expect("Failed to install rustls crypto provider");
 
     dotenvy::dotenv().ok();
+    let cli = CliArgs::parse();
+
+    // Load and validate configuration (defaults < TOML file < env < CLI flags)
+    let config = match AppConfig::load(&cli) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if cli.print_config {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&config.redacted()).expect("config serializes")
+        );
+        return;
+    }
 
+    // Request spans carry `request_id`; JSON output puts it in a field.
+    // Commands print their results on stdout and log to stderr.
+    let json_logs = config.server.log_format == LogFormat::Json;
+    let serving = matches!(cli.command, None | Some(Command::Serve));
+    let writer = move || -> BoxMakeWriter {
+        if serving {
+            BoxMakeWriter::new(std::io::stdout)
+        } else {
+            BoxMakeWriter::new(std::io::stderr)
+        }
+    };
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "multitenancy_chat_api=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with((!json_logs).then(|| tracing_subscriber::fmt::layer().with_writer(writer())))
+        .with(json_logs.then(|| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_writer(writer())
+        }))
         .init();
 
-    // Initialize admin token (read once, constant-time comparison)
-    middleware::init_admin_token();
-
-    // Initialize JWT config for Chat API authentication
-    JwtConfig::init();
-
-    // Initialize database connection pool
-    let db_config = DatabaseConfig::from_env();
-    tracing::info!(
-        "Connecting to database (pool: {}-{} connections)...",
-        db_config.min_connections,
-        db_config.max_connections
-    );
-    let db = PgPoolOptions::new()
-        .max_connections(db_config.max_connections)
-        .min_connections(db_config.min_connections)
-        .acquire_timeout(db_config.acquire_timeout)
-        .idle_timeout(Some(db_config.idle_timeout))
-        .max_lifetime(Some(db_config.max_lifetime))
-        .connect(&db_config.url)
-        .await
-        .expect("Failed to connect to database");
-
-    tracing::info!("Running migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&db)
-        .await
-        .expect("Failed to run migrations");
-
-    // Initialize webhook sender
-    let webhooks = match (env::var("WEBHOOK_URL"), env::var("WEBHOOK_SECRET")) {
-        (Ok(url), Ok(secret)) => {
-            tracing::info!("Webhooks enabled, sending to: {}", url);
-            WebhookSender::new(WebhookConfig::new(url, secret))
-        }
-        _ => {
-            tracing::info!("Webhooks disabled (WEBHOOK_URL or WEBHOOK_SECRET not set)");
-            WebhookSender::noop()
+    match cli.command {
+        None | Some(Command::Serve) => {}
+        Some(command) => {
+            if let Err(e) = cli::run(command, &config).await {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
         }
-    };
+    }
 
-    // Initialize S3 service
-    let s3 = match S3Config::from_env() {
-        Ok(config) => {
-            tracing::info!("S3 enabled, bucket: {}", config.bucket);
-            S3Service::new(config).await
-        }
+    let (app, state) = match multitenancy_chat_api::build_router(config.clone()).await {
+        Ok(built) => built,
         Err(e) => {
-            tracing::warn!("S3 disabled: {}", e);
-            S3Service::noop()
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
     };
+    if let Err(e) = multitenancy_chat_api::spawn_background(&state).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
-    // Initialize Redis, presence service, and job queue
-    let (presence, jobs, redis_pool) = match env::var("REDIS_URL") {
-        Ok(url) => {
-            tracing::info!("Connecting to Redis...");
-            let config = Config::from_url(&url).expect("Failed to parse REDIS_URL");
-            let pool = Builder::from_config(config)
-                // Enable TCP keepalive so the OS probes idle connections and
-                // detects silently-dropped sockets (NAT/firewall idle timeout)
-                // before they are handed out from the pool and hang on the next
-                // command. Without this, an idle-killed connection stays "alive"
-                // to the app and the first mget after a lull blocks forever.
-                .with_connection_config(|c| {
-                    c.tcp = fred::types::config::TcpConfig {
-                        keepalive: Some(
-                            socket2::TcpKeepalive::new()
-                                .with_time(std::time::Duration::from_secs(60))
-                                .with_interval(std::time::Duration::from_secs(10)),
-                        ),
-                        ..Default::default()
-                    };
-                })
-                .build_pool(5)
-                .expect("Failed to create Redis pool");
-            pool.init().await.expect("Failed to connect to Redis");
-            tracing::info!("Redis connected, presence tracking enabled");
-
-            let redis_pool = Arc::new(pool);
-
-            // Initialize job producer
-            let worker_config = WorkerConfig::from_env();
-            let apalis_conn = apalis_redis::connect(url.clone())
-                .await
-                .expect("Failed to connect to Redis for job queue");
-            let notification_storage: RedisStorage<NotificationJob> = RedisStorage::new_with_config(
-                apalis_conn,
-                apalis_redis::Config::default()
-                    .set_poll_interval(std::time::Duration::from_millis(200)),
-            );
-
-            let jobs = JobProducer::new(notification_storage.clone());
-
-            tracing::info!("Job queue enabled");
-
-            (
-                PresenceService::new(redis_pool.clone()),
-                jobs,
-                Some((redis_pool, notification_storage, worker_config)),
-            )
-        }
-        Err(_) => {
-            tracing::info!(
-                "Redis disabled (REDIS_URL not set), presence tracking and job queue disabled"
-            );
-            (PresenceService::noop(), JobProducer::noop(), None)
+    let listener = match Listener::bind(&config.server).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {:?}: {}", config.server.listen, e);
+            std::process::exit(1);
         }
     };
 
-    let state = AppState::new(db.clone(), webhooks.clone(), s3, presence, jobs);
-
-    let cors_config = CorsConfig::from_env();
-    tracing::info!(
-        "CORS configured: origins={}, methods={}, credentials={}",
-        cors_config.allowed_origins,
-        cors_config.allowed_methods,
-        cors_config.allow_credentials
-    );
-    let cors = cors_config.into_layer();
-
-    // Initialize rate limiting
-    let rate_limit_config = RateLimitConfig::from_env();
-    let rate_limiter = rate_limit_config.create_limiter();
-    if rate_limiter.is_some() {
-        tracing::info!(
-            "Rate limiting enabled: {} req/s, burst {}",
-            rate_limit_config.requests_per_second,
-            rate_limit_config.burst_size
-        );
-    } else {
-        tracing::info!("Rate limiting disabled");
-    }
-
-    // Management API routes (with admin auth middleware)
-    let management_routes = Router::new()
-        .route("/dialogs", post(api::management::management_create_dialog))
-        .route(
-            "/dialogs/search",
-            post(api::management::management_find_dialog),
-        )
-        .route(
-            "/dialogs/{id}",
-            get(api::management::management_get_dialog)
-                .delete(api::management::management_delete_dialog),
-        )
-        .route(
-            "/dialogs/{id}/participants",
-            post(api::management::management_add_participant),
-        )
-        .route(
-            "/dialogs/{id}/participants/{user_id}",
-            delete(api::management::management_remove_participant),
-        )
-        .route(
-            "/dialogs/{id}/access-scopes",
-            put(api::management::management_update_access_scopes),
-        )
-        .layer(axum_middleware::from_fn(middleware::admin_auth::admin_auth));
-
-    // Chat API routes (with optional JWT middleware)
-    let chat_routes = Router::new()
-        // Dialogs
-        .route("/dialogs", get(api::dialogs::list_dialogs))
-        .route("/dialogs/{id}", get(api::dialogs::get_dialog))
-        .route(
-            "/dialogs/by-object/{object_type}/{object_id}",
-            get(api::dialogs::get_dialog_by_object),
-        )
-        .route(
-            "/dialogs/by-object/{object_type}/{object_id}/list",
-            get(api::dialogs::list_dialogs_by_object),
-        )
-        .route("/dialogs/{id}/join", post(api::dialogs::join_dialog))
-        .route("/dialogs/{id}/leave", post(api::dialogs::leave_dialog))
-        .route("/dialogs/{id}/archive", post(api::dialogs::archive_dialog))
-        .route(
-            "/dialogs/{id}/unarchive",
-            post(api::dialogs::unarchive_dialog),
-        )
-        .route("/dialogs/{id}/pin", post(api::dialogs::pin_dialog))
-        .route("/dialogs/{id}/unpin", post(api::dialogs::unpin_dialog))
-        .route(
-            "/dialogs/{id}/notifications",
-            post(api::dialogs::set_dialog_notifications),
-        )
-        .route("/dialogs/{id}/read", post(api::participants::mark_as_read))
-        .route(
-            "/dialogs/{id}/participants",
-            get(api::participants::list_participants),
-        )
-        // Messages
-        .route(
-            "/dialogs/{dialog_id}/messages",
-            get(api::messages::list_messages).post(api::messages::send_message),
-        )
-        .route(
-            "/dialogs/{dialog_id}/messages/{id}",
-            get(api::messages::get_message)
-                .put(api::messages::edit_message)
-                .delete(api::messages::delete_message),
-        )
-        // Upload API
-        .route("/upload/presign", post(api::upload::presign_upload))
-        .route(
-            "/attachments/{id}/url",
-            get(api::upload::get_attachment_url),
-        )
-        // Apply JWT middleware to all Chat API routes (when enabled)
-        .layer(axum_middleware::from_fn(middleware::jwt_auth::jwt_auth));
-
-    let app = Router::new()
-        // Health
-        .route("/health", get(api::health::health))
-        .route("/health/ready", get(api::health::health_ready))
-        // Management API (admin auth)
-        .nest("/api/v1/management", management_routes)
-        // Chat API (JWT auth when enabled)
-        .nest("/api/v1", chat_routes)
-        // WebSocket (JWT validated in handler)
-        .route("/api/v1/ws", get(api::ws_handler::ws_handler))
-        .layer(axum_middleware::from_fn(move |req, next| {
-            middleware::rate_limit(req, next, rate_limiter.clone())
-        }))
-        .layer(cors)
-        .with_state(state.clone());
-
-    // Start job workers if Redis is configured
-    if let Some((redis_pool, notification_storage, worker_config)) = redis_pool {
-        let job_ctx = JobContext {
-            db: db.clone(),
-            redis: redis_pool.clone(),
-            dialogs: state.dialogs.clone(),
-            participants: state.participants.clone(),
-            messages: state.messages.clone(),
-            webhooks: webhooks.clone(),
-            connections: state.connections.clone(),
-            archive_after_secs: worker_config.archive_after_secs,
-        };
-
-        let monitor = start_workers(notification_storage, redis_pool, job_ctx, worker_config)
-            .await
-            .expect("Failed to start job workers");
-
-        tokio::spawn(async move {
-            tracing::info!("Job workers started");
-            if let Err(e) = monitor.run().await {
-                tracing::error!("Job workers error: {}", e);
-            }
-        });
-    }
-
-    let port: u16 = env::var("PORT")
-        .unwrap_or_else(|_| "8080".into())
-        .parse()
-        .unwrap();
-    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
-
-    tracing::info!("Starting server on {}", addr);
+    tracing::info!("Starting server on {}", listener);
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    listener.serve(app).await.unwrap();
 }
@@ -9,30 +9,36 @@
 
 use axum::{
     extract::Request,
-    http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
-    Json,
 };
-use serde::Serialize;
+
+use crate::api::{ApiError, AuthChallenge};
 use sha2::{Digest, Sha256};
 use std::sync::OnceLock;
 
-/// Cached admin token digest, computed once at startup.
-/// None = no token configured (dev mode, all requests allowed).
-static ADMIN_TOKEN_DIGEST: OnceLock<Option<[u8; 32]>> = OnceLock::new();
-
-/// Initialize the admin token from environment.
-/// Must be called once during server startup.
-pub fn init_admin_token() {
-    ADMIN_TOKEN_DIGEST.get_or_init(|| match std::env::var("ADMIN_API_TOKEN") {
-        Ok(token) if !token.is_empty() => {
-            tracing::info!("Admin API token configured");
-            Some(sha256_digest(token.as_bytes()))
+/// Cached digests of the accepted admin tokens, computed once at startup.
+/// Empty = no token configured (dev mode, all requests allowed).
+static ADMIN_TOKEN_DIGESTS: OnceLock<Vec<[u8; 32]>> = OnceLock::new();
+
+/// Initialize the admin token (`ADMIN_API_TOKEN` / `admin.api_token`) and
+/// the previous one still accepted during a rotation
+/// (`ADMIN_API_TOKEN_PREVIOUS`). Must be called once during server startup.
+pub fn init_admin_token(token: Option<&str>, previous: Option<&str>) {
+    ADMIN_TOKEN_DIGESTS.get_or_init(|| match token {
+        Some(token) if !token.is_empty() => {
+            let mut digests = vec![sha256_digest(token.as_bytes())];
+            if let Some(previous) = previous.filter(|p| !p.is_empty()) {
+                tracing::info!("Admin API token configured, previous token still accepted");
+                digests.push(sha256_digest(previous.as_bytes()));
+            } else {
+                tracing::info!("Admin API token configured");
+            }
+            digests
         }
         _ => {
             tracing::warn!("ADMIN_API_TOKEN not set — Management API is unprotected (dev mode)");
-            None
+            Vec::new()
         }
     });
 }
@@ -47,56 +53,28 @@ fn sha256_digest(data: &[u8]) -> [u8; 32] {
 /// Comparing fixed-length hash digests avoids timing side-channels
 /// that exist in variable-length string comparison.
 fn verify_token(provided: &str) -> bool {
-    let expected = match ADMIN_TOKEN_DIGEST.get() {
-        Some(Some(digest)) => digest,
-        _ => return false,
+    let Some(expected) = ADMIN_TOKEN_DIGESTS.get() else {
+        return false;
     };
     let provided_digest = sha256_digest(provided.as_bytes());
     // Fixed-length array comparison — compiler emits constant-time code
-    // for [u8; 32] equality (no early exit on mismatch).
-    *expected == provided_digest
-}
-
-/// Error response for auth failures
-#[derive(Debug, Serialize)]
-struct AuthError {
-    error: AuthErrorBody,
-}
-
-#[derive(Debug, Serialize)]
-struct AuthErrorBody {
-    code: String,
-    message: String,
-}
-
-impl AuthError {
-    fn unauthorized(message: impl Into<String>) -> Self {
-        Self {
-            error: AuthErrorBody {
-                code: "UNAUTHORIZED".to_string(),
-                message: message.into(),
-            },
-        }
-    }
-
-    fn forbidden(message: impl Into<String>) -> Self {
-        Self {
-            error: AuthErrorBody {
-                code: "FORBIDDEN".to_string(),
-                message: message.into(),
-            },
-        }
-    }
+    // for [u8; 32] equality (no early exit on mismatch). Every accepted
+    // digest is compared.
+    expected.iter().fold(false, |matched, digest| {
+        matched | (*digest == provided_digest)
+    })
 }
 
 /// Admin authentication middleware
 ///
 /// Checks for valid admin token in Authorization header.
 /// Token is configured via `ADMIN_API_TOKEN` environment variable.
+/// A missing, malformed or unknown token gets 401 with a `WWW-Authenticate`
+/// challenge.
 ///
 /// If no token is configured, all requests are allowed (development mode).
 pub async fn admin_auth(request: Request, next: Next) -> Response {
-    let token_configured = matches!(ADMIN_TOKEN_DIGEST.get(), Some(Some(_)));
+    let token_configured = ADMIN_TOKEN_DIGESTS.get().is_some_and(|d| !d.is_empty());
 
     // If no admin token configured, allow all (dev mode)
     if !token_configured {
@@ -110,31 +88,24 @@ pub async fn admin_auth(request: Request, next: Next) -> Response {
         .and_then(|v| v.to_str().ok());
 
     match auth_header {
-        None => (
-            StatusCode::UNAUTHORIZED,
-            Json(AuthError::unauthorized("Authorization header required")),
-        )
+        None => ApiError::unauthorized(AuthChallenge::MANAGEMENT, "Authorization header required")
             .into_response(),
         Some(header) => {
             // Expect "Bearer <token>" format
-            if !header.starts_with("Bearer ") {
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(AuthError::unauthorized(
-                        "Invalid authorization format. Use: Bearer <token>",
-                    )),
+            let Some(token) = header.strip_prefix("Bearer ") else {
+                return ApiError::unauthorized(
+                    AuthChallenge::MANAGEMENT,
+                    "Invalid authorization format. Use: Bearer <token>",
                 )
-                    .into_response();
-            }
-
-            let token = &header[7..]; // Skip "Bearer "
+                .into_response();
+            };
 
             if !verify_token(token) {
-                return (
-                    StatusCode::FORBIDDEN,
-                    Json(AuthError::forbidden("Invalid admin token")),
+                return ApiError::unauthorized(
+                    AuthChallenge::MANAGEMENT.invalid(),
+                    "Invalid admin token",
                 )
-                    .into_response();
+                .into_response();
             }
 
             // Token valid, proceed
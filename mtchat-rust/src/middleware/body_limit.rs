@@ -0,0 +1,154 @@
+//! Request body size limits
+//!
+//! Bodies are rejected up front when `Content-Length` exceeds the route's
+//! limit, and streamed bodies are cut off once they go past it. Either way
+//! the client gets a structured 413 `PAYLOAD_TOO_LARGE` error instead of the
+//! plain-text rejection of axum's extractors.
+//!
+//! Chat API routes are limited to what the largest valid message can take
+//! (see [`chat_body_limit`]); Management API routes get the larger
+//! `body_limits.management_bytes` for bulk endpoints.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::Limited;
+
+use crate::api::{ApiError, ErrorCode};
+use crate::domain::attachment_limits::MAX_ATTACHMENTS_PER_MESSAGE;
+use crate::domain::MAX_CONTENT_BLOCKS_BYTES;
+
+/// Worst-case JSON-encoded size of one content byte (`\u0000` escapes)
+const JSON_BYTES_PER_CONTENT_BYTE: usize = 6;
+
+/// Allowance per attachment entry (key, filename, content type, size)
+const ATTACHMENT_ENTRY_BYTES: usize = 2 * 1024;
+
+/// Allowance for the rest of a message request (`reply_to`, field names)
+const MESSAGE_ENVELOPE_BYTES: usize = 4 * 1024;
+
+/// Smallest Chat API limit, however short messages are configured
+pub const MIN_CHAT_BODY_LIMIT: usize = 64 * 1024;
+
+/// Chat API body limit for a `max_message_length` setting: the content fully
+/// escaped plus the maximum number of attachments and content blocks
+pub fn chat_body_limit(max_message_length: usize) -> usize {
+    max_message_length
+        .saturating_mul(JSON_BYTES_PER_CONTENT_BYTE)
+        .saturating_add(MAX_ATTACHMENTS_PER_MESSAGE * ATTACHMENT_ENTRY_BYTES)
+        .saturating_add(MAX_CONTENT_BLOCKS_BYTES)
+        .saturating_add(MESSAGE_ENVELOPE_BYTES)
+        .max(MIN_CHAT_BODY_LIMIT)
+}
+
+fn payload_too_large(limit: usize) -> Response {
+    ApiError::new(
+        ErrorCode::PayloadTooLarge,
+        format!("Request body exceeds the limit of {} bytes", limit),
+    )
+    .into_response()
+}
+
+/// Enforce a body size limit on the request
+///
+/// Routes using it should disable axum's `DefaultBodyLimit` so this limit is
+/// the only one applied.
+pub async fn body_limit(request: Request, next: Next, limit: usize) -> Response {
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if content_length.is_some_and(|len| len > limit as u64) {
+        tracing::warn!(content_length, limit, "Request body too large");
+        return payload_too_large(limit);
+    }
+
+    let request = request.map(|body| Body::new(Limited::new(body, limit)));
+    let response = next.run(request).await;
+
+    // Extractors reject a body cut off by `Limited` with a plain-text 413
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        tracing::warn!(limit, "Streamed request body too large");
+        return payload_too_large(limit);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, middleware::from_fn, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn app(limit: usize) -> Router {
+        Router::new()
+            .route(
+                "/",
+                post(|body: Bytes| async move { body.len().to_string() }),
+            )
+            .layer(from_fn(move |req, next| body_limit(req, next, limit)))
+            .layer(axum::extract::DefaultBodyLimit::disable())
+    }
+
+    async fn error_code(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        json["error"]["code"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_chat_body_limit_covers_escaped_content_and_attachments() {
+        let limit = chat_body_limit(50_000);
+        assert!(limit >= 50_000 * 6 + MAX_ATTACHMENTS_PER_MESSAGE * 1024);
+        assert_eq!(chat_body_limit(10), MIN_CHAT_BODY_LIMIT);
+        assert_eq!(chat_body_limit(usize::MAX), usize::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit_passes() {
+        let response = app(16)
+            .oneshot(Request::post("/").body(Body::from("hello")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_content_length_over_limit_rejected() {
+        let response = app(16)
+            .oneshot(
+                Request::post("/")
+                    .header(header::CONTENT_LENGTH, "17")
+                    .body(Body::from("x".repeat(17)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error_code(response).await, "PAYLOAD_TOO_LARGE");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_over_limit_rejected() {
+        let chunks = futures::stream::iter(
+            ["0123456789", "0123456789"].map(|c| Ok::<_, std::io::Error>(Bytes::from(c))),
+        );
+        let response = app(16)
+            .oneshot(Request::post("/").body(Body::from_stream(chunks)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error_code(response).await, "PAYLOAD_TOO_LARGE");
+    }
+}
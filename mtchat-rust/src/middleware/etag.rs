@@ -0,0 +1,148 @@
+//! Conditional GET support
+//!
+//! Adds a weak `ETag` to successful responses of polled endpoints and
+//! answers `304 Not Modified` when the client's `If-None-Match` still matches.
+//! The tag is a digest of the response body, so it changes with anything the
+//! response reflects (`updated_at` of dialogs, messages and participants,
+//! last message, unread counts) and is per-user like the response itself.
+//! Responses with presigned attachment URLs change on every request and are
+//! left without an ETag.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Responses may be cached by the client only, and must be revalidated
+const CACHE_CONTROL: &str = "private, no-cache";
+
+/// Weak ETag of a response body
+fn weak_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether an `If-None-Match` header matches the ETag (weak comparison)
+fn if_none_match_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// Tag GET responses and answer 304 when the client's copy is current
+pub async fn etag(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let request_headers = request.headers().clone();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response body for ETag: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = weak_etag(&bytes);
+    let etag_value = HeaderValue::from_str(&etag).expect("ETag is a valid header value");
+    let cache_control = HeaderValue::from_static(CACHE_CONTROL);
+
+    if if_none_match_matches(&request_headers, &etag) {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, etag_value),
+                (header::CACHE_CONTROL, cache_control),
+            ],
+        )
+            .into_response();
+    }
+
+    parts.headers.insert(header::ETAG, etag_value);
+    parts.headers.insert(header::CACHE_CONTROL, cache_control);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { "[1,2,3]" }))
+            .layer(from_fn(etag))
+    }
+
+    async fn get_with(if_none_match: Option<&str>) -> Response {
+        let mut request = Request::get("/");
+        if let Some(tag) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, tag);
+        }
+        app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_weak_etag_depends_on_body() {
+        assert!(weak_etag(b"a").starts_with("W/\""));
+        assert_eq!(weak_etag(b"a"), weak_etag(b"a"));
+        assert_ne!(weak_etag(b"a"), weak_etag(b"b"));
+    }
+
+    #[test]
+    fn test_if_none_match_comparison() {
+        let etag = weak_etag(b"a");
+        let strong = etag.trim_start_matches("W/").to_string();
+        let mut headers = HeaderMap::new();
+        assert!(!if_none_match_matches(&headers, &etag));
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", {}", strong)).unwrap(),
+        );
+        assert!(if_none_match_matches(&headers, &etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(if_none_match_matches(&headers, &etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("W/\"x\""));
+        assert!(!if_none_match_matches(&headers, &etag));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_response_returns_304() {
+        let first = get_with(None).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(first.headers()[header::CACHE_CONTROL], CACHE_CONTROL);
+
+        let second = get_with(Some(&etag)).await;
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[header::ETAG], etag.as_str());
+        let body = to_bytes(second.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        let stale = get_with(Some("W/\"stale\"")).await;
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+}
@@ -6,7 +6,7 @@
 
 use axum::{
     extract::{FromRequestParts, Request},
-    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap},
     middleware::Next,
     response::{IntoResponse, Response},
 };
@@ -14,6 +14,7 @@ use jsonwebtoken::decode;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
+use crate::api::{ApiError, AuthChallenge};
 use crate::config::JwtConfig;
 
 /// JWT claims as a flexible map.
@@ -43,7 +44,8 @@ impl JwtClaims {
 /// If JWT auth is enabled:
 /// - Validates the Bearer token from Authorization header
 /// - Verifies the configured user-id claim is present and is a string/number
-/// - Returns 401 if token is missing, invalid, or claim is absent
+/// - Returns 401 with a `WWW-Authenticate` challenge if the token is
+///   missing, invalid, or the claim is absent
 ///
 /// If JWT auth is disabled:
 /// - Passes request through without validation
@@ -54,35 +56,9 @@ pub async fn jwt_auth(request: Request, next: Next) -> Response {
         None => return next.run(request).await,
     };
 
-    // Extract token from Authorization header
-    let auth_header = request
-        .headers()
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok());
-
-    let token = match auth_header {
-        Some(h) if h.starts_with("Bearer ") => &h[7..],
-        _ => {
-            return (StatusCode::UNAUTHORIZED, "Authorization header required").into_response();
-        }
-    };
-
-    // Validate signature and ensure the configured user-id claim exists
-    match decode::<JwtClaims>(token, &config.decoding_key, &config.validation) {
-        Ok(data) => {
-            if data.claims.user_id(&config.user_id_claim).is_none() {
-                tracing::debug!(
-                    "JWT validation failed: missing or non-string claim '{}'",
-                    config.user_id_claim
-                );
-                return (StatusCode::UNAUTHORIZED, "Invalid token").into_response();
-            }
-            next.run(request).await
-        }
-        Err(e) => {
-            tracing::debug!("JWT validation failed: {}", e);
-            (StatusCode::UNAUTHORIZED, "Invalid token").into_response()
-        }
+    match bearer_user_id(request.headers(), config) {
+        Ok(_) => next.run(request).await,
+        Err(e) => e.into_response(),
     }
 }
 
@@ -93,51 +69,61 @@ pub async fn jwt_auth(request: Request, next: Next) -> Response {
 ///
 /// When JWT auth is disabled:
 /// - Falls back to `user_id` or `sender_id` query parameter
+///
+/// A missing or invalid identity is rejected with 401.
 pub struct JwtUserId(pub String);
 
-#[allow(clippy::result_large_err)]
 impl<S> FromRequestParts<S> for JwtUserId
 where
     S: Send + Sync,
 {
-    type Rejection = Response;
+    type Rejection = ApiError;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        // If JWT auth disabled - fallback to query parameter
-        let config = match JwtConfig::get() {
-            Some(c) => c,
-            None => return extract_user_id_from_query(parts),
-        };
-
-        // Extract token from Authorization header
-        let auth_header = parts
-            .headers
-            .get(AUTHORIZATION)
-            .and_then(|v| v.to_str().ok());
-
-        let token = match auth_header {
-            Some(h) if h.starts_with("Bearer ") => &h[7..],
-            _ => {
-                return Err((StatusCode::UNAUTHORIZED, "Missing token").into_response());
-            }
-        };
-
-        // Decode and extract user_id from the configured claim
-        match decode::<JwtClaims>(token, &config.decoding_key, &config.validation) {
-            Ok(data) => match data.claims.user_id(&config.user_id_claim) {
-                Some(id) => Ok(JwtUserId(id)),
-                None => Err((StatusCode::UNAUTHORIZED, "Invalid token").into_response()),
-            },
-            Err(_) => Err((StatusCode::UNAUTHORIZED, "Invalid token").into_response()),
-        }
+        request_user_id(parts).map(JwtUserId)
     }
 }
 
-/// Extract user_id from query parameters (fallback when JWT disabled)
-#[allow(clippy::result_large_err)]
-fn extract_user_id_from_query(parts: &Parts) -> Result<JwtUserId, Response> {
-    let query = parts.uri.query().unwrap_or("");
+/// Caller's user ID: the configured claim of the Bearer token when JWT auth
+/// is enabled, otherwise the `user_id` or `sender_id` query parameter
+pub(crate) fn request_user_id(parts: &Parts) -> Result<String, ApiError> {
+    match JwtConfig::get() {
+        Some(config) => bearer_user_id(&parts.headers, config),
+        None => query_user_id(parts.uri.query().unwrap_or("")),
+    }
+}
 
+fn bearer_user_id(headers: &HeaderMap, config: &JwtConfig) -> Result<String, ApiError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            ApiError::unauthorized(AuthChallenge::CHAT, "Authorization header required")
+        })?;
+    token_user_id(token, config)
+}
+
+/// Validate a token and read the user ID from the configured claim (also
+/// for WebSocket handshakes, which pass the token as a query parameter)
+pub(crate) fn token_user_id(token: &str, config: &JwtConfig) -> Result<String, ApiError> {
+    let invalid = || ApiError::unauthorized(AuthChallenge::CHAT.invalid(), "Invalid token");
+    let data =
+        decode::<JwtClaims>(token, &config.decoding_key, &config.validation).map_err(|e| {
+            tracing::debug!("JWT validation failed: {}", e);
+            invalid()
+        })?;
+    data.claims.user_id(&config.user_id_claim).ok_or_else(|| {
+        tracing::debug!(
+            "JWT validation failed: missing or non-string claim '{}'",
+            config.user_id_claim
+        );
+        invalid()
+    })
+}
+
+/// Extract user_id from query parameters (fallback when JWT disabled)
+fn query_user_id(query: &str) -> Result<String, ApiError> {
     for pair in query.split('&') {
         let mut kv = pair.splitn(2, '=');
         if let (Some(key), Some(value)) = (kv.next(), kv.next()) {
@@ -145,16 +131,20 @@ fn extract_user_id_from_query(parts: &Parts) -> Result<JwtUserId, Response> {
                 // URL decode the value
                 let decoded = urlencoding::decode(value).unwrap_or_else(|_| value.into());
                 if decoded.is_empty() {
-                    return Err(
-                        (StatusCode::BAD_REQUEST, "user_id cannot be empty").into_response()
-                    );
+                    return Err(ApiError::unauthorized(
+                        AuthChallenge::CHAT,
+                        "user_id cannot be empty",
+                    ));
                 }
-                return Ok(JwtUserId(decoded.into_owned()));
+                return Ok(decoded.into_owned());
             }
         }
     }
 
-    Err((StatusCode::BAD_REQUEST, "user_id query parameter required").into_response())
+    Err(ApiError::unauthorized(
+        AuthChallenge::CHAT,
+        "user_id query parameter required",
+    ))
 }
 
 #[cfg(test)]
@@ -281,4 +271,20 @@ mod tests {
 
         assert!(decoded.claims.user_id("user_id").is_none());
     }
+
+    #[test]
+    fn test_query_user_id() {
+        assert_eq!(
+            query_user_id("type=a&user_id=u%201").ok().as_deref(),
+            Some("u 1")
+        );
+        assert_eq!(query_user_id("sender_id=u2").ok().as_deref(), Some("u2"));
+        for query in ["type=a", "user_id="] {
+            let response = query_user_id(query).unwrap_err().into_response();
+            assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+            assert!(response
+                .headers()
+                .contains_key(axum::http::header::WWW_AUTHENTICATE));
+        }
+    }
 }
@@ -1,11 +1,22 @@
 //! Middleware for authentication and authorization
 
 pub mod admin_auth;
+pub mod body_limit;
+pub mod etag;
 pub mod jwt_auth;
 pub mod rate_limit;
+pub mod request_id;
 pub mod scope_config;
+pub mod tenant_context;
 
 pub use admin_auth::init_admin_token;
+pub use body_limit::{body_limit, chat_body_limit};
+pub use etag::etag;
 pub use jwt_auth::{jwt_auth, JwtClaims, JwtUserId};
 pub use rate_limit::{rate_limit, SharedRateLimiter};
+pub use request_id::{current_request_id, request_id, with_request_id, REQUEST_ID_HEADER};
 pub use scope_config::{OptionalScopeConfig, ScopeConfig, UserId};
+pub use tenant_context::{
+    as_service, begin_transaction, current_db_context, service_context, tenant_context,
+    with_tenant_context, DbContext, ServiceContextLayer, TenantContext,
+};
@@ -19,6 +19,8 @@ use governor::{
 use serde::Serialize;
 use std::sync::Arc;
 
+use super::request_id::current_request_id;
+
 /// Shared rate limiter type
 pub type SharedRateLimiter =
     Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>>;
@@ -33,6 +35,8 @@ struct RateLimitError {
 struct RateLimitErrorBody {
     code: &'static str,
     message: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl RateLimitError {
@@ -41,6 +45,7 @@ impl RateLimitError {
             error: RateLimitErrorBody {
                 code: "RATE_LIMIT_EXCEEDED",
                 message: "Too many requests. Please slow down.",
+                request_id: current_request_id(),
             },
         }
     }
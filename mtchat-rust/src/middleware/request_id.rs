@@ -0,0 +1,113 @@
+//! Request ID middleware
+//!
+//! Every request gets an `X-Request-Id`: a valid incoming value is kept (so
+//! integrators can pass their own correlation ID), otherwise a new UUID is
+//! generated. The ID is echoed in the response header, recorded on the
+//! request's tracing span, included in error bodies and sent with webhooks
+//! triggered by the request.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header carrying the request ID
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest accepted incoming request ID
+pub const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Request ID of the request being handled, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run `future` as part of the request with ID `request_id` (work moved out
+/// of the request task, e.g. event subscribers)
+pub async fn with_request_id<F: std::future::Future>(
+    request_id: Option<String>,
+    future: F,
+) -> F::Output {
+    match request_id {
+        Some(id) => REQUEST_ID.scope(id, future).await,
+        None => future.await,
+    }
+}
+
+/// Incoming IDs are kept only if short and free of characters that could
+/// forge log lines or headers
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Assign the request ID and handle the request inside its span
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::now_v7().to_string());
+
+    let header = HeaderValue::from_str(&id).expect("request ID is a valid header value");
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER.clone(), header.clone());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let mut response = REQUEST_ID
+        .scope(id, next.run(request))
+        .instrument(span)
+        .await;
+    response
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER.clone(), header);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_request_ids() {
+        assert!(is_valid_request_id("0199f1a2-7c3e-7b1a-9c1e-2f3a4b5c6d7e"));
+        assert!(is_valid_request_id("widget:abc_123.4"));
+    }
+
+    #[test]
+    fn test_invalid_request_ids() {
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has space"));
+        assert!(!is_valid_request_id("line\nbreak"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+    }
+
+    #[tokio::test]
+    async fn test_current_request_id_scope() {
+        assert!(current_request_id().is_none());
+        let id = REQUEST_ID
+            .scope("req-1".into(), async { current_request_id() })
+            .await;
+        assert_eq!(id.as_deref(), Some("req-1"));
+    }
+}
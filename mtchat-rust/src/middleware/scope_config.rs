@@ -19,6 +19,10 @@ use axum::{
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 
+use super::jwt_auth::request_user_id;
+use super::request_id::current_request_id;
+use crate::api::ApiError;
+
 /// User scope configuration extracted from X-Scope-Config header
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ScopeConfig {
@@ -41,6 +45,8 @@ struct ScopeError {
 struct ScopeErrorBody {
     code: String,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl ScopeError {
@@ -49,6 +55,7 @@ impl ScopeError {
             error: ScopeErrorBody {
                 code: "BAD_REQUEST".to_string(),
                 message: message.into(),
+                request_id: current_request_id(),
             },
         }
     }
@@ -157,9 +164,11 @@ where
     }
 }
 
-/// User ID extractor from query parameters
+/// Caller's user ID
 ///
-/// Extracts user_id from ?user_id= or ?sender_id= query parameter
+/// Read from the configured claim of the Bearer token when JWT auth is
+/// enabled, otherwise from the ?user_id= or ?sender_id= query parameter.
+/// A missing or invalid identity is rejected with 401.
 #[derive(Debug, Clone)]
 pub struct UserId(pub String);
 
@@ -167,35 +176,10 @@ impl<S> FromRequestParts<S> for UserId
 where
     S: Send + Sync,
 {
-    type Rejection = Response;
+    type Rejection = ApiError;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let query = parts.uri.query().unwrap_or("");
-
-        // Simple query parsing
-        for pair in query.split('&') {
-            let mut kv = pair.splitn(2, '=');
-            if let (Some(key), Some(value)) = (kv.next(), kv.next()) {
-                if key == "user_id" || key == "sender_id" {
-                    // URL decode the value
-                    let decoded = urlencoding::decode(value).unwrap_or_else(|_| value.into());
-                    if decoded.is_empty() {
-                        return Err((
-                            StatusCode::BAD_REQUEST,
-                            Json(ScopeError::bad_request("user_id cannot be empty")),
-                        )
-                            .into_response());
-                    }
-                    return Ok(UserId(decoded.into_owned()));
-                }
-            }
-        }
-
-        Err((
-            StatusCode::BAD_REQUEST,
-            Json(ScopeError::bad_request("user_id query parameter required")),
-        )
-            .into_response())
+        request_user_id(parts).map(UserId)
     }
 }
 
@@ -0,0 +1,221 @@
+//! Database context for row-level security
+//!
+//! With `database.row_level_security` enabled, the Postgres policies on
+//! dialogs and their rows (scopes, participants, messages, attachments, list
+//! entries, reactions) fail closed: a connection sees and writes nothing
+//! unless it carries one of two contexts.
+//!
+//! - Chat API requests run inside a tenant context: the caller's tenants
+//!   (`scope_level0` of `X-Scope-Config`) and user ID, set as the
+//!   `app.tenant` and `app.user_id` settings. A query that skips the access
+//!   check then still can't reach another tenant's rows.
+//! - Service work (management API, jobs, event subscribers, WebSocket
+//!   connections, startup) runs with the explicit bypass, `app.rls_bypass`.
+//!
+//! Transactions begun with [`begin_transaction`] pin the context with
+//! transaction-local settings. Single statements outside a transaction get
+//! it when their connection is checked out; checkouts without a context get
+//! every setting cleared, which the policies deny.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    middleware::Next,
+    response::Response,
+};
+use futures::future::BoxFuture;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use tower::{Layer, Service};
+
+use super::{JwtUserId, OptionalScopeConfig};
+
+/// Tenants and user of the request being handled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenantContext {
+    /// `scope_level0` of the caller (empty = no tenant)
+    pub tenants: Vec<String>,
+    /// Caller's user ID (empty when the request has none)
+    pub user_id: String,
+}
+
+/// Context the database policies are applied with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbContext {
+    /// Chat API request, restricted to the caller's dialogs
+    Tenant(TenantContext),
+    /// Service work, unrestricted
+    Service,
+}
+
+tokio::task_local! {
+    static DB_CONTEXT: DbContext;
+}
+
+/// Set once a pool applies the context, so transactions skip the extra
+/// statement otherwise
+static ROW_LEVEL_SECURITY: AtomicBool = AtomicBool::new(false);
+
+/// Database context of the task, if any
+pub fn current_db_context() -> Option<DbContext> {
+    DB_CONTEXT.try_with(Clone::clone).ok()
+}
+
+/// Run `future` as service work, e.g. writes after the handler has checked
+/// access that end the caller's own access midway (leaving a dialog).
+/// Tasks spawned from it don't inherit the context.
+pub async fn as_service<F: Future>(future: F) -> F::Output {
+    DB_CONTEXT.scope(DbContext::Service, future).await
+}
+
+/// Run the request as service work (management API and other routes
+/// without a tenant context)
+pub async fn service_context(request: Request, next: Next) -> Response {
+    as_service(next.run(request)).await
+}
+
+/// Run the request inside the tenant context of its caller. The user ID is
+/// read like the handlers read it (JWT claim or `user_id` query parameter);
+/// a missing or invalid scope header means no tenants.
+pub async fn tenant_context(request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let user_id = JwtUserId::from_request_parts(&mut parts, &())
+        .await
+        .map(|JwtUserId(id)| id)
+        .unwrap_or_default();
+    let tenants = match OptionalScopeConfig::from_request_parts(&mut parts, &()).await {
+        Ok(OptionalScopeConfig(Some(scope))) => scope.scope_level0,
+        _ => Vec::new(),
+    };
+    let context = DbContext::Tenant(TenantContext { tenants, user_id });
+
+    DB_CONTEXT
+        .scope(context, next.run(Request::from_parts(parts, body)))
+        .await
+}
+
+/// Set the settings the policies read, for the rest of the session or
+/// (`local`) of the current transaction
+pub async fn apply_db_context(conn: &mut PgConnection, local: bool) -> Result<(), sqlx::Error> {
+    let (tenants, user_id, bypass) = match current_db_context() {
+        Some(DbContext::Tenant(context)) => (Some(context.tenants), context.user_id, ""),
+        Some(DbContext::Service) => (None, String::new(), "on"),
+        None => (None, String::new(), ""),
+    };
+    sqlx::query(
+        r#"SELECT set_config('app.tenant', COALESCE($1::text[]::text, ''), $4),
+                  set_config('app.user_id', $2, $4),
+                  set_config('app.rls_bypass', $3, $4)"#,
+    )
+    .bind(tenants)
+    .bind(user_id)
+    .bind(bypass)
+    .bind(local)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Begin a transaction pinned to the task's database context
+pub async fn begin_transaction(
+    pool: &PgPool,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if ROW_LEVEL_SECURITY.load(Ordering::Relaxed) {
+        apply_db_context(&mut tx, true).await?;
+    }
+    Ok(tx)
+}
+
+/// Pool options that apply the database context to the connections checked
+/// out. Also for pools passed to `build_router_with_db`.
+pub fn with_tenant_context(options: PgPoolOptions) -> PgPoolOptions {
+    ROW_LEVEL_SECURITY.store(true, Ordering::Relaxed);
+    options
+        .after_connect(|conn, _| Box::pin(apply_db_context(conn, false)))
+        .before_acquire(|conn, _| {
+            Box::pin(async move {
+                apply_db_context(conn, false).await?;
+                Ok(true)
+            })
+        })
+}
+
+/// Job worker layer running every job as service work
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServiceContextLayer;
+
+impl<S> Layer<S> for ServiceContextLayer {
+    type Service = ServiceContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ServiceContextService { inner }
+    }
+}
+
+/// Service of [`ServiceContextLayer`]
+#[derive(Debug, Clone)]
+pub struct ServiceContextService<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for ServiceContextService<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let fut = DB_CONTEXT.sync_scope(DbContext::Service, || self.inner.call(req));
+        Box::pin(as_service(fut))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_context_is_scoped_to_the_request() {
+        assert_eq!(current_db_context(), None);
+
+        let context = DbContext::Tenant(TenantContext {
+            tenants: vec!["tenant-a".into()],
+            user_id: "user-1".into(),
+        });
+        DB_CONTEXT
+            .scope(context.clone(), async {
+                assert_eq!(current_db_context(), Some(context));
+                as_service(async {
+                    assert_eq!(current_db_context(), Some(DbContext::Service));
+                })
+                .await;
+            })
+            .await;
+
+        // Spawned tasks start without a context, which the policies deny
+        as_service(async {
+            let spawned = tokio::spawn(async { current_db_context() });
+            assert_eq!(spawned.await.unwrap(), None);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_service_context_layer() {
+        let mut service = ServiceContextLayer.layer(tower::service_fn(|_: ()| async {
+            Ok::<_, std::convert::Infallible>(current_db_context())
+        }));
+        assert_eq!(service.call(()).await.unwrap(), Some(DbContext::Service));
+    }
+}
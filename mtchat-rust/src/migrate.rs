@@ -0,0 +1,221 @@
+//! Database migrations
+//!
+//! Migrations run under a PostgreSQL advisory lock, so replicas starting at
+//! the same time apply them once: the others wait, then find nothing left
+//! to do. They run at startup unless `database.run_migrations` is off, in
+//! which case a dedicated `migrate` run (e.g. a deploy job) applies them and
+//! the readiness probe reports the instance down while the schema is behind.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::{PgConnection, PgPool};
+
+/// Advisory lock key held while migrating ("mtchat" in ASCII)
+const MIGRATION_LOCK_KEY: i64 = 0x6d74_6368_6174;
+
+/// How often a waiting instance retries the lock
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Set once the schema was seen up to date; it stays so for the process
+static SCHEMA_CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Migrations embedded in this build
+pub fn migrator() -> Migrator {
+    sqlx::migrate!("./migrations")
+}
+
+/// Applied migrations compared with the ones this build expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaStatus {
+    /// Latest successfully applied version
+    pub applied: Option<i64>,
+    /// Versions this build has that are not applied
+    pub pending: Vec<i64>,
+    /// Applied versions this build doesn't know (a newer build migrated)
+    pub unknown: Vec<i64>,
+}
+
+impl SchemaStatus {
+    /// Every migration of this build is applied
+    pub fn is_current(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.applied {
+            Some(version) => write!(f, "schema at {}", version)?,
+            None => write!(f, "schema not initialized")?,
+        }
+        if !self.pending.is_empty() {
+            write!(f, ", {} pending migration(s)", self.pending.len())?;
+        }
+        if !self.unknown.is_empty() {
+            write!(
+                f,
+                ", {} migration(s) from a newer version",
+                self.unknown.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare the applied migrations with the embedded ones
+pub async fn status(conn: &mut PgConnection) -> Result<SchemaStatus, sqlx::Error> {
+    let initialized: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&mut *conn)
+            .await?;
+    let applied: BTreeSet<i64> = if initialized {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .collect()
+    } else {
+        BTreeSet::new()
+    };
+
+    let migrator = migrator();
+    let expected: BTreeSet<i64> = migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| m.version)
+        .collect();
+
+    Ok(SchemaStatus {
+        applied: applied.last().copied(),
+        pending: expected.difference(&applied).copied().collect(),
+        unknown: applied.difference(&expected).copied().collect(),
+    })
+}
+
+/// Apply pending migrations while holding the migration lock.
+///
+/// Waits for an instance already migrating. Migrations from a newer build
+/// are left alone, so an older replica still starts during a rolling deploy.
+pub async fn run(conn: &mut PgConnection) -> Result<SchemaStatus, MigrateError> {
+    // Migrations may take longer than the statement timeout allows
+    sqlx::query("SET statement_timeout = 0")
+        .execute(&mut *conn)
+        .await?;
+    acquire_lock(conn).await?;
+
+    let result = apply_pending(conn).await;
+
+    let unlocked = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await;
+    let reset = sqlx::query("RESET statement_timeout")
+        .execute(&mut *conn)
+        .await;
+    let status = result?;
+    unlocked?;
+    reset?;
+    Ok(status)
+}
+
+async fn acquire_lock(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let mut waiting = false;
+    loop {
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .fetch_one(&mut *conn)
+            .await?;
+        if locked {
+            return Ok(());
+        }
+        if !waiting {
+            tracing::info!("Waiting for another instance to finish migrating...");
+            waiting = true;
+        }
+        tokio::time::sleep(LOCK_RETRY_INTERVAL).await;
+    }
+}
+
+async fn apply_pending(conn: &mut PgConnection) -> Result<SchemaStatus, MigrateError> {
+    let before = status(conn).await?;
+    if !before.unknown.is_empty() {
+        tracing::warn!(
+            unknown = ?before.unknown,
+            "Database has migrations from a newer version"
+        );
+    }
+    if before.is_current() {
+        tracing::info!("Database schema is up to date ({})", before);
+        SCHEMA_CURRENT.store(true, Ordering::Relaxed);
+        return Ok(before);
+    }
+
+    tracing::info!("Applying {} migration(s)...", before.pending.len());
+    let mut migrator = migrator();
+    // Serialized by the migration lock above
+    migrator.set_locking(false);
+    migrator.set_ignore_missing(true);
+    migrator.run(&mut *conn).await?;
+
+    let after = status(conn).await?;
+    tracing::info!("Database schema migrated ({})", after);
+    SCHEMA_CURRENT.store(after.is_current(), Ordering::Relaxed);
+    Ok(after)
+}
+
+/// Readiness check of the database: reachable, and no migrations of this
+/// build pending
+pub async fn check_current(db: &PgPool) -> Result<(), String> {
+    let mut conn = db.acquire().await.map_err(|e| e.to_string())?;
+    if SCHEMA_CURRENT.load(Ordering::Relaxed) {
+        return sqlx::query("SELECT 1")
+            .execute(&mut *conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+    }
+    let status = status(&mut conn).await.map_err(|e| e.to_string())?;
+    if !status.is_current() {
+        return Err(format!("Database schema is behind: {}", status));
+    }
+    SCHEMA_CURRENT.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_display() {
+        let status = SchemaStatus {
+            applied: Some(20261017000040),
+            pending: vec![20261017000041],
+            unknown: vec![],
+        };
+        assert!(!status.is_current());
+        assert_eq!(
+            status.to_string(),
+            "schema at 20261017000040, 1 pending migration(s)"
+        );
+
+        let status = SchemaStatus {
+            applied: None,
+            pending: vec![],
+            unknown: vec![],
+        };
+        assert!(status.is_current());
+        assert_eq!(status.to_string(), "schema not initialized");
+    }
+
+    #[test]
+    fn test_embedded_migrations_are_ordered() {
+        let versions: Vec<i64> = migrator().iter().map(|m| m.version).collect();
+        assert!(!versions.is_empty());
+        assert!(versions.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -1,9 +1,11 @@
 //! Attachment repository
 
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::domain::Attachment;
+use crate::middleware::begin_transaction;
 
 pub struct AttachmentRepository {
     pool: PgPool,
@@ -40,7 +42,7 @@ impl AttachmentRepository {
         &self,
         attachments: &[Attachment],
     ) -> Result<Vec<Attachment>, sqlx::Error> {
-        let mut tx = self.pool.begin().await?;
+        let mut tx = begin_transaction(&self.pool).await?;
         let mut created = Vec::with_capacity(attachments.len());
 
         for attachment in attachments {
@@ -112,6 +114,67 @@ impl AttachmentRepository {
         .await
     }
 
+    /// Search attachments of the dialogs a user participates in by filename
+    /// or extracted text (case-insensitive substring), newest first. `before` is the ID of the
+    /// last attachment of the previous page.
+    pub async fn search(
+        &self,
+        user_id: &str,
+        query: &str,
+        dialog_id: Option<Uuid>,
+        limit: i64,
+        before: Option<Uuid>,
+    ) -> Result<Vec<Attachment>, sqlx::Error> {
+        sqlx::query_as::<_, Attachment>(
+            r#"SELECT a.* FROM attachments a
+               JOIN messages m ON m.id = a.message_id
+               JOIN dialog_participants dp ON dp.dialog_id = m.dialog_id AND dp.user_id = $1
+               JOIN dialogs d ON d.id = m.dialog_id AND d.deleted_at IS NULL
+               WHERE (a.filename ILIKE '%' || $2 || '%'
+                      OR EXISTS (SELECT 1 FROM attachment_texts t
+                                 WHERE t.attachment_id = a.id
+                                   AND t.content ILIKE '%' || $2 || '%'))
+                 AND ($3::uuid IS NULL OR m.dialog_id = $3)
+                 AND ($5::uuid IS NULL OR a.id < $5)
+               ORDER BY a.id DESC
+               LIMIT $4"#,
+        )
+        .bind(user_id)
+        .bind(query)
+        .bind(dialog_id)
+        .bind(limit)
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Store the extracted text of an attachment (replaces earlier text)
+    pub async fn save_text(
+        &self,
+        attachment_id: Uuid,
+        content: &str,
+    ) -> Result<DateTime<Utc>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"INSERT INTO attachment_texts (attachment_id, content)
+               VALUES ($1, $2)
+               ON CONFLICT (attachment_id)
+               DO UPDATE SET content = EXCLUDED.content, extracted_at = NOW()
+               RETURNING extracted_at"#,
+        )
+        .bind(attachment_id)
+        .bind(content)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Extracted text of an attachment
+    pub async fn find_text(&self, attachment_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT content FROM attachment_texts WHERE attachment_id = $1")
+            .bind(attachment_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
     /// Update attachment with image metadata
     pub async fn update_image_metadata(
         &self,
@@ -134,6 +197,59 @@ impl AttachmentRepository {
         .await
     }
 
+    /// Set the preview thumbnail of an attachment (documents have no dimensions)
+    pub async fn update_thumbnail(
+        &self,
+        id: Uuid,
+        thumbnail_s3_key: &str,
+    ) -> Result<Option<Attachment>, sqlx::Error> {
+        sqlx::query_as::<_, Attachment>(
+            r#"UPDATE attachments
+               SET thumbnail_s3_key = $2
+               WHERE id = $1
+               RETURNING *"#,
+        )
+        .bind(id)
+        .bind(thumbnail_s3_key)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Storage keys (files and thumbnails) of all attachments in a dialog
+    pub async fn list_keys_by_dialog(&self, dialog_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT k.key FROM attachments a
+               INNER JOIN messages m ON m.id = a.message_id
+               CROSS JOIN LATERAL (VALUES (a.s3_key), (a.thumbnail_s3_key)) AS k(key)
+               WHERE m.dialog_id = $1 AND k.key IS NOT NULL"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Storage keys (files and thumbnails) of a message's attachments
+    pub async fn list_keys_by_message(&self, message_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT k.key FROM attachments a
+               CROSS JOIN LATERAL (VALUES (a.s3_key), (a.thumbnail_s3_key)) AS k(key)
+               WHERE a.message_id = $1 AND k.key IS NOT NULL"#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Check if any attachment still references a storage key (as file or thumbnail)
+    pub async fn is_key_referenced(&self, key: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM attachments WHERE s3_key = $1 OR thumbnail_s3_key = $1)",
+        )
+        .bind(key)
+        .fetch_one(&self.pool)
+        .await
+    }
+
     /// Delete attachment
     pub async fn delete(&self, id: Uuid) -> Result<bool, sqlx::Error> {
         let result = sqlx::query("DELETE FROM attachments WHERE id = $1")
@@ -0,0 +1,55 @@
+//! Audit log repository (append-only)
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::AuditEntry;
+
+pub struct AuditLogRepository {
+    pool: PgPool,
+}
+
+impl AuditLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append an entry
+    pub async fn record(&self, entry: &AuditEntry) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO audit_log (id, actor, action, dialog_id, user_id, details, created_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(entry.id)
+        .bind(&entry.actor)
+        .bind(&entry.action)
+        .bind(entry.dialog_id)
+        .bind(&entry.user_id)
+        .bind(&entry.details)
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Entries newest first, optionally filtered by dialog and action
+    pub async fn list(
+        &self,
+        dialog_id: Option<Uuid>,
+        action: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        sqlx::query_as::<_, AuditEntry>(
+            r#"SELECT * FROM audit_log
+               WHERE ($1::UUID IS NULL OR dialog_id = $1)
+                 AND ($2::VARCHAR IS NULL OR action = $2)
+               ORDER BY created_at DESC, id DESC
+               LIMIT $3"#,
+        )
+        .bind(dialog_id)
+        .bind(action)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
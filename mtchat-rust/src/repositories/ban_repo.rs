@@ -0,0 +1,73 @@
+//! Dialog ban repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::DialogBan;
+
+pub struct DialogBanRepository {
+    pool: PgPool,
+}
+
+impl DialogBanRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Ban a user. Banning again replaces the reason and expiry and restarts
+    /// the ban.
+    pub async fn upsert(&self, ban: &DialogBan) -> Result<DialogBan, sqlx::Error> {
+        sqlx::query_as::<_, DialogBan>(
+            r#"INSERT INTO dialog_bans (dialog_id, user_id, reason, banned_at, expires_at)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (dialog_id, user_id) DO UPDATE
+               SET reason = EXCLUDED.reason,
+                   banned_at = EXCLUDED.banned_at,
+                   expires_at = EXCLUDED.expires_at
+               RETURNING *"#,
+        )
+        .bind(ban.dialog_id)
+        .bind(&ban.user_id)
+        .bind(&ban.reason)
+        .bind(ban.banned_at)
+        .bind(ban.expires_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Bans of a dialog in effect, oldest first
+    pub async fn list_active(&self, dialog_id: Uuid) -> Result<Vec<DialogBan>, sqlx::Error> {
+        sqlx::query_as::<_, DialogBan>(
+            r#"SELECT * FROM dialog_bans
+               WHERE dialog_id = $1 AND (expires_at IS NULL OR expires_at > NOW())
+               ORDER BY banned_at, user_id"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Whether the user is banned from the dialog right now
+    pub async fn is_banned(&self, dialog_id: Uuid, user_id: &str) -> Result<bool, sqlx::Error> {
+        let result: Option<(i32,)> = sqlx::query_as(
+            r#"SELECT 1 FROM dialog_bans
+               WHERE dialog_id = $1 AND user_id = $2
+                 AND (expires_at IS NULL OR expires_at > NOW())"#,
+        )
+        .bind(dialog_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(result.is_some())
+    }
+
+    /// Lift a ban. Returns true if the user was banned (expired bans included).
+    pub async fn delete(&self, dialog_id: Uuid, user_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM dialog_bans WHERE dialog_id = $1 AND user_id = $2")
+            .bind(dialog_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
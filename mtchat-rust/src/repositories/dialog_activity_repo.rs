@@ -0,0 +1,70 @@
+//! Dialog activity repository (append-only)
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::DialogActivity;
+use crate::middleware::begin_transaction;
+
+pub struct DialogActivityRepository {
+    pool: PgPool,
+}
+
+impl DialogActivityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append entries
+    pub async fn record(&self, entries: &[DialogActivity]) -> Result<(), sqlx::Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut tx = begin_transaction(&self.pool).await?;
+        for entry in entries {
+            sqlx::query(
+                r#"INSERT INTO dialog_activity
+                   (id, dialog_id, activity_type, user_id, actor_id, personal, details, created_at)
+                   SELECT $1, $2, $3, $4, $5, $6, $7, $8
+                   WHERE EXISTS (SELECT 1 FROM dialogs WHERE id = $2)"#,
+            )
+            .bind(entry.id)
+            .bind(entry.dialog_id)
+            .bind(entry.activity_type)
+            .bind(&entry.user_id)
+            .bind(&entry.actor_id)
+            .bind(entry.personal)
+            .bind(&entry.details)
+            .bind(entry.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Entries `viewer` may see, newest first, older than the `before` entry
+    pub async fn list(
+        &self,
+        dialog_id: Uuid,
+        viewer: &str,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<DialogActivity>, sqlx::Error> {
+        sqlx::query_as::<_, DialogActivity>(
+            r#"SELECT * FROM dialog_activity a
+               WHERE a.dialog_id = $1
+                 AND (NOT a.personal OR a.user_id = $2)
+                 AND ($3::UUID IS NULL OR (a.created_at, a.id) < (
+                     SELECT c.created_at, c.id FROM dialog_activity c
+                     WHERE c.id = $3 AND c.dialog_id = $1))
+               ORDER BY a.created_at DESC, a.id DESC
+               LIMIT $4"#,
+        )
+        .bind(dialog_id)
+        .bind(viewer)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,37 @@
+//! Dialog event (changelog) repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::DialogEvent;
+
+pub struct DialogEventRepository {
+    pool: PgPool,
+}
+
+impl DialogEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Events with seq greater than `since_seq`, oldest first
+    pub async fn list_since(
+        &self,
+        dialog_id: Uuid,
+        since_seq: i64,
+        limit: i64,
+    ) -> Result<Vec<DialogEvent>, sqlx::Error> {
+        sqlx::query_as::<_, DialogEvent>(
+            r#"SELECT seq, event_type, message_id, user_id, created_at
+               FROM dialog_events
+               WHERE dialog_id = $1 AND seq > $2
+               ORDER BY seq ASC
+               LIMIT $3"#,
+        )
+        .bind(dialog_id)
+        .bind(since_seq)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
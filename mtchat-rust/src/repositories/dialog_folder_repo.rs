@@ -0,0 +1,102 @@
+//! Dialog folder repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{DialogFilter, DialogFolder};
+
+/// Type alias for external user identifier
+type UserId = str;
+
+pub struct DialogFolderRepository {
+    pool: PgPool,
+}
+
+impl DialogFolderRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List a user's folders in display order
+    pub async fn list_for_user(&self, user_id: &UserId) -> Result<Vec<DialogFolder>, sqlx::Error> {
+        sqlx::query_as::<_, DialogFolder>(
+            "SELECT * FROM dialog_folders WHERE user_id = $1 ORDER BY position, created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Number of folders a user has
+    pub async fn count_for_user(&self, user_id: &UserId) -> Result<i64, sqlx::Error> {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM dialog_folders WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(count)
+    }
+
+    /// Create a folder at the end of the user's list
+    pub async fn create(&self, folder: &DialogFolder) -> Result<DialogFolder, sqlx::Error> {
+        sqlx::query_as::<_, DialogFolder>(
+            r#"INSERT INTO dialog_folders
+               (id, user_id, name, object_type, search, archived, unread_only, mentions_only,
+                position, created_at, updated_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8,
+                       (SELECT COALESCE(MAX(position) + 1, 0) FROM dialog_folders WHERE user_id = $2),
+                       $9, $9)
+               RETURNING *"#,
+        )
+        .bind(folder.id)
+        .bind(&folder.user_id)
+        .bind(&folder.name)
+        .bind(&folder.filter.object_type)
+        .bind(&folder.filter.search)
+        .bind(folder.filter.archived)
+        .bind(folder.filter.unread_only)
+        .bind(folder.filter.mentions_only)
+        .bind(folder.created_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Replace a folder's name, criteria and position
+    pub async fn update(
+        &self,
+        id: Uuid,
+        user_id: &UserId,
+        name: &str,
+        filter: &DialogFilter,
+        position: Option<i32>,
+    ) -> Result<Option<DialogFolder>, sqlx::Error> {
+        sqlx::query_as::<_, DialogFolder>(
+            r#"UPDATE dialog_folders
+               SET name = $3, object_type = $4, search = $5, archived = $6, unread_only = $7,
+                   mentions_only = $8, position = COALESCE($9, position), updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING *"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(name)
+        .bind(&filter.object_type)
+        .bind(&filter.search)
+        .bind(filter.archived)
+        .bind(filter.unread_only)
+        .bind(filter.mentions_only)
+        .bind(position)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Delete a folder. Returns true if it existed.
+    pub async fn delete(&self, id: Uuid, user_id: &UserId) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM dialog_folders WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,116 @@
+//! Dialog notes repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{DialogNotes, DialogNotesRevision};
+use crate::middleware::begin_transaction;
+
+/// Type alias for external user identifier
+type UserId = str;
+
+pub struct DialogNotesRepository {
+    pool: PgPool,
+}
+
+impl DialogNotesRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Current notes of a dialog (`None` until they are first written)
+    pub async fn find(&self, dialog_id: Uuid) -> Result<Option<DialogNotes>, sqlx::Error> {
+        sqlx::query_as::<_, DialogNotes>("SELECT * FROM dialog_notes WHERE dialog_id = $1")
+            .bind(dialog_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Replace the notes, keeping the previous revision in the history.
+    ///
+    /// With `expected_version` the write only succeeds if the notes are still
+    /// at that version (0 = not written yet). Returns `None` on a conflict.
+    pub async fn update(
+        &self,
+        dialog_id: Uuid,
+        content: &str,
+        updated_by: &UserId,
+        expected_version: Option<i32>,
+    ) -> Result<Option<DialogNotes>, sqlx::Error> {
+        let mut tx = begin_transaction(&self.pool).await?;
+
+        let current = sqlx::query_as::<_, DialogNotes>(
+            "SELECT * FROM dialog_notes WHERE dialog_id = $1 FOR UPDATE",
+        )
+        .bind(dialog_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let current_version = current.as_ref().map_or(0, |n| n.version);
+        if expected_version.is_some_and(|v| v != current_version) {
+            return Ok(None);
+        }
+
+        let updated = match current {
+            Some(current) => {
+                sqlx::query(
+                    r#"INSERT INTO dialog_notes_history
+                       (id, dialog_id, content, version, updated_by, updated_at, replaced_at)
+                       VALUES ($1, $2, $3, $4, $5, $6, NOW())"#,
+                )
+                .bind(Uuid::now_v7())
+                .bind(dialog_id)
+                .bind(&current.content)
+                .bind(current.version)
+                .bind(&current.updated_by)
+                .bind(current.updated_at)
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query_as::<_, DialogNotes>(
+                    r#"UPDATE dialog_notes
+                       SET content = $2, version = version + 1, updated_by = $3, updated_at = NOW()
+                       WHERE dialog_id = $1
+                       RETURNING *"#,
+                )
+                .bind(dialog_id)
+                .bind(content)
+                .bind(updated_by)
+                .fetch_optional(&mut *tx)
+                .await?
+            }
+            // A concurrent first write wins the insert; this one conflicts
+            None => sqlx::query_as::<_, DialogNotes>(
+                r#"INSERT INTO dialog_notes (dialog_id, content, version, updated_by, updated_at)
+                       VALUES ($1, $2, 1, $3, NOW())
+                       ON CONFLICT (dialog_id) DO NOTHING
+                       RETURNING *"#,
+            )
+            .bind(dialog_id)
+            .bind(content)
+            .bind(updated_by)
+            .fetch_optional(&mut *tx)
+            .await?,
+        };
+
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// Replaced revisions of the notes, newest first
+    pub async fn list_history(
+        &self,
+        dialog_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<DialogNotesRevision>, sqlx::Error> {
+        sqlx::query_as::<_, DialogNotesRevision>(
+            r#"SELECT * FROM dialog_notes_history
+               WHERE dialog_id = $1
+               ORDER BY version DESC
+               LIMIT $2"#,
+        )
+        .bind(dialog_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
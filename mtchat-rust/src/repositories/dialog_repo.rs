@@ -1,9 +1,16 @@
 //! Dialog repository
 
-use sqlx::PgPool;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgConnection, PgPool};
 use uuid::Uuid;
 
-use crate::domain::{Dialog, Message};
+use crate::domain::{
+    Dialog, DialogAccessScope, DialogFilter, DialogParticipant, Message, MessagePreview,
+    QuietHours, SanitizeProfile,
+};
+use crate::middleware::begin_transaction;
+use crate::repositories::StorageUsageRepository;
 
 /// Type alias for external user identifier
 type UserId = str;
@@ -12,16 +19,69 @@ pub struct DialogRepository {
     pool: PgPool,
 }
 
+/// Rows created together with a dialog
+#[derive(Debug, Default)]
+pub struct DialogChildren {
+    pub participants: Vec<DialogParticipant>,
+    pub access_scopes: Vec<DialogAccessScope>,
+    /// First message of the dialog, e.g. "chat created"
+    pub system_message: Option<Message>,
+}
+
+/// Dialog of a dialog list with a summary of its messages
+#[derive(Debug, FromRow)]
+pub struct ListedDialog {
+    #[sqlx(flatten)]
+    pub dialog: Dialog,
+    pub messages_count: i64,
+    pub last_message_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Latest message; only loaded for dialogs the user participates in
+    pub last_message: Option<Json<MessagePreview>>,
+}
+
+/// Read state of a dialog for a participant, as sent in `sync.state`
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DialogReadState {
+    pub dialog_id: Uuid,
+    pub unread_count: i32,
+    pub last_read_message_id: Option<Uuid>,
+    /// Latest message (None while the dialog has no messages)
+    pub last_message_id: Option<Uuid>,
+    pub last_message_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Highest message `seq` of the dialog; later `message.new` events carry
+    /// a greater one
+    pub last_message_seq: i64,
+    pub is_archived: bool,
+}
+
 impl DialogRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
 
-    /// Create a new dialog
-    pub async fn create(&self, dialog: &Dialog) -> Result<Dialog, sqlx::Error> {
-        sqlx::query_as::<_, Dialog>(
-            r#"INSERT INTO dialogs (id, object_id, object_type, title, object_url, created_by, created_at, meta)
-               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    /// Create a dialog with its participants, access scopes and first
+    /// message. Either everything is created or nothing is.
+    pub async fn create_with_children(
+        &self,
+        dialog: &Dialog,
+        children: &DialogChildren,
+    ) -> Result<Dialog, sqlx::Error> {
+        let mut tx = begin_transaction(&self.pool).await?;
+        let created = Self::insert_with_children(&mut tx, dialog, children).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Same as [`Self::create_with_children`] inside the caller's transaction,
+    /// for callers that read or lock rows before creating
+    pub async fn insert_with_children(
+        conn: &mut PgConnection,
+        dialog: &Dialog,
+        children: &DialogChildren,
+    ) -> Result<Dialog, sqlx::Error> {
+        let created = sqlx::query_as::<_, Dialog>(
+            r#"INSERT INTO dialogs (id, object_id, object_type, title, object_url, created_by, created_at, meta, timezone, locale)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                RETURNING *"#,
         )
         .bind(dialog.id)
@@ -32,18 +92,134 @@ impl DialogRepository {
         .bind(&dialog.created_by)
         .bind(dialog.created_at)
         .bind(&dialog.meta)
-        .fetch_one(&self.pool)
-        .await
+        .bind(&dialog.timezone)
+        .bind(&dialog.locale)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        for participant in &children.participants {
+            sqlx::query(
+                r#"INSERT INTO dialog_participants
+                   (dialog_id, user_id, joined_as, joined_at, display_name, company, company_uid, email, phone)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+            )
+            .bind(created.id)
+            .bind(&participant.user_id)
+            .bind(&participant.joined_as)
+            .bind(participant.joined_at)
+            .bind(&participant.display_name)
+            .bind(&participant.company)
+            .bind(&participant.company_uid)
+            .bind(&participant.email)
+            .bind(&participant.phone)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        insert_scopes(conn, created.id, &children.access_scopes).await?;
+
+        if let Some(message) = &children.system_message {
+            sqlx::query(
+                r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type, external_id)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+            )
+            .bind(message.id)
+            .bind(created.id)
+            .bind(&message.sender_id)
+            .bind(&message.content)
+            .bind(message.sent_at)
+            .bind(message.reply_to_id)
+            .bind(message.message_type.as_str())
+            .bind(&message.external_id)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(created)
+    }
+
+    /// Create a dialog from a template, unless one was already created for
+    /// the same object and creation key
+    ///
+    /// Concurrent calls converge on one dialog: the insert of the loser waits
+    /// for the winner's transaction and then returns the winner's dialog.
+    /// Soft-deleted dialogs do not hold their key, so a new one is created.
+    pub async fn create_auto(
+        &self,
+        dialog: &Dialog,
+        auto_create_key: &str,
+        scopes: &[DialogAccessScope],
+    ) -> Result<Dialog, sqlx::Error> {
+        let mut tx = begin_transaction(&self.pool).await?;
+
+        let created = sqlx::query_as::<_, Dialog>(
+            r#"INSERT INTO dialogs (id, object_id, object_type, title, created_at, auto_create_key)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               ON CONFLICT (object_type, object_id, auto_create_key)
+                   WHERE auto_create_key IS NOT NULL AND deleted_at IS NULL
+                   DO NOTHING
+               RETURNING *"#,
+        )
+        .bind(dialog.id)
+        .bind(&dialog.object_id)
+        .bind(&dialog.object_type)
+        .bind(&dialog.title)
+        .bind(dialog.created_at)
+        .bind(auto_create_key)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(created) = created else {
+            tx.rollback().await?;
+            return sqlx::query_as::<_, Dialog>(
+                r#"SELECT * FROM dialogs
+                   WHERE object_type = $1 AND object_id = $2 AND auto_create_key = $3
+                     AND deleted_at IS NULL"#,
+            )
+            .bind(&dialog.object_type)
+            .bind(&dialog.object_id)
+            .bind(auto_create_key)
+            .fetch_one(&self.pool)
+            .await;
+        };
+
+        insert_scopes(&mut tx, created.id, scopes).await?;
+
+        tx.commit().await?;
+        Ok(created)
     }
 
-    /// Find dialog by ID
+    /// Find dialog by ID (soft-deleted dialogs are not found)
     pub async fn find_by_id(&self, id: Uuid) -> Result<Option<Dialog>, sqlx::Error> {
-        sqlx::query_as::<_, Dialog>("SELECT * FROM dialogs WHERE id = $1")
+        sqlx::query_as::<_, Dialog>("SELECT * FROM dialogs WHERE id = $1 AND deleted_at IS NULL")
             .bind(id)
             .fetch_optional(&self.pool)
             .await
     }
 
+    /// Find dialogs by IDs (soft-deleted and unknown dialogs are left out)
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Dialog>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        sqlx::query_as::<_, Dialog>(
+            "SELECT * FROM dialogs WHERE id = ANY($1) AND deleted_at IS NULL ORDER BY id",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Find a soft-deleted dialog by ID
+    pub async fn find_deleted(&self, id: Uuid) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            "SELECT * FROM dialogs WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
     /// Find the most recent dialog by object (type + id) that the caller can access.
     ///
     /// Multiple dialogs can exist per object (one per access scope, e.g. one chat
@@ -70,6 +246,7 @@ impl DialogRepository {
         sqlx::query_as::<_, Dialog>(
             r#"SELECT d.* FROM dialogs d
                WHERE d.object_type = $1 AND d.object_id = $2
+                 AND d.deleted_at IS NULL
                  AND (
                    EXISTS (
                      SELECT 1 FROM dialog_participants dp
@@ -81,6 +258,11 @@ impl DialogRepository {
                        AND (s.scope_level0 = '{}' OR s.scope_level0 && $5)
                        AND (s.scope_level1 = '{}' OR s.scope_level1 && $6)
                        AND (s.scope_level2 = '{}' OR s.scope_level2 && $7)
+                       AND NOT EXISTS (
+                         SELECT 1 FROM dialog_bans b
+                         WHERE b.dialog_id = d.id AND b.user_id = $3
+                           AND (b.expires_at IS NULL OR b.expires_at > NOW())
+                       )
                    ))
                  )
                ORDER BY d.created_at DESC
@@ -107,7 +289,9 @@ impl DialogRepository {
         object_id: &str,
     ) -> Result<Vec<Dialog>, sqlx::Error> {
         sqlx::query_as::<_, Dialog>(
-            "SELECT * FROM dialogs WHERE object_type = $1 AND object_id = $2 ORDER BY created_at DESC",
+            r#"SELECT * FROM dialogs
+               WHERE object_type = $1 AND object_id = $2 AND deleted_at IS NULL
+               ORDER BY created_at DESC"#,
         )
         .bind(object_type)
         .bind(object_id)
@@ -117,42 +301,124 @@ impl DialogRepository {
 
     /// Find dialogs where user is a direct participant
     ///
-    /// - archived: None = all, Some(true) = only archived, Some(false) = only active
-    /// - search: searches in dialog title AND participant company names
+    /// - filter.archived: None = all, Some(true) = only archived, Some(false) = only active
+    /// - filter.search: searches in dialog title AND participant company names
+    /// - filter.object_type: exact object type
+    /// - filter.unread_only: only dialogs with unread messages for the user
+    /// - filter.mentions_only: only dialogs with messages mentioning the user sent
+    ///   after the user's last read message
     /// - limit/offset: pagination parameters
+    ///
+    /// Reads the `dialog_list_entries` read model, which carries each dialog's
+    /// message count and last message, so a page is one index scan plus the
+    /// dialogs of the page.
     pub async fn find_participating(
         &self,
         user_id: &UserId,
-        search: Option<&str>,
-        archived: Option<bool>,
+        filter: &DialogFilter,
         limit: i64,
         offset: i64,
-    ) -> Result<Vec<Dialog>, sqlx::Error> {
-        sqlx::query_as::<_, Dialog>(
-            r#"SELECT d.* FROM dialogs d
-               INNER JOIN dialog_participants dp ON dp.dialog_id = d.id
-               WHERE dp.user_id = $1
-                 AND ($2::text IS NULL OR (
-                   d.title ILIKE '%' || $2 || '%'
-                   OR EXISTS (
-                     SELECT 1 FROM dialog_participants p
-                     WHERE p.dialog_id = d.id
-                       AND p.company ILIKE '%' || $2 || '%'
-                   )
+    ) -> Result<Vec<ListedDialog>, sqlx::Error> {
+        sqlx::query_as::<_, ListedDialog>(
+            r#"SELECT d.*, e.messages_count, e.last_message_at, e.last_message
+               FROM (
+               SELECT * FROM dialog_list_entries e
+               WHERE e.user_id = $1
+                 AND NOT e.is_deleted
+                 AND ($2::text IS NULL OR e.title ILIKE '%' || $2 || '%'
+                      OR e.companies ILIKE '%' || $2 || '%')
+                 AND ($3::boolean IS NULL OR e.is_archived = $3)
+                 AND ($4::text IS NULL OR e.object_type = $4)
+                 AND (NOT $5 OR e.unread_count > 0)
+                 AND (NOT $6 OR EXISTS (
+                   SELECT 1 FROM message_mentions mm
+                   INNER JOIN messages m ON m.id = mm.message_id
+                   INNER JOIN dialog_participants dp
+                     ON dp.dialog_id = e.dialog_id AND dp.user_id = e.user_id
+                   WHERE mm.dialog_id = e.dialog_id
+                     AND mm.user_id = e.user_id
+                     AND m.sent_at > COALESCE(
+                       (SELECT lr.sent_at FROM messages lr WHERE lr.id = dp.last_read_message_id),
+                       '-infinity'
+                     )
                  ))
-                 AND ($3::boolean IS NULL OR dp.is_archived = $3)
-               ORDER BY d.created_at DESC
-               LIMIT $4 OFFSET $5"#,
+               ORDER BY e.dialog_created_at DESC, e.dialog_id DESC
+               LIMIT $7 OFFSET $8
+               ) e
+               INNER JOIN dialogs d ON d.id = e.dialog_id
+               ORDER BY e.dialog_created_at DESC, e.dialog_id DESC"#,
         )
         .bind(user_id)
-        .bind(search)
-        .bind(archived)
+        .bind(&filter.search)
+        .bind(filter.archived)
+        .bind(&filter.object_type)
+        .bind(filter.unread_only)
+        .bind(filter.mentions_only)
         .bind(limit)
         .bind(offset)
         .fetch_all(&self.pool)
         .await
     }
 
+    /// Read state of the user's dialogs: unread ones first, then by latest
+    /// message. One query over the `dialog_list_entries` read model.
+    pub async fn find_read_states(
+        &self,
+        user_id: &UserId,
+        limit: i64,
+    ) -> Result<Vec<DialogReadState>, sqlx::Error> {
+        sqlx::query_as::<_, DialogReadState>(
+            r#"SELECT e.dialog_id, e.unread_count, dp.last_read_message_id,
+                      (e.last_message->>'id')::uuid AS last_message_id, e.last_message_at,
+                      d.last_message_seq, e.is_archived
+               FROM dialog_list_entries e
+               INNER JOIN dialog_participants dp
+                 ON dp.dialog_id = e.dialog_id AND dp.user_id = e.user_id
+               INNER JOIN dialogs d ON d.id = e.dialog_id
+               WHERE e.user_id = $1 AND NOT e.is_deleted
+               ORDER BY e.unread_count > 0 DESC, e.last_message_at DESC NULLS LAST,
+                        e.dialog_id DESC
+               LIMIT $2"#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Rebuild the `dialog_list_entries` of up to `limit` dialogs with ids
+    /// after `after` from the source tables.
+    ///
+    /// Returns the number of entries added, corrected or removed and the
+    /// last dialog id of the batch (`None` when there are no dialogs left).
+    pub async fn reconcile_list_entries(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<(i32, Option<Uuid>), sqlx::Error> {
+        let dialog_ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"SELECT id FROM dialogs
+               WHERE $1::uuid IS NULL OR id > $1
+               ORDER BY id
+               LIMIT $2"#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let Some(&last) = dialog_ids.last() else {
+            return Ok((0, None));
+        };
+
+        let repaired: i32 = sqlx::query_scalar("SELECT dialog_list_rebuild($1)")
+            .bind(&dialog_ids)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((repaired, Some(last)))
+    }
+
     /// Find dialogs available to user via scope (not yet participating)
     ///
     /// Matching logic (consistent OR across all levels):
@@ -161,6 +427,11 @@ impl DialogRepository {
     /// - scope_level2: empty array in DB = wildcard (match all), otherwise requires overlap
     /// - search: searches in dialog title AND participant company names
     /// - limit/offset: pagination parameters
+    ///
+    /// Dialogs the user is banned from are not available.
+    ///
+    /// Message content is not readable before joining, so dialogs come with
+    /// their message count and last message time but no last message.
     #[allow(clippy::too_many_arguments)]
     pub async fn find_available(
         &self,
@@ -171,13 +442,24 @@ impl DialogRepository {
         search: Option<&str>,
         limit: i64,
         offset: i64,
-    ) -> Result<Vec<Dialog>, sqlx::Error> {
-        sqlx::query_as::<_, Dialog>(
-            r#"SELECT DISTINCT d.* FROM dialogs d
+    ) -> Result<Vec<ListedDialog>, sqlx::Error> {
+        sqlx::query_as::<_, ListedDialog>(
+            r#"SELECT d.*,
+                      lm.messages_count,
+                      lm.last_message_at,
+                      NULL::jsonb AS last_message
+               FROM (
+               SELECT DISTINCT d.* FROM dialogs d
                INNER JOIN dialog_access_scopes s ON s.dialog_id = d.id
                WHERE (s.scope_level0 = '{}' OR s.scope_level0 && $1)
                  AND (s.scope_level1 = '{}' OR s.scope_level1 && $2)
                  AND (s.scope_level2 = '{}' OR s.scope_level2 && $3)
+                 AND d.deleted_at IS NULL
+                 AND NOT EXISTS (
+                   SELECT 1 FROM dialog_bans b
+                   WHERE b.dialog_id = d.id AND b.user_id = $4
+                     AND (b.expires_at IS NULL OR b.expires_at > NOW())
+                 )
                  AND NOT EXISTS (
                    SELECT 1 FROM dialog_participants dp
                    WHERE dp.dialog_id = d.id AND dp.user_id = $4
@@ -191,7 +473,14 @@ impl DialogRepository {
                    )
                  ))
                ORDER BY d.created_at DESC
-               LIMIT $6 OFFSET $7"#,
+               LIMIT $6 OFFSET $7
+               ) d
+               CROSS JOIN LATERAL (
+                 SELECT COUNT(*) AS messages_count, MAX(m.sent_at) AS last_message_at
+                 FROM messages m
+                 WHERE m.dialog_id = d.id
+               ) lm
+               ORDER BY d.created_at DESC"#,
         )
         .bind(scope_level0)
         .bind(scope_level1)
@@ -256,6 +545,7 @@ impl DialogRepository {
         sqlx::query_as::<_, Dialog>(
             r#"SELECT d.* FROM dialogs d
                WHERE d.object_type = $1 AND d.object_id = $2
+                 AND d.deleted_at IS NULL
                  AND (
                    ($10 AND EXISTS (
                      SELECT 1 FROM dialog_participants dp
@@ -268,6 +558,11 @@ impl DialogRepository {
                        AND (s.scope_level0 = '{}' OR s.scope_level0 && $5)
                        AND (s.scope_level1 = '{}' OR s.scope_level1 && $6)
                        AND (s.scope_level2 = '{}' OR s.scope_level2 && $7)
+                       AND NOT EXISTS (
+                         SELECT 1 FROM dialog_bans b
+                         WHERE b.dialog_id = d.id AND b.user_id = $3
+                           AND (b.expires_at IS NULL OR b.expires_at > NOW())
+                       )
                    ))
                  )
                  AND ($9::text IS NULL OR (
@@ -295,12 +590,160 @@ impl DialogRepository {
         .await
     }
 
-    /// Delete dialog by ID
+    /// Set the timezone and locale of a dialog (None clears the value)
+    pub async fn update_locale(
+        &self,
+        id: Uuid,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+    ) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            "UPDATE dialogs SET timezone = $2, locale = $3 WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(timezone)
+        .bind(locale)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Set (or with `None`, turn off) slow mode
+    pub async fn update_slow_mode(
+        &self,
+        id: Uuid,
+        slow_mode_secs: Option<i32>,
+    ) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            "UPDATE dialogs SET slow_mode_secs = $2 WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(slow_mode_secs)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Set (or with `None`, clear) the notification delay of a dialog
+    pub async fn update_notification_delay(
+        &self,
+        id: Uuid,
+        notification_delay_ms: Option<i32>,
+    ) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            "UPDATE dialogs SET notification_delay_ms = $2 WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(notification_delay_ms)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Set or clear the response time SLA of a dialog
+    pub async fn update_sla(
+        &self,
+        id: Uuid,
+        response_secs: Option<i32>,
+        warning_secs: Option<i32>,
+        responder_company: Option<&str>,
+    ) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            r#"UPDATE dialogs
+               SET sla_response_secs = $2, sla_warning_secs = $3, sla_responder_company = $4
+               WHERE id = $1
+               RETURNING *"#,
+        )
+        .bind(id)
+        .bind(response_secs)
+        .bind(warning_secs)
+        .bind(responder_company)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Set or clear the sanitization profile of a dialog
+    pub async fn update_sanitize_profile(
+        &self,
+        id: Uuid,
+        profile: Option<SanitizeProfile>,
+    ) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            "UPDATE dialogs SET sanitize_profile = $2 WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(profile)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Whether the database knows an IANA timezone name
+    pub async fn is_known_timezone(&self, timezone: &str) -> Result<bool, sqlx::Error> {
+        let (known,): (bool,) =
+            sqlx::query_as("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+                .bind(timezone)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(known)
+    }
+
+    /// Soft-delete a dialog (hide it until restored or purged)
+    pub async fn soft_delete(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE dialogs SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Restore a dialog soft-deleted after `deleted_since`
+    pub async fn restore(
+        &self,
+        id: Uuid,
+        deleted_since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<Dialog>, sqlx::Error> {
+        sqlx::query_as::<_, Dialog>(
+            r#"UPDATE dialogs d
+               SET deleted_at = NULL,
+                   auto_create_key = CASE
+                       WHEN EXISTS (SELECT 1 FROM dialogs live
+                                    WHERE live.object_type = d.object_type
+                                      AND live.object_id = d.object_id
+                                      AND live.auto_create_key = d.auto_create_key
+                                      AND live.deleted_at IS NULL)
+                           THEN NULL
+                       ELSE d.auto_create_key
+                   END
+               WHERE d.id = $1 AND d.deleted_at >= $2
+               RETURNING d.*"#,
+        )
+        .bind(id)
+        .bind(deleted_since)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Find dialogs soft-deleted before the cutoff (due for purging)
+    pub async fn find_deleted_before(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar("SELECT id FROM dialogs WHERE deleted_at < $1 ORDER BY deleted_at")
+            .bind(cutoff)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Delete dialog by ID (with messages, participants and attachments rows)
+    ///
+    /// Its storage usage is released in the same transaction.
     pub async fn delete(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = begin_transaction(&self.pool).await?;
+        StorageUsageRepository::remove_dialog(&mut tx, id).await?;
         let result = sqlx::query("DELETE FROM dialogs WHERE id = $1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
         Ok(result.rows_affected() > 0)
     }
 
@@ -314,7 +757,10 @@ impl DialogRepository {
         Ok(count)
     }
 
-    /// Count participants for multiple dialogs in one query
+    /// Count participants for multiple dialogs in one query.
+    ///
+    /// Dialog responses use the trigger-maintained `Dialog::participants_count`;
+    /// this counts the rows, for consistency checks.
     pub async fn count_participants_batch(
         &self,
         dialog_ids: &[Uuid],
@@ -399,17 +845,23 @@ impl DialogRepository {
 
     /// Find dialogs with no messages since the cutoff date.
     ///
-    /// Used by auto-archive job to find inactive dialogs.
+    /// Used by auto-archive job to find inactive dialogs. With `quiet_hours`
+    /// only dialogs whose local time is inside the window are returned; the
+    /// local time is taken in the dialog's timezone, else the timezone of
+    /// one of its tenants, else UTC.
     pub async fn find_inactive_since(
         &self,
         cutoff: chrono::DateTime<chrono::Utc>,
+        quiet_hours: Option<QuietHours>,
     ) -> Result<Vec<Uuid>, sqlx::Error> {
         // Find dialogs where:
         // - No messages exist OR latest message is older than cutoff
         // - At least one participant is not archived (to avoid re-processing)
+        // - The local hour is in [start, end), wrapping around midnight
         sqlx::query_scalar(
             r#"SELECT d.id FROM dialogs d
-               WHERE (
+               WHERE d.deleted_at IS NULL
+               AND (
                    NOT EXISTS (SELECT 1 FROM messages m WHERE m.dialog_id = d.id)
                    OR (
                        SELECT MAX(m.sent_at) FROM messages m WHERE m.dialog_id = d.id
@@ -418,10 +870,50 @@ impl DialogRepository {
                AND EXISTS (
                    SELECT 1 FROM dialog_participants dp
                    WHERE dp.dialog_id = d.id AND dp.is_archived = false
+               )
+               AND (
+                   $2::int IS NULL
+                   OR (EXTRACT(HOUR FROM NOW() AT TIME ZONE COALESCE(
+                           d.timezone,
+                           (SELECT ts.timezone FROM tenant_settings ts
+                            WHERE ts.timezone IS NOT NULL
+                              AND ts.tenant_uid IN (
+                                  SELECT unnest(scope_level0) FROM dialog_access_scopes
+                                  WHERE dialog_id = d.id
+                              )
+                            ORDER BY ts.tenant_uid
+                            LIMIT 1),
+                           'UTC'
+                       ))::int - $2 + 24) % 24 < ($3 - $2 + 24) % 24
                )"#,
         )
         .bind(cutoff)
+        .bind(quiet_hours.map(|h| i32::from(h.start_hour)))
+        .bind(quiet_hours.map(|h| i32::from(h.end_hour)))
         .fetch_all(&self.pool)
         .await
     }
 }
+
+/// Insert the access scopes of a dialog being created
+async fn insert_scopes(
+    conn: &mut PgConnection,
+    dialog_id: Uuid,
+    scopes: &[DialogAccessScope],
+) -> Result<(), sqlx::Error> {
+    for scope in scopes {
+        sqlx::query(
+            r#"INSERT INTO dialog_access_scopes (id, dialog_id, scope_level0, scope_level1, scope_level2, created_at)
+               VALUES ($1, $2, $3, $4, $5, $6)"#,
+        )
+        .bind(scope.id)
+        .bind(dialog_id)
+        .bind(&scope.scope_level0)
+        .bind(&scope.scope_level1)
+        .bind(&scope.scope_level2)
+        .bind(scope.created_at)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
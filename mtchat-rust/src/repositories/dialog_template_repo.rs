@@ -0,0 +1,59 @@
+//! Auto-create dialog template repository
+
+use sqlx::PgPool;
+
+use crate::domain::DialogTemplate;
+
+pub struct DialogTemplateRepository {
+    pool: PgPool,
+}
+
+impl DialogTemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the template of an object type
+    pub async fn find(&self, object_type: &str) -> Result<Option<DialogTemplate>, sqlx::Error> {
+        sqlx::query_as::<_, DialogTemplate>("SELECT * FROM dialog_templates WHERE object_type = $1")
+            .bind(object_type)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// List all templates
+    pub async fn list(&self) -> Result<Vec<DialogTemplate>, sqlx::Error> {
+        sqlx::query_as::<_, DialogTemplate>("SELECT * FROM dialog_templates ORDER BY object_type")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Create or replace the template of an object type
+    pub async fn upsert(&self, template: &DialogTemplate) -> Result<DialogTemplate, sqlx::Error> {
+        sqlx::query_as::<_, DialogTemplate>(
+            r#"INSERT INTO dialog_templates (object_type, title, access_scopes, caller_tenant)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (object_type) DO UPDATE
+               SET title = EXCLUDED.title,
+                   access_scopes = EXCLUDED.access_scopes,
+                   caller_tenant = EXCLUDED.caller_tenant,
+                   updated_at = NOW()
+               RETURNING *"#,
+        )
+        .bind(&template.object_type)
+        .bind(&template.title)
+        .bind(&template.access_scopes)
+        .bind(template.caller_tenant)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Delete the template of an object type. Returns true if it existed.
+    pub async fn delete(&self, object_type: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM dialog_templates WHERE object_type = $1")
+            .bind(object_type)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,160 @@
+//! Incremental export repository
+//!
+//! Pages of rows changed since a time or cursor, in `(updated_at, key)`
+//! order. Rows changed after `settled_before` are left for a later page.
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::domain::{
+    ExportCursor, ExportDeletion, ExportDialog, ExportMessage, ExportParticipant, StoredContent,
+};
+
+/// Exported message with its stored content, to decompress
+#[derive(FromRow)]
+struct ExportMessageRow {
+    #[sqlx(flatten)]
+    message: ExportMessage,
+    #[sqlx(flatten)]
+    stored: StoredContent,
+}
+
+pub struct ExportRepository {
+    pool: PgPool,
+}
+
+impl ExportRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Dialogs (including deleted ones not purged yet)
+    pub async fn dialogs(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+        after: Option<&ExportCursor>,
+        settled_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ExportDialog>, sqlx::Error> {
+        sqlx::query_as::<_, ExportDialog>(
+            r#"SELECT id, object_id, object_type, title, object_url, created_by, created_at,
+                      updated_at, deleted_at, participants_count, observers_count,
+                      timezone, locale
+               FROM dialogs
+               WHERE ($1::timestamptz IS NULL OR updated_at >= $1)
+                 AND ($2::timestamptz IS NULL OR (updated_at, id) > ($2, $3))
+                 AND updated_at < $4
+               ORDER BY updated_at, id
+               LIMIT $5"#,
+        )
+        .bind(updated_since)
+        .bind(after.map(|c| c.updated_at))
+        .bind(after.map(|c| c.id))
+        .bind(settled_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn messages(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+        after: Option<&ExportCursor>,
+        settled_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ExportMessage>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, ExportMessageRow>(
+            r#"SELECT id, external_id, dialog_id, seq, sender_id, sender_display_name,
+                      sender_company, message_type, priority, content, content_encoding,
+                      content_compressed, reply_to_id, sent_at, last_edited_at, version,
+                      updated_at
+               FROM messages
+               WHERE ($1::timestamptz IS NULL OR updated_at >= $1)
+                 AND ($2::timestamptz IS NULL OR (updated_at, id) > ($2, $3))
+                 AND updated_at < $4
+               ORDER BY updated_at, id
+               LIMIT $5"#,
+        )
+        .bind(updated_since)
+        .bind(after.map(|c| c.updated_at))
+        .bind(after.map(|c| c.id))
+        .bind(settled_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let content = row.stored.decode().map_err(sqlx::Error::Decode)?;
+                Ok(ExportMessage {
+                    content,
+                    ..row.message
+                })
+            })
+            .collect()
+    }
+
+    pub async fn participants(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+        after: Option<&ExportCursor>,
+        settled_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ExportParticipant>, sqlx::Error> {
+        sqlx::query_as::<_, ExportParticipant>(
+            r#"SELECT dialog_id, user_id, joined_as, joined_at, display_name, company,
+                      company_uid, email, notifications_enabled, is_archived,
+                      last_read_message_id, pending_removal_at, updated_at
+               FROM dialog_participants
+               WHERE ($1::timestamptz IS NULL OR updated_at >= $1)
+                 AND ($2::timestamptz IS NULL
+                      OR (updated_at, dialog_id, user_id) > ($2, $3, $4))
+                 AND updated_at < $5
+               ORDER BY updated_at, dialog_id, user_id
+               LIMIT $6"#,
+        )
+        .bind(updated_since)
+        .bind(after.map(|c| c.updated_at))
+        .bind(after.map(|c| c.id))
+        .bind(after.map(|c| c.user_id.clone().unwrap_or_default()))
+        .bind(settled_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Tombstones of hard-deleted rows, in `(deleted_at, id)` order
+    pub async fn deletions(
+        &self,
+        deleted_since: Option<DateTime<Utc>>,
+        after: Option<&ExportCursor>,
+        settled_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ExportDeletion>, sqlx::Error> {
+        sqlx::query_as::<_, ExportDeletion>(
+            r#"SELECT id, entity, dialog_id, message_id, user_id, deleted_at
+               FROM export_deletions
+               WHERE ($1::timestamptz IS NULL OR deleted_at >= $1)
+                 AND ($2::timestamptz IS NULL OR (deleted_at, id) > ($2, $3))
+                 AND deleted_at < $4
+               ORDER BY deleted_at, id
+               LIMIT $5"#,
+        )
+        .bind(deleted_since)
+        .bind(after.map(|c| c.updated_at))
+        .bind(after.map(|c| c.id))
+        .bind(settled_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Drop tombstones older than `before`. Returns the number deleted.
+    pub async fn delete_deletions_before(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM export_deletions WHERE deleted_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
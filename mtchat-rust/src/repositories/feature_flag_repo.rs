@@ -0,0 +1,113 @@
+//! Feature flag override repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{FeatureFlagOverride, FlagScope};
+
+pub struct FeatureFlagRepository {
+    pool: PgPool,
+}
+
+impl FeatureFlagRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List all overrides, optionally for a single flag
+    pub async fn list(&self, flag: Option<&str>) -> Result<Vec<FeatureFlagOverride>, sqlx::Error> {
+        sqlx::query_as::<_, FeatureFlagOverride>(
+            r#"SELECT * FROM feature_flag_overrides
+               WHERE $1::text IS NULL OR flag = $1
+               ORDER BY flag, scope_type, scope_id"#,
+        )
+        .bind(flag)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Overrides of a dialog and of every tenant it belongs to
+    pub async fn find_for_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<Vec<FeatureFlagOverride>, sqlx::Error> {
+        sqlx::query_as::<_, FeatureFlagOverride>(
+            r#"SELECT * FROM feature_flag_overrides
+               WHERE (scope_type = 'dialog' AND scope_id = $1::text)
+                  OR (scope_type = 'tenant' AND scope_id IN (
+                      SELECT unnest(scope_level0) FROM dialog_access_scopes WHERE dialog_id = $1
+                  ))
+               ORDER BY flag, scope_type, scope_id"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Overrides of a tenant
+    pub async fn find_for_tenant(
+        &self,
+        tenant_uid: &str,
+    ) -> Result<Vec<FeatureFlagOverride>, sqlx::Error> {
+        sqlx::query_as::<_, FeatureFlagOverride>(
+            r#"SELECT * FROM feature_flag_overrides
+               WHERE scope_type = 'tenant' AND scope_id = $1
+               ORDER BY flag"#,
+        )
+        .bind(tenant_uid)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Set an override
+    pub async fn set(
+        &self,
+        flag: &str,
+        scope_type: FlagScope,
+        scope_id: &str,
+        enabled: bool,
+    ) -> Result<FeatureFlagOverride, sqlx::Error> {
+        sqlx::query_as::<_, FeatureFlagOverride>(
+            r#"INSERT INTO feature_flag_overrides (flag, scope_type, scope_id, enabled)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (flag, scope_type, scope_id) DO UPDATE
+               SET enabled = EXCLUDED.enabled, updated_at = NOW()
+               RETURNING *"#,
+        )
+        .bind(flag)
+        .bind(scope_type.as_str())
+        .bind(scope_id)
+        .bind(enabled)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Remove an override. Returns true if one existed.
+    pub async fn delete(
+        &self,
+        flag: &str,
+        scope_type: FlagScope,
+        scope_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM feature_flag_overrides WHERE flag = $1 AND scope_type = $2 AND scope_id = $3",
+        )
+        .bind(flag)
+        .bind(scope_type.as_str())
+        .bind(scope_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove all overrides of a dialog (call when deleting the dialog)
+    pub async fn delete_for_dialog(&self, dialog_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM feature_flag_overrides WHERE scope_type = 'dialog' AND scope_id = $1::text",
+        )
+        .bind(dialog_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
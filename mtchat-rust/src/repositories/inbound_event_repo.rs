@@ -0,0 +1,85 @@
+//! Processed inbound integration event repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Outcome of claiming an inbound event ID
+#[derive(Debug, Clone, PartialEq)]
+pub enum InboundEventClaim {
+    /// First delivery: apply the event, then `complete` (or `release`) it
+    New,
+    /// Already applied; the recorded type and result
+    Applied {
+        event_type: String,
+        result: serde_json::Value,
+    },
+    /// Another delivery of the event is being applied
+    InProgress,
+}
+
+pub struct InboundEventRepository {
+    pool: PgPool,
+}
+
+impl InboundEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Claim an event ID before applying it. Events received more than
+    /// `retention_secs` ago are forgotten first.
+    pub async fn claim(
+        &self,
+        id: Uuid,
+        event_type: &str,
+        retention_secs: i64,
+    ) -> Result<InboundEventClaim, sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM inbound_events WHERE received_at < NOW() - make_interval(secs => $1)",
+        )
+        .bind(retention_secs as f64)
+        .execute(&self.pool)
+        .await?;
+
+        let claimed = sqlx::query(
+            r#"INSERT INTO inbound_events (id, event_type) VALUES ($1, $2)
+               ON CONFLICT (id) DO NOTHING"#,
+        )
+        .bind(id)
+        .bind(event_type)
+        .execute(&self.pool)
+        .await?;
+        if claimed.rows_affected() > 0 {
+            return Ok(InboundEventClaim::New);
+        }
+
+        let recorded: Option<(String, Option<serde_json::Value>)> =
+            sqlx::query_as("SELECT event_type, result FROM inbound_events WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(match recorded {
+            Some((event_type, Some(result))) => InboundEventClaim::Applied { event_type, result },
+            _ => InboundEventClaim::InProgress,
+        })
+    }
+
+    /// Record the result of an applied event
+    pub async fn complete(&self, id: Uuid, result: &serde_json::Value) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE inbound_events SET result = $2 WHERE id = $1")
+            .bind(id)
+            .bind(result)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Drop the claim of an event that failed, so it can be delivered again
+    pub async fn release(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM inbound_events WHERE id = $1 AND result IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Participant invite repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::ParticipantInvite;
+use crate::middleware::begin_transaction;
+
+/// An invite consumed by [`ParticipantInviteRepository::activate`]
+#[derive(Debug, Clone)]
+pub struct ActivatedInvite {
+    pub invite: ParticipantInvite,
+    /// Whether it added the participant (false if the user already was one)
+    pub joined: bool,
+}
+
+pub struct ParticipantInviteRepository {
+    pool: PgPool,
+}
+
+impl ParticipantInviteRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store an invite. Inviting an email again updates the pending invite
+    /// (keeping its ID and invite time).
+    pub async fn upsert(
+        &self,
+        invite: &ParticipantInvite,
+    ) -> Result<ParticipantInvite, sqlx::Error> {
+        sqlx::query_as::<_, ParticipantInvite>(
+            r#"INSERT INTO participant_invites
+               (id, dialog_id, email, joined_as, display_name, company, company_uid, phone, invited_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+               ON CONFLICT (dialog_id, email) DO UPDATE
+               SET joined_as = EXCLUDED.joined_as,
+                   display_name = EXCLUDED.display_name,
+                   company = EXCLUDED.company,
+                   company_uid = EXCLUDED.company_uid,
+                   phone = EXCLUDED.phone
+               RETURNING *"#,
+        )
+        .bind(invite.id)
+        .bind(invite.dialog_id)
+        .bind(&invite.email)
+        .bind(&invite.joined_as)
+        .bind(&invite.display_name)
+        .bind(&invite.company)
+        .bind(&invite.company_uid)
+        .bind(&invite.phone)
+        .bind(invite.invited_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Pending invites of a dialog, oldest first
+    pub async fn list_by_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<Vec<ParticipantInvite>, sqlx::Error> {
+        sqlx::query_as::<_, ParticipantInvite>(
+            "SELECT * FROM participant_invites WHERE dialog_id = $1 ORDER BY invited_at, id",
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Revoke an invite. Returns true if it was pending.
+    pub async fn delete(&self, dialog_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM participant_invites WHERE dialog_id = $1 AND id = $2")
+                .bind(dialog_id)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Turn all pending invites of an email into participants with `user_id`.
+    /// Dialogs the user already participates in keep the existing
+    /// participant; their invites are consumed all the same. Returns the
+    /// activated invites.
+    pub async fn activate(
+        &self,
+        email: &str,
+        user_id: &str,
+    ) -> Result<Vec<ActivatedInvite>, sqlx::Error> {
+        let mut tx = begin_transaction(&self.pool).await?;
+
+        let invites = sqlx::query_as::<_, ParticipantInvite>(
+            "DELETE FROM participant_invites WHERE email = $1 RETURNING *",
+        )
+        .bind(email)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut activated = Vec::with_capacity(invites.len());
+        for invite in invites {
+            let profile = invite.profile();
+            let result = sqlx::query(
+                r#"INSERT INTO dialog_participants
+                   (dialog_id, user_id, joined_as, joined_at, display_name, company, company_uid, email, phone)
+                   VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7, $8)
+                   ON CONFLICT (dialog_id, user_id) DO NOTHING"#,
+            )
+            .bind(invite.dialog_id)
+            .bind(user_id)
+            .bind(&invite.joined_as)
+            .bind(&profile.display_name)
+            .bind(&profile.company)
+            .bind(&profile.company_uid)
+            .bind(&profile.email)
+            .bind(&profile.phone)
+            .execute(&mut *tx)
+            .await?;
+            activated.push(ActivatedInvite {
+                invite,
+                joined: result.rows_affected() > 0,
+            });
+        }
+
+        tx.commit().await?;
+        Ok(activated)
+    }
+}
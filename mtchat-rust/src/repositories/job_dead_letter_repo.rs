@@ -0,0 +1,51 @@
+//! Dead-lettered job repository (append-only)
+
+use sqlx::PgPool;
+
+use crate::domain::JobDeadLetter;
+
+pub struct JobDeadLetterRepository {
+    pool: PgPool,
+}
+
+impl JobDeadLetterRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a permanently failed job
+    pub async fn record(&self, letter: &JobDeadLetter) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO job_dead_letters (id, job_type, task_id, payload, error, attempts, failed_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(letter.id)
+        .bind(&letter.job_type)
+        .bind(&letter.task_id)
+        .bind(&letter.payload)
+        .bind(&letter.error)
+        .bind(letter.attempts)
+        .bind(letter.failed_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Dead letters newest first, optionally filtered by job type
+    pub async fn list(
+        &self,
+        job_type: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<JobDeadLetter>, sqlx::Error> {
+        sqlx::query_as::<_, JobDeadLetter>(
+            r#"SELECT * FROM job_dead_letters
+               WHERE ($1::VARCHAR IS NULL OR job_type = $1)
+               ORDER BY failed_at DESC, id DESC
+               LIMIT $2"#,
+        )
+        .bind(job_type)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,82 @@
+//! Message reaction repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::ReactionSummary;
+
+/// Type alias for external user identifier
+type UserId = str;
+
+pub struct MessageReactionRepository {
+    pool: PgPool,
+}
+
+impl MessageReactionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// React to a message. Returns false if the user already reacted with
+    /// this emoji.
+    pub async fn add(
+        &self,
+        dialog_id: Uuid,
+        message_id: Uuid,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"INSERT INTO message_reactions (message_id, user_id, emoji, dialog_id)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (message_id, user_id, emoji) DO NOTHING"#,
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .bind(emoji)
+        .bind(dialog_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a reaction. Returns true if the user had reacted with this emoji.
+    pub async fn remove(
+        &self,
+        dialog_id: Uuid,
+        message_id: Uuid,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"DELETE FROM message_reactions
+               WHERE message_id = $1 AND user_id = $2 AND emoji = $3 AND dialog_id = $4"#,
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .bind(emoji)
+        .bind(dialog_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Reactions to a message per emoji, in the order they were first used
+    pub async fn summarize(
+        &self,
+        message_id: Uuid,
+        user_id: &UserId,
+    ) -> Result<Vec<ReactionSummary>, sqlx::Error> {
+        sqlx::query_as::<_, ReactionSummary>(
+            r#"SELECT emoji, COUNT(*) AS count, BOOL_OR(user_id = $2) AS reacted
+               FROM message_reactions
+               WHERE message_id = $1
+               GROUP BY emoji
+               ORDER BY MIN(reacted_at), emoji"#,
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -1,9 +1,13 @@
 //! Message repository
 
-use sqlx::PgPool;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::domain::Message;
+use crate::domain::{
+    Message, MessageDayCount, MessagePriority, ReplyPreview, StoredContent, REPLY_PREVIEW_CHARS,
+};
 
 pub struct MessageRepository {
     pool: PgPool,
@@ -16,18 +20,25 @@ impl MessageRepository {
 
     /// Create a new message (user or system)
     pub async fn create(&self, message: &Message) -> Result<Message, sqlx::Error> {
+        let stored = message.stored_content();
         sqlx::query_as::<_, Message>(
-            r#"INSERT INTO messages (id, dialog_id, sender_id, content, sent_at, reply_to_id, message_type)
-               VALUES ($1, $2, $3, $4, $5, $6, $7)
+            r#"INSERT INTO messages (id, dialog_id, sender_id, content, content_encoding, content_compressed, sent_at, reply_to_id, message_type, metadata, content_blocks, external_id, content_search_vector)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, strip(to_tsvector('simple', $13)))
                RETURNING *"#,
         )
         .bind(message.id)
         .bind(message.dialog_id)
         .bind(&message.sender_id)
-        .bind(&message.content)
+        .bind(&stored.content)
+        .bind(stored.encoding)
+        .bind(&stored.compressed)
         .bind(message.sent_at)
         .bind(message.reply_to_id)
         .bind(message.message_type.as_str())
+        .bind(&message.metadata)
+        .bind(&message.content_blocks)
+        .bind(&message.external_id)
+        .bind(&stored.search_text)
         .fetch_one(&self.pool)
         .await
     }
@@ -40,6 +51,17 @@ impl MessageRepository {
             .await
     }
 
+    /// Find message by its external ID
+    pub async fn find_by_external_id(
+        &self,
+        external_id: &str,
+    ) -> Result<Option<Message>, sqlx::Error> {
+        sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE external_id = $1")
+            .bind(external_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
     /// Find message by ID and dialog (for access control)
     pub async fn find_by_id_and_dialog(
         &self,
@@ -53,6 +75,62 @@ impl MessageRepository {
             .await
     }
 
+    /// Find messages of any dialogs by IDs (missing IDs are skipped)
+    pub async fn find_many(&self, ids: &[Uuid]) -> Result<Vec<Message>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Find messages of a dialog by IDs (missing IDs are skipped)
+    pub async fn find_by_ids(
+        &self,
+        dialog_id: Uuid,
+        ids: &[Uuid],
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE dialog_id = $1 AND id = ANY($2)")
+            .bind(dialog_id)
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Search messages of the dialogs a user participates in by content
+    /// (case-insensitive substring; all words of the query for compressed
+    /// messages), newest first. `before` is the ID of the
+    /// last message of the previous page.
+    pub async fn search(
+        &self,
+        user_id: &str,
+        query: &str,
+        dialog_id: Option<Uuid>,
+        limit: i64,
+        before: Option<Uuid>,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        sqlx::query_as::<_, Message>(
+            r#"SELECT m.* FROM messages m
+               JOIN dialog_participants dp ON dp.dialog_id = m.dialog_id AND dp.user_id = $1
+               JOIN dialogs d ON d.id = m.dialog_id AND d.deleted_at IS NULL
+               WHERE (m.content ILIKE '%' || $2 || '%'
+                      OR m.content_search_vector @@ plainto_tsquery('simple', $2))
+                 AND ($3::uuid IS NULL OR m.dialog_id = $3)
+                 AND ($5::uuid IS NULL OR m.id < $5)
+               ORDER BY m.id DESC
+               LIMIT $4"#,
+        )
+        .bind(user_id)
+        .bind(query)
+        .bind(dialog_id)
+        .bind(limit)
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     /// List messages in a dialog with pagination
     pub async fn list_by_dialog(
         &self,
@@ -89,6 +167,98 @@ impl MessageRepository {
         Ok(messages.into_iter().rev().collect())
     }
 
+    /// List the latest messages of a priority (before a message, if given)
+    /// in chronological order
+    pub async fn list_by_priority(
+        &self,
+        dialog_id: Uuid,
+        priority: MessagePriority,
+        limit: i64,
+        before: Option<Uuid>,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        let messages = sqlx::query_as::<_, Message>(
+            r#"SELECT * FROM messages
+               WHERE dialog_id = $1 AND priority = $2
+                 AND ($3::uuid IS NULL OR id < $3)
+               ORDER BY id DESC
+               LIMIT $4"#,
+        )
+        .bind(dialog_id)
+        .bind(priority.as_str())
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages.into_iter().rev().collect())
+    }
+
+    /// List the first messages of a dialog in chronological order
+    pub async fn list_chronological(
+        &self,
+        dialog_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        sqlx::query_as::<_, Message>(
+            r#"SELECT * FROM messages
+               WHERE dialog_id = $1
+               ORDER BY id ASC
+               LIMIT $2"#,
+        )
+        .bind(dialog_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// First message of a dialog sent on or after `date` (a day in `timezone`),
+    /// the target of a jump to that date
+    pub async fn first_on_or_after_date(
+        &self,
+        dialog_id: Uuid,
+        date: NaiveDate,
+        timezone: &str,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT id FROM messages
+               WHERE dialog_id = $1
+                 AND sent_at >= ($2::date::timestamp AT TIME ZONE $3)
+               ORDER BY id ASC
+               LIMIT 1"#,
+        )
+        .bind(dialog_id)
+        .bind(date)
+        .bind(timezone)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Message counts per day from `from` to `to` (inclusive, days in
+    /// `timezone`). Days without messages are left out.
+    pub async fn count_by_day(
+        &self,
+        dialog_id: Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: &str,
+    ) -> Result<Vec<MessageDayCount>, sqlx::Error> {
+        sqlx::query_as::<_, MessageDayCount>(
+            r#"SELECT (sent_at AT TIME ZONE $4)::date AS date, COUNT(*) AS count
+               FROM messages
+               WHERE dialog_id = $1
+                 AND sent_at >= ($2::date::timestamp AT TIME ZONE $4)
+                 AND sent_at < (($3::date + 1)::timestamp AT TIME ZONE $4)
+               GROUP BY 1
+               ORDER BY 1"#,
+        )
+        .bind(dialog_id)
+        .bind(from)
+        .bind(to)
+        .bind(timezone)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     /// List messages in a dialog after a specific message (for loading newer messages)
     pub async fn list_after(
         &self,
@@ -112,6 +282,109 @@ impl MessageRepository {
         Ok(messages)
     }
 
+    /// List user messages that have direct replies (questions), oldest first
+    ///
+    /// Optionally filtered by the sender's company (name at send time, or the
+    /// current `company_uid` of the participant) and send time range.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_answered(
+        &self,
+        dialog_id: Uuid,
+        company: Option<&str>,
+        company_uid: Option<&str>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        sqlx::query_as::<_, Message>(
+            r#"SELECT q.* FROM messages q
+               WHERE q.dialog_id = $1
+                 AND q.message_type = 'user'
+                 AND EXISTS (SELECT 1 FROM messages r WHERE r.reply_to_id = q.id)
+                 AND ($2::text IS NULL OR q.sender_company = $2)
+                 AND ($3::text IS NULL OR EXISTS (
+                     SELECT 1 FROM dialog_participants dp
+                     WHERE dp.dialog_id = q.dialog_id
+                       AND dp.user_id = q.sender_id
+                       AND dp.company_uid = $3
+                 ))
+                 AND ($4::timestamptz IS NULL OR q.sent_at >= $4)
+                 AND ($5::timestamptz IS NULL OR q.sent_at < $5)
+                 AND ($6::uuid IS NULL OR q.id > $6)
+               ORDER BY q.id ASC
+               LIMIT $7"#,
+        )
+        .bind(dialog_id)
+        .bind(company)
+        .bind(company_uid)
+        .bind(from)
+        .bind(to)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Reply previews of the given messages of a dialog, in one query
+    ///
+    /// IDs without a message in the dialog get a `deleted` preview.
+    pub async fn reply_previews(
+        &self,
+        dialog_id: Uuid,
+        ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, ReplyPreview>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows: Vec<(Uuid, Option<String>, String)> = sqlx::query_as(
+            r#"SELECT id, sender_id, LEFT(content, $3) FROM messages
+               WHERE dialog_id = $1 AND id = ANY($2)"#,
+        )
+        .bind(dialog_id)
+        .bind(ids)
+        .bind(REPLY_PREVIEW_CHARS as i32)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut previews: HashMap<Uuid, ReplyPreview> = rows
+            .into_iter()
+            .map(|(id, sender_id, content)| {
+                let preview = ReplyPreview {
+                    id,
+                    sender_id,
+                    content,
+                    deleted: false,
+                };
+                (id, preview)
+            })
+            .collect();
+        for &id in ids {
+            previews
+                .entry(id)
+                .or_insert_with(|| ReplyPreview::deleted(id));
+        }
+        Ok(previews)
+    }
+
+    /// List direct replies to the given messages, oldest first
+    pub async fn list_replies(
+        &self,
+        dialog_id: Uuid,
+        reply_to_ids: &[Uuid],
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        sqlx::query_as::<_, Message>(
+            r#"SELECT * FROM messages
+               WHERE dialog_id = $1 AND reply_to_id = ANY($2)
+               ORDER BY id ASC"#,
+        )
+        .bind(dialog_id)
+        .bind(reply_to_ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     /// Save old content to edit history before updating
     pub async fn save_edit_history(
         &self,
@@ -136,14 +409,20 @@ impl MessageRepository {
         id: Uuid,
         content: &str,
     ) -> Result<Option<Message>, sqlx::Error> {
+        let stored = StoredContent::encode(content);
         sqlx::query_as::<_, Message>(
             r#"UPDATE messages
-               SET content = $2, last_edited_at = NOW()
+               SET content = $2, content_encoding = $3, content_compressed = $4,
+                   content_search_vector = strip(to_tsvector('simple', $5)),
+                   last_edited_at = NOW(), version = version + 1
                WHERE id = $1
                RETURNING *"#,
         )
         .bind(id)
-        .bind(content)
+        .bind(&stored.content)
+        .bind(stored.encoding)
+        .bind(&stored.compressed)
+        .bind(&stored.search_text)
         .fetch_optional(&self.pool)
         .await
     }
@@ -157,6 +436,25 @@ impl MessageRepository {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Delete a message inside the caller's transaction
+    ///
+    /// Returns the total size of its attachments (removed by cascade), or
+    /// `None` if the message was already gone.
+    pub async fn delete_with_attachments_size(
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"DELETE FROM messages m
+               WHERE m.id = $1
+               RETURNING (SELECT COALESCE(SUM(a.size), 0)::BIGINT
+                          FROM attachments a WHERE a.message_id = m.id)"#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+    }
+
     /// Count messages in a dialog
     pub async fn count_by_dialog(&self, dialog_id: Uuid) -> Result<i64, sqlx::Error> {
         let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages WHERE dialog_id = $1")
@@ -166,6 +464,22 @@ impl MessageRepository {
         Ok(count)
     }
 
+    /// Time of each sender's latest message in a dialog
+    pub async fn last_sent_at_by_sender(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<HashMap<String, DateTime<Utc>>, sqlx::Error> {
+        let rows: Vec<(String, DateTime<Utc>)> = sqlx::query_as(
+            r#"SELECT sender_id, MAX(sent_at) FROM messages
+               WHERE dialog_id = $1 AND sender_id IS NOT NULL
+               GROUP BY sender_id"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().collect())
+    }
+
     /// Count unread messages for a user in a dialog
     pub async fn count_unread(
         &self,
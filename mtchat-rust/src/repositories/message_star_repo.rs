@@ -0,0 +1,111 @@
+//! Message star repository
+
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::StarredMessage;
+
+/// Type alias for external user identifier
+type UserId = str;
+
+pub struct MessageStarRepository {
+    pool: PgPool,
+}
+
+impl MessageStarRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Star a message (idempotent)
+    pub async fn star(
+        &self,
+        user_id: &UserId,
+        dialog_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO message_stars (user_id, message_id, dialog_id)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (user_id, message_id) DO NOTHING"#,
+        )
+        .bind(user_id)
+        .bind(message_id)
+        .bind(dialog_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a star of a message of `dialog_id`. Returns true if the
+    /// message was starred.
+    pub async fn unstar(
+        &self,
+        user_id: &UserId,
+        dialog_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM message_stars WHERE user_id = $1 AND message_id = $2 AND dialog_id = $3",
+        )
+        .bind(user_id)
+        .bind(message_id)
+        .bind(dialog_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Which of the given messages the user starred (batch, for message lists)
+    pub async fn starred_among(
+        &self,
+        user_id: &UserId,
+        message_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, sqlx::Error> {
+        if message_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let rows: Vec<(Uuid,)> = sqlx::query_as(
+            "SELECT message_id FROM message_stars WHERE user_id = $1 AND message_id = ANY($2)",
+        )
+        .bind(user_id)
+        .bind(message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Starred messages of a user, newest star first.
+    ///
+    /// Only messages of dialogs the user still participates in are returned.
+    /// `before` is the message ID of the last item of the previous page.
+    pub async fn list_for_user(
+        &self,
+        user_id: &UserId,
+        limit: i64,
+        before: Option<Uuid>,
+    ) -> Result<Vec<StarredMessage>, sqlx::Error> {
+        sqlx::query_as::<_, StarredMessage>(
+            r#"SELECT m.*, s.starred_at
+               FROM message_stars s
+               JOIN messages m ON m.id = s.message_id
+               JOIN dialog_participants dp ON dp.dialog_id = s.dialog_id AND dp.user_id = s.user_id
+               JOIN dialogs d ON d.id = s.dialog_id AND d.deleted_at IS NULL
+               WHERE s.user_id = $1
+                 AND ($3::uuid IS NULL OR (s.starred_at, s.message_id) < (
+                     SELECT starred_at, message_id FROM message_stars
+                     WHERE user_id = $1 AND message_id = $3
+                 ))
+               ORDER BY s.starred_at DESC, s.message_id DESC
+               LIMIT $2"#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -3,13 +3,49 @@
 //! Each repository handles CRUD operations for a specific entity.
 
 mod attachment_repo;
+mod audit_log_repo;
+mod ban_repo;
+mod dialog_activity_repo;
+mod dialog_event_repo;
+mod dialog_folder_repo;
+mod dialog_notes_repo;
 mod dialog_repo;
+mod dialog_template_repo;
+mod export_repo;
+mod feature_flag_repo;
+mod inbound_event_repo;
+mod invite_repo;
+mod job_dead_letter_repo;
+mod message_reaction_repo;
 mod message_repo;
+mod message_star_repo;
 mod participant_repo;
 mod scope_repo;
+mod settings_repo;
+mod sla_repo;
+mod storage_usage_repo;
+mod tenant_settings_repo;
 
 pub use attachment_repo::AttachmentRepository;
-pub use dialog_repo::DialogRepository;
+pub use audit_log_repo::AuditLogRepository;
+pub use ban_repo::DialogBanRepository;
+pub use dialog_activity_repo::DialogActivityRepository;
+pub use dialog_event_repo::DialogEventRepository;
+pub use dialog_folder_repo::DialogFolderRepository;
+pub use dialog_notes_repo::DialogNotesRepository;
+pub use dialog_repo::{DialogChildren, DialogReadState, DialogRepository, ListedDialog};
+pub use dialog_template_repo::DialogTemplateRepository;
+pub use export_repo::ExportRepository;
+pub use feature_flag_repo::FeatureFlagRepository;
+pub use inbound_event_repo::{InboundEventClaim, InboundEventRepository};
+pub use invite_repo::{ActivatedInvite, ParticipantInviteRepository};
+pub use job_dead_letter_repo::JobDeadLetterRepository;
+pub use message_reaction_repo::MessageReactionRepository;
 pub use message_repo::MessageRepository;
-pub use participant_repo::ParticipantRepository;
+pub use message_star_repo::MessageStarRepository;
+pub use participant_repo::{ParticipantRepository, UnreadRepair};
 pub use scope_repo::AccessScopeRepository;
+pub use settings_repo::SettingsRepository;
+pub use sla_repo::{SlaCheck, SlaRepository};
+pub use storage_usage_repo::StorageUsageRepository;
+pub use tenant_settings_repo::TenantSettingsRepository;
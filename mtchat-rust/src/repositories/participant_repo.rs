@@ -1,13 +1,25 @@
 //! Participant repository
 
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::domain::{DialogParticipant, JoinedAs, ParticipantProfile};
+use crate::domain::{
+    BulkDialogAction, DialogParticipant, JoinedAs, ParticipantProfile, ParticipantSort,
+};
 
 /// Type alias for external user identifier
 type UserId = str;
 
+/// An `unread_count` corrected by [`ParticipantRepository::reconcile_unread`]
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnreadRepair {
+    pub dialog_id: Uuid,
+    pub user_id: String,
+    pub old_count: i32,
+    pub new_count: i32,
+}
+
 pub struct ParticipantRepository {
     pool: PgPool,
 }
@@ -46,8 +58,8 @@ impl ParticipantRepository {
     ) -> Result<DialogParticipant, sqlx::Error> {
         sqlx::query_as::<_, DialogParticipant>(
             r#"INSERT INTO dialog_participants
-               (dialog_id, user_id, joined_as, joined_at, display_name, company, email, phone)
-               VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7)
+               (dialog_id, user_id, joined_as, joined_at, display_name, company, company_uid, email, phone)
+               VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7, $8)
                RETURNING *"#,
         )
         .bind(dialog_id)
@@ -55,6 +67,7 @@ impl ParticipantRepository {
         .bind(&joined_as)
         .bind(&profile.display_name)
         .bind(&profile.company)
+        .bind(&profile.company_uid)
         .bind(&profile.email)
         .bind(&profile.phone)
         .fetch_one(&self.pool)
@@ -82,17 +95,19 @@ impl ParticipantRepository {
     }
 
     /// Add participant with profile, ignore if already exists
+    ///
+    /// Returns false if the user already participated.
     pub async fn add_with_profile_if_not_exists(
         &self,
         dialog_id: Uuid,
         user_id: &UserId,
         joined_as: JoinedAs,
         profile: &ParticipantProfile,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query(
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
             r#"INSERT INTO dialog_participants
-               (dialog_id, user_id, joined_as, joined_at, display_name, company, email, phone)
-               VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7)
+               (dialog_id, user_id, joined_as, joined_at, display_name, company, company_uid, email, phone)
+               VALUES ($1, $2, $3, NOW(), $4, $5, $6, $7, $8)
                ON CONFLICT (dialog_id, user_id) DO NOTHING"#,
         )
         .bind(dialog_id)
@@ -100,11 +115,12 @@ impl ParticipantRepository {
         .bind(&joined_as)
         .bind(&profile.display_name)
         .bind(&profile.company)
+        .bind(&profile.company_uid)
         .bind(&profile.email)
         .bind(&profile.phone)
         .execute(&self.pool)
         .await?;
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
 
     /// Remove a participant from a dialog
@@ -118,10 +134,50 @@ impl ParticipantRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// Check if user is a participant
+    /// Schedule (or with `None`, cancel) the removal of a participant
+    pub async fn set_pending_removal(
+        &self,
+        dialog_id: Uuid,
+        user_id: &UserId,
+        at: Option<DateTime<Utc>>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"UPDATE dialog_participants
+               SET pending_removal_at = $3
+               WHERE dialog_id = $1 AND user_id = $2"#,
+        )
+        .bind(dialog_id)
+        .bind(user_id)
+        .bind(at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove participants whose grace period has ended, returning them
+    pub async fn remove_due(&self, limit: i64) -> Result<Vec<DialogParticipant>, sqlx::Error> {
+        sqlx::query_as::<_, DialogParticipant>(
+            r#"DELETE FROM dialog_participants
+               WHERE (dialog_id, user_id) IN (
+                   SELECT dialog_id, user_id FROM dialog_participants
+                   WHERE pending_removal_at <= NOW()
+                   ORDER BY pending_removal_at
+                   LIMIT $1
+                   FOR UPDATE SKIP LOCKED
+               )
+               RETURNING *"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Check if user is a participant (of a dialog that is not deleted)
     pub async fn exists(&self, dialog_id: Uuid, user_id: &UserId) -> Result<bool, sqlx::Error> {
         let result: Option<(i32,)> = sqlx::query_as(
-            "SELECT 1 FROM dialog_participants WHERE dialog_id = $1 AND user_id = $2",
+            r#"SELECT 1 FROM dialog_participants dp
+               INNER JOIN dialogs d ON d.id = dp.dialog_id AND d.deleted_at IS NULL
+               WHERE dp.dialog_id = $1 AND dp.user_id = $2"#,
         )
         .bind(dialog_id)
         .bind(user_id)
@@ -179,6 +235,38 @@ impl ParticipantRepository {
         .await
     }
 
+    /// List a page of a dialog's participants. `search` matches display name
+    /// or company (case-insensitive substring), `user_ids` (when `Some`)
+    /// limits the list to these users. No `limit` lists all.
+    pub async fn list_page(
+        &self,
+        dialog_id: Uuid,
+        search: Option<&str>,
+        user_ids: Option<&[String]>,
+        sort: ParticipantSort,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> Result<Vec<DialogParticipant>, sqlx::Error> {
+        sqlx::query_as::<_, DialogParticipant>(
+            r#"SELECT * FROM dialog_participants
+               WHERE dialog_id = $1
+                 AND ($2::text IS NULL
+                      OR display_name ILIKE '%' || $2 || '%'
+                      OR company ILIKE '%' || $2 || '%')
+                 AND ($3::text[] IS NULL OR user_id = ANY($3))
+               ORDER BY CASE WHEN $4 THEN LOWER(display_name) END, joined_at, user_id
+               LIMIT $5 OFFSET $6"#,
+        )
+        .bind(dialog_id)
+        .bind(search)
+        .bind(user_ids)
+        .bind(sort == ParticipantSort::Name)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     /// List participants for multiple dialogs in one query.
     ///
     /// Returns a map of dialog_id -> participants (ordered by joined_at within
@@ -228,6 +316,26 @@ impl ParticipantRepository {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Snooze notifications until a time (`None` ends the snooze)
+    pub async fn set_snooze(
+        &self,
+        dialog_id: Uuid,
+        user_id: &UserId,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"UPDATE dialog_participants
+               SET snoozed_until = $3
+               WHERE dialog_id = $1 AND user_id = $2"#,
+        )
+        .bind(dialog_id)
+        .bind(user_id)
+        .bind(until)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Update last read message (legacy - use mark_as_read instead)
     pub async fn update_last_read(
         &self,
@@ -268,7 +376,7 @@ impl ParticipantRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// Increment unread_count for all participants except the author
+    /// Increment unread_count for all participants except the author and observers
     pub async fn increment_unread(
         &self,
         dialog_id: Uuid,
@@ -277,7 +385,7 @@ impl ParticipantRepository {
         let result = sqlx::query(
             r#"UPDATE dialog_participants
                SET unread_count = unread_count + 1
-               WHERE dialog_id = $1 AND user_id != $2"#,
+               WHERE dialog_id = $1 AND user_id != $2 AND joined_as <> 'observer'"#,
         )
         .bind(dialog_id)
         .bind(exclude_user_id)
@@ -304,7 +412,140 @@ impl ParticipantRepository {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Set or clear the avatar of a participant, resetting its variants.
+    ///
+    /// Returns the previous avatar key (`Ok(None)` also when the participant
+    /// doesn't exist; check with [`find`](Self::find) first).
+    pub async fn set_avatar(
+        &self,
+        dialog_id: Uuid,
+        user_id: &UserId,
+        avatar_s3_key: Option<&str>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"UPDATE dialog_participants dp
+               SET avatar_s3_key = $3, avatar_variants_ready = false
+               FROM dialog_participants old
+               WHERE dp.dialog_id = $1 AND dp.user_id = $2
+                 AND old.dialog_id = dp.dialog_id AND old.user_id = dp.user_id
+               RETURNING old.avatar_s3_key"#,
+        )
+        .bind(dialog_id)
+        .bind(user_id)
+        .bind(avatar_s3_key)
+        .fetch_optional(&self.pool)
+        .await
+        .map(Option::flatten)
+    }
+
+    /// Mark the variants of an avatar as rendered.
+    ///
+    /// No-op if the participant changed the avatar in the meantime.
+    pub async fn set_avatar_variants_ready(
+        &self,
+        dialog_id: Uuid,
+        user_id: &UserId,
+        avatar_s3_key: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"UPDATE dialog_participants
+               SET avatar_variants_ready = true
+               WHERE dialog_id = $1 AND user_id = $2 AND avatar_s3_key = $3"#,
+        )
+        .bind(dialog_id)
+        .bind(user_id)
+        .bind(avatar_s3_key)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Avatar keys of all participants of a dialog
+    pub async fn list_avatar_keys_by_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT avatar_s3_key FROM dialog_participants
+               WHERE dialog_id = $1 AND avatar_s3_key IS NOT NULL"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Recompute unread counters of the participants of a batch of dialogs.
+    ///
+    /// Takes up to `limit` dialogs with ids after `after` (keyset pagination)
+    /// and returns the repaired counters and the last dialog id of the batch
+    /// (`None` when there are no dialogs left).
+    ///
+    /// Not every system message is counted as unread (join/leave notices are
+    /// not), so a counter is only considered drifted when it falls outside the
+    /// range of messages after `last_read_message_id` that the participant did
+    /// not send: at least the user messages, at most all messages. Without a
+    /// last read message (never read, or it was deleted) only the upper bound
+    /// applies. Observers keep no counter (the range is 0). Drifted counters
+    /// are clamped into that range. Rows that changed since the counts were
+    /// taken (a message arrived, the dialog was read) are skipped.
+    pub async fn reconcile_unread(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<(Vec<UnreadRepair>, Option<Uuid>), sqlx::Error> {
+        let dialog_ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"SELECT id FROM dialogs
+               WHERE $1::uuid IS NULL OR id > $1
+               ORDER BY id
+               LIMIT $2"#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let Some(&last) = dialog_ids.last() else {
+            return Ok((Vec::new(), None));
+        };
+
+        let repairs = sqlx::query_as::<_, UnreadRepair>(
+            r#"WITH expected AS (
+                   SELECT dp.dialog_id, dp.user_id, dp.unread_count,
+                          CASE WHEN dp.last_read_message_id IS NULL
+                                    OR dp.joined_as = 'observer' THEN 0
+                               ELSE COUNT(m.id) FILTER (WHERE m.message_type = 'user')
+                          END::int AS min_unread,
+                          CASE WHEN dp.joined_as = 'observer' THEN 0
+                               ELSE COUNT(m.id)
+                          END::int AS max_unread
+                   FROM dialog_participants dp
+                   LEFT JOIN messages lr ON lr.id = dp.last_read_message_id
+                   LEFT JOIN messages m ON m.dialog_id = dp.dialog_id
+                       AND m.seq > COALESCE(lr.seq, 0)
+                       AND m.sender_id IS DISTINCT FROM dp.user_id
+                   WHERE dp.dialog_id = ANY($1)
+                   GROUP BY dp.dialog_id, dp.user_id, dp.unread_count, dp.last_read_message_id,
+                            dp.joined_as
+               )
+               UPDATE dialog_participants dp
+               SET unread_count = LEAST(GREATEST(dp.unread_count, e.min_unread), e.max_unread)
+               FROM expected e
+               WHERE dp.dialog_id = e.dialog_id AND dp.user_id = e.user_id
+                 AND dp.unread_count = e.unread_count
+                 AND (dp.unread_count < e.min_unread OR dp.unread_count > e.max_unread)
+               RETURNING dp.dialog_id, dp.user_id, e.unread_count AS old_count,
+                         dp.unread_count AS new_count"#,
+        )
+        .bind(&dialog_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((repairs, Some(last)))
+    }
+
     /// Archive or unarchive a dialog for a specific user
+    ///
+    /// Returns false if the participation already had that state.
     pub async fn set_archived(
         &self,
         dialog_id: Uuid,
@@ -314,7 +555,7 @@ impl ParticipantRepository {
         let result = sqlx::query(
             r#"UPDATE dialog_participants
                SET is_archived = $3
-               WHERE dialog_id = $1 AND user_id = $2"#,
+               WHERE dialog_id = $1 AND user_id = $2 AND is_archived <> $3"#,
         )
         .bind(dialog_id)
         .bind(user_id)
@@ -324,6 +565,47 @@ impl ParticipantRepository {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Apply a bulk action to the user's participations in one statement.
+    ///
+    /// Dialogs the user does not participate in are left out. Returns the
+    /// updated dialog IDs.
+    pub async fn apply_bulk_action(
+        &self,
+        user_id: &UserId,
+        dialog_ids: &[Uuid],
+        action: BulkDialogAction,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let set = match action {
+            BulkDialogAction::Archive => "is_archived = true",
+            BulkDialogAction::Unarchive => "is_archived = false",
+            BulkDialogAction::Pin => "is_pinned = true",
+            BulkDialogAction::Unpin => "is_pinned = false",
+            BulkDialogAction::Mute => "notifications_enabled = false",
+            BulkDialogAction::Unmute => "notifications_enabled = true",
+            BulkDialogAction::MarkRead => {
+                r#"unread_count = 0,
+                   last_read_message_id = COALESCE(
+                     (SELECT m.id FROM messages m
+                      WHERE m.dialog_id = dp.dialog_id
+                      ORDER BY m.sent_at DESC, m.id DESC
+                      LIMIT 1),
+                     dp.last_read_message_id
+                   )"#
+            }
+        };
+
+        sqlx::query_scalar(&format!(
+            r#"UPDATE dialog_participants dp
+               SET {set}
+               WHERE dp.user_id = $1 AND dp.dialog_id = ANY($2)
+               RETURNING dp.dialog_id"#
+        ))
+        .bind(user_id)
+        .bind(dialog_ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     /// Get all dialog IDs that a user participates in
     pub async fn get_user_dialogs(&self, user_id: &UserId) -> Result<Vec<Uuid>, sqlx::Error> {
         sqlx::query_scalar("SELECT dialog_id FROM dialog_participants WHERE user_id = $1")
@@ -350,6 +632,8 @@ impl ParticipantRepository {
     }
 
     /// Pin or unpin a dialog for a specific user
+    ///
+    /// Returns false if the participation already had that state.
     pub async fn set_pinned(
         &self,
         dialog_id: Uuid,
@@ -359,7 +643,7 @@ impl ParticipantRepository {
         let result = sqlx::query(
             r#"UPDATE dialog_participants
                SET is_pinned = $3
-               WHERE dialog_id = $1 AND user_id = $2"#,
+               WHERE dialog_id = $1 AND user_id = $2 AND is_pinned <> $3"#,
         )
         .bind(dialog_id)
         .bind(user_id)
@@ -371,31 +655,37 @@ impl ParticipantRepository {
 
     /// Archive dialog for all participants.
     ///
-    /// Used by auto-archive job. Returns number of participants affected.
-    pub async fn archive_all_for_dialog(&self, dialog_id: Uuid) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query(
+    /// Used by auto-archive job. Returns the participants whose dialog was archived.
+    pub async fn archive_all_for_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
             r#"UPDATE dialog_participants
                SET is_archived = true
-               WHERE dialog_id = $1 AND is_archived = false"#,
+               WHERE dialog_id = $1 AND is_archived = false
+               RETURNING user_id"#,
         )
         .bind(dialog_id)
-        .execute(&self.pool)
-        .await?;
-        Ok(result.rows_affected())
+        .fetch_all(&self.pool)
+        .await
     }
 
     /// Unarchive dialog for all participants.
     ///
-    /// Used for auto-unarchive on new message. Returns number of participants affected.
-    pub async fn unarchive_all_for_dialog(&self, dialog_id: Uuid) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query(
+    /// Returns the participants whose dialog was unarchived.
+    pub async fn unarchive_all_for_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
             r#"UPDATE dialog_participants
                SET is_archived = false
-               WHERE dialog_id = $1 AND is_archived = true"#,
+               WHERE dialog_id = $1 AND is_archived = true
+               RETURNING user_id"#,
         )
         .bind(dialog_id)
-        .execute(&self.pool)
-        .await?;
-        Ok(result.rows_affected())
+        .fetch_all(&self.pool)
+        .await
     }
 }
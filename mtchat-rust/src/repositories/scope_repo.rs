@@ -4,6 +4,7 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::domain::DialogAccessScope;
+use crate::middleware::begin_transaction;
 
 pub struct AccessScopeRepository {
     pool: PgPool,
@@ -14,26 +15,6 @@ impl AccessScopeRepository {
         Self { pool }
     }
 
-    /// Create a new access scope
-    pub async fn create(
-        &self,
-        scope: &DialogAccessScope,
-    ) -> Result<DialogAccessScope, sqlx::Error> {
-        sqlx::query_as::<_, DialogAccessScope>(
-            r#"INSERT INTO dialog_access_scopes (id, dialog_id, scope_level0, scope_level1, scope_level2, created_at)
-               VALUES ($1, $2, $3, $4, $5, $6)
-               RETURNING *"#,
-        )
-        .bind(scope.id)
-        .bind(scope.dialog_id)
-        .bind(&scope.scope_level0)
-        .bind(&scope.scope_level1)
-        .bind(&scope.scope_level2)
-        .bind(scope.created_at)
-        .fetch_one(&self.pool)
-        .await
-    }
-
     /// Find all scopes for a dialog
     pub async fn find_by_dialog(
         &self,
@@ -53,9 +34,12 @@ impl AccessScopeRepository {
     /// - scope_level0: empty array in DB = wildcard (match all), otherwise requires overlap
     /// - scope_level1: empty array in DB = wildcard (match all), otherwise requires overlap
     /// - scope_level2: empty array in DB = wildcard (match all), otherwise requires overlap
+    ///
+    /// A user banned from the dialog has no scope access.
     pub async fn check_access(
         &self,
         dialog_id: Uuid,
+        user_id: &str,
         scope_level0: &[String],
         scope_level1: &[String],
         scope_level2: &[String],
@@ -65,17 +49,64 @@ impl AccessScopeRepository {
                WHERE dialog_id = $1
                  AND (scope_level0 = '{}' OR scope_level0 && $2)
                  AND (scope_level1 = '{}' OR scope_level1 && $3)
-                 AND (scope_level2 = '{}' OR scope_level2 && $4)"#,
+                 AND (scope_level2 = '{}' OR scope_level2 && $4)
+                 AND NOT EXISTS (
+                   SELECT 1 FROM dialog_bans b
+                   WHERE b.dialog_id = $1 AND b.user_id = $5
+                     AND (b.expires_at IS NULL OR b.expires_at > NOW())
+                 )"#,
         )
         .bind(dialog_id)
         .bind(scope_level0)
         .bind(scope_level1)
         .bind(scope_level2)
+        .bind(user_id)
         .fetch_optional(&self.pool)
         .await?;
         Ok(result.is_some())
     }
 
+    /// Dialogs among `dialog_ids` the user can access, as a participant or
+    /// through scope access ([`Self::check_access`]). One query for all.
+    pub async fn filter_accessible(
+        &self,
+        dialog_ids: &[Uuid],
+        user_id: &str,
+        scope_level0: &[String],
+        scope_level1: &[String],
+        scope_level2: &[String],
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let rows: Vec<(Uuid,)> = sqlx::query_as(
+            r#"SELECT d.id FROM dialogs d
+               WHERE d.id = ANY($1) AND d.deleted_at IS NULL
+                 AND (EXISTS (
+                        SELECT 1 FROM dialog_participants p
+                        WHERE p.dialog_id = d.id AND p.user_id = $2
+                      )
+                      OR (EXISTS (
+                            SELECT 1 FROM dialog_access_scopes s
+                            WHERE s.dialog_id = d.id
+                              AND (s.scope_level0 = '{}' OR s.scope_level0 && $3)
+                              AND (s.scope_level1 = '{}' OR s.scope_level1 && $4)
+                              AND (s.scope_level2 = '{}' OR s.scope_level2 && $5)
+                          )
+                          AND NOT EXISTS (
+                            SELECT 1 FROM dialog_bans b
+                            WHERE b.dialog_id = d.id AND b.user_id = $2
+                              AND (b.expires_at IS NULL OR b.expires_at > NOW())
+                          )))
+               ORDER BY d.id"#,
+        )
+        .bind(dialog_ids)
+        .bind(user_id)
+        .bind(scope_level0)
+        .bind(scope_level1)
+        .bind(scope_level2)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
     /// Delete all scopes for a dialog
     pub async fn delete_by_dialog(&self, dialog_id: Uuid) -> Result<u64, sqlx::Error> {
         let result = sqlx::query("DELETE FROM dialog_access_scopes WHERE dialog_id = $1")
@@ -85,13 +116,39 @@ impl AccessScopeRepository {
         Ok(result.rows_affected())
     }
 
-    /// Replace all scopes for a dialog (delete + insert) atomically
+    /// Replace all scopes for a dialog (delete + insert) atomically, moving
+    /// the dialog's storage usage from its old tenants to the new ones
     pub async fn replace_for_dialog(
         &self,
         dialog_id: Uuid,
         scopes: Vec<DialogAccessScope>,
     ) -> Result<Vec<DialogAccessScope>, sqlx::Error> {
-        let mut tx = self.pool.begin().await?;
+        let mut tx = begin_transaction(&self.pool).await?;
+
+        // The dialog's storage usage moves from its old tenants to the new ones
+        let used_bytes: Option<i64> = sqlx::query_scalar(
+            r#"SELECT used_bytes FROM storage_usage
+               WHERE scope_type = 'dialog' AND scope_id = $1::text
+               FOR UPDATE"#,
+        )
+        .bind(dialog_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let used_bytes = used_bytes.unwrap_or(0);
+        if used_bytes > 0 {
+            sqlx::query(
+                r#"UPDATE storage_usage
+                   SET used_bytes = GREATEST(used_bytes - $2, 0), updated_at = NOW()
+                   WHERE scope_type = 'tenant'
+                     AND scope_id IN (
+                         SELECT unnest(scope_level0) FROM dialog_access_scopes WHERE dialog_id = $1
+                     )"#,
+            )
+            .bind(dialog_id)
+            .bind(used_bytes)
+            .execute(&mut *tx)
+            .await?;
+        }
 
         // Delete existing
         sqlx::query("DELETE FROM dialog_access_scopes WHERE dialog_id = $1")
@@ -118,6 +175,22 @@ impl AccessScopeRepository {
             result.push(created);
         }
 
+        if used_bytes > 0 {
+            sqlx::query(
+                r#"INSERT INTO storage_usage (scope_type, scope_id, used_bytes)
+                   SELECT DISTINCT 'tenant', tenant, $2::bigint
+                   FROM dialog_access_scopes, unnest(scope_level0) AS tenant
+                   WHERE dialog_id = $1
+                   ON CONFLICT (scope_type, scope_id) DO UPDATE
+                   SET used_bytes = storage_usage.used_bytes + EXCLUDED.used_bytes,
+                       updated_at = NOW()"#,
+            )
+            .bind(dialog_id)
+            .bind(used_bytes)
+            .execute(&mut *tx)
+            .await?;
+        }
+
         tx.commit().await?;
         Ok(result)
     }
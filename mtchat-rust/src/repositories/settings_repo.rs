@@ -0,0 +1,50 @@
+//! Runtime settings repository
+
+use sqlx::PgPool;
+
+use crate::domain::Setting;
+
+pub struct SettingsRepository {
+    pool: PgPool,
+}
+
+impl SettingsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List all stored overrides
+    pub async fn list(&self) -> Result<Vec<Setting>, sqlx::Error> {
+        sqlx::query_as::<_, Setting>("SELECT * FROM settings ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Insert or replace an override
+    pub async fn upsert(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<Setting, sqlx::Error> {
+        sqlx::query_as::<_, Setting>(
+            r#"INSERT INTO settings (key, value)
+               VALUES ($1, $2)
+               ON CONFLICT (key) DO UPDATE
+               SET value = EXCLUDED.value, updated_at = NOW()
+               RETURNING *"#,
+        )
+        .bind(key)
+        .bind(value)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Remove an override. Returns true if one existed.
+    pub async fn delete(&self, key: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM settings WHERE key = $1")
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
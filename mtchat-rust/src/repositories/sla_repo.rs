@@ -0,0 +1,294 @@
+//! Response time SLA repository
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    DialogSlaState, SlaPolicy, SlaSource, SlaStatus, SlaTarget, UnansweredMessage,
+};
+use crate::middleware::begin_transaction;
+
+/// Dialog with an SLA, as seen by the SLA job
+#[derive(Debug, Clone)]
+pub struct SlaCheck {
+    pub dialog_id: Uuid,
+    pub last_message_seq: i64,
+    pub target: SlaTarget,
+    /// Company held to the SLA (None = the company of the dialog's creator)
+    pub responder_company: Option<String>,
+    /// What the previous run saw (None before the first run)
+    pub state: Option<DialogSlaState>,
+}
+
+#[derive(FromRow)]
+struct SlaCheckRow {
+    dialog_id: Uuid,
+    last_message_seq: i64,
+    sla_response_secs: Option<i32>,
+    sla_warning_secs: Option<i32>,
+    policy_response_secs: Option<i32>,
+    policy_warning_secs: Option<i32>,
+    responder_company: Option<String>,
+    checked_seq: Option<i64>,
+    message_id: Option<Uuid>,
+    sender_id: Option<String>,
+    pending_since: Option<DateTime<Utc>>,
+    warned_at: Option<DateTime<Utc>>,
+    breached_at: Option<DateTime<Utc>>,
+}
+
+impl From<SlaCheckRow> for SlaCheck {
+    fn from(row: SlaCheckRow) -> Self {
+        let target = match row.sla_response_secs {
+            Some(response_secs) => {
+                SlaTarget::new(response_secs, row.sla_warning_secs, SlaSource::Dialog)
+            }
+            None => SlaTarget::new(
+                row.policy_response_secs.unwrap_or_default(),
+                row.policy_warning_secs,
+                SlaSource::ObjectType,
+            ),
+        };
+        let state = row.checked_seq.map(|checked_seq| DialogSlaState {
+            dialog_id: row.dialog_id,
+            checked_seq,
+            message_id: row.message_id,
+            sender_id: row.sender_id,
+            pending_since: row.pending_since,
+            warned_at: row.warned_at,
+            breached_at: row.breached_at,
+        });
+        Self {
+            dialog_id: row.dialog_id,
+            last_message_seq: row.last_message_seq,
+            target,
+            responder_company: row.responder_company,
+            state,
+        }
+    }
+}
+
+pub struct SlaRepository {
+    pool: PgPool,
+}
+
+impl SlaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the SLA policy of an object type
+    pub async fn find_policy(&self, object_type: &str) -> Result<Option<SlaPolicy>, sqlx::Error> {
+        sqlx::query_as::<_, SlaPolicy>("SELECT * FROM sla_policies WHERE object_type = $1")
+            .bind(object_type)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// List all SLA policies
+    pub async fn list_policies(&self) -> Result<Vec<SlaPolicy>, sqlx::Error> {
+        sqlx::query_as::<_, SlaPolicy>("SELECT * FROM sla_policies ORDER BY object_type")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Create or replace the SLA policy of an object type
+    pub async fn upsert_policy(
+        &self,
+        object_type: &str,
+        response_secs: i32,
+        warning_secs: Option<i32>,
+        responder_company: Option<&str>,
+    ) -> Result<SlaPolicy, sqlx::Error> {
+        let mut tx = begin_transaction(&self.pool).await?;
+        let policy = sqlx::query_as::<_, SlaPolicy>(
+            r#"INSERT INTO sla_policies (object_type, response_secs, warning_secs, responder_company)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (object_type) DO UPDATE
+               SET response_secs = EXCLUDED.response_secs,
+                   warning_secs = EXCLUDED.warning_secs,
+                   responder_company = EXCLUDED.responder_company,
+                   updated_at = NOW()
+               RETURNING *"#,
+        )
+        .bind(object_type)
+        .bind(response_secs)
+        .bind(warning_secs)
+        .bind(responder_company)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // The responding company decides which message is unanswered
+        sqlx::query(
+            r#"DELETE FROM dialog_sla_state s USING dialogs d
+               WHERE d.id = s.dialog_id AND d.object_type = $1
+                 AND d.sla_response_secs IS NULL"#,
+        )
+        .bind(object_type)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(policy)
+    }
+
+    /// Forget what the SLA job saw of a dialog, e.g. after its SLA changed
+    pub async fn clear_state(&self, dialog_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM dialog_sla_state WHERE dialog_id = $1")
+            .bind(dialog_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the SLA policy of an object type. Returns true if it existed.
+    pub async fn delete_policy(&self, object_type: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM sla_policies WHERE object_type = $1")
+            .bind(object_type)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Dialogs (not deleted) with an SLA of their own or of their object
+    /// type, ordered by ID, with what the SLA job saw of them last time
+    pub async fn list_checks(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<SlaCheck>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, SlaCheckRow>(
+            r#"SELECT d.id AS dialog_id, d.last_message_seq,
+                      d.sla_response_secs, d.sla_warning_secs,
+                      p.response_secs AS policy_response_secs,
+                      p.warning_secs AS policy_warning_secs,
+                      CASE WHEN d.sla_response_secs IS NOT NULL
+                           THEN d.sla_responder_company
+                           ELSE p.responder_company
+                      END AS responder_company,
+                      s.checked_seq, s.message_id, s.sender_id, s.pending_since,
+                      s.warned_at, s.breached_at
+               FROM dialogs d
+               LEFT JOIN sla_policies p ON p.object_type = d.object_type
+               LEFT JOIN dialog_sla_state s ON s.dialog_id = d.id
+               WHERE d.deleted_at IS NULL
+                 AND (d.sla_response_secs IS NOT NULL OR p.object_type IS NOT NULL)
+                 AND ($1::uuid IS NULL OR d.id > $1)
+               ORDER BY d.id
+               LIMIT $2"#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(SlaCheck::from).collect())
+    }
+
+    /// Oldest user message from another company than `responder_company`
+    /// written after the responding company's last message. The responding
+    /// company is matched by `company_uid` or name; None stands for the
+    /// company of the dialog's creator (the creator alone if they have none).
+    pub async fn oldest_unanswered(
+        &self,
+        dialog_id: Uuid,
+        responder_company: Option<&str>,
+    ) -> Result<Option<UnansweredMessage>, sqlx::Error> {
+        sqlx::query_as::<_, UnansweredMessage>(
+            r#"WITH responder AS (
+                   SELECT COALESCE($2, (
+                       SELECT COALESCE(p.company_uid, p.company, 'user:' || p.user_id)
+                       FROM dialogs d
+                       INNER JOIN dialog_participants p
+                         ON p.dialog_id = d.id AND p.user_id = d.created_by
+                       WHERE d.id = $1
+                   )) AS company
+               ),
+               sent AS (
+                   SELECT m.id, m.seq, m.sender_id, m.sent_at,
+                          COALESCE(r.company IN (p.company_uid, p.company, m.sender_company,
+                                                 'user:' || m.sender_id), false) AS by_responder
+                   FROM messages m
+                   CROSS JOIN responder r
+                   LEFT JOIN dialog_participants p
+                     ON p.dialog_id = m.dialog_id AND p.user_id = m.sender_id
+                   WHERE m.dialog_id = $1 AND m.message_type = 'user'
+                     AND r.company IS NOT NULL
+               )
+               SELECT id AS message_id, sender_id, sent_at
+               FROM sent
+               WHERE NOT by_responder
+                 AND seq > COALESCE((SELECT MAX(seq) FROM sent WHERE by_responder), 0)
+               ORDER BY seq
+               LIMIT 1"#,
+        )
+        .bind(dialog_id)
+        .bind(responder_company)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Record the oldest unanswered message found at `checked_seq`. The
+    /// escalations sent are kept while it is the same message.
+    pub async fn save_state(
+        &self,
+        dialog_id: Uuid,
+        checked_seq: i64,
+        unanswered: Option<&UnansweredMessage>,
+    ) -> Result<DialogSlaState, sqlx::Error> {
+        sqlx::query_as::<_, DialogSlaState>(
+            r#"INSERT INTO dialog_sla_state (dialog_id, checked_seq, message_id, sender_id, pending_since)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (dialog_id) DO UPDATE
+               SET checked_seq = EXCLUDED.checked_seq,
+                   message_id = EXCLUDED.message_id,
+                   sender_id = EXCLUDED.sender_id,
+                   pending_since = EXCLUDED.pending_since,
+                   warned_at = CASE
+                       WHEN dialog_sla_state.message_id = EXCLUDED.message_id
+                       THEN dialog_sla_state.warned_at
+                   END,
+                   breached_at = CASE
+                       WHEN dialog_sla_state.message_id = EXCLUDED.message_id
+                       THEN dialog_sla_state.breached_at
+                   END
+               RETURNING *"#,
+        )
+        .bind(dialog_id)
+        .bind(checked_seq)
+        .bind(unanswered.map(|m| m.message_id))
+        .bind(unanswered.map(|m| m.sender_id.as_str()))
+        .bind(unanswered.map(|m| m.sent_at))
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Claim sending an escalation (`Warning` or `Breached`) for an
+    /// unanswered message. Returns false if it was already sent or the
+    /// message is no longer the unanswered one.
+    pub async fn claim_escalation(
+        &self,
+        dialog_id: Uuid,
+        message_id: Uuid,
+        status: SlaStatus,
+    ) -> Result<bool, sqlx::Error> {
+        let query = match status {
+            SlaStatus::Warning => {
+                r#"UPDATE dialog_sla_state SET warned_at = NOW()
+                   WHERE dialog_id = $1 AND message_id = $2
+                     AND warned_at IS NULL AND breached_at IS NULL"#
+            }
+            SlaStatus::Breached => {
+                r#"UPDATE dialog_sla_state SET breached_at = NOW()
+                   WHERE dialog_id = $1 AND message_id = $2 AND breached_at IS NULL"#
+            }
+            SlaStatus::Answered | SlaStatus::Pending => return Ok(false),
+        };
+        let result = sqlx::query(query)
+            .bind(dialog_id)
+            .bind(message_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
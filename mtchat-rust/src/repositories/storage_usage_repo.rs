@@ -0,0 +1,178 @@
+//! Storage usage repository
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{StorageScope, StorageUsage};
+
+pub struct StorageUsageRepository {
+    pool: PgPool,
+}
+
+impl StorageUsageRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get usage of a dialog and of every tenant it belongs to
+    ///
+    /// Scopes without a usage row yet are returned with zero usage.
+    pub async fn find_for_dialog(&self, dialog_id: Uuid) -> Result<Vec<StorageUsage>, sqlx::Error> {
+        sqlx::query_as::<_, StorageUsage>(
+            r#"WITH scopes AS (
+                   SELECT 'dialog'::text AS scope_type, $1::text AS scope_id
+                   UNION
+                   SELECT 'tenant', unnest(scope_level0)
+                   FROM dialog_access_scopes WHERE dialog_id = $1
+               )
+               SELECT s.scope_type, s.scope_id,
+                      COALESCE(u.used_bytes, 0) AS used_bytes,
+                      u.quota_bytes,
+                      COALESCE(u.updated_at, NOW()) AS updated_at
+               FROM scopes s
+               LEFT JOIN storage_usage u USING (scope_type, scope_id)
+               ORDER BY s.scope_type, s.scope_id"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Get usage of a single scope (zero usage if it has no row yet)
+    pub async fn find(
+        &self,
+        scope_type: StorageScope,
+        scope_id: &str,
+    ) -> Result<StorageUsage, sqlx::Error> {
+        sqlx::query_as::<_, StorageUsage>(
+            r#"SELECT $1::text AS scope_type, $2::text AS scope_id,
+                      COALESCE(u.used_bytes, 0) AS used_bytes,
+                      u.quota_bytes,
+                      COALESCE(u.updated_at, NOW()) AS updated_at
+               FROM (SELECT 1) AS one
+               LEFT JOIN storage_usage u ON u.scope_type = $1 AND u.scope_id = $2"#,
+        )
+        .bind(scope_type.as_str())
+        .bind(scope_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Lock the usage rows of a dialog and of every tenant it belongs to
+    /// until the caller's transaction ends, creating missing ones
+    ///
+    /// Rows are locked in key order, so concurrent callers sharing a tenant
+    /// wait for each other instead of deadlocking.
+    pub async fn lock_for_dialog(
+        conn: &mut PgConnection,
+        dialog_id: Uuid,
+    ) -> Result<Vec<StorageUsage>, sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO storage_usage (scope_type, scope_id)
+               SELECT 'dialog', $1::text
+               UNION
+               SELECT 'tenant', unnest(scope_level0)
+               FROM dialog_access_scopes WHERE dialog_id = $1
+               ON CONFLICT (scope_type, scope_id) DO NOTHING"#,
+        )
+        .bind(dialog_id)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query_as::<_, StorageUsage>(
+            r#"SELECT * FROM storage_usage
+               WHERE (scope_type = 'dialog' AND scope_id = $1::text)
+                  OR (scope_type = 'tenant' AND scope_id IN (
+                      SELECT unnest(scope_level0) FROM dialog_access_scopes WHERE dialog_id = $1
+                  ))
+               ORDER BY scope_type, scope_id
+               FOR UPDATE"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&mut *conn)
+        .await
+    }
+
+    /// Add `delta` bytes (negative to release) to a dialog and its tenants
+    pub async fn add_bytes(&self, dialog_id: Uuid, delta: i64) -> Result<(), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        Self::add_dialog_bytes(&mut conn, dialog_id, delta).await
+    }
+
+    /// Same as [`Self::add_bytes`] inside the caller's transaction, so the
+    /// usage changes together with the attachments
+    pub async fn add_dialog_bytes(
+        conn: &mut PgConnection,
+        dialog_id: Uuid,
+        delta: i64,
+    ) -> Result<(), sqlx::Error> {
+        if delta == 0 {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"INSERT INTO storage_usage (scope_type, scope_id, used_bytes)
+               SELECT 'dialog', $1::text, GREATEST($2, 0)
+               UNION
+               SELECT 'tenant', tenant, GREATEST($2, 0)
+               FROM dialog_access_scopes, unnest(scope_level0) AS tenant
+               WHERE dialog_id = $1
+               ON CONFLICT (scope_type, scope_id) DO UPDATE
+               SET used_bytes = GREATEST(storage_usage.used_bytes + $2, 0),
+                   updated_at = NOW()"#,
+        )
+        .bind(dialog_id)
+        .bind(delta)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Release all usage of a dialog inside the transaction deleting the
+    /// dialog (before the delete, while its tenants are still known)
+    pub async fn remove_dialog(
+        conn: &mut PgConnection,
+        dialog_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"WITH removed AS (
+                   DELETE FROM storage_usage
+                   WHERE scope_type = 'dialog' AND scope_id = $1::text
+                   RETURNING used_bytes
+               )
+               UPDATE storage_usage u
+               SET used_bytes = GREATEST(u.used_bytes - r.used_bytes, 0),
+                   updated_at = NOW()
+               FROM removed r
+               WHERE u.scope_type = 'tenant'
+                 AND u.scope_id IN (
+                     SELECT unnest(scope_level0) FROM dialog_access_scopes WHERE dialog_id = $1
+                 )"#,
+        )
+        .bind(dialog_id)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Set or clear (None) the quota override of a scope
+    pub async fn set_quota(
+        &self,
+        scope_type: StorageScope,
+        scope_id: &str,
+        quota_bytes: Option<i64>,
+    ) -> Result<StorageUsage, sqlx::Error> {
+        sqlx::query_as::<_, StorageUsage>(
+            r#"INSERT INTO storage_usage (scope_type, scope_id, quota_bytes)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (scope_type, scope_id) DO UPDATE
+               SET quota_bytes = EXCLUDED.quota_bytes, updated_at = NOW()
+               RETURNING *"#,
+        )
+        .bind(scope_type.as_str())
+        .bind(scope_id)
+        .bind(quota_bytes)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,74 @@
+//! Tenant settings repository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{SanitizeProfile, TenantSettings};
+
+pub struct TenantSettingsRepository {
+    pool: PgPool,
+}
+
+impl TenantSettingsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the settings of a tenant
+    pub async fn find(&self, tenant_uid: &str) -> Result<Option<TenantSettings>, sqlx::Error> {
+        sqlx::query_as::<_, TenantSettings>("SELECT * FROM tenant_settings WHERE tenant_uid = $1")
+            .bind(tenant_uid)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Sanitization profiles configured by the tenants of a dialog
+    pub async fn sanitize_profiles_for_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<Vec<SanitizeProfile>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT sanitize_profile FROM tenant_settings
+               WHERE sanitize_profile IS NOT NULL
+                 AND tenant_uid IN (
+                     SELECT unnest(scope_level0) FROM dialog_access_scopes WHERE dialog_id = $1
+                 )"#,
+        )
+        .bind(dialog_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Create or replace the settings of a tenant
+    pub async fn upsert(&self, settings: &TenantSettings) -> Result<TenantSettings, sqlx::Error> {
+        sqlx::query_as::<_, TenantSettings>(
+            r#"INSERT INTO tenant_settings (tenant_uid, branding, behavior, locale, timezone, sanitize_profile)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               ON CONFLICT (tenant_uid) DO UPDATE
+               SET branding = EXCLUDED.branding,
+                   behavior = EXCLUDED.behavior,
+                   locale = EXCLUDED.locale,
+                   timezone = EXCLUDED.timezone,
+                   sanitize_profile = EXCLUDED.sanitize_profile,
+                   updated_at = NOW()
+               RETURNING *"#,
+        )
+        .bind(&settings.tenant_uid)
+        .bind(&settings.branding)
+        .bind(&settings.behavior)
+        .bind(&settings.locale)
+        .bind(&settings.timezone)
+        .bind(settings.sanitize_profile)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Delete the settings of a tenant. Returns true if they existed.
+    pub async fn delete(&self, tenant_uid: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM tenant_settings WHERE tenant_uid = $1")
+            .bind(tenant_uid)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,540 @@
+//! Demo data for staging environments and load tests
+//!
+//! Generates dialogs between the companies of a few demo tenants, with
+//! participants, access scopes, a message history spread over the past
+//! weeks (replies and read positions included) and image and spreadsheet
+//! attachments. Everything is built with the domain constructors and stored
+//! through the repositories, the way the API stores it. The same random seed
+//! produces the same content (IDs and timestamps still differ per run).
+
+use std::io::Cursor;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{
+    system_messages, Attachment, Dialog, DialogAccessScope, DialogParticipant, JoinedAs, Message,
+    ParticipantProfile,
+};
+use crate::repositories::{
+    AttachmentRepository, DialogChildren, DialogRepository, MessageRepository,
+    ParticipantRepository, StorageUsageRepository,
+};
+use crate::services::{BlobStorage, StorageError};
+
+const OBJECT_TYPES: &[&str] = &["tender", "order", "shipment", "contract"];
+
+const SUBJECTS: &[&str] = &[
+    "Office furniture supply",
+    "Steel pipes, 2 000 m",
+    "Warehouse cleaning services",
+    "Laptops for the sales team",
+    "Annual IT support contract",
+    "Packaging materials",
+    "Freight Moscow - Kazan",
+    "Printing of catalogues",
+    "Security system maintenance",
+    "Construction materials, phase 2",
+];
+
+const COMPANIES: &[&str] = &[
+    "Northwind Trading",
+    "Contoso Logistics",
+    "Fabrikam Industries",
+    "Tailspin Supplies",
+    "Litware Systems",
+    "Adventure Works",
+    "Proseware Group",
+    "Wide World Importers",
+];
+
+const FIRST_NAMES: &[&str] = &[
+    "Anna", "Ivan", "Maria", "Alexey", "Elena", "Dmitry", "Olga", "Sergey", "Natalia", "Pavel",
+    "Irina", "Mikhail",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Smirnova",
+    "Petrov",
+    "Ivanova",
+    "Sokolov",
+    "Kuznetsova",
+    "Popov",
+    "Volkova",
+    "Morozov",
+];
+
+const PHRASES: &[&str] = &[
+    "Good afternoon! We have reviewed the specification.",
+    "Could you confirm the delivery dates?",
+    "The updated price list is attached.",
+    "We can offer a 5% discount for prepayment.",
+    "Please send the signed contract by Friday.",
+    "The documents have been uploaded to the portal.",
+    "Is partial delivery acceptable?",
+    "Thank you, everything is clear.",
+    "We need the certificates of conformity for all items.",
+    "The invoice will be issued tomorrow morning.",
+    "Our lawyers have a few comments on section 4.",
+    "Delivery is scheduled for next Tuesday.",
+    "Can we move the meeting to 3 pm?",
+    "The samples were received, quality is fine.",
+    "Please specify the warranty period.",
+];
+
+/// Users per demo tenant
+const USERS_PER_TENANT: usize = 6;
+
+/// Volume of the generated data
+#[derive(Debug, Clone, PartialEq, Eq, clap::Args)]
+pub struct SeedOptions {
+    /// Dialogs to create
+    #[arg(long, default_value_t = 20)]
+    pub dialogs: usize,
+    /// Participants per dialog
+    #[arg(long, default_value_t = 4)]
+    pub participants: usize,
+    /// Messages per dialog (besides the "chat created" message)
+    #[arg(long, default_value_t = 30)]
+    pub messages: usize,
+    /// Demo tenants (companies) the users belong to
+    #[arg(long, default_value_t = 4)]
+    pub tenants: usize,
+    /// Attach a file to every n-th message (0 = no attachments)
+    #[arg(long, default_value_t = 10)]
+    pub attachment_every: usize,
+    /// Random seed; the same seed generates the same content
+    #[arg(long, default_value_t = 1)]
+    pub random_seed: u64,
+}
+
+impl Default for SeedOptions {
+    fn default() -> Self {
+        Self {
+            dialogs: 20,
+            participants: 4,
+            messages: 30,
+            tenants: 4,
+            attachment_every: 10,
+            random_seed: 1,
+        }
+    }
+}
+
+/// What was created
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct SeedSummary {
+    pub dialogs: usize,
+    pub participants: usize,
+    pub messages: usize,
+    pub attachments: usize,
+    /// IDs of the demo users (`scope_level0` of their tenant in `tenants`)
+    pub users: Vec<String>,
+    pub tenants: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SeedError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to upload attachment: {0}")]
+    Storage(#[from] StorageError),
+    #[error("Failed to render attachment: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("Invalid seed options: {0}")]
+    InvalidOptions(String),
+}
+
+/// Demo user of a tenant
+#[derive(Debug, Clone)]
+struct DemoUser {
+    user_id: String,
+    tenant: String,
+    display_name: String,
+    company: String,
+}
+
+/// Small deterministic generator (xorshift64*), enough for demo content
+struct DemoRng(u64);
+
+impl DemoRng {
+    fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `0..n` (n > 0)
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    /// Fisher-Yates
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Generate demo data. Attachments are only created when `storage` is
+/// configured, since their files are uploaded.
+pub async fn seed(
+    db: &PgPool,
+    storage: &dyn BlobStorage,
+    options: &SeedOptions,
+) -> Result<SeedSummary, SeedError> {
+    if options.tenants == 0 || options.participants == 0 {
+        return Err(SeedError::InvalidOptions(
+            "tenants and participants must be at least 1".into(),
+        ));
+    }
+    if options.participants > options.tenants * USERS_PER_TENANT {
+        return Err(SeedError::InvalidOptions(format!(
+            "at most {} participants per dialog with {} tenants",
+            options.tenants * USERS_PER_TENANT,
+            options.tenants
+        )));
+    }
+    let with_attachments = options.attachment_every > 0 && storage.is_configured();
+    if options.attachment_every > 0 && !with_attachments {
+        tracing::warn!("File storage not configured, seeding without attachments");
+    }
+
+    let mut rng = DemoRng::new(options.random_seed);
+    let users = demo_users(&mut rng, options.tenants);
+    let mut summary = SeedSummary {
+        users: users.iter().map(|u| u.user_id.clone()).collect(),
+        tenants: (0..options.tenants).map(demo_tenant).collect(),
+        ..Default::default()
+    };
+
+    let messages = MessageRepository::new(db.clone());
+    let participants = ParticipantRepository::new(db.clone());
+    let attachments = AttachmentRepository::new(db.clone());
+    let storage_usage = StorageUsageRepository::new(db.clone());
+
+    for _ in 0..options.dialogs {
+        let members = pick_members(&mut rng, &users, options.participants, options.tenants);
+        let started_at = Utc::now() - Duration::minutes(rng.below(30 * 24 * 60) as i64 + 60);
+        let dialog = insert_dialog(db, &mut rng, &members, started_at).await?;
+        summary.dialogs += 1;
+        summary.participants += members.len();
+
+        // Spread the history from the start of the dialog until now
+        let step = (Utc::now() - started_at).num_seconds() / (options.messages as i64 + 1);
+        let mut sent: Vec<Message> = Vec::new();
+        for n in 0..options.messages {
+            let sender = rng.pick(&members);
+            let sent_at = started_at + Duration::seconds(step * (n as i64 + 1));
+            let mut message = Message::new(dialog.id, &sender.user_id, demo_content(&mut rng))
+                .with_sent_at(sent_at);
+            if !sent.is_empty() && rng.chance(15) {
+                message = message.with_reply(sent[rng.below(sent.len())].id);
+            }
+            let message = messages.create(&message).await?;
+
+            if with_attachments && (n + 1) % options.attachment_every == 0 {
+                let size = attach_file(storage, &attachments, &mut rng, &message).await?;
+                storage_usage.add_bytes(dialog.id, size).await?;
+                summary.attachments += 1;
+            }
+
+            // As when sent through the API: unread for the others, read by the sender
+            participants
+                .increment_unread(dialog.id, &sender.user_id)
+                .await?;
+            participants
+                .mark_as_read(dialog.id, &sender.user_id, message.id)
+                .await?;
+            sent.push(message);
+            summary.messages += 1;
+        }
+
+        // About half the participants have caught up
+        if let Some(last) = sent.last() {
+            for member in &members {
+                if rng.chance(50) {
+                    participants
+                        .mark_as_read(dialog.id, &member.user_id, last.id)
+                        .await?;
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn demo_tenant(index: usize) -> String {
+    format!("demo-tenant-{}", index + 1)
+}
+
+fn demo_users(rng: &mut DemoRng, tenants: usize) -> Vec<DemoUser> {
+    (0..tenants)
+        .flat_map(|t| (0..USERS_PER_TENANT).map(move |u| (t, u)))
+        .map(|(t, u)| DemoUser {
+            user_id: format!("demo-user-{}-{}", t + 1, u + 1),
+            tenant: demo_tenant(t),
+            display_name: format!("{} {}", rng.pick(FIRST_NAMES), rng.pick(LAST_NAMES)),
+            company: COMPANIES[t % COMPANIES.len()].to_string(),
+        })
+        .collect()
+}
+
+/// Participants from two tenants (buyer and supplier), alternating, then
+/// from the other tenants if those two have too few users
+fn pick_members(
+    rng: &mut DemoRng,
+    users: &[DemoUser],
+    count: usize,
+    tenants: usize,
+) -> Vec<DemoUser> {
+    let first = rng.below(tenants);
+    let second = (first + 1 + rng.below(tenants.max(2) - 1)) % tenants;
+    let mut buyers = tenant_users(users, first);
+    rng.shuffle(&mut buyers);
+    let mut order = if second == first {
+        buyers
+    } else {
+        let mut suppliers = tenant_users(users, second);
+        rng.shuffle(&mut suppliers);
+        buyers
+            .into_iter()
+            .zip(suppliers)
+            .flat_map(|(buyer, supplier)| [buyer, supplier])
+            .collect()
+    };
+    let mut others: Vec<&DemoUser> = (0..tenants)
+        .filter(|&t| t != first && t != second)
+        .flat_map(|t| tenant_users(users, t))
+        .collect();
+    rng.shuffle(&mut others);
+    order.extend(others);
+    order.into_iter().take(count).cloned().collect()
+}
+
+fn tenant_users(users: &[DemoUser], tenant: usize) -> Vec<&DemoUser> {
+    users[tenant * USERS_PER_TENANT..(tenant + 1) * USERS_PER_TENANT]
+        .iter()
+        .collect()
+}
+
+async fn insert_dialog(
+    db: &PgPool,
+    rng: &mut DemoRng,
+    members: &[DemoUser],
+    created_at: DateTime<Utc>,
+) -> Result<Dialog, sqlx::Error> {
+    let object_type = *rng.pick(OBJECT_TYPES);
+    let number = 10_000 + rng.below(90_000);
+    let mut dialog = Dialog::new(
+        format!("demo-{}-{}", object_type, number),
+        object_type,
+        Some(format!(
+            "{} #{}: {}",
+            capitalize(object_type),
+            number,
+            rng.pick(SUBJECTS)
+        )),
+        None,
+        members.first().map(|m| m.user_id.clone()),
+        None,
+    );
+    dialog.created_at = created_at;
+
+    let participants = members
+        .iter()
+        .map(|member| {
+            let profile = ParticipantProfile {
+                display_name: member.display_name.clone(),
+                company: Some(member.company.clone()),
+                company_uid: Some(member.tenant.clone()),
+                email: None,
+                phone: None,
+            };
+            let mut participant = DialogParticipant::with_profile(
+                dialog.id,
+                &member.user_id,
+                JoinedAs::Participant,
+                profile,
+            );
+            participant.joined_at = created_at;
+            participant
+        })
+        .collect();
+    let mut tenants: Vec<String> = members.iter().map(|m| m.tenant.clone()).collect();
+    tenants.sort();
+    tenants.dedup();
+    let access_scopes = vec![DialogAccessScope::new(dialog.id, tenants, vec![], vec![])];
+    let infos = members
+        .iter()
+        .map(|m| system_messages::ParticipantInfo {
+            name: m.display_name.clone(),
+            company: Some(m.company.clone()),
+        })
+        .collect();
+    let system_message = Message::system(
+        dialog.id,
+        system_messages::chat_created_content(infos, &dialog.locale_context()),
+    )
+    .with_sent_at(created_at);
+    let children = DialogChildren {
+        participants,
+        access_scopes,
+        system_message: Some(system_message),
+    };
+
+    let mut tx = db.begin().await?;
+    let dialog = DialogRepository::insert_with_children(&mut tx, &dialog, &children).await?;
+    tx.commit().await?;
+    Ok(dialog)
+}
+
+/// One to three sentences, sometimes with a bold part
+fn demo_content(rng: &mut DemoRng) -> String {
+    let sentences: Vec<&str> = (0..1 + rng.below(3)).map(|_| *rng.pick(PHRASES)).collect();
+    let text = sentences.join(" ");
+    if rng.chance(10) {
+        format!("<p><strong>Important:</strong> {}</p>", text)
+    } else {
+        format!("<p>{}</p>", text)
+    }
+}
+
+/// Upload an image or a spreadsheet and attach it. Returns its size.
+async fn attach_file(
+    storage: &dyn BlobStorage,
+    attachments: &AttachmentRepository,
+    rng: &mut DemoRng,
+    message: &Message,
+) -> Result<i64, SeedError> {
+    let (filename, content_type, ext, data, dimensions) = if rng.chance(50) {
+        let (width, height) = (640, 400);
+        let data = demo_image(rng, width, height)?;
+        ("photo.png", "image/png", "png", data, Some((width, height)))
+    } else {
+        (
+            "price-list.csv",
+            "text/csv",
+            "csv",
+            demo_spreadsheet(rng),
+            None,
+        )
+    };
+
+    let key = format!("dialogs/{}/{}.{}", message.dialog_id, Uuid::new_v4(), ext);
+    let size = data.len() as i64;
+    storage.put_object(&key, data, content_type).await?;
+
+    let mut attachment = Attachment::new(message.id, filename, content_type, size, key);
+    if let Some((width, height)) = dimensions {
+        attachment = attachment.with_image_metadata(width as i32, height as i32, None);
+    }
+    attachments.create(&attachment).await?;
+    Ok(size)
+}
+
+/// Diagonal two-color gradient
+fn demo_image(rng: &mut DemoRng, width: u32, height: u32) -> Result<Vec<u8>, image::ImageError> {
+    let from = [
+        rng.below(256) as f32,
+        rng.below(256) as f32,
+        rng.below(256) as f32,
+    ];
+    let to = [
+        rng.below(256) as f32,
+        rng.below(256) as f32,
+        rng.below(256) as f32,
+    ];
+    let image = image::RgbImage::from_fn(width, height, |x, y| {
+        let t = (x + y) as f32 / (width + height) as f32;
+        image::Rgb(std::array::from_fn(|i| {
+            (from[i] + (to[i] - from[i]) * t) as u8
+        }))
+    });
+    let mut data = Vec::new();
+    image.write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)?;
+    Ok(data)
+}
+
+fn demo_spreadsheet(rng: &mut DemoRng) -> Vec<u8> {
+    let mut csv = String::from("item,quantity,unit_price\n");
+    for n in 1..=5 + rng.below(10) {
+        csv.push_str(&format!(
+            "Item {},{},{}.{:02}\n",
+            n,
+            1 + rng.below(500),
+            10 + rng.below(5000),
+            rng.below(100)
+        ));
+    }
+    csv.into_bytes()
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut a = DemoRng::new(7);
+        let mut b = DemoRng::new(7);
+        let first: Vec<u64> = (0..5).map(|_| a.next()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next()).collect::<Vec<_>>());
+        assert_ne!(first[0], DemoRng::new(8).next());
+        assert_ne!(DemoRng::new(0).next(), 0);
+    }
+
+    #[test]
+    fn test_members_are_distinct_and_span_two_tenants() {
+        let mut rng = DemoRng::new(1);
+        let users = demo_users(&mut rng, 3);
+        for count in [1, 4, 12, 18] {
+            let members = pick_members(&mut rng, &users, count, 3);
+            assert_eq!(members.len(), count);
+            let mut ids: Vec<_> = members.iter().map(|m| &m.user_id).collect();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), count);
+            if count >= 2 {
+                assert_ne!(members[0].tenant, members[1].tenant);
+            }
+        }
+
+        // A single tenant still works
+        let users = demo_users(&mut rng, 1);
+        assert_eq!(pick_members(&mut rng, &users, 6, 1).len(), 6);
+    }
+
+    #[test]
+    fn test_demo_image_is_png() {
+        let data = demo_image(&mut DemoRng::new(1), 64, 40).unwrap();
+        let image = image::load_from_memory(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 40));
+    }
+}
@@ -0,0 +1,200 @@
+//! Cross-instance pub/sub
+//!
+//! Instances notify each other (runtime settings invalidation, force-disconnect
+//! requests) through the [`Broker`] trait. Redis pub/sub ([`RedisBroker`]) is
+//! the default; deployments without Redis can use Postgres `LISTEN/NOTIFY`
+//! ([`PgBroker`]) instead (`broker.backend = "postgres"`).
+//!
+//! Delivery is best effort with both backends: messages published while a
+//! subscriber reconnects are lost, so consumers keep a periodic fallback.
+
+use fred::clients::Pool;
+use fred::interfaces::{ClientLike, EventInterface, PubsubInterface};
+use fred::types::config::Config;
+use fred::types::Builder;
+use futures::future::BoxFuture;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// Payloads published on a channel. Closed when the subscriber gives up.
+pub type Subscription = broadcast::Receiver<String>;
+
+/// Buffered payloads per subscription before a slow consumer lags
+const SUBSCRIPTION_CAPACITY: usize = 64;
+
+/// Pause before retrying a failed Postgres listener reconnect
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum BrokerError {
+    #[error("Redis pub/sub failed: {0}")]
+    Redis(#[from] fred::error::Error),
+
+    #[error("Postgres LISTEN/NOTIFY failed: {0}")]
+    Postgres(#[from] sqlx::Error),
+}
+
+/// Pub/sub channel shared by all instances.
+///
+/// Methods return boxed futures so the trait stays object-safe and can be held
+/// as `Arc<dyn Broker>`.
+pub trait Broker: Send + Sync {
+    /// Backend name for logs
+    fn name(&self) -> &'static str;
+
+    /// Publish a payload to every subscriber of `channel`, including this instance
+    fn publish<'a>(
+        &'a self,
+        channel: &'a str,
+        payload: &'a str,
+    ) -> BoxFuture<'a, Result<(), BrokerError>>;
+
+    /// Subscribe to `channel` on a dedicated connection
+    fn subscribe<'a>(
+        &'a self,
+        channel: &'a str,
+    ) -> BoxFuture<'a, Result<Subscription, BrokerError>>;
+}
+
+/// Redis pub/sub broker
+pub struct RedisBroker {
+    pool: Arc<Pool>,
+    url: String,
+}
+
+impl RedisBroker {
+    /// Publish through `pool`; subscriptions open their own client to `url`
+    pub fn new(pool: Arc<Pool>, url: impl Into<String>) -> Self {
+        Self {
+            pool,
+            url: url.into(),
+        }
+    }
+}
+
+impl Broker for RedisBroker {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn publish<'a>(
+        &'a self,
+        channel: &'a str,
+        payload: &'a str,
+    ) -> BoxFuture<'a, Result<(), BrokerError>> {
+        Box::pin(async move {
+            self.pool
+                .next()
+                .publish::<i64, _, _>(channel, payload)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn subscribe<'a>(
+        &'a self,
+        channel: &'a str,
+    ) -> BoxFuture<'a, Result<Subscription, BrokerError>> {
+        Box::pin(async move {
+            let subscriber =
+                Builder::from_config(Config::from_url(&self.url)?).build_subscriber_client()?;
+            subscriber.init().await?;
+            subscriber.subscribe(channel).await?;
+            subscriber.manage_subscriptions();
+
+            let mut messages = subscriber.message_rx();
+            let (tx, rx) = broadcast::channel(SUBSCRIPTION_CAPACITY);
+            tokio::spawn(async move {
+                // Keep the client alive as long as the subscription is forwarded
+                let _subscriber = subscriber;
+                loop {
+                    match messages.recv().await {
+                        Ok(message) => {
+                            let payload = message.value.as_string().unwrap_or_default();
+                            if tx.send(payload).is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            Ok(rx)
+        })
+    }
+}
+
+/// Postgres `LISTEN/NOTIFY` broker.
+///
+/// Each subscription holds one database connection. Payloads are limited to
+/// 8000 bytes by Postgres.
+pub struct PgBroker {
+    pool: PgPool,
+}
+
+impl PgBroker {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl Broker for PgBroker {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn publish<'a>(
+        &'a self,
+        channel: &'a str,
+        payload: &'a str,
+    ) -> BoxFuture<'a, Result<(), BrokerError>> {
+        Box::pin(async move {
+            sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(channel)
+                .bind(payload)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn subscribe<'a>(
+        &'a self,
+        channel: &'a str,
+    ) -> BoxFuture<'a, Result<Subscription, BrokerError>> {
+        Box::pin(async move {
+            let mut listener = PgListener::connect_with(&self.pool).await?;
+            listener.listen(channel).await?;
+
+            let channel = channel.to_string();
+            let (tx, rx) = broadcast::channel(SUBSCRIPTION_CAPACITY);
+            tokio::spawn(async move {
+                loop {
+                    // `try_recv` reconnects and re-listens after a lost connection
+                    match listener.try_recv().await {
+                        Ok(Some(notification)) => {
+                            if tx.send(notification.payload().to_string()).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(None) => {
+                            tracing::warn!(channel = %channel, "Postgres listener connection lost, reconnecting");
+                        }
+                        Err(e) => {
+                            tracing::warn!(channel = %channel, error = %e, "Postgres listener failed");
+                            tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+                        }
+                    }
+                }
+            });
+
+            Ok(rx)
+        })
+    }
+}
@@ -0,0 +1,416 @@
+//! Circuit breaker for the blob storage backend
+//!
+//! [`GuardedStorage`] wraps a [`BlobStorage`] so a slow or failing backend
+//! cannot stall requests: every call is bounded by a timeout, and after
+//! `failure_threshold` consecutive failures the circuit opens and calls fail
+//! immediately with [`StorageError::Unavailable`] for `open_duration`. Then a
+//! single trial call is let through; its result closes the circuit again or
+//! keeps it open for another period.
+//!
+//! Missing objects are answers, not failures, and don't count.
+
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::storage::{BlobStorage, StorageError};
+
+/// Circuit breaker settings (from the `[s3]` section)
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial call
+    pub open_duration: Duration,
+    /// Timeout of URL, metadata and delete calls
+    pub timeout: Duration,
+    /// Timeout of object downloads and uploads
+    pub transfer_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A trial call is in flight. Another one is let through if it has not
+    /// reported back by `retry_at` (e.g. the request was cancelled).
+    HalfOpen {
+        retry_at: Instant,
+    },
+}
+
+/// Consecutive-failure circuit breaker
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether a call may go through now
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until: at } | State::HalfOpen { retry_at: at }
+                if Instant::now() >= at =>
+            {
+                *state = State::HalfOpen {
+                    retry_at: Instant::now() + self.open_duration,
+                };
+                true
+            }
+            State::Open { .. } | State::HalfOpen { .. } => false,
+        }
+    }
+
+    /// Whether calls are currently rejected
+    pub fn is_open(&self) -> bool {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match *state {
+            State::Closed { .. } => false,
+            State::Open { until } => Instant::now() < until,
+            State::HalfOpen { .. } => true,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = State::Closed { failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let open = match *state {
+            State::Closed { failures } if failures + 1 < self.failure_threshold => {
+                *state = State::Closed {
+                    failures: failures + 1,
+                };
+                false
+            }
+            State::Closed { .. } | State::HalfOpen { .. } => true,
+            // Failures of calls started before the circuit opened
+            State::Open { .. } => false,
+        };
+        if open {
+            tracing::warn!(
+                open_secs = self.open_duration.as_secs(),
+                "Storage circuit opened"
+            );
+            *state = State::Open {
+                until: Instant::now() + self.open_duration,
+            };
+        }
+    }
+}
+
+/// [`BlobStorage`] with call timeouts and a circuit breaker
+pub struct GuardedStorage {
+    inner: Arc<dyn BlobStorage>,
+    breaker: CircuitBreaker,
+    timeout: Duration,
+    transfer_timeout: Duration,
+}
+
+impl GuardedStorage {
+    pub fn new(inner: Arc<dyn BlobStorage>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            breaker: CircuitBreaker::new(config.failure_threshold, config.open_duration),
+            timeout: config.timeout,
+            transfer_timeout: config.transfer_timeout,
+        }
+    }
+
+    async fn guard<T>(
+        &self,
+        timeout: Duration,
+        call: impl Future<Output = Result<T, StorageError>>,
+    ) -> Result<T, StorageError> {
+        if !self.breaker.allow() {
+            return Err(StorageError::Unavailable("circuit open".into()));
+        }
+        match tokio::time::timeout(timeout, call).await {
+            Ok(Ok(value)) => {
+                self.breaker.record_success();
+                Ok(value)
+            }
+            Ok(Err(e @ StorageError::NotFound(_))) => {
+                self.breaker.record_success();
+                Err(e)
+            }
+            Ok(Err(e)) => {
+                self.breaker.record_failure();
+                Err(e)
+            }
+            Err(_) => {
+                self.breaker.record_failure();
+                Err(StorageError::Unavailable(format!(
+                    "timed out after {:?}",
+                    timeout
+                )))
+            }
+        }
+    }
+}
+
+impl BlobStorage for GuardedStorage {
+    fn is_configured(&self) -> bool {
+        self.inner.is_configured()
+    }
+
+    fn is_available(&self) -> bool {
+        !self.breaker.is_open()
+    }
+
+    fn upload_expiry_secs(&self) -> u64 {
+        self.inner.upload_expiry_secs()
+    }
+
+    fn download_expiry_secs(&self) -> Option<u64> {
+        self.inner.download_expiry_secs()
+    }
+
+    fn generate_upload_url<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        size: i64,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(self.guard(
+            self.timeout,
+            self.inner.generate_upload_url(key, content_type, size),
+        ))
+    }
+
+    fn generate_download_url<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(self.guard(self.timeout, self.inner.generate_download_url(key)))
+    }
+
+    fn object_exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<bool, StorageError>> {
+        Box::pin(self.guard(self.timeout, self.inner.object_exists(key)))
+    }
+
+    fn get_object_info<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(String, i64), StorageError>> {
+        Box::pin(self.guard(self.timeout, self.inner.get_object_info(key)))
+    }
+
+    fn get_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>> {
+        Box::pin(self.guard(self.transfer_timeout, self.inner.get_object(key)))
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        key: &'a str,
+        data: Vec<u8>,
+        content_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(self.guard(
+            self.transfer_timeout,
+            self.inner.put_object(key, data, content_type),
+        ))
+    }
+
+    fn delete_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(self.guard(self.timeout, self.inner.delete_object(key)))
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), StorageError>> {
+        Box::pin(self.guard(self.timeout, self.inner.health_check()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails download URLs while `failing` is set and counts the calls
+    #[derive(Default)]
+    struct FlakyStorage {
+        failing: std::sync::atomic::AtomicBool,
+        calls: AtomicU32,
+    }
+
+    impl BlobStorage for FlakyStorage {
+        fn is_configured(&self) -> bool {
+            true
+        }
+
+        fn upload_expiry_secs(&self) -> u64 {
+            300
+        }
+
+        fn download_expiry_secs(&self) -> Option<u64> {
+            Some(3600)
+        }
+
+        fn generate_upload_url<'a>(
+            &'a self,
+            _key: &'a str,
+            _content_type: &'a str,
+            _size: i64,
+        ) -> BoxFuture<'a, Result<String, StorageError>> {
+            Box::pin(async { Ok("upload".into()) })
+        }
+
+        fn generate_download_url<'a>(
+            &'a self,
+            key: &'a str,
+        ) -> BoxFuture<'a, Result<String, StorageError>> {
+            Box::pin(async move {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                if self.failing.load(Ordering::SeqCst) {
+                    Err(StorageError::PresigningFailed("down".into()))
+                } else {
+                    Ok(format!("https://s3/{}", key))
+                }
+            })
+        }
+
+        fn object_exists<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, Result<bool, StorageError>> {
+            Box::pin(async { Ok(true) })
+        }
+
+        fn get_object_info<'a>(
+            &'a self,
+            key: &'a str,
+        ) -> BoxFuture<'a, Result<(String, i64), StorageError>> {
+            Box::pin(async move { Err(StorageError::NotFound(key.to_string())) })
+        }
+
+        fn get_object<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>> {
+            Box::pin(std::future::pending())
+        }
+
+        fn put_object<'a>(
+            &'a self,
+            _key: &'a str,
+            _data: Vec<u8>,
+            _content_type: &'a str,
+        ) -> BoxFuture<'a, Result<(), StorageError>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn delete_object<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, Result<(), StorageError>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn health_check(&self) -> BoxFuture<'_, Result<(), StorageError>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn guarded(inner: Arc<FlakyStorage>, open_duration: Duration) -> GuardedStorage {
+        GuardedStorage::new(
+            inner,
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_duration,
+                timeout: Duration::from_millis(50),
+                transfer_timeout: Duration::from_millis(50),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_after_consecutive_failures() {
+        let inner = Arc::new(FlakyStorage::default());
+        inner.failing.store(true, Ordering::SeqCst);
+        let storage = guarded(inner.clone(), Duration::from_secs(60));
+
+        for _ in 0..2 {
+            assert!(matches!(
+                storage.generate_download_url("a").await,
+                Err(StorageError::PresigningFailed(_))
+            ));
+        }
+        assert!(!storage.is_available());
+
+        // Open: fails fast without reaching the backend
+        assert!(matches!(
+            storage.generate_download_url("a").await,
+            Err(StorageError::Unavailable(_))
+        ));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        // Batches degrade to missing URLs instead of waiting
+        let urls = storage.generate_download_urls_batch(&["a", "b"]).await;
+        assert!(urls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trial_call_closes_circuit() {
+        let inner = Arc::new(FlakyStorage::default());
+        inner.failing.store(true, Ordering::SeqCst);
+        let storage = guarded(inner.clone(), Duration::ZERO);
+
+        for _ in 0..2 {
+            let _ = storage.generate_download_url("a").await;
+        }
+        inner.failing.store(false, Ordering::SeqCst);
+
+        // The open period is over: the trial call goes through and closes it
+        assert_eq!(
+            storage.generate_download_url("a").await.unwrap(),
+            "https://s3/a"
+        );
+        assert!(storage.is_available());
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_count_and_missing_objects_do_not() {
+        let storage = guarded(Arc::new(FlakyStorage::default()), Duration::from_secs(60));
+
+        assert!(matches!(
+            storage.get_object_info("a").await,
+            Err(StorageError::NotFound(_))
+        ));
+        assert!(matches!(
+            storage.get_object("a").await,
+            Err(StorageError::Unavailable(_))
+        ));
+        assert!(storage.is_available());
+        let _ = storage.get_object("a").await;
+        assert!(!storage.is_available());
+    }
+
+    #[test]
+    fn test_half_open_allows_one_trial() {
+        let open = Duration::from_millis(20);
+        let breaker = CircuitBreaker::new(1, open);
+        breaker.record_failure();
+        assert!(!breaker.allow());
+
+        std::thread::sleep(open);
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+        // A trial that never reports back does not keep the circuit open forever
+        std::thread::sleep(open);
+        assert!(breaker.allow());
+
+        breaker.record_success();
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+}
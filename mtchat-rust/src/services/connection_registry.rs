@@ -0,0 +1,306 @@
+//! WebSocket connection registry across instances
+//!
+//! Every instance keeps its own connections in memory ([`Connections`]). To
+//! let the Management API see all of them, each instance publishes a snapshot
+//! of its connections to Redis every [`SNAPSHOT_INTERVAL`]; snapshots expire
+//! when an instance stops. Force-disconnect requests are published on
+//! [`DISCONNECT_CHANNEL`] through the [`Broker`] so the instance holding the
+//! socket closes it.
+//!
+//! Without Redis the registry only lists the local instance; without a broker
+//! only local connections can be closed.
+
+use chrono::{DateTime, Utc};
+use fred::clients::Pool;
+use fred::error::Error as RedisError;
+use fred::interfaces::{KeysInterface, SetsInterface};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::services::broker::{Broker, BrokerError, Subscription};
+use crate::ws::Connections;
+
+/// Broker channel for force-disconnect requests (payload: [`DisconnectRequest`] JSON)
+pub const DISCONNECT_CHANNEL: &str = "mtchat:ws:disconnect";
+
+/// How often each instance publishes its connection snapshot
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Snapshots of instances that stopped publishing expire after this long
+const SNAPSHOT_TTL: i64 = 45;
+
+/// Set of instance ids that published a snapshot
+const INSTANCES_KEY: &str = "mtchat:ws:instances";
+
+fn snapshot_key(instance_id: Uuid) -> String {
+    format!("mtchat:ws:instance:{}", instance_id)
+}
+
+/// A connected user as seen by one instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectedUser {
+    pub user_id: String,
+    pub connected_at: DateTime<Utc>,
+}
+
+/// Connections of one instance at `updated_at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceSnapshot {
+    pub instance_id: Uuid,
+    pub connections: usize,
+    pub users: Vec<ConnectedUser>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Force-disconnect relayed to the other instances
+#[derive(Debug, Serialize, Deserialize)]
+struct DisconnectRequest {
+    /// Instance that already closed its local connection
+    origin: Uuid,
+    user_id: String,
+}
+
+/// Counters exported to `/metrics`
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionMetrics {
+    pub connections: usize,
+    pub opened_total: u64,
+    pub forced_disconnects_total: u64,
+}
+
+pub struct ConnectionRegistry {
+    instance_id: Uuid,
+    connections: Connections,
+    redis: Option<Arc<Pool>>,
+    broker: Option<Arc<dyn Broker>>,
+    opened_total: AtomicU64,
+    forced_disconnects_total: AtomicU64,
+}
+
+impl ConnectionRegistry {
+    /// Create a registry sharing snapshots through Redis
+    pub fn new(redis: Arc<Pool>) -> Self {
+        Self::with_redis(Some(redis))
+    }
+
+    /// Create a registry that only knows this instance (when Redis is not configured)
+    pub fn local() -> Self {
+        Self::with_redis(None)
+    }
+
+    fn with_redis(redis: Option<Arc<Pool>>) -> Self {
+        Self {
+            instance_id: Uuid::new_v4(),
+            connections: Arc::new(dashmap::DashMap::new()),
+            redis,
+            broker: None,
+            opened_total: AtomicU64::new(0),
+            forced_disconnects_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Relay force-disconnect requests to other instances through `broker`
+    pub fn with_broker(mut self, broker: Arc<dyn Broker>) -> Self {
+        self.broker = Some(broker);
+        self
+    }
+
+    /// Id of this instance (random per process)
+    pub fn instance_id(&self) -> Uuid {
+        self.instance_id
+    }
+
+    /// Connections of this instance
+    pub fn connections(&self) -> &Connections {
+        &self.connections
+    }
+
+    /// Count a newly opened connection
+    pub fn connection_opened(&self) {
+        self.opened_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> ConnectionMetrics {
+        ConnectionMetrics {
+            connections: self.connections.len(),
+            opened_total: self.opened_total.load(Ordering::Relaxed),
+            forced_disconnects_total: self.forced_disconnects_total.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Snapshot of this instance's connections
+    pub fn local_snapshot(&self) -> InstanceSnapshot {
+        let mut users: Vec<ConnectedUser> = self
+            .connections
+            .iter()
+            .map(|entry| ConnectedUser {
+                user_id: entry.key().clone(),
+                connected_at: entry.value().connected_at,
+            })
+            .collect();
+        users.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+
+        InstanceSnapshot {
+            instance_id: self.instance_id,
+            connections: users.len(),
+            users,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Snapshots of all live instances. The local one is always current;
+    /// others are at most [`SNAPSHOT_INTERVAL`] old.
+    pub async fn snapshots(&self) -> Result<Vec<InstanceSnapshot>, RedisError> {
+        let local = self.local_snapshot();
+        let Some(redis) = &self.redis else {
+            return Ok(vec![local]);
+        };
+
+        let ids: Vec<String> = redis.smembers(INSTANCES_KEY).await?;
+        let ids: Vec<Uuid> = ids
+            .iter()
+            .filter_map(|id| id.parse().ok())
+            .filter(|id| *id != self.instance_id)
+            .collect();
+
+        let mut snapshots = vec![local];
+        if ids.is_empty() {
+            return Ok(snapshots);
+        }
+
+        let keys: Vec<String> = ids.iter().map(|id| snapshot_key(*id)).collect();
+        let values: Vec<Option<String>> = redis.mget(keys).await?;
+
+        let mut stale = Vec::new();
+        for (id, value) in ids.into_iter().zip(values) {
+            match value.and_then(|v| serde_json::from_str::<InstanceSnapshot>(&v).ok()) {
+                Some(snapshot) => snapshots.push(snapshot),
+                None => stale.push(id.to_string()),
+            }
+        }
+        if !stale.is_empty() {
+            redis.srem::<(), _, _>(INSTANCES_KEY, stale).await?;
+        }
+
+        Ok(snapshots)
+    }
+
+    /// Close the connection of a user on whichever instance holds it.
+    /// Returns true if it was connected to this instance.
+    pub async fn disconnect(&self, user_id: &str) -> Result<bool, BrokerError> {
+        let local = self.disconnect_local(user_id);
+        if let Some(broker) = &self.broker {
+            let request = serde_json::to_string(&DisconnectRequest {
+                origin: self.instance_id,
+                user_id: user_id.to_string(),
+            })
+            .expect("request serialization cannot fail");
+            broker.publish(DISCONNECT_CHANNEL, &request).await?;
+        }
+        Ok(local)
+    }
+
+    fn disconnect_local(&self, user_id: &str) -> bool {
+        match self.connections.get(user_id) {
+            Some(conn) => {
+                conn.close();
+                self.forced_disconnects_total
+                    .fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn publish_snapshot(&self) -> Result<(), RedisError> {
+        let Some(redis) = &self.redis else {
+            return Ok(());
+        };
+
+        let snapshot = serde_json::to_string(&self.local_snapshot())
+            .expect("snapshot serialization cannot fail");
+        redis
+            .set::<(), _, _>(
+                snapshot_key(self.instance_id),
+                snapshot,
+                Some(fred::types::Expiration::EX(SNAPSHOT_TTL)),
+                None,
+                false,
+            )
+            .await?;
+        redis
+            .sadd::<(), _, _>(INSTANCES_KEY, self.instance_id.to_string())
+            .await?;
+        Ok(())
+    }
+
+    /// Publish snapshots every [`SNAPSHOT_INTERVAL`] and close connections
+    /// when another instance relays a force-disconnect (when a subscription is given).
+    pub fn spawn(self: Arc<Self>, subscription: Option<Subscription>) {
+        if self.redis.is_none() && subscription.is_none() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut messages = subscription;
+            let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+
+            loop {
+                match messages.as_mut() {
+                    Some(rx) => tokio::select! {
+                        message = rx.recv() => match message {
+                            Ok(message) => {
+                                let request = serde_json::from_str::<DisconnectRequest>(&message).ok();
+                                if let Some(request) = request.filter(|r| r.origin != self.instance_id) {
+                                    self.disconnect_local(&request.user_id);
+                                }
+                                continue;
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                                tracing::warn!("Disconnect subscriber closed, force-disconnect only works locally");
+                                messages = None;
+                                continue;
+                            }
+                        },
+                        _ = interval.tick() => {}
+                    },
+                    None => {
+                        interval.tick().await;
+                    }
+                }
+
+                if let Err(e) = self.publish_snapshot().await {
+                    tracing::warn!("Failed to publish WebSocket connection snapshot: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_registry_snapshot_and_disconnect() {
+        let registry = ConnectionRegistry::local();
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        registry
+            .connections()
+            .insert("u1".to_string(), crate::ws::Connection::new(tx));
+
+        let snapshots = registry.snapshots().await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].instance_id, registry.instance_id());
+        assert_eq!(snapshots[0].connections, 1);
+        assert_eq!(snapshots[0].users[0].user_id, "u1");
+
+        assert!(registry.disconnect("u1").await.unwrap());
+        assert!(!registry.disconnect("u2").await.unwrap());
+        assert_eq!(registry.metrics().forced_disconnects_total, 1);
+    }
+}
@@ -0,0 +1,388 @@
+//! Event stream publisher
+//!
+//! Publishes domain events (the webhook event envelopes, without the
+//! per-recipient `notification.*` events) to Kafka or NATS, so data
+//! warehouses can ingest chat activity without polling the REST API. Each
+//! event goes to the topic (Kafka) or subject (NATS) `{topic_prefix}{type}`,
+//! e.g. `mtchat.message.new`, keyed by dialog ID.
+//!
+//! The Kafka and NATS clients are only compiled with the `kafka` and `nats`
+//! features.
+//!
+//! Environment variables:
+//! - `EVENT_STREAM_BACKEND` - `none` (default), `kafka` or `nats`
+//! - `EVENT_STREAM_SERVERS` - Comma-separated Kafka bootstrap brokers
+//!   (`host:9092`) or NATS server URLs (`nats://host:4222`)
+//! - `EVENT_STREAM_TOPIC_PREFIX` - Topic/subject prefix (default: `mtchat.`)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::webhooks::{WebhookEvent, DEFAULT_WEBHOOK_PAYLOAD_VERSION};
+
+/// Events waiting to be published; further events are dropped
+const EVENT_STREAM_BUFFER: usize = 10_000;
+
+/// Event stream backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventStreamBackend {
+    #[default]
+    None,
+    Kafka,
+    Nats,
+}
+
+impl EventStreamBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Kafka => "kafka",
+            Self::Nats => "nats",
+        }
+    }
+
+    /// This build includes the client for the backend
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Kafka => cfg!(feature = "kafka"),
+            Self::Nats => cfg!(feature = "nats"),
+        }
+    }
+}
+
+/// Event stream settings (`[event_stream]` section)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventStreamConfig {
+    pub backend: EventStreamBackend,
+    /// Comma-separated broker addresses or server URLs
+    pub servers: String,
+    /// Prepended to the event type to form the topic
+    pub topic_prefix: String,
+    /// Webhook payload version of the published events (default: 1)
+    pub payload_version: u32,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            backend: EventStreamBackend::None,
+            servers: String::new(),
+            topic_prefix: "mtchat.".to_string(),
+            payload_version: DEFAULT_WEBHOOK_PAYLOAD_VERSION,
+        }
+    }
+}
+
+impl EventStreamConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.backend != EventStreamBackend::None
+    }
+
+    pub fn server_list(&self) -> Vec<String> {
+        self.servers
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Topic (or subject) an event type is published to
+    pub fn topic(&self, event_type: &str) -> String {
+        format!("{}{}", self.topic_prefix, event_type)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EventStreamError {
+    #[error("Event stream backend '{0}' is not enabled in this build")]
+    Unsupported(&'static str),
+
+    #[error("Event stream is not configured")]
+    NotConfigured,
+
+    #[error("Failed to connect to the event stream: {0}")]
+    Connect(String),
+
+    #[error("Failed to publish event: {0}")]
+    Publish(String),
+}
+
+/// Event ready to be published
+#[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(dead_code))]
+struct StreamRecord {
+    topic: String,
+    /// Dialog ID (partition key)
+    key: Uuid,
+    id: Uuid,
+    event_type: &'static str,
+    timestamp: DateTime<Utc>,
+    payload: Vec<u8>,
+}
+
+/// Handle for publishing events in the background
+#[derive(Clone)]
+pub struct EventStream {
+    tx: mpsc::Sender<WebhookEvent>,
+    payload_version: u32,
+}
+
+impl EventStream {
+    /// Connect to the configured backend and spawn the publisher task
+    pub async fn connect(config: &EventStreamConfig) -> Result<Self, EventStreamError> {
+        let publisher = Publisher::connect(config).await?;
+        let (tx, rx) = mpsc::channel(EVENT_STREAM_BUFFER);
+
+        tracing::info!(
+            backend = config.backend.as_str(),
+            servers = %config.servers,
+            "Event stream enabled"
+        );
+        tokio::spawn(publish_worker(publisher, config.clone(), rx));
+
+        Ok(Self {
+            tx,
+            payload_version: config.payload_version,
+        })
+    }
+
+    /// Queue an event for publishing (non-blocking)
+    ///
+    /// Events are dropped with a warning while the publisher is behind by
+    /// more than its buffer.
+    pub fn publish(&self, event: &WebhookEvent) {
+        let versioned = event.clone().into_version(self.payload_version);
+        if let Err(e) = self.tx.try_send(versioned) {
+            tracing::warn!(
+                event_id = %event.id,
+                event_type = %event.event_type,
+                "Failed to queue event for the event stream: {}",
+                e
+            );
+        }
+    }
+}
+
+async fn publish_worker(
+    publisher: Publisher,
+    config: EventStreamConfig,
+    mut rx: mpsc::Receiver<WebhookEvent>,
+) {
+    while let Some(event) = rx.recv().await {
+        let payload = match serde_json::to_vec(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!(event_id = %event.id, "Failed to serialize event: {}", e);
+                continue;
+            }
+        };
+        let record = StreamRecord {
+            topic: config.topic(event.event_type.as_str()),
+            // Only per-recipient digests have no dialog, and they are not published
+            key: event.payload.dialog_id().unwrap_or(event.id),
+            id: event.id,
+            event_type: event.event_type.as_str(),
+            timestamp: event.timestamp,
+            payload,
+        };
+
+        if let Err(e) = publisher.publish(&record).await {
+            tracing::error!(
+                event_id = %record.id,
+                topic = %record.topic,
+                error = %e,
+                "Event stream publish failed"
+            );
+        }
+    }
+
+    tracing::info!("Event stream publisher stopped");
+}
+
+/// Client of the configured backend
+enum Publisher {
+    #[cfg(feature = "kafka")]
+    Kafka(kafka::KafkaPublisher),
+    #[cfg(feature = "nats")]
+    Nats(async_nats::Client),
+}
+
+impl Publisher {
+    async fn connect(config: &EventStreamConfig) -> Result<Self, EventStreamError> {
+        let servers = config.server_list();
+        if servers.is_empty() {
+            return Err(EventStreamError::NotConfigured);
+        }
+
+        match config.backend {
+            EventStreamBackend::None => Err(EventStreamError::NotConfigured),
+            #[cfg(feature = "kafka")]
+            EventStreamBackend::Kafka => {
+                Ok(Self::Kafka(kafka::KafkaPublisher::connect(servers).await?))
+            }
+            #[cfg(not(feature = "kafka"))]
+            EventStreamBackend::Kafka => Err(EventStreamError::Unsupported("kafka")),
+            #[cfg(feature = "nats")]
+            EventStreamBackend::Nats => async_nats::connect(servers.join(","))
+                .await
+                .map(Self::Nats)
+                .map_err(|e| EventStreamError::Connect(e.to_string())),
+            #[cfg(not(feature = "nats"))]
+            EventStreamBackend::Nats => Err(EventStreamError::Unsupported("nats")),
+        }
+    }
+
+    #[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(unused_variables))]
+    async fn publish(&self, record: &StreamRecord) -> Result<(), EventStreamError> {
+        match *self {
+            #[cfg(feature = "kafka")]
+            Self::Kafka(ref kafka) => kafka.publish(record).await,
+            #[cfg(feature = "nats")]
+            Self::Nats(ref client) => {
+                let mut headers = async_nats::HeaderMap::new();
+                headers.insert("Nats-Msg-Id", record.id.to_string().as_str());
+                headers.insert("Mtchat-Event-Type", record.event_type);
+                headers.insert("Mtchat-Dialog-Id", record.key.to_string().as_str());
+                client
+                    .publish_with_headers(
+                        record.topic.clone(),
+                        headers,
+                        record.payload.clone().into(),
+                    )
+                    .await
+                    .map_err(|e| EventStreamError::Publish(e.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+    use rskafka::client::{Client, ClientBuilder};
+    use rskafka::record::Record;
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    use super::{EventStreamError, StreamRecord};
+
+    /// Kafka producer; events of a dialog always go to the same partition
+    pub(super) struct KafkaPublisher {
+        client: Client,
+        /// Partition clients per topic, resolved on first use
+        partitions: Mutex<HashMap<String, Arc<Vec<PartitionClient>>>>,
+    }
+
+    impl KafkaPublisher {
+        pub(super) async fn connect(brokers: Vec<String>) -> Result<Self, EventStreamError> {
+            let client = ClientBuilder::new(brokers)
+                .client_id("mtchat")
+                .build()
+                .await
+                .map_err(|e| EventStreamError::Connect(e.to_string()))?;
+            Ok(Self {
+                client,
+                partitions: Mutex::new(HashMap::new()),
+            })
+        }
+
+        async fn partitions(
+            &self,
+            topic: &str,
+        ) -> Result<Arc<Vec<PartitionClient>>, EventStreamError> {
+            let mut cache = self.partitions.lock().await;
+            if let Some(partitions) = cache.get(topic) {
+                return Ok(partitions.clone());
+            }
+
+            let error = |e: rskafka::client::error::Error| EventStreamError::Publish(e.to_string());
+            let topics = self.client.list_topics().await.map_err(error)?;
+            let ids = topics
+                .into_iter()
+                .find(|t| t.name == topic)
+                .map(|t| t.partitions)
+                .unwrap_or_default();
+            if ids.is_empty() {
+                return Err(EventStreamError::Publish(format!(
+                    "topic {} does not exist",
+                    topic
+                )));
+            }
+
+            let mut partitions = Vec::with_capacity(ids.len());
+            for id in ids {
+                partitions.push(
+                    self.client
+                        .partition_client(topic, id, UnknownTopicHandling::Retry)
+                        .await
+                        .map_err(error)?,
+                );
+            }
+            let partitions = Arc::new(partitions);
+            cache.insert(topic.to_string(), partitions.clone());
+            Ok(partitions)
+        }
+
+        pub(super) async fn publish(&self, record: &StreamRecord) -> Result<(), EventStreamError> {
+            let partitions = self.partitions(&record.topic).await?;
+            let partition = &partitions[(record.key.as_u128() % partitions.len() as u128) as usize];
+
+            let headers = BTreeMap::from([
+                ("event_id".to_string(), record.id.to_string().into_bytes()),
+                (
+                    "event_type".to_string(),
+                    record.event_type.as_bytes().to_vec(),
+                ),
+            ]);
+            let kafka_record = Record {
+                key: Some(record.key.to_string().into_bytes()),
+                value: Some(record.payload.clone()),
+                headers,
+                timestamp: record.timestamp,
+            };
+
+            partition
+                .produce(vec![kafka_record], Compression::NoCompression)
+                .await
+                .map(|_| ())
+                .map_err(|e| EventStreamError::Publish(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_topics_and_servers() {
+        let config = EventStreamConfig {
+            backend: EventStreamBackend::Nats,
+            servers: "nats://a:4222, nats://b:4222,".to_string(),
+            ..Default::default()
+        };
+        assert!(config.is_enabled());
+        assert_eq!(config.server_list(), ["nats://a:4222", "nats://b:4222"]);
+        assert_eq!(config.topic("message.new"), "mtchat.message.new");
+        assert!(!EventStreamConfig::default().is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_connect_requires_servers() {
+        let config = EventStreamConfig {
+            backend: EventStreamBackend::Kafka,
+            ..Default::default()
+        };
+        assert!(matches!(
+            EventStream::connect(&config).await,
+            Err(EventStreamError::NotConfigured)
+        ));
+    }
+}
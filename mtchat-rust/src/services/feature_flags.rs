@@ -0,0 +1,128 @@
+//! Feature flags for staged rollouts
+//!
+//! Global flag values come from the `feature_flags` runtime setting; per-tenant
+//! and per-dialog overrides live in `feature_flag_overrides`. Handlers gating a
+//! new capability call [`FeatureFlagService::require`] with the dialog the
+//! request targets.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::domain::feature_flag::{is_valid_flag_name, resolve_flags, MAX_FLAG_LENGTH};
+use crate::domain::{FeatureFlagOverride, FlagScope};
+use crate::repositories::FeatureFlagRepository;
+
+use super::SettingsService;
+
+#[derive(Debug, Error)]
+pub enum FeatureFlagError {
+    #[error("Invalid flag name '{0}': use up to {MAX_FLAG_LENGTH} lowercase letters, digits, '_' or '-'")]
+    InvalidFlag(String),
+
+    #[error("Feature '{0}' is not enabled for this dialog")]
+    Disabled(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Resolves effective flags for dialogs
+pub struct FeatureFlagService {
+    repo: FeatureFlagRepository,
+    settings: Arc<SettingsService>,
+}
+
+impl FeatureFlagService {
+    pub fn new(repo: FeatureFlagRepository, settings: Arc<SettingsService>) -> Self {
+        Self { repo, settings }
+    }
+
+    /// Effective flags of a dialog (global values with overrides applied)
+    pub async fn flags_for_dialog(
+        &self,
+        dialog_id: Uuid,
+    ) -> Result<BTreeMap<String, bool>, FeatureFlagError> {
+        let overrides = self.repo.find_for_dialog(dialog_id).await?;
+        Ok(resolve_flags(
+            &self.settings.current().feature_flags,
+            &overrides,
+        ))
+    }
+
+    /// Effective flags of a tenant (global values with its overrides applied)
+    pub async fn flags_for_tenant(
+        &self,
+        tenant_uid: &str,
+    ) -> Result<BTreeMap<String, bool>, FeatureFlagError> {
+        let overrides = self.repo.find_for_tenant(tenant_uid).await?;
+        Ok(resolve_flags(
+            &self.settings.current().feature_flags,
+            &overrides,
+        ))
+    }
+
+    /// Whether a flag is on for a dialog (unset flags are off)
+    pub async fn is_enabled(&self, dialog_id: Uuid, flag: &str) -> Result<bool, FeatureFlagError> {
+        let flags = self.flags_for_dialog(dialog_id).await?;
+        Ok(flags.get(flag).copied().unwrap_or(false))
+    }
+
+    /// Fail with [`FeatureFlagError::Disabled`] unless the flag is on
+    pub async fn require(&self, dialog_id: Uuid, flag: &str) -> Result<(), FeatureFlagError> {
+        if self.is_enabled(dialog_id, flag).await? {
+            Ok(())
+        } else {
+            Err(FeatureFlagError::Disabled(flag.to_string()))
+        }
+    }
+
+    /// List overrides, optionally for a single flag
+    pub async fn list_overrides(
+        &self,
+        flag: Option<&str>,
+    ) -> Result<Vec<FeatureFlagOverride>, FeatureFlagError> {
+        if let Some(flag) = flag {
+            validate_flag(flag)?;
+        }
+        Ok(self.repo.list(flag).await?)
+    }
+
+    /// Turn a flag on or off for a tenant or dialog
+    pub async fn set_override(
+        &self,
+        flag: &str,
+        scope_type: FlagScope,
+        scope_id: &str,
+        enabled: bool,
+    ) -> Result<FeatureFlagOverride, FeatureFlagError> {
+        validate_flag(flag)?;
+        Ok(self.repo.set(flag, scope_type, scope_id, enabled).await?)
+    }
+
+    /// Remove an override so the tenant or global value applies again.
+    /// Returns true if one existed.
+    pub async fn remove_override(
+        &self,
+        flag: &str,
+        scope_type: FlagScope,
+        scope_id: &str,
+    ) -> Result<bool, FeatureFlagError> {
+        validate_flag(flag)?;
+        Ok(self.repo.delete(flag, scope_type, scope_id).await?)
+    }
+
+    /// Drop the overrides of a deleted dialog
+    pub async fn remove_dialog(&self, dialog_id: Uuid) -> Result<(), FeatureFlagError> {
+        Ok(self.repo.delete_for_dialog(dialog_id).await?)
+    }
+}
+
+fn validate_flag(flag: &str) -> Result<(), FeatureFlagError> {
+    if is_valid_flag_name(flag) {
+        Ok(())
+    } else {
+        Err(FeatureFlagError::InvalidFlag(flag.to_string()))
+    }
+}
@@ -0,0 +1,506 @@
+//! Local filesystem storage backend
+//!
+//! Stores attachments under a root directory so small self-hosted deployments
+//! can run without MinIO/S3. Files are served through `/api/v1/files/{key}`.
+//! Upload and download URLs carry an expiry and an HMAC-SHA256 signature,
+//! mirroring S3 presigned URLs, so browsers can use them without an
+//! `Authorization` header.
+
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use super::storage::{BlobStorage, StorageError};
+use crate::config::serde_helpers::secs;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Route prefix the signed file URLs point to
+pub const FILES_ROUTE_PREFIX: &str = "/api/v1/files";
+
+/// Filesystem storage configuration (`[storage.fs]` section)
+///
+/// Environment variables:
+/// - `STORAGE_FS_ROOT` (default: ./data/attachments)
+/// - `STORAGE_FS_PUBLIC_URL` (default: empty, URLs are relative to the API host)
+/// - `STORAGE_FS_SECRET` (default: random per process; set it when running
+///   several replicas or to keep URLs valid across restarts)
+/// - `S3_PRESIGN_UPLOAD_EXPIRY` (default: 300 seconds)
+/// - `S3_PRESIGN_DOWNLOAD_EXPIRY` (default: 3600 seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FsStorageConfig {
+    /// Root directory for stored files
+    pub root: PathBuf,
+    /// Public base URL of the MTChat API as seen by browsers (empty = relative URLs)
+    pub public_url: String,
+    /// Secret for signing file URLs (empty = random per process)
+    pub secret: String,
+    /// Upload URL expiry (default: 5 minutes)
+    #[serde(rename = "upload_expiry_secs", with = "secs")]
+    pub upload_expiry: Duration,
+    /// Download URL expiry (default: 1 hour)
+    #[serde(rename = "download_expiry_secs", with = "secs")]
+    pub download_expiry: Duration,
+}
+
+impl Default for FsStorageConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("./data/attachments"),
+            public_url: String::new(),
+            secret: String::new(),
+            upload_expiry: Duration::from_secs(300),
+            download_expiry: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// Operation a signed file URL grants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAccess {
+    Upload,
+    Download,
+}
+
+impl FileAccess {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FileAccess::Upload => "PUT",
+            FileAccess::Download => "GET",
+        }
+    }
+}
+
+/// Local filesystem storage
+///
+/// Layout under the root directory:
+/// - `objects/{key}` - file contents
+/// - `meta/{key}` - content type recorded at upload
+pub struct FsStorage {
+    root: PathBuf,
+    public_url: String,
+    base_path: String,
+    secret: String,
+    upload_expiry: Duration,
+    download_expiry: Duration,
+}
+
+impl FsStorage {
+    /// Create a new filesystem storage, creating the root directory if needed
+    pub fn new(config: FsStorageConfig) -> Result<Self, StorageError> {
+        std::fs::create_dir_all(&config.root).map_err(|e| {
+            StorageError::ConfigError(format!(
+                "Cannot create storage root {}: {}",
+                config.root.display(),
+                e
+            ))
+        })?;
+
+        let secret = if config.secret.is_empty() {
+            tracing::warn!(
+                "STORAGE_FS_SECRET not set — using a random secret, file URLs won't survive restarts"
+            );
+            format!("{}{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4())
+        } else {
+            config.secret
+        };
+
+        Ok(Self {
+            root: config.root,
+            public_url: config.public_url.trim_end_matches('/').to_string(),
+            base_path: String::new(),
+            secret,
+            upload_expiry: config.upload_expiry,
+            download_expiry: config.download_expiry,
+        })
+    }
+
+    /// Prefix of the service's routes (see `server.base_path`) for signed URLs
+    pub fn with_base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Get the root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Verify a signed file URL
+    ///
+    /// `content_type` and `size` are only part of the signature for uploads
+    /// (empty and `None` for downloads).
+    pub fn verify_signature(
+        &self,
+        access: FileAccess,
+        key: &str,
+        expires: i64,
+        content_type: &str,
+        size: Option<i64>,
+        signature: &str,
+    ) -> bool {
+        if expires < chrono::Utc::now().timestamp() {
+            return false;
+        }
+        let expected = self.sign(access, key, expires, content_type, size);
+        constant_time_eq(expected.as_bytes(), signature.as_bytes())
+    }
+
+    fn sign(
+        &self,
+        access: FileAccess,
+        key: &str,
+        expires: i64,
+        content_type: &str,
+        size: Option<i64>,
+    ) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(
+            format!(
+                "{}\n{}\n{}\n{}",
+                access.as_str(),
+                key,
+                expires,
+                content_type
+            )
+            .as_bytes(),
+        );
+        if let Some(size) = size {
+            mac.update(format!("\n{}", size).as_bytes());
+        }
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn signed_url(
+        &self,
+        access: FileAccess,
+        key: &str,
+        expiry: Duration,
+        content_type: &str,
+        size: Option<i64>,
+    ) -> String {
+        let expires = chrono::Utc::now().timestamp() + expiry.as_secs() as i64;
+        let signature = self.sign(access, key, expires, content_type, size);
+        format!(
+            "{}{}{}/{}?expires={}&signature={}",
+            self.public_url, self.base_path, FILES_ROUTE_PREFIX, key, expires, signature
+        )
+    }
+
+    fn object_path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.root.join("objects").join(safe_relative_path(key)?))
+    }
+
+    fn meta_path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.root.join("meta").join(safe_relative_path(key)?))
+    }
+
+    async fn write_file(path: &Path, data: &[u8]) -> Result<(), StorageError> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| StorageError::OperationFailed(e.to_string()))?;
+        }
+        tokio::fs::write(path, data)
+            .await
+            .map_err(|e| StorageError::OperationFailed(e.to_string()))
+    }
+
+    pub async fn object_exists(&self, key: &str) -> Result<bool, StorageError> {
+        let path = self.object_path(key)?;
+        tokio::fs::try_exists(&path)
+            .await
+            .map_err(|e| StorageError::OperationFailed(e.to_string()))
+    }
+
+    pub async fn get_object_info(&self, key: &str) -> Result<(String, i64), StorageError> {
+        let metadata = tokio::fs::metadata(self.object_path(key)?)
+            .await
+            .map_err(|e| map_io_error(e, key))?;
+
+        let content_type = tokio::fs::read_to_string(self.meta_path(key)?)
+            .await
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        Ok((content_type, metadata.len() as i64))
+    }
+
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        tokio::fs::read(self.object_path(key)?)
+            .await
+            .map_err(|e| map_io_error(e, key))
+    }
+
+    pub async fn put_object(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        Self::write_file(&self.object_path(key)?, &data).await?;
+        Self::write_file(&self.meta_path(key)?, content_type.as_bytes()).await
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
+        for path in [self.object_path(key)?, self.meta_path(key)?] {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(StorageError::OperationFailed(e.to_string())),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BlobStorage for FsStorage {
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    fn upload_expiry_secs(&self) -> u64 {
+        self.upload_expiry.as_secs()
+    }
+
+    fn download_expiry_secs(&self) -> Option<u64> {
+        Some(self.download_expiry.as_secs())
+    }
+
+    fn generate_upload_url<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        size: i64,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            safe_relative_path(key)?;
+            Ok(self.signed_url(
+                FileAccess::Upload,
+                key,
+                self.upload_expiry,
+                content_type,
+                Some(size),
+            ))
+        })
+    }
+
+    fn generate_download_url<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            safe_relative_path(key)?;
+            Ok(self.signed_url(FileAccess::Download, key, self.download_expiry, "", None))
+        })
+    }
+
+    fn object_exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<bool, StorageError>> {
+        Box::pin(FsStorage::object_exists(self, key))
+    }
+
+    fn get_object_info<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(String, i64), StorageError>> {
+        Box::pin(FsStorage::get_object_info(self, key))
+    }
+
+    fn get_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>> {
+        Box::pin(FsStorage::get_object(self, key))
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        key: &'a str,
+        data: Vec<u8>,
+        content_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(FsStorage::put_object(self, key, data, content_type))
+    }
+
+    fn delete_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(FsStorage::delete_object(self, key))
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), StorageError>> {
+        Box::pin(async move {
+            let metadata = tokio::fs::metadata(&self.root)
+                .await
+                .map_err(|e| StorageError::OperationFailed(e.to_string()))?;
+            if !metadata.is_dir() {
+                return Err(StorageError::ConfigError(format!(
+                    "{} is not a directory",
+                    self.root.display()
+                )));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Convert an object key to a relative path, rejecting anything that could
+/// escape the storage root (absolute paths, `..`, empty segments, NUL bytes).
+fn safe_relative_path(key: &str) -> Result<PathBuf, StorageError> {
+    let invalid = || StorageError::OperationFailed(format!("Invalid object key: {}", key));
+
+    if key.is_empty() || key.contains('\0') || key.contains('\\') {
+        return Err(invalid());
+    }
+
+    let path = Path::new(key);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(invalid());
+    }
+
+    Ok(path.to_path_buf())
+}
+
+fn map_io_error(e: std::io::Error, key: &str) -> StorageError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        StorageError::NotFound(key.to_string())
+    } else {
+        StorageError::OperationFailed(e.to_string())
+    }
+}
+
+/// Constant-time comparison
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_storage() -> FsStorage {
+        FsStorage::new(FsStorageConfig {
+            root: std::env::temp_dir().join(format!("mtchat-fs-{}", uuid::Uuid::new_v4())),
+            public_url: "http://localhost:8080".to_string(),
+            secret: "test-secret".to_string(),
+            upload_expiry: Duration::from_secs(300),
+            download_expiry: Duration::from_secs(3600),
+        })
+        .unwrap()
+    }
+
+    fn query_param<'a>(url: &'a str, name: &str) -> &'a str {
+        url.split(['?', '&'])
+            .find_map(|pair| pair.strip_prefix(&format!("{}=", name)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert!(safe_relative_path("dialogs/abc/file.jpg").is_ok());
+        assert!(safe_relative_path("../etc/passwd").is_err());
+        assert!(safe_relative_path("dialogs/../../etc/passwd").is_err());
+        assert!(safe_relative_path("/etc/passwd").is_err());
+        assert!(safe_relative_path("dialogs\\..\\x").is_err());
+        assert!(safe_relative_path("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_download_url_roundtrip() {
+        let storage = test_storage();
+        let key = "dialogs/abc/file.jpg";
+        let url = BlobStorage::generate_download_url(&storage, key)
+            .await
+            .unwrap();
+
+        assert!(url.starts_with("http://localhost:8080/api/v1/files/dialogs/abc/file.jpg?"));
+
+        let expires: i64 = query_param(&url, "expires").parse().unwrap();
+        let signature = query_param(&url, "signature");
+        assert!(storage.verify_signature(FileAccess::Download, key, expires, "", None, signature));
+        // Signature is bound to the operation and key
+        assert!(!storage.verify_signature(FileAccess::Upload, key, expires, "", None, signature));
+        assert!(!storage.verify_signature(
+            FileAccess::Download,
+            "other",
+            expires,
+            "",
+            None,
+            signature
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signed_upload_url_is_bound_to_the_size() {
+        let storage = test_storage();
+        let key = "dialogs/abc/file.jpg";
+        let url = BlobStorage::generate_upload_url(&storage, key, "image/jpeg", 1024)
+            .await
+            .unwrap();
+
+        let expires: i64 = query_param(&url, "expires").parse().unwrap();
+        let signature = query_param(&url, "signature");
+        let verify = |content_type, size| {
+            storage.verify_signature(
+                FileAccess::Upload,
+                key,
+                expires,
+                content_type,
+                size,
+                signature,
+            )
+        };
+        assert!(verify("image/jpeg", Some(1024)));
+        assert!(!verify("image/jpeg", Some(1025)));
+        assert!(!verify("image/png", Some(1024)));
+        assert!(!verify("image/jpeg", None));
+    }
+
+    #[test]
+    fn test_expired_signature_rejected() {
+        let storage = test_storage();
+        let expires = chrono::Utc::now().timestamp() - 1;
+        let signature = storage.sign(FileAccess::Download, "k", expires, "", None);
+        assert!(!storage.verify_signature(
+            FileAccess::Download,
+            "k",
+            expires,
+            "",
+            None,
+            &signature
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_put_get_delete() {
+        let storage = test_storage();
+        let key = "dialogs/abc/file.txt";
+
+        storage
+            .put_object(key, b"hello".to_vec(), "text/plain")
+            .await
+            .unwrap();
+        assert!(storage.object_exists(key).await.unwrap());
+        assert_eq!(storage.get_object(key).await.unwrap(), b"hello");
+        assert_eq!(
+            storage.get_object_info(key).await.unwrap(),
+            ("text/plain".to_string(), 5)
+        );
+
+        storage.delete_object(key).await.unwrap();
+        assert!(!storage.object_exists(key).await.unwrap());
+        assert!(matches!(
+            storage.get_object(key).await,
+            Err(StorageError::NotFound(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(storage.root());
+    }
+}
@@ -0,0 +1,201 @@
+//! Support impersonation tokens
+//!
+//! The Management API issues short-lived tokens that let a support engineer
+//! view one dialog exactly as one of its participants sees it. A token is the
+//! base64url-encoded claims plus an HMAC-SHA256 signature; it is accepted only
+//! by the read-only impersonation routes, never by the Chat API.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::serde_helpers::secs;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Route prefix of the read-only impersonation views
+pub const IMPERSONATION_ROUTE_PREFIX: &str = "/api/v1/impersonation";
+
+/// Header carrying the impersonation token
+pub const IMPERSONATION_TOKEN_HEADER: &str = "X-Impersonation-Token";
+
+/// Impersonation configuration (`[impersonation]` section)
+///
+/// Environment variables:
+/// - `IMPERSONATION_SECRET` (default: random per process; set it when running
+///   several replicas)
+/// - `IMPERSONATION_MAX_TTL_SECS` (default: 3600 seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImpersonationConfig {
+    /// Secret for signing tokens (empty = random per process)
+    pub secret: String,
+    /// Longest lifetime a token can be issued for (default: 1 hour)
+    #[serde(rename = "max_ttl_secs", with = "secs")]
+    pub max_ttl: Duration,
+}
+
+impl Default for ImpersonationConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            max_ttl: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// What an impersonation token grants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImpersonationClaims {
+    /// Session ID, referenced by the audit log entries
+    pub sid: Uuid,
+    pub dialog_id: Uuid,
+    /// Participant being impersonated
+    pub user_id: String,
+    /// Support engineer the token was issued to
+    pub actor: String,
+    /// Expiry (unix seconds)
+    pub exp: i64,
+}
+
+impl ImpersonationClaims {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.exp, 0).unwrap_or_default()
+    }
+}
+
+/// Signs and verifies impersonation tokens
+pub struct ImpersonationSigner {
+    secret: String,
+    max_ttl: Duration,
+}
+
+impl ImpersonationSigner {
+    pub fn new(config: ImpersonationConfig) -> Self {
+        let secret = if config.secret.is_empty() {
+            tracing::warn!(
+                "IMPERSONATION_SECRET not set — using a random secret, impersonation tokens are valid on this instance only"
+            );
+            format!("{}{}", Uuid::new_v4(), Uuid::new_v4())
+        } else {
+            config.secret
+        };
+
+        Self {
+            secret,
+            max_ttl: config.max_ttl,
+        }
+    }
+
+    /// Longest lifetime a token can be issued for
+    pub fn max_ttl(&self) -> Duration {
+        self.max_ttl
+    }
+
+    /// Build a signed token
+    pub fn issue(&self, claims: &ImpersonationClaims) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).expect("claims serialize"));
+        let signature = self.sign(&payload);
+        format!("{}.{}", payload, signature)
+    }
+
+    /// Verify a token; `None` if it is malformed, tampered with or expired
+    pub fn verify(&self, token: &str) -> Option<ImpersonationClaims> {
+        let (payload, signature) = token.split_once('.')?;
+        if !constant_time_eq(self.sign(payload).as_bytes(), signature.as_bytes()) {
+            return None;
+        }
+        let claims: ImpersonationClaims =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        if claims.exp < Utc::now().timestamp() {
+            return None;
+        }
+        Some(claims)
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(b"impersonation\n");
+        mac.update(payload.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+/// Constant-time comparison
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer() -> ImpersonationSigner {
+        ImpersonationSigner::new(ImpersonationConfig {
+            secret: "test-secret".into(),
+            ..Default::default()
+        })
+    }
+
+    fn claims(exp: i64) -> ImpersonationClaims {
+        ImpersonationClaims {
+            sid: Uuid::now_v7(),
+            dialog_id: Uuid::now_v7(),
+            user_id: "user-1".into(),
+            actor: "support@example.com".into(),
+            exp,
+        }
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let signer = signer();
+        let claims = claims(Utc::now().timestamp() + 60);
+        let token = signer.issue(&claims);
+        assert_eq!(signer.verify(&token), Some(claims));
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let signer = signer();
+        let token = signer.issue(&claims(Utc::now().timestamp() - 1));
+        assert!(signer.verify(&token).is_none());
+    }
+
+    #[test]
+    fn test_tampered_token_rejected() {
+        let signer = signer();
+        let token = signer.issue(&claims(Utc::now().timestamp() + 60));
+        let (_, signature) = token.split_once('.').unwrap();
+
+        let mut forged = claims(Utc::now().timestamp() + 60);
+        forged.user_id = "user-2".into();
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+        assert!(signer
+            .verify(&format!("{}.{}", payload, signature))
+            .is_none());
+        assert!(signer.verify("not-a-token").is_none());
+    }
+
+    #[test]
+    fn test_other_secret_rejected() {
+        let token = signer().issue(&claims(Utc::now().timestamp() + 60));
+        let other = ImpersonationSigner::new(ImpersonationConfig {
+            secret: "other-secret".into(),
+            ..Default::default()
+        });
+        assert!(other.verify(&token).is_none());
+    }
+}
@@ -2,8 +2,46 @@
 //!
 //! Contains business logic and external service integrations.
 
+mod broker;
+mod circuit_breaker;
+mod connection_registry;
+mod event_stream;
+mod feature_flags;
+mod fs_storage;
+mod impersonation;
 mod presence;
+pub mod preview;
 mod s3;
+mod settings;
+mod slow_mode;
+mod storage;
+pub mod text_extract;
+mod transcript;
+mod upload_limiter;
 
+pub use broker::{Broker, BrokerError, PgBroker, RedisBroker, Subscription};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, GuardedStorage};
+pub use connection_registry::{
+    ConnectedUser, ConnectionMetrics, ConnectionRegistry, InstanceSnapshot, DISCONNECT_CHANNEL,
+    SNAPSHOT_INTERVAL,
+};
+pub use event_stream::{EventStream, EventStreamBackend, EventStreamConfig, EventStreamError};
+pub use feature_flags::{FeatureFlagError, FeatureFlagService};
+pub use fs_storage::{FileAccess, FsStorage, FsStorageConfig, FILES_ROUTE_PREFIX};
+pub use impersonation::{
+    ImpersonationClaims, ImpersonationConfig, ImpersonationSigner, IMPERSONATION_ROUTE_PREFIX,
+    IMPERSONATION_TOKEN_HEADER,
+};
 pub use presence::PresenceService;
-pub use s3::{S3Config, S3Error, S3Service};
+pub use s3::{S3Config, S3Service};
+pub use settings::{
+    RuntimeSettings, SettingEntry, SettingsError, SettingsService, DEFAULT_NOTIFICATION_DELAY_MS,
+    MAX_NOTIFICATION_DELAY_MS, SETTINGS_CHANNEL, SETTINGS_RELOAD_INTERVAL,
+};
+pub use slow_mode::{SlowModeError, SlowModeLimiter, MAX_SLOW_MODE_SECS};
+pub use storage::{BlobStorage, StorageError};
+pub use transcript::{
+    Transcript, TranscriptAttachment, TranscriptConfig, TranscriptFormat, TranscriptSigner,
+    MAX_TRANSCRIPT_MESSAGES, MAX_TRANSCRIPT_RECIPIENT_LENGTH, TRANSCRIPTS_ROUTE_PREFIX,
+};
+pub use upload_limiter::{UploadLimitConfig, UploadLimitError, UploadLimiter};
@@ -1,12 +1,16 @@
 //! Presence service for tracking user online status
 //!
-//! Uses Redis to store online status with TTL-based expiration.
+//! Uses Redis to store online status with TTL-based expiration. Without Redis
+//! (single instance) users are online while they hold a WebSocket connection
+//! of this instance.
 
 use fred::clients::Pool;
 use fred::error::Error as RedisError;
-use fred::interfaces::KeysInterface;
+use fred::interfaces::{ClientLike, KeysInterface};
 use std::sync::Arc;
 
+use crate::ws::Connections;
+
 /// TTL for online status keys in seconds (60s)
 /// Heartbeat is 30s, so status expires if 2 heartbeats are missed
 const ONLINE_TTL: i64 = 60;
@@ -14,22 +18,45 @@ const ONLINE_TTL: i64 = 60;
 /// Service for managing user online presence via Redis
 pub struct PresenceService {
     redis: Option<Arc<Pool>>,
+    /// Connections of this instance, used when Redis is not configured
+    local: Option<Connections>,
 }
 
 impl PresenceService {
     /// Create a new presence service with Redis connection
     pub fn new(redis: Arc<Pool>) -> Self {
-        Self { redis: Some(redis) }
+        Self {
+            redis: Some(redis),
+            local: None,
+        }
+    }
+
+    /// Create a presence service backed by this instance's connections
+    /// (single-instance mode without Redis)
+    pub fn local(connections: Connections) -> Self {
+        Self {
+            redis: None,
+            local: Some(connections),
+        }
     }
 
-    /// Create a no-op presence service (when Redis is not configured)
+    /// Create a no-op presence service that reports everyone offline
     pub fn noop() -> Self {
-        Self { redis: None }
+        Self {
+            redis: None,
+            local: None,
+        }
     }
 
     /// Check if service is configured
     pub fn is_configured(&self) -> bool {
-        self.redis.is_some()
+        self.redis.is_some() || self.local.is_some()
+    }
+
+    /// Ping Redis (`None` when Redis is not configured)
+    pub async fn ping(&self) -> Option<Result<(), RedisError>> {
+        let redis = self.redis.as_ref()?;
+        Some(redis.ping::<String>(None).await.map(|_| ()))
     }
 
     /// Set user as online (with TTL)
@@ -76,7 +103,7 @@ impl PresenceService {
     /// Get list of online users from a list of user IDs (batch check)
     pub async fn get_online_users(&self, user_ids: &[String]) -> Result<Vec<String>, RedisError> {
         let Some(redis) = &self.redis else {
-            return Ok(vec![]);
+            return Ok(self.local_online(user_ids));
         };
 
         if user_ids.is_empty() {
@@ -110,11 +137,56 @@ impl PresenceService {
     /// Check if a single user is online
     pub async fn is_online(&self, user_id: &str) -> Result<bool, RedisError> {
         let Some(redis) = &self.redis else {
-            return Ok(false);
+            return Ok(self
+                .local
+                .as_ref()
+                .is_some_and(|connections| connections.contains_key(user_id)));
         };
 
         let key = format!("online:{}", user_id);
         let result: Option<String> = redis.get(&key).await?;
         Ok(result.is_some())
     }
+
+    /// Users of `user_ids` connected to this instance
+    fn local_online(&self, user_ids: &[String]) -> Vec<String> {
+        let Some(connections) = &self.local else {
+            return vec![];
+        };
+        user_ids
+            .iter()
+            .filter(|id| connections.contains_key(id.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_presence_follows_connections() {
+        let connections: Connections = Arc::new(dashmap::DashMap::new());
+        let presence = PresenceService::local(connections.clone());
+        assert!(presence.is_configured());
+
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        connections.insert("u1".to_string(), crate::ws::Connection::new(tx));
+
+        let users = vec!["u1".to_string(), "u2".to_string()];
+        assert_eq!(presence.get_online_users(&users).await.unwrap(), vec!["u1"]);
+        assert!(presence.is_online("u1").await.unwrap());
+        assert!(!presence.is_online("u2").await.unwrap());
+
+        connections.remove("u1");
+        assert!(presence.get_online_users(&users).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_noop_presence_reports_offline() {
+        let presence = PresenceService::noop();
+        assert!(!presence.is_configured());
+        assert!(!presence.is_online("u1").await.unwrap());
+    }
 }
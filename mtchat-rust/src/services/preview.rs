@@ -0,0 +1,170 @@
+//! Document preview rendering
+//!
+//! Renders the first page of PDF attachments to a PNG so the widget can show
+//! document previews the same way it shows image thumbnails. Rendering uses
+//! pdfium and is only compiled with the `pdf-preview` feature. The pdfium
+//! shared library is loaded at runtime from the configured directory
+//! (`jobs.pdfium_lib_path`) or the system library path.
+//!
+//! Also renders the resized variants of participant avatars (always available).
+
+use thiserror::Error;
+
+/// Maximum width/height of a rendered preview in pixels
+pub const PREVIEW_MAX_DIMENSION: i32 = 480;
+
+/// Content type of rendered previews
+pub const PREVIEW_CONTENT_TYPE: &str = "image/png";
+
+#[derive(Debug, Error)]
+pub enum PreviewError {
+    #[error("Preview rendering is not enabled in this build")]
+    Unsupported,
+
+    #[error("Failed to render preview: {0}")]
+    RenderFailed(String),
+}
+
+/// Check if this build can render PDF previews
+pub fn is_enabled() -> bool {
+    cfg!(feature = "pdf-preview")
+}
+
+/// Storage key for the preview of an attachment
+///
+/// `dialogs/{id}/{uuid}.pdf` -> `dialogs/{id}/{uuid}_preview.png`
+pub fn preview_key(s3_key: &str) -> String {
+    let name_start = s3_key.rfind('/').map_or(0, |i| i + 1);
+    let stem = match s3_key[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => &s3_key[..name_start + dot],
+        _ => s3_key,
+    };
+    format!("{}_preview.png", stem)
+}
+
+/// Load the pdfium library from `lib_path` (directory) or the system library path
+#[cfg(any(feature = "pdf-preview", feature = "text-extract"))]
+pub(crate) fn bind_pdfium(
+    lib_path: Option<&str>,
+) -> Result<pdfium_render::prelude::Pdfium, pdfium_render::prelude::PdfiumError> {
+    use pdfium_render::prelude::*;
+
+    let bindings = match lib_path {
+        Some(path) => Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(path)),
+        None => Pdfium::bind_to_system_library(),
+    }?;
+    Ok(Pdfium::new(bindings))
+}
+
+/// Render the first page of a PDF to PNG bytes, with pdfium loaded from
+/// `pdfium_lib_path` (see [`bind_pdfium`])
+///
+/// This is CPU-bound; call it from `spawn_blocking`.
+#[cfg(feature = "pdf-preview")]
+pub fn render_pdf_preview(
+    data: &[u8],
+    pdfium_lib_path: Option<&str>,
+) -> Result<Vec<u8>, PreviewError> {
+    use pdfium_render::prelude::*;
+
+    let render_err = |e: PdfiumError| PreviewError::RenderFailed(e.to_string());
+
+    let pdfium = bind_pdfium(pdfium_lib_path).map_err(render_err)?;
+
+    let document = pdfium
+        .load_pdf_from_byte_slice(data, None)
+        .map_err(render_err)?;
+    let page = document.pages().first().map_err(render_err)?;
+
+    let config = PdfRenderConfig::new()
+        .set_maximum_width(PREVIEW_MAX_DIMENSION)
+        .set_maximum_height(PREVIEW_MAX_DIMENSION);
+    let image = page
+        .render_with_config(&config)
+        .map_err(render_err)?
+        .as_image();
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| PreviewError::RenderFailed(e.to_string()))?;
+
+    Ok(png)
+}
+
+/// Render square (center-cropped) PNG variants of an avatar image
+///
+/// Returns `(size, png)` per requested size. This is CPU-bound; call it from
+/// `spawn_blocking`.
+pub fn render_avatar_variants(
+    data: &[u8],
+    sizes: &[u32],
+) -> Result<Vec<(u32, Vec<u8>)>, PreviewError> {
+    let render_err = |e: image::ImageError| PreviewError::RenderFailed(e.to_string());
+
+    let image = image::load_from_memory(data).map_err(render_err)?;
+    sizes
+        .iter()
+        .map(|&size| {
+            let variant = image.resize_to_fill(size, size, image::imageops::FilterType::Lanczos3);
+            let mut png = Vec::new();
+            variant
+                .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+                .map_err(render_err)?;
+            Ok((size, png))
+        })
+        .collect()
+}
+
+/// Render the first page of a PDF to PNG bytes
+///
+/// Always fails: the crate was built without the `pdf-preview` feature.
+#[cfg(not(feature = "pdf-preview"))]
+pub fn render_pdf_preview(
+    _data: &[u8],
+    _pdfium_lib_path: Option<&str>,
+) -> Result<Vec<u8>, PreviewError> {
+    Err(PreviewError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_key() {
+        assert_eq!(
+            preview_key("dialogs/abc/0192.pdf"),
+            "dialogs/abc/0192_preview.png"
+        );
+        assert_eq!(
+            preview_key("dialogs/abc/file"),
+            "dialogs/abc/file_preview.png"
+        );
+        assert_eq!(
+            preview_key("dialogs/a.b/file"),
+            "dialogs/a.b/file_preview.png"
+        );
+    }
+
+    #[test]
+    fn test_render_avatar_variants_are_square() {
+        let source = image::DynamicImage::new_rgb8(40, 20);
+        let mut data = Vec::new();
+        source
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let variants = render_avatar_variants(&data, &[8, 16]).unwrap();
+        assert_eq!(variants.len(), 2);
+        for (size, png) in variants {
+            let variant = image::load_from_memory(&png).unwrap();
+            assert_eq!((variant.width(), variant.height()), (size, size));
+        }
+
+        assert!(render_avatar_variants(b"not an image", &[8]).is_err());
+    }
+}
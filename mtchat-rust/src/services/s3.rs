@@ -1,6 +1,7 @@
 //! S3 Service for file storage operations
 //!
 //! Provides presigned URLs for secure upload/download of attachments.
+//! Default [`BlobStorage`] backend.
 
 use aws_config::BehaviorVersion;
 use aws_sdk_s3::{
@@ -8,26 +9,32 @@ use aws_sdk_s3::{
     presigning::PresigningConfig,
     Client,
 };
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use thiserror::Error;
 
-#[derive(Debug, Error)]
-pub enum S3Error {
-    #[error("S3 operation failed: {0}")]
-    OperationFailed(String),
-
-    #[error("Presigning failed: {0}")]
-    PresigningFailed(String),
-
-    #[error("Object not found: {0}")]
-    NotFound(String),
-
-    #[error("Configuration error: {0}")]
-    ConfigError(String),
-}
-
-/// S3 configuration from environment variables
-#[derive(Debug, Clone)]
+use super::circuit_breaker::CircuitBreakerConfig;
+use super::storage::{BlobStorage, StorageError};
+use crate::config::serde_helpers::secs;
+
+/// S3 configuration (`[s3]` section)
+///
+/// Environment variables:
+/// - `S3_ENDPOINT` (enables S3; the other credentials are then required)
+/// - `S3_REGION`
+/// - `S3_ACCESS_KEY_ID`
+/// - `S3_SECRET_ACCESS_KEY`
+/// - `S3_BUCKET`
+/// - `S3_PUBLIC_ENDPOINT` (default: `S3_ENDPOINT`)
+/// - `S3_PRESIGN_UPLOAD_EXPIRY` (default: 300 seconds)
+/// - `S3_PRESIGN_DOWNLOAD_EXPIRY` (default: 3600 seconds)
+/// - `S3_CDN_BASE_URL` (enables public-read mode, default: unset)
+/// - `S3_TIMEOUT_SECS` (URL, metadata and delete calls, default: 5)
+/// - `S3_TRANSFER_TIMEOUT_SECS` (downloads and uploads, default: 60)
+/// - `S3_BREAKER_FAILURE_THRESHOLD` (default: 5)
+/// - `S3_BREAKER_OPEN_SECS` (default: 30)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct S3Config {
     pub endpoint: String,
     pub region: String,
@@ -37,65 +44,75 @@ pub struct S3Config {
     /// Public endpoint for presigned URLs (may differ from internal endpoint)
     pub public_endpoint: Option<String>,
     /// Presigned upload URL expiry (default: 5 minutes)
+    #[serde(rename = "upload_expiry_secs", with = "secs")]
     pub upload_expiry: Duration,
     /// Presigned download URL expiry (default: 1 hour)
+    #[serde(rename = "download_expiry_secs", with = "secs")]
     pub download_expiry: Duration,
+    /// Base URL for public-read mode (e.g. a CDN in front of the bucket).
+    /// When set, download URLs are built as `{cdn_base_url}/{key}` instead of presigned.
+    pub cdn_base_url: Option<String>,
+    /// Timeout of URL, metadata and delete calls
+    #[serde(rename = "timeout_secs", with = "secs")]
+    pub timeout: Duration,
+    /// Timeout of object downloads and uploads (previews, text extraction)
+    #[serde(rename = "transfer_timeout_secs", with = "secs")]
+    pub transfer_timeout: Duration,
+    /// Consecutive failures after which S3 calls fail fast
+    pub breaker_failure_threshold: u32,
+    /// How long S3 calls fail fast before S3 is tried again
+    #[serde(rename = "breaker_open_secs", with = "secs")]
+    pub breaker_open: Duration,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            region: String::new(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            bucket: String::new(),
+            public_endpoint: None,
+            upload_expiry: Duration::from_secs(300),
+            download_expiry: Duration::from_secs(3600),
+            cdn_base_url: None,
+            timeout: Duration::from_secs(5),
+            transfer_timeout: Duration::from_secs(60),
+            breaker_failure_threshold: 5,
+            breaker_open: Duration::from_secs(30),
+        }
+    }
 }
 
 impl S3Config {
-    /// Create config from environment variables
-    ///
-    /// Required:
-    /// - S3_ENDPOINT
-    /// - S3_REGION
-    /// - S3_ACCESS_KEY_ID
-    /// - S3_SECRET_ACCESS_KEY
-    /// - S3_BUCKET
-    ///
-    /// Optional:
-    /// - S3_PUBLIC_ENDPOINT (default: S3_ENDPOINT)
-    /// - S3_PRESIGN_UPLOAD_EXPIRY (default: 300 seconds)
-    /// - S3_PRESIGN_DOWNLOAD_EXPIRY (default: 3600 seconds)
-    pub fn from_env() -> Result<Self, S3Error> {
-        let endpoint = std::env::var("S3_ENDPOINT")
-            .map_err(|_| S3Error::ConfigError("S3_ENDPOINT not set".into()))?;
-
-        let region = std::env::var("S3_REGION")
-            .map_err(|_| S3Error::ConfigError("S3_REGION not set".into()))?;
-
-        let access_key_id = std::env::var("S3_ACCESS_KEY_ID")
-            .map_err(|_| S3Error::ConfigError("S3_ACCESS_KEY_ID not set".into()))?;
-
-        let secret_access_key = std::env::var("S3_SECRET_ACCESS_KEY")
-            .map_err(|_| S3Error::ConfigError("S3_SECRET_ACCESS_KEY not set".into()))?;
-
-        let bucket = std::env::var("S3_BUCKET")
-            .map_err(|_| S3Error::ConfigError("S3_BUCKET not set".into()))?;
-
-        let public_endpoint = std::env::var("S3_PUBLIC_ENDPOINT").ok();
-
-        let upload_expiry = std::env::var("S3_PRESIGN_UPLOAD_EXPIRY")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .map(Duration::from_secs)
-            .unwrap_or(Duration::from_secs(300));
-
-        let download_expiry = std::env::var("S3_PRESIGN_DOWNLOAD_EXPIRY")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .map(Duration::from_secs)
-            .unwrap_or(Duration::from_secs(3600));
-
-        Ok(Self {
-            endpoint,
-            region,
-            access_key_id,
-            secret_access_key,
-            bucket,
-            public_endpoint,
-            upload_expiry,
-            download_expiry,
-        })
+    /// Timeouts and circuit breaker of S3 calls
+    pub fn circuit_breaker(&self) -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: self.breaker_failure_threshold,
+            open_duration: self.breaker_open,
+            timeout: self.timeout,
+            transfer_timeout: self.transfer_timeout,
+        }
+    }
+
+    /// S3 is enabled once an endpoint is configured
+    pub fn is_configured(&self) -> bool {
+        !self.endpoint.is_empty()
+    }
+
+    /// Names of required settings that are empty
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        [
+            ("region", &self.region),
+            ("access_key_id", &self.access_key_id),
+            ("secret_access_key", &self.secret_access_key),
+            ("bucket", &self.bucket),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_empty())
+        .map(|(name, _)| name)
+        .collect()
     }
 }
 
@@ -107,6 +124,7 @@ pub struct S3Service {
     public_endpoint: String,
     upload_expiry: Duration,
     download_expiry: Duration,
+    cdn_base_url: Option<String>,
 }
 
 impl S3Service {
@@ -133,9 +151,16 @@ impl S3Service {
         Self {
             client,
             bucket: config.bucket,
-            public_endpoint: config.public_endpoint.unwrap_or(config.endpoint),
+            public_endpoint: config
+                .public_endpoint
+                .filter(|s| !s.is_empty())
+                .unwrap_or(config.endpoint),
             upload_expiry: config.upload_expiry,
             download_expiry: config.download_expiry,
+            cdn_base_url: config
+                .cdn_base_url
+                .filter(|s| !s.is_empty())
+                .map(|s| s.trim_end_matches('/').to_string()),
         }
     }
 
@@ -154,6 +179,7 @@ impl S3Service {
             public_endpoint: String::new(),
             upload_expiry: Duration::from_secs(300),
             download_expiry: Duration::from_secs(3600),
+            cdn_base_url: None,
         }
     }
 
@@ -162,11 +188,18 @@ impl S3Service {
         !self.bucket.is_empty()
     }
 
+    /// Check if download URLs are stable public (CDN) URLs rather than presigned
+    pub fn is_public_read(&self) -> bool {
+        self.cdn_base_url.is_some()
+    }
+
     /// Generate a presigned URL for uploading a file
     ///
     /// # Arguments
     /// * `key` - The S3 object key (path)
     /// * `content_type` - The MIME type of the file
+    /// * `size` - The file size in bytes; signed as `Content-Length`, so S3
+    ///   rejects a body of any other size
     ///
     /// # Returns
     /// The presigned URL that can be used for PUT request
@@ -174,11 +207,12 @@ impl S3Service {
         &self,
         key: &str,
         content_type: &str,
-    ) -> Result<String, S3Error> {
+        size: i64,
+    ) -> Result<String, StorageError> {
         let presigning_config = PresigningConfig::builder()
             .expires_in(self.upload_expiry)
             .build()
-            .map_err(|e| S3Error::PresigningFailed(e.to_string()))?;
+            .map_err(|e| StorageError::PresigningFailed(e.to_string()))?;
 
         let presigned = self
             .client
@@ -186,25 +220,32 @@ impl S3Service {
             .bucket(&self.bucket)
             .key(key)
             .content_type(content_type)
+            .content_length(size)
             .presigned(presigning_config)
             .await
-            .map_err(|e| S3Error::PresigningFailed(e.to_string()))?;
+            .map_err(|e| StorageError::PresigningFailed(e.to_string()))?;
 
         Ok(self.rewrite_url(presigned.uri()))
     }
 
-    /// Generate a presigned URL for downloading a file
+    /// Generate a URL for downloading a file
+    ///
+    /// In public-read mode this is the stable CDN URL and no presigning happens.
     ///
     /// # Arguments
     /// * `key` - The S3 object key (path)
     ///
     /// # Returns
-    /// The presigned URL that can be used for GET request
-    pub async fn generate_download_url(&self, key: &str) -> Result<String, S3Error> {
+    /// The URL that can be used for GET request
+    pub async fn generate_download_url(&self, key: &str) -> Result<String, StorageError> {
+        if let Some(base) = &self.cdn_base_url {
+            return Ok(public_url(base, key));
+        }
+
         let presigning_config = PresigningConfig::builder()
             .expires_in(self.download_expiry)
             .build()
-            .map_err(|e| S3Error::PresigningFailed(e.to_string()))?;
+            .map_err(|e| StorageError::PresigningFailed(e.to_string()))?;
 
         let presigned = self
             .client
@@ -213,43 +254,11 @@ impl S3Service {
             .key(key)
             .presigned(presigning_config)
             .await
-            .map_err(|e| S3Error::PresigningFailed(e.to_string()))?;
+            .map_err(|e| StorageError::PresigningFailed(e.to_string()))?;
 
         Ok(self.rewrite_url(presigned.uri()))
     }
 
-    /// Generate presigned URLs for downloading multiple files concurrently
-    ///
-    /// # Arguments
-    /// * `keys` - Slice of S3 object keys
-    ///
-    /// # Returns
-    /// HashMap mapping keys to their presigned URLs (missing on error)
-    pub async fn generate_download_urls_batch(
-        &self,
-        keys: &[&str],
-    ) -> std::collections::HashMap<String, String> {
-        use futures::future::join_all;
-
-        let futures: Vec<_> = keys
-            .iter()
-            .map(|key| {
-                let key = key.to_string();
-                async move {
-                    let result = self.generate_download_url(&key).await;
-                    (key, result)
-                }
-            })
-            .collect();
-
-        let results = join_all(futures).await;
-
-        results
-            .into_iter()
-            .filter_map(|(key, result)| result.ok().map(|url| (key, url)))
-            .collect()
-    }
-
     /// Check if an object exists in S3
     ///
     /// # Arguments
@@ -257,7 +266,7 @@ impl S3Service {
     ///
     /// # Returns
     /// true if the object exists, false otherwise
-    pub async fn object_exists(&self, key: &str) -> Result<bool, S3Error> {
+    pub async fn object_exists(&self, key: &str) -> Result<bool, StorageError> {
         use aws_sdk_s3::operation::head_object::HeadObjectError;
 
         match self
@@ -273,7 +282,7 @@ impl S3Service {
                 let service_err = e.into_service_error();
                 match service_err {
                     HeadObjectError::NotFound(_) => Ok(false),
-                    _ => Err(S3Error::OperationFailed(service_err.to_string())),
+                    _ => Err(StorageError::OperationFailed(service_err.to_string())),
                 }
             }
         }
@@ -286,7 +295,7 @@ impl S3Service {
     ///
     /// # Returns
     /// Content type and size of the object
-    pub async fn get_object_info(&self, key: &str) -> Result<(String, i64), S3Error> {
+    pub async fn get_object_info(&self, key: &str) -> Result<(String, i64), StorageError> {
         use aws_sdk_s3::operation::head_object::HeadObjectError;
 
         let response = self
@@ -299,8 +308,8 @@ impl S3Service {
             .map_err(|e| {
                 let service_err = e.into_service_error();
                 match service_err {
-                    HeadObjectError::NotFound(_) => S3Error::NotFound(key.to_string()),
-                    _ => S3Error::OperationFailed(service_err.to_string()),
+                    HeadObjectError::NotFound(_) => StorageError::NotFound(key.to_string()),
+                    _ => StorageError::OperationFailed(service_err.to_string()),
                 }
             })?;
 
@@ -317,14 +326,26 @@ impl S3Service {
     ///
     /// # Arguments
     /// * `key` - The S3 object key (path)
-    pub async fn delete_object(&self, key: &str) -> Result<(), S3Error> {
+    pub async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
         self.client
             .delete_object()
             .bucket(&self.bucket)
             .key(key)
             .send()
             .await
-            .map_err(|e| S3Error::OperationFailed(e.to_string()))?;
+            .map_err(|e| StorageError::OperationFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Check that the bucket is reachable
+    pub async fn head_bucket(&self) -> Result<(), StorageError> {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .map_err(|e| StorageError::OperationFailed(e.into_service_error().to_string()))?;
 
         Ok(())
     }
@@ -336,7 +357,7 @@ impl S3Service {
     ///
     /// # Returns
     /// The raw bytes of the object
-    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>, S3Error> {
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>, StorageError> {
         use aws_sdk_s3::operation::get_object::GetObjectError;
 
         let response = self
@@ -349,16 +370,15 @@ impl S3Service {
             .map_err(|e| {
                 let service_err = e.into_service_error();
                 match service_err {
-                    GetObjectError::NoSuchKey(_) => S3Error::NotFound(key.to_string()),
-                    _ => S3Error::OperationFailed(service_err.to_string()),
+                    GetObjectError::NoSuchKey(_) => StorageError::NotFound(key.to_string()),
+                    _ => StorageError::OperationFailed(service_err.to_string()),
                 }
             })?;
 
-        let aggregated = response
-            .body
-            .collect()
-            .await
-            .map_err(|e| S3Error::OperationFailed(format!("Failed to read body: {}", e)))?;
+        let aggregated =
+            response.body.collect().await.map_err(|e| {
+                StorageError::OperationFailed(format!("Failed to read body: {}", e))
+            })?;
 
         let bytes = aggregated.into_bytes().to_vec();
 
@@ -376,7 +396,7 @@ impl S3Service {
         key: &str,
         data: Vec<u8>,
         content_type: &str,
-    ) -> Result<(), S3Error> {
+    ) -> Result<(), StorageError> {
         self.client
             .put_object()
             .bucket(&self.bucket)
@@ -385,7 +405,7 @@ impl S3Service {
             .content_type(content_type)
             .send()
             .await
-            .map_err(|e| S3Error::OperationFailed(e.to_string()))?;
+            .map_err(|e| StorageError::OperationFailed(e.to_string()))?;
 
         Ok(())
     }
@@ -409,17 +429,94 @@ impl S3Service {
     }
 }
 
+impl BlobStorage for S3Service {
+    fn is_configured(&self) -> bool {
+        S3Service::is_configured(self)
+    }
+
+    fn upload_expiry_secs(&self) -> u64 {
+        self.upload_expiry.as_secs()
+    }
+
+    /// `None` in public-read mode, where CDN URLs don't expire
+    fn download_expiry_secs(&self) -> Option<u64> {
+        if self.is_public_read() {
+            None
+        } else {
+            Some(self.download_expiry.as_secs())
+        }
+    }
+
+    fn generate_upload_url<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        size: i64,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(S3Service::generate_upload_url(
+            self,
+            key,
+            content_type,
+            size,
+        ))
+    }
+
+    fn generate_download_url<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(S3Service::generate_download_url(self, key))
+    }
+
+    fn object_exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<bool, StorageError>> {
+        Box::pin(S3Service::object_exists(self, key))
+    }
+
+    fn get_object_info<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(String, i64), StorageError>> {
+        Box::pin(S3Service::get_object_info(self, key))
+    }
+
+    fn get_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>> {
+        Box::pin(S3Service::get_object(self, key))
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        key: &'a str,
+        data: Vec<u8>,
+        content_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(S3Service::put_object(self, key, data, content_type))
+    }
+
+    fn delete_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(S3Service::delete_object(self, key))
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), StorageError>> {
+        Box::pin(S3Service::head_bucket(self))
+    }
+}
+
+/// Build a stable public URL for an object key under a CDN base URL
+fn public_url(base: &str, key: &str) -> String {
+    format!("{}/{}", base, key.trim_start_matches('/'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_rewrite_url() {
-        let service = S3Service {
+    fn test_service() -> S3Service {
+        S3Service {
             client: {
                 let config = aws_sdk_s3::Config::builder()
                     .behavior_version(BehaviorVersion::latest())
                     .region(Region::new("us-east-1"))
+                    .credentials_provider(Credentials::new("key", "secret", None, None, "test"))
                     .build();
                 Client::from_conf(config)
             },
@@ -427,7 +524,13 @@ mod tests {
             public_endpoint: "http://localhost:9000".to_string(),
             upload_expiry: Duration::from_secs(300),
             download_expiry: Duration::from_secs(3600),
-        };
+            cdn_base_url: None,
+        }
+    }
+
+    #[test]
+    fn test_rewrite_url() {
+        let service = test_service();
 
         let internal_url = "http://minio:9000/mtchat-attachments/test/file.jpg?X-Amz-Signature=abc";
         let public_url = service.rewrite_url(internal_url);
@@ -437,4 +540,29 @@ mod tests {
             "http://localhost:9000/mtchat-attachments/test/file.jpg?X-Amz-Signature=abc"
         );
     }
+
+    #[tokio::test]
+    async fn test_upload_url_signs_content_length() {
+        let url = test_service()
+            .generate_upload_url("dialogs/abc/file.jpg", "image/jpeg", 1024)
+            .await
+            .unwrap();
+        let signed_headers = url
+            .split(['?', '&'])
+            .find_map(|param| param.strip_prefix("X-Amz-SignedHeaders="))
+            .unwrap();
+        assert!(signed_headers.contains("content-length"));
+    }
+
+    #[test]
+    fn test_public_url() {
+        assert_eq!(
+            public_url("https://cdn.example.com", "dialogs/abc/file.jpg"),
+            "https://cdn.example.com/dialogs/abc/file.jpg"
+        );
+        assert_eq!(
+            public_url("https://cdn.example.com", "/dialogs/abc/file.jpg"),
+            "https://cdn.example.com/dialogs/abc/file.jpg"
+        );
+    }
 }
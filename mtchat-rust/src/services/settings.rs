@@ -0,0 +1,557 @@
+//! Hot-reloadable runtime settings
+//!
+//! Values that operators tune without a redeploy (notification delay and
+//! digest window, archive window, message length limit, sanitization profile,
+//! feature flags). Defaults come from the static configuration; overrides
+//! live in the `settings` table and are cached in memory. When an override
+//! changes, the instance that wrote it publishes the key on
+//! [`SETTINGS_CHANNEL`] so every instance reloads immediately; a periodic
+//! reload covers deployments without a broker or missed messages.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::domain::{
+    validation::MAX_MESSAGE_LENGTH, Dialog, MessagePriority, QuietHours, SanitizeProfile, Setting,
+};
+use crate::repositories::SettingsRepository;
+use crate::services::broker::{Broker, Subscription};
+
+/// Broker channel announcing changed settings (payload: the setting key)
+pub const SETTINGS_CHANNEL: &str = "mtchat:settings";
+
+/// Fallback reload interval when no invalidation message arrives
+pub const SETTINGS_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default delay before checking whether a notified message was read
+pub const DEFAULT_NOTIFICATION_DELAY_MS: u64 = 1000;
+
+/// Upper bound for `max_message_length`
+const MAX_MESSAGE_LENGTH_LIMIT: usize = 1_000_000;
+
+/// Upper bound for notification delays (jobs sleep for this long)
+pub const MAX_NOTIFICATION_DELAY_MS: u64 = 60_000;
+
+/// Upper bound for `notification_digest_window_secs`
+const MAX_NOTIFICATION_DIGEST_WINDOW_SECS: u64 = 3600;
+
+/// Effective runtime settings. Field names are the setting keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeSettings {
+    /// Delay before checking whether a message was read (`notification.pending`)
+    pub notification_delay_ms: u64,
+    /// `notification_delay_ms` of dialogs by object type
+    pub notification_delay_by_object_type: BTreeMap<String, u64>,
+    /// Longest delay for recipients reached by `@channel` / `@here`
+    /// (unset: like other messages)
+    pub mention_notification_delay_ms: Option<u64>,
+    /// Longest delay for urgent messages
+    pub urgent_notification_delay_ms: u64,
+    /// Window in which `notification.pending` webhooks of a recipient are
+    /// coalesced into one `notification.digest` (0 = send each one)
+    pub notification_digest_window_secs: u64,
+    /// Seconds of inactivity before a dialog is auto-archived
+    pub archive_after_secs: i64,
+    /// Local hours in which auto-archiving happens (any time when unset)
+    pub archive_quiet_hours: Option<QuietHours>,
+    /// Maximum message content length in bytes
+    pub max_message_length: usize,
+    /// HTML sanitization profile of dialogs without a tenant or dialog profile
+    pub sanitize_profile: SanitizeProfile,
+    /// Global feature flags
+    pub feature_flags: BTreeMap<String, bool>,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            notification_delay_ms: DEFAULT_NOTIFICATION_DELAY_MS,
+            notification_delay_by_object_type: BTreeMap::new(),
+            mention_notification_delay_ms: None,
+            urgent_notification_delay_ms: 0,
+            notification_digest_window_secs: 0,
+            archive_after_secs: 259200, // 3 days
+            archive_quiet_hours: None,
+            max_message_length: MAX_MESSAGE_LENGTH,
+            sanitize_profile: SanitizeProfile::default(),
+            feature_flags: BTreeMap::new(),
+        }
+    }
+}
+
+impl RuntimeSettings {
+    /// Setting keys, in display order
+    pub const KEYS: [&'static str; 10] = [
+        "notification_delay_ms",
+        "notification_delay_by_object_type",
+        "mention_notification_delay_ms",
+        "urgent_notification_delay_ms",
+        "notification_digest_window_secs",
+        "archive_after_secs",
+        "archive_quiet_hours",
+        "max_message_length",
+        "sanitize_profile",
+        "feature_flags",
+    ];
+
+    pub fn is_known_key(key: &str) -> bool {
+        Self::KEYS.contains(&key)
+    }
+
+    /// Whether a global feature flag is on (unset flags are off)
+    pub fn feature_enabled(&self, flag: &str) -> bool {
+        self.feature_flags.get(flag).copied().unwrap_or(false)
+    }
+
+    /// Delay before checking whether a message in `dialog` was read: the
+    /// dialog's own delay, else its object type's, else the global one.
+    /// Mentions and urgent messages wait at most their own delay.
+    pub fn notification_delay_for(
+        &self,
+        dialog: &Dialog,
+        priority: MessagePriority,
+        mention: bool,
+    ) -> u64 {
+        let delay = dialog
+            .notification_delay_ms
+            .map(|ms| ms.max(0) as u64)
+            .or_else(|| {
+                self.notification_delay_by_object_type
+                    .get(&dialog.object_type)
+                    .copied()
+            })
+            .unwrap_or(self.notification_delay_ms);
+
+        if priority.is_urgent() {
+            delay.min(self.urgent_notification_delay_ms)
+        } else if mention {
+            self.mention_notification_delay_ms
+                .map_or(delay, |ms| delay.min(ms))
+        } else {
+            delay
+        }
+    }
+
+    /// Value of a setting as JSON
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let mut map = self.to_map();
+        map.remove(key)
+    }
+
+    /// Apply one override, validating the key, the type and the range
+    pub fn with_override(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<RuntimeSettings, SettingsError> {
+        if !Self::is_known_key(key) {
+            return Err(SettingsError::UnknownKey(key.to_string()));
+        }
+
+        let mut map = self.to_map();
+        map.insert(key.to_string(), value);
+
+        let settings: RuntimeSettings = serde_json::from_value(serde_json::Value::Object(map))
+            .map_err(|e| SettingsError::InvalidValue {
+                key: key.to_string(),
+                message: e.to_string(),
+            })?;
+        settings
+            .validate()
+            .map_err(|message| SettingsError::InvalidValue {
+                key: key.to_string(),
+                message,
+            })?;
+        Ok(settings)
+    }
+
+    /// Resolve stored overrides on top of `defaults`. Unknown keys and
+    /// invalid values are logged and skipped, so a bad row can't take the
+    /// service down.
+    pub fn resolve(defaults: &RuntimeSettings, overrides: &[Setting]) -> RuntimeSettings {
+        let mut settings = defaults.clone();
+        for setting in overrides {
+            match settings.with_override(&setting.key, setting.value.clone()) {
+                Ok(updated) => settings = updated,
+                Err(e) => tracing::warn!(key = %setting.key, "Ignoring stored setting: {}", e),
+            }
+        }
+        settings
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.notification_delay_ms > MAX_NOTIFICATION_DELAY_MS {
+            return Err(format!(
+                "notification_delay_ms must be at most {}",
+                MAX_NOTIFICATION_DELAY_MS
+            ));
+        }
+        if self
+            .notification_delay_by_object_type
+            .values()
+            .chain(&self.mention_notification_delay_ms)
+            .chain([&self.urgent_notification_delay_ms])
+            .any(|ms| *ms > MAX_NOTIFICATION_DELAY_MS)
+        {
+            return Err(format!(
+                "Notification delays must be at most {}",
+                MAX_NOTIFICATION_DELAY_MS
+            ));
+        }
+        if self.notification_digest_window_secs > MAX_NOTIFICATION_DIGEST_WINDOW_SECS {
+            return Err(format!(
+                "notification_digest_window_secs must be at most {}",
+                MAX_NOTIFICATION_DIGEST_WINDOW_SECS
+            ));
+        }
+        if self.archive_after_secs <= 0 {
+            return Err("archive_after_secs must be positive".to_string());
+        }
+        if let Some(hours) = &self.archive_quiet_hours {
+            if hours.start_hour > 23 || hours.end_hour > 23 || hours.start_hour == hours.end_hour {
+                return Err(
+                    "archive_quiet_hours needs two different hours between 0 and 23".to_string(),
+                );
+            }
+        }
+        if self.max_message_length == 0 || self.max_message_length > MAX_MESSAGE_LENGTH_LIMIT {
+            return Err(format!(
+                "max_message_length must be between 1 and {}",
+                MAX_MESSAGE_LENGTH_LIMIT
+            ));
+        }
+        Ok(())
+    }
+
+    fn to_map(&self) -> serde_json::Map<String, serde_json::Value> {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => unreachable!("RuntimeSettings serializes to an object"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Unknown setting: {0}")]
+    UnknownKey(String),
+
+    #[error("Invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A setting as shown by the Management API
+#[derive(Debug, Serialize)]
+pub struct SettingEntry {
+    pub key: &'static str,
+    /// Effective value
+    pub value: serde_json::Value,
+    /// Configured default
+    pub default: serde_json::Value,
+    /// Whether a stored override is in effect
+    pub overridden: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Cached runtime settings backed by the `settings` table
+pub struct SettingsService {
+    repo: SettingsRepository,
+    defaults: RuntimeSettings,
+    current: RwLock<Arc<RuntimeSettings>>,
+    broker: Option<Arc<dyn Broker>>,
+}
+
+impl SettingsService {
+    /// Create the service. Call [`reload`](Self::reload) before serving
+    /// traffic; until then the defaults are in effect.
+    pub fn new(
+        repo: SettingsRepository,
+        defaults: RuntimeSettings,
+        broker: Option<Arc<dyn Broker>>,
+    ) -> Self {
+        Self {
+            repo,
+            current: RwLock::new(Arc::new(defaults.clone())),
+            defaults,
+            broker,
+        }
+    }
+
+    /// Current settings snapshot (cheap, never blocks on I/O)
+    pub fn current(&self) -> Arc<RuntimeSettings> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Reload overrides from the database
+    pub async fn reload(&self) -> Result<(), sqlx::Error> {
+        let overrides = self.repo.list().await?;
+        let settings = RuntimeSettings::resolve(&self.defaults, &overrides);
+
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        if **current != settings {
+            tracing::info!("Runtime settings reloaded ({} overrides)", overrides.len());
+            *current = Arc::new(settings);
+        }
+        Ok(())
+    }
+
+    /// All settings with their defaults and overrides
+    pub async fn list(&self) -> Result<Vec<SettingEntry>, SettingsError> {
+        let overrides = self.repo.list().await?;
+        let current = RuntimeSettings::resolve(&self.defaults, &overrides);
+
+        Ok(RuntimeSettings::KEYS
+            .into_iter()
+            .map(|key| {
+                let stored = overrides.iter().find(|s| s.key == key);
+                SettingEntry {
+                    key,
+                    value: current.get(key).unwrap_or_default(),
+                    default: self.defaults.get(key).unwrap_or_default(),
+                    overridden: stored.is_some(),
+                    updated_at: stored.map(|s| s.updated_at),
+                }
+            })
+            .collect())
+    }
+
+    /// Store an override and propagate it to all instances
+    pub async fn set(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<SettingEntry, SettingsError> {
+        // Validate against the defaults so an override is valid on its own
+        self.defaults.with_override(key, value.clone())?;
+        self.repo.upsert(key, &value).await?;
+        self.changed(key).await?;
+        self.entry(key).await
+    }
+
+    /// Remove an override, restoring the configured default
+    pub async fn reset(&self, key: &str) -> Result<SettingEntry, SettingsError> {
+        if !RuntimeSettings::is_known_key(key) {
+            return Err(SettingsError::UnknownKey(key.to_string()));
+        }
+        if self.repo.delete(key).await? {
+            self.changed(key).await?;
+        }
+        self.entry(key).await
+    }
+
+    async fn entry(&self, key: &str) -> Result<SettingEntry, SettingsError> {
+        self.list()
+            .await?
+            .into_iter()
+            .find(|e| e.key == key)
+            .ok_or_else(|| SettingsError::UnknownKey(key.to_string()))
+    }
+
+    async fn changed(&self, key: &str) -> Result<(), SettingsError> {
+        self.reload().await?;
+
+        if let Some(broker) = &self.broker {
+            if let Err(e) = broker.publish(SETTINGS_CHANNEL, key).await {
+                tracing::warn!(
+                    "Failed to publish settings invalidation, other instances reload within {}s: {}",
+                    SETTINGS_RELOAD_INTERVAL.as_secs(),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep the cache fresh: reload on invalidation messages (when a
+    /// subscription is given) and every [`SETTINGS_RELOAD_INTERVAL`].
+    pub fn spawn_reloader(self: Arc<Self>, subscription: Option<Subscription>) {
+        tokio::spawn(async move {
+            let mut messages = subscription;
+            let mut interval = tokio::time::interval(SETTINGS_RELOAD_INTERVAL);
+            interval.tick().await;
+
+            loop {
+                let reason = match messages.as_mut() {
+                    Some(rx) => tokio::select! {
+                        message = rx.recv() => match message {
+                            Ok(_) => "invalidation",
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => "invalidation",
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                                tracing::warn!("Settings subscriber closed, falling back to periodic reload");
+                                messages = None;
+                                continue;
+                            }
+                        },
+                        _ = interval.tick() => "interval",
+                    },
+                    None => {
+                        interval.tick().await;
+                        "interval"
+                    }
+                };
+
+                if let Err(e) = self.reload().await {
+                    tracing::warn!(reason, "Failed to reload runtime settings: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn stored(key: &str, value: serde_json::Value) -> Setting {
+        Setting {
+            key: key.to_string(),
+            value,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_with_override_validates() {
+        let defaults = RuntimeSettings::default();
+
+        let updated = defaults
+            .with_override("max_message_length", json!(1000))
+            .unwrap();
+        assert_eq!(updated.max_message_length, 1000);
+
+        assert!(matches!(
+            defaults.with_override("nope", json!(1)),
+            Err(SettingsError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            defaults.with_override("max_message_length", json!("long")),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            defaults.with_override("archive_after_secs", json!(0)),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            defaults.with_override("notification_delay_ms", json!(3_600_000)),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            defaults.with_override("notification_digest_window_secs", json!(86_400)),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+
+        let updated = defaults
+            .with_override(
+                "archive_quiet_hours",
+                json!({ "start_hour": 20, "end_hour": 8 }),
+            )
+            .unwrap();
+        assert!(updated.archive_quiet_hours.is_some());
+        let cleared = updated
+            .with_override("archive_quiet_hours", json!(null))
+            .unwrap();
+        assert_eq!(cleared.archive_quiet_hours, None);
+        for invalid in [
+            json!({ "start_hour": 20, "end_hour": 24 }),
+            json!({ "start_hour": 8, "end_hour": 8 }),
+            json!("20-8"),
+        ] {
+            assert!(matches!(
+                defaults.with_override("archive_quiet_hours", invalid),
+                Err(SettingsError::InvalidValue { .. })
+            ));
+        }
+
+        let updated = defaults
+            .with_override("sanitize_profile", json!("rich"))
+            .unwrap();
+        assert_eq!(updated.sanitize_profile, SanitizeProfile::Rich);
+        assert!(matches!(
+            defaults.with_override("sanitize_profile", json!("full")),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_notification_delay_for_dialog_and_priority() {
+        let settings = RuntimeSettings::default()
+            .with_override(
+                "notification_delay_by_object_type",
+                json!({ "tender": 5000 }),
+            )
+            .unwrap()
+            .with_override("mention_notification_delay_ms", json!(500))
+            .unwrap();
+        let order = Dialog::new("order-1", "order", None, None, None, None);
+        let mut tender = Dialog::new("tender-1", "tender", None, None, None, None);
+        let normal = MessagePriority::Normal;
+
+        assert_eq!(settings.notification_delay_for(&order, normal, false), 1000);
+        assert_eq!(
+            settings.notification_delay_for(&tender, normal, false),
+            5000
+        );
+        assert_eq!(settings.notification_delay_for(&tender, normal, true), 500);
+        assert_eq!(
+            settings.notification_delay_for(&tender, MessagePriority::Urgent, false),
+            0
+        );
+
+        // The dialog's own delay wins, and is only shortened by the priority
+        tender.notification_delay_ms = Some(200);
+        assert_eq!(settings.notification_delay_for(&tender, normal, false), 200);
+        assert_eq!(settings.notification_delay_for(&tender, normal, true), 200);
+
+        assert!(matches!(
+            settings.with_override(
+                "notification_delay_by_object_type",
+                json!({ "order": 120_000 })
+            ),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            settings.with_override("urgent_notification_delay_ms", json!(120_000)),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_skips_invalid_rows() {
+        let defaults = RuntimeSettings::default();
+        let settings = RuntimeSettings::resolve(
+            &defaults,
+            &[
+                stored("notification_delay_ms", json!(250)),
+                stored("max_message_length", json!(-5)),
+                stored("removed_setting", json!(true)),
+                stored("feature_flags", json!({ "reactions": true })),
+            ],
+        );
+
+        assert_eq!(settings.notification_delay_ms, 250);
+        assert_eq!(settings.max_message_length, defaults.max_message_length);
+        assert!(settings.feature_enabled("reactions"));
+        assert!(!settings.feature_enabled("threads"));
+    }
+
+    #[test]
+    fn test_get_returns_every_key() {
+        let settings = RuntimeSettings::default();
+        for key in RuntimeSettings::KEYS {
+            assert!(settings.get(key).is_some(), "{}", key);
+        }
+        assert!(settings.get("unknown").is_none());
+    }
+}
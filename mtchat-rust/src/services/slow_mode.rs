@@ -0,0 +1,102 @@
+//! Per-dialog slow mode
+//!
+//! Dialogs with `slow_mode_secs` set let each participant send one message
+//! per interval. The time of a participant's last message is kept in Redis
+//! with the interval as TTL, so all instances enforce the same limit.
+
+use fred::clients::Pool;
+use fred::error::Error as RedisError;
+use fred::interfaces::KeysInterface;
+use fred::types::{Expiration, SetOptions};
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Longest slow mode interval (1 day)
+pub const MAX_SLOW_MODE_SECS: i32 = 86400;
+
+#[derive(Debug, Error)]
+pub enum SlowModeError {
+    #[error("Slow mode is on: wait {retry_after_secs}s before sending another message")]
+    TooSoon { retry_after_secs: i64 },
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] RedisError),
+}
+
+/// Service enforcing slow mode intervals via Redis
+pub struct SlowModeLimiter {
+    redis: Option<Arc<Pool>>,
+}
+
+impl SlowModeLimiter {
+    /// Create a new slow mode limiter with Redis connection
+    pub fn new(redis: Arc<Pool>) -> Self {
+        Self { redis: Some(redis) }
+    }
+
+    /// Create a no-op limiter (when Redis is not configured)
+    pub fn noop() -> Self {
+        Self { redis: None }
+    }
+
+    /// Record a message of `user_id` in a dialog with the given interval
+    ///
+    /// Fails without recording anything if the user's previous message was
+    /// sent less than `interval_secs` ago.
+    pub async fn check_and_record(
+        &self,
+        dialog_id: Uuid,
+        user_id: &str,
+        interval_secs: i32,
+    ) -> Result<(), SlowModeError> {
+        let Some(redis) = &self.redis else {
+            return Ok(());
+        };
+
+        let key = format!("slow_mode:{}:{}", dialog_id, user_id);
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let interval_ms = i64::from(interval_secs) * 1000;
+
+        let recorded: Option<String> = redis
+            .set(
+                &key,
+                now_ms,
+                Some(Expiration::PX(interval_ms)),
+                Some(SetOptions::NX),
+                false,
+            )
+            .await?;
+        if recorded.is_some() {
+            return Ok(());
+        }
+
+        // The key expired between SET and GET: nothing to wait for
+        let Some(last_ms) = redis.get::<Option<i64>, _>(&key).await? else {
+            return Ok(());
+        };
+        Err(SlowModeError::TooSoon {
+            retry_after_secs: retry_after_secs(last_ms, interval_ms, now_ms),
+        })
+    }
+}
+
+/// Whole seconds until the next message is allowed (at least 1)
+fn retry_after_secs(last_ms: i64, interval_ms: i64, now_ms: i64) -> i64 {
+    let remaining_ms = (last_ms + interval_ms - now_ms).max(0);
+    ((remaining_ms + 999) / 1000).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after_rounds_up() {
+        assert_eq!(retry_after_secs(0, 30_000, 0), 30);
+        assert_eq!(retry_after_secs(0, 30_000, 100), 30);
+        assert_eq!(retry_after_secs(0, 30_000, 29_001), 1);
+        // Never tells the client to retry immediately
+        assert_eq!(retry_after_secs(0, 30_000, 30_000), 1);
+    }
+}
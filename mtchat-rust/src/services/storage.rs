@@ -0,0 +1,110 @@
+//! Blob storage abstraction for attachments
+//!
+//! Attachments are stored through the [`BlobStorage`] trait so the backend can
+//! be swapped per deployment: S3/MinIO ([`super::S3Service`]) or the local
+//! filesystem ([`super::FsStorage`]) for small self-hosted setups.
+
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Storage operation failed: {0}")]
+    OperationFailed(String),
+
+    #[error("Presigning failed: {0}")]
+    PresigningFailed(String),
+
+    #[error("Object not found: {0}")]
+    NotFound(String),
+
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
+    /// The backend is timing out or failing; see [`super::GuardedStorage`]
+    #[error("Storage unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Object storage used for attachment files and thumbnails.
+///
+/// Methods return boxed futures so the trait stays object-safe and can be held
+/// as `Arc<dyn BlobStorage>` in the application state.
+pub trait BlobStorage: Send + Sync {
+    /// Check if storage is properly configured
+    fn is_configured(&self) -> bool;
+
+    /// Whether calls are expected to succeed (false while the circuit is open)
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// Lifetime of upload URLs in seconds
+    fn upload_expiry_secs(&self) -> u64;
+
+    /// Lifetime of download URLs in seconds (`None` when URLs don't expire)
+    fn download_expiry_secs(&self) -> Option<u64>;
+
+    /// Generate a URL the client can `PUT` the file to. The URL only accepts
+    /// a body of exactly `size` bytes.
+    fn generate_upload_url<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        size: i64,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+
+    /// Generate a URL the client can `GET` the file from
+    fn generate_download_url<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+
+    /// Check if an object exists
+    fn object_exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<bool, StorageError>>;
+
+    /// Get content type and size of an object
+    fn get_object_info<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(String, i64), StorageError>>;
+
+    /// Get raw object data
+    fn get_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>>;
+
+    /// Upload raw object data
+    fn put_object<'a>(
+        &'a self,
+        key: &'a str,
+        data: Vec<u8>,
+        content_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// Delete an object
+    fn delete_object<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// Check that the backend is reachable (used by the readiness probe)
+    fn health_check(&self) -> BoxFuture<'_, Result<(), StorageError>>;
+
+    /// Generate download URLs for multiple files concurrently
+    ///
+    /// Returns a map of key -> URL (keys that failed are missing).
+    fn generate_download_urls_batch<'a>(
+        &'a self,
+        keys: &'a [&'a str],
+    ) -> BoxFuture<'a, HashMap<String, String>> {
+        Box::pin(async move {
+            let futures = keys.iter().map(|key| async move {
+                let result = self.generate_download_url(key).await;
+                (key.to_string(), result)
+            });
+
+            futures::future::join_all(futures)
+                .await
+                .into_iter()
+                .filter_map(|(key, result)| result.ok().map(|url| (key, url)))
+                .collect()
+        })
+    }
+}
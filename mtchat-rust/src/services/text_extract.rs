@@ -0,0 +1,379 @@
+//! Document text extraction
+//!
+//! Pulls the plain text out of PDF and DOCX attachments so they can be found
+//! by search and passed to the host for indexing. Only compiled with the
+//! `text-extract` feature. PDFs are read with pdfium (loaded at runtime like
+//! for previews, see [`super::preview`]); DOCX files are unzipped and their
+//! `word/document.xml` is read directly.
+
+use thiserror::Error;
+
+/// Maximum characters of text kept per attachment
+pub const MAX_EXTRACTED_TEXT_CHARS: usize = 100_000;
+
+/// Content type of Word documents (DOCX)
+pub const DOCX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// Largest `word/document.xml` that is inflated (guards against zip bombs)
+#[cfg(feature = "text-extract")]
+const MAX_DOCUMENT_XML_BYTES: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum TextExtractError {
+    #[error("Text extraction is not enabled in this build")]
+    Unsupported,
+
+    #[error("Failed to extract text: {0}")]
+    ExtractFailed(String),
+}
+
+/// Check if this build can extract document text
+pub fn is_enabled() -> bool {
+    cfg!(feature = "text-extract")
+}
+
+/// Whether text can be extracted from attachments of this content type
+pub fn is_extractable(content_type: &str) -> bool {
+    content_type == "application/pdf" || content_type == DOCX_CONTENT_TYPE
+}
+
+/// Extract the text of a PDF or DOCX document, at most
+/// [`MAX_EXTRACTED_TEXT_CHARS`] characters with blank lines removed. PDFs
+/// are read with pdfium loaded from `pdfium_lib_path` or the system library
+/// path.
+///
+/// This is CPU-bound; call it from `spawn_blocking`.
+#[cfg(feature = "text-extract")]
+pub fn extract_text(
+    content_type: &str,
+    data: &[u8],
+    pdfium_lib_path: Option<&str>,
+) -> Result<String, TextExtractError> {
+    let text = match content_type {
+        "application/pdf" => pdf_text(data, pdfium_lib_path)?,
+        DOCX_CONTENT_TYPE => docx_text(data)?,
+        _ => return Err(TextExtractError::Unsupported),
+    };
+    Ok(normalize(&text))
+}
+
+/// Extract the text of a PDF or DOCX document
+///
+/// Always fails: the crate was built without the `text-extract` feature.
+#[cfg(not(feature = "text-extract"))]
+pub fn extract_text(
+    _content_type: &str,
+    _data: &[u8],
+    _pdfium_lib_path: Option<&str>,
+) -> Result<String, TextExtractError> {
+    Err(TextExtractError::Unsupported)
+}
+
+#[cfg(feature = "text-extract")]
+fn pdf_text(data: &[u8], pdfium_lib_path: Option<&str>) -> Result<String, TextExtractError> {
+    let extract_err =
+        |e: pdfium_render::prelude::PdfiumError| TextExtractError::ExtractFailed(e.to_string());
+
+    let pdfium = super::preview::bind_pdfium(pdfium_lib_path).map_err(extract_err)?;
+    let document = pdfium
+        .load_pdf_from_byte_slice(data, None)
+        .map_err(extract_err)?;
+
+    let mut text = String::new();
+    for page in document.pages().iter() {
+        text.push_str(&page.text().map_err(extract_err)?.all());
+        text.push('\n');
+        if text.len() > MAX_EXTRACTED_TEXT_CHARS * 4 {
+            break;
+        }
+    }
+    Ok(text)
+}
+
+/// Text of the paragraphs of a DOCX document, one paragraph per line
+#[cfg(feature = "text-extract")]
+fn docx_text(data: &[u8]) -> Result<String, TextExtractError> {
+    use xmlparser::{ElementEnd, Token, Tokenizer};
+
+    let xml = zip_entry(data, "word/document.xml")?;
+    let xml = String::from_utf8(xml)
+        .map_err(|_| TextExtractError::ExtractFailed("document.xml is not UTF-8".into()))?;
+
+    let mut text = String::new();
+    let mut in_text = false;
+    for token in Tokenizer::from(xml.as_str()) {
+        let token = token.map_err(|e| TextExtractError::ExtractFailed(e.to_string()))?;
+        match token {
+            Token::ElementStart { prefix, local, .. } if prefix.as_str() == "w" => {
+                match local.as_str() {
+                    "t" => in_text = true,
+                    "tab" => text.push('\t'),
+                    "br" | "cr" => text.push('\n'),
+                    _ => {}
+                }
+            }
+            Token::ElementEnd { end, .. } => match end {
+                ElementEnd::Close(prefix, local) if prefix.as_str() == "w" => {
+                    match local.as_str() {
+                        "t" => in_text = false,
+                        "p" => text.push('\n'),
+                        _ => {}
+                    }
+                }
+                ElementEnd::Empty => in_text = false,
+                _ => {}
+            },
+            Token::Text { text: span } | Token::Cdata { text: span, .. } if in_text => {
+                text.push_str(&unescape_xml(span.as_str()));
+            }
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+/// Read an entry of a ZIP archive (stored or deflated)
+#[cfg(feature = "text-extract")]
+fn zip_entry(data: &[u8], name: &str) -> Result<Vec<u8>, TextExtractError> {
+    use std::io::Read;
+
+    const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+    const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+    const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+
+    let corrupt = || TextExtractError::ExtractFailed("not a valid DOCX (ZIP) file".into());
+    let u16_at = |offset: usize| -> Option<usize> {
+        data.get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    // The end record is within the last 22 + 65535 (comment) bytes
+    let search_from = data.len().saturating_sub(22 + 0xffff);
+    let end = (search_from..data.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(i) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(corrupt)?;
+    let entries = u16_at(end + 10).ok_or_else(corrupt)?;
+    let mut offset = u32_at(end + 16).ok_or_else(corrupt)? as usize;
+
+    for _ in 0..entries {
+        if u32_at(offset) != Some(CENTRAL_DIRECTORY_HEADER) {
+            return Err(corrupt());
+        }
+        let method = u16_at(offset + 10).ok_or_else(corrupt)?;
+        let compressed_size = u32_at(offset + 20).ok_or_else(corrupt)? as usize;
+        let name_len = u16_at(offset + 28).ok_or_else(corrupt)?;
+        let extra_len = u16_at(offset + 30).ok_or_else(corrupt)?;
+        let comment_len = u16_at(offset + 32).ok_or_else(corrupt)?;
+        let local_offset = u32_at(offset + 42).ok_or_else(corrupt)? as usize;
+        let entry_name = data
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(corrupt)?;
+
+        if entry_name == name.as_bytes() {
+            if u32_at(local_offset) != Some(LOCAL_FILE_HEADER) {
+                return Err(corrupt());
+            }
+            let start = local_offset
+                + 30
+                + u16_at(local_offset + 26).ok_or_else(corrupt)?
+                + u16_at(local_offset + 28).ok_or_else(corrupt)?;
+            let compressed = data
+                .get(start..start + compressed_size)
+                .ok_or_else(corrupt)?;
+
+            let mut out = Vec::new();
+            let read = match method {
+                0 => compressed
+                    .take(MAX_DOCUMENT_XML_BYTES)
+                    .read_to_end(&mut out),
+                8 => flate2::read::DeflateDecoder::new(compressed)
+                    .take(MAX_DOCUMENT_XML_BYTES)
+                    .read_to_end(&mut out),
+                _ => {
+                    return Err(TextExtractError::ExtractFailed(format!(
+                        "unsupported ZIP compression method {}",
+                        method
+                    )))
+                }
+            };
+            read.map_err(|e| TextExtractError::ExtractFailed(e.to_string()))?;
+            return Ok(out);
+        }
+
+        offset += 46 + name_len + extra_len + comment_len;
+    }
+
+    Err(TextExtractError::ExtractFailed(format!(
+        "{} not found in document",
+        name
+    )))
+}
+
+/// Replace the predefined XML entities and character references
+#[cfg(feature = "text-extract")]
+fn unescape_xml(text: &str) -> std::borrow::Cow<'_, str> {
+    if !text.contains('&') {
+        return text.into();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.into()
+}
+
+/// Trim lines, drop blank ones and control characters (Postgres rejects NUL),
+/// and cap the length
+#[cfg(feature = "text-extract")]
+fn normalize(text: &str) -> String {
+    let lines = text
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| !c.is_control() || *c == '\t')
+                .collect::<String>()
+        })
+        .filter(|line| !line.trim().is_empty());
+
+    let mut out = String::new();
+    for line in lines {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(line.trim());
+    }
+    match out.char_indices().nth(MAX_EXTRACTED_TEXT_CHARS) {
+        Some((cut, _)) => out[..cut].to_string(),
+        None => out,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_extractable() {
+        assert!(is_extractable("application/pdf"));
+        assert!(is_extractable(DOCX_CONTENT_TYPE));
+        assert!(!is_extractable("image/png"));
+        assert!(!is_extractable("application/msword"));
+    }
+
+    #[cfg(feature = "text-extract")]
+    fn zip(name: &str, content: &[u8], deflate: bool) -> Vec<u8> {
+        use std::io::Write;
+
+        let (method, stored): (u16, Vec<u8>) = if deflate {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content).unwrap();
+            (8, encoder.finish().unwrap())
+        } else {
+            (0, content.to_vec())
+        };
+        let header = |out: &mut Vec<u8>| {
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 8]); // time, date, crc (not checked)
+            out.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+        };
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[20, 0, 0, 0]); // version, flags
+        header(&mut out);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&stored);
+
+        let central = out.len();
+        out.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[20, 0, 20, 0, 0, 0]); // versions, flags
+        header(&mut out);
+        out.extend_from_slice(&[0; 10]); // comment, disk, attributes
+        out.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+        out.extend_from_slice(name.as_bytes());
+        let central_size = out.len() - central;
+
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        out.extend_from_slice(&(central_size as u32).to_le_bytes());
+        out.extend_from_slice(&(central as u32).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[cfg(feature = "text-extract")]
+    #[test]
+    fn test_docx_text() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:r><w:t>Invoice</w:t></w:r><w:r><w:t xml:space="preserve"> #42 &amp; terms</w:t></w:r></w:p>
+    <w:p/>
+    <w:p><w:r><w:t>Total:</w:t><w:tab/><w:t>&#8364;100</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+
+        for deflate in [false, true] {
+            let docx = zip("word/document.xml", xml.as_bytes(), deflate);
+            let text = extract_text(DOCX_CONTENT_TYPE, &docx, None).unwrap();
+            assert_eq!(text, "Invoice #42 & terms\nTotal:\t€100");
+        }
+
+        let other = zip("word/styles.xml", xml.as_bytes(), false);
+        assert!(extract_text(DOCX_CONTENT_TYPE, &other, None).is_err());
+        assert!(extract_text(DOCX_CONTENT_TYPE, b"not a zip", None).is_err());
+    }
+
+    #[cfg(feature = "text-extract")]
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("  a \n\n\u{0}b\t\n   \n"), "a\nb");
+        let long = "x".repeat(MAX_EXTRACTED_TEXT_CHARS + 10);
+        assert_eq!(normalize(&long).len(), MAX_EXTRACTED_TEXT_CHARS);
+        assert_eq!(
+            unescape_xml("&lt;a&gt; &amp;x; &#x41;&#66; &bogus;"),
+            "<a> &x; AB &bogus;"
+        );
+    }
+}
@@ -0,0 +1,498 @@
+//! Read-only dialog transcripts
+//!
+//! The Management API issues expiring links to a transcript of a dialog so it
+//! can be shared with people who are not chat users (auditors reviewing
+//! tender clarifications). Like the filesystem storage URLs, links carry an
+//! expiry and an HMAC-SHA256 signature instead of an `Authorization` header.
+//!
+//! Transcripts never link to attachments: files are listed by name and size
+//! only, and every page carries a watermark naming the link recipient.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::serde_helpers::secs;
+use crate::domain::{Attachment, Dialog, Message, MessageType};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Route prefix the transcript links point to
+pub const TRANSCRIPTS_ROUTE_PREFIX: &str = "/api/v1/transcripts";
+
+/// Maximum number of messages rendered in one transcript
+pub const MAX_TRANSCRIPT_MESSAGES: i64 = 5000;
+
+/// Maximum length of the recipient label shown in the watermark
+pub const MAX_TRANSCRIPT_RECIPIENT_LENGTH: usize = 200;
+
+/// Transcript link configuration (`[transcripts]` section)
+///
+/// Environment variables:
+/// - `TRANSCRIPT_SECRET` (default: random per process; set it when running
+///   several replicas or to keep links valid across restarts)
+/// - `TRANSCRIPT_PUBLIC_URL` (default: empty, links are relative to the API host)
+/// - `TRANSCRIPT_MAX_EXPIRY_SECS` (default: 2592000 seconds, 30 days)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptConfig {
+    /// Secret for signing transcript links (empty = random per process)
+    pub secret: String,
+    /// Public base URL of the MTChat API as seen by browsers (empty = relative URLs)
+    pub public_url: String,
+    /// Longest lifetime a link can be issued for (default: 30 days)
+    #[serde(rename = "max_expiry_secs", with = "secs")]
+    pub max_expiry: Duration,
+}
+
+impl Default for TranscriptConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            public_url: String::new(),
+            max_expiry: Duration::from_secs(30 * 24 * 3600),
+        }
+    }
+}
+
+/// Transcript representation served by a link
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    #[default]
+    Html,
+    Json,
+}
+
+/// Signs and verifies transcript links
+pub struct TranscriptSigner {
+    secret: String,
+    public_url: String,
+    base_path: String,
+    max_expiry: Duration,
+}
+
+impl TranscriptSigner {
+    pub fn new(config: TranscriptConfig) -> Self {
+        let secret = if config.secret.is_empty() {
+            tracing::warn!(
+                "TRANSCRIPT_SECRET not set — using a random secret, transcript links won't survive restarts"
+            );
+            format!("{}{}", Uuid::new_v4(), Uuid::new_v4())
+        } else {
+            config.secret
+        };
+
+        Self {
+            secret,
+            public_url: config.public_url.trim_end_matches('/').to_string(),
+            base_path: String::new(),
+            max_expiry: config.max_expiry,
+        }
+    }
+
+    /// Prefix of the service's routes (see `server.base_path`) for links
+    pub fn with_base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Longest lifetime a link can be issued for
+    pub fn max_expiry(&self) -> Duration {
+        self.max_expiry
+    }
+
+    /// Build a signed link to the transcript of a dialog
+    pub fn signed_url(
+        &self,
+        dialog_id: Uuid,
+        recipient: &str,
+        expires_at: DateTime<Utc>,
+    ) -> String {
+        let expires = expires_at.timestamp();
+        let signature = self.sign(dialog_id, recipient, expires);
+        let mut url = format!(
+            "{}{}{}/{}?expires={}&signature={}",
+            self.public_url,
+            self.base_path,
+            TRANSCRIPTS_ROUTE_PREFIX,
+            dialog_id,
+            expires,
+            signature
+        );
+        if !recipient.is_empty() {
+            url.push_str("&recipient=");
+            url.push_str(&urlencoding::encode(recipient));
+        }
+        url
+    }
+
+    /// Verify a signed transcript link
+    pub fn verify(&self, dialog_id: Uuid, recipient: &str, expires: i64, signature: &str) -> bool {
+        if expires < Utc::now().timestamp() {
+            return false;
+        }
+        let expected = self.sign(dialog_id, recipient, expires);
+        constant_time_eq(expected.as_bytes(), signature.as_bytes())
+    }
+
+    fn sign(&self, dialog_id: Uuid, recipient: &str, expires: i64) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(format!("transcript\n{}\n{}\n{}", dialog_id, expires, recipient).as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+/// Constant-time comparison
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+/// Attachment as listed in a transcript (no download URL)
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+}
+
+impl From<&Attachment> for TranscriptAttachment {
+    fn from(a: &Attachment) -> Self {
+        Self {
+            filename: a.filename.clone(),
+            content_type: a.content_type.clone(),
+            size: a.size,
+        }
+    }
+}
+
+/// Message as rendered in a transcript
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptMessage {
+    pub id: Uuid,
+    pub sent_at: DateTime<Utc>,
+    pub message_type: MessageType,
+    pub sender_id: Option<String>,
+    pub sender_name: Option<String>,
+    /// Sanitized HTML for user messages, plain text for system messages
+    pub content: String,
+    pub edited: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<TranscriptAttachment>,
+}
+
+/// Read-only transcript of a dialog
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub dialog_id: Uuid,
+    pub object_type: String,
+    pub object_id: String,
+    pub title: Option<String>,
+    pub recipient: Option<String>,
+    pub generated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+impl Transcript {
+    /// Assemble a transcript from messages in chronological order
+    ///
+    /// `names` maps sender ids to display names; `attachments` are matched
+    /// to their messages by `message_id`.
+    pub fn build(
+        dialog: &Dialog,
+        messages: Vec<Message>,
+        attachments: &[Attachment],
+        names: &HashMap<String, String>,
+        recipient: Option<String>,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        let mut by_message: HashMap<Uuid, Vec<TranscriptAttachment>> = HashMap::new();
+        for a in attachments {
+            by_message.entry(a.message_id).or_default().push(a.into());
+        }
+
+        let messages = messages
+            .into_iter()
+            .map(|m| TranscriptMessage {
+                id: m.id,
+                sent_at: m.sent_at,
+                message_type: m.message_type,
+                sender_name: m.sender_id.as_ref().and_then(|s| names.get(s).cloned()),
+                sender_id: m.sender_id,
+                content: match m.message_type {
+                    MessageType::User => m.content,
+                    MessageType::System => system_text(&m.content),
+                },
+                edited: m.last_edited_at.is_some(),
+                attachments: by_message.remove(&m.id).unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            dialog_id: dialog.id,
+            object_type: dialog.object_type.clone(),
+            object_id: dialog.object_id.clone(),
+            title: dialog.title.clone(),
+            recipient,
+            generated_at: Utc::now(),
+            expires_at,
+            messages,
+        }
+    }
+
+    /// Render as a standalone read-only HTML page
+    pub fn render_html(&self) -> String {
+        let title = self
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.object_type, self.object_id));
+        let watermark = match &self.recipient {
+            Some(r) => format!("Read-only copy issued to {}", r),
+            None => "Read-only copy".to_string(),
+        };
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+             <meta name=\"robots\" content=\"noindex\">\
+             <title>{title}</title><style>{STYLE}</style></head><body>\
+             <div class=\"watermark\">{wm}</div>\
+             <header><h1>{title}</h1><p>{wm} · generated {generated} · valid until {expires}</p></header>\
+             <main>",
+            title = escape_html(&title),
+            wm = escape_html(&watermark),
+            generated = self.generated_at.format("%Y-%m-%d %H:%M UTC"),
+            expires = self.expires_at.format("%Y-%m-%d %H:%M UTC"),
+        );
+
+        for m in &self.messages {
+            let time = m.sent_at.format("%Y-%m-%d %H:%M");
+            match m.message_type {
+                MessageType::System => {
+                    let _ = write!(
+                        html,
+                        "<div class=\"system\"><time>{}</time> {}</div>",
+                        time,
+                        escape_html(&m.content)
+                    );
+                }
+                MessageType::User => {
+                    let sender = m
+                        .sender_name
+                        .as_deref()
+                        .or(m.sender_id.as_deref())
+                        .unwrap_or_default();
+                    let _ = write!(
+                        html,
+                        "<article><div class=\"meta\"><b>{}</b> <time>{}</time>{}</div>\
+                         <div class=\"content\">{}</div>",
+                        escape_html(sender),
+                        time,
+                        if m.edited { " (edited)" } else { "" },
+                        m.content
+                    );
+                    if !m.attachments.is_empty() {
+                        html.push_str("<ul class=\"files\">");
+                        for a in &m.attachments {
+                            let _ = write!(
+                                html,
+                                "<li>{} ({})</li>",
+                                escape_html(&a.filename),
+                                format_size(a.size)
+                            );
+                        }
+                        html.push_str("</ul>");
+                    }
+                    html.push_str("</article>");
+                }
+            }
+        }
+
+        html.push_str("</main></body></html>");
+        html
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:48rem;margin:2rem auto;color:#222}\
+header p{color:#666;font-size:.85rem}\
+article{border-bottom:1px solid #eee;padding:.5rem 0}\
+.meta{font-size:.85rem;color:#555}\
+.system{text-align:center;color:#888;font-size:.85rem;padding:.5rem 0}\
+.files{font-size:.85rem;color:#555}\
+.watermark{position:fixed;top:40%;left:0;right:0;text-align:center;font-size:2.5rem;\
+color:rgba(0,0,0,.06);transform:rotate(-20deg);pointer-events:none;user-select:none}";
+
+/// Plain-text rendering of a structured system message
+fn system_text(content: &str) -> String {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return content.to_string();
+    };
+    let str_field = |key: &str| value.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+
+    match str_field("event") {
+        "chat_created" => "Chat created".to_string(),
+        "participant_joined" => format!("{} joined the chat", str_field("name")),
+        "participant_left" => format!("{} left the chat", str_field("name")),
+        "object_event" => match str_field("text") {
+            "" => str_field("type").to_string(),
+            text => text.to_string(),
+        },
+        _ => content.to_string(),
+    }
+}
+
+fn format_size(bytes: i64) -> String {
+    const KB: i64 = 1024;
+    const MB: i64 = 1024 * KB;
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_signer() -> TranscriptSigner {
+        TranscriptSigner::new(TranscriptConfig {
+            secret: "test-secret".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn query_param<'a>(url: &'a str, name: &str) -> &'a str {
+        url.split(['?', '&'])
+            .find_map(|p| p.strip_prefix(&format!("{}=", name)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_signed_url_roundtrip() {
+        let signer = test_signer();
+        let dialog_id = Uuid::new_v4();
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+        let url = signer.signed_url(dialog_id, "Audit Co", expires_at);
+
+        assert!(url.starts_with(&format!("{}/{}?", TRANSCRIPTS_ROUTE_PREFIX, dialog_id)));
+        assert_eq!(query_param(&url, "recipient"), "Audit%20Co");
+        let signature = query_param(&url, "signature");
+        let expires = expires_at.timestamp();
+        assert!(signer.verify(dialog_id, "Audit Co", expires, signature));
+        assert!(!signer.verify(dialog_id, "Someone else", expires, signature));
+        assert!(!signer.verify(Uuid::new_v4(), "Audit Co", expires, signature));
+        assert!(!signer.verify(dialog_id, "Audit Co", expires + 1, signature));
+    }
+
+    #[test]
+    fn test_signed_url_under_base_path() {
+        let signer = TranscriptSigner::new(TranscriptConfig {
+            secret: "test-secret".to_string(),
+            public_url: "https://app.example.com/".to_string(),
+            ..Default::default()
+        })
+        .with_base_path("/chat");
+        let dialog_id = Uuid::new_v4();
+        let url = signer.signed_url(dialog_id, "", Utc::now());
+        assert!(url.starts_with(&format!(
+            "https://app.example.com/chat{}/{}?",
+            TRANSCRIPTS_ROUTE_PREFIX, dialog_id
+        )));
+    }
+
+    #[test]
+    fn test_expired_link_rejected() {
+        let signer = test_signer();
+        let dialog_id = Uuid::new_v4();
+        let expires = Utc::now().timestamp() - 10;
+        let signature = signer.sign(dialog_id, "", expires);
+        assert!(!signer.verify(dialog_id, "", expires, &signature));
+    }
+
+    #[test]
+    fn test_system_text() {
+        assert_eq!(
+            system_text(r#"{"event":"participant_joined","name":"Ann"}"#),
+            "Ann joined the chat"
+        );
+        assert_eq!(
+            system_text(r#"{"event":"object_event","type":"status_changed","payload":{}}"#),
+            "status_changed"
+        );
+        assert_eq!(
+            system_text(r#"{"event":"object_event","type":"x","text":"Closed"}"#),
+            "Closed"
+        );
+        assert_eq!(system_text("plain"), "plain");
+    }
+
+    #[test]
+    fn test_render_html_escapes_metadata_and_hides_files() {
+        let dialog = Dialog::new(
+            "T-1",
+            "tender",
+            Some("<b>Tender</b>".to_string()),
+            None,
+            None,
+            None,
+        );
+        let message = Message::new(dialog.id, "u1", "<p>Hello</p>");
+        let attachment = Attachment::new(
+            message.id,
+            "spec<1>.pdf",
+            "application/pdf",
+            2048,
+            "attachments/secret-key.pdf",
+        );
+        let names = HashMap::from([("u1".to_string(), "Ann & Co".to_string())]);
+
+        let transcript = Transcript::build(
+            &dialog,
+            vec![message],
+            &[attachment],
+            &names,
+            Some("Auditor".to_string()),
+            Utc::now(),
+        );
+        let html = transcript.render_html();
+
+        assert!(html.contains("&lt;b&gt;Tender&lt;/b&gt;"));
+        assert!(html.contains("<p>Hello</p>"));
+        assert!(html.contains("Ann &amp; Co"));
+        assert!(html.contains("spec&lt;1&gt;.pdf (2.0 KB)"));
+        assert!(html.contains("Read-only copy issued to Auditor"));
+        assert!(!html.contains("secret-key"));
+    }
+}
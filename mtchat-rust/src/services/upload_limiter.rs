@@ -0,0 +1,190 @@
+//! Per-user limits on presigned upload issuance
+//!
+//! Counts presigned uploads and their declared bytes per user in hourly Redis
+//! windows, so a single user can't mint unlimited upload URLs. Upload URLs
+//! only accept the declared size, so the counted bytes are the uploaded ones.
+
+use fred::clients::Pool;
+use fred::error::Error as RedisError;
+use fred::interfaces::LuaInterface;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Length of a limit window in seconds
+const WINDOW_SECS: i64 = 3600;
+
+/// Check and record an upload in one step, so concurrent requests can't
+/// all pass the check and a window key can't be left without a TTL.
+///
+/// KEYS: count key, bytes key. ARGV: size, max uploads, max bytes (0 =
+/// unlimited), window length. Returns `{recorded, count, bytes}` with the
+/// totals including this upload; nothing is recorded over a limit.
+const CHECK_AND_RECORD_SCRIPT: &str = r#"
+local count = tonumber(redis.call('GET', KEYS[1]) or '0') + 1
+local bytes = tonumber(redis.call('GET', KEYS[2]) or '0') + tonumber(ARGV[1])
+local max_count = tonumber(ARGV[2])
+local max_bytes = tonumber(ARGV[3])
+if (max_count > 0 and count > max_count) or (max_bytes > 0 and bytes > max_bytes) then
+    return {0, count, bytes}
+end
+redis.call('INCR', KEYS[1])
+redis.call('INCRBY', KEYS[2], ARGV[1])
+for _, key in ipairs(KEYS) do
+    if redis.call('TTL', key) < 0 then
+        redis.call('EXPIRE', key, ARGV[4])
+    end
+end
+return {1, count, bytes}
+"#;
+
+/// Upload limit configuration (`[upload_limits]` section)
+///
+/// Environment variables:
+/// - `UPLOAD_LIMIT_COUNT_PER_HOUR` - Presigned uploads per user per hour (default: 200)
+/// - `UPLOAD_LIMIT_BYTES_PER_HOUR` - Upload bytes per user per hour (default: 2 GB)
+/// - `UPLOAD_LIMIT_FAIL_OPEN` - Allow uploads while Redis is unreachable (default: false)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadLimitConfig {
+    /// Maximum presigned uploads per user per hour (0 = unlimited)
+    pub max_uploads_per_hour: i64,
+    /// Maximum declared upload bytes per user per hour (0 = unlimited)
+    pub max_bytes_per_hour: i64,
+    /// Issue upload URLs without checking the limits when Redis fails,
+    /// instead of rejecting them with 503
+    pub fail_open: bool,
+}
+
+impl Default for UploadLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_uploads_per_hour: 200,
+            max_bytes_per_hour: 2 * 1024 * 1024 * 1024, // 2 GB
+            fail_open: false,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum UploadLimitError {
+    #[error("Upload limit reached: at most {0} uploads per hour")]
+    TooManyUploads(i64),
+
+    #[error("Upload limit reached: at most {0} bytes per hour")]
+    TooManyBytes(i64),
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] RedisError),
+}
+
+/// Service enforcing per-user upload limits via Redis
+pub struct UploadLimiter {
+    redis: Option<Arc<Pool>>,
+    config: UploadLimitConfig,
+}
+
+impl UploadLimiter {
+    /// Create a new upload limiter with Redis connection
+    pub fn new(redis: Arc<Pool>, config: UploadLimitConfig) -> Self {
+        Self {
+            redis: Some(redis),
+            config,
+        }
+    }
+
+    /// Create a no-op limiter (when Redis is not configured)
+    pub fn noop() -> Self {
+        Self {
+            redis: None,
+            config: UploadLimitConfig::default(),
+        }
+    }
+
+    /// Whether uploads are allowed while Redis fails (`fail_open`)
+    pub fn fails_open(&self) -> bool {
+        self.config.fail_open
+    }
+
+    /// Record a presigned upload of `size` bytes for a user
+    ///
+    /// Fails without recording anything if the upload would exceed the
+    /// user's hourly count or byte limit.
+    pub async fn check_and_record(&self, user_id: &str, size: i64) -> Result<(), UploadLimitError> {
+        let Some(redis) = &self.redis else {
+            return Ok(());
+        };
+
+        let window = chrono::Utc::now().timestamp() / WINDOW_SECS;
+        let count_key = format!("upload_limit:count:{}:{}", user_id, window);
+        let bytes_key = format!("upload_limit:bytes:{}:{}", user_id, window);
+
+        let (recorded, count, bytes): (i64, i64, i64) = redis
+            .eval(
+                CHECK_AND_RECORD_SCRIPT,
+                vec![count_key, bytes_key],
+                vec![
+                    size,
+                    self.config.max_uploads_per_hour,
+                    self.config.max_bytes_per_hour,
+                    WINDOW_SECS,
+                ],
+            )
+            .await?;
+        if recorded == 1 {
+            return Ok(());
+        }
+
+        Err(
+            exceeded_limit(&self.config, count, bytes).unwrap_or(UploadLimitError::TooManyUploads(
+                self.config.max_uploads_per_hour,
+            )),
+        )
+    }
+}
+
+/// Check window totals against the configured limits
+fn exceeded_limit(config: &UploadLimitConfig, count: i64, bytes: i64) -> Option<UploadLimitError> {
+    if config.max_uploads_per_hour > 0 && count > config.max_uploads_per_hour {
+        return Some(UploadLimitError::TooManyUploads(
+            config.max_uploads_per_hour,
+        ));
+    }
+    if config.max_bytes_per_hour > 0 && bytes > config.max_bytes_per_hour {
+        return Some(UploadLimitError::TooManyBytes(config.max_bytes_per_hour));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exceeded_limit() {
+        let config = UploadLimitConfig {
+            max_uploads_per_hour: 2,
+            max_bytes_per_hour: 100,
+            fail_open: false,
+        };
+        assert!(exceeded_limit(&config, 2, 100).is_none());
+        assert!(matches!(
+            exceeded_limit(&config, 3, 10),
+            Some(UploadLimitError::TooManyUploads(2))
+        ));
+        assert!(matches!(
+            exceeded_limit(&config, 1, 101),
+            Some(UploadLimitError::TooManyBytes(100))
+        ));
+    }
+
+    #[test]
+    fn test_zero_means_unlimited() {
+        let config = UploadLimitConfig {
+            max_uploads_per_hour: 0,
+            max_bytes_per_hour: 0,
+            fail_open: false,
+        };
+        assert!(exceeded_limit(&config, 1_000_000, i64::MAX).is_none());
+    }
+}
@@ -0,0 +1,340 @@
+//! Helpers for integration tests against a running server (`test_utils`
+//! feature).
+//!
+//! [`WsTestClient`] connects to `/api/v1/ws` like the JS SDK does and waits
+//! for events by type:
+//!
+//! ```ignore
+//! let mut alice = WsTestClient::connect("http://localhost:8080", "alice").await?;
+//! // ... send a message over HTTP ...
+//! let event = alice.expect_event("message.new").await;
+//! assert_eq!(event.str("content"), Some("Hello"));
+//! ```
+//!
+//! `expect_*` methods panic like `assert!` so tests read top to bottom.
+
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+/// How long `expect_event` waits by default
+pub const DEFAULT_EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long `expect_no_event` listens by default
+pub const DEFAULT_SILENCE_WINDOW: Duration = Duration::from_millis(500);
+
+#[derive(Debug, thiserror::Error)]
+pub enum WsTestError {
+    #[error("WebSocket connect failed: {0}")]
+    Connect(String),
+    #[error("WebSocket send failed: {0}")]
+    Send(String),
+    #[error("Unexpected handshake event: {0}")]
+    Handshake(String),
+}
+
+/// A server event received over the WebSocket
+#[derive(Debug, Clone)]
+pub struct WsTestEvent {
+    /// The `type` field, e.g. `message.new`
+    pub event_type: String,
+    /// The whole event JSON
+    pub data: Value,
+}
+
+impl WsTestEvent {
+    fn parse(text: &str) -> Option<Self> {
+        let data: Value = serde_json::from_str(text).ok()?;
+        let event_type = data.get("type")?.as_str()?.to_string();
+        Some(Self { event_type, data })
+    }
+
+    /// String field of the event
+    pub fn str(&self, field: &str) -> Option<&str> {
+        self.data.get(field).and_then(Value::as_str)
+    }
+
+    /// `dialog_id` of dialog and message events
+    pub fn dialog_id(&self) -> Option<Uuid> {
+        self.str("dialog_id").and_then(|id| id.parse().ok())
+    }
+}
+
+/// WebSocket client for integration tests
+pub struct WsTestClient {
+    user_id: String,
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    timeout: Duration,
+}
+
+impl WsTestClient {
+    /// Connect as `user_id` (servers without JWT auth) and wait for `connected`.
+    ///
+    /// `base_url` is the HTTP base URL of the API, including the base path if
+    /// the service is mounted under one.
+    pub async fn connect(base_url: &str, user_id: &str) -> Result<Self, WsTestError> {
+        let query = format!("user_id={}", urlencoding::encode(user_id));
+        Self::connect_with_query(base_url, &query).await
+    }
+
+    /// Connect with a JWT (servers with `JWT_SECRET`) and wait for `connected`
+    pub async fn connect_with_token(base_url: &str, token: &str) -> Result<Self, WsTestError> {
+        let query = format!("token={}", urlencoding::encode(token));
+        Self::connect_with_query(base_url, &query).await
+    }
+
+    async fn connect_with_query(base_url: &str, query: &str) -> Result<Self, WsTestError> {
+        let url = format!("{}/api/v1/ws?{}", ws_base_url(base_url), query);
+        let (stream, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| WsTestError::Connect(e.to_string()))?;
+
+        let mut client = Self {
+            user_id: String::new(),
+            stream,
+            timeout: DEFAULT_EVENT_TIMEOUT,
+        };
+        // The server greets every connection before anything else
+        match client.next_event(DEFAULT_EVENT_TIMEOUT).await {
+            Some(event) if event.event_type == "connected" => {
+                client.user_id = event.str("employee_id").unwrap_or_default().to_string();
+                Ok(client)
+            }
+            Some(event) => Err(WsTestError::Handshake(event.data.to_string())),
+            None => Err(WsTestError::Handshake("no connected event".into())),
+        }
+    }
+
+    /// Change how long `expect_event` waits
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// User the server registered the connection for
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Send a client event, e.g. `{"type": "ping"}`
+    pub async fn send(&mut self, event: &Value) -> Result<(), WsTestError> {
+        self.stream
+            .send(Message::text(event.to_string()))
+            .await
+            .map_err(|e| WsTestError::Send(e.to_string()))
+    }
+
+    /// Send `ping` and wait for `pong`
+    pub async fn ping(&mut self) {
+        self.send(&json!({ "type": "ping" }))
+            .await
+            .expect("Failed to send ping");
+        self.expect_event("pong").await;
+    }
+
+    /// Next event within `timeout`; `None` on timeout or when the server closed
+    /// the connection. Non-JSON and control frames are skipped.
+    pub async fn next_event(&mut self, timeout: Duration) -> Option<WsTestEvent> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let frame = timeout_at(deadline, self.stream.next()).await.ok()??;
+            match frame.ok()? {
+                Message::Text(text) => {
+                    if let Some(event) = WsTestEvent::parse(&text) {
+                        return Some(event);
+                    }
+                }
+                Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Wait for an event of `event_type`, skipping other events.
+    ///
+    /// # Panics
+    ///
+    /// If no such event arrives within the client timeout.
+    pub async fn expect_event(&mut self, event_type: &str) -> WsTestEvent {
+        self.expect_event_where(event_type, |_| true).await
+    }
+
+    /// Wait for an event of `event_type` that matches `predicate`, skipping
+    /// other events.
+    ///
+    /// # Panics
+    ///
+    /// If no such event arrives within the client timeout.
+    pub async fn expect_event_where(
+        &mut self,
+        event_type: &str,
+        predicate: impl Fn(&WsTestEvent) -> bool,
+    ) -> WsTestEvent {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.next_event(remaining).await {
+                Some(event) if event.event_type == event_type && predicate(&event) => return event,
+                Some(_) => {}
+                None => panic!(
+                    "{}: no {} event within {:?}",
+                    self.user_id, event_type, self.timeout
+                ),
+            }
+        }
+    }
+
+    /// Assert no event of `event_type` arrives within `window`.
+    ///
+    /// # Panics
+    ///
+    /// If such an event arrives.
+    pub async fn expect_no_event(&mut self, event_type: &str, window: Duration) {
+        self.expect_no_event_where(event_type, window, |_| true)
+            .await
+    }
+
+    /// Assert no event of `event_type` matching `predicate` arrives within
+    /// `window`.
+    ///
+    /// # Panics
+    ///
+    /// If such an event arrives.
+    pub async fn expect_no_event_where(
+        &mut self,
+        event_type: &str,
+        window: Duration,
+        predicate: impl Fn(&WsTestEvent) -> bool,
+    ) {
+        let deadline = Instant::now() + window;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.next_event(remaining).await {
+                Some(event) if event.event_type == event_type && predicate(&event) => {
+                    panic!("{}: unexpected event {}", self.user_id, event.data)
+                }
+                Some(_) => {}
+                None => return,
+            }
+        }
+    }
+
+    /// Close the connection
+    pub async fn close(mut self) {
+        let _ = self.stream.close(None).await;
+    }
+}
+
+/// `http(s)://host/base` -> `ws(s)://host/base`
+fn ws_base_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    if let Some(rest) = base_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        base_url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::{Message as AxumMessage, WebSocket, WebSocketUpgrade};
+    use axum::extract::Query;
+    use axum::response::Response;
+    use axum::routing::get;
+    use axum::Router;
+    use std::collections::HashMap;
+
+    /// Greets like the real server, answers pings and echoes other events
+    async fn echo(ws: WebSocketUpgrade, Query(params): Query<HashMap<String, String>>) -> Response {
+        let user_id = params.get("user_id").cloned().unwrap_or_default();
+        ws.on_upgrade(move |mut socket: WebSocket| async move {
+            let connected = json!({ "type": "connected", "employee_id": user_id });
+            let _ = socket
+                .send(AxumMessage::Text(connected.to_string().into()))
+                .await;
+            while let Some(Ok(AxumMessage::Text(text))) = socket.recv().await {
+                let event: Value = serde_json::from_str(&text).unwrap();
+                let reply = if event["type"] == "ping" {
+                    json!({ "type": "pong" })
+                } else {
+                    event
+                };
+                let _ = socket
+                    .send(AxumMessage::Text(reply.to_string().into()))
+                    .await;
+            }
+        })
+    }
+
+    async fn serve() -> String {
+        let app = Router::new().route("/api/v1/ws", get(echo));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/", addr)
+    }
+
+    #[test]
+    fn test_ws_base_url() {
+        assert_eq!(ws_base_url("http://localhost:8080/"), "ws://localhost:8080");
+        assert_eq!(
+            ws_base_url("https://example.com/chat"),
+            "wss://example.com/chat"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_waits_for_events_by_type() {
+        let base_url = serve().await;
+        let mut client = WsTestClient::connect(&base_url, "user 1")
+            .await
+            .unwrap()
+            .with_timeout(Duration::from_secs(2));
+        assert_eq!(client.user_id(), "user 1");
+        client.ping().await;
+
+        let dialog_id = Uuid::new_v4();
+        for (event_type, dialog) in [
+            ("message.read", Uuid::new_v4()),
+            ("message.new", Uuid::new_v4()),
+            ("message.new", dialog_id),
+        ] {
+            client
+                .send(&json!({ "type": event_type, "dialog_id": dialog }))
+                .await
+                .unwrap();
+        }
+        // Skips the read event and the message in the other dialog
+        let event = client
+            .expect_event_where("message.new", |e| e.dialog_id() == Some(dialog_id))
+            .await;
+        assert_eq!(event.dialog_id(), Some(dialog_id));
+        client
+            .expect_no_event("message.new", Duration::from_millis(100))
+            .await;
+        client.close().await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "unexpected event")]
+    async fn test_expect_no_event_panics_on_event() {
+        let base_url = serve().await;
+        let mut client = WsTestClient::connect(&base_url, "user-1").await.unwrap();
+        client
+            .send(&json!({ "type": "message.new" }))
+            .await
+            .unwrap();
+        client
+            .expect_no_event("message.new", DEFAULT_SILENCE_WINDOW)
+            .await;
+    }
+}
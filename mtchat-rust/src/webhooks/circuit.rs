@@ -0,0 +1,230 @@
+//! Circuit breaker and health tracking for the webhook endpoint
+//!
+//! After `circuit_failure_threshold` consecutive failed deliveries the circuit
+//! opens: deliveries pause and new events are buffered as dead letters. Every
+//! `circuit_open_secs` one request probes the endpoint (half-open, no retries);
+//! a successful probe closes the circuit and the dead letters are redelivered.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Circuit state of an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Deliveries go through
+    Closed,
+    /// Deliveries are paused, events are buffered as dead letters
+    Open,
+    /// A probe request is in flight
+    HalfOpen,
+}
+
+/// Delivery health of a webhook endpoint (this instance only)
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub state: CircuitState,
+    /// Failed deliveries since the last success
+    pub consecutive_failures: u32,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// When the circuit last opened (`None` while closed)
+    pub opened_at: Option<DateTime<Utc>>,
+    /// When the next probe is due (`None` while closed)
+    pub next_probe_at: Option<DateTime<Utc>>,
+    /// Events buffered while the circuit is open
+    pub dead_letters: usize,
+    /// Events dropped because the dead-letter buffer was full
+    pub dead_letters_dropped: u64,
+}
+
+impl EndpointHealth {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            last_success_at: None,
+            last_failure_at: None,
+            last_error: None,
+            opened_at: None,
+            next_probe_at: None,
+            dead_letters: 0,
+            dead_letters_dropped: 0,
+        }
+    }
+}
+
+/// Shared handle to an endpoint's health, read by the management API
+pub type HealthHandle = Arc<Mutex<EndpointHealth>>;
+
+/// Circuit breaker driven by the webhook worker
+pub(super) struct CircuitBreaker {
+    /// Consecutive failures that open the circuit (0 = never open)
+    threshold: u32,
+    open_for: Duration,
+    probe_at: Option<Instant>,
+    health: HealthHandle,
+}
+
+impl CircuitBreaker {
+    pub(super) fn new(url: &str, threshold: u32, open_for: Duration) -> Self {
+        Self {
+            threshold,
+            open_for,
+            probe_at: None,
+            health: Arc::new(Mutex::new(EndpointHealth::new(url))),
+        }
+    }
+
+    pub(super) fn health(&self) -> HealthHandle {
+        self.health.clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut EndpointHealth)) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut health);
+    }
+
+    /// Deliveries are paused
+    pub(super) fn is_open(&self) -> bool {
+        self.probe_at.is_some()
+    }
+
+    /// When the open circuit may be probed
+    pub(super) fn probe_at(&self) -> Option<Instant> {
+        self.probe_at
+    }
+
+    /// The circuit is open and due for a probe
+    pub(super) fn probe_due(&self) -> bool {
+        self.probe_at.is_some_and(|at| at <= Instant::now())
+    }
+
+    /// Mark the next delivery as a probe of the open circuit
+    pub(super) fn start_probe(&self) {
+        self.update(|h| h.state = CircuitState::HalfOpen);
+    }
+
+    pub(super) fn record_success(&mut self) {
+        self.probe_at = None;
+        self.update(|h| {
+            h.state = CircuitState::Closed;
+            h.consecutive_failures = 0;
+            h.last_success_at = Some(Utc::now());
+            h.opened_at = None;
+            h.next_probe_at = None;
+        });
+    }
+
+    /// Record a failed delivery; returns whether the circuit is now open
+    pub(super) fn record_failure(&mut self, error: &str) -> bool {
+        let now = Utc::now();
+        let mut failures = 0;
+        self.update(|h| {
+            h.consecutive_failures += 1;
+            h.last_failure_at = Some(now);
+            h.last_error = Some(error.to_string());
+            failures = h.consecutive_failures;
+        });
+
+        let reopen = self.is_open();
+        if !reopen && (self.threshold == 0 || failures < self.threshold) {
+            return false;
+        }
+
+        self.probe_at = Some(Instant::now() + self.open_for);
+        let next_probe_at = chrono::Duration::from_std(self.open_for)
+            .ok()
+            .map(|d| now + d);
+        self.update(|h| {
+            h.state = CircuitState::Open;
+            if !reopen {
+                h.opened_at = Some(now);
+            }
+            h.next_probe_at = next_probe_at;
+        });
+        true
+    }
+
+    /// Report the size of the dead-letter buffer
+    pub(super) fn set_dead_letters(&self, buffered: usize, dropped: u64) {
+        self.update(|h| {
+            h.dead_letters = buffered;
+            h.dead_letters_dropped = dropped;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(breaker: &CircuitBreaker) -> EndpointHealth {
+        breaker.health().lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let mut breaker = CircuitBreaker::new("https://example.com", 3, Duration::from_secs(30));
+
+        assert!(!breaker.record_failure("HTTP 500"));
+        assert!(!breaker.record_failure("HTTP 500"));
+        assert!(breaker.record_failure("HTTP 502"));
+
+        assert!(breaker.is_open());
+        let health = snapshot(&breaker);
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(health.consecutive_failures, 3);
+        assert_eq!(health.last_error.as_deref(), Some("HTTP 502"));
+        assert!(health.opened_at.is_some());
+        assert!(health.next_probe_at.is_some());
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let mut breaker = CircuitBreaker::new("https://example.com", 2, Duration::from_secs(30));
+
+        breaker.record_failure("HTTP 500");
+        breaker.record_success();
+        assert!(!breaker.record_failure("HTTP 500"));
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let mut breaker = CircuitBreaker::new("https://example.com", 1, Duration::from_secs(30));
+        breaker.record_failure("HTTP 500");
+        let opened_at = snapshot(&breaker).opened_at;
+
+        breaker.start_probe();
+        assert_eq!(snapshot(&breaker).state, CircuitState::HalfOpen);
+        assert!(breaker.record_failure("timeout"));
+
+        let health = snapshot(&breaker);
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(health.opened_at, opened_at);
+
+        breaker.start_probe();
+        breaker.record_success();
+        let health = snapshot(&breaker);
+        assert_eq!(health.state, CircuitState::Closed);
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.opened_at.is_none());
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_zero_threshold_never_opens() {
+        let mut breaker = CircuitBreaker::new("https://example.com", 0, Duration::from_secs(30));
+        for _ in 0..10 {
+            assert!(!breaker.record_failure("HTTP 500"));
+        }
+        assert_eq!(snapshot(&breaker).state, CircuitState::Closed);
+    }
+}
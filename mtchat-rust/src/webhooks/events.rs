@@ -2,9 +2,16 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::domain::{Dialog, DialogParticipant, JoinedAs, Message};
+use crate::domain::{
+    ActionButton, Attachment, BroadcastMention, ContentBlock, Dialog, DialogParticipant, JoinedAs,
+    Message, MessagePriority, SlaTarget, UnansweredMessage,
+};
+
+/// Characters of extracted text sent in `attachment.text_extracted`
+pub const ATTACHMENT_TEXT_WEBHOOK_CHARS: usize = 1000;
 
 /// Webhook event types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -12,31 +19,93 @@ use crate::domain::{Dialog, DialogParticipant, JoinedAs, Message};
 pub enum WebhookEventType {
     /// New message was sent
     MessageNew,
+    /// Message content was edited
+    MessageEdited,
+    /// Message was deleted
+    MessageDeleted,
+    /// Participant's read pointer advanced (opt-in, coalesced)
+    MessageRead,
+    /// Participant clicked a callback button of a message
+    MessageAction,
     /// User joined a dialog
     ParticipantJoined,
     /// User left a dialog
     ParticipantLeft,
+    /// Dialog was archived for one or more participants
+    DialogArchived,
+    /// Dialog was unarchived for one or more participants
+    DialogUnarchived,
     /// Notification pending - message not read after delay
     NotificationPending,
+    /// Unread message addressed to the recipient via `@channel` / `@here`
+    NotificationMention,
+    /// Unread urgent message, sent without the notification delay
+    NotificationUrgent,
+    /// Pending notifications of a recipient coalesced over the digest window
+    NotificationDigest,
+    /// Text of a document attachment was extracted (`text-extract` feature)
+    AttachmentTextExtracted,
+    /// Unanswered message passed the SLA warning time
+    SlaWarning,
+    /// Unanswered message passed the SLA response time
+    SlaBreached,
 }
 
 impl WebhookEventType {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::MessageNew => "message.new",
+            Self::MessageEdited => "message.edited",
+            Self::MessageDeleted => "message.deleted",
+            Self::MessageRead => "message.read",
+            Self::MessageAction => "message.action",
             Self::ParticipantJoined => "participant.joined",
             Self::ParticipantLeft => "participant.left",
+            Self::DialogArchived => "dialog.archived",
+            Self::DialogUnarchived => "dialog.unarchived",
             Self::NotificationPending => "notification.pending",
+            Self::NotificationMention => "notification.mention",
+            Self::NotificationUrgent => "notification.urgent",
+            Self::NotificationDigest => "notification.digest",
+            Self::AttachmentTextExtracted => "attachment.text_extracted",
+            Self::SlaWarning => "sla.warning",
+            Self::SlaBreached => "sla.breached",
         }
     }
 }
 
+impl WebhookEventType {
+    /// Delivery instructions for one recipient rather than dialog activity
+    pub fn is_notification(&self) -> bool {
+        matches!(
+            self,
+            Self::NotificationPending
+                | Self::NotificationMention
+                | Self::NotificationUrgent
+                | Self::NotificationDigest
+        )
+    }
+}
+
 impl std::fmt::Display for WebhookEventType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())
     }
 }
 
+/// Payload version sent to receivers that did not opt into a newer one
+pub const DEFAULT_WEBHOOK_PAYLOAD_VERSION: u32 = 1;
+
+/// Newest payload version this build can send.
+///
+/// Version 2 adds to message data the message `seq`, the `attachments` of
+/// `message.new` and the parsed `system_event` of system messages.
+pub const LATEST_WEBHOOK_PAYLOAD_VERSION: u32 = 2;
+
+fn default_payload_version() -> u32 {
+    DEFAULT_WEBHOOK_PAYLOAD_VERSION
+}
+
 /// Webhook event wrapper
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhookEvent {
@@ -45,10 +114,62 @@ pub struct WebhookEvent {
     /// Event type
     #[serde(rename = "type")]
     pub event_type: WebhookEventType,
+    /// Version of the payload schema, chosen per receiver
+    #[serde(default = "default_payload_version")]
+    pub payload_version: u32,
     /// When the event occurred
     pub timestamp: DateTime<Utc>,
     /// Event payload
     pub payload: WebhookPayload,
+    /// `X-Request-Id` of the request that triggered the event (sent as a header)
+    #[serde(skip)]
+    pub request_id: Option<String>,
+}
+
+/// Version of the [`WebhookBatch`] envelope (single events are unversioned)
+pub const WEBHOOK_BATCH_VERSION: u32 = 2;
+
+/// Events delivered together in one request when batching is enabled
+///
+/// The batch is signed as a whole; events keep their own id, type and
+/// timestamp, and carry their request ID in the body.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookBatch {
+    /// Envelope version ([`WEBHOOK_BATCH_VERSION`])
+    pub version: u32,
+    /// Unique batch ID (sent as `X-Webhook-Id`)
+    pub id: Uuid,
+    /// When the batch was flushed
+    pub timestamp: DateTime<Utc>,
+    /// Events in the order they were queued
+    pub events: Vec<BatchedWebhookEvent>,
+}
+
+/// Event inside a [`WebhookBatch`]
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchedWebhookEvent {
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+    /// `X-Request-Id` of the request that triggered the event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl WebhookBatch {
+    pub fn new(events: Vec<WebhookEvent>) -> Self {
+        Self {
+            version: WEBHOOK_BATCH_VERSION,
+            id: Uuid::now_v7(),
+            timestamp: Utc::now(),
+            events: events
+                .into_iter()
+                .map(|event| BatchedWebhookEvent {
+                    request_id: event.request_id.clone(),
+                    event,
+                })
+                .collect(),
+        }
+    }
 }
 
 impl WebhookEvent {
@@ -57,9 +178,34 @@ impl WebhookEvent {
         Self {
             id: Uuid::now_v7(),
             event_type,
+            payload_version: LATEST_WEBHOOK_PAYLOAD_VERSION,
             timestamp: Utc::now(),
             payload,
+            request_id: None,
+        }
+    }
+
+    /// The event as sent to a receiver of payload `version`: fields added
+    /// in later versions are left out
+    pub fn into_version(mut self, version: u32) -> Self {
+        self.payload_version = version;
+        if version < 2 {
+            if let Some(message) = self.payload.message_mut() {
+                message.seq = None;
+                message.attachments = None;
+                message.system_event = None;
+            }
         }
+        self
+    }
+
+    /// Attachments of a `message.new` event (payload version 2)
+    pub fn with_attachments(mut self, attachments: &[Attachment]) -> Self {
+        if let WebhookPayload::MessageNew(payload) = &mut self.payload {
+            payload.message.attachments =
+                Some(attachments.iter().map(AttachmentData::from).collect());
+        }
+        self
     }
 
     /// Create a message.new event
@@ -71,17 +217,89 @@ impl WebhookEvent {
                 object_id: dialog.object_id.clone(),
                 object_type: dialog.object_type.clone(),
                 message: MessageData {
-                    id: message.id,
-                    sender_id: message.sender_id.clone(),
-                    content: message.content.clone(),
-                    reply_to: message.reply_to_id,
-                    created_at: message.sent_at,
-                    message_type: message.message_type.as_str().to_string(),
+                    attachments: Some(Vec::new()),
+                    ..MessageData::from(message)
                 },
             }),
         )
     }
 
+    /// Create a message.edited event
+    ///
+    /// `previous_content` is the content before the edit; only its hash is sent.
+    pub fn message_edited(dialog: &Dialog, message: &Message, previous_content: &str) -> Self {
+        Self::new(
+            WebhookEventType::MessageEdited,
+            WebhookPayload::MessageEdited(MessageEditedPayload {
+                dialog_id: dialog.id,
+                object_id: dialog.object_id.clone(),
+                object_type: dialog.object_type.clone(),
+                message: MessageData::from(message),
+                previous_content_hash: content_hash(previous_content),
+                edited_at: message.last_edited_at.unwrap_or_else(Utc::now),
+            }),
+        )
+    }
+
+    /// Create a message.deleted event for a message that no longer exists
+    pub fn message_deleted(dialog: &Dialog, message: &Message) -> Self {
+        Self::new(
+            WebhookEventType::MessageDeleted,
+            WebhookPayload::MessageDeleted(MessageDeletedPayload {
+                dialog_id: dialog.id,
+                object_id: dialog.object_id.clone(),
+                object_type: dialog.object_type.clone(),
+                message_id: message.id,
+                sender_id: message.sender_id.clone(),
+                previous_content_hash: content_hash(&message.content),
+                deleted_at: Utc::now(),
+            }),
+        )
+    }
+
+    /// Create a message.read event: `user_id` has read up to `message`
+    pub fn message_read(dialog: &Dialog, user_id: &str, message: &Message) -> Self {
+        Self::new(
+            WebhookEventType::MessageRead,
+            WebhookPayload::MessageRead(MessageReadPayload {
+                dialog_id: dialog.id,
+                object_id: dialog.object_id.clone(),
+                object_type: dialog.object_type.clone(),
+                user_id: user_id.to_string(),
+                last_read_message_id: message.id,
+                message_sender_id: message.sender_id.clone(),
+                message_sent_at: message.sent_at,
+                read_at: Utc::now(),
+            }),
+        )
+    }
+
+    /// Create a message.action event: `user_id` clicked button `action` of
+    /// `message`; `system_message_id` is the message recording the click
+    pub fn message_action(
+        dialog: &Dialog,
+        message: &Message,
+        action: &ActionButton,
+        user_id: &str,
+        system_message_id: Uuid,
+    ) -> Self {
+        Self::new(
+            WebhookEventType::MessageAction,
+            WebhookPayload::MessageAction(MessageActionPayload {
+                dialog_id: dialog.id,
+                object_id: dialog.object_id.clone(),
+                object_type: dialog.object_type.clone(),
+                message_id: message.id,
+                action_id: action.id.clone(),
+                label: action.label.clone(),
+                user_id: user_id.to_string(),
+                message_metadata: message.metadata.clone(),
+                system_message_id,
+                clicked_at: Utc::now(),
+            }),
+        )
+    }
+
     /// Create a participant.joined event
     pub fn participant_joined(dialog: &Dialog, participant: &DialogParticipant) -> Self {
         Self::new(
@@ -111,6 +329,42 @@ impl WebhookEvent {
         )
     }
 
+    /// Create a dialog.archived event
+    pub fn dialog_archived(
+        dialog: &Dialog,
+        user_ids: Vec<String>,
+        trigger: ArchiveTrigger,
+        triggered_by: Option<&str>,
+    ) -> Self {
+        Self::new(
+            WebhookEventType::DialogArchived,
+            WebhookPayload::DialogArchive(DialogArchivePayload::new(
+                dialog,
+                user_ids,
+                trigger,
+                triggered_by,
+            )),
+        )
+    }
+
+    /// Create a dialog.unarchived event
+    pub fn dialog_unarchived(
+        dialog: &Dialog,
+        user_ids: Vec<String>,
+        trigger: ArchiveTrigger,
+        triggered_by: Option<&str>,
+    ) -> Self {
+        Self::new(
+            WebhookEventType::DialogUnarchived,
+            WebhookPayload::DialogArchive(DialogArchivePayload::new(
+                dialog,
+                user_ids,
+                trigger,
+                triggered_by,
+            )),
+        )
+    }
+
     /// Create a notification.pending event (smart notifications)
     ///
     /// Sent when a message was not read after delay period.
@@ -123,36 +377,211 @@ impl WebhookEvent {
     ) -> Self {
         Self::new(
             WebhookEventType::NotificationPending,
-            WebhookPayload::NotificationPending(NotificationPendingPayload {
+            WebhookPayload::NotificationPending(NotificationPendingPayload::new(
+                dialog,
+                message,
+                recipient_id,
+                sender_company,
+            )),
+        )
+    }
+
+    /// Create a notification.mention event
+    ///
+    /// Like notification.pending, for recipients reached by a broadcast
+    /// mention. The receiving system should treat it as high priority.
+    pub fn notification_mention(
+        dialog: &Dialog,
+        message: &Message,
+        recipient_id: &str,
+        sender_company: Option<String>,
+        mention: BroadcastMention,
+    ) -> Self {
+        Self::new(
+            WebhookEventType::NotificationMention,
+            WebhookPayload::NotificationMention(NotificationMentionPayload {
+                mention,
+                notification: NotificationPendingPayload::new(
+                    dialog,
+                    message,
+                    recipient_id,
+                    sender_company,
+                ),
+            }),
+        )
+    }
+
+    /// Create a notification.urgent event
+    ///
+    /// Like notification.pending, for urgent messages. Sent right away
+    /// instead of after the notification delay.
+    pub fn notification_urgent(
+        dialog: &Dialog,
+        message: &Message,
+        recipient_id: &str,
+        sender_company: Option<String>,
+    ) -> Self {
+        Self::new(
+            WebhookEventType::NotificationUrgent,
+            WebhookPayload::NotificationPending(NotificationPendingPayload::new(
+                dialog,
+                message,
+                recipient_id,
+                sender_company,
+            )),
+        )
+    }
+
+    /// Create a notification.digest event
+    ///
+    /// Replaces the notification.pending events of a recipient within the
+    /// `notification_digest_window_secs` window.
+    pub fn notification_digest(recipient_id: &str, dialogs: Vec<DigestDialog>) -> Self {
+        Self::new(
+            WebhookEventType::NotificationDigest,
+            WebhookPayload::NotificationDigest(NotificationDigestPayload {
+                recipient_id: recipient_id.to_string(),
+                notification_count: dialogs.iter().map(|d| d.notification_count).sum(),
+                dialogs,
+            }),
+        )
+    }
+
+    /// Create an attachment.text_extracted event
+    ///
+    /// Carries the first [`ATTACHMENT_TEXT_WEBHOOK_CHARS`] characters of the
+    /// text for host-side indexing.
+    pub fn attachment_text_extracted(
+        dialog: &Dialog,
+        attachment: &Attachment,
+        text: &str,
+        extracted_at: DateTime<Utc>,
+    ) -> Self {
+        let text_length = text.chars().count();
+        Self::new(
+            WebhookEventType::AttachmentTextExtracted,
+            WebhookPayload::AttachmentTextExtracted(AttachmentTextExtractedPayload {
                 dialog_id: dialog.id,
                 object_id: dialog.object_id.clone(),
                 object_type: dialog.object_type.clone(),
-                recipient_id: recipient_id.to_string(),
-                chat_title: dialog.title.clone(),
-                sender_company,
-                message: MessageData {
-                    id: message.id,
-                    sender_id: message.sender_id.clone(),
-                    content: message.content.clone(),
-                    reply_to: message.reply_to_id,
-                    created_at: message.sent_at,
-                    message_type: message.message_type.as_str().to_string(),
-                },
+                message_id: attachment.message_id,
+                attachment_id: attachment.id,
+                filename: attachment.filename.clone(),
+                content_type: attachment.content_type.clone(),
+                text: text.chars().take(ATTACHMENT_TEXT_WEBHOOK_CHARS).collect(),
+                text_length,
+                truncated: text_length > ATTACHMENT_TEXT_WEBHOOK_CHARS,
+                extracted_at,
             }),
         )
     }
+
+    /// Create an sla.warning event
+    ///
+    /// Sent once per unanswered message when it passes the warning time.
+    pub fn sla_warning(dialog: &Dialog, message: &UnansweredMessage, target: &SlaTarget) -> Self {
+        Self::new(
+            WebhookEventType::SlaWarning,
+            WebhookPayload::Sla(SlaPayload::new(dialog, message, target)),
+        )
+    }
+
+    /// Create an sla.breached event
+    ///
+    /// Sent once per unanswered message when it passes the response time.
+    pub fn sla_breached(dialog: &Dialog, message: &UnansweredMessage, target: &SlaTarget) -> Self {
+        Self::new(
+            WebhookEventType::SlaBreached,
+            WebhookPayload::Sla(SlaPayload::new(dialog, message, target)),
+        )
+    }
 }
 
 /// Event payload variants
+///
+/// Untagged: variants with more required fields come before the ones whose
+/// fields they include (`message.edited` before `message.new`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum WebhookPayload {
+    NotificationDigest(NotificationDigestPayload),
+    AttachmentTextExtracted(AttachmentTextExtractedPayload),
+    Sla(SlaPayload),
+    MessageEdited(MessageEditedPayload),
+    MessageDeleted(MessageDeletedPayload),
+    MessageAction(MessageActionPayload),
     MessageNew(MessageNewPayload),
+    MessageRead(MessageReadPayload),
     ParticipantJoined(ParticipantPayload),
     ParticipantLeft(ParticipantLeftPayload),
+    DialogArchive(DialogArchivePayload),
+    NotificationMention(NotificationMentionPayload),
     NotificationPending(NotificationPendingPayload),
 }
 
+impl WebhookPayload {
+    /// Dialog the event belongs to (None for digests spanning dialogs)
+    pub fn dialog_id(&self) -> Option<Uuid> {
+        match self {
+            Self::MessageNew(p) => Some(p.dialog_id),
+            Self::MessageEdited(p) => Some(p.dialog_id),
+            Self::MessageDeleted(p) => Some(p.dialog_id),
+            Self::ParticipantJoined(p) => Some(p.dialog_id),
+            Self::MessageRead(p) => Some(p.dialog_id),
+            Self::MessageAction(p) => Some(p.dialog_id),
+            Self::ParticipantLeft(p) => Some(p.dialog_id),
+            Self::DialogArchive(p) => Some(p.dialog_id),
+            Self::NotificationMention(p) => Some(p.notification.dialog_id),
+            Self::NotificationPending(p) => Some(p.dialog_id),
+            Self::NotificationDigest(_) => None,
+            Self::AttachmentTextExtracted(p) => Some(p.dialog_id),
+            Self::Sla(p) => Some(p.dialog_id),
+        }
+    }
+
+    /// Message data of the payload, if it carries a message
+    pub fn message_mut(&mut self) -> Option<&mut MessageData> {
+        match self {
+            Self::MessageNew(p) => Some(&mut p.message),
+            Self::MessageEdited(p) => Some(&mut p.message),
+            Self::NotificationPending(p) => Some(&mut p.message),
+            Self::NotificationMention(p) => Some(&mut p.notification.message),
+            _ => None,
+        }
+    }
+}
+
+/// Payload for sla.warning and sla.breached events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaPayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    /// Oldest unanswered message
+    pub message_id: Uuid,
+    pub sender_id: String,
+    /// When the unanswered message was sent
+    pub pending_since: DateTime<Utc>,
+    /// When the SLA is (or was) breached
+    pub due_at: DateTime<Utc>,
+    pub response_secs: i32,
+}
+
+impl SlaPayload {
+    fn new(dialog: &Dialog, message: &UnansweredMessage, target: &SlaTarget) -> Self {
+        Self {
+            dialog_id: dialog.id,
+            object_id: dialog.object_id.clone(),
+            object_type: dialog.object_type.clone(),
+            message_id: message.message_id,
+            sender_id: message.sender_id.clone(),
+            pending_since: message.sent_at,
+            due_at: target.due_at(message.sent_at),
+            response_secs: target.response_secs,
+        }
+    }
+}
+
 /// Payload for message.new events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageNewPayload {
@@ -166,21 +595,167 @@ pub struct MessageNewPayload {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageData {
     pub id: Uuid,
+    /// ID in the deployment's external ID format
+    #[serde(default)]
+    pub external_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sender_id: Option<String>,
     pub content: String,
+    /// Markdown source of `content` (messages sent as Markdown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_markdown: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_to: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     /// Message type: 'user' or 'system'
     #[serde(default = "default_message_type")]
     pub message_type: String,
+    /// `normal` or `urgent`
+    #[serde(default)]
+    pub priority: MessagePriority,
+    /// Integration data attached on send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Structured content rendered next to `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_blocks: Option<Vec<ContentBlock>>,
+    /// Position of the message in the dialog (payload version 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<i64>,
+    /// Attachments of a new message (payload version 2, `message.new` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<AttachmentData>>,
+    /// `content` of a system message parsed, e.g. `{"event": "participant_joined",
+    /// "name": "..."}` (payload version 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_event: Option<serde_json::Value>,
+}
+
+/// Attachment in message data (payload version 2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentData {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+}
+
+impl From<&Attachment> for AttachmentData {
+    fn from(attachment: &Attachment) -> Self {
+        Self {
+            id: attachment.id,
+            filename: attachment.filename.clone(),
+            content_type: attachment.content_type.clone(),
+            size: attachment.size,
+            width: attachment.width,
+            height: attachment.height,
+        }
+    }
 }
 
 fn default_message_type() -> String {
     "user".to_string()
 }
 
+impl From<&Message> for MessageData {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            external_id: message.external_id.clone(),
+            sender_id: message.sender_id.clone(),
+            content: message.content.clone(),
+            content_markdown: message.content_markdown.clone(),
+            reply_to: message.reply_to_id,
+            created_at: message.sent_at,
+            message_type: message.message_type.as_str().to_string(),
+            priority: message.priority,
+            metadata: message.metadata.clone(),
+            content_blocks: message.content_blocks.clone().map(|b| b.0),
+            seq: Some(message.seq),
+            attachments: None,
+            system_event: message
+                .is_system()
+                .then(|| serde_json::from_str(&message.content).ok())
+                .flatten(),
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of message content, so mirrors can check which
+/// version of a message an edit or delete applies to without resending it
+fn content_hash(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Payload for message.edited events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageEditedPayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    /// Message after the edit
+    pub message: MessageData,
+    /// SHA-256 (hex) of the content before the edit
+    pub previous_content_hash: String,
+    pub edited_at: DateTime<Utc>,
+}
+
+/// Payload for message.deleted events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageDeletedPayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    pub message_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_id: Option<String>,
+    /// SHA-256 (hex) of the content of the deleted message
+    pub previous_content_hash: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Payload for message.read events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReadPayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    /// Participant whose read pointer advanced
+    pub user_id: String,
+    pub last_read_message_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_sender_id: Option<String>,
+    /// When the last read message was sent (`read_at - message_sent_at` is
+    /// the response time)
+    pub message_sent_at: DateTime<Utc>,
+    pub read_at: DateTime<Utc>,
+}
+
+/// Payload for message.action events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageActionPayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    /// Message carrying the button
+    pub message_id: Uuid,
+    pub action_id: String,
+    /// Button label at the time of the click
+    pub label: String,
+    /// Participant who clicked
+    pub user_id: String,
+    /// Integration data of the message, for routing the action in the host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_metadata: Option<serde_json::Value>,
+    /// System message recording the click
+    pub system_message_id: Uuid,
+    pub clicked_at: DateTime<Utc>,
+}
+
 /// Payload for participant.joined events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParticipantPayload {
@@ -202,6 +777,56 @@ pub struct ParticipantLeftPayload {
     pub left_at: DateTime<Utc>,
 }
 
+/// What archived or unarchived a dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveTrigger {
+    /// A participant archived or unarchived the dialog for themselves
+    User,
+    /// A participant's bulk dialog action
+    BulkAction,
+    /// The auto-archive job (inactive dialog)
+    AutoArchive,
+    /// A new message unarchived the dialog for everyone
+    NewMessage,
+    /// A `dialog.archive` inbound integration event
+    Integration,
+}
+
+/// Payload for dialog.archived and dialog.unarchived events
+///
+/// Archiving is per participant: `user_ids` lists whose dialog list changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DialogArchivePayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    /// Participants the dialog was archived or unarchived for
+    pub user_ids: Vec<String>,
+    pub trigger: ArchiveTrigger,
+    /// User whose action caused the change (the sender for `new_message`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triggered_by: Option<String>,
+}
+
+impl DialogArchivePayload {
+    fn new(
+        dialog: &Dialog,
+        user_ids: Vec<String>,
+        trigger: ArchiveTrigger,
+        triggered_by: Option<&str>,
+    ) -> Self {
+        Self {
+            dialog_id: dialog.id,
+            object_id: dialog.object_id.clone(),
+            object_type: dialog.object_type.clone(),
+            user_ids,
+            trigger,
+            triggered_by: triggered_by.map(str::to_string),
+        }
+    }
+}
+
 /// Payload for notification.pending events (smart notifications)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationPendingPayload {
@@ -216,10 +841,104 @@ pub struct NotificationPendingPayload {
     /// Company of the message sender (for notification text)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sender_company: Option<String>,
+    /// Dialog timezone for formatting times in the notification (e.g. digests)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Dialog locale for the notification text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
     /// Message that triggered the notification
     pub message: MessageData,
 }
 
+impl NotificationPendingPayload {
+    fn new(
+        dialog: &Dialog,
+        message: &Message,
+        recipient_id: &str,
+        sender_company: Option<String>,
+    ) -> Self {
+        Self {
+            dialog_id: dialog.id,
+            object_id: dialog.object_id.clone(),
+            object_type: dialog.object_type.clone(),
+            recipient_id: recipient_id.to_string(),
+            chat_title: dialog.title.clone(),
+            sender_company,
+            timezone: dialog.timezone.clone(),
+            locale: dialog.locale.clone(),
+            message: MessageData::from(message),
+        }
+    }
+}
+
+/// Payload for notification.mention events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationMentionPayload {
+    /// Which broadcast mention reached the recipient
+    pub mention: BroadcastMention,
+    #[serde(flatten)]
+    pub notification: NotificationPendingPayload,
+}
+
+/// Payload for notification.digest events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationDigestPayload {
+    /// User who should receive the notification
+    pub recipient_id: String,
+    /// Dialogs that still have unread messages, most notifications first
+    pub dialogs: Vec<DigestDialog>,
+    /// Notifications coalesced into the digest
+    pub notification_count: u64,
+}
+
+/// Dialog listed in a notification.digest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestDialog {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    /// Human-readable chat title (for notification text)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_title: Option<String>,
+    /// Notifications coalesced for this dialog
+    pub notification_count: u64,
+    /// Unread messages of the recipient in the dialog
+    pub unread_count: i32,
+}
+
+impl DigestDialog {
+    pub fn new(dialog: &Dialog, notification_count: u64, unread_count: i32) -> Self {
+        Self {
+            dialog_id: dialog.id,
+            object_id: dialog.object_id.clone(),
+            object_type: dialog.object_type.clone(),
+            chat_title: dialog.title.clone(),
+            notification_count,
+            unread_count,
+        }
+    }
+}
+
+/// Payload for attachment.text_extracted events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentTextExtractedPayload {
+    pub dialog_id: Uuid,
+    pub object_id: String,
+    pub object_type: String,
+    pub message_id: Uuid,
+    pub attachment_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    /// Beginning of the extracted text
+    pub text: String,
+    /// Length of the full extracted text in characters
+    pub text_length: usize,
+    /// Whether `text` is shorter than the extracted text
+    pub truncated: bool,
+    pub extracted_at: DateTime<Utc>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -247,11 +966,19 @@ mod tests {
                 object_type: "tender".to_string(),
                 message: MessageData {
                     id: Uuid::nil(),
+                    external_id: Uuid::nil().to_string(),
                     sender_id: Some("user-1".to_string()),
                     content: "Hello".to_string(),
+                    content_markdown: None,
                     reply_to: None,
                     created_at: Utc::now(),
                     message_type: "user".to_string(),
+                    priority: MessagePriority::Normal,
+                    metadata: None,
+                    content_blocks: None,
+                    seq: None,
+                    attachments: None,
+                    system_event: None,
                 },
             }),
         );
@@ -5,10 +5,20 @@
 //! - `participant.joined` - User joined a dialog
 //! - `participant.left` - User left a dialog
 //!
-//! Webhooks are signed with HMAC-SHA256 for verification.
+//! Webhooks are signed with HMAC-SHA256 for verification. Payloads are
+//! versioned: an endpoint receives version 1 unless it opts into a newer one,
+//! so payloads can evolve without breaking existing receivers. With batching
+//! enabled, queued events are delivered together as one signed request.
+//! A circuit breaker pauses delivery to an endpoint that keeps failing.
 
+mod circuit;
 mod events;
 mod sender;
 
-pub use events::{WebhookEvent, WebhookEventType, WebhookPayload};
-pub use sender::{WebhookConfig, WebhookSender};
+pub use circuit::{CircuitState, EndpointHealth};
+pub use events::{
+    ArchiveTrigger, BatchedWebhookEvent, DigestDialog, WebhookBatch, WebhookEvent,
+    WebhookEventType, WebhookPayload, ATTACHMENT_TEXT_WEBHOOK_CHARS,
+    DEFAULT_WEBHOOK_PAYLOAD_VERSION, LATEST_WEBHOOK_PAYLOAD_VERSION, WEBHOOK_BATCH_VERSION,
+};
+pub use sender::{verify_signature, WebhookConfig, WebhookSender};
@@ -1,18 +1,34 @@
 //! Webhook sender with HMAC signing and retry logic
 
 use hmac::{Hmac, Mac};
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Identity};
+use serde::{Deserialize, Serialize};
 use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
+use uuid::Uuid;
 
-use super::WebhookEvent;
+use super::circuit::{CircuitBreaker, EndpointHealth, HealthHandle};
+use super::{WebhookBatch, WebhookEvent, WebhookPayload, DEFAULT_WEBHOOK_PAYLOAD_VERSION};
+use crate::config::serde_helpers::header_map;
+use crate::middleware::current_request_id;
+use crate::services::EventStream;
 
 type HmacSha256 = Hmac<Sha256>;
 
-/// Webhook configuration
-#[derive(Debug, Clone)]
+/// Webhook configuration (`[webhooks]` section)
+///
+/// Environment variables: `WEBHOOK_URL`, `WEBHOOK_SECRET` (both required to
+/// enable webhooks), `WEBHOOK_BATCH_MAX_EVENTS`, `WEBHOOK_BATCH_INTERVAL_MS`,
+/// `WEBHOOK_CIRCUIT_FAILURE_THRESHOLD`, `WEBHOOK_CIRCUIT_OPEN_SECS`,
+/// `WEBHOOK_DEAD_LETTER_CAPACITY`, `WEBHOOK_CLIENT_CERT`, `WEBHOOK_CLIENT_KEY`,
+/// `WEBHOOK_CA_BUNDLE`, `WEBHOOK_HEADERS`, `WEBHOOK_READ_RECEIPTS`,
+/// `WEBHOOK_READ_RECEIPT_INTERVAL_MS`, `WEBHOOK_PAYLOAD_VERSION`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WebhookConfig {
     /// Webhook endpoint URL
     pub url: String,
@@ -24,6 +40,33 @@ pub struct WebhookConfig {
     pub retry_delay_ms: u64,
     /// Request timeout in seconds (default: 10)
     pub timeout_secs: u64,
+    /// Deliver events in batches of at most this many (default: 0, batching off)
+    pub batch_max_events: usize,
+    /// Flush a batch this long after its first event (default: 1000)
+    pub batch_interval_ms: u64,
+    /// Consecutive failed deliveries that open the circuit (default: 5, 0 = never)
+    pub circuit_failure_threshold: u32,
+    /// Seconds between probes of an open circuit (default: 30)
+    pub circuit_open_secs: u64,
+    /// Events buffered while the circuit is open; the oldest are dropped
+    /// beyond this (default: 10000)
+    pub dead_letter_capacity: usize,
+    /// PEM client certificate for mutual TLS (with `client_key_path`)
+    pub client_cert_path: Option<String>,
+    /// PEM (PKCS#8) private key of the client certificate
+    pub client_key_path: Option<String>,
+    /// PEM bundle of extra CA certificates to trust
+    pub ca_bundle_path: Option<String>,
+    /// Static headers sent with every request, e.g. `Authorization`
+    #[serde(deserialize_with = "header_map")]
+    pub headers: BTreeMap<String, String>,
+    /// Send `message.read` events to this endpoint (default: false)
+    pub read_receipts: bool,
+    /// Send at most one `message.read` per participant and dialog in this
+    /// interval, with the latest read pointer (default: 10000)
+    pub read_receipt_interval_ms: u64,
+    /// Payload version the endpoint receives (default: 1)
+    pub payload_version: u32,
 }
 
 impl WebhookConfig {
@@ -34,9 +77,26 @@ impl WebhookConfig {
             max_retries: 3,
             retry_delay_ms: 1000,
             timeout_secs: 10,
+            batch_max_events: 0,
+            batch_interval_ms: 1000,
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+            dead_letter_capacity: 10_000,
+            client_cert_path: None,
+            client_key_path: None,
+            ca_bundle_path: None,
+            headers: BTreeMap::new(),
+            read_receipts: false,
+            read_receipt_interval_ms: 10_000,
+            payload_version: DEFAULT_WEBHOOK_PAYLOAD_VERSION,
         }
     }
 
+    /// Webhooks are sent only when both URL and secret are set
+    pub fn is_configured(&self) -> bool {
+        !self.url.is_empty() && !self.secret.is_empty()
+    }
+
     pub fn with_retries(mut self, max_retries: u32) -> Self {
         self.max_retries = max_retries;
         self
@@ -51,6 +111,128 @@ impl WebhookConfig {
         self.timeout_secs = timeout_secs;
         self
     }
+
+    /// Deliver events in batches flushed every `interval_ms` or `max_events`
+    pub fn with_batching(mut self, max_events: usize, interval_ms: u64) -> Self {
+        self.batch_max_events = max_events;
+        self.batch_interval_ms = interval_ms;
+        self
+    }
+
+    /// Open the circuit after `failure_threshold` consecutive failures and
+    /// probe it every `open_secs`
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, open_secs: u64) -> Self {
+        self.circuit_failure_threshold = failure_threshold;
+        self.circuit_open_secs = open_secs;
+        self
+    }
+
+    /// Authenticate with a client certificate (mutual TLS)
+    pub fn with_client_cert(
+        mut self,
+        cert_path: impl Into<String>,
+        key_path: impl Into<String>,
+    ) -> Self {
+        self.client_cert_path = Some(cert_path.into());
+        self.client_key_path = Some(key_path.into());
+        self
+    }
+
+    /// Trust the CA certificates in a PEM bundle
+    pub fn with_ca_bundle(mut self, path: impl Into<String>) -> Self {
+        self.ca_bundle_path = Some(path.into());
+        self
+    }
+
+    /// Send a static header with every request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Send payloads of `version` instead of version 1
+    pub fn with_payload_version(mut self, version: u32) -> Self {
+        self.payload_version = version;
+        self
+    }
+
+    /// Send `message.read` events, coalesced per participant every `interval_ms`
+    pub fn with_read_receipts(mut self, interval_ms: u64) -> Self {
+        self.read_receipts = true;
+        self.read_receipt_interval_ms = interval_ms;
+        self
+    }
+
+    /// Static headers, checked for valid names and values; the headers
+    /// the sender sets itself can't be overridden
+    pub fn header_map(&self) -> Result<HeaderMap, String> {
+        let mut map = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("invalid header name {:?}", name))?;
+            if RESERVED_HEADERS.contains(&header.as_str()) {
+                return Err(format!("header {:?} is set by the sender", name));
+            }
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| format!("invalid value for header {:?}", name))?;
+            map.insert(header, value);
+        }
+        Ok(map)
+    }
+
+    /// HTTP client with the timeout, TLS settings and static headers
+    pub fn build_client(&self) -> Result<Client, String> {
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .default_headers(self.header_map()?);
+
+        match (&self.client_cert_path, &self.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let cert = read_pem(cert_path)?;
+                let key = read_pem(key_path)?;
+                let identity = Identity::from_pkcs8_pem(&cert, &key)
+                    .map_err(|e| format!("invalid client certificate or key: {}", e))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => return Err("client certificate and key must be set together".into()),
+        }
+
+        if let Some(path) = &self.ca_bundle_path {
+            let certs = Certificate::from_pem_bundle(&read_pem(path)?)
+                .map_err(|e| format!("invalid CA bundle {}: {}", path, e))?;
+            if certs.is_empty() {
+                return Err(format!("CA bundle {} contains no certificates", path));
+            }
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        builder
+            .build()
+            .map_err(|e| format!("failed to create HTTP client: {}", e))
+    }
+
+    /// Events are grouped into batches instead of one request per event
+    pub fn is_batching(&self) -> bool {
+        self.batch_max_events > 0
+    }
+}
+
+/// Headers set by the sender on every request (lowercase)
+const RESERVED_HEADERS: &[&str] = &[
+    "content-type",
+    "x-webhook-signature",
+    "x-webhook-event",
+    "x-webhook-id",
+    "x-webhook-batch-size",
+    "x-webhook-payload-version",
+    "x-request-id",
+];
+
+fn read_pem(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path, e))
 }
 
 impl Default for WebhookConfig {
@@ -61,6 +243,18 @@ impl Default for WebhookConfig {
             max_retries: 3,
             retry_delay_ms: 1000,
             timeout_secs: 10,
+            batch_max_events: 0,
+            batch_interval_ms: 1000,
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+            dead_letter_capacity: 10_000,
+            client_cert_path: None,
+            client_key_path: None,
+            ca_bundle_path: None,
+            headers: BTreeMap::new(),
+            read_receipts: false,
+            read_receipt_interval_ms: 10_000,
+            payload_version: DEFAULT_WEBHOOK_PAYLOAD_VERSION,
         }
     }
 }
@@ -72,19 +266,49 @@ impl Default for WebhookConfig {
 #[derive(Clone)]
 pub struct WebhookSender {
     tx: mpsc::Sender<WebhookEvent>,
+    /// Endpoint health (`None` for the no-op sender)
+    health: Option<HealthHandle>,
+    /// Also publishes dialog events to Kafka/NATS
+    stream: Option<EventStream>,
+    /// Coalesces `message.read` events (`None` unless the endpoint opted in)
+    read_receipts: Option<mpsc::Sender<WebhookEvent>>,
+    /// Payload version the endpoint receives
+    payload_version: u32,
 }
 
 impl WebhookSender {
     /// Create a new webhook sender with the given configuration
     ///
     /// Returns the sender handle and spawns a background task for delivery.
-    pub fn new(config: WebhookConfig) -> Self {
+    /// Fails if the TLS files or static headers are invalid.
+    pub fn new(config: WebhookConfig) -> Result<Self, String> {
+        let client = config.build_client()?;
         let (tx, rx) = mpsc::channel::<WebhookEvent>(1000);
+        let breaker = CircuitBreaker::new(
+            &config.url,
+            config.circuit_failure_threshold,
+            Duration::from_secs(config.circuit_open_secs),
+        );
+        let health = Some(breaker.health());
+        let payload_version = config.payload_version;
+
+        let read_receipts = config.read_receipts.then(|| {
+            let (receipt_tx, receipt_rx) = mpsc::channel::<WebhookEvent>(1000);
+            let interval = Duration::from_millis(config.read_receipt_interval_ms);
+            tokio::spawn(read_receipt_worker(interval, receipt_rx, tx.clone()));
+            receipt_tx
+        });
 
         // Spawn background worker
-        tokio::spawn(webhook_worker(config, rx));
+        tokio::spawn(webhook_worker(config, client, rx, breaker));
 
-        Self { tx }
+        Ok(Self {
+            tx,
+            health,
+            stream: None,
+            read_receipts,
+            payload_version,
+        })
     }
 
     /// Create a no-op sender that discards all events
@@ -100,74 +324,521 @@ impl WebhookSender {
             }
         });
 
-        Self { tx }
+        Self {
+            tx,
+            health: None,
+            stream: None,
+            read_receipts: None,
+            payload_version: DEFAULT_WEBHOOK_PAYLOAD_VERSION,
+        }
+    }
+
+    /// Publish events to an event stream as well (except `notification.*`)
+    pub fn with_event_stream(mut self, stream: EventStream) -> Self {
+        self.stream = Some(stream);
+        self
     }
 
     /// Send a webhook event (non-blocking)
     ///
-    /// Returns immediately. Event is delivered in background.
-    pub async fn send(&self, event: WebhookEvent) {
+    /// Returns immediately. Event is delivered in background, tagged with
+    /// the current request ID unless it already carries one, in the payload
+    /// version of the endpoint (the event stream applies its own).
+    pub async fn send(&self, mut event: WebhookEvent) {
+        if event.request_id.is_none() {
+            event.request_id = current_request_id();
+        }
+        if let Some(stream) = &self.stream {
+            if !event.event_type.is_notification() {
+                stream.publish(&event);
+            }
+        }
+        let event = event.into_version(self.payload_version);
         if let Err(e) = self.tx.send(event).await {
             error!("Failed to queue webhook event: {}", e);
         }
     }
 
+    /// The endpoint opted in to `message.read` events
+    pub fn read_receipts_enabled(&self) -> bool {
+        self.read_receipts.is_some()
+    }
+
+    /// Send a `message.read` event (non-blocking)
+    ///
+    /// Dropped unless the endpoint opted in. Events are held back and only
+    /// the latest per participant and dialog is delivered each interval;
+    /// they are not published to the event stream.
+    pub async fn send_read_receipt(&self, mut event: WebhookEvent) {
+        let Some(read_receipts) = &self.read_receipts else {
+            return;
+        };
+        if event.request_id.is_none() {
+            event.request_id = current_request_id();
+        }
+        let event = event.into_version(self.payload_version);
+        if let Err(e) = read_receipts.send(event).await {
+            error!("Failed to queue read receipt: {}", e);
+        }
+    }
+
     /// Check if the sender is still active
     pub fn is_active(&self) -> bool {
         !self.tx.is_closed()
     }
+
+    /// Delivery health of the endpoint (`None` when webhooks are disabled)
+    pub fn health(&self) -> Option<EndpointHealth> {
+        let health = self.health.as_ref()?;
+        Some(health.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
 }
 
 /// Background worker that processes webhook events
-async fn webhook_worker(config: WebhookConfig, mut rx: mpsc::Receiver<WebhookEvent>) {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(config.timeout_secs))
-        .build()
-        .expect("Failed to create HTTP client");
+async fn webhook_worker(
+    config: WebhookConfig,
+    client: Client,
+    mut rx: mpsc::Receiver<WebhookEvent>,
+    breaker: CircuitBreaker,
+) {
+    if config.is_batching() {
+        info!(
+            max_events = config.batch_max_events,
+            interval_ms = config.batch_interval_ms,
+            "Webhook worker started in batching mode, sending to: {}",
+            config.url
+        );
+    } else {
+        info!("Webhook worker started, sending to: {}", config.url);
+    }
 
-    info!("Webhook worker started, sending to: {}", config.url);
+    let max_events = config.batch_max_events.max(1);
+    let interval = Duration::from_millis(config.batch_interval_ms);
+    let mut worker = Worker {
+        dead_letters: DeadLetters::new(config.dead_letter_capacity),
+        client,
+        config,
+        breaker,
+    };
 
-    while let Some(event) = rx.recv().await {
-        let event_type = event.event_type.to_string();
-        let event_id = event.id;
+    loop {
+        // While the circuit is open, wake up to probe it with the dead letters
+        let probe_at = worker
+            .breaker
+            .probe_at()
+            .filter(|_| !worker.dead_letters.is_empty());
+        let first = match probe_at {
+            Some(probe_at) => tokio::select! {
+                event = rx.recv() => event,
+                _ = tokio::time::sleep_until(probe_at) => {
+                    let events = worker.dead_letters.take(max_events);
+                    worker.probe(events).await;
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        let Some(first) = first else {
+            break;
+        };
 
-        match send_with_retry(&client, &config, &event).await {
-            Ok(()) => {
-                info!(
-                    event_id = %event_id,
-                    event_type = %event_type,
-                    "Webhook delivered successfully"
+        let events = collect_batch(&mut rx, first, max_events, interval).await;
+        if worker.breaker.probe_due() {
+            // Probe with the oldest events so that order is kept
+            let events = if worker.dead_letters.is_empty() {
+                events
+            } else {
+                worker.dead_letters.push(events);
+                worker.dead_letters.take(max_events)
+            };
+            worker.probe(events).await;
+        } else if worker.breaker.is_open() {
+            worker.bury(events);
+        } else {
+            worker.deliver(events).await;
+        }
+    }
+
+    if !worker.dead_letters.is_empty() {
+        warn!(
+            dead_letters = worker.dead_letters.len(),
+            "Webhook worker stopped with undelivered dead letters"
+        );
+    }
+    info!("Webhook worker stopped");
+}
+
+/// Collect events after `first` until the batch holds `max_events` or
+/// `interval` has passed
+async fn collect_batch(
+    rx: &mut mpsc::Receiver<WebhookEvent>,
+    first: WebhookEvent,
+    max_events: usize,
+    interval: Duration,
+) -> Vec<WebhookEvent> {
+    let mut events = vec![first];
+    let deadline = tokio::time::Instant::now() + interval;
+    while events.len() < max_events {
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(event)) => events.push(event),
+            // Channel closed (flush what we have) or interval elapsed
+            Ok(None) | Err(_) => break,
+        }
+    }
+    events
+}
+
+/// Hold `message.read` events back and pass the latest per participant and
+/// dialog on to the delivery worker every `interval`
+async fn read_receipt_worker(
+    interval: Duration,
+    mut rx: mpsc::Receiver<WebhookEvent>,
+    tx: mpsc::Sender<WebhookEvent>,
+) {
+    let mut pending = ReadReceipts::default();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(event) => pending.insert(event),
+                None => break,
+            },
+            _ = ticker.tick() => {
+                for event in pending.drain() {
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    for event in pending.drain() {
+        if tx.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// `message.read` events waiting for the next flush, one per participant
+/// and dialog
+#[derive(Default)]
+struct ReadReceipts {
+    events: HashMap<(Uuid, String), WebhookEvent>,
+}
+
+impl ReadReceipts {
+    /// Keep the event unless a pending one has a later read pointer
+    fn insert(&mut self, event: WebhookEvent) {
+        let WebhookPayload::MessageRead(payload) = &event.payload else {
+            return;
+        };
+        let key = (payload.dialog_id, payload.user_id.clone());
+        let newer = match self.events.get(&key).map(|e| &e.payload) {
+            Some(WebhookPayload::MessageRead(pending)) => {
+                payload.last_read_message_id >= pending.last_read_message_id
+            }
+            _ => true,
+        };
+        if newer {
+            self.events.insert(key, event);
+        }
+    }
+
+    /// Take the pending events, oldest read first
+    fn drain(&mut self) -> Vec<WebhookEvent> {
+        let mut events: Vec<WebhookEvent> = self.events.drain().map(|(_, e)| e).collect();
+        events.sort_by_key(|e| e.timestamp);
+        events
+    }
+}
+
+/// Bounded buffer of events held back while the circuit is open
+struct DeadLetters {
+    events: VecDeque<WebhookEvent>,
+    capacity: usize,
+    dropped: u64,
+}
+
+impl DeadLetters {
+    fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Append events, dropping the oldest beyond capacity
+    fn push(&mut self, events: Vec<WebhookEvent>) {
+        self.events.extend(events);
+        self.trim();
+    }
+
+    /// Put events taken for a failed delivery back in front
+    fn push_front(&mut self, events: Vec<WebhookEvent>) {
+        for event in events.into_iter().rev() {
+            self.events.push_front(event);
+        }
+        self.trim();
+    }
+
+    /// Take up to `max` of the oldest events
+    fn take(&mut self, max: usize) -> Vec<WebhookEvent> {
+        let n = max.min(self.events.len());
+        self.events.drain(..n).collect()
+    }
+
+    fn trim(&mut self) {
+        while self.events.len() > self.capacity {
+            if let Some(event) = self.events.pop_front() {
+                self.dropped += 1;
+                warn!(
+                    event_id = %event.id,
+                    event_type = %event.event_type,
+                    "Webhook dead-letter buffer full, dropping event"
                 );
             }
+        }
+    }
+}
+
+/// Delivery state of the worker
+struct Worker {
+    client: Client,
+    config: WebhookConfig,
+    breaker: CircuitBreaker,
+    dead_letters: DeadLetters,
+}
+
+impl Worker {
+    fn max_events(&self) -> usize {
+        self.config.batch_max_events.max(1)
+    }
+
+    /// Hold events back until the circuit closes
+    fn bury(&mut self, events: Vec<WebhookEvent>) {
+        self.dead_letters.push(events);
+        self.report_dead_letters();
+    }
+
+    fn report_dead_letters(&self) {
+        self.breaker
+            .set_dead_letters(self.dead_letters.len(), self.dead_letters.dropped);
+    }
+
+    /// Record a failed delivery; returns whether the circuit is open
+    fn fail(&mut self, error: &str) -> bool {
+        let was_open = self.breaker.is_open();
+        let open = self.breaker.record_failure(error);
+        if open && !was_open {
+            warn!(
+                url = %self.config.url,
+                open_secs = self.config.circuit_open_secs,
+                "Webhook endpoint keeps failing, circuit opened"
+            );
+        }
+        open
+    }
+
+    /// Deliver events while the circuit is closed
+    async fn deliver(&mut self, events: Vec<WebhookEvent>) {
+        match send_events(&self.client, &self.config, &events, self.config.max_retries).await {
+            Ok(()) => {
+                self.breaker.record_success();
+                self.redeliver().await;
+            }
             Err(e) => {
-                error!(
-                    event_id = %event_id,
-                    event_type = %event_type,
-                    error = %e,
-                    "Webhook delivery failed after retries"
-                );
+                if self.fail(&e) {
+                    self.bury(events);
+                }
             }
         }
     }
 
-    info!("Webhook worker stopped");
+    /// Probe the open circuit with one delivery (no retries)
+    async fn probe(&mut self, events: Vec<WebhookEvent>) {
+        self.breaker.start_probe();
+        info!(url = %self.config.url, "Probing webhook endpoint");
+
+        match send_events(&self.client, &self.config, &events, 0).await {
+            Ok(()) => {
+                info!(url = %self.config.url, "Webhook endpoint recovered, circuit closed");
+                self.breaker.record_success();
+                self.redeliver().await;
+            }
+            Err(e) => {
+                self.fail(&e);
+                self.dead_letters.push_front(events);
+            }
+        }
+        self.report_dead_letters();
+    }
+
+    /// Deliver the dead letters after the circuit closed
+    async fn redeliver(&mut self) {
+        if self.dead_letters.is_empty() {
+            return;
+        }
+        info!(
+            dead_letters = self.dead_letters.len(),
+            "Redelivering webhook dead letters"
+        );
+
+        while !self.dead_letters.is_empty() {
+            let events = self.dead_letters.take(self.max_events());
+            let result =
+                send_events(&self.client, &self.config, &events, self.config.max_retries).await;
+            match result {
+                Ok(()) => self.breaker.record_success(),
+                Err(e) => {
+                    if self.fail(&e) {
+                        self.dead_letters.push_front(events);
+                        break;
+                    }
+                }
+            }
+        }
+        self.report_dead_letters();
+    }
 }
 
-/// Send event with retry logic
-async fn send_with_retry(
+/// Send events as a single event or a batch, logging the outcome
+async fn send_events(
+    client: &Client,
+    config: &WebhookConfig,
+    events: &[WebhookEvent],
+    max_retries: u32,
+) -> Result<(), String> {
+    if !config.is_batching() {
+        if let [event] = events {
+            return send_event(client, config, event, max_retries).await;
+        }
+    }
+
+    let batch = WebhookBatch::new(events.to_vec());
+    let batch_size = batch.events.len();
+    let result = match serde_json::to_string(&batch) {
+        Ok(body) => {
+            let delivery = Delivery {
+                body,
+                event: "batch",
+                id: batch.id,
+                request_id: None,
+                batch_size: Some(batch_size),
+            };
+            send_with_retry(client, config, &delivery, max_retries).await
+        }
+        Err(e) => Err(format!("Failed to serialize batch: {}", e)),
+    };
+
+    match &result {
+        Ok(()) => {
+            info!(
+                batch_id = %batch.id,
+                batch_size = batch_size,
+                "Webhook batch delivered successfully"
+            );
+        }
+        Err(e) => {
+            let event_ids: Vec<String> = batch
+                .events
+                .iter()
+                .map(|e| e.event.id.to_string())
+                .collect();
+            error!(
+                batch_id = %batch.id,
+                batch_size = batch_size,
+                event_ids = %event_ids.join(","),
+                error = %e,
+                "Webhook batch delivery failed after retries"
+            );
+        }
+    }
+    result
+}
+
+/// Send a single event in its own request, logging the outcome
+async fn send_event(
     client: &Client,
     config: &WebhookConfig,
     event: &WebhookEvent,
+    max_retries: u32,
 ) -> Result<(), String> {
-    let payload =
-        serde_json::to_string(event).map_err(|e| format!("Failed to serialize event: {}", e))?;
+    let request_id = event.request_id.clone().unwrap_or_default();
+
+    let result = match serde_json::to_string(event) {
+        Ok(body) => {
+            let delivery = Delivery {
+                body,
+                event: event.event_type.as_str(),
+                id: event.id,
+                request_id: event.request_id.as_deref(),
+                batch_size: None,
+            };
+            send_with_retry(client, config, &delivery, max_retries).await
+        }
+        Err(e) => Err(format!("Failed to serialize event: {}", e)),
+    };
+
+    match &result {
+        Ok(()) => {
+            info!(
+                event_id = %event.id,
+                event_type = %event.event_type,
+                request_id = %request_id,
+                "Webhook delivered successfully"
+            );
+        }
+        Err(e) => {
+            error!(
+                event_id = %event.id,
+                event_type = %event.event_type,
+                request_id = %request_id,
+                error = %e,
+                "Webhook delivery failed after retries"
+            );
+        }
+    }
+    result
+}
+
+/// Serialized request body with the values of its headers
+struct Delivery<'a> {
+    body: String,
+    /// `X-Webhook-Event` (`batch` for batches)
+    event: &'a str,
+    /// `X-Webhook-Id` (event or batch ID)
+    id: Uuid,
+    /// `X-Request-Id`, single events only
+    request_id: Option<&'a str>,
+    /// `X-Webhook-Batch-Size`, batches only
+    batch_size: Option<usize>,
+}
 
-    let signature = compute_signature(&config.secret, &payload);
+/// Send a request body with retry logic
+async fn send_with_retry(
+    client: &Client,
+    config: &WebhookConfig,
+    delivery: &Delivery<'_>,
+    max_retries: u32,
+) -> Result<(), String> {
+    let signature = compute_signature(&config.secret, &delivery.body);
 
     let mut last_error = String::new();
     let mut delay = config.retry_delay_ms;
 
-    for attempt in 0..=config.max_retries {
+    for attempt in 0..=max_retries {
         if attempt > 0 {
             warn!(
                 attempt = attempt,
@@ -178,16 +849,24 @@ async fn send_with_retry(
             delay *= 2; // Exponential backoff
         }
 
-        match client
+        let mut request = client
             .post(&config.url)
             .header("Content-Type", "application/json")
             .header("X-Webhook-Signature", &signature)
-            .header("X-Webhook-Event", event.event_type.as_str())
-            .header("X-Webhook-Id", event.id.to_string())
-            .body(payload.clone())
-            .send()
-            .await
-        {
+            .header("X-Webhook-Event", delivery.event)
+            .header("X-Webhook-Id", delivery.id.to_string())
+            .header(
+                "X-Webhook-Payload-Version",
+                config.payload_version.to_string(),
+            );
+        if let Some(request_id) = delivery.request_id {
+            request = request.header("X-Request-Id", request_id);
+        }
+        if let Some(batch_size) = delivery.batch_size {
+            request = request.header("X-Webhook-Batch-Size", batch_size.to_string());
+        }
+
+        match request.body(delivery.body.clone()).send().await {
             Ok(response) => {
                 if response.status().is_success() {
                     return Ok(());
@@ -222,8 +901,8 @@ fn compute_signature(secret: &str, payload: &str) -> String {
 
 /// Verify HMAC-SHA256 signature
 ///
-/// Use this on the receiving end to verify webhook authenticity.
-#[allow(dead_code)]
+/// Use this on the receiving end to verify webhook authenticity; inbound
+/// integration events are verified with it too.
 pub fn verify_signature(secret: &str, payload: &str, signature: &str) -> bool {
     let expected = compute_signature(secret, payload);
     // Constant-time comparison to prevent timing attacks
@@ -231,7 +910,6 @@ pub fn verify_signature(secret: &str, payload: &str, signature: &str) -> bool {
 }
 
 /// Constant-time comparison
-#[allow(dead_code)]
 fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
     if a.len() != b.len() {
         return false;
@@ -281,5 +959,146 @@ mod tests {
         assert_eq!(config.max_retries, 5);
         assert_eq!(config.retry_delay_ms, 2000);
         assert_eq!(config.timeout_secs, 30);
+        assert!(!config.is_batching());
+
+        let config = config.with_batching(50, 500);
+        assert!(config.is_batching());
+        assert_eq!(config.batch_max_events, 50);
+        assert_eq!(config.batch_interval_ms, 500);
+    }
+
+    #[test]
+    fn test_header_map() {
+        let config = WebhookConfig::new("https://example.com/webhook", "secret")
+            .with_header("Authorization", "Bearer token")
+            .with_header("X-Tenant", "acme");
+        let headers = config.header_map().unwrap();
+        assert_eq!(headers["authorization"], "Bearer token");
+        assert_eq!(headers["x-tenant"], "acme");
+
+        let reserved = config.clone().with_header("X-Webhook-Signature", "forged");
+        assert!(reserved.header_map().is_err());
+
+        let invalid = config.clone().with_header("Bad Header", "value");
+        assert!(invalid.header_map().is_err());
+
+        let invalid = config.with_header("X-Tenant", "line\nbreak");
+        assert!(invalid.header_map().is_err());
+    }
+
+    #[test]
+    fn test_build_client_requires_tls_files() {
+        let config = WebhookConfig::new("https://example.com/webhook", "secret");
+        assert!(config.build_client().is_ok());
+
+        let missing = config
+            .clone()
+            .with_client_cert("/nonexistent/cert.pem", "/nonexistent/key.pem");
+        assert!(missing.build_client().unwrap_err().contains("cert.pem"));
+
+        let missing = config.with_ca_bundle("/nonexistent/ca.pem");
+        assert!(missing.build_client().unwrap_err().contains("ca.pem"));
+    }
+
+    fn left_event(user_id: &str) -> WebhookEvent {
+        let dialog = crate::domain::Dialog::new("tender-1", "tender", None, None, None, None);
+        WebhookEvent::participant_left(&dialog, user_id)
+    }
+
+    #[tokio::test]
+    async fn test_collect_batch_flushes_when_full() {
+        let (tx, mut rx) = mpsc::channel(10);
+        for user in ["b", "c", "d"] {
+            tx.send(left_event(user)).await.unwrap();
+        }
+
+        let batch = collect_batch(&mut rx, left_event("a"), 2, Duration::from_secs(60)).await;
+        assert_eq!(batch.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_collect_batch_flushes_after_interval() {
+        let (tx, mut rx) = mpsc::channel(10);
+        tx.send(left_event("b")).await.unwrap();
+
+        let batch = collect_batch(&mut rx, left_event("a"), 100, Duration::from_millis(20)).await;
+        assert_eq!(batch.len(), 2);
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_collect_batch_flushes_on_close() {
+        let (tx, mut rx) = mpsc::channel(10);
+        tx.send(left_event("b")).await.unwrap();
+        drop(tx);
+
+        let batch = collect_batch(&mut rx, left_event("a"), 100, Duration::from_secs(60)).await;
+        assert_eq!(batch.len(), 2);
+    }
+
+    #[test]
+    fn test_read_receipts_keep_latest_per_participant() {
+        let dialog = crate::domain::Dialog::new("tender-1", "tender", None, None, None, None);
+        let first = crate::domain::Message::new(dialog.id, "seller", "one");
+        let second = crate::domain::Message::new(dialog.id, "seller", "two");
+
+        let mut pending = ReadReceipts::default();
+        pending.insert(WebhookEvent::message_read(&dialog, "buyer", &second));
+        // An older pointer arriving late doesn't replace the newer one
+        pending.insert(WebhookEvent::message_read(&dialog, "buyer", &first));
+        pending.insert(WebhookEvent::message_read(&dialog, "other", &first));
+        // Only message.read events are coalesced
+        pending.insert(left_event("buyer"));
+
+        let events = pending.drain();
+        assert_eq!(events.len(), 2);
+        let buyer = events
+            .iter()
+            .find_map(|e| match &e.payload {
+                WebhookPayload::MessageRead(p) if p.user_id == "buyer" => Some(p),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(buyer.last_read_message_id, second.id);
+        assert!(pending.drain().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_receipt_worker_flushes_on_close() {
+        let dialog = crate::domain::Dialog::new("tender-1", "tender", None, None, None, None);
+        let message = crate::domain::Message::new(dialog.id, "seller", "hi");
+        let (receipt_tx, receipt_rx) = mpsc::channel(10);
+        let (tx, mut rx) = mpsc::channel(10);
+        let worker = tokio::spawn(read_receipt_worker(Duration::from_secs(60), receipt_rx, tx));
+
+        for _ in 0..3 {
+            let event = WebhookEvent::message_read(&dialog, "buyer", &message);
+            receipt_tx.send(event).await.unwrap();
+        }
+        drop(receipt_tx);
+        worker.await.unwrap();
+
+        assert!(rx.recv().await.is_some());
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[test]
+    fn test_dead_letters_keep_order_and_drop_oldest() {
+        let mut dead_letters = DeadLetters::new(3);
+        let events: Vec<WebhookEvent> =
+            ["a", "b", "c", "d"].iter().map(|u| left_event(u)).collect();
+        let ids: Vec<Uuid> = events.iter().map(|e| e.id).collect();
+
+        dead_letters.push(events);
+        assert_eq!(dead_letters.len(), 3);
+        assert_eq!(dead_letters.dropped, 1);
+
+        let taken = dead_letters.take(2);
+        assert_eq!(taken.iter().map(|e| e.id).collect::<Vec<_>>(), ids[1..3]);
+
+        dead_letters.push_front(taken);
+        let all = dead_letters.take(10);
+        assert_eq!(all.iter().map(|e| e.id).collect::<Vec<_>>(), ids[1..]);
+        assert!(dead_letters.is_empty());
     }
 }
@@ -9,17 +9,47 @@ use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
 use uuid::Uuid;
 
-use crate::repositories::ParticipantRepository;
-use crate::services::PresenceService;
+use crate::domain::{ContentBlock, MessagePriority, ReplyPreview, SenderProfile};
+use crate::repositories::{DialogReadState, DialogRepository, ParticipantRepository};
+use crate::services::{ConnectionRegistry, PresenceService};
 
 pub type ConnectionTx = mpsc::Sender<String>;
+
+/// Maximum number of dialogs in the `sync.state` event
+pub const MAX_SYNC_STATE_DIALOGS: usize = 200;
+
+/// A registered WebSocket connection of this instance
+pub struct Connection {
+    /// Distinguishes a reconnect of the same user from the connection it replaced
+    pub id: Uuid,
+    pub tx: ConnectionTx,
+    pub connected_at: DateTime<Utc>,
+    close: Arc<Notify>,
+}
+
+impl Connection {
+    pub fn new(tx: ConnectionTx) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            tx,
+            connected_at: Utc::now(),
+            close: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Ask the socket task to close the connection
+    pub fn close(&self) {
+        self.close.notify_one();
+    }
+}
+
 /// Concurrent connection map using DashMap for better performance
 /// compared to RwLock<HashMap> under high contention.
 /// Key is the external user identifier (String).
-pub type Connections = Arc<DashMap<String, ConnectionTx>>;
+pub type Connections = Arc<DashMap<String, Connection>>;
 
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -27,6 +57,13 @@ pub enum WsEvent {
     Connected {
         employee_id: String,
     },
+    /// Read state of the user's dialogs, sent after `connected` on request
+    #[serde(rename = "sync.state")]
+    SyncState {
+        dialogs: Vec<DialogReadState>,
+        /// The user has more dialogs than [`MAX_SYNC_STATE_DIALOGS`]
+        has_more: bool,
+    },
     #[serde(rename = "message.new")]
     MessageNew {
         id: Uuid,
@@ -36,6 +73,24 @@ pub enum WsEvent {
         content: String,
         sent_at: DateTime<Utc>,
         message_type: String,
+        /// `normal` or `urgent`
+        priority: MessagePriority,
+        /// Per-dialog sequence number for ordering and deduplication
+        seq: i64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reply_to_id: Option<Uuid>,
+        /// Preview of the message this one replies to
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reply_to: Option<Box<ReplyPreview>>,
+        /// Sender profile at send time (absent for system messages)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sender: Option<SenderProfile>,
+        /// Integration data attached on send
+        #[serde(skip_serializing_if = "Option::is_none")]
+        metadata: Option<serde_json::Value>,
+        /// Structured content rendered next to `content`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        content_blocks: Option<Vec<ContentBlock>>,
     },
     #[serde(rename = "message.edited")]
     MessageEdited {
@@ -43,12 +98,23 @@ pub enum WsEvent {
         dialog_id: Uuid,
         content: String,
         last_edited_at: DateTime<Utc>,
+        /// Message version after the edit
+        version: i32,
     },
     #[serde(rename = "message.deleted")]
     MessageDeleted {
         id: Uuid,
         dialog_id: Uuid,
     },
+    #[serde(rename = "message.reaction")]
+    MessageReaction {
+        dialog_id: Uuid,
+        message_id: Uuid,
+        user_id: String,
+        emoji: String,
+        /// false when the reaction was removed
+        added: bool,
+    },
     #[serde(rename = "message.read")]
     MessageRead {
         dialog_id: Uuid,
@@ -73,6 +139,21 @@ pub enum WsEvent {
     DialogUnarchived {
         dialog_id: Uuid,
     },
+    #[serde(rename = "dialog.notes_updated")]
+    DialogNotesUpdated {
+        dialog_id: Uuid,
+        content: String,
+        version: i32,
+        updated_by: Option<String>,
+        updated_at: Option<DateTime<Utc>>,
+    },
+    /// One event for a bulk action over many dialogs of one user
+    #[serde(rename = "dialogs.bulk_updated")]
+    DialogsBulkUpdated {
+        user_id: String,
+        action: String,
+        dialog_ids: Vec<Uuid>,
+    },
     #[serde(rename = "presence.update")]
     PresenceUpdate {
         user_id: String,
@@ -90,18 +171,27 @@ pub enum WsClientMessage {
     Ping,
 }
 
+/// Handle a connection. With `sync_state`, the `sync.state` event follows
+/// `connected`.
 pub async fn handle_socket(
     socket: WebSocket,
-    connections: Connections,
+    registry: Arc<ConnectionRegistry>,
     user_id: String,
     presence: Arc<PresenceService>,
     participants: Arc<ParticipantRepository>,
+    dialogs: Arc<DialogRepository>,
+    sync_state: bool,
 ) {
     let (mut sender, mut receiver) = socket.split();
     let (tx, mut rx) = mpsc::channel::<String>(100);
 
     // Register connection
-    connections.insert(user_id.clone(), tx.clone());
+    let connection = Connection::new(tx.clone());
+    let connection_id = connection.id;
+    let close = connection.close.clone();
+    let connections = registry.connections().clone();
+    connections.insert(user_id.clone(), connection);
+    registry.connection_opened();
 
     tracing::info!("WebSocket connected: {}", user_id);
 
@@ -120,6 +210,14 @@ pub async fn handle_socket(
     .unwrap();
     let _ = sender.send(Message::Text(connected.into())).await;
 
+    // Read state after registering, so no later event is missed; events
+    // already counted in it carry a seq up to its `last_message_seq`
+    if sync_state {
+        let event = sync_state_event(&dialogs, &user_id).await;
+        let json = serde_json::to_string(&event).unwrap();
+        let _ = sender.send(Message::Text(json.into())).await;
+    }
+
     // Spawn task to forward messages from channel to WebSocket
     let send_task = tokio::spawn(async move {
         while let Some(msg) = rx.recv().await {
@@ -132,7 +230,17 @@ pub async fn handle_socket(
     // Handle incoming messages
     let presence_for_loop = presence.clone();
     let user_id_for_loop = user_id.clone();
-    while let Some(Ok(msg)) = receiver.next().await {
+    loop {
+        let msg = tokio::select! {
+            msg = receiver.next() => match msg {
+                Some(Ok(msg)) => msg,
+                _ => break,
+            },
+            _ = close.notified() => {
+                tracing::info!("WebSocket force-disconnected: {}", user_id);
+                break;
+            }
+        };
         match msg {
             Message::Text(text) => {
                 if let Ok(client_msg) = serde_json::from_str::<WsClientMessage>(&text) {
@@ -159,8 +267,8 @@ pub async fn handle_socket(
         }
     }
 
-    // Cleanup
-    connections.remove(&user_id);
+    // Cleanup (unless a newer connection of the same user replaced this one)
+    connections.remove_if(&user_id, |_, c| c.id == connection_id);
 
     // Set user as offline
     if let Err(e) = presence.set_offline(&user_id).await {
@@ -174,6 +282,29 @@ pub async fn handle_socket(
     tracing::info!("WebSocket disconnected: {}", user_id);
 }
 
+/// The `sync.state` event of a user (an `error` event if it cannot be loaded)
+async fn sync_state_event(dialogs: &DialogRepository, user_id: &str) -> WsEvent {
+    match dialogs
+        .find_read_states(user_id, MAX_SYNC_STATE_DIALOGS as i64 + 1)
+        .await
+    {
+        Ok(mut states) => {
+            let has_more = states.len() > MAX_SYNC_STATE_DIALOGS;
+            states.truncate(MAX_SYNC_STATE_DIALOGS);
+            WsEvent::SyncState {
+                dialogs: states,
+                has_more,
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Failed to load sync state of user {}: {}", user_id, e);
+            WsEvent::Error {
+                message: "Failed to load sync state".to_string(),
+            }
+        }
+    }
+}
+
 /// Broadcast presence update to users who share dialogs with the target user
 async fn broadcast_presence(
     connections: &Connections,
@@ -219,8 +350,8 @@ async fn broadcast_presence(
     // Broadcast to connected recipients (except the user themselves)
     for recipient_id in recipient_ids {
         if recipient_id != user_id {
-            if let Some(tx) = connections.get(&recipient_id) {
-                let _ = tx.send(json.clone()).await;
+            if let Some(conn) = connections.get(&recipient_id) {
+                let _ = conn.tx.send(json.clone()).await;
             }
         }
     }
@@ -237,7 +368,7 @@ async fn broadcast_to_all(connections: &Connections, event: &WsEvent) {
     };
 
     for entry in connections.iter() {
-        if entry.value().send(json.clone()).await.is_err() {
+        if entry.value().tx.send(json.clone()).await.is_err() {
             tracing::debug!("Failed to send to user {}", entry.key());
         }
     }
@@ -254,18 +385,32 @@ async fn broadcast_to_users(connections: &Connections, event: &WsEvent, user_ids
     };
 
     for user_id in user_ids {
-        if let Some(tx) = connections.get(user_id) {
-            if tx.send(json.clone()).await.is_err() {
+        if let Some(conn) = connections.get(user_id) {
+            if conn.tx.send(json.clone()).await.is_err() {
                 tracing::debug!("Failed to send to user {}", user_id);
             }
         }
     }
 }
 
+/// Participants of a dialog, the recipients of its message events
+async fn dialog_recipients(participants: &ParticipantRepository, dialog_id: Uuid) -> Vec<String> {
+    participants
+        .get_dialog_participants_user_ids(&[dialog_id])
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(dialog_id = %dialog_id, error = %e, "Failed to get dialog participants");
+            Vec::new()
+        })
+}
+
+/// Broadcast a new message to the dialog's participants.
 pub async fn broadcast_message(
     connections: &Connections,
-    _dialog_id: Uuid,
+    participants: &ParticipantRepository,
+    dialog_id: Uuid,
     message: &crate::domain::Message,
+    reply_to: Option<&ReplyPreview>,
 ) {
     let event = WsEvent::MessageNew {
         id: message.id,
@@ -274,8 +419,16 @@ pub async fn broadcast_message(
         content: message.content.clone(),
         sent_at: message.sent_at,
         message_type: message.message_type.as_str().to_string(),
+        priority: message.priority,
+        seq: message.seq,
+        reply_to_id: message.reply_to_id,
+        reply_to: reply_to.cloned().map(Box::new),
+        sender: message.sender_profile(),
+        metadata: message.metadata.clone(),
+        content_blocks: message.content_blocks.clone().map(|b| b.0),
     };
-    broadcast_to_all(connections, &event).await;
+    let user_ids = dialog_recipients(participants, dialog_id).await;
+    broadcast_to_users(connections, &event, &user_ids).await;
 }
 
 pub async fn broadcast_read(
@@ -292,7 +445,12 @@ pub async fn broadcast_read(
     broadcast_to_all(connections, &event).await;
 }
 
-pub async fn broadcast_message_edited(connections: &Connections, message: &crate::domain::Message) {
+/// Broadcast an edited message to the dialog's participants.
+pub async fn broadcast_message_edited(
+    connections: &Connections,
+    participants: &ParticipantRepository,
+    message: &crate::domain::Message,
+) {
     let last_edited_at = match message.last_edited_at {
         Some(ts) => ts,
         None => return,
@@ -303,12 +461,16 @@ pub async fn broadcast_message_edited(connections: &Connections, message: &crate
         dialog_id: message.dialog_id,
         content: message.content.clone(),
         last_edited_at,
+        version: message.version,
     };
-    broadcast_to_all(connections, &event).await;
+    let user_ids = dialog_recipients(participants, message.dialog_id).await;
+    broadcast_to_users(connections, &event, &user_ids).await;
 }
 
+/// Broadcast a message deletion to the dialog's participants.
 pub async fn broadcast_message_deleted(
     connections: &Connections,
+    participants: &ParticipantRepository,
     dialog_id: Uuid,
     message_id: Uuid,
 ) {
@@ -316,7 +478,29 @@ pub async fn broadcast_message_deleted(
         id: message_id,
         dialog_id,
     };
-    broadcast_to_all(connections, &event).await;
+    let user_ids = dialog_recipients(participants, dialog_id).await;
+    broadcast_to_users(connections, &event, &user_ids).await;
+}
+
+/// Broadcast an added or removed reaction to the dialog's participants.
+pub async fn broadcast_message_reaction(
+    connections: &Connections,
+    participants: &ParticipantRepository,
+    dialog_id: Uuid,
+    message_id: Uuid,
+    user_id: &str,
+    emoji: &str,
+    added: bool,
+) {
+    let event = WsEvent::MessageReaction {
+        dialog_id,
+        message_id,
+        user_id: user_id.to_string(),
+        emoji: emoji.to_string(),
+        added,
+    };
+    let user_ids = dialog_recipients(participants, dialog_id).await;
+    broadcast_to_users(connections, &event, &user_ids).await;
 }
 
 pub async fn broadcast_participant_joined(
@@ -359,3 +543,42 @@ pub async fn broadcast_dialog_unarchived(
     let event = WsEvent::DialogUnarchived { dialog_id };
     broadcast_to_users(connections, &event, user_ids).await;
 }
+
+/// Broadcast new dialog notes to the dialog's participants.
+pub async fn broadcast_dialog_notes_updated(
+    connections: &Connections,
+    notes: &crate::domain::DialogNotes,
+    user_ids: &[String],
+) {
+    let event = WsEvent::DialogNotesUpdated {
+        dialog_id: notes.dialog_id,
+        content: notes.content.clone(),
+        version: notes.version,
+        updated_by: notes.updated_by.clone(),
+        updated_at: notes.updated_at,
+    };
+    broadcast_to_users(connections, &event, user_ids).await;
+}
+
+/// Broadcast the result of a bulk dialog action.
+///
+/// Read positions are visible to other participants, so `mark_read` goes to
+/// everyone like `message.read`; other actions are per-user state and only
+/// reach the acting user.
+pub async fn broadcast_dialogs_bulk_updated(
+    connections: &Connections,
+    user_id: &str,
+    action: crate::domain::BulkDialogAction,
+    dialog_ids: Vec<Uuid>,
+) {
+    let event = WsEvent::DialogsBulkUpdated {
+        user_id: user_id.to_string(),
+        action: action.as_str().to_string(),
+        dialog_ids,
+    };
+    if action == crate::domain::BulkDialogAction::MarkRead {
+        broadcast_to_all(connections, &event).await;
+    } else {
+        broadcast_to_users(connections, &event, &[user_id.to_string()]).await;
+    }
+}
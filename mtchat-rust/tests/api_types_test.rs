@@ -2,7 +2,10 @@
 
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use multitenancy_chat_api::api::{ApiError, ApiResponse, ErrorBody, ErrorResponse};
+use multitenancy_chat_api::api::{
+    ApiError, ApiResponse, AuthChallenge, ErrorBody, ErrorCode, ErrorResponse,
+};
+use multitenancy_chat_api::services::StorageError;
 
 // ============ ApiError ============
 
@@ -48,6 +51,47 @@ async fn test_api_error_forbidden() {
     assert_eq!(json["error"]["message"], "Not a participant");
 }
 
+#[tokio::test]
+async fn test_api_error_unauthorized_has_challenge() {
+    let response = ApiError::unauthorized(AuthChallenge::CHAT, "Authorization header required")
+        .into_response();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()[axum::http::header::WWW_AUTHENTICATE],
+        r#"Bearer realm="mtchat""#
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["code"], "UNAUTHORIZED");
+
+    let response =
+        ApiError::unauthorized(AuthChallenge::MANAGEMENT.invalid(), "Invalid admin token")
+            .into_response();
+    assert_eq!(
+        response.headers()[axum::http::header::WWW_AUTHENTICATE],
+        r#"Bearer realm="mtchat-management", error="invalid_token""#
+    );
+
+    // No bearer error code for other schemes
+    let response = ApiError::unauthorized(
+        AuthChallenge::IMPERSONATION.invalid(),
+        "Invalid or expired impersonation token",
+    )
+    .into_response();
+    assert_eq!(
+        response.headers()[axum::http::header::WWW_AUTHENTICATE],
+        r#"Impersonation realm="mtchat""#
+    );
+
+    // Denied access is not an authentication problem
+    let response = ApiError::Forbidden("Not a participant".into()).into_response();
+    assert!(!response
+        .headers()
+        .contains_key(axum::http::header::WWW_AUTHENTICATE));
+}
+
 #[tokio::test]
 async fn test_api_error_internal_hides_details() {
     let error = ApiError::Internal("connection refused: db pool exhausted".to_string());
@@ -67,6 +111,21 @@ async fn test_api_error_internal_hides_details() {
         .contains("connection refused"));
 }
 
+#[tokio::test]
+async fn test_api_error_with_details() {
+    let error = ApiError::new(ErrorCode::VersionConflict, "Message was changed")
+        .with_details(serde_json::json!({ "current": { "version": 3 } }));
+    let response = error.into_response();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["code"], "VERSION_CONFLICT");
+    assert_eq!(json["error"]["details"]["current"]["version"], 3);
+}
+
 #[tokio::test]
 async fn test_api_error_from_sqlx_error() {
     // Simulate a sqlx error by creating an ApiError::Internal
@@ -76,6 +135,27 @@ async fn test_api_error_from_sqlx_error() {
     assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
 }
 
+#[tokio::test]
+async fn test_api_error_pool_timeout_is_503_with_retry_after() {
+    let response = ApiError::from(sqlx::Error::PoolTimedOut).into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "1");
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["code"], "DATABASE_BUSY");
+}
+
+#[test]
+fn test_api_error_storage_unavailable_is_503_with_retry_after() {
+    let error = ApiError::from(StorageError::Unavailable("circuit open".into()));
+    let response = error.into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "5");
+}
+
 // ============ ApiResponse ============
 
 #[test]
@@ -121,9 +201,26 @@ fn test_error_response_serialization() {
         error: ErrorBody {
             code: "NOT_FOUND".to_string(),
             message: "Dialog not found".to_string(),
+            request_id: None,
+            details: None,
         },
     };
     let json = serde_json::to_value(&response).unwrap();
     assert_eq!(json["error"]["code"], "NOT_FOUND");
     assert_eq!(json["error"]["message"], "Dialog not found");
+    assert!(json["error"].get("request_id").is_none());
+}
+
+#[test]
+fn test_error_response_with_request_id() {
+    let response = ErrorResponse {
+        error: ErrorBody {
+            code: "INTERNAL_ERROR".to_string(),
+            message: "Internal server error".to_string(),
+            request_id: Some("req-42".to_string()),
+            details: None,
+        },
+    };
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["error"]["request_id"], "req-42");
 }
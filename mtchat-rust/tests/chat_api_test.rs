@@ -358,6 +358,83 @@ async fn test_get_dialog_by_object_as_potential() {
     delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
 
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_get_dialog_by_object_auto_creates_from_template() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let object_type = format!("auto_{}", Uuid::new_v4().simple());
+    let object_id = Uuid::new_v4();
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/dialog-templates/{}",
+            base_url, object_type
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "title": "Order {object_id}",
+            "access_scopes": [{ "scope_level2": ["orders:view"] }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let scope_header = |tenant: &str, permission: &str| {
+        let config = json!({ "scope_level0": [tenant], "scope_level2": [permission] });
+        base64::engine::general_purpose::STANDARD.encode(config.to_string().as_bytes())
+    };
+    let get = |user_id: Uuid, scope: String| {
+        let request = client
+            .get(format!(
+                "{}/api/v1/dialogs/by-object/{}/{}?user_id={}",
+                base_url, object_type, object_id, user_id
+            ))
+            .header("X-Scope-Config", scope);
+        async move {
+            let resp = request.send().await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            resp.json::<Value>().await.unwrap()
+        }
+    };
+
+    // Concurrent first accesses of one tenant converge on one dialog
+    let (a, b) = tokio::join!(
+        get(Uuid::new_v4(), scope_header("tenant-a", "orders:view")),
+        get(Uuid::new_v4(), scope_header("tenant-a", "orders:view"))
+    );
+    let dialog_id = a["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(b["data"]["id"], dialog_id.as_str());
+    assert_eq!(a["data"]["title"], format!("Order {}", object_id));
+    assert_eq!(a["data"]["can_join"], true);
+
+    // Another tenant gets its own dialog
+    let other = get(Uuid::new_v4(), scope_header("tenant-b", "orders:view")).await;
+    let other_id = other["data"]["id"].as_str().unwrap().to_string();
+    assert_ne!(other_id, dialog_id);
+
+    // Callers the template would not grant access get nothing
+    let denied = get(Uuid::new_v4(), scope_header("tenant-c", "other")).await;
+    assert!(denied["data"].is_null());
+
+    for id in [&dialog_id, &other_id] {
+        delete_test_dialog(&client, &base_url, &auth_header, id).await;
+    }
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialog-templates/{}",
+            base_url, object_type
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 #[ignore] // Requires running server
 async fn test_get_dialog_by_object_forbidden() {
@@ -1089,11 +1166,9 @@ async fn test_list_dialogs_by_object_search_filter() {
     delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
 
-// ============ Join/Leave Tests ============
-
 #[tokio::test]
 #[ignore] // Requires running server
-async fn test_join_dialog() {
+async fn test_messages_have_increasing_seq() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
@@ -1101,60 +1176,122 @@ async fn test_join_dialog() {
         .unwrap_or_default();
 
     let user_id = Uuid::new_v4();
-    let tenant_uid = Uuid::new_v4();
-    let object_id = Uuid::new_v4();
-
-    // Create dialog without user
     let dialog_id = create_test_dialog(
         &client,
         &base_url,
         &auth_header,
-        object_id,
-        "tender",
+        Uuid::new_v4(),
+        "route",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
         &[],
-        tenant_uid,
-        &["procurement"],
-        &["buyer"],
     )
     .await;
 
-    // Join with matching scope
-    let scope_header = encode_scope_config(tenant_uid, &["procurement"], &["buyer"]);
+    let first = send_test_message(&client, &base_url, &dialog_id, user_id, "one").await;
+    let second = send_test_message(&client, &base_url, &dialog_id, user_id, "two").await;
+
     let resp = client
-        .post(format!(
-            "{}/api/v1/dialogs/{}/join?user_id={}",
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
             base_url, dialog_id, user_id
         ))
-        .header("X-Scope-Config", &scope_header)
         .send()
         .await
         .unwrap();
-
     assert_eq!(resp.status(), StatusCode::OK);
     let body: Value = resp.json().await.unwrap();
-    assert_eq!(body["status"], "joined");
+    let messages = body["data"]["messages"].as_array().unwrap();
+    let seq_of = |id: &str| {
+        messages.iter().find(|m| m["id"] == id).unwrap()["seq"]
+            .as_i64()
+            .unwrap()
+    };
+    assert_eq!(seq_of(&second), seq_of(&first) + 1);
 
-    // Verify now in participating list
-    let list_resp = client
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_jump_to_date_and_message_calendar() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "route",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let sent = send_test_message(&client, &base_url, &dialog_id, user_id, "today").await;
+    let today = chrono::Utc::now().date_naive();
+
+    let resp = client
         .get(format!(
-            "{}/api/v1/dialogs?type=participating&user_id={}",
-            base_url, user_id
+            "{}/api/v1/dialogs/{}/messages/calendar?from={}&to={}&tz=UTC&user_id={}",
+            base_url,
+            dialog_id,
+            today - chrono::Duration::days(7),
+            today,
+            user_id
         ))
         .send()
         .await
         .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let days = body["data"].as_array().unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0]["date"], today.to_string());
+    assert!(days[0]["count"].as_i64().unwrap() >= 1);
 
-    let list_body: Value = list_resp.json().await.unwrap();
-    let dialogs = list_body["data"].as_array().unwrap();
-    assert!(dialogs.iter().any(|d| d["id"] == dialog_id));
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?around_date={}&tz=UTC&user_id={}",
+            base_url, dialog_id, today, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let messages = body["data"]["messages"].as_array().unwrap();
+    assert!(messages.iter().any(|m| m["id"] == sent));
+
+    // Reversed range and unknown timezone are rejected
+    for query in [
+        format!("from={}&to={}", today, today - chrono::Duration::days(1)),
+        format!("from={}&to={}&tz=Mars/Olympus", today, today),
+    ] {
+        let resp = client
+            .get(format!(
+                "{}/api/v1/dialogs/{}/messages/calendar?{}&user_id={}",
+                base_url, dialog_id, query, user_id
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
 
-    // Cleanup
     delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
 
 #[tokio::test]
 #[ignore] // Requires running server
-async fn test_join_dialog_forbidden() {
+async fn test_message_context() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
@@ -1162,45 +1299,83 @@ async fn test_join_dialog_forbidden() {
         .unwrap_or_default();
 
     let user_id = Uuid::new_v4();
-    let dialog_tenant = Uuid::new_v4();
-    let user_tenant = Uuid::new_v4();
-    let object_id = Uuid::new_v4();
-
-    // Create dialog
     let dialog_id = create_test_dialog(
         &client,
         &base_url,
         &auth_header,
-        object_id,
-        "order",
+        Uuid::new_v4(),
+        "route",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
         &[],
-        dialog_tenant,
-        &["sales"],
-        &["seller"],
     )
     .await;
+    let mut sent = Vec::new();
+    for i in 0..7 {
+        let text = format!("message {}", i);
+        sent.push(send_test_message(&client, &base_url, &dialog_id, user_id, &text).await);
+    }
 
-    // Try to join with non-matching scope
-    let scope_header = encode_scope_config(user_tenant, &["support"], &["agent"]);
     let resp = client
-        .post(format!(
-            "{}/api/v1/dialogs/{}/join?user_id={}",
-            base_url, dialog_id, user_id
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages/{}/context?limit=4&user_id={}",
+            base_url, dialog_id, sent[3], user_id
         ))
-        .header("X-Scope-Config", &scope_header)
         .send()
         .await
         .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let data = &body["data"];
+    assert_eq!(data["message_id"], sent[3]);
+    let ids: Vec<&str> = data["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        sent[1..6].iter().map(String::as_str).collect::<Vec<_>>()
+    );
+    assert_eq!(data["has_more_before"], true);
+    assert_eq!(data["has_more_after"], true);
+    assert_eq!(data["before_cursor"], sent[1]);
+    assert_eq!(data["after_cursor"], sent[5]);
 
+    // Non-participants cannot read it, unknown messages are not found
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages/{}/context?user_id={}",
+            base_url,
+            dialog_id,
+            sent[3],
+            Uuid::new_v4()
+        ))
+        .send()
+        .await
+        .unwrap();
     assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages/{}/context?user_id={}",
+            base_url,
+            dialog_id,
+            Uuid::now_v7(),
+            user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 
-    // Cleanup
     delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
 
 #[tokio::test]
 #[ignore] // Requires running server
-async fn test_join_already_participant() {
+async fn test_list_messages_embeds_reply_preview() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
@@ -1208,44 +1383,94 @@ async fn test_join_already_participant() {
         .unwrap_or_default();
 
     let user_id = Uuid::new_v4();
-    let tenant_uid = Uuid::new_v4();
-    let object_id = Uuid::new_v4();
-
-    // Create dialog with user already in it
     let dialog_id = create_test_dialog(
         &client,
         &base_url,
         &auth_header,
-        object_id,
-        "project",
+        Uuid::new_v4(),
+        "route",
         &[user_id],
-        tenant_uid,
-        &["dev"],
-        &["engineer"],
+        Uuid::new_v4(),
+        &[],
+        &[],
     )
     .await;
+    let messages_url = format!(
+        "{}/api/v1/dialogs/{}/messages?user_id={}",
+        base_url, dialog_id, user_id
+    );
 
-    // Try to join again
-    let scope_header = encode_scope_config(tenant_uid, &["dev"], &["engineer"]);
+    let long_content = "a".repeat(300);
+    let original = send_test_message(&client, &base_url, &dialog_id, user_id, &long_content).await;
     let resp = client
-        .post(format!(
-            "{}/api/v1/dialogs/{}/join?user_id={}",
-            base_url, dialog_id, user_id
+        .post(&messages_url)
+        .json(&json!({ "content": "reply", "reply_to": original }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["reply_to"]["id"], original.as_str());
+    let reply = body["data"]["id"].as_str().unwrap().to_string();
+
+    let reply_of = |body: &Value| {
+        body["data"]["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["id"] == reply.as_str())
+            .unwrap()["reply_to"]
+            .clone()
+    };
+    let body: Value = client
+        .get(&messages_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let preview = reply_of(&body);
+    assert_eq!(preview["sender_id"], user_id.to_string());
+    assert_eq!(preview["content"].as_str().unwrap().chars().count(), 200);
+    assert_eq!(preview["deleted"], false);
+
+    // The reference survives deletion of the original
+    client
+        .delete(format!(
+            "{}/api/v1/dialogs/{}/messages/{}?user_id={}",
+            base_url, dialog_id, original, user_id
         ))
-        .header("X-Scope-Config", &scope_header)
         .send()
         .await
         .unwrap();
+    let body: Value = client
+        .get(&messages_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let preview = reply_of(&body);
+    assert_eq!(preview["id"], original.as_str());
+    assert_eq!(preview["deleted"], true);
 
+    // Replies must refer to a message of the same dialog
+    let resp = client
+        .post(&messages_url)
+        .json(&json!({ "content": "reply", "reply_to": Uuid::new_v4() }))
+        .send()
+        .await
+        .unwrap();
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
-    // Cleanup
     delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
 
 #[tokio::test]
 #[ignore] // Requires running server
-async fn test_leave_dialog() {
+async fn test_sync_dialog_returns_changes_after_cursor() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
@@ -1253,68 +1478,1609 @@ async fn test_leave_dialog() {
         .unwrap_or_default();
 
     let user_id = Uuid::new_v4();
-    let tenant_uid = Uuid::new_v4();
-    let object_id = Uuid::new_v4();
-
-    // Create dialog with user
     let dialog_id = create_test_dialog(
         &client,
         &base_url,
         &auth_header,
-        object_id,
-        "meeting",
+        Uuid::new_v4(),
+        "route",
         &[user_id],
-        tenant_uid,
-        &["team"],
-        &["member"],
+        Uuid::new_v4(),
+        &[],
+        &[],
     )
     .await;
 
-    // Leave
+    let sync_url = |since_seq: i64| {
+        format!(
+            "{}/api/v1/dialogs/{}/sync?since_seq={}&user_id={}",
+            base_url, dialog_id, since_seq, user_id
+        )
+    };
+    let body: Value = client
+        .get(sync_url(0))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let cursor = body["data"]["last_seq"].as_i64().unwrap();
+
+    let message_id = send_test_message(&client, &base_url, &dialog_id, user_id, "hi").await;
     let resp = client
-        .post(format!(
-            "{}/api/v1/dialogs/{}/leave?user_id={}",
-            base_url, dialog_id, user_id
+        .delete(format!(
+            "{}/api/v1/dialogs/{}/messages/{}?user_id={}",
+            base_url, dialog_id, message_id, user_id
         ))
         .send()
         .await
         .unwrap();
+    assert!(resp.status().is_success());
 
+    let resp = client.get(sync_url(cursor)).send().await.unwrap();
     assert_eq!(resp.status(), StatusCode::OK);
     let body: Value = resp.json().await.unwrap();
-    assert_eq!(body["status"], "left");
-
-    // Verify no longer in participating list
-    let list_resp = client
+    let events = body["data"]["events"].as_array().unwrap();
+    let types: Vec<&str> = events
+        .iter()
+        .filter(|e| e["message_id"] == message_id.as_str())
+        .map(|e| e["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["message.new", "message.deleted"]);
+    // Deleted since, so no message snapshot
+    let created = events.iter().find(|e| e["type"] == "message.new").unwrap();
+    assert!(created["message"].is_null());
+    assert_eq!(body["data"]["has_more"], false);
+
+    // Outsiders cannot sync
+    let resp = client
         .get(format!(
-            "{}/api/v1/dialogs?type=participating&user_id={}",
-            base_url, user_id
+            "{}/api/v1/dialogs/{}/sync?user_id={}",
+            base_url,
+            dialog_id,
+            Uuid::new_v4()
         ))
         .send()
         .await
         .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
 
-    let list_body: Value = list_resp.json().await.unwrap();
-    let dialogs = list_body["data"].as_array().unwrap();
-    assert!(!dialogs.iter().any(|d| d["id"] == dialog_id));
-
-    // Cleanup
     delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
 
-// ============ User ID Tests ============
+// ============ Join/Leave Tests ============
 
 #[tokio::test]
 #[ignore] // Requires running server
-async fn test_missing_user_id_returns_error() {
+async fn test_list_messages_includes_sender_after_leave() {
     let client = Client::new();
     let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let reader_id = Uuid::new_v4();
+    let sender_id = Uuid::new_v4();
+    let tenant_uid = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[reader_id],
+        tenant_uid,
+        &["procurement"],
+        &["buyer"],
+    )
+    .await;
 
+    let scope_header = encode_scope_config(tenant_uid, &["procurement"], &["buyer"]);
     let resp = client
-        .get(format!("{}/api/v1/dialogs?type=participating", base_url))
+        .post(format!(
+            "{}/api/v1/dialogs/{}/join?user_id={}",
+            base_url, dialog_id, sender_id
+        ))
+        .header("X-Scope-Config", &scope_header)
+        .json(&json!({ "display_name": "Bob", "company": "Acme" }))
         .send()
         .await
         .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
 
-    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let message_id = send_test_message(&client, &base_url, &dialog_id, sender_id, "hi").await;
+    client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/leave?user_id={}",
+            base_url, dialog_id, sender_id
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    let list = |include: &'static str| {
+        client
+            .get(format!(
+                "{}/api/v1/dialogs/{}/messages?user_id={}{}",
+                base_url, dialog_id, reader_id, include
+            ))
+            .send()
+    };
+    let find = |body: &Value| {
+        body["data"]["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["id"] == message_id.as_str())
+            .cloned()
+            .unwrap()
+    };
+
+    // Sender profile is only embedded on request
+    let body: Value = list("").await.unwrap().json().await.unwrap();
+    assert!(find(&body).get("sender").is_none());
+
+    // ...and survives the sender leaving
+    let body: Value = list("&include=sender").await.unwrap().json().await.unwrap();
+    let message = find(&body);
+    assert_eq!(message["sender"]["display_name"], "Bob");
+    assert_eq!(message["sender"]["company"], "Acme");
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_join_dialog() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let tenant_uid = Uuid::new_v4();
+    let object_id = Uuid::new_v4();
+
+    // Create dialog without user
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        object_id,
+        "tender",
+        &[],
+        tenant_uid,
+        &["procurement"],
+        &["buyer"],
+    )
+    .await;
+
+    // Join with matching scope
+    let scope_header = encode_scope_config(tenant_uid, &["procurement"], &["buyer"]);
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/join?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .header("X-Scope-Config", &scope_header)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "joined");
+
+    // Verify now in participating list
+    let list_resp = client
+        .get(format!(
+            "{}/api/v1/dialogs?type=participating&user_id={}",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    let list_body: Value = list_resp.json().await.unwrap();
+    let dialogs = list_body["data"].as_array().unwrap();
+    let dialog = dialogs.iter().find(|d| d["id"] == dialog_id).unwrap();
+    // Participant count follows joins
+    assert_eq!(dialog["participants_count"], 1);
+
+    // Cleanup
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_join_dialog_forbidden() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_tenant = Uuid::new_v4();
+    let user_tenant = Uuid::new_v4();
+    let object_id = Uuid::new_v4();
+
+    // Create dialog
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        object_id,
+        "order",
+        &[],
+        dialog_tenant,
+        &["sales"],
+        &["seller"],
+    )
+    .await;
+
+    // Try to join with non-matching scope
+    let scope_header = encode_scope_config(user_tenant, &["support"], &["agent"]);
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/join?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .header("X-Scope-Config", &scope_header)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Cleanup
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_join_already_participant() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let tenant_uid = Uuid::new_v4();
+    let object_id = Uuid::new_v4();
+
+    // Create dialog with user already in it
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        object_id,
+        "project",
+        &[user_id],
+        tenant_uid,
+        &["dev"],
+        &["engineer"],
+    )
+    .await;
+
+    // Try to join again
+    let scope_header = encode_scope_config(tenant_uid, &["dev"], &["engineer"]);
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/join?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .header("X-Scope-Config", &scope_header)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Cleanup
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_leave_dialog() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let tenant_uid = Uuid::new_v4();
+    let object_id = Uuid::new_v4();
+
+    // Create dialog with user
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        object_id,
+        "meeting",
+        &[user_id],
+        tenant_uid,
+        &["team"],
+        &["member"],
+    )
+    .await;
+
+    // Leave
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/leave?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "left");
+
+    // Verify no longer in participating list
+    let list_resp = client
+        .get(format!(
+            "{}/api/v1/dialogs?type=participating&user_id={}",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    let list_body: Value = list_resp.json().await.unwrap();
+    let dialogs = list_body["data"].as_array().unwrap();
+    assert!(!dialogs.iter().any(|d| d["id"] == dialog_id));
+
+    // Cleanup
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ User ID Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_missing_user_id_returns_unauthorized() {
+    let client = Client::new();
+    let base_url = get_base_url();
+
+    let resp = client
+        .get(format!("{}/api/v1/dialogs?type=participating", base_url))
+        .send()
+        .await
+        .unwrap();
+
+    // No identity: 401 with a challenge, not 400
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers().contains_key("www-authenticate"));
+}
+
+// ============ Request ID Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_request_id_is_propagated() {
+    let client = Client::new();
+    let base_url = get_base_url();
+
+    // Client-supplied ID is echoed and included in the error body
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}?user_id=nobody",
+            base_url,
+            Uuid::new_v4()
+        ))
+        .header("X-Request-Id", "widget-req-1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(resp.headers()["x-request-id"], "widget-req-1");
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["request_id"], "widget-req-1");
+
+    // Invalid IDs are replaced with a generated one
+    let resp = client
+        .get(format!("{}/health", base_url))
+        .header("X-Request-Id", "bad id")
+        .send()
+        .await
+        .unwrap();
+    let id = resp.headers()["x-request-id"].to_str().unwrap();
+    assert!(Uuid::parse_str(id).is_ok());
+}
+
+// ============ Body Limit Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_oversized_message_body_returns_413() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    // Far beyond any valid message, rejected before reaching the handler
+    let blob = "<div>".repeat(2_000_000);
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .json(&json!({ "content": blob }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+    assert!(body["error"]["request_id"].is_string());
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ ETag Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_dialog_etag_returns_304_until_changed() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let url = format!(
+        "{}/api/v1/dialogs/{}?user_id={}",
+        base_url, dialog_id, user_id
+    );
+
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers()["etag"].to_str().unwrap().to_string();
+    assert!(etag.starts_with("W/"));
+
+    let resp = client
+        .get(&url)
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+    // A new message changes the dialog's last message
+    send_test_message(&client, &base_url, &dialog_id, user_id, "Changed").await;
+    let resp = client
+        .get(&url)
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_ne!(resp.headers()["etag"], etag.as_str());
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Message Edit Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_conditional_edit_conflicts_on_stale_version() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let message_id = send_test_message(&client, &base_url, &dialog_id, user_id, "Draft").await;
+    let url = format!(
+        "{}/api/v1/dialogs/{}/messages/{}?user_id={}",
+        base_url, dialog_id, message_id, user_id
+    );
+
+    // First device edits version 1
+    let resp = client
+        .put(&url)
+        .json(&json!({ "content": "From laptop", "version": 1 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["version"], 2);
+
+    // Second device still has version 1
+    let resp = client
+        .put(&url)
+        .header("If-Match", "\"1\"")
+        .json(&json!({ "content": "From phone" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "VERSION_CONFLICT");
+    assert_eq!(
+        body["error"]["details"]["current"]["content"],
+        "From laptop"
+    );
+    assert_eq!(body["error"]["details"]["current"]["version"], 2);
+
+    // Edits without a precondition still apply
+    let resp = client
+        .put(&url)
+        .json(&json!({ "content": "Merged" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Starred Messages Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_star_and_unstar_message() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let message_id = send_test_message(&client, &base_url, &dialog_id, user_id, "Star me").await;
+
+    let star_url = format!(
+        "{}/api/v1/dialogs/{}/messages/{}/star?user_id={}",
+        base_url, dialog_id, message_id, user_id
+    );
+    let resp = client.post(&star_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    // Starred status in message list
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    let starred = body["data"]["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["id"] == message_id.as_str())
+        .unwrap();
+    assert_eq!(starred["is_starred"], true);
+
+    // Listed across dialogs
+    let resp = client
+        .get(format!(
+            "{}/api/v1/starred-messages?user_id={}",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"][0]["id"], message_id.as_str());
+    assert!(body["data"][0]["starred_at"].is_string());
+
+    let resp = client.delete(&star_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/starred-messages?user_id={}",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Reaction Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_reactions_behind_feature_flag() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let message_id = send_test_message(&client, &base_url, &dialog_id, user_id, "React").await;
+
+    let reaction_url = format!(
+        "{}/api/v1/dialogs/{}/messages/{}/reactions/%F0%9F%91%8D?user_id={}",
+        base_url, dialog_id, message_id, user_id
+    );
+
+    // Disabled by default
+    let resp = client.put(&reaction_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "FEATURE_DISABLED");
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/feature-flags/reactions/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "enabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = client.put(&reaction_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages/{}/reactions?user_id={}",
+            base_url, dialog_id, message_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"][0]["emoji"], "👍");
+    assert_eq!(body["data"][0]["count"], 1);
+    assert_eq!(body["data"][0]["reacted"], true);
+
+    let resp = client.delete(&reaction_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Markdown Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_send_markdown_message() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let messages_url = format!(
+        "{}/api/v1/dialogs/{}/messages?user_id={}",
+        base_url, dialog_id, user_id
+    );
+
+    let resp = client
+        .post(&messages_url)
+        .json(&json!({
+            "content": "**Shipped** <script>alert(1)</script>",
+            "content_format": "markdown"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    // The response comes back in the format it was sent in
+    assert_eq!(body["data"]["content_format"], "markdown");
+    assert_eq!(
+        body["data"]["content"],
+        "**Shipped** <script>alert(1)</script>"
+    );
+
+    // Reads default to the rendered, sanitized HTML
+    let resp = client.get(&messages_url).send().await.unwrap();
+    let body: Value = resp.json().await.unwrap();
+    let message = &body["data"]["messages"][0];
+    assert_eq!(message["content_format"], "html");
+    let html = message["content"].as_str().unwrap();
+    assert!(html.contains("<strong>Shipped</strong>"));
+    assert!(!html.contains("<script>"));
+
+    let resp = client
+        .get(format!("{}&content_format=markdown", messages_url))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["messages"][0]["content_format"], "markdown");
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Batch Send Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_send_message_batch_in_order_and_retry_safe() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id, Uuid::new_v4()],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let batch_url = format!(
+        "{}/api/v1/dialogs/{}/messages/batch?user_id={}",
+        base_url, dialog_id, user_id
+    );
+    let batch = json!({
+        "messages": [
+            { "client_id": "local-1", "client_sent_at": "2026-10-17T06:42:10Z", "content": "First" },
+            { "client_id": "local-2", "client_sent_at": "2026-10-17T06:43:55Z", "content": "Second" }
+        ]
+    });
+
+    let resp = client.post(&batch_url).json(&batch).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let sent = body["data"]["messages"].as_array().unwrap();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0]["client_id"], "local-1");
+    assert_eq!(sent[0]["duplicate"], false);
+    assert_eq!(
+        sent[0]["metadata"]["client_sent_at"],
+        "2026-10-17T06:42:10Z"
+    );
+    assert_eq!(sent[1]["content"], "Second");
+
+    // A retry with one more message only sends the new one
+    let mut retry = batch.clone();
+    retry["messages"].as_array_mut().unwrap().push(json!({
+        "client_id": "local-3", "client_sent_at": "2026-10-17T06:45:00Z", "content": "Third"
+    }));
+    let resp = client.post(&batch_url).json(&retry).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let resent = body["data"]["messages"].as_array().unwrap();
+    assert_eq!(resent[0]["duplicate"], true);
+    assert_eq!(resent[0]["id"], sent[0]["id"]);
+    assert_eq!(resent[2]["duplicate"], false);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    let contents: Vec<&str> = body["data"]["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(contents, ["First", "Second", "Third"]);
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_send_message_batch_is_all_or_nothing() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/messages/batch?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .json(&json!({
+            "messages": [
+                { "client_id": "local-1", "client_sent_at": "2026-10-17T06:42:10Z", "content": "Fine" },
+                { "client_id": "local-2", "client_sent_at": "2026-10-17T06:43:55Z", "content": "Reply", "reply_to": Uuid::new_v4() }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("messages[1]:"));
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["data"]["messages"].as_array().unwrap().is_empty());
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Message Actions Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_click_message_action() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let outsider_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .json(&json!({
+            "content": "<p>Approve quote #7?</p>",
+            "content_blocks": [{ "type": "actions", "buttons": [
+                { "id": "approve", "label": "Approve", "style": "primary" },
+                { "id": "open", "label": "Open", "url": "https://tms.example.com/q/7" }
+            ]}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    let message_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let action_url = |action_id: &str, user: Uuid| {
+        format!(
+            "{}/api/v1/messages/{}/actions/{}?user_id={}",
+            base_url, message_id, action_id, user
+        )
+    };
+
+    let resp = client
+        .post(action_url("approve", outsider_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Link buttons and unknown IDs have no callback
+    for action_id in ["open", "decline"] {
+        let resp = client
+            .post(action_url(action_id, user_id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "ACTION_NOT_FOUND");
+    }
+
+    let resp = client
+        .post(action_url("approve", user_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["message_type"], "system");
+    let content: Value = serde_json::from_str(body["data"]["content"].as_str().unwrap()).unwrap();
+    assert_eq!(content["event"], "action_clicked");
+    assert_eq!(content["action_id"], "approve");
+    assert_eq!(content["message_id"], message_id.as_str());
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Dialog Folders Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_dialog_folders() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let object_type = format!("folder_test_{}", Uuid::new_v4().simple());
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        &object_type,
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    let folders_url = format!("{}/api/v1/folders?user_id={}", base_url, user_id);
+    let resp = client
+        .post(&folders_url)
+        .json(&json!({ "name": "Urgent tenders", "object_type": object_type }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let folder_id = body["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["data"]["unread_only"], false);
+
+    // Duplicate name is rejected
+    let resp = client
+        .post(&folders_url)
+        .json(&json!({ "name": "Urgent tenders" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Grouped listing
+    let resp = client
+        .get(format!(
+            "{}/api/v1/folders/dialogs?user_id={}",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"][0]["folder"]["id"], folder_id.as_str());
+    assert_eq!(body["data"][0]["dialogs"][0]["id"], dialog_id.as_str());
+
+    // Unread-only folder is empty without messages
+    let folder_url = format!(
+        "{}/api/v1/folders/{}?user_id={}",
+        base_url, folder_id, user_id
+    );
+    let resp = client
+        .put(&folder_url)
+        .json(&json!({ "name": "Urgent tenders", "object_type": object_type, "unread_only": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = client
+        .get(format!(
+            "{}/api/v1/folders/dialogs?user_id={}",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["data"][0]["dialogs"].as_array().unwrap().is_empty());
+
+    let resp = client.delete(&folder_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let resp = client.delete(&folder_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Snooze Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_snooze_dialog() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "route",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    let snooze_url = format!(
+        "{}/api/v1/dialogs/{}/snooze?user_id={}",
+        base_url, dialog_id, user_id
+    );
+    let list_url = format!(
+        "{}/api/v1/dialogs?type=participating&user_id={}",
+        base_url, user_id
+    );
+
+    let resp = client
+        .post(&snooze_url)
+        .json(&json!({ "duration_secs": 0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = client
+        .post(&snooze_url)
+        .json(&json!({ "duration_secs": 3600 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "snoozed");
+    let snoozed_until = body["snoozed_until"].clone();
+    assert!(snoozed_until.is_string());
+
+    let body: Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let dialog = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["id"] == dialog_id)
+        .unwrap();
+    assert_eq!(dialog["snoozed_until"], snoozed_until);
+
+    let resp = client.delete(&snooze_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let dialog = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["id"] == dialog_id)
+        .unwrap();
+    assert!(dialog["snoozed_until"].is_null());
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Dialog Notes Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_dialog_notes_versioning() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "route",
+        &[user_id],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    let notes_url = format!(
+        "{}/api/v1/dialogs/{}/notes?user_id={}",
+        base_url, dialog_id, user_id
+    );
+
+    let body: Value = client
+        .get(&notes_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["version"], 0);
+    assert_eq!(body["data"]["content"], "");
+
+    let resp = client
+        .put(&notes_url)
+        .json(&json!({ "content": "<p>Delivery by Friday</p>", "version": 0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["version"], 1);
+
+    // An edit based on the old version conflicts
+    let resp = client
+        .put(&notes_url)
+        .header("If-Match", "\"0\"")
+        .json(&json!({ "content": "<p>Stale</p>" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "VERSION_CONFLICT");
+
+    let resp = client
+        .put(&notes_url)
+        .json(&json!({ "content": "<p>Delivery by Monday</p>", "version": 1 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/notes/history?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let history = body["data"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["version"], 1);
+    assert_eq!(history[0]["content"], "<p>Delivery by Friday</p>");
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Unread / Mention Filters Tests ============
+
+/// Whether the dialog appears in the user's list with `{filter}=true`
+async fn dialog_listed(
+    client: &Client,
+    base_url: &str,
+    user_id: Uuid,
+    filter: &str,
+    dialog_id: &str,
+) -> bool {
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs?user_id={}&{}=true",
+            base_url, user_id, filter
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|d| d["id"] == dialog_id)
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_list_dialogs_unread_and_mention_filters() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let sender = Uuid::new_v4();
+    let reader = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[sender, reader],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+
+    let list = |filter| dialog_listed(&client, &base_url, reader, filter, &dialog_id);
+
+    send_test_message(&client, &base_url, &dialog_id, sender, "No mention").await;
+    assert!(list("unread").await);
+    assert!(!list("mentions").await);
+
+    let content = format!(
+        r#"<p><span data-type="mention" data-id="{}">@Reader</span> please check</p>"#,
+        reader
+    );
+    let message_id = send_test_message(&client, &base_url, &dialog_id, sender, &content).await;
+    assert!(list("mentions").await);
+
+    // Reading the dialog clears both filters
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/read?user_id={}",
+            base_url, dialog_id, reader
+        ))
+        .json(&json!({ "last_read_message_id": message_id }))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+    assert!(!list("unread").await);
+    assert!(!list("mentions").await);
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
+}
+
+// ============ Bulk Dialog Actions Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_bulk_dialog_actions() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let mut dialog_ids = Vec::new();
+    for _ in 0..2 {
+        dialog_ids.push(
+            create_test_dialog(
+                &client,
+                &base_url,
+                &auth_header,
+                Uuid::new_v4(),
+                "tender",
+                &[user_id],
+                Uuid::new_v4(),
+                &[],
+                &[],
+            )
+            .await,
+        );
+    }
+    let foreign = Uuid::new_v4().to_string();
+
+    let url = format!(
+        "{}/api/v1/dialogs/bulk-actions?user_id={}",
+        base_url, user_id
+    );
+    let resp = client
+        .post(&url)
+        .json(&json!({
+            "dialog_ids": [dialog_ids[0], dialog_ids[1], foreign],
+            "action": "archive"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["updated"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"]["skipped"][0], foreign.as_str());
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs?user_id={}&archived=true",
+            base_url, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    // Unknown action is rejected
+    let resp = client
+        .post(&url)
+        .json(&json!({ "dialog_ids": [dialog_ids[0]], "action": "explode" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_client_error());
+
+    for dialog_id in &dialog_ids {
+        delete_test_dialog(&client, &base_url, &auth_header, dialog_id).await;
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_batch_get_participants() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let other_id = Uuid::new_v4();
+    let tenant = Uuid::new_v4();
+    let mut dialog_ids = Vec::new();
+    // Participating, visible through the scope, and of another tenant
+    for (participants, dialog_tenant) in [
+        (vec![user_id, other_id], Uuid::new_v4()),
+        (vec![other_id], tenant),
+        (vec![other_id], Uuid::new_v4()),
+    ] {
+        dialog_ids.push(
+            create_test_dialog(
+                &client,
+                &base_url,
+                &auth_header,
+                Uuid::new_v4(),
+                "tender",
+                &participants,
+                dialog_tenant,
+                &[],
+                &[],
+            )
+            .await,
+        );
+    }
+
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/participants:batchGet?user_id={}",
+            base_url, user_id
+        ))
+        .header("X-Scope-Config", encode_scope_config(tenant, &[], &[]))
+        .json(&json!({ "dialog_ids": dialog_ids }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let rosters = body["data"]["dialogs"].as_array().unwrap();
+    assert_eq!(rosters.len(), 2);
+    let roster_of = |id: &str| {
+        rosters.iter().find(|r| r["dialog_id"] == id).unwrap()["participants"]
+            .as_array()
+            .unwrap()
+            .len()
+    };
+    assert_eq!(roster_of(&dialog_ids[0]), 2);
+    assert_eq!(roster_of(&dialog_ids[1]), 1);
+    assert_eq!(body["data"]["skipped"], json!([dialog_ids[2]]));
+
+    // Empty requests are rejected
+    let resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/participants:batchGet?user_id={}",
+            base_url, user_id
+        ))
+        .json(&json!({ "dialog_ids": [] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    for dialog_id in &dialog_ids {
+        delete_test_dialog(&client, &base_url, &auth_header, dialog_id).await;
+    }
+}
+
+// ============ Message Priority Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_urgent_messages_are_flagged_and_filterable() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let user_id = Uuid::new_v4();
+    let dialog_id = create_test_dialog(
+        &client,
+        &base_url,
+        &auth_header,
+        Uuid::new_v4(),
+        "tender",
+        &[user_id, Uuid::new_v4()],
+        Uuid::new_v4(),
+        &[],
+        &[],
+    )
+    .await;
+    let messages_url = format!(
+        "{}/api/v1/dialogs/{}/messages?user_id={}",
+        base_url, dialog_id, user_id
+    );
+
+    send_test_message(&client, &base_url, &dialog_id, user_id, "Regular").await;
+    let resp = client
+        .post(&messages_url)
+        .json(&json!({ "content": "Deadline moved", "priority": "urgent" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["priority"], "urgent");
+    send_test_message(&client, &base_url, &dialog_id, user_id, "Regular again").await;
+
+    let resp = client
+        .get(format!("{}&priority=urgent", messages_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let messages = body["data"]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["content"], "Deadline moved");
+
+    // The filter only pages backwards
+    let resp = client
+        .get(format!(
+            "{}&priority=urgent&after={}",
+            messages_url,
+            messages[0]["id"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    delete_test_dialog(&client, &base_url, &auth_header, &dialog_id).await;
 }
@@ -3,9 +3,14 @@
 //! Tests the actual domain model constructors, conversions, and business logic
 //! using the library crate exports.
 
+use chrono::{TimeZone, Utc};
 use multitenancy_chat_api::domain::{
-    attachment_limits, Attachment, AttachmentType, Dialog, DialogAccessScope, DialogParticipant,
-    JoinedAs, Message, MessageType, ParticipantProfile,
+    attachment_limits, avatar, ActionButton, Attachment, AttachmentResponse, AttachmentType,
+    ButtonStyle, ContentBlock, ContentFormat, Dialog, DialogAccessScope, DialogBan, DialogEvent,
+    DialogNotes, DialogParticipant, DialogSla, DialogTemplate, JoinedAs, Message,
+    MessageAttribution, MessagePriority, MessageType, ParticipantInvite, ParticipantProfile,
+    ReplyPreview, ScopeTemplate, SlaSource, SlaStatus, SlaTarget, UnansweredMessage,
+    REPLY_PREVIEW_CHARS,
 };
 use uuid::Uuid;
 
@@ -41,12 +46,45 @@ fn test_dialog_new_preserves_fields() {
     assert_eq!(dialog.created_by.as_deref(), Some(creator));
 }
 
+#[test]
+fn test_new_records_start_unchanged() {
+    let dialog = Dialog::new("order-1", "order", None, None, None, None);
+    assert_eq!(dialog.updated_at, dialog.created_at);
+
+    let participant = DialogParticipant::new(dialog.id, "user-1", JoinedAs::Creator);
+    assert_eq!(participant.updated_at, participant.joined_at);
+
+    let message = Message::new(dialog.id, "user-1", "Hi");
+    assert_eq!(message.updated_at, message.sent_at);
+
+    let json = serde_json::to_value(&message).unwrap();
+    assert!(json["updated_at"].is_string());
+}
+
 #[test]
 fn test_dialog_new_optional_fields() {
     let dialog = Dialog::new("tender-1", "tender", None, None, None, None);
     assert!(dialog.title.is_none());
     assert!(dialog.object_url.is_none());
     assert!(dialog.created_by.is_none());
+    assert!(dialog.deleted_at.is_none());
+    assert_eq!(dialog.participants_count, 0);
+    // Returned by the API response types, not as part of the dialog itself
+    let json = serde_json::to_value(&dialog).unwrap();
+    assert!(json.get("participants_count").is_none());
+}
+
+#[test]
+fn test_dialog_with_locale() {
+    let dialog = Dialog::new("tender-1", "tender", None, None, None, None);
+    assert!(dialog.locale_context().is_empty());
+    let json = serde_json::to_value(&dialog).unwrap();
+    assert!(json.get("timezone").is_none());
+
+    let dialog = dialog.with_locale(Some("Europe/Moscow".into()), Some("ru-RU".into()));
+    let ctx = dialog.locale_context();
+    assert_eq!(ctx.timezone.as_deref(), Some("Europe/Moscow"));
+    assert_eq!(ctx.locale.as_deref(), Some("ru-RU"));
 }
 
 #[test]
@@ -70,6 +108,48 @@ fn test_dialog_accepts_string_object_type() {
     assert_eq!(dialog.object_type, "delivery_note");
 }
 
+// ============ DialogNotes ============
+
+#[test]
+fn test_dialog_notes_empty() {
+    let dialog_id = Uuid::new_v4();
+    let notes = DialogNotes::empty(dialog_id);
+    assert_eq!(notes.dialog_id, dialog_id);
+    assert_eq!(notes.version, 0);
+    assert!(notes.content.is_empty());
+    assert!(notes.updated_by.is_none());
+}
+
+// ============ DialogTemplate ============
+
+#[test]
+fn test_dialog_template_caller_tenant() {
+    let mut template = DialogTemplate::new(
+        "tender",
+        vec![ScopeTemplate {
+            scope_level0: vec!["ignored".into()],
+            scope_level2: vec!["tender:view".into()],
+            ..Default::default()
+        }],
+    );
+    template.title = Some("Tender {object_id}".into());
+    let tenants = vec!["tenant-b".to_string(), "tenant-a".to_string()];
+
+    assert_eq!(template.title_for("T-1").as_deref(), Some("Tender T-1"));
+    let scopes = template.scopes_for(&tenants);
+    assert_eq!(scopes[0].scope_level0, tenants);
+    assert_eq!(scopes[0].scope_level2, ["tender:view"]);
+    // Same tenants in any order map to the same dialog
+    assert_eq!(
+        template.creation_key(&tenants),
+        template.creation_key(&["tenant-a".to_string(), "tenant-b".to_string()])
+    );
+
+    template.caller_tenant = false;
+    assert_eq!(template.scopes_for(&tenants)[0].scope_level0, ["ignored"]);
+    assert_eq!(template.creation_key(&tenants), "");
+}
+
 // ============ JoinedAs ============
 
 #[test]
@@ -77,6 +157,7 @@ fn test_joined_as_as_str() {
     assert_eq!(JoinedAs::Creator.as_str(), "creator");
     assert_eq!(JoinedAs::Participant.as_str(), "participant");
     assert_eq!(JoinedAs::Joined.as_str(), "joined");
+    assert_eq!(JoinedAs::Observer.as_str(), "observer");
 }
 
 #[test]
@@ -94,6 +175,7 @@ fn test_joined_as_from_str() {
     assert_eq!(JoinedAs::from("creator"), JoinedAs::Creator);
     assert_eq!(JoinedAs::from("participant"), JoinedAs::Participant);
     assert_eq!(JoinedAs::from("joined"), JoinedAs::Joined);
+    assert_eq!(JoinedAs::from("observer"), JoinedAs::Observer);
 }
 
 #[test]
@@ -105,13 +187,26 @@ fn test_joined_as_unknown_defaults_to_participant() {
 
 #[test]
 fn test_joined_as_roundtrip() {
-    for variant in [JoinedAs::Creator, JoinedAs::Participant, JoinedAs::Joined] {
+    for variant in [
+        JoinedAs::Creator,
+        JoinedAs::Participant,
+        JoinedAs::Joined,
+        JoinedAs::Observer,
+    ] {
         let s = variant.as_str();
         let recovered = JoinedAs::from(s);
         assert_eq!(recovered, variant);
     }
 }
 
+#[test]
+fn test_joined_as_is_observer() {
+    assert!(JoinedAs::Observer.is_observer());
+    assert!(!JoinedAs::Creator.is_observer());
+    assert!(!JoinedAs::Participant.is_observer());
+    assert!(!JoinedAs::Joined.is_observer());
+}
+
 // ============ DialogParticipant ============
 
 #[test]
@@ -132,6 +227,33 @@ fn test_participant_new_defaults() {
     assert!(p.phone.is_none());
     assert!(!p.is_archived);
     assert!(!p.is_pinned);
+    assert!(p.snoozed_until.is_none());
+    assert!(p.pending_removal_at.is_none());
+}
+
+#[test]
+fn test_participant_pending_removal() {
+    let mut p = DialogParticipant::new(Uuid::new_v4(), "user-1", JoinedAs::Participant);
+    assert!(!p.is_pending_removal());
+
+    p.pending_removal_at = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+    assert!(p.is_pending_removal());
+}
+
+#[test]
+fn test_participant_active_snooze() {
+    let now = chrono::Utc::now();
+    let mut p = DialogParticipant::new(Uuid::new_v4(), "user-1", JoinedAs::Creator);
+    assert!(p.active_snooze(now).is_none());
+
+    let until = now + chrono::Duration::hours(1);
+    p.snoozed_until = Some(until);
+    assert_eq!(p.active_snooze(now), Some(until));
+
+    // Expired snoozes are ignored
+    assert!(p
+        .active_snooze(until + chrono::Duration::seconds(1))
+        .is_none());
 }
 
 #[test]
@@ -141,6 +263,7 @@ fn test_participant_with_profile() {
     let profile = ParticipantProfile {
         display_name: "John Doe".to_string(),
         company: Some("Acme Inc".to_string()),
+        company_uid: Some("acme".to_string()),
         email: Some("john@acme.com".to_string()),
         phone: Some("+1234567890".to_string()),
     };
@@ -148,6 +271,7 @@ fn test_participant_with_profile() {
 
     assert_eq!(p.display_name.as_deref(), Some("John Doe"));
     assert_eq!(p.company.as_deref(), Some("Acme Inc"));
+    assert_eq!(p.company_uid.as_deref(), Some("acme"));
     assert_eq!(p.email.as_deref(), Some("john@acme.com"));
     assert_eq!(p.phone.as_deref(), Some("+1234567890"));
     assert_eq!(p.joined_as, JoinedAs::Joined);
@@ -158,6 +282,7 @@ fn test_participant_with_profile_partial() {
     let profile = ParticipantProfile {
         display_name: "Anonymous".to_string(),
         company: None,
+        company_uid: None,
         email: None,
         phone: None,
     };
@@ -178,6 +303,65 @@ fn test_participant_joined_as() {
     assert_eq!(p2.joined_as, JoinedAs::Joined);
 }
 
+#[test]
+fn test_message_attribution_default_and_serde() {
+    assert_eq!(MessageAttribution::default(), MessageAttribution::Keep);
+    assert_eq!(
+        serde_json::from_str::<MessageAttribution>("\"reassign\"").unwrap(),
+        MessageAttribution::Reassign
+    );
+    assert_eq!(
+        serde_json::to_string(&MessageAttribution::Keep).unwrap(),
+        "\"keep\""
+    );
+}
+
+// ============ ParticipantInvite ============
+
+#[test]
+fn test_participant_invite_profile_uses_invite_email() {
+    let dialog_id = Uuid::new_v4();
+    let profile = ParticipantProfile {
+        display_name: "Ann Smith".into(),
+        company: Some("Supplier Ltd".into()),
+        company_uid: Some("sup-1".into()),
+        email: Some("other@example.com".into()),
+        phone: None,
+    };
+    let invite = ParticipantInvite::new(
+        dialog_id,
+        "  Ann@Supplier.Example ",
+        JoinedAs::Observer,
+        profile,
+    );
+    assert_eq!(invite.dialog_id, dialog_id);
+    assert_eq!(invite.email, "ann@supplier.example");
+    assert_eq!(invite.joined_as, JoinedAs::Observer);
+
+    let profile = invite.profile();
+    assert_eq!(profile.display_name, "Ann Smith");
+    assert_eq!(profile.company_uid.as_deref(), Some("sup-1"));
+    assert_eq!(profile.email.as_deref(), Some("ann@supplier.example"));
+}
+
+// ============ DialogBan ============
+
+#[test]
+fn test_dialog_ban_expiry() {
+    let now = Utc::now();
+    let ban = DialogBan::new(Uuid::new_v4(), "user-1", None, None);
+    assert!(ban.is_active(now));
+
+    let ban = DialogBan::new(
+        ban.dialog_id,
+        "user-1",
+        Some("Spam".into()),
+        Some(now + chrono::Duration::hours(1)),
+    );
+    assert!(ban.is_active(now));
+    assert!(!ban.is_active(now + chrono::Duration::hours(1)));
+}
+
 // ============ MessageType ============
 
 #[test]
@@ -213,6 +397,33 @@ fn test_message_type_roundtrip() {
     }
 }
 
+// ============ MessagePriority ============
+
+#[test]
+fn test_message_priority_default_and_serde() {
+    assert_eq!(MessagePriority::default(), MessagePriority::Normal);
+    assert_eq!(
+        serde_json::to_value(MessagePriority::Urgent).unwrap(),
+        "urgent"
+    );
+    let msg = Message::new(Uuid::new_v4(), "user-1", "Hi");
+    assert_eq!(msg.priority, MessagePriority::Normal);
+    assert!(msg
+        .with_priority(MessagePriority::Urgent)
+        .priority
+        .is_urgent());
+}
+
+#[test]
+fn test_urgent_priority_roles() {
+    let urgent = MessagePriority::Urgent;
+    assert!(urgent.allowed_for(&JoinedAs::Creator));
+    assert!(urgent.allowed_for(&JoinedAs::Participant));
+    assert!(!urgent.allowed_for(&JoinedAs::Joined));
+    assert!(!urgent.allowed_for(&JoinedAs::Observer));
+    assert!(MessagePriority::Normal.allowed_for(&JoinedAs::Joined));
+}
+
 // ============ Message ============
 
 #[test]
@@ -228,6 +439,8 @@ fn test_message_new_user_message() {
     assert_eq!(msg.message_type, MessageType::User);
     assert!(msg.reply_to_id.is_none());
     assert!(msg.last_edited_at.is_none());
+    assert_eq!(msg.seq, 0); // assigned by the database on insert
+    assert_eq!(msg.version, 1);
     assert!(!msg.is_edited());
     assert!(!msg.is_system());
 }
@@ -254,6 +467,113 @@ fn test_message_with_reply() {
     assert_eq!(msg.content, "Reply");
 }
 
+#[test]
+fn test_reply_preview_truncates_content() {
+    let content = "ж".repeat(REPLY_PREVIEW_CHARS + 50);
+    let original = Message::new(Uuid::new_v4(), "user-original", content);
+
+    let preview = ReplyPreview::of(&original);
+    assert_eq!(preview.id, original.id);
+    assert_eq!(preview.sender_id.as_deref(), Some("user-original"));
+    assert_eq!(preview.content.chars().count(), REPLY_PREVIEW_CHARS);
+    assert!(!preview.deleted);
+
+    let deleted = ReplyPreview::deleted(original.id);
+    assert!(deleted.deleted);
+    assert!(deleted.content.is_empty());
+    assert!(deleted.sender_id.is_none());
+}
+
+#[test]
+fn test_message_action_button_lookup() {
+    let button = |id: &str, url: Option<&str>| ActionButton {
+        id: id.into(),
+        label: id.to_uppercase(),
+        url: url.map(Into::into),
+        style: ButtonStyle::Default,
+    };
+    let msg =
+        Message::new(Uuid::new_v4(), "integration", "Approve?").with_content_blocks(Some(vec![
+            ContentBlock::Text {
+                text: "Quote #7".into(),
+            },
+            ContentBlock::Actions {
+                buttons: vec![
+                    button("approve", None),
+                    button("open", Some("https://tms.example.com/q/7")),
+                ],
+            },
+        ]));
+
+    assert_eq!(msg.action_button("approve").unwrap().label, "APPROVE");
+    // Link buttons have no callback
+    assert!(msg.action_button("open").is_none());
+    assert!(msg.action_button("decline").is_none());
+    assert!(Message::new(Uuid::new_v4(), "u", "Hi")
+        .action_button("approve")
+        .is_none());
+}
+
+#[test]
+fn test_message_in_markdown_format() {
+    let msg = Message::new(
+        Uuid::new_v4(),
+        "integration",
+        "<p><strong>Done</strong></p>",
+    )
+    .with_markdown_source(Some("**Done**".into()));
+
+    let html = msg.clone().in_format(ContentFormat::Html);
+    assert_eq!(html.content, "<p><strong>Done</strong></p>");
+    assert_eq!(html.content_format, ContentFormat::Html);
+
+    let markdown = msg.in_format(ContentFormat::Markdown);
+    assert_eq!(markdown.content, "**Done**");
+    let json = serde_json::to_value(&markdown).unwrap();
+    assert_eq!(json["content_format"], "markdown");
+    assert!(json.get("content_markdown").is_none());
+
+    // Messages sent as HTML have no Markdown source to return
+    let plain = Message::new(Uuid::new_v4(), "u", "<p>Hi</p>").in_format(ContentFormat::Markdown);
+    assert_eq!(plain.content, "<p>Hi</p>");
+    assert_eq!(plain.content_format, ContentFormat::Html);
+}
+
+#[test]
+fn test_message_external_id() {
+    // Without configuration the external ID is the UUID primary key
+    let msg = Message::new(Uuid::new_v4(), "u", "Hi");
+    assert_eq!(msg.external_id, msg.id.to_string());
+    let json = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json["external_id"], msg.id.to_string());
+
+    // Backdating changes the ID, and the external ID follows it
+    let sent_at = Utc.with_ymd_and_hms(2024, 3, 1, 10, 15, 0).unwrap();
+    let imported = Message::new(Uuid::new_v4(), "u", "Old").with_sent_at(sent_at);
+    assert_eq!(imported.external_id, imported.id.to_string());
+
+    let imported = imported.with_external_id(Some("legacy-42".into()));
+    assert_eq!(imported.external_id, "legacy-42");
+}
+
+#[test]
+fn test_message_client_origin() {
+    let composed_at = Utc.with_ymd_and_hms(2026, 10, 1, 8, 30, 0).unwrap();
+    let msg = Message::new(Uuid::new_v4(), "u", "Sent from the plane")
+        .with_metadata(Some(serde_json::json!({ "quote_line": 7 })))
+        .with_client_origin("local-1", composed_at);
+    assert_eq!(msg.client_id(), Some("local-1"));
+    let metadata = msg.metadata.unwrap();
+    assert_eq!(metadata["quote_line"], 7);
+    assert_eq!(metadata["client_id"], "local-1");
+    assert_eq!(metadata["client_sent_at"], "2026-10-01T08:30:00Z");
+
+    // Without metadata of its own the message gets just the origin
+    let msg = Message::new(Uuid::new_v4(), "u", "Hi").with_client_origin("local-2", composed_at);
+    assert_eq!(msg.metadata.unwrap().as_object().unwrap().len(), 2);
+    assert_eq!(Message::new(Uuid::new_v4(), "u", "Hi").client_id(), None);
+}
+
 #[test]
 fn test_message_accepts_string_content() {
     let msg = Message::new(Uuid::new_v4(), "user-str", String::from("owned string"));
@@ -272,6 +592,63 @@ fn test_message_ids_are_unique_and_ordered() {
     assert!(m2.id > m1.id, "UUIDv7 messages should be time-ordered");
 }
 
+#[test]
+fn test_message_with_sent_at_orders_by_original_time() {
+    let dialog_id = Uuid::new_v4();
+    let sent_at = chrono::Utc::now() - chrono::Duration::days(365);
+    let imported = Message::new(dialog_id, "user-1", "old").with_sent_at(sent_at);
+    let live = Message::new(dialog_id, "user-1", "new");
+
+    assert_eq!(imported.sent_at, sent_at);
+    assert_eq!(imported.id.get_version_num(), 7);
+    assert!(imported.id < live.id);
+
+    let earlier = Message::new(dialog_id, "user-2", "older")
+        .with_sent_at(sent_at - chrono::Duration::days(1));
+    assert!(earlier.id < imported.id);
+}
+
+#[test]
+fn test_message_sender_profile_snapshot() {
+    let mut msg = Message::new(Uuid::new_v4(), "user-1", "Hi");
+    // Not snapshotted yet (filled by the database on insert)
+    assert!(msg.sender_profile().is_none());
+
+    msg.sender_display_name = Some("Ivan".into());
+    let profile = msg.sender_profile().unwrap();
+    assert_eq!(profile.display_name.as_deref(), Some("Ivan"));
+    assert!(profile.company.is_none());
+
+    // The snapshot is not part of the plain message payload
+    let json = serde_json::to_value(&msg).unwrap();
+    assert!(json.get("sender_display_name").is_none());
+
+    let mut system = Message::system(Uuid::new_v4(), "{}");
+    system.sender_display_name = Some("Ivan".into());
+    assert!(system.sender_profile().is_none());
+}
+
+// ============ DialogEvent ============
+
+#[test]
+fn test_dialog_event_has_message() {
+    let event = |event_type: &str| DialogEvent {
+        seq: 1,
+        event_type: event_type.to_string(),
+        message_id: Some(Uuid::new_v4()),
+        user_id: Some("user-1".into()),
+        created_at: chrono::Utc::now(),
+    };
+
+    assert!(event("message.new").has_message());
+    assert!(event("message.edited").has_message());
+    assert!(!event("message.deleted").has_message());
+    assert!(!event("message.read").has_message());
+
+    let json = serde_json::to_value(event("message.read")).unwrap();
+    assert_eq!(json["type"], "message.read");
+}
+
 // ============ Attachment ============
 
 #[test]
@@ -320,6 +697,21 @@ fn test_attachment_is_image() {
     assert!(svg.is_image());
 }
 
+#[test]
+fn test_attachment_response_without_url_is_unavailable() {
+    let att = Attachment::new(Uuid::new_v4(), "doc.pdf", "application/pdf", 100, "k");
+
+    let json = serde_json::to_value(AttachmentResponse::from_attachment(&att, None, None)).unwrap();
+    assert!(json["url"].is_null());
+    assert_eq!(json["url_status"], "unavailable");
+
+    let response = AttachmentResponse::from_attachment(&att, Some("https://s3/k".into()), None);
+    assert!(!response.is_url_unavailable());
+    let json = serde_json::to_value(response).unwrap();
+    assert_eq!(json["url"], "https://s3/k");
+    assert!(json.get("url_status").is_none());
+}
+
 #[test]
 fn test_attachment_is_pdf() {
     let pdf = Attachment::new(Uuid::new_v4(), "doc.pdf", "application/pdf", 100, "k");
@@ -384,6 +776,51 @@ fn test_attachment_limits_empty_type_allowed() {
     assert!(attachment_limits::is_allowed_type(""));
 }
 
+// ============ Avatars ============
+
+#[test]
+fn test_avatar_type_and_size_limits() {
+    assert!(avatar::is_avatar_type("image/png"));
+    assert!(avatar::is_avatar_type("image/webp"));
+    assert!(!avatar::is_avatar_type("image/gif"));
+    assert!(!avatar::is_avatar_type("application/pdf"));
+
+    assert!(avatar::is_valid_avatar_size(1));
+    assert!(avatar::is_valid_avatar_size(avatar::MAX_AVATAR_SIZE));
+    assert!(!avatar::is_valid_avatar_size(0));
+    assert!(!avatar::is_valid_avatar_size(avatar::MAX_AVATAR_SIZE + 1));
+}
+
+#[test]
+fn test_avatar_key_prefix_encodes_user_id() {
+    let dialog_id = Uuid::nil();
+    assert_eq!(
+        avatar::avatar_key_prefix(dialog_id, "user/1"),
+        format!("avatars/{}/user%2F1/", dialog_id)
+    );
+}
+
+#[test]
+fn test_avatar_variant_keys() {
+    assert_eq!(
+        avatar::avatar_variant_key("avatars/d/u/0192.jpg", 64),
+        "avatars/d/u/0192_64.png"
+    );
+    // Dots in directories are not extensions
+    assert_eq!(
+        avatar::avatar_variant_key("avatars/d/u.v/0192", 256),
+        "avatars/d/u.v/0192_256.png"
+    );
+    assert_eq!(
+        avatar::avatar_object_keys("avatars/d/u/0192.webp"),
+        vec![
+            "avatars/d/u/0192.webp",
+            "avatars/d/u/0192_64.png",
+            "avatars/d/u/0192_256.png",
+        ]
+    );
+}
+
 // ============ DialogAccessScope ============
 
 #[test]
@@ -528,3 +965,34 @@ fn test_scope_mixed_empty_levels() {
     assert!(scope2.matches(&["any".into()], &["dept_a".into()], &[]));
     assert!(!scope2.matches(&["any".into()], &["dept_b".into()], &[]));
 }
+
+// ============ SLA ============
+
+#[test]
+fn test_dialog_sla_status_and_serde() {
+    let target = SlaTarget::new(4 * 3600, None, SlaSource::ObjectType);
+    let now = Utc::now();
+
+    let answered = DialogSla::new(target, None, now);
+    assert_eq!(answered.status, SlaStatus::Answered);
+    let json = serde_json::to_value(&answered).unwrap();
+    assert_eq!(json["status"], "answered");
+    assert_eq!(json["source"], "object_type");
+    assert_eq!(json["response_secs"], 14400);
+    assert!(json.get("pending_since").is_none());
+
+    let message = UnansweredMessage {
+        message_id: Uuid::now_v7(),
+        sender_id: "buyer-1".into(),
+        sent_at: now - chrono::Duration::hours(5),
+    };
+    let breached = DialogSla::new(target, Some(&message), now);
+    assert_eq!(breached.status, SlaStatus::Breached);
+    assert_eq!(
+        breached.due_at,
+        Some(message.sent_at + chrono::Duration::hours(4))
+    );
+    let json = serde_json::to_value(&breached).unwrap();
+    assert_eq!(json["status"], "breached");
+    assert_eq!(json["pending_message_id"], message.message_id.to_string());
+}
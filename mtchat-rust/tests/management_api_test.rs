@@ -46,6 +46,10 @@ async fn test_admin_auth_rejects_missing_header() {
         .expect("Request failed");
 
     assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        r#"Bearer realm="mtchat-management""#
+    );
     let body: Value = resp.json().await.unwrap();
     assert_eq!(body["error"]["code"], "UNAUTHORIZED");
 }
@@ -73,7 +77,11 @@ async fn test_admin_auth_rejects_invalid_token() {
         .await
         .expect("Request failed");
 
-    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        r#"Bearer realm="mtchat-management", error="invalid_token""#
+    );
 }
 
 #[tokio::test]
@@ -162,6 +170,74 @@ async fn test_create_dialog_with_participants() {
         .expect("Cleanup failed");
 }
 
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_create_dialog_dedupe() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let object_id = Uuid::new_v4();
+    let create = |user: &str, title: &str| {
+        client
+            .post(format!("{}/api/v1/management/dialogs", base_url))
+            .header("Authorization", &auth_header)
+            .json(&json!({
+                "object_id": object_id,
+                "object_type": "tender",
+                "title": title,
+                "participants": [{ "user_id": user, "display_name": user }],
+                "dedupe": true
+            }))
+            .send()
+    };
+
+    let first: Value = create("alice", "Quote")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let second: Value = create("bob", "Quote").await.unwrap().json().await.unwrap();
+    let dialog_id = first["data"]["id"].as_str().unwrap();
+    assert_eq!(second["data"]["id"], dialog_id);
+
+    // The second request's participant was added to the existing dialog
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["participants"].as_array().unwrap().len(), 2);
+
+    // A different title is a different dialog
+    let other: Value = create("alice", "Delivery")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let other_id = other["data"]["id"].as_str().unwrap();
+    assert_ne!(other_id, dialog_id);
+
+    // Cleanup
+    for id in [dialog_id, other_id] {
+        client
+            .delete(format!("{}/api/v1/management/dialogs/{}", base_url, id))
+            .header("Authorization", &auth_header)
+            .send()
+            .await
+            .unwrap();
+    }
+}
+
 #[tokio::test]
 #[ignore] // Requires running server
 async fn test_find_dialog_by_object_and_scope() {
@@ -379,6 +455,51 @@ async fn test_delete_dialog() {
         .unwrap();
 
     assert_eq!(get_resp.status(), StatusCode::NOT_FOUND);
+
+    // Soft-deleted dialogs can be restored
+    let restore_url = format!(
+        "{}/api/v1/management/dialogs/{}/restore",
+        base_url, dialog_id
+    );
+    let restore_resp = client
+        .post(&restore_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(restore_resp.status(), StatusCode::OK);
+    let restore_body: Value = restore_resp.json().await.unwrap();
+    assert_eq!(restore_body["data"]["id"], dialog_id);
+
+    let get_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(get_resp.status(), StatusCode::OK);
+
+    // Nothing to restore once it is back
+    let restore_resp = client
+        .post(&restore_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(restore_resp.status(), StatusCode::NOT_FOUND);
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
 }
 
 // ============ Participant Management Tests ============
@@ -465,69 +586,88 @@ async fn test_add_and_remove_participant() {
         .unwrap();
 }
 
-// ============ Access Scopes Tests ============
-
 #[tokio::test]
 #[ignore] // Requires running server
-async fn test_update_access_scopes() {
+async fn test_invite_and_activate_participant() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
         .map(|t| format!("Bearer {}", t))
         .unwrap_or_default();
 
-    let tenant1 = Uuid::new_v4();
-    let tenant2 = Uuid::new_v4();
-
-    // Create dialog with initial scopes
     let create_resp = client
         .post(format!("{}/api/v1/management/dialogs", base_url))
         .header("Authorization", &auth_header)
         .json(&json!({
             "object_id": Uuid::new_v4(),
             "object_type": "test",
-            "participants": [],
-            "access_scopes": [{
-                "tenant_uid": tenant1,
-                "scope_level1": ["old_dept"],
-                "scope_level2": ["old_role"]
-            }]
+            "participants": []
         }))
         .send()
         .await
         .unwrap();
-
     let create_body: Value = create_resp.json().await.unwrap();
     let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let email = format!("supplier-{}@example.com", Uuid::new_v4());
 
-    // Update access scopes (replace all)
-    let update_resp = client
-        .put(format!(
-            "{}/api/v1/management/dialogs/{}/access-scopes",
+    // Invite by email
+    let invite_resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/invites",
             base_url, dialog_id
         ))
         .header("Authorization", &auth_header)
         .json(&json!({
-            "access_scopes": [
-                {
-                    "tenant_uid": tenant2,
-                    "scope_level1": ["new_dept1", "new_dept2"],
-                    "scope_level2": ["manager", "admin"]
-                }
-            ]
+            "email": email.to_uppercase(),
+            "display_name": "Supplier",
+            "company": "Acme"
         }))
         .send()
         .await
         .unwrap();
+    assert_eq!(invite_resp.status(), StatusCode::CREATED);
+    let invite: Value = invite_resp.json().await.unwrap();
+    assert_eq!(invite["data"]["email"], email);
 
-    assert_eq!(update_resp.status(), StatusCode::OK);
+    // Invalid email is rejected
+    let bad_resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/invites",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "email": "not-an-email", "display_name": "X" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad_resp.status(), StatusCode::BAD_REQUEST);
 
-    let update_body: Value = update_resp.json().await.unwrap();
-    let scopes = update_body["data"].as_array().unwrap();
-    assert_eq!(scopes.len(), 1);
-    assert_eq!(scopes[0]["tenant_uid"], tenant2.to_string());
+    // Pending invite is listed
+    let list_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}/invites",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    let list: Value = list_resp.json().await.unwrap();
+    assert_eq!(list["data"].as_array().unwrap().len(), 1);
+
+    // Activation turns the invite into a participant
+    let user_id = Uuid::new_v4().to_string();
+    let activate_resp = client
+        .post(format!("{}/api/v1/management/invites/activate", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "email": email, "user_id": user_id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(activate_resp.status(), StatusCode::OK);
+    let activated: Value = activate_resp.json().await.unwrap();
+    assert_eq!(activated["data"]["dialog_ids"][0], dialog_id);
 
-    // Verify via GET
     let get_resp = client
         .get(format!(
             "{}/api/v1/management/dialogs/{}",
@@ -537,18 +677,14 @@ async fn test_update_access_scopes() {
         .send()
         .await
         .unwrap();
-
     let body: Value = get_resp.json().await.unwrap();
-    let access_scopes = body["data"]["access_scopes"].as_array().unwrap();
-    assert_eq!(access_scopes.len(), 1);
-    assert_eq!(access_scopes[0]["tenant_uid"], tenant2.to_string());
-
-    // Old scope should be gone
-    assert!(!access_scopes
+    assert!(body["data"]["invites"].as_array().unwrap().is_empty());
+    assert!(body["data"]["participants"]
+        .as_array()
+        .unwrap()
         .iter()
-        .any(|s| s["tenant_uid"] == tenant1.to_string()));
+        .any(|p| p["user_id"] == user_id));
 
-    // Cleanup
     client
         .delete(format!(
             "{}/api/v1/management/dialogs/{}",
@@ -560,42 +696,97 @@ async fn test_update_access_scopes() {
         .unwrap();
 }
 
-// ============ Dialog Meta Tests ============
-
 #[tokio::test]
 #[ignore] // Requires running server
-async fn create_dialog_persists_meta() {
+async fn test_ban_and_unban_user() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
         .map(|t| format!("Bearer {}", t))
         .unwrap_or_default();
 
-    let object_id = Uuid::new_v4();
-
-    let resp = client
+    let create_resp = client
         .post(format!("{}/api/v1/management/dialogs", base_url))
         .header("Authorization", &auth_header)
         .json(&json!({
-            "object_id": object_id,
-            "object_type": "tender",
-            "title": "Meta test",
-            "participants": [{ "user_id": "u1", "display_name": "Alice" }],
-            "meta": { "tender_number": "T-123", "priority": "high" }
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": []
         }))
         .send()
         .await
-        .expect("Request failed");
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let user_id = Uuid::new_v4().to_string();
+    let ban_url = format!(
+        "{}/api/v1/management/dialogs/{}/bans/{}",
+        base_url, dialog_id, user_id
+    );
+
+    // Expiry in the past is rejected
+    let bad_resp = client
+        .put(&ban_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "expires_at": "2001-01-01T00:00:00Z" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad_resp.status(), StatusCode::BAD_REQUEST);
 
-    assert_eq!(resp.status(), StatusCode::OK);
+    let ban_resp = client
+        .put(&ban_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "reason": "Spam" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(ban_resp.status(), StatusCode::OK);
+    let ban: Value = ban_resp.json().await.unwrap();
+    assert_eq!(ban["data"]["user_id"], user_id);
+    assert!(ban["data"]["expires_at"].is_null());
 
-    let body: Value = resp.json().await.unwrap();
-    let dialog_id = body["data"]["id"].as_str().unwrap().to_string();
+    // The ban is listed with the dialog
+    let get_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    let body: Value = get_resp.json().await.unwrap();
+    assert_eq!(body["data"]["bans"][0]["reason"], "Spam");
 
-    assert_eq!(body["data"]["meta"]["tender_number"], "T-123");
-    assert_eq!(body["data"]["meta"]["priority"], "high");
+    let unban_resp = client
+        .delete(&ban_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unban_resp.status(), StatusCode::NO_CONTENT);
+
+    let list_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}/bans",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    let list: Value = list_resp.json().await.unwrap();
+    assert!(list["data"].as_array().unwrap().is_empty());
+
+    let again_resp = client
+        .delete(&ban_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(again_resp.status(), StatusCode::NOT_FOUND);
 
-    // Cleanup
     client
         .delete(format!(
             "{}/api/v1/management/dialogs/{}",
@@ -604,100 +795,1989 @@ async fn create_dialog_persists_meta() {
         .header("Authorization", &auth_header)
         .send()
         .await
-        .expect("Cleanup failed");
+        .unwrap();
 }
 
 #[tokio::test]
 #[ignore] // Requires running server
-async fn create_dialog_without_meta_omits_field() {
+async fn test_remove_participant_with_grace_period() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
         .map(|t| format!("Bearer {}", t))
         .unwrap_or_default();
+    let user = Uuid::new_v4();
 
-    let object_id = Uuid::new_v4();
-
-    let resp = client
+    let create_resp = client
         .post(format!("{}/api/v1/management/dialogs", base_url))
         .header("Authorization", &auth_header)
         .json(&json!({
-            "object_id": object_id,
-            "object_type": "tender",
-            "title": "No meta test",
-            "participants": [{ "user_id": "u1", "display_name": "Alice" }]
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [user]
         }))
         .send()
         .await
-        .expect("Request failed");
-
-    assert_eq!(resp.status(), StatusCode::OK);
-
-    let body: Value = resp.json().await.unwrap();
-    let dialog_id = body["data"]["id"].as_str().unwrap().to_string();
+        .unwrap();
 
-    assert!(body["data"].get("meta").is_none() || body["data"]["meta"].is_null());
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
 
-    // Cleanup
-    client
+    // Out-of-range grace periods are rejected
+    let invalid_resp = client
         .delete(format!(
-            "{}/api/v1/management/dialogs/{}",
-            base_url, dialog_id
+            "{}/api/v1/management/dialogs/{}/participants/{}?grace_secs=-1",
+            base_url, dialog_id, user
         ))
         .header("Authorization", &auth_header)
         .send()
         .await
-        .expect("Cleanup failed");
-}
-
-// ============ Error Handling Tests ============
-
-#[tokio::test]
-#[ignore] // Requires running server
-async fn test_get_nonexistent_dialog_returns_404() {
-    let client = Client::new();
-    let base_url = get_base_url();
-    let auth_header = get_admin_token()
-        .map(|t| format!("Bearer {}", t))
-        .unwrap_or_default();
+        .unwrap();
+    assert_eq!(invalid_resp.status(), StatusCode::BAD_REQUEST);
 
-    let fake_id = Uuid::new_v4();
+    let remove_resp = client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}/participants/{}?grace_secs=3600",
+            base_url, dialog_id, user
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(remove_resp.status(), StatusCode::ACCEPTED);
 
-    let resp = client
+    // Still a participant, but read-only
+    let get_resp = client
         .get(format!(
             "{}/api/v1/management/dialogs/{}",
-            base_url, fake_id
+            base_url, dialog_id
         ))
         .header("Authorization", &auth_header)
         .send()
         .await
         .unwrap();
+    let body: Value = get_resp.json().await.unwrap();
+    let participant = body["data"]["participants"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["user_id"] == user.to_string())
+        .unwrap()
+        .clone();
+    assert!(participant["pending_removal_at"].is_string());
 
-    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
-}
+    let list_resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(list_resp.status(), StatusCode::OK);
 
-#[tokio::test]
+    let send_resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user
+        ))
+        .json(&json!({ "content": "<p>Still here?</p>" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(send_resp.status(), StatusCode::FORBIDDEN);
+    let send_body: Value = send_resp.json().await.unwrap();
+    assert_eq!(send_body["error"]["code"], "PARTICIPANT_REMOVAL_PENDING");
+
+    // Removing again without a grace period removes immediately
+    let remove_resp = client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}/participants/{}?grace_secs=0",
+            base_url, dialog_id, user
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(remove_resp.status(), StatusCode::NO_CONTENT);
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_add_observer_participant() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [Uuid::new_v4()]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let observer = Uuid::new_v4();
+
+    let add_resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/participants",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "user_id": observer, "observer": true }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(add_resp.status(), StatusCode::CREATED);
+
+    let get_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+
+    let body: Value = get_resp.json().await.unwrap();
+    let participants = body["data"]["participants"].as_array().unwrap();
+    let added = participants
+        .iter()
+        .find(|p| p["user_id"] == observer.to_string())
+        .unwrap();
+    assert_eq!(added["joined_as"], "observer");
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_transfer_participant() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let old_user = Uuid::new_v4();
+    let new_user = Uuid::new_v4();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [{ "user_id": old_user, "display_name": "Bob" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+
+    let transfer_resp = client
+        .post(format!(
+            "{}/api/v1/management/participants/transfer",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "from_user_id": old_user,
+            "to_user_id": new_user,
+            "dialog_ids": [dialog_id],
+            "display_name": "Maria"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(transfer_resp.status(), StatusCode::OK);
+    let body: Value = transfer_resp.json().await.unwrap();
+    assert_eq!(body["data"]["transferred"][0], dialog_id);
+
+    let get_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+
+    let body: Value = get_resp.json().await.unwrap();
+    let participants = body["data"]["participants"].as_array().unwrap();
+    assert!(participants
+        .iter()
+        .any(|p| p["user_id"] == new_user.to_string()));
+    assert!(!participants
+        .iter()
+        .any(|p| p["user_id"] == old_user.to_string()));
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+// ============ Access Scopes Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_update_access_scopes() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let tenant1 = Uuid::new_v4();
+    let tenant2 = Uuid::new_v4();
+
+    // Create dialog with initial scopes
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [],
+            "access_scopes": [{
+                "tenant_uid": tenant1,
+                "scope_level1": ["old_dept"],
+                "scope_level2": ["old_role"]
+            }]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+
+    // Update access scopes (replace all)
+    let update_resp = client
+        .put(format!(
+            "{}/api/v1/management/dialogs/{}/access-scopes",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "access_scopes": [
+                {
+                    "tenant_uid": tenant2,
+                    "scope_level1": ["new_dept1", "new_dept2"],
+                    "scope_level2": ["manager", "admin"]
+                }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(update_resp.status(), StatusCode::OK);
+
+    let update_body: Value = update_resp.json().await.unwrap();
+    let scopes = update_body["data"].as_array().unwrap();
+    assert_eq!(scopes.len(), 1);
+    assert_eq!(scopes[0]["tenant_uid"], tenant2.to_string());
+
+    // Verify via GET
+    let get_resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+
+    let body: Value = get_resp.json().await.unwrap();
+    let access_scopes = body["data"]["access_scopes"].as_array().unwrap();
+    assert_eq!(access_scopes.len(), 1);
+    assert_eq!(access_scopes[0]["tenant_uid"], tenant2.to_string());
+
+    // Old scope should be gone
+    assert!(!access_scopes
+        .iter()
+        .any(|s| s["tenant_uid"] == tenant1.to_string()));
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+// ============ Dialog Meta Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn create_dialog_persists_meta() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let object_id = Uuid::new_v4();
+
+    let resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": object_id,
+            "object_type": "tender",
+            "title": "Meta test",
+            "participants": [{ "user_id": "u1", "display_name": "Alice" }],
+            "meta": { "tender_number": "T-123", "priority": "high" }
+        }))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = resp.json().await.unwrap();
+    let dialog_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    assert_eq!(body["data"]["meta"]["tender_number"], "T-123");
+    assert_eq!(body["data"]["meta"]["priority"], "high");
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .expect("Cleanup failed");
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn create_dialog_without_meta_omits_field() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let object_id = Uuid::new_v4();
+
+    let resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": object_id,
+            "object_type": "tender",
+            "title": "No meta test",
+            "participants": [{ "user_id": "u1", "display_name": "Alice" }]
+        }))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = resp.json().await.unwrap();
+    let dialog_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    assert!(body["data"].get("meta").is_none() || body["data"]["meta"].is_null());
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .expect("Cleanup failed");
+}
+
+// ============ Error Handling Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_get_nonexistent_dialog_returns_404() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let fake_id = Uuid::new_v4();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, fake_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_add_participant_to_nonexistent_dialog() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let fake_dialog_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+
+    let resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/participants",
+            base_url, fake_dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "user_id": user_id }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+// ============ Runtime Settings Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_runtime_setting_update_and_reset() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/settings/notification_delay_ms",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "value": 250 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["value"], 250);
+    assert_eq!(body["data"]["overridden"], true);
+
+    let resp = client
+        .delete(format!(
+            "{}/api/v1/management/settings/notification_delay_ms",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["overridden"], false);
+    assert_eq!(body["data"]["value"], body["data"]["default"]);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_runtime_setting_rejects_unknown_and_invalid() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/settings/no_such_setting",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "value": 1 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "SETTING_NOT_FOUND");
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/settings/max_message_length",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "value": 0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+// ============ Feature Flag Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_feature_flag_tenant_override() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let tenant = Uuid::new_v4().to_string();
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/feature-flags/reactions/tenants/{}",
+            base_url, tenant
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "enabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["scope_type"], "tenant");
+    assert_eq!(body["data"]["enabled"], true);
+
+    let resp = client
+        .delete(format!(
+            "{}/api/v1/management/feature-flags/reactions/tenants/{}",
+            base_url, tenant
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = client
+        .put(format!(
+            "{}/api/v1/management/feature-flags/Not%20Valid/tenants/{}",
+            base_url, tenant
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "enabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+// ============ Slow Mode Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_dialog_notification_delay() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [Uuid::new_v4()]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let delay_url = format!(
+        "{}/api/v1/management/dialogs/{}/notification-delay",
+        base_url, dialog_id
+    );
+
+    for invalid in [-1, 600_000] {
+        let resp = client
+            .put(&delay_url)
+            .header("Authorization", &auth_header)
+            .json(&json!({ "notification_delay_ms": invalid }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let set_resp = client
+        .put(&delay_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "notification_delay_ms": 0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(set_resp.status(), StatusCode::OK);
+    let set_body: Value = set_resp.json().await.unwrap();
+    assert_eq!(set_body["data"]["notification_delay_ms"], 0);
+
+    let clear_resp = client
+        .put(&delay_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "notification_delay_ms": null }))
+        .send()
+        .await
+        .unwrap();
+    let clear_body: Value = clear_resp.json().await.unwrap();
+    assert!(clear_body["data"].get("notification_delay_ms").is_none());
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Redis
+async fn test_slow_mode() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let user = Uuid::new_v4();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [user]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let slow_mode_url = format!(
+        "{}/api/v1/management/dialogs/{}/slow-mode",
+        base_url, dialog_id
+    );
+
+    let invalid_resp = client
+        .put(&slow_mode_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "slow_mode_secs": -5 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid_resp.status(), StatusCode::BAD_REQUEST);
+
+    let set_resp = client
+        .put(&slow_mode_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "slow_mode_secs": 60 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(set_resp.status(), StatusCode::OK);
+    let set_body: Value = set_resp.json().await.unwrap();
+    assert_eq!(set_body["data"]["slow_mode_secs"], 60);
+
+    let messages_url = format!(
+        "{}/api/v1/dialogs/{}/messages?user_id={}",
+        base_url, dialog_id, user
+    );
+    let first_resp = client
+        .post(&messages_url)
+        .json(&json!({ "content": "<p>First</p>" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(first_resp.status(), StatusCode::OK);
+
+    let second_resp = client
+        .post(&messages_url)
+        .json(&json!({ "content": "<p>Second</p>" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(second_resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let second_body: Value = second_resp.json().await.unwrap();
+    assert_eq!(second_body["error"]["code"], "SLOW_MODE_ACTIVE");
+    assert_eq!(second_body["error"]["details"]["slow_mode_secs"], 60);
+    assert!(
+        second_body["error"]["details"]["retry_after_secs"]
+            .as_i64()
+            .unwrap()
+            > 0
+    );
+
+    // Turning slow mode off removes the field
+    let off_resp = client
+        .put(&slow_mode_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "slow_mode_secs": 0 }))
+        .send()
+        .await
+        .unwrap();
+    let off_body: Value = off_resp.json().await.unwrap();
+    assert!(off_body["data"].get("slow_mode_secs").is_none());
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_dialog_sanitize_profile() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let user = Uuid::new_v4();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [user]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let profile_url = format!(
+        "{}/api/v1/management/dialogs/{}/sanitize-profile",
+        base_url, dialog_id
+    );
+
+    let invalid_resp = client
+        .put(&profile_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "sanitize_profile": "everything" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(invalid_resp.status().is_client_error());
+
+    let set_resp = client
+        .put(&profile_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "sanitize_profile": "minimal" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(set_resp.status(), StatusCode::OK);
+    let set_body: Value = set_resp.json().await.unwrap();
+    assert_eq!(set_body["data"]["sanitize_profile"], "minimal");
+
+    // Lists are not part of the minimal profile
+    let send_resp = client
+        .post(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user
+        ))
+        .json(&json!({ "content": "<p><strong>Hi</strong></p><ul><li>Item</li></ul>" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(send_resp.status(), StatusCode::OK);
+    let send_body: Value = send_resp.json().await.unwrap();
+    let content = send_body["data"]["content"].as_str().unwrap();
+    assert!(content.contains("<strong>"));
+    assert!(!content.contains("<ul>"));
+
+    let reset_resp = client
+        .put(&profile_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "sanitize_profile": null }))
+        .send()
+        .await
+        .unwrap();
+    let reset_body: Value = reset_resp.json().await.unwrap();
+    assert!(reset_body["data"].get("sanitize_profile").is_none());
+
+    // Cleanup
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+// ============ System Events Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_push_system_event() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "tender",
+            "participants": []
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let events_url = format!(
+        "{}/api/v1/management/dialogs/{}/system-events",
+        base_url, dialog_id
+    );
+
+    let resp = client
+        .post(&events_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "type": "status_changed",
+            "payload": { "from": "open", "to": "evaluation" },
+            "text": "Tender moved to evaluation"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["message_type"], "system");
+    let content: Value = serde_json::from_str(body["data"]["content"].as_str().unwrap()).unwrap();
+    assert_eq!(content["event"], "object_event");
+    assert_eq!(content["type"], "status_changed");
+    assert_eq!(content["payload"]["to"], "evaluation");
+
+    // Invalid type and non-object payload are rejected
+    for invalid in [
+        json!({ "type": "Status Changed" }),
+        json!({ "type": "status_changed", "payload": [1, 2] }),
+    ] {
+        let resp = client
+            .post(&events_url)
+            .header("Authorization", &auth_header)
+            .json(&invalid)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_import_messages() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let user_id = Uuid::new_v4().to_string();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "tender",
+            "participants": [{ "user_id": user_id, "display_name": "Alice" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let import_url = format!(
+        "{}/api/v1/management/dialogs/{}/messages/import",
+        base_url, dialog_id
+    );
+
+    // Out of order on purpose: history is stored by sent_at
+    let resp = client
+        .post(&import_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "sender_map": { "legacy-1": user_id },
+            "messages": [
+                { "sender_id": "legacy-1", "content": "second", "sent_at": "2024-03-01T10:16:00Z" },
+                {
+                    "sender_id": "legacy-2",
+                    "sender_display_name": "Former Employee",
+                    "content": "first",
+                    "sent_at": "2024-03-01T10:15:00Z"
+                }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["imported"], 2);
+    let ids = body["data"]["message_ids"].as_array().unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    let messages = body["data"]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0]["id"], ids[1]);
+    assert_eq!(messages[0]["sender_id"], "legacy-2");
+    assert_eq!(messages[0]["sent_at"], "2024-03-01T10:15:00Z");
+    assert_eq!(messages[1]["id"], ids[0]);
+    assert_eq!(messages[1]["sender_id"], user_id.as_str());
+
+    // Imports do not count as unread
+    let resp = client
+        .get(format!(
+            "{}/api/v1/dialogs/{}?user_id={}",
+            base_url, dialog_id, user_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["unread_count"], 0);
+
+    // Empty batches and future timestamps are rejected
+    for invalid in [
+        json!({ "messages": [] }),
+        json!({ "messages": [{ "sender_id": "u", "content": "x", "sent_at": "2999-01-01T00:00:00Z" }] }),
+    ] {
+        let resp = client
+            .post(&import_url)
+            .header("Authorization", &auth_header)
+            .json(&invalid)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_message_by_external_id() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let user_id = Uuid::new_v4().to_string();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "tender",
+            "participants": [{ "user_id": user_id, "display_name": "Alice" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let import_url = format!(
+        "{}/api/v1/management/dialogs/{}/messages/import",
+        base_url, dialog_id
+    );
+
+    let legacy_id = format!("legacy-{}", Uuid::new_v4());
+    let resp = client
+        .post(&import_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "messages": [{
+                "sender_id": user_id,
+                "content": "from the old system",
+                "sent_at": "2024-03-01T10:15:00Z",
+                "external_id": legacy_id
+            }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let message_id = body["data"]["message_ids"][0].clone();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/messages/by-external-id/{}",
+            base_url, legacy_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["id"], message_id);
+    assert_eq!(body["data"]["external_id"], legacy_id.as_str());
+
+    // External IDs are unique
+    let resp = client
+        .post(&import_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "messages": [{
+                "sender_id": user_id,
+                "content": "again",
+                "sent_at": "2024-03-01T10:16:00Z",
+                "external_id": legacy_id
+            }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/messages/by-external-id/missing-{}",
+            base_url,
+            Uuid::new_v4()
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_export_qa_pairs() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let bidder = Uuid::new_v4().to_string();
+    let other_bidder = Uuid::new_v4().to_string();
+    let organizer = Uuid::new_v4().to_string();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "tender",
+            "participants": [
+                { "user_id": bidder, "display_name": "Bob", "company": "Acme", "company_uid": "acme" },
+                { "user_id": other_bidder, "display_name": "Eve", "company": "Globex" },
+                { "user_id": organizer, "display_name": "Olga", "company": "Buyer" }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+
+    let send = |user_id: String, body: Value| {
+        let client = client.clone();
+        let url = format!(
+            "{}/api/v1/dialogs/{}/messages?user_id={}",
+            base_url, dialog_id, user_id
+        );
+        async move {
+            let resp = client.post(url).json(&body).send().await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body: Value = resp.json().await.unwrap();
+            body["data"]["id"].as_str().unwrap().to_string()
+        }
+    };
+    let question = send(
+        bidder.clone(),
+        json!({ "content": "Is delivery included?" }),
+    )
+    .await;
+    let other = send(other_bidder.clone(), json!({ "content": "Deadline?" })).await;
+    send(bidder.clone(), json!({ "content": "Unanswered" })).await;
+    let answer = send(
+        organizer.clone(),
+        json!({ "content": "Yes", "reply_to": question }),
+    )
+    .await;
+    send(
+        organizer.clone(),
+        json!({ "content": "Friday", "reply_to": other }),
+    )
+    .await;
+
+    let qa_url = format!(
+        "{}/api/v1/management/dialogs/{}/qa-pairs",
+        base_url, dialog_id
+    );
+    let resp = client
+        .get(&qa_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let pairs = body["data"]["pairs"].as_array().unwrap();
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(pairs[0]["question"]["id"], question.as_str());
+    assert_eq!(pairs[0]["question"]["sender_company"], "Acme");
+    assert_eq!(pairs[0]["answers"][0]["id"], answer.as_str());
+    assert!(body["data"]["next_cursor"].is_null());
+
+    for filter in ["company=Acme", "company_uid=acme"] {
+        let resp = client
+            .get(format!("{}?{}", qa_url, filter))
+            .header("Authorization", &auth_header)
+            .send()
+            .await
+            .unwrap();
+        let body: Value = resp.json().await.unwrap();
+        let pairs = body["data"]["pairs"].as_array().unwrap();
+        assert_eq!(pairs.len(), 1, "filter {}", filter);
+        assert_eq!(pairs[0]["question"]["id"], question.as_str());
+    }
+
+    // Paging and date range
+    let resp = client
+        .get(format!("{}?limit=1", qa_url))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["next_cursor"], question.as_str());
+
+    let resp = client
+        .get(format!("{}?to=2000-01-01T00:00:00Z", qa_url))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["data"]["pairs"].as_array().unwrap().is_empty());
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+// ============ Transcript Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_transcript_link() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "tender",
+            "title": "Tender <clarifications>",
+            "participants": []
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+
+    let resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/transcript-links",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "expires_in_secs": 3600, "recipient": "Audit Co." }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let url = body["data"]["url"].as_str().unwrap();
+    // Relative unless TRANSCRIPT_PUBLIC_URL is set
+    let url = if url.starts_with('/') {
+        format!("{}{}", base_url, url)
+    } else {
+        url.to_string()
+    };
+
+    // HTML transcript opens without authentication
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let html = resp.text().await.unwrap();
+    assert!(html.contains("Tender &lt;clarifications&gt;"));
+    assert!(html.contains("Read-only copy issued to Audit Co."));
+
+    let resp = client
+        .get(format!("{}&format=json", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let feed: Value = resp.json().await.unwrap();
+    assert_eq!(feed["dialog_id"], dialog_id);
+    assert_eq!(feed["recipient"], "Audit Co.");
+
+    // Tampered recipient invalidates the signature
+    let resp = client
+        .get(url.replace("Audit%20Co.", "Someone"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+// ============ Impersonation Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_impersonation_is_read_only_and_audited() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let user_id = format!("user-{}", Uuid::new_v4());
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "tender",
+            "participants": [{ "user_id": user_id, "display_name": "Alice" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+
+    // Only participants can be impersonated
+    let resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/impersonations",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "user_id": "someone-else", "actor": "support@example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = client
+        .post(format!(
+            "{}/api/v1/management/dialogs/{}/impersonations",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "user_id": user_id,
+            "actor": "support@example.com",
+            "reason": "TICKET-1",
+            "expires_in_secs": 60
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let token = body["data"]["token"].as_str().unwrap();
+    let session_id = body["data"]["session_id"].as_str().unwrap();
+
+    let resp = client
+        .get(format!("{}/api/v1/impersonation/dialog", base_url))
+        .header("X-Impersonation-Token", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["id"], dialog_id);
+    assert_eq!(body["data"]["i_am_participant"], true);
+
+    let resp = client
+        .get(format!("{}/api/v1/impersonation/messages", base_url))
+        .header("X-Impersonation-Token", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // No write routes and a forged token is rejected
+    let resp = client
+        .post(format!("{}/api/v1/impersonation/messages", base_url))
+        .header("X-Impersonation-Token", token)
+        .json(&json!({ "content": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let resp = client
+        .get(format!("{}/api/v1/impersonation/dialog", base_url))
+        .header("X-Impersonation-Token", format!("{}0", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Issuance and both views are in the audit log
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/audit-log?dialog_id={}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let entries = body["data"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(entries
+        .iter()
+        .all(|e| e["details"]["session_id"] == session_id && e["user_id"] == user_id.as_str()));
+    assert_eq!(entries[2]["action"], "impersonation.issued");
+    assert_eq!(entries[2]["details"]["reason"], "TICKET-1");
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+// ============ Tenant Settings Tests ============
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_tenant_settings_and_widget_config() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let tenant = Uuid::new_v4().to_string();
+    let settings_url = format!("{}/api/v1/management/tenants/{}/settings", base_url, tenant);
+    let widget_url = format!(
+        "{}/api/v1/tenants/{}/widget-config?user_id={}",
+        base_url,
+        tenant,
+        Uuid::new_v4()
+    );
+
+    // Unconfigured tenant gets empty settings
+    let resp = client.get(&widget_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["branding"], json!({}));
+
+    let resp = client
+        .put(&settings_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "branding": { "primary_color": "#0057b8" },
+            "behavior": { "show_company": true },
+            "locale": "ru"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = client.get(&widget_url).send().await.unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["tenant_uid"], tenant);
+    assert_eq!(body["data"]["branding"]["primary_color"], "#0057b8");
+    assert_eq!(body["data"]["behavior"]["show_company"], true);
+    assert_eq!(body["data"]["locale"], "ru");
+    assert!(body["data"]["features"].is_object());
+
+    // Branding must be an object
+    let resp = client
+        .put(&settings_url)
+        .header("Authorization", &auth_header)
+        .json(&json!({ "branding": "blue" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = client
+        .delete(&settings_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
+
+// ============ WebSocket Connection Tests ============
+
+#[tokio::test]
 #[ignore] // Requires running server
-async fn test_add_participant_to_nonexistent_dialog() {
+async fn test_list_and_disconnect_connections() {
     let client = Client::new();
     let base_url = get_base_url();
     let auth_header = get_admin_token()
         .map(|t| format!("Bearer {}", t))
         .unwrap_or_default();
 
-    let fake_dialog_id = Uuid::new_v4();
-    let user_id = Uuid::new_v4();
+    let resp = client
+        .get(format!("{}/api/v1/management/connections", base_url))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let instances = body["data"]["instances"].as_array().unwrap();
+    assert!(instances.iter().any(|i| i["current"] == true));
+    assert!(body["data"]["users"].is_array());
+
+    // Disconnecting a user without a connection is a no-op
+    let resp = client
+        .delete(format!(
+            "{}/api/v1/management/connections/{}",
+            base_url,
+            Uuid::new_v4()
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = client
+        .get(format!("{}/metrics", base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let metrics = resp.text().await.unwrap();
+    assert!(metrics.contains("# TYPE mtchat_ws_connections gauge"));
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_webhook_health() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let resp = client
+        .get(format!("{}/api/v1/management/webhooks/health", base_url))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let endpoints = body["data"]["endpoints"].as_array().unwrap();
+    assert_eq!(body["data"]["enabled"], !endpoints.is_empty());
+    for endpoint in endpoints {
+        assert!(["closed", "open", "half_open"].contains(&endpoint["state"].as_str().unwrap()));
+        assert!(endpoint["dead_letters"].is_u64());
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_skip_notification_backlog() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let resp = client
+        .get(format!("{}/api/v1/management/jobs/notifications", base_url))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["data"]["max_age_secs"].as_i64().unwrap() > 0);
+    assert!(body["data"]["stale_total"].is_u64());
 
     let resp = client
         .post(format!(
-            "{}/api/v1/management/dialogs/{}/participants",
-            base_url, fake_dialog_id
+            "{}/api/v1/management/jobs/notifications/skip",
+            base_url
         ))
         .header("Authorization", &auth_header)
-        .json(&json!({ "user_id": user_id }))
+        .json(&json!({ "older_than_secs": -1 }))
         .send()
         .await
         .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
-    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = client
+        .post(format!(
+            "{}/api/v1/management/jobs/notifications/skip",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "older_than_secs": 60 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    if body["data"]["enabled"].as_bool().unwrap() {
+        assert!(body["data"]["skipped_before"].is_string());
+    } else {
+        assert!(body["data"]["skipped_before"].is_null());
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_list_job_dead_letters() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/jobs/dead-letters?job_type=attachment_cleanup&limit=10",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let letters = body["data"].as_array().unwrap();
+    assert!(letters.len() <= 10);
+    for letter in letters {
+        assert_eq!(letter["job_type"], "attachment_cleanup");
+        assert!(letter["attempts"].as_i64().unwrap() >= 1);
+    }
+}
+
+/// Signature of an inbound event body (`X-Webhook-Signature`)
+fn sign_inbound(body: &str) -> String {
+    use hmac::{Hmac, Mac};
+
+    let secret = env::var("WEBHOOK_SECRET").unwrap_or_default();
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with WEBHOOK_SECRET
+async fn test_inbound_integration_events() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "participants": [{ "user_id": Uuid::new_v4(), "display_name": "Owner" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap().to_string();
+
+    let push = |event: Value, signature: Option<String>| {
+        let body = event.to_string();
+        let signature = signature.unwrap_or_else(|| sign_inbound(&body));
+        client
+            .post(format!("{}/api/v1/integrations/events", base_url))
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Signature", signature)
+            .body(body)
+            .send()
+    };
+    let event = |event_type: &str, payload: Value| {
+        json!({
+            "id": Uuid::new_v4(),
+            "type": event_type,
+            "timestamp": chrono::Utc::now(),
+            "payload": payload,
+        })
+    };
+
+    // Unsigned events are rejected
+    let resp = push(
+        event("dialog.archive", json!({ "dialog_id": dialog_id })),
+        Some("sha256=invalid".into()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let system_event = event(
+        "message.system",
+        json!({ "dialog_id": dialog_id, "type": "status_changed", "text": "Closed" }),
+    );
+    let resp = push(system_event.clone(), None).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["type"], "message.system");
+    assert_eq!(body["data"]["result"]["message_type"], "system");
+
+    // A replay returns the original message instead of posting another
+    let resp = push(system_event, None).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let replay: Value = resp.json().await.unwrap();
+    assert_eq!(replay["data"], body["data"]);
+
+    let user_id = Uuid::new_v4().to_string();
+    let resp = push(
+        event(
+            "participant.add",
+            json!({ "dialog_id": dialog_id, "user_id": user_id, "display_name": "Bot" }),
+        ),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = push(
+        event("dialog.archive", json!({ "dialog_id": dialog_id })),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["data"]["result"]["archived_participants"], 2);
+
+    let resp = push(event("dialog.explode", json!({})), None)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/dialogs/{}",
+            base_url, dialog_id
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_dialog_sla_overrides_object_type_policy() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let object_type = format!("sla-{}", Uuid::new_v4().simple());
+
+    let policy_resp = client
+        .put(format!(
+            "{}/api/v1/management/sla-policies/{}",
+            base_url, object_type
+        ))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "response_secs": 14400 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(policy_resp.status(), StatusCode::OK);
+
+    let create_resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": object_type,
+            "participants": []
+        }))
+        .send()
+        .await
+        .unwrap();
+    let create_body: Value = create_resp.json().await.unwrap();
+    let dialog_id = create_body["data"]["id"].as_str().unwrap();
+    let dialog_url = format!("{}/api/v1/management/dialogs/{}", base_url, dialog_id);
+
+    let body: Value = client
+        .get(&dialog_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["sla"]["source"], "object_type");
+    assert_eq!(body["data"]["sla"]["status"], "answered");
+
+    // Warning time must be shorter than the response time
+    let bad_resp = client
+        .put(format!("{}/sla", dialog_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "response_secs": 600, "warning_secs": 600 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad_resp.status(), StatusCode::BAD_REQUEST);
+
+    let set_resp = client
+        .put(format!("{}/sla", dialog_url))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "response_secs": 600,
+            "warning_secs": 300,
+            "responder_company": "Supplier Ltd"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(set_resp.status(), StatusCode::OK);
+
+    let body: Value = client
+        .get(&dialog_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["sla"]["source"], "dialog");
+    assert_eq!(body["data"]["sla"]["response_secs"], 600);
+    assert_eq!(body["data"]["sla"]["warning_secs"], 300);
+    assert_eq!(body["data"]["sla_responder_company"], "Supplier Ltd");
+
+    client
+        .delete(format!(
+            "{}/api/v1/management/sla-policies/{}",
+            base_url, object_type
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    client
+        .delete(&dialog_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_export_returns_jsonl_pages() {
+    let client = Client::new();
+    let base_url = get_base_url();
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/management/export/dialogs?updated_since=2000-01-01T00:00:00Z&limit=1",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()["content-type"].to_str().unwrap(),
+        "application/x-ndjson"
+    );
+    let has_more = resp.headers()["x-has-more"].to_str().unwrap() == "true";
+    let cursor = resp
+        .headers()
+        .get("x-next-cursor")
+        .map(|v| v.to_str().unwrap().to_string());
+    let body = resp.text().await.unwrap();
+    for line in body.lines() {
+        let row: Value = serde_json::from_str(line).unwrap();
+        assert!(row["updated_at"].is_string());
+    }
+
+    if has_more {
+        let next = client
+            .get(format!(
+                "{}/api/v1/management/export/dialogs?cursor={}&limit=1",
+                base_url,
+                cursor.unwrap()
+            ))
+            .header("Authorization", &auth_header)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(next.status(), StatusCode::OK);
+        assert_ne!(next.text().await.unwrap(), body);
+    }
+
+    // Malformed cursors are rejected
+    let bad = client
+        .get(format!(
+            "{}/api/v1/management/export/messages?cursor=bogus",
+            base_url
+        ))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad.status(), StatusCode::BAD_REQUEST);
 }
@@ -3,8 +3,15 @@
 //! Verifies that WebhookEvent factory methods correctly map
 //! domain entities to event payloads.
 
-use multitenancy_chat_api::domain::{Dialog, DialogParticipant, JoinedAs, Message};
-use multitenancy_chat_api::webhooks::{WebhookEvent, WebhookEventType, WebhookPayload};
+use multitenancy_chat_api::domain::{
+    ActionButton, Attachment, BroadcastMention, ButtonStyle, ContentBlock, Dialog,
+    DialogParticipant, JoinedAs, KeyValueRow, Message, MessagePriority, SlaSource, SlaTarget,
+    UnansweredMessage,
+};
+use multitenancy_chat_api::webhooks::{
+    ArchiveTrigger, DigestDialog, WebhookBatch, WebhookEvent, WebhookEventType, WebhookPayload,
+    ATTACHMENT_TEXT_WEBHOOK_CHARS, DEFAULT_WEBHOOK_PAYLOAD_VERSION, WEBHOOK_BATCH_VERSION,
+};
 use uuid::Uuid;
 
 fn make_dialog() -> Dialog {
@@ -73,6 +80,73 @@ fn test_message_new_event_with_reply() {
     }
 }
 
+#[test]
+fn test_message_new_event_carries_metadata() {
+    let dialog = make_dialog();
+    let metadata = serde_json::json!({ "quote_line_id": "line-7" });
+    let message =
+        Message::new(dialog.id, "user-sender", "See line 7").with_metadata(Some(metadata.clone()));
+
+    let event = WebhookEvent::message_new(&dialog, &message);
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["payload"]["message"]["metadata"], metadata);
+
+    // Absent when the message has none
+    let plain = Message::new(dialog.id, "user-sender", "Hi");
+    let json = serde_json::to_value(WebhookEvent::message_new(&dialog, &plain)).unwrap();
+    assert!(json["payload"]["message"].get("metadata").is_none());
+}
+
+#[test]
+fn test_message_edited_event() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-sender", "Hello, edited");
+
+    let event = WebhookEvent::message_edited(&dialog, &message, "Hello");
+
+    assert_eq!(event.event_type, WebhookEventType::MessageEdited);
+
+    if let WebhookPayload::MessageEdited(payload) = &event.payload {
+        assert_eq!(payload.dialog_id, dialog.id);
+        assert_eq!(payload.message.id, message.id);
+        assert_eq!(payload.message.content, "Hello, edited");
+        // sha256("Hello")
+        assert_eq!(
+            payload.previous_content_hash,
+            "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969"
+        );
+    } else {
+        panic!("Expected MessageEdited payload");
+    }
+
+    // The untagged payload deserializes back to the edited variant
+    let json = serde_json::to_string(&event).unwrap();
+    let parsed: WebhookEvent = serde_json::from_str(&json).unwrap();
+    assert!(matches!(parsed.payload, WebhookPayload::MessageEdited(_)));
+}
+
+#[test]
+fn test_message_deleted_event() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-sender", "Hello");
+
+    let event = WebhookEvent::message_deleted(&dialog, &message);
+
+    assert_eq!(event.event_type, WebhookEventType::MessageDeleted);
+    assert_eq!(event.payload.dialog_id(), Some(dialog.id));
+
+    if let WebhookPayload::MessageDeleted(payload) = &event.payload {
+        assert_eq!(payload.message_id, message.id);
+        assert_eq!(payload.sender_id.as_deref(), Some("user-sender"));
+        assert_eq!(
+            payload.previous_content_hash,
+            "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969"
+        );
+    } else {
+        panic!("Expected MessageDeleted payload");
+    }
+}
+
 #[test]
 fn test_participant_joined_event() {
     let dialog = make_dialog();
@@ -141,6 +215,262 @@ fn test_notification_pending_event() {
     }
 }
 
+#[test]
+fn test_notification_mention_event() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-sender", "@channel please review");
+
+    let event = WebhookEvent::notification_mention(
+        &dialog,
+        &message,
+        "user-recipient",
+        None,
+        BroadcastMention::Channel,
+    );
+
+    assert_eq!(event.event_type, WebhookEventType::NotificationMention);
+
+    let json = serde_json::to_value(&event).expect("serialize");
+    assert_eq!(json["type"], "notification_mention");
+    assert_eq!(json["payload"]["mention"], "channel");
+    assert_eq!(json["payload"]["recipient_id"], "user-recipient");
+    assert_eq!(json["payload"]["message"]["id"], message.id.to_string());
+}
+
+#[test]
+fn test_notification_urgent_event() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-sender", "Deadline moved to 14:00")
+        .with_priority(MessagePriority::Urgent);
+
+    let event = WebhookEvent::notification_urgent(&dialog, &message, "user-recipient", None);
+
+    assert_eq!(event.event_type, WebhookEventType::NotificationUrgent);
+    assert!(event.event_type.is_notification());
+
+    let json = serde_json::to_value(&event).expect("serialize");
+    assert_eq!(json["type"], "notification_urgent");
+    assert_eq!(json["payload"]["recipient_id"], "user-recipient");
+    assert_eq!(json["payload"]["message"]["priority"], "urgent");
+}
+
+#[test]
+fn test_notification_digest_event() {
+    let dialog = make_dialog();
+    let other = Dialog::new("tender-456", "tender", None, None, None, None);
+
+    let event = WebhookEvent::notification_digest(
+        "user-recipient",
+        vec![
+            DigestDialog::new(&dialog, 3, 5),
+            DigestDialog::new(&other, 1, 1),
+        ],
+    );
+
+    assert_eq!(event.event_type, WebhookEventType::NotificationDigest);
+    assert_eq!(event.event_type.as_str(), "notification.digest");
+    assert!(event.event_type.is_notification());
+    assert_eq!(event.payload.dialog_id(), None);
+
+    let json = serde_json::to_value(&event).expect("serialize");
+    assert_eq!(json["type"], "notification_digest");
+    assert_eq!(json["payload"]["recipient_id"], "user-recipient");
+    assert_eq!(json["payload"]["notification_count"], 4);
+    assert_eq!(
+        json["payload"]["dialogs"][0]["dialog_id"],
+        dialog.id.to_string()
+    );
+    assert_eq!(json["payload"]["dialogs"][0]["chat_title"], "Test Dialog");
+    assert_eq!(json["payload"]["dialogs"][0]["unread_count"], 5);
+    assert!(json["payload"]["dialogs"][1].get("chat_title").is_none());
+
+    let parsed: WebhookEvent = serde_json::from_value(json).expect("deserialize");
+    assert!(matches!(
+        parsed.payload,
+        WebhookPayload::NotificationDigest(_)
+    ));
+}
+
+#[test]
+fn test_message_new_event_carries_content_blocks() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-sender", "Quote #7: 1 200 EUR")
+        .with_content_blocks(Some(vec![ContentBlock::KeyValue {
+            title: Some("Quote #7".into()),
+            rows: vec![KeyValueRow {
+                key: "Price".into(),
+                value: "1 200 EUR".into(),
+            }],
+        }]));
+
+    let event = WebhookEvent::message_new(&dialog, &message);
+
+    let json = serde_json::to_value(&event).expect("serialize");
+    let block = &json["payload"]["message"]["content_blocks"][0];
+    assert_eq!(block["type"], "key_value");
+    assert_eq!(block["rows"][0]["value"], "1 200 EUR");
+
+    let plain = WebhookEvent::message_new(&dialog, &Message::new(dialog.id, "u", "Hi"));
+    let json = serde_json::to_value(&plain).expect("serialize");
+    assert!(json["payload"]["message"].get("content_blocks").is_none());
+}
+
+#[test]
+fn test_message_read_event() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-seller", "Offer attached");
+
+    let event = WebhookEvent::message_read(&dialog, "user-buyer", &message);
+
+    assert_eq!(event.event_type, WebhookEventType::MessageRead);
+
+    if let WebhookPayload::MessageRead(payload) = &event.payload {
+        assert_eq!(payload.dialog_id, dialog.id);
+        assert_eq!(payload.object_id, "tender-123");
+        assert_eq!(payload.user_id, "user-buyer");
+        assert_eq!(payload.last_read_message_id, message.id);
+        assert_eq!(payload.message_sender_id.as_deref(), Some("user-seller"));
+        assert_eq!(payload.message_sent_at, message.sent_at);
+        assert!(payload.read_at >= message.sent_at);
+    } else {
+        panic!("Expected MessageRead payload");
+    }
+
+    let json = serde_json::to_value(&event).expect("serialize");
+    assert_eq!(json["type"], "message_read");
+}
+
+#[test]
+fn test_message_new_event_carries_markdown_source() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "integration", "<p><strong>Done</strong></p>")
+        .with_markdown_source(Some("**Done**".into()));
+
+    let json = serde_json::to_value(WebhookEvent::message_new(&dialog, &message)).unwrap();
+    assert_eq!(
+        json["payload"]["message"]["content"],
+        "<p><strong>Done</strong></p>"
+    );
+    assert_eq!(json["payload"]["message"]["content_markdown"], "**Done**");
+}
+
+#[test]
+fn test_message_action_event() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "integration", "Approve the quote?")
+        .with_metadata(Some(serde_json::json!({ "quote_id": 7 })));
+    let button = ActionButton {
+        id: "approve".into(),
+        label: "Approve".into(),
+        url: None,
+        style: ButtonStyle::Primary,
+    };
+    let system_message_id = Uuid::now_v7();
+
+    let event =
+        WebhookEvent::message_action(&dialog, &message, &button, "user-buyer", system_message_id);
+
+    assert_eq!(event.event_type, WebhookEventType::MessageAction);
+    assert_eq!(event.event_type.to_string(), "message.action");
+    assert_eq!(event.payload.dialog_id(), Some(dialog.id));
+
+    let json = serde_json::to_string(&event).expect("serialize");
+    let parsed: WebhookEvent = serde_json::from_str(&json).expect("deserialize");
+    if let WebhookPayload::MessageAction(payload) = &parsed.payload {
+        assert_eq!(payload.object_id, "tender-123");
+        assert_eq!(payload.message_id, message.id);
+        assert_eq!(payload.action_id, "approve");
+        assert_eq!(payload.label, "Approve");
+        assert_eq!(payload.user_id, "user-buyer");
+        assert_eq!(payload.system_message_id, system_message_id);
+        assert_eq!(
+            payload.message_metadata,
+            Some(serde_json::json!({ "quote_id": 7 }))
+        );
+    } else {
+        panic!("Expected MessageAction payload");
+    }
+}
+
+#[test]
+fn test_dialog_archived_event() {
+    let dialog = make_dialog();
+
+    let event = WebhookEvent::dialog_archived(
+        &dialog,
+        vec!["user-1".into(), "user-2".into()],
+        ArchiveTrigger::AutoArchive,
+        None,
+    );
+
+    assert_eq!(event.event_type, WebhookEventType::DialogArchived);
+    assert_eq!(event.payload.dialog_id(), Some(dialog.id));
+
+    let json = serde_json::to_value(&event).expect("serialize");
+    assert_eq!(json["type"], "dialog_archived");
+    assert_eq!(json["payload"]["object_id"], "tender-123");
+    assert_eq!(json["payload"]["user_ids"][1], "user-2");
+    assert_eq!(json["payload"]["trigger"], "auto_archive");
+    assert!(json["payload"].get("triggered_by").is_none());
+}
+
+#[test]
+fn test_dialog_unarchived_event() {
+    let dialog = make_dialog();
+
+    let event = WebhookEvent::dialog_unarchived(
+        &dialog,
+        vec!["user-1".into()],
+        ArchiveTrigger::NewMessage,
+        Some("user-sender"),
+    );
+
+    assert_eq!(event.event_type, WebhookEventType::DialogUnarchived);
+
+    let json = serde_json::to_string(&event).expect("serialize");
+    let parsed: WebhookEvent = serde_json::from_str(&json).expect("deserialize");
+    if let WebhookPayload::DialogArchive(payload) = &parsed.payload {
+        assert_eq!(payload.user_ids, vec!["user-1".to_string()]);
+        assert_eq!(payload.trigger, ArchiveTrigger::NewMessage);
+        assert_eq!(payload.triggered_by.as_deref(), Some("user-sender"));
+    } else {
+        panic!("Expected DialogArchive payload");
+    }
+}
+
+#[test]
+fn test_attachment_text_extracted_event() {
+    let dialog = make_dialog();
+    let attachment = Attachment::new(
+        Uuid::now_v7(),
+        "contract.pdf",
+        "application/pdf",
+        1024,
+        "attachments/contract.pdf",
+    );
+    let text = "Контракт ".repeat(200);
+
+    let event =
+        WebhookEvent::attachment_text_extracted(&dialog, &attachment, &text, chrono::Utc::now());
+
+    assert_eq!(event.event_type, WebhookEventType::AttachmentTextExtracted);
+    assert_eq!(event.event_type.as_str(), "attachment.text_extracted");
+
+    let json = serde_json::to_string(&event).expect("serialize");
+    let parsed: WebhookEvent = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(parsed.payload.dialog_id(), Some(dialog.id));
+    if let WebhookPayload::AttachmentTextExtracted(payload) = &parsed.payload {
+        assert_eq!(payload.attachment_id, attachment.id);
+        assert_eq!(payload.message_id, attachment.message_id);
+        assert_eq!(payload.filename, "contract.pdf");
+        assert_eq!(payload.text.chars().count(), ATTACHMENT_TEXT_WEBHOOK_CHARS);
+        assert_eq!(payload.text_length, 1800);
+        assert!(payload.truncated);
+    } else {
+        panic!("Expected AttachmentTextExtracted payload");
+    }
+}
+
 #[test]
 fn test_event_serialization_roundtrip() {
     let dialog = make_dialog();
@@ -157,9 +487,73 @@ fn test_event_serialization_roundtrip() {
     assert_eq!(parsed["payload"]["message"]["content"], "Test");
 }
 
+#[test]
+fn test_payload_versions() {
+    let dialog = make_dialog();
+    let mut message = Message::system(dialog.id, r#"{"event":"chat_created"}"#);
+    message.seq = 7;
+    let attachment = Attachment::new(message.id, "scan.pdf", "application/pdf", 1024, "key");
+    let event = WebhookEvent::message_new(&dialog, &message).with_attachments(&[attachment]);
+
+    let v2 = serde_json::to_value(event.clone().into_version(2)).expect("serialize");
+    assert_eq!(v2["payload_version"], 2);
+    let data = &v2["payload"]["message"];
+    assert_eq!(data["seq"], 7);
+    assert_eq!(data["attachments"][0]["filename"], "scan.pdf");
+    assert_eq!(data["attachments"][0]["size"], 1024);
+    assert_eq!(data["system_event"]["event"], "chat_created");
+
+    let v1 = serde_json::to_value(event.into_version(DEFAULT_WEBHOOK_PAYLOAD_VERSION))
+        .expect("serialize");
+    assert_eq!(v1["payload_version"], 1);
+    let data = v1["payload"]["message"].as_object().unwrap();
+    assert_eq!(data["content"], r#"{"event":"chat_created"}"#);
+    assert!(!data.contains_key("seq"));
+    assert!(!data.contains_key("attachments"));
+    assert!(!data.contains_key("system_event"));
+
+    // Events stored before versioning read as version 1
+    let mut legacy = v1.clone();
+    legacy.as_object_mut().unwrap().remove("payload_version");
+    let parsed: WebhookEvent = serde_json::from_value(legacy).expect("deserialize");
+    assert_eq!(parsed.payload_version, DEFAULT_WEBHOOK_PAYLOAD_VERSION);
+}
+
+#[test]
+fn test_batch_envelope() {
+    let dialog = make_dialog();
+    let message = Message::new(dialog.id, "user-test", "Test");
+    let mut first = WebhookEvent::message_new(&dialog, &message);
+    first.request_id = Some("req-1".into());
+    let second = WebhookEvent::participant_left(&dialog, "user-left");
+
+    let batch = WebhookBatch::new(vec![first.clone(), second.clone()]);
+    let json = serde_json::to_value(&batch).expect("serialize");
+
+    assert_eq!(json["version"], WEBHOOK_BATCH_VERSION);
+    assert_eq!(json["id"], batch.id.to_string());
+    assert!(json["timestamp"].is_string());
+    let events = json["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["id"], first.id.to_string());
+    assert_eq!(events[0]["type"], "message_new");
+    assert_eq!(events[0]["request_id"], "req-1");
+    assert_eq!(events[0]["payload"]["message"]["content"], "Test");
+    assert_eq!(events[1]["id"], second.id.to_string());
+    assert!(events[1].get("request_id").is_none());
+}
+
 #[test]
 fn test_event_type_display() {
     assert_eq!(WebhookEventType::MessageNew.to_string(), "message.new");
+    assert_eq!(
+        WebhookEventType::MessageEdited.to_string(),
+        "message.edited"
+    );
+    assert_eq!(
+        WebhookEventType::MessageDeleted.to_string(),
+        "message.deleted"
+    );
     assert_eq!(
         WebhookEventType::ParticipantJoined.to_string(),
         "participant.joined"
@@ -168,8 +562,55 @@ fn test_event_type_display() {
         WebhookEventType::ParticipantLeft.to_string(),
         "participant.left"
     );
+    assert_eq!(WebhookEventType::MessageRead.to_string(), "message.read");
+    assert_eq!(
+        WebhookEventType::DialogArchived.to_string(),
+        "dialog.archived"
+    );
+    assert_eq!(
+        WebhookEventType::DialogUnarchived.to_string(),
+        "dialog.unarchived"
+    );
     assert_eq!(
         WebhookEventType::NotificationPending.to_string(),
         "notification.pending"
     );
+    assert_eq!(
+        WebhookEventType::NotificationMention.to_string(),
+        "notification.mention"
+    );
+    assert_eq!(
+        WebhookEventType::NotificationUrgent.to_string(),
+        "notification.urgent"
+    );
+}
+
+#[test]
+fn test_sla_events() {
+    let dialog = make_dialog();
+    let message = UnansweredMessage {
+        message_id: Uuid::now_v7(),
+        sender_id: "user-buyer".into(),
+        sent_at: chrono::Utc::now(),
+    };
+    let target = SlaTarget::new(3600, None, SlaSource::Dialog);
+
+    let warning = WebhookEvent::sla_warning(&dialog, &message, &target);
+    assert_eq!(warning.event_type.as_str(), "sla.warning");
+    assert!(!warning.event_type.is_notification());
+    assert_eq!(warning.payload.dialog_id(), Some(dialog.id));
+
+    let breached = WebhookEvent::sla_breached(&dialog, &message, &target);
+    assert_eq!(breached.event_type.as_str(), "sla.breached");
+    let json = serde_json::to_value(&breached).expect("serialize");
+    assert_eq!(
+        json["payload"]["message_id"],
+        message.message_id.to_string()
+    );
+    assert_eq!(json["payload"]["sender_id"], "user-buyer");
+    assert_eq!(json["payload"]["response_secs"], 3600);
+
+    // Round-trips to the SLA payload
+    let parsed: WebhookEvent = serde_json::from_value(json).expect("deserialize");
+    assert!(matches!(parsed.payload, WebhookPayload::Sla(_)));
 }
@@ -0,0 +1,184 @@
+//! Integration tests for WebSocket events
+//!
+//! Checks that message and presence events reach the dialog's participants
+//! and nobody else.
+//!
+//! These tests require a running server. Run with:
+//! ```
+//! cargo test --features test_utils --test ws_test -- --ignored
+//! ```
+
+use multitenancy_chat_api::test_utils::{WsTestClient, WsTestEvent, DEFAULT_SILENCE_WINDOW};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+use std::env;
+use uuid::Uuid;
+
+fn get_base_url() -> String {
+    env::var("API_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string())
+}
+
+fn get_admin_token() -> Option<String> {
+    env::var("ADMIN_API_TOKEN").ok()
+}
+
+// Helper to create a dialog with the given participants
+async fn create_test_dialog(client: &Client, base_url: &str, participants: &[Uuid]) -> Uuid {
+    let auth_header = get_admin_token()
+        .map(|t| format!("Bearer {}", t))
+        .unwrap_or_default();
+    let resp = client
+        .post(format!("{}/api/v1/management/dialogs", base_url))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "object_id": Uuid::new_v4(),
+            "object_type": "test",
+            "title": "WS Test Dialog",
+            "participants": participants,
+        }))
+        .send()
+        .await
+        .expect("Create dialog failed");
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = resp.json().await.unwrap();
+    body["data"]["id"].as_str().unwrap().parse().unwrap()
+}
+
+async fn connect(base_url: &str, user_id: Uuid) -> WsTestClient {
+    WsTestClient::connect(base_url, &user_id.to_string())
+        .await
+        .expect("WebSocket connect failed")
+}
+
+fn messages_url(base_url: &str, dialog_id: Uuid, user_id: Uuid) -> String {
+    format!(
+        "{}/api/v1/dialogs/{}/messages?user_id={}",
+        base_url, dialog_id, user_id
+    )
+}
+
+fn message_url(base_url: &str, dialog_id: Uuid, message_id: &str, user_id: Uuid) -> String {
+    format!(
+        "{}/api/v1/dialogs/{}/messages/{}?user_id={}",
+        base_url, dialog_id, message_id, user_id
+    )
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_message_events_reach_only_participants() {
+    let client = Client::new();
+    let base_url = get_base_url();
+
+    let sender = Uuid::new_v4();
+    let recipient = Uuid::new_v4();
+    let outsider = Uuid::new_v4();
+    let dialog_id = create_test_dialog(&client, &base_url, &[sender, recipient]).await;
+    let in_dialog = |e: &WsTestEvent| e.dialog_id() == Some(dialog_id);
+
+    let mut sender_ws = connect(&base_url, sender).await;
+    let mut recipient_ws = connect(&base_url, recipient).await;
+    let mut outsider_ws = connect(&base_url, outsider).await;
+
+    // message.new
+    let resp = client
+        .post(messages_url(&base_url, dialog_id, sender))
+        .json(&json!({ "content": "Hello over WS" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let message_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    for ws in [&mut sender_ws, &mut recipient_ws] {
+        let event = ws.expect_event_where("message.new", in_dialog).await;
+        assert_eq!(event.str("id"), Some(message_id.as_str()));
+        assert_eq!(event.str("content"), Some("Hello over WS"));
+        assert_eq!(event.str("sender_id"), Some(sender.to_string().as_str()));
+    }
+    outsider_ws
+        .expect_no_event_where("message.new", DEFAULT_SILENCE_WINDOW, in_dialog)
+        .await;
+
+    // message.edited
+    let resp = client
+        .put(message_url(&base_url, dialog_id, &message_id, sender))
+        .json(&json!({ "content": "Edited over WS" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let event = recipient_ws
+        .expect_event_where("message.edited", in_dialog)
+        .await;
+    assert_eq!(event.str("id"), Some(message_id.as_str()));
+    assert_eq!(event.str("content"), Some("Edited over WS"));
+    assert_eq!(event.data["version"], 2);
+    outsider_ws
+        .expect_no_event_where("message.edited", DEFAULT_SILENCE_WINDOW, in_dialog)
+        .await;
+
+    // message.deleted
+    let resp = client
+        .delete(message_url(&base_url, dialog_id, &message_id, sender))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+
+    let event = recipient_ws
+        .expect_event_where("message.deleted", in_dialog)
+        .await;
+    assert_eq!(event.str("id"), Some(message_id.as_str()));
+    outsider_ws
+        .expect_no_event_where("message.deleted", DEFAULT_SILENCE_WINDOW, in_dialog)
+        .await;
+
+    sender_ws.close().await;
+    recipient_ws.close().await;
+    outsider_ws.close().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires running server
+async fn test_presence_reaches_users_sharing_a_dialog() {
+    let client = Client::new();
+    let base_url = get_base_url();
+
+    let watcher = Uuid::new_v4();
+    let user = Uuid::new_v4();
+    let outsider = Uuid::new_v4();
+    create_test_dialog(&client, &base_url, &[watcher, user]).await;
+    // The outsider has dialogs of their own, just none shared with the user
+    create_test_dialog(&client, &base_url, &[outsider, watcher]).await;
+
+    let mut watcher_ws = connect(&base_url, watcher).await;
+    let mut outsider_ws = connect(&base_url, outsider).await;
+    let is_user = |e: &WsTestEvent| e.str("user_id") == Some(user.to_string().as_str());
+
+    // Coming online
+    let user_ws = connect(&base_url, user).await;
+    let event = watcher_ws
+        .expect_event_where("presence.update", is_user)
+        .await;
+    assert_eq!(event.data["is_online"], true);
+    outsider_ws
+        .expect_no_event_where("presence.update", DEFAULT_SILENCE_WINDOW, is_user)
+        .await;
+
+    // Going offline
+    user_ws.close().await;
+    let event = watcher_ws
+        .expect_event_where("presence.update", is_user)
+        .await;
+    assert_eq!(event.data["is_online"], false);
+    outsider_ws
+        .expect_no_event_where("presence.update", DEFAULT_SILENCE_WINDOW, is_user)
+        .await;
+
+    watcher_ws.close().await;
+    outsider_ws.close().await;
+}
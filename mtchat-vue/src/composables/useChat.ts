@@ -903,6 +903,23 @@ export function useChat(options: UseChatOptions): UseChatReturn {
     }
   }
 
+  // ============ Helper: Insert message by seq ============
+
+  /**
+   * Insert a message keeping seq order (a WebSocket event may arrive
+   * before the send response or after a newer message)
+   */
+  function insertMessage(message: Message): void {
+    const idx = message.seq === undefined
+      ? -1
+      : messages.value.findIndex((m) => m.seq !== undefined && m.seq > message.seq!)
+    if (idx === -1) {
+      messages.value = [...messages.value, message]
+    } else {
+      messages.value = [...messages.value.slice(0, idx), message, ...messages.value.slice(idx)]
+    }
+  }
+
   // ============ Reply ============
 
   function setReplyTo(message: Message): void {
@@ -932,7 +949,9 @@ export function useChat(options: UseChatOptions): UseChatReturn {
 
     try {
       error.value = null
-      const updated = await client.api.editMessage(currentDialog.value.id, messageId, content)
+      // Based on the version shown, so edits made on another device are not overwritten
+      const version = messages.value.find((m) => m.id === messageId)?.version
+      const updated = await client.api.editMessage(currentDialog.value.id, messageId, content, version)
 
       // Update in local list
       const idx = messages.value.findIndex((m) => m.id === messageId)
@@ -997,7 +1016,7 @@ export function useChat(options: UseChatOptions): UseChatReturn {
           ...messages.value.slice(existingIndex + 1),
         ]
       } else {
-        messages.value = [...messages.value, message]
+        insertMessage(message)
       }
 
       // Update last_message so dialog moves up and preview refreshes
@@ -1045,6 +1064,9 @@ export function useChat(options: UseChatOptions): UseChatReturn {
         content: event.content as string,
         sent_at: event.sent_at as string,
         message_type: msgType,
+        seq: event.seq as number | undefined,
+        sender: event.sender as Message['sender'],
+        metadata: event.metadata as Message['metadata'],
       }
     }
 
@@ -1082,7 +1104,7 @@ export function useChat(options: UseChatOptions): UseChatReturn {
     if (currentDialog.value && message.dialog_id === currentDialog.value.id) {
       // Check for duplicates
       if (!messages.value.some((m) => m.id === message!.id)) {
-        messages.value = [...messages.value, message]
+        insertMessage(message)
       }
 
       // If user is viewing this dialog and message is from another user,
@@ -1308,6 +1330,7 @@ export function useChat(options: UseChatOptions): UseChatReturn {
     const dialog_id = event.dialog_id || event.payload?.dialog_id
     const content = (event.content || event.payload?.content) as string | undefined
     const last_edited_at = (event.last_edited_at || event.payload?.last_edited_at) as string | undefined
+    const version = (event.version ?? event.payload?.version) as number | undefined
     if (!id || !dialog_id || !content) return
 
     // Update last_message preview across all dialog lists if this was the last message
@@ -1326,7 +1349,7 @@ export function useChat(options: UseChatOptions): UseChatReturn {
     if (idx !== -1) {
       messages.value = [
         ...messages.value.slice(0, idx),
-        { ...messages.value[idx], content, last_edited_at },
+        { ...messages.value[idx], content, last_edited_at, version },
         ...messages.value.slice(idx + 1),
       ]
     }
@@ -57,6 +57,8 @@ export interface TranslationStrings {
     chatCreated: string
     participantJoined: string
     participantLeft: string
+    participantReplaced: string
+    actionClicked: string
   }
   input: {
     placeholder: string
@@ -207,6 +209,8 @@ export const translations: Record<Locale, TranslationStrings> = {
       chatCreated: 'Чат создан с участниками: {participants}',
       participantJoined: '{name} присоединился к чату',
       participantLeft: '{name} покинул чат',
+      participantReplaced: '{name} заменяет {oldName} в чате',
+      actionClicked: '{name} нажал «{label}»',
     },
     input: {
       placeholder: 'Введите сообщение... (Enter для отправки)',
@@ -356,6 +360,8 @@ export const translations: Record<Locale, TranslationStrings> = {
       chatCreated: 'Chat created with participants: {participants}',
       participantJoined: '{name} joined the chat',
       participantLeft: '{name} left the chat',
+      participantReplaced: '{name} replaced {oldName} in the chat',
+      actionClicked: '{name} clicked "{label}"',
     },
     input: {
       placeholder: 'Type a message... (Enter to send)',
@@ -505,6 +511,8 @@ export const translations: Record<Locale, TranslationStrings> = {
       chatCreated: '聊天已创建，参与者：{participants}',
       participantJoined: '{name} 加入了聊天',
       participantLeft: '{name} 离开了聊天',
+      participantReplaced: '{name} 接替了 {oldName}',
+      actionClicked: '{name} 点击了"{label}"',
     },
     input: {
       placeholder: '输入消息... (Enter 发送)',
@@ -110,8 +110,13 @@ export type {
   Dialog,
   DialogListItem,
   DialogParticipant,
+  CompanyGroup,
+  InvitedParticipant,
+  DialogNotes,
   DialogAccessScope,
   Message,
+  MessagePriority,
+  SenderProfile,
   ObjectNavigateEvent,
 
   // Attachments
@@ -131,7 +136,13 @@ export type {
   // API types
   ApiResponse,
   PaginationOptions,
+  ParticipantListOptions,
   DialogListType,
+  DialogSyncEvent,
+  DialogSyncResponse,
+  DialogActivity,
+  DialogActivityType,
+  DialogActivityResponse,
 
   // WebSocket types
   WsEvent,
@@ -8,15 +8,26 @@ import type {
   Dialog,
   DialogListItem,
   DialogParticipant,
+  InvitedParticipant,
   Message,
+  MessagePriority,
+  ContentFormat,
   ApiResponse,
   PaginationOptions,
+  ParticipantListOptions,
   DialogListType,
   ScopeConfig,
   PresignUploadResponse,
   AttachmentInput,
+  BatchMessageInput,
+  BatchSentMessage,
   MessagesResponse,
+  MessageDayCount,
+  DialogSyncResponse,
+  DialogActivityResponse,
   JoinDialogRequest,
+  CompanyGroup,
+  DialogNotes,
 } from '../types'
 
 /**
@@ -273,12 +284,92 @@ export class MTChatApi {
   }
 
   /**
-   * Get dialog participants
+   * Snooze notifications for a dialog; they resume automatically when the snooze expires
    */
-  async getParticipants(dialogId: string): Promise<DialogParticipant[]> {
-    const response = await this.request<ApiResponse<DialogParticipant[]>>(
+  async snoozeDialog(dialogId: string, durationSecs: number): Promise<string> {
+    const response = await this.request<{ status: string; snoozed_until: string }>(
+      'POST',
+      `/api/v1/dialogs/${dialogId}/snooze`,
+      { body: { duration_secs: durationSecs } }
+    )
+    return response.snoozed_until
+  }
+
+  /**
+   * End a notification snooze early
+   */
+  async unsnoozeDialog(dialogId: string): Promise<void> {
+    await this.request<{ status: string }>('DELETE', `/api/v1/dialogs/${dialogId}/snooze`)
+  }
+
+  /**
+   * Get dialog participants (without pending invites)
+   */
+  async getParticipants(
+    dialogId: string,
+    options?: ParticipantListOptions
+  ): Promise<DialogParticipant[]> {
+    const entries = await this.getParticipantEntries(dialogId, options)
+    return entries.filter((p): p is DialogParticipant => !('status' in p))
+  }
+
+  /**
+   * Get dialog participants followed by participants invited by email who
+   * have no user ID yet (`status: 'invited'`)
+   */
+  async getParticipantEntries(
+    dialogId: string,
+    options?: ParticipantListOptions
+  ): Promise<Array<DialogParticipant | InvitedParticipant>> {
+    const params: Record<string, string> = {}
+    if (options?.search) params.search = options.search
+    if (options?.onlineOnly) params.online_only = 'true'
+    if (options?.sort) params.sort = options.sort
+    if (options?.limit) params.limit = String(options.limit)
+    if (options?.offset) params.offset = String(options.offset)
+
+    const response = await this.request<
+      ApiResponse<Array<DialogParticipant | InvitedParticipant>>
+    >('GET', `/api/v1/dialogs/${dialogId}/participants`, { params })
+    return response.data
+  }
+
+  /**
+   * Get participants of a dialog grouped by company, with per-company stats
+   */
+  async getParticipantsByCompany(dialogId: string): Promise<CompanyGroup[]> {
+    const response = await this.request<ApiResponse<CompanyGroup[]>>(
+      'GET',
+      `/api/v1/dialogs/${dialogId}/participants`,
+      { params: { group_by: 'company' } }
+    )
+    return response.data
+  }
+
+  /**
+   * Get the shared notes of a dialog
+   */
+  async getNotes(dialogId: string): Promise<DialogNotes> {
+    const response = await this.request<ApiResponse<DialogNotes>>(
       'GET',
-      `/api/v1/dialogs/${dialogId}/participants`
+      `/api/v1/dialogs/${dialogId}/notes`
+    )
+    return response.data
+  }
+
+  /**
+   * Replace the shared notes of a dialog
+   *
+   * With `version`, the update fails with a VERSION_CONFLICT error if the
+   * notes were edited elsewhere since that version.
+   */
+  async updateNotes(dialogId: string, content: string, version?: number): Promise<DialogNotes> {
+    const response = await this.request<ApiResponse<DialogNotes>>(
+      'PUT',
+      `/api/v1/dialogs/${dialogId}/notes`,
+      {
+        body: version === undefined ? { content } : { content, version },
+      }
     )
     return response.data
   }
@@ -293,13 +384,18 @@ export class MTChatApi {
    * - No options: Load latest messages
    * - before: Load messages before the specified ID (infinite scroll up)
    * - around: Load messages centered around the specified ID (jump to message)
+   * - aroundDate: Load messages centered around the first message of a day (jump to date)
    */
   async getMessages(dialogId: string, options?: PaginationOptions): Promise<MessagesResponse> {
-    const params: Record<string, string> = {}
+    // Sender profiles keep names of participants who left the dialog
+    const params: Record<string, string> = { include: 'sender' }
     if (options?.limit) params.limit = String(options.limit)
     if (options?.before) params.before = options.before
     if (options?.after) params.after = options.after
     if (options?.around) params.around = options.around
+    if (options?.aroundDate) params.around_date = options.aroundDate
+    if (options?.tz) params.tz = options.tz
+    if (options?.priority) params.priority = options.priority
 
     const response = await this.request<ApiResponse<MessagesResponse>>(
       'GET',
@@ -309,6 +405,60 @@ export class MTChatApi {
     return response.data
   }
 
+  /**
+   * Get message counts per day from `from` to `to` (inclusive, YYYY-MM-DD),
+   * for marking days in a jump-to-date picker
+   */
+  async getMessageCalendar(
+    dialogId: string,
+    from: string,
+    to: string,
+    tz?: string
+  ): Promise<MessageDayCount[]> {
+    const params: Record<string, string> = { from, to }
+    if (tz) params.tz = tz
+
+    const response = await this.request<ApiResponse<MessageDayCount[]>>(
+      'GET',
+      `/api/v1/dialogs/${dialogId}/messages/calendar`,
+      { params }
+    )
+    return response.data
+  }
+
+  /**
+   * Get dialog changes after an event cursor (delta sync after being offline).
+   * Pass the returned last_seq as sinceSeq next time; repeat while has_more.
+   */
+  async syncDialog(dialogId: string, sinceSeq = 0, limit?: number): Promise<DialogSyncResponse> {
+    const params: Record<string, string> = { since_seq: String(sinceSeq) }
+    if (limit) params.limit = String(limit)
+
+    const response = await this.request<ApiResponse<DialogSyncResponse>>(
+      'GET',
+      `/api/v1/dialogs/${dialogId}/sync`,
+      { params }
+    )
+    return response.data
+  }
+
+  /**
+   * Get the activity feed of a dialog (joins, leaves, archives, pins, scope
+   * changes), newest first. Pass the last entry's id as before for older ones.
+   */
+  async getDialogActivity(dialogId: string, before?: string, limit?: number): Promise<DialogActivityResponse> {
+    const params: Record<string, string> = {}
+    if (before) params.before = before
+    if (limit) params.limit = String(limit)
+
+    const response = await this.request<ApiResponse<DialogActivityResponse>>(
+      'GET',
+      `/api/v1/dialogs/${dialogId}/activity`,
+      { params }
+    )
+    return response.data
+  }
+
   /**
    * Mark messages as read up to specified message
    */
@@ -341,7 +491,14 @@ export class MTChatApi {
   async sendMessage(
     dialogId: string,
     content: string,
-    options?: { replyTo?: string; attachments?: AttachmentInput[] }
+    options?: {
+      replyTo?: string
+      attachments?: AttachmentInput[]
+      metadata?: Record<string, unknown>
+      contentFormat?: ContentFormat
+      /** Creator and invited participants only */
+      priority?: MessagePriority
+    }
   ): Promise<Message> {
     const response = await this.request<ApiResponse<Message>>(
       'POST',
@@ -349,23 +506,52 @@ export class MTChatApi {
       {
         body: {
           content,
+          content_format: options?.contentFormat,
           reply_to: options?.replyTo,
           attachments: options?.attachments || [],
+          metadata: options?.metadata,
+          priority: options?.priority,
         },
       }
     )
     return response.data
   }
 
+  /**
+   * Send messages composed offline, in order and all or nothing
+   *
+   * Retrying a batch is safe: messages already stored under their
+   * `client_id` come back with `duplicate: true`.
+   */
+  async sendMessageBatch(
+    dialogId: string,
+    messages: BatchMessageInput[]
+  ): Promise<BatchSentMessage[]> {
+    const response = await this.request<ApiResponse<{ messages: BatchSentMessage[] }>>(
+      'POST',
+      `/api/v1/dialogs/${dialogId}/messages/batch`,
+      { body: { messages } }
+    )
+    return response.data.messages
+  }
+
   /**
    * Edit a message
+   *
+   * With `version`, the edit fails with a VERSION_CONFLICT error if the
+   * message was edited elsewhere since that version.
    */
-  async editMessage(dialogId: string, messageId: string, content: string): Promise<Message> {
+  async editMessage(
+    dialogId: string,
+    messageId: string,
+    content: string,
+    version?: number
+  ): Promise<Message> {
     const response = await this.request<ApiResponse<Message>>(
       'PUT',
       `/api/v1/dialogs/${dialogId}/messages/${messageId}`,
       {
-        body: { content },
+        body: version === undefined ? { content } : { content, version },
       }
     )
     return response.data
@@ -381,6 +567,20 @@ export class MTChatApi {
     )
   }
 
+  /**
+   * Click a callback button of a message
+   *
+   * Returns the system message recording the click; the host receives a
+   * `message.action` webhook.
+   */
+  async clickMessageAction(messageId: string, actionId: string): Promise<Message> {
+    const response = await this.request<ApiResponse<Message>>(
+      'POST',
+      `/api/v1/messages/${messageId}/actions/${encodeURIComponent(actionId)}`
+    )
+    return response.data
+  }
+
   // ============ Upload ============
 
   /**
@@ -58,12 +58,13 @@ export class MTChatClient {
   }
 
   /**
-   * Derive WebSocket URL from HTTP URL
+   * Derive WebSocket URL from HTTP URL (keeping a base path such as `/chat`)
    */
   private deriveWsUrl(baseUrl: string): string {
     const url = new URL(baseUrl)
     const wsProtocol = url.protocol === 'https:' ? 'wss:' : 'ws:'
-    return `${wsProtocol}//${url.host}/api/v1/ws`
+    const basePath = url.pathname.replace(/\/+$/, '')
+    return `${wsProtocol}//${url.host}${basePath}/api/v1/ws`
   }
 
   /**
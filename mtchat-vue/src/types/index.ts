@@ -22,6 +22,8 @@ export interface Dialog {
   /** User who created the dialog */
   created_by?: string
   created_at: string
+  /** Last change of the dialog's own fields (not of its messages) */
+  updated_at?: string
   /** Free-form metadata supplied by the host application. Opaque to MTChat. */
   meta?: Record<string, unknown>
   /** Whether current user is a participant */
@@ -30,6 +32,8 @@ export interface Dialog {
   can_join?: boolean
   /** Number of participants; present only for dialogs the user participates in */
   participants_count?: number
+  /** Number of read-only observers (not included in participants_count) */
+  observers_count?: number
   /** Participant list; present only for dialogs the user participates in */
   participants?: ParticipantSummary[]
 }
@@ -44,11 +48,25 @@ export interface LastMessage {
   sender_id?: string
   /** Sender display name resolved from participant profile; absent for system messages */
   sender_name?: string
+  /** Sender's smallest available avatar image; absent without an avatar */
+  sender_avatar_url?: string
   sent_at: string
   /** 'user' | 'system' */
   message_type: string
 }
 
+/**
+ * Presigned avatar URLs of a participant
+ */
+export interface AvatarUrls {
+  /** Uploaded image */
+  url: string
+  /** 64x64 PNG; absent until rendered */
+  small_url?: string
+  /** 256x256 PNG; absent until rendered */
+  medium_url?: string
+}
+
 /**
  * Participant summary for dialog list display
  */
@@ -56,6 +74,7 @@ export interface ParticipantSummary {
   user_id: string
   display_name?: string
   company?: string
+  avatar?: AvatarUrls
 }
 
 /**
@@ -64,6 +83,8 @@ export interface ParticipantSummary {
 export interface DialogListItem extends Dialog {
   /** Number of participants */
   participants_count: number
+  /** Number of read-only observers (not included in participants_count) */
+  observers_count?: number
   /** Whether current user is a participant */
   i_am_participant?: boolean
   /** Whether current user can join */
@@ -76,10 +97,14 @@ export interface DialogListItem extends Dialog {
   is_pinned?: boolean
   /** Whether notifications are enabled for current user */
   notifications_enabled?: boolean
+  /** End of an active notification snooze for current user (absent when not snoozed) */
+  snoozed_until?: string
   /** Timestamp of the last message in this dialog */
   last_message_at?: string
-  /** Full last message object for list preview. Present only for dialogs the user participates in (hidden for can-join dialogs). */
+  /** Last message for list preview, content truncated to 200 characters. Present only for dialogs the user participates in (hidden for can-join dialogs). */
   last_message?: LastMessage
+  /** Number of messages in this dialog (dialog list and folder responses) */
+  messages_count?: number
   /** Full list of participants in this dialog */
   participants?: ParticipantSummary[]
 }
@@ -100,8 +125,10 @@ export interface DialogParticipant {
   dialog_id: string
   user_id: string
   joined_at: string
+  /** Last change of the participant, except unread_count */
+  updated_at?: string
   /** How user joined: 'creator', 'participant', 'joined' */
-  joined_as: 'creator' | 'participant' | 'joined'
+  joined_as: 'creator' | 'participant' | 'joined' | 'observer'
   notifications_enabled: boolean
   last_read_message_id?: string
   /** Number of unread messages */
@@ -110,12 +137,33 @@ export interface DialogParticipant {
   display_name?: string
   /** Company/organization name */
   company?: string
+  /** Host-defined company identifier (groups participants by company) */
+  company_uid?: string
   /** Contact email (optional, can be hidden) */
   email?: string
   /** Contact phone (optional, can be hidden) */
   phone?: string
   /** Whether user is currently online */
   is_online?: boolean
+  /** Avatar URLs; absent without an avatar */
+  avatar?: AvatarUrls
+}
+
+/**
+ * Participant invited by email who has no user ID yet
+ */
+export interface InvitedParticipant {
+  invite_id: string
+  status: 'invited'
+  /** Role on activation */
+  joined_as: 'participant' | 'observer'
+  display_name: string
+  company?: string
+  company_uid?: string
+  /** Hidden from potential participants */
+  email?: string
+  phone?: string
+  invited_at: string
 }
 
 /**
@@ -124,16 +172,34 @@ export interface DialogParticipant {
 export interface ParticipantProfile {
   display_name: string
   company?: string
+  company_uid?: string
   email?: string
   phone?: string
 }
 
+/**
+ * Participants of one company with stats for the dialog header
+ */
+export interface CompanyGroup {
+  /** Company identifier the group is keyed by (absent for groups keyed by name) */
+  company_uid?: string
+  /** Company name (absent for participants without a company) */
+  company?: string
+  /** Messages no participant of the company has read yet */
+  unread_count: number
+  /** Latest message sent by a participant of the company */
+  last_activity_at?: string
+  /** Participants, then pending invites */
+  participants: Array<DialogParticipant | InvitedParticipant>
+}
+
 /**
  * Request body for joining a dialog
  */
 export interface JoinDialogRequest {
   display_name: string
   company: string
+  company_uid?: string
   email?: string
   phone?: string
 }
@@ -155,10 +221,21 @@ export interface DialogAccessScope {
  */
 export type MessageType = 'user' | 'system'
 
+/**
+ * Message priority: urgent messages are notified without delay
+ */
+export type MessagePriority = 'normal' | 'urgent'
+
 /**
  * System message event types
  */
-export type SystemMessageEvent = 'chat_created' | 'participant_joined' | 'participant_left'
+export type SystemMessageEvent =
+  | 'chat_created'
+  | 'participant_joined'
+  | 'participant_left'
+  | 'participant_replaced'
+  | 'object_event'
+  | 'action_clicked'
 
 /**
  * System message content structure (parsed from JSON)
@@ -167,28 +244,122 @@ export interface SystemMessageContent {
   event: SystemMessageEvent
   /** Participants list for chat_created event */
   participants?: Array<{ name: string; company?: string }>
-  /** Participant name for joined/left events */
+  /** Participant name for joined/left/replaced events */
   name?: string
-  /** Participant company for joined event */
+  /** Participant company for joined/replaced events */
   company?: string
+  /** Replaced participant name for participant_replaced event */
+  old_name?: string
+  /** Host-defined event type for object_event (e.g. "status_changed") */
+  type?: string
+  /** Structured data of an object_event */
+  payload?: Record<string, unknown>
+  /** Fallback text of an object_event */
+  text?: string
+  /** When the object_event happened in the host system */
+  occurred_at?: string
+  /** Message whose button was clicked (action_clicked) */
+  message_id?: string
+  /** Clicked button ID (action_clicked) */
+  action_id?: string
+  /** Clicked button label (action_clicked) */
+  label?: string
 }
 
+/**
+ * Format of message content
+ */
+export type ContentFormat = 'html' | 'markdown'
+
 /**
  * Chat message
  */
 export interface Message {
   id: string
+  /** ID in the deployment's external ID format (equals `id` unless configured) */
+  external_id: string
   dialog_id: string
   /** Sender ID. Null for system messages. */
   sender_id: string | null
   content: string
+  /** What `content` holds: 'markdown' only when requested and the message was written in Markdown */
+  content_format?: ContentFormat
   sent_at: string
   last_edited_at?: string
+  /** Last change of the message (edits, metadata) */
+  updated_at?: string
   reply_to_id?: string
+  /** Preview of the message this one replies to */
+  reply_to?: ReplyPreview
   /** Attachments with presigned URLs */
   attachments?: Attachment[]
   /** Message type: 'user' or 'system' (default: 'user') */
   message_type?: MessageType
+  /** 'urgent' for time-critical messages (default: 'normal') */
+  priority?: MessagePriority
+  /** Per-dialog sequence number; order and deduplicate by it */
+  seq?: number
+  /** Incremented on every edit; pass it when editing to detect concurrent edits */
+  version?: number
+  /** Sender profile at send time; kept after the sender left (absent for system messages) */
+  sender?: SenderProfile
+  /** Sender's current smallest avatar image */
+  sender_avatar_url?: string
+  /** Integration data attached by the host system */
+  metadata?: Record<string, unknown>
+  /** Structured content rendered next to `content` */
+  content_blocks?: ContentBlock[]
+}
+
+/**
+ * Shared dialog notes pinned above the messages
+ */
+export interface DialogNotes {
+  dialog_id: string
+  /** Sanitized HTML (empty until first written) */
+  content: string
+  /** Incremented on every edit (0 until first written) */
+  version: number
+  updated_by?: string
+  updated_at?: string
+}
+
+/**
+ * Sender profile snapshotted when the message was sent
+ */
+export interface SenderProfile {
+  display_name?: string
+  company?: string
+}
+
+/**
+ * Block of structured message content (text is plain text, not HTML)
+ */
+export type ContentBlock =
+  | { type: 'text'; text: string }
+  | { type: 'quote'; text: string; author?: string }
+  | { type: 'file'; name: string; url: string; size?: number; content_type?: string }
+  | { type: 'key_value'; title?: string; rows: { key: string; value: string }[] }
+  | { type: 'actions'; buttons: ActionButton[] }
+
+export interface ActionButton {
+  id: string
+  label: string
+  /** Link opened by the button */
+  url?: string
+  style?: 'default' | 'primary' | 'danger'
+}
+
+/**
+ * Compact view of the original message of a reply
+ */
+export interface ReplyPreview {
+  id: string
+  sender_id?: string
+  /** First 200 characters of the content (empty when deleted) */
+  content: string
+  /** The original message was deleted */
+  deleted: boolean
 }
 
 // ============ Attachments ============
@@ -205,8 +376,10 @@ export interface Attachment {
   width?: number
   /** Image height in pixels (images only) */
   height?: number
-  /** Presigned download URL */
-  url: string
+  /** Presigned download URL; null while file storage is unavailable */
+  url: string | null
+  /** Set when `url` could not be issued; fetch it later via the attachment URL endpoint */
+  url_status?: 'unavailable'
   /** Presigned thumbnail URL (images only) */
   thumbnail_url?: string
 }
@@ -265,6 +438,31 @@ export interface AttachmentInput {
   size: number
 }
 
+/**
+ * Message composed offline, for batch send
+ */
+export interface BatchMessageInput {
+  /** Client's ID of the message; a message already stored under it is not sent again */
+  client_id: string
+  /** When the message was composed (ISO 8601) */
+  client_sent_at: string
+  content: string
+  content_format?: ContentFormat
+  reply_to?: string
+  attachments?: AttachmentInput[]
+  metadata?: Record<string, unknown>
+  priority?: MessagePriority
+}
+
+/**
+ * Message of a batch send result
+ */
+export interface BatchSentMessage extends Message {
+  client_id: string
+  /** Stored by an earlier attempt of the batch and not sent again */
+  duplicate: boolean
+}
+
 /**
  * Attachment limits
  */
@@ -436,6 +634,36 @@ export interface PaginationOptions {
   after?: string
   /** Load messages centered around this message ID (for jumping to replies) */
   around?: string
+  /** Load messages centered around the first message of this day (YYYY-MM-DD) */
+  aroundDate?: string
+  /** IANA timezone of aroundDate (default: the dialog's timezone, else UTC) */
+  tz?: string
+  /** Only messages of this priority (pages with `before` only) */
+  priority?: MessagePriority
+}
+
+/**
+ * Filter, order and page of a participant list
+ */
+export interface ParticipantListOptions {
+  /** Display name or company contains this text (case-insensitive) */
+  search?: string
+  /** Only participants that are online */
+  onlineOnly?: boolean
+  /** `joined_at` (default) or `name` */
+  sort?: 'joined_at' | 'name'
+  /** Page size (max 100, all participants when absent) */
+  limit?: number
+  offset?: number
+}
+
+/**
+ * Number of messages sent on one day (message calendar)
+ */
+export interface MessageDayCount {
+  /** Day, YYYY-MM-DD */
+  date: string
+  count: number
 }
 
 /**
@@ -451,6 +679,69 @@ export interface MessagesResponse {
   has_more_after?: boolean
 }
 
+/**
+ * Dialog changelog entry (delta sync)
+ */
+export interface DialogSyncEvent {
+  /** Event seq within the dialog (separate from message seq) */
+  seq: number
+  type: 'message.new' | 'message.edited' | 'message.deleted' | 'message.read'
+  /** Affected message; for message.read the last read message */
+  message_id?: string
+  /** Sender, or the reader for message.read */
+  user_id?: string
+  created_at: string
+  /** Current message state for message.new/message.edited (absent if deleted since) */
+  message?: Message
+}
+
+/**
+ * Response from the dialog sync endpoint
+ */
+export interface DialogSyncResponse {
+  events: DialogSyncEvent[]
+  /** Cursor for the next request */
+  last_seq: number
+  has_more: boolean
+}
+
+/**
+ * Dialog activity entry type
+ */
+export type DialogActivityType =
+  | 'participant_joined'
+  | 'participant_left'
+  | 'dialog_archived'
+  | 'dialog_unarchived'
+  | 'dialog_pinned'
+  | 'dialog_unpinned'
+  | 'access_scopes_changed'
+
+/**
+ * Non-message event of a dialog (activity feed)
+ */
+export interface DialogActivity {
+  id: string
+  dialog_id: string
+  type: DialogActivityType
+  /** Participant the entry concerns */
+  user_id?: string
+  /** User whose action caused it (absent for management, jobs and integrations) */
+  actor_id?: string
+  /** Type-specific data, e.g. joined_as or the archive trigger */
+  details: Record<string, unknown>
+  created_at: string
+}
+
+/**
+ * Response from the dialog activity endpoint
+ */
+export interface DialogActivityResponse {
+  /** Newest first */
+  activity: DialogActivity[]
+  has_more: boolean
+}
+
 /**
  * Dialog list filter type
  */
@@ -472,6 +763,7 @@ export type WsEventType =
   | 'participant.left'
   | 'dialog.archived'
   | 'dialog.unarchived'
+  | 'dialog.notes_updated'
   | 'presence.update'
   | 'typing'
   | 'pong'
@@ -97,6 +97,12 @@ describe('getSenderDisplayName', () => {
     expect(getSenderDisplayName('user-1', participants, 'user-1', 'You')).toBe('John Doe')
   })
 
+  it('falls back to the snapshot for senders who left', () => {
+    expect(getSenderDisplayName('user-9', participants, 'user-3', 'You', { display_name: 'Old Name' })).toBe('Old Name')
+    // Current profile wins over the snapshot
+    expect(getSenderDisplayName('user-1', participants, 'user-3', 'You', { display_name: 'Old Name' })).toBe('John Doe')
+  })
+
   it('handles empty participants array', () => {
     // Falls back to truncated ID (first 8 chars)
     expect(getSenderDisplayName('user-1-long-id', [], 'user-2', 'You')).toBe('user-1-l')
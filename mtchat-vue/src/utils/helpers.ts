@@ -1,4 +1,4 @@
-import type { DialogParticipant } from '../types'
+import type { DialogParticipant, SenderProfile } from '../types'
 
 /**
  * Get initials from a display name (first letters of first two words).
@@ -21,15 +21,18 @@ export function truncateText(text: string, maxLength: number): string {
 }
 
 /**
- * Get a participant's display name, falling back to "You" label or truncated ID.
+ * Get a participant's display name, falling back to the name snapshotted on
+ * the message (sender left the dialog), then to "You" label or truncated ID.
  */
 export function getSenderDisplayName(
   senderId: string,
   participants: DialogParticipant[],
   currentUserId: string,
   youLabel: string,
+  snapshot?: SenderProfile,
 ): string {
   const participant = participants.find(p => p.user_id === senderId)
   if (participant?.display_name) return participant.display_name
+  if (snapshot?.display_name) return snapshot.display_name
   return senderId === currentUserId ? youLabel : senderId.slice(0, 8)
 }